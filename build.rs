@@ -5,8 +5,8 @@ use std::path::Path;
 use std::collections::HashMap;
 
 fn main() {
-    // Link X11 library for window title detection on Linux
-    if cfg!(target_os = "linux") {
+    // Link X11 library for window title detection on Linux, headless builds don't use it
+    if cfg!(target_os = "linux") && env::var_os("CARGO_FEATURE_GUI").is_some() {
         println!("cargo:rustc-link-lib=X11");
     }
     
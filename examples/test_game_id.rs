@@ -34,7 +34,8 @@ async fn main() -> anyhow::Result<()> {
     let game = db.get_or_create_game_with_id(
         "Harry Potter and the Philosopher's Stone",
         "PCSX2",
-        Some("SLES-52056")
+        Some("SLES-52056"),
+        None,
     ).await?;
     
     println!("  Created game:");
@@ -28,7 +28,7 @@ fn main() {
         
         println!("---------------------------------");
         println!("\n=== Harry Potter Detection ===");
-        println!("Has Harry Potter save: {}", card.has_harry_potter_save());
+        println!("Has Harry Potter save: {}", card.has_game_saves("Harry Potter"));
         
     } else {
         println!("Failed to load memory card - invalid format or size");
@@ -58,8 +58,20 @@ impl BrowserOAuth {
         }
     }
     
-    /// Start OAuth flow
+    /// Start OAuth flow in the default browser
     pub async fn authenticate(&self) -> Result<TokenResponse> {
+        self.authenticate_with(|auth_url, _| {
+            info!("Opening browser to: {}", auth_url);
+            open::that(auth_url).context("Failed to open browser")
+        }).await
+    }
+    
+    /// Start OAuth flow without a browser on this machine: `show_url` is given
+    /// the sign in URL and the local port the browser is sent back to
+    pub async fn authenticate_with<F>(&self, show_url: F) -> Result<TokenResponse>
+    where
+        F: FnOnce(&str, u16) -> Result<()>,
+    {
         // Find available port for callback
         let listener = TcpListener::bind("127.0.0.1:0")
             .context("Failed to bind to local port")?;
@@ -74,7 +86,7 @@ impl BrowserOAuth {
         let device_info = collect_device_info();
         
         // Initiate OAuth flow with backend
-        let client = reqwest::Client::new();
+        let client = crate::sync::http::http_client();
         let response = client
             .post(format!("{}/api/auth/desktop/initiate", self.api_base_url))
             .json(&serde_json::json!({
@@ -107,10 +119,7 @@ impl BrowserOAuth {
             });
         }
         
-        // Open browser
-        info!("Opening browser to: {}", initiate_response.auth_url);
-        open::that(&initiate_response.auth_url)
-            .context("Failed to open browser")?;
+        show_url(&initiate_response.auth_url, redirect_port)?;
         
         // Wait for callback
         let (code, callback_state) = self.wait_for_callback(listener)
@@ -306,7 +315,7 @@ Content-Type: text/html; charset=utf-8
         state: String,
         code_verifier: String,
     ) -> Result<TokenResponse> {
-        let client = reqwest::Client::new();
+        let client = crate::sync::http::http_client();
         
         let response = client
             .post(format!("{}/api/auth/desktop/exchange", self.api_base_url))
@@ -0,0 +1,323 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use zip::write::SimpleFileOptions;
+
+use crate::monitor::{self, process};
+use crate::storage::database::DEFAULT_PROFILE;
+use crate::storage::{Database, SettingsManager};
+use crate::sync::{AuthManager, SyncApi, WebSocketClient};
+use crate::settings::Settings;
+
+/// Rotated log files included in an export
+const MAX_LOG_FILES: usize = 3;
+
+/// Default location for a diagnostics bundle, e.g. `~/Downloads/retrosave-diagnostics-20240101-120000.zip`
+pub fn default_export_path() -> PathBuf {
+    let dir = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    dir.join(format!("retrosave-diagnostics-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S")))
+}
+
+/// Write a zip with everything needed to look into a bug report:
+/// system and version info, database stats, detected emulators, settings
+/// and recent logs. Emails, tokens and the home directory are redacted.
+pub async fn export_diagnostics(path: &Path, database: &Database, settings: &Settings) -> Result<()> {
+    let mut system = String::new();
+    system.push_str(&format!("Retrosave version: {}\n", env!("CARGO_PKG_VERSION")));
+    system.push_str(&format!("OS: {} ({})\n", std::env::consts::OS, std::env::consts::ARCH));
+    system.push_str(&format!("Cloud sync enabled: {}\n", settings.cloud_sync_enabled));
+    system.push_str(&format!("Sync backend: {}\n", settings.sync_backend.label()));
+    match database.get_stats().await {
+        Ok((games, saves)) => system.push_str(&format!("Database: {} games, {} saves\n", games, saves)),
+        Err(e) => system.push_str(&format!("Database: failed to read stats: {}\n", e)),
+    }
+    
+    system.push_str("\nRunning emulators:\n");
+    let running = process::detect_running_emulators(&[]);
+    if running.is_empty() {
+        system.push_str("  none\n");
+    }
+    for emulator in running {
+        system.push_str(&format!("  {:?}\n", emulator));
+    }
+    
+    system.push_str("\nSave directories:\n");
+    for emulator in monitor::EMULATORS {
+        match monitor::find_save_directory(emulator) {
+            Some(dir) => system.push_str(&format!("  {}: {}\n", emulator, dir.display())),
+            None => system.push_str(&format!("  {}: not found\n", emulator)),
+        }
+    }
+    
+    let mut logs = Vec::new();
+    for log_file in crate::logging::log_files().into_iter().take(MAX_LOG_FILES) {
+        match std::fs::read(&log_file) {
+            Ok(data) => {
+                let name = log_file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                logs.push((name, String::from_utf8_lossy(&data).to_string()));
+            }
+            Err(e) => warn!("Failed to read log file {}: {}", log_file.display(), e),
+        }
+    }
+    
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+    
+    zip.start_file("system.txt", options)?;
+    zip.write_all(redact(&system).as_bytes())?;
+    
+    let mut settings = settings.clone();
+    settings.proxy_url = settings.proxy_url.as_deref().map(crate::sync::http::redact_proxy_credentials);
+    // Webhook URLs carry their token in the path
+    settings.failure_webhook_url = settings.failure_webhook_url.as_ref().map(|_| "<redacted>".to_string());
+    zip.start_file("settings.txt", options)?;
+    zip.write_all(redact(&format!("{:#?}\n", settings)).as_bytes())?;
+    
+    zip.start_file("logs/recent.log", options)?;
+    zip.write_all(redact(&crate::logging::recent_logs().join("\n")).as_bytes())?;
+    
+    for (name, contents) in logs {
+        zip.start_file(format!("logs/{}", name), options)?;
+        zip.write_all(redact(&contents).as_bytes())?;
+    }
+    
+    zip.finish()?;
+    
+    info!("Diagnostics exported to {}", path.display());
+    Ok(())
+}
+
+/// How long `retrosave doctor` waits for the WebSocket server
+const DOCTOR_WEBSOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One line of the `retrosave doctor` report
+#[derive(Debug, Clone)]
+pub struct DoctorCheck {
+    pub name: String,
+    /// What was found, or why the check failed
+    pub result: Result<String, String>,
+    /// A failure makes `retrosave doctor` exit nonzero
+    pub critical: bool,
+}
+
+impl DoctorCheck {
+    fn new(name: impl Into<String>, critical: bool, result: Result<String, String>) -> Self {
+        Self { name: name.into(), result, critical }
+    }
+}
+
+/// Result of every `retrosave doctor` check
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// No critical check failed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.result.is_ok() || !check.critical)
+    }
+}
+
+impl std::fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Retrosave {} self-test", env!("CARGO_PKG_VERSION"))?;
+        for check in &self.checks {
+            let (status, detail) = match check.result {
+                Ok(ref detail) => ("PASS", detail),
+                Err(ref detail) if check.critical => ("FAIL", detail),
+                Err(ref detail) => ("WARN", detail),
+            };
+            writeln!(f, "[{}] {}: {}", status, check.name, detail)?;
+        }
+        let failed = self.checks.iter().filter(|check| check.result.is_err() && check.critical).count();
+        if failed == 0 {
+            writeln!(f, "All critical checks passed")
+        } else {
+            writeln!(f, "{} critical check(s) failed", failed)
+        }
+    }
+}
+
+/// Self-test for support and packaging: checks the data directory, database,
+/// save directories, stored tokens and the connection to the cloud.
+/// Backs `retrosave doctor`.
+pub async fn run_doctor(data_dir: &Path) -> DoctorReport {
+    let mut report = DoctorReport::default();
+    
+    report.checks.push(DoctorCheck::new("Data directory writable", true, check_writable(data_dir)
+        .map(|()| data_dir.display().to_string())
+        .map_err(|e| format!("{}: {:#}", data_dir.display(), e))));
+    
+    let database = match Database::new(Some(data_dir.join("retrosave.db"))).await {
+        Ok(database) => Some(Arc::new(database)),
+        Err(e) => {
+            report.checks.push(DoctorCheck::new("Database", true, Err(format!("{:#}", e))));
+            None
+        }
+    };
+    let mut settings = Settings::default();
+    let mut profile = DEFAULT_PROFILE.to_string();
+    if let Some(ref database) = database {
+        let stats = async {
+            let (games, saves) = database.get_stats().await?;
+            let version = database.schema_version().await?;
+            Ok::<_, anyhow::Error>(format!("{} games, {} saves, schema version {}", games, saves, version))
+        }.await;
+        report.checks.push(DoctorCheck::new("Database", true, stats.map_err(|e| format!("{:#}", e))));
+        
+        match SettingsManager::new(database.clone()).load_settings().await {
+            Ok(loaded) => settings = loaded,
+            Err(e) => report.checks.push(DoctorCheck::new("Settings", false, Err(format!("{:#}", e)))),
+        }
+        if let Ok(active) = database.get_active_profile().await {
+            profile = active;
+        }
+    }
+    
+    for emulator in monitor::EMULATORS {
+        let result = match monitor::find_save_directory(emulator) {
+            Some(dir) => Ok(dir.display().to_string()),
+            None => Err("not found".to_string()),
+        };
+        report.checks.push(DoctorCheck::new(format!("{} save directory", emulator), false, result));
+    }
+    
+    crate::sync::http::set_client_options(crate::sync::http::ClientOptions {
+        ca_cert_path: settings.custom_ca_cert_path.as_ref().map(PathBuf::from),
+        accept_invalid_certs: settings.accept_invalid_certs,
+        proxy_url: settings.proxy_url.clone(),
+    });
+    
+    let auth_manager = Arc::new(AuthManager::with_profile(settings.cloud_api_url.clone(), profile.clone()));
+    report.checks.push(DoctorCheck::new("Stored credentials", false, match auth_manager.has_readable_tokens() {
+        Ok(true) => Ok(format!("tokens of profile {} can be read", profile)),
+        Ok(false) => Ok(format!("not signed in with profile {}", profile)),
+        Err(e) => Err(format!("{:#}", e)),
+    }));
+    
+    // The cloud only matters when sync is turned on
+    let cloud_critical = settings.cloud_sync_enabled;
+    let api = SyncApi::new(settings.cloud_api_url.clone(), auth_manager);
+    report.checks.push(DoctorCheck::new("API reachable", cloud_critical, api.test_connection().await
+        .map(|()| settings.cloud_api_url.clone())
+        .map_err(|e| format!("{:#}", e))));
+    
+    let (event_tx, _event_rx) = tokio::sync::mpsc::unbounded_channel();
+    let websocket = WebSocketClient::new(settings.cloud_api_url.clone(), event_tx);
+    let connected = match tokio::time::timeout(DOCTOR_WEBSOCKET_TIMEOUT, websocket.connect()).await {
+        Ok(Ok(())) => {
+            let _ = websocket.disconnect().await;
+            Ok("connected".to_string())
+        }
+        Ok(Err(e)) => Err(format!("{:#}", e)),
+        Err(_) => Err(format!("no answer within {} seconds", DOCTOR_WEBSOCKET_TIMEOUT.as_secs())),
+    };
+    report.checks.push(DoctorCheck::new("WebSocket connection", cloud_critical, connected));
+    
+    report
+}
+
+/// Create the directory if needed and write and remove a file in it
+fn check_writable(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir).context("Failed to create directory")?;
+    let probe = dir.join(".retrosave-doctor");
+    std::fs::write(&probe, b"ok").context("Failed to write a file")?;
+    std::fs::remove_file(&probe).context("Failed to remove a file")?;
+    Ok(())
+}
+
+/// Remove personal data and credentials from text
+fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    
+    // Paths under the home directory reveal the user name
+    if let Some(home) = dirs::home_dir() {
+        let home = home.to_string_lossy().to_string();
+        if home.len() > 1 {
+            text = text.replace(&home, "~");
+        }
+    }
+    
+    text.split_inclusive(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == ',')
+        .map(|chunk| {
+            let word_len = chunk.trim_end_matches(|c: char| c.is_whitespace() || c == '"' || c == '\'' || c == ',').len();
+            let (word, rest) = chunk.split_at(word_len);
+            format!("{}{}", redact_word(word), rest)
+        })
+        .collect()
+}
+
+fn redact_word(word: &str) -> String {
+    // Email addresses
+    if let Some(at) = word.find('@') {
+        let (user, domain) = (&word[..at], &word[at + 1..]);
+        if !user.is_empty() && domain.contains('.') && !domain.starts_with('.') {
+            return "[email]".to_string();
+        }
+    }
+    
+    // JWTs and other long opaque tokens. Save hashes are lowercase hex and are kept.
+    let value = word.split_once('=').map_or(word, |(_, value)| value);
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    let looks_random = value.chars().any(|c| c.is_ascii_uppercase())
+        && value.chars().any(|c| c.is_ascii_lowercase())
+        && value.chars().any(|c| c.is_ascii_digit());
+    if value.starts_with("eyJ") || (value.len() >= 32 && value.chars().all(is_token_char) && looks_random) {
+        return word[..word.len() - value.len()].to_string() + "[token]";
+    }
+    
+    word.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_redacts_emails_and_tokens() {
+        let text = "Logged in as player.one@example.com with token eyJhbGciOiJIUzI1NiJ9.abc.def";
+        let redacted = redact(text);
+        assert_eq!(redacted, "Logged in as [email] with token [token]");
+    }
+    
+    #[test]
+    fn test_redacts_token_values() {
+        let redacted = redact("access_token=3fA9c0dXb7e6f5aQc3b2a1d0e9fRa7b6c5d, status=ok");
+        assert_eq!(redacted, "access_token=[token], status=ok");
+    }
+    
+    #[test]
+    fn test_doctor_fails_only_on_critical_checks() {
+        let mut report = DoctorReport::default();
+        report.checks.push(DoctorCheck::new("Database", true, Ok("2 games, 5 saves".to_string())));
+        report.checks.push(DoctorCheck::new("PCSX2 save directory", false, Err("not found".to_string())));
+        assert!(report.passed());
+        assert!(report.to_string().contains("[WARN] PCSX2 save directory: not found"));
+        
+        report.checks.push(DoctorCheck::new("Data directory writable", true, Err("permission denied".to_string())));
+        assert!(!report.passed());
+        assert!(report.to_string().contains("[FAIL] Data directory writable: permission denied"));
+    }
+    
+    #[test]
+    fn test_check_writable() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join("data");
+        check_writable(&dir).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    }
+    
+    #[test]
+    fn test_keeps_regular_text() {
+        let text = "Recorded save #3 for Final Fantasy X (PCSX2) hash 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        assert_eq!(redact(text), text);
+    }
+}
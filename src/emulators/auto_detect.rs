@@ -32,7 +32,7 @@ impl EmulatorManager {
     
     /// Scan for all running emulators and update tracking
     pub async fn scan_emulators(&mut self) -> Vec<String> {
-        let running_emulators = process::detect_running_emulators();
+        let running_emulators = process::detect_running_emulators(&[]);
         let mut changes = Vec::new();
         
         // Track which emulators are currently running
@@ -79,11 +79,11 @@ impl EmulatorManager {
         info!("Setting up save monitoring for {} at: {}", emulator_name, save_dir.display());
         
         // Create save watcher
-        let (mut watcher, receiver) = SaveWatcher::new(save_dir.clone(), self.database.clone())?;
+        let (mut watcher, receiver) = SaveWatcher::new_with_emulator(save_dir.clone(), self.database.clone(), emulator_name.clone())?;
         watcher.start().await?;
         
         // Get current game if possible
-        let current_game = Self::detect_current_game(&emulator);
+        let current_game = Self::detect_current_game(&emulator).await;
         
         let tracked = TrackedEmulator {
             process: emulator,
@@ -112,7 +112,7 @@ impl EmulatorManager {
         let mut game_updates = Vec::new();
         
         for (emulator_name, tracked) in &mut self.tracked_emulators {
-            if let Some(new_game) = Self::detect_current_game(&tracked.process) {
+            if let Some(new_game) = Self::detect_current_game(&tracked.process).await {
                 if tracked.current_game.as_ref() != Some(&new_game) {
                     info!("{} now playing: {}", emulator_name, new_game);
                     game_updates.push((emulator_name.clone(), new_game.clone()));
@@ -220,17 +220,11 @@ impl EmulatorManager {
             .ok_or_else(|| anyhow::anyhow!("Could not find save directory"))
     }
     
-    fn detect_current_game(emulator: &EmulatorProcess) -> Option<String> {
-        match emulator {
-            EmulatorProcess::PCSX2 { pid, .. } => process::get_pcsx2_game_name(*pid),
-            EmulatorProcess::Dolphin { pid, .. } => process::get_dolphin_game_name(*pid),
-            EmulatorProcess::RPCS3 { pid, .. } => process::get_rpcs3_game_name(*pid),
-            EmulatorProcess::Citra { pid, .. } => process::get_citra_game_name(*pid),
-            EmulatorProcess::RetroArch { pid, .. } => process::get_retroarch_game_name(*pid),
-            EmulatorProcess::Yuzu { pid, .. } => process::get_yuzu_game_name(*pid),
-            EmulatorProcess::Ryujinx { pid, .. } => process::get_ryujinx_game_name(*pid),
-            EmulatorProcess::PPSSPP { pid, .. } => process::get_ppsspp_game_name(*pid),
-        }
+    async fn detect_current_game(emulator: &EmulatorProcess) -> Option<String> {
+        process::detect_game_name(emulator).await.unwrap_or_else(|e| {
+            warn!("{}", e);
+            None
+        })
     }
 }
 
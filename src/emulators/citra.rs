@@ -34,43 +34,29 @@ impl Citra {
     fn get_citra_save_directory() -> Option<String> {
         #[cfg(target_os = "windows")]
         {
-            if let Ok(appdata) = std::env::var("APPDATA") {
-                // Check Citra save data location
-                let citra_path = format!("{}\\Citra\\sdmc\\Nintendo 3DS", appdata);
-                if std::path::Path::new(&citra_path).exists() {
-                    return Some(citra_path);
-                }
-                
-                // Check portable installation
-                if let Ok(home) = std::env::var("USERPROFILE") {
-                    let portable_path = format!("{}\\Citra\\user\\sdmc\\Nintendo 3DS", home);
-                    if std::path::Path::new(&portable_path).exists() {
-                        return Some(portable_path);
-                    }
-                }
+            // Portable builds keep a `user` folder next to citra-qt.exe
+            let mut candidates: Vec<PathBuf> = super::paths::running_install_dirs("Citra")
+                .into_iter()
+                .map(|dir| dir.join("user\\sdmc\\Nintendo 3DS"))
+                .collect();
+            candidates.extend([
+                super::paths::config_path("Citra\\sdmc\\Nintendo 3DS"),
+                super::paths::home_path("Citra\\user\\sdmc\\Nintendo 3DS"),
+            ].into_iter().flatten());
+            if let Some(dir) = super::paths::resolve_emulator_dir(&candidates) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
         #[cfg(target_os = "linux")]
         {
-            if let Ok(home) = std::env::var("HOME") {
-                // Check Flatpak location first
-                let flatpak_path = format!("{}/.var/app/org.citra_emu.citra/data/citra-emu/sdmc/Nintendo 3DS", home);
-                if std::path::Path::new(&flatpak_path).exists() {
-                    return Some(flatpak_path);
-                }
-                
-                // Check standard location
-                let standard_path = format!("{}/.local/share/citra-emu/sdmc/Nintendo 3DS", home);
-                if std::path::Path::new(&standard_path).exists() {
-                    return Some(standard_path);
-                }
-                
-                // Check old location
-                let old_path = format!("{}/.citra-emu/sdmc/Nintendo 3DS", home);
-                if std::path::Path::new(&old_path).exists() {
-                    return Some(old_path);
-                }
+            let candidates: Vec<PathBuf> = [
+                super::paths::flatpak_path("org.citra_emu.citra", "data/citra-emu/sdmc/Nintendo 3DS"),
+                super::paths::data_path("citra-emu/sdmc/Nintendo 3DS"),
+                super::paths::home_path(".citra-emu/sdmc/Nintendo 3DS"),
+            ].into_iter().flatten().collect();
+            if let Some(dir) = super::paths::resolve_emulator_dir(&candidates) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
@@ -162,7 +148,7 @@ impl Emulator for Citra {
                 return Some(game_name);
             }
             // Fallback to generic name if we can't get the actual title
-            Some("Unknown 3DS Game".to_string())
+            Some(super::unknown_game_name(self.name()))
         } else {
             None
         }
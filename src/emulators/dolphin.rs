@@ -43,46 +43,33 @@ impl Dolphin {
         
         #[cfg(target_os = "windows")]
         {
-            if let Ok(home) = std::env::var("USERPROFILE") {
-                // Check Documents folder for Dolphin
-                let dolphin_path = format!("{}\\Documents\\Dolphin Emulator", home);
-                if std::path::Path::new(&dolphin_path).exists() {
-                    save_dir = Some(format!("{}\\GC", dolphin_path));
-                    state_dir = Some(format!("{}\\StateSaves", dolphin_path));
-                }
-                
-                // Check AppData for portable installation
-                let appdata_path = format!("{}\\AppData\\Roaming\\Dolphin Emulator", home);
-                if save_dir.is_none() && std::path::Path::new(&appdata_path).exists() {
-                    save_dir = Some(format!("{}\\GC", appdata_path));
-                    state_dir = Some(format!("{}\\StateSaves", appdata_path));
-                }
+            // Same order as Dolphin itself: a portable.txt next to the exe,
+            // the legacy Documents folder, then AppData
+            let mut candidates: Vec<PathBuf> = super::paths::running_install_dirs("Dolphin")
+                .into_iter()
+                .filter(|dir| dir.join("portable.txt").exists())
+                .map(|dir| dir.join("User"))
+                .collect();
+            candidates.extend([
+                super::paths::documents_path("Dolphin Emulator"),
+                super::paths::config_path("Dolphin Emulator"),
+            ].into_iter().flatten());
+            if let Some(dolphin_path) = super::paths::resolve_emulator_dir(&candidates) {
+                save_dir = Some(dolphin_path.join("GC").to_string_lossy().to_string());
+                state_dir = Some(dolphin_path.join("StateSaves").to_string_lossy().to_string());
             }
         }
         
         #[cfg(target_os = "linux")]
         {
-            if let Ok(home) = std::env::var("HOME") {
-                // Check Flatpak location first
-                let flatpak_path = format!("{}/.var/app/org.DolphinEmu.dolphin-emu/data/dolphin-emu", home);
-                if std::path::Path::new(&flatpak_path).exists() {
-                    save_dir = Some(format!("{}/GC", flatpak_path));
-                    state_dir = Some(format!("{}/StateSaves", flatpak_path));
-                } else {
-                    // Check standard location
-                    let standard_path = format!("{}/.local/share/dolphin-emu", home);
-                    if std::path::Path::new(&standard_path).exists() {
-                        save_dir = Some(format!("{}/GC", standard_path));
-                        state_dir = Some(format!("{}/StateSaves", standard_path));
-                    } else {
-                        // Check old location
-                        let old_path = format!("{}/.dolphin-emu", home);
-                        if std::path::Path::new(&old_path).exists() {
-                            save_dir = Some(format!("{}/GC", old_path));
-                            state_dir = Some(format!("{}/StateSaves", old_path));
-                        }
-                    }
-                }
+            let candidates: Vec<PathBuf> = [
+                super::paths::flatpak_path("org.DolphinEmu.dolphin-emu", "data/dolphin-emu"),
+                super::paths::data_path("dolphin-emu"),
+                super::paths::home_path(".dolphin-emu"),
+            ].into_iter().flatten().collect();
+            if let Some(dolphin_path) = super::paths::resolve_emulator_dir(&candidates) {
+                save_dir = Some(dolphin_path.join("GC").to_string_lossy().to_string());
+                state_dir = Some(dolphin_path.join("StateSaves").to_string_lossy().to_string());
             }
         }
         
@@ -196,7 +183,7 @@ impl Emulator for Dolphin {
                 return Some(game_name);
             }
             // Fallback to generic name if we can't get the actual title
-            Some("Unknown GameCube/Wii Game".to_string())
+            Some(super::unknown_game_name(self.name()))
         } else {
             None
         }
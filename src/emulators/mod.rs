@@ -6,10 +6,47 @@ pub mod retroarch;
 pub mod yuzu_ryujinx;
 pub mod ppsspp;
 pub mod auto_detect;
+pub mod paths;
 
 use async_trait::async_trait;
 use anyhow::Result;
 
+/// Placeholder for a game that couldn't be identified from its save
+pub const UNKNOWN_GAME: &str = "Unknown Game";
+
+/// Placeholder shown while an emulator's running game can't be identified.
+/// Saves are never recorded under it, see `is_unknown_game`.
+pub fn unknown_game_name(emulator: &str) -> String {
+    match emulator {
+        "PCSX2" => "Unknown PS2 Game",
+        "Dolphin" => "Unknown GameCube/Wii Game",
+        "RPCS3" => "Unknown PS3 Game",
+        "Citra" => "Unknown 3DS Game",
+        "RetroArch" => "Unknown RetroArch Game",
+        "Yuzu" | "Ryujinx" => "Unknown Switch Game",
+        "PPSSPP" => "Unknown PSP Game",
+        _ => UNKNOWN_GAME,
+    }.to_string()
+}
+
+/// Placeholder for an unidentified game told apart from others by `stable_id`
+pub fn unknown_game_name_with_id(emulator: &str, stable_id: &str) -> String {
+    format!("{} ({})", unknown_game_name(emulator), &stable_id[..stable_id.len().min(8)])
+}
+
+/// Whether `name` is one of the placeholders rather than a real game
+pub fn is_unknown_game(name: &str) -> bool {
+    let name = match name.rsplit_once(" (") {
+        Some((base, suffix)) if suffix.ends_with(')') => base,
+        _ => name,
+    };
+    name == UNKNOWN_GAME
+        || name == "Unknown"
+        || ["PCSX2", "Dolphin", "RPCS3", "Citra", "RetroArch", "Yuzu", "PPSSPP"]
+            .iter()
+            .any(|emulator| name == unknown_game_name(emulator))
+}
+
 /// Trait that all emulator implementations must follow
 #[async_trait]
 pub trait Emulator {
@@ -91,4 +128,18 @@ mod tests {
         let result = emulator.monitor_saves().await;
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_unknown_game_names() {
+        assert_eq!(unknown_game_name("RPCS3"), "Unknown PS3 Game");
+        assert_eq!(unknown_game_name("Ryujinx"), unknown_game_name("Yuzu"));
+        for emulator in ["PCSX2", "Dolphin", "RPCS3", "Citra", "RetroArch", "Yuzu", "Ryujinx", "PPSSPP", "Other"] {
+            assert!(is_unknown_game(&unknown_game_name(emulator)));
+        }
+        assert!(is_unknown_game(UNKNOWN_GAME));
+        assert!(is_unknown_game(&unknown_game_name_with_id("Citra", "0123456789abcdef")));
+        assert!(!is_unknown_game("Pokemon Y (Europe)"));
+        assert!(!is_unknown_game("Unknown Soldier"));
+        assert!(!is_unknown_game("Final Fantasy X"));
+    }
 }
\ No newline at end of file
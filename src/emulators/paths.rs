@@ -0,0 +1,103 @@
+//! Where emulators keep their files.
+//!
+//! The same emulator can live in several places depending on how it was
+//! installed. On Linux that's a Flatpak sandbox under `~/.var/app`, a Snap
+//! under `~/snap`, or natively in the XDG config/data directories (which users
+//! can move with `$XDG_CONFIG_HOME`/`$XDG_DATA_HOME`, common on immutable
+//! distros). On Windows it's `%APPDATA%`, Documents, or a portable install
+//! keeping its data next to the executable. Each emulator lists its
+//! candidates with the helpers here and takes the first one that exists.
+//! Base directories come from the `dirs` crate, which applies the XDG
+//! overrides and ignores relative values as the spec requires.
+
+use std::path::{Path, PathBuf};
+
+/// The user's home directory
+pub fn home_dir() -> Option<PathBuf> {
+    dirs::home_dir()
+}
+
+/// `rel` inside the home directory, for dotfolders like `~/.dolphin-emu`
+pub fn home_path(rel: impl AsRef<Path>) -> Option<PathBuf> {
+    home_dir().map(|home| home.join(rel))
+}
+
+/// `rel` inside `$XDG_CONFIG_HOME`, `~/.config` when unset. `%APPDATA%` on Windows.
+pub fn config_path(rel: impl AsRef<Path>) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(rel))
+}
+
+/// `rel` inside `$XDG_DATA_HOME`, `~/.local/share` when unset
+pub fn data_path(rel: impl AsRef<Path>) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(rel))
+}
+
+/// `rel` inside the user's Documents folder, wherever it was moved to (OneDrive
+/// redirects it on many Windows machines)
+pub fn documents_path(rel: impl AsRef<Path>) -> Option<PathBuf> {
+    dirs::document_dir().map(|dir| dir.join(rel))
+}
+
+/// Folders the running copies of `emulator` were started from. Portable
+/// installs keep their data there, and RPCS3 on Windows always does.
+pub fn running_install_dirs(emulator: &str) -> Vec<PathBuf> {
+    crate::monitor::process::detect_running_emulators(&[])
+        .into_iter()
+        .filter(|process| process.name() == emulator)
+        .filter_map(|process| Path::new(process.exe_path()).parent().map(Path::to_path_buf))
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .collect()
+}
+
+/// `rel` inside the Flatpak sandbox of `app_id`, e.g. `config/dolphin-emu`.
+/// The sandbox has its own XDG directories, so the host's overrides don't apply.
+pub fn flatpak_path(app_id: &str, rel: impl AsRef<Path>) -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".var/app").join(app_id).join(rel))
+}
+
+/// `rel` inside the home directory Snap gives `snap`, e.g. `.config/retroarch`
+pub fn snap_path(snap: &str, rel: impl AsRef<Path>) -> Option<PathBuf> {
+    home_dir().map(|home| home.join("snap").join(snap).join("current").join(rel))
+}
+
+/// First of `candidates` that exists
+pub fn resolve_emulator_dir(candidates: &[PathBuf]) -> Option<PathBuf> {
+    candidates.iter().find(|path| path.exists()).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_resolve_emulator_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let flatpak = temp_dir.path().join("flatpak");
+        let native = temp_dir.path().join("native");
+        let legacy = temp_dir.path().join("legacy");
+        std::fs::create_dir(&native).unwrap();
+        std::fs::create_dir(&legacy).unwrap();
+        
+        let candidates = [flatpak, native.clone(), legacy];
+        assert_eq!(resolve_emulator_dir(&candidates), Some(native));
+        assert_eq!(resolve_emulator_dir(&candidates[..1]), None);
+        assert_eq!(resolve_emulator_dir(&[]), None);
+    }
+    
+    #[test]
+    fn test_sandbox_paths() {
+        let Some(home) = home_dir() else {
+            return;
+        };
+        
+        assert_eq!(
+            flatpak_path("org.DolphinEmu.dolphin-emu", "data/dolphin-emu"),
+            Some(home.join(".var/app/org.DolphinEmu.dolphin-emu/data/dolphin-emu"))
+        );
+        assert_eq!(
+            snap_path("retroarch", ".config/retroarch"),
+            Some(home.join("snap/retroarch/current/.config/retroarch"))
+        );
+    }
+}
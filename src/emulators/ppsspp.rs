@@ -74,43 +74,21 @@ impl PPSSPP {
         
         #[cfg(target_os = "linux")]
         {
-            if let Ok(home) = std::env::var("HOME") {
-                // Check Flatpak location first
-                let flatpak_save = format!("{}/.var/app/org.ppsspp.PPSSPP/.config/ppsspp/PSP/SAVEDATA", home);
-                if std::path::Path::new(&flatpak_save).exists() {
-                    save_dir = Some(flatpak_save);
-                }
-                
-                let flatpak_state = format!("{}/.var/app/org.ppsspp.PPSSPP/.config/ppsspp/PSP/PPSSPP_STATE", home);
-                if std::path::Path::new(&flatpak_state).exists() {
-                    state_dir = Some(flatpak_state);
-                }
-                
-                // Check standard location
-                if save_dir.is_none() {
-                    let standard_save = format!("{}/.config/ppsspp/PSP/SAVEDATA", home);
-                    if std::path::Path::new(&standard_save).exists() {
-                        save_dir = Some(standard_save);
-                    }
-                    
-                    let standard_state = format!("{}/.config/ppsspp/PSP/PPSSPP_STATE", home);
-                    if std::path::Path::new(&standard_state).exists() {
-                        state_dir = Some(standard_state);
-                    }
-                }
-                
-                // Check old location
-                if save_dir.is_none() {
-                    let old_save = format!("{}/.ppsspp/PSP/SAVEDATA", home);
-                    if std::path::Path::new(&old_save).exists() {
-                        save_dir = Some(old_save);
-                    }
-                    
-                    let old_state = format!("{}/.ppsspp/PSP/PPSSPP_STATE", home);
-                    if std::path::Path::new(&old_state).exists() {
-                        state_dir = Some(old_state);
-                    }
-                }
+            let ppsspp_dirs: Vec<PathBuf> = [
+                super::paths::flatpak_path("org.ppsspp.PPSSPP", ".config/ppsspp"),
+                super::paths::snap_path("ppsspp-emu", ".config/ppsspp"),
+                super::paths::config_path("ppsspp"),
+                super::paths::home_path(".ppsspp"),
+            ].into_iter().flatten().collect();
+            
+            let saves: Vec<PathBuf> = ppsspp_dirs.iter().map(|dir| dir.join("PSP/SAVEDATA")).collect();
+            if let Some(dir) = super::paths::resolve_emulator_dir(&saves) {
+                save_dir = Some(dir.to_string_lossy().to_string());
+            }
+            
+            let states: Vec<PathBuf> = ppsspp_dirs.iter().map(|dir| dir.join("PSP/PPSSPP_STATE")).collect();
+            if let Some(dir) = super::paths::resolve_emulator_dir(&states) {
+                state_dir = Some(dir.to_string_lossy().to_string());
             }
         }
         
@@ -278,7 +256,7 @@ impl Emulator for PPSSPP {
                 }
             }
             
-            Some("Unknown PSP Game".to_string())
+            Some(super::unknown_game_name(self.name()))
         } else {
             None
         }
@@ -70,34 +70,22 @@ impl RetroArch {
         
         #[cfg(target_os = "linux")]
         {
-            if let Ok(home) = std::env::var("HOME") {
-                // Check Flatpak location first
-                let flatpak_path = format!("{}/.var/app/org.libretro.RetroArch/config/retroarch", home);
-                if std::path::Path::new(&flatpak_path).exists() {
-                    config_path = Some(format!("{}/retroarch.cfg", flatpak_path));
-                    
-                    if let Some((saves, states)) = Self::parse_config(&config_path.clone().unwrap()) {
-                        save_dir = Some(saves);
-                        state_dir = Some(states);
-                    } else {
-                        save_dir = Some(format!("{}/saves", flatpak_path));
-                        state_dir = Some(format!("{}/states", flatpak_path));
-                    }
+            let candidates: Vec<PathBuf> = [
+                super::paths::flatpak_path("org.libretro.RetroArch", "config/retroarch"),
+                super::paths::snap_path("retroarch", ".config/retroarch"),
+                super::paths::config_path("retroarch"),
+            ].into_iter().flatten().collect();
+            if let Some(retroarch_path) = super::paths::resolve_emulator_dir(&candidates) {
+                let cfg = retroarch_path.join("retroarch.cfg").to_string_lossy().to_string();
+                
+                if let Some((saves, states)) = Self::parse_config(&cfg) {
+                    save_dir = Some(saves);
+                    state_dir = Some(states);
                 } else {
-                    // Check standard location
-                    let standard_path = format!("{}/.config/retroarch", home);
-                    if std::path::Path::new(&standard_path).exists() {
-                        config_path = Some(format!("{}/retroarch.cfg", standard_path));
-                        
-                        if let Some((saves, states)) = Self::parse_config(&config_path.clone().unwrap()) {
-                            save_dir = Some(saves);
-                            state_dir = Some(states);
-                        } else {
-                            save_dir = Some(format!("{}/saves", standard_path));
-                            state_dir = Some(format!("{}/states", standard_path));
-                        }
-                    }
+                    save_dir = Some(retroarch_path.join("saves").to_string_lossy().to_string());
+                    state_dir = Some(retroarch_path.join("states").to_string_lossy().to_string());
                 }
+                config_path = Some(cfg);
             }
         }
         
@@ -294,7 +282,7 @@ impl Emulator for RetroArch {
             }
             
             // Generic fallback
-            Some("Unknown RetroArch Game".to_string())
+            Some(super::unknown_game_name(self.name()))
         } else {
             None
         }
@@ -2,7 +2,12 @@ use super::Emulator;
 use async_trait::async_trait;
 use anyhow::Result;
 use tracing::{info, debug, warn};
-use std::path::PathBuf;
+use std::fs;
+use crate::storage::param_sfo::{ParamSfo, PARAM_SFO};
+use std::path::{Path, PathBuf};
+
+/// User RPCS3 creates on first start
+const DEFAULT_USER: &str = "00000001";
 
 pub struct RPCS3 {
     pid: Option<u32>,
@@ -34,41 +39,27 @@ impl RPCS3 {
     fn get_rpcs3_save_directory() -> Option<String> {
         #[cfg(target_os = "windows")]
         {
-            if let Ok(home) = std::env::var("USERPROFILE") {
-                // Check for RPCS3 save data
-                let rpcs3_path = format!("{}\\RPCS3\\dev_hdd0\\home\\00000001\\savedata", home);
-                if std::path::Path::new(&rpcs3_path).exists() {
-                    return Some(rpcs3_path);
-                }
-                
-                // Check Documents folder
-                let docs_path = format!("{}\\Documents\\RPCS3\\dev_hdd0\\home\\00000001\\savedata", home);
-                if std::path::Path::new(&docs_path).exists() {
-                    return Some(docs_path);
-                }
+            // RPCS3 has no installer on Windows, its data lives next to rpcs3.exe
+            let mut roots = super::paths::running_install_dirs("RPCS3");
+            roots.extend([
+                super::paths::home_path("RPCS3"),
+                super::paths::documents_path("RPCS3"),
+                Some(PathBuf::from("C:\\RPCS3")),
+            ].into_iter().flatten());
+            if let Some(dir) = roots.iter().find_map(|root| Self::find_savedata(root)) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
         #[cfg(target_os = "linux")]
         {
-            if let Ok(home) = std::env::var("HOME") {
-                // Check Flatpak location first
-                let flatpak_path = format!("{}/.var/app/net.rpcs3.RPCS3/config/rpcs3/dev_hdd0/home/00000001/savedata", home);
-                if std::path::Path::new(&flatpak_path).exists() {
-                    return Some(flatpak_path);
-                }
-                
-                // Check standard location
-                let standard_path = format!("{}/.config/rpcs3/dev_hdd0/home/00000001/savedata", home);
-                if std::path::Path::new(&standard_path).exists() {
-                    return Some(standard_path);
-                }
-                
-                // Check old location
-                let old_path = format!("{}/.rpcs3/dev_hdd0/home/00000001/savedata", home);
-                if std::path::Path::new(&old_path).exists() {
-                    return Some(old_path);
-                }
+            let roots: Vec<PathBuf> = [
+                super::paths::flatpak_path("net.rpcs3.RPCS3", "config/rpcs3"),
+                super::paths::config_path("rpcs3"),
+                super::paths::home_path(".rpcs3"),
+            ].into_iter().flatten().collect();
+            if let Some(dir) = roots.iter().find_map(|root| Self::find_savedata(root)) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
@@ -85,6 +76,34 @@ impl RPCS3 {
         None
     }
     
+    /// Save data of the RPCS3 install at `root`: the active user's, else the
+    /// default user's (`00000001`), else any user's
+    fn find_savedata(root: &Path) -> Option<PathBuf> {
+        let home = root.join("dev_hdd0").join("home");
+        let preferred = Self::active_user(root).into_iter().chain([DEFAULT_USER.to_string()]);
+        for user in preferred {
+            let savedata = home.join(user).join("savedata");
+            if savedata.exists() {
+                return Some(savedata);
+            }
+        }
+        
+        let mut users: Vec<PathBuf> = fs::read_dir(&home)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path().join("savedata"))
+            .filter(|path| path.exists())
+            .collect();
+        users.sort();
+        users.into_iter().next()
+    }
+    
+    /// User RPCS3 logs in as, from `GuiConfigs/persistent_settings.dat`
+    fn active_user(root: &Path) -> Option<String> {
+        let settings = fs::read_to_string(root.join("GuiConfigs").join("persistent_settings.dat")).ok()?;
+        parse_active_user(&settings)
+    }
+    
     fn detect_save_files(&self) -> Vec<PathBuf> {
         let mut save_files = Vec::new();
         
@@ -110,17 +129,28 @@ impl RPCS3 {
     }
     
     fn get_game_title_from_save(&self, save_path: &PathBuf) -> Option<String> {
-        // Try to read PARAM.SFO file which contains game metadata
-        let param_file = save_path.join("PARAM.SFO");
-        if param_file.exists() {
-            // For now, use the directory name (game ID) as the title
-            // In a full implementation, we would parse the SFO file
-            if let Some(dir_name) = save_path.file_name() {
-                return Some(dir_name.to_string_lossy().to_string());
+        // PARAM.SFO holds the game's name, the folder is named after its title ID
+        let sfo = ParamSfo::parse(save_path.join(PARAM_SFO))?;
+        sfo.title()
+            .map(str::to_string)
+            .or_else(|| save_path.file_name().map(|name| name.to_string_lossy().to_string()))
+    }
+}
+
+/// `active_user` under `[Users]` in RPCS3's persistent settings, a user ID
+/// is eight digits
+fn parse_active_user(settings: &str) -> Option<String> {
+    let mut in_users = false;
+    for line in settings.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_users = line == "[Users]";
+        } else if let Some(("active_user", value)) = line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+            if in_users && value.len() == 8 && value.chars().all(|c| c.is_ascii_digit()) {
+                return Some(value.to_string());
             }
         }
-        None
     }
+    None
 }
 
 #[async_trait]
@@ -144,7 +174,7 @@ impl Emulator for RPCS3 {
                 return Some(game_name);
             }
             // Fallback to generic name if we can't get the actual title
-            Some("Unknown PS3 Game".to_string())
+            Some(super::unknown_game_name(self.name()))
         } else {
             None
         }
@@ -223,6 +253,34 @@ mod tests {
         assert!(!rpcs3.is_running());
     }
     
+    #[test]
+    fn test_find_savedata() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let home = temp_dir.path().join("dev_hdd0").join("home");
+        assert_eq!(RPCS3::find_savedata(temp_dir.path()), None);
+        
+        // Any user's save data when the default one has none
+        fs::create_dir_all(home.join("00000002").join("savedata")).unwrap();
+        assert_eq!(RPCS3::find_savedata(temp_dir.path()), Some(home.join("00000002").join("savedata")));
+        
+        fs::create_dir_all(home.join("00000001").join("savedata")).unwrap();
+        assert_eq!(RPCS3::find_savedata(temp_dir.path()), Some(home.join("00000001").join("savedata")));
+        
+        // The active user wins over the default one
+        let gui_configs = temp_dir.path().join("GuiConfigs");
+        fs::create_dir(&gui_configs).unwrap();
+        fs::write(gui_configs.join("persistent_settings.dat"), "[Users]\nactive_user=00000002\n").unwrap();
+        assert_eq!(RPCS3::find_savedata(temp_dir.path()), Some(home.join("00000002").join("savedata")));
+    }
+    
+    #[test]
+    fn test_parse_active_user() {
+        assert_eq!(parse_active_user("[Meta]\nfoo=bar\n[Users]\nactive_user=00000003\n"), Some("00000003".to_string()));
+        assert_eq!(parse_active_user("[Other]\nactive_user=00000003\n"), None);
+        assert_eq!(parse_active_user("[Users]\nactive_user=../../etc\n"), None);
+        assert_eq!(parse_active_user(""), None);
+    }
+    
     #[test]
     fn test_detect_save_files() {
         let rpcs3 = RPCS3::new();
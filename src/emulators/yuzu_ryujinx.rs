@@ -61,35 +61,26 @@ impl YuzuRyujinx {
     fn get_yuzu_save_directory() -> Option<String> {
         #[cfg(target_os = "windows")]
         {
-            if let Ok(appdata) = std::env::var("APPDATA") {
-                // Yuzu save data location
-                let yuzu_path = format!("{}\\yuzu\\nand\\user\\save", appdata);
-                if std::path::Path::new(&yuzu_path).exists() {
-                    return Some(yuzu_path);
-                }
+            // Portable installs keep a `user` folder next to yuzu.exe
+            let mut candidates: Vec<PathBuf> = super::paths::running_install_dirs("Yuzu")
+                .into_iter()
+                .map(|dir| dir.join("user\\nand\\user\\save"))
+                .collect();
+            candidates.extend(super::paths::config_path("yuzu\\nand\\user\\save"));
+            if let Some(dir) = super::paths::resolve_emulator_dir(&candidates) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
         #[cfg(target_os = "linux")]
         {
-            if let Ok(home) = std::env::var("HOME") {
-                // Check Flatpak location first
-                let flatpak_path = format!("{}/.var/app/org.yuzu_emu.yuzu/data/yuzu/nand/user/save", home);
-                if std::path::Path::new(&flatpak_path).exists() {
-                    return Some(flatpak_path);
-                }
-                
-                // Check standard location
-                let standard_path = format!("{}/.local/share/yuzu/nand/user/save", home);
-                if std::path::Path::new(&standard_path).exists() {
-                    return Some(standard_path);
-                }
-                
-                // Check old location
-                let old_path = format!("{}/.yuzu/nand/user/save", home);
-                if std::path::Path::new(&old_path).exists() {
-                    return Some(old_path);
-                }
+            let candidates: Vec<PathBuf> = [
+                super::paths::flatpak_path("org.yuzu_emu.yuzu", "data/yuzu/nand/user/save"),
+                super::paths::data_path("yuzu/nand/user/save"),
+                super::paths::home_path(".yuzu/nand/user/save"),
+            ].into_iter().flatten().collect();
+            if let Some(dir) = super::paths::resolve_emulator_dir(&candidates) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
@@ -109,29 +100,25 @@ impl YuzuRyujinx {
     fn get_ryujinx_save_directory() -> Option<String> {
         #[cfg(target_os = "windows")]
         {
-            if let Ok(appdata) = std::env::var("APPDATA") {
-                // Ryujinx save data location
-                let ryujinx_path = format!("{}\\Ryujinx\\bis\\user\\save", appdata);
-                if std::path::Path::new(&ryujinx_path).exists() {
-                    return Some(ryujinx_path);
-                }
+            // Portable installs keep a `portable` folder next to Ryujinx.exe
+            let mut candidates: Vec<PathBuf> = super::paths::running_install_dirs("Ryujinx")
+                .into_iter()
+                .map(|dir| dir.join("portable\\bis\\user\\save"))
+                .collect();
+            candidates.extend(super::paths::config_path("Ryujinx\\bis\\user\\save"));
+            if let Some(dir) = super::paths::resolve_emulator_dir(&candidates) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
         #[cfg(target_os = "linux")]
         {
-            if let Ok(home) = std::env::var("HOME") {
-                // Check Flatpak location first
-                let flatpak_path = format!("{}/.var/app/org.ryujinx.Ryujinx/config/Ryujinx/bis/user/save", home);
-                if std::path::Path::new(&flatpak_path).exists() {
-                    return Some(flatpak_path);
-                }
-                
-                // Check standard location
-                let standard_path = format!("{}/.config/Ryujinx/bis/user/save", home);
-                if std::path::Path::new(&standard_path).exists() {
-                    return Some(standard_path);
-                }
+            let candidates: Vec<PathBuf> = [
+                super::paths::flatpak_path("org.ryujinx.Ryujinx", "config/Ryujinx/bis/user/save"),
+                super::paths::config_path("Ryujinx/bis/user/save"),
+            ].into_iter().flatten().collect();
+            if let Some(dir) = super::paths::resolve_emulator_dir(&candidates) {
+                return Some(dir.to_string_lossy().to_string());
             }
         }
         
@@ -237,7 +224,7 @@ impl Emulator for YuzuRyujinx {
             }
             
             // Fallback to generic name
-            Some(format!("Unknown Switch Game ({})", self.name()))
+            Some(super::unknown_game_name(self.name()))
         } else {
             None
         }
@@ -74,7 +74,7 @@ impl EmulationStation {
             #[cfg(target_os = "linux")]
             Self::get_home_path().map(|p| p.join(".emulationstation")).unwrap_or_default(),
             #[cfg(target_os = "linux")]
-            Self::get_home_path().map(|p| p.join(".config/emulationstation")).unwrap_or_default(),
+            crate::emulators::paths::config_path("emulationstation").unwrap_or_default(),
             #[cfg(target_os = "linux")]
             PathBuf::from("/etc/emulationstation"),
             
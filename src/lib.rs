@@ -1,10 +1,18 @@
 // Expose modules for testing
 pub mod auth;
+pub mod diagnostics;
 pub mod monitor;
 pub mod emulators;
+#[cfg(feature = "gui")]
 pub mod ui;
+pub mod settings;
 pub mod storage;
+#[cfg(feature = "gui")]
 pub mod hotkey;
+pub mod logging;
+pub mod metrics;
 pub mod sync;
 pub mod launchers;
-pub mod payment;
\ No newline at end of file
+pub mod payment;
+pub mod startup;
+pub mod supervisor;
\ No newline at end of file
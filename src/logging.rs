@@ -0,0 +1,157 @@
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{info, warn};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{fmt, prelude::*, reload, EnvFilter, Layer, Registry};
+
+/// Filter used when neither `RETROSAVE_LOG` nor `RUST_LOG` is set
+const DEFAULT_FILTER: &str = "info";
+
+/// Filter directives chosen at startup
+static BASE_FILTER: OnceCell<String> = OnceCell::new();
+/// Lets the filter be swapped while the app is running
+static RELOAD_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+/// Directory and file name prefix of the `--log-file` logs
+static LOG_FILE: OnceCell<(PathBuf, String)> = OnceCell::new();
+
+/// Lines kept in memory for diagnostics exports
+const RECENT_LOG_LINES: usize = 2000;
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Set up logging to the console and optionally a daily rotated file.
+///
+/// - `RETROSAVE_LOG` or `RUST_LOG` set the filter, defaulting to `info`
+/// - `--log-format json` / `RETROSAVE_LOG_FORMAT=json` switches to JSON lines
+/// - `--log-file <path>` / `RETROSAVE_LOG_FILE` also writes logs to that file,
+///   relative paths are placed in the data directory
+///
+/// Keep the returned guard alive, dropping it flushes and closes the log file.
+pub fn init(data_dir: &Path) -> Result<Option<WorkerGuard>> {
+    let base_filter = std::env::var("RETROSAVE_LOG")
+        .or_else(|_| std::env::var("RUST_LOG"))
+        .unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+    let (filter_layer, reload_handle) = reload::Layer::new(EnvFilter::new(&base_filter));
+    
+    let json = arg_value("--log-format")
+        .or_else(|| std::env::var("RETROSAVE_LOG_FORMAT").ok())
+        .is_some_and(|format| format.eq_ignore_ascii_case("json"));
+    
+    let console_layer = if json {
+        fmt::layer().json().boxed()
+    } else {
+        fmt::layer().boxed()
+    };
+    
+    let mut guard = None;
+    let file_layer = match arg_value("--log-file").or_else(|| std::env::var("RETROSAVE_LOG_FILE").ok()) {
+        Some(log_file) => {
+            let log_path = data_dir.join(log_file);
+            let log_dir = log_path.parent().unwrap_or(data_dir).to_path_buf();
+            let file_name = log_path.file_name()
+                .map(|name| name.to_os_string())
+                .unwrap_or_else(|| "retrosave.log".into());
+            std::fs::create_dir_all(&log_dir)?;
+            let _ = LOG_FILE.set((log_dir.clone(), file_name.to_string_lossy().to_string()));
+            
+            let (writer, worker_guard) = tracing_appender::non_blocking(
+                tracing_appender::rolling::daily(log_dir, file_name)
+            );
+            guard = Some(worker_guard);
+            
+            let layer = fmt::layer().with_writer(writer).with_ansi(false);
+            Some(if json { layer.json().boxed() } else { layer.boxed() })
+        }
+        None => None,
+    };
+    
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(console_layer)
+        .with(file_layer)
+        .with(fmt::layer().with_writer(|| RecentLogWriter).with_ansi(false))
+        .init();
+    
+    let _ = BASE_FILTER.set(base_filter);
+    let _ = RELOAD_HANDLE.set(reload_handle);
+    
+    Ok(guard)
+}
+
+/// Switch debug logging for Retrosave on or off without restarting
+pub fn set_verbose(verbose: bool) {
+    let (Some(handle), Some(base_filter)) = (RELOAD_HANDLE.get(), BASE_FILTER.get()) else {
+        return;
+    };
+    
+    let directives = if verbose {
+        format!("{},retrosave=debug", base_filter)
+    } else {
+        base_filter.clone()
+    };
+    
+    match handle.reload(EnvFilter::new(&directives)) {
+        Ok(()) => info!("Log filter set to {}", directives),
+        Err(e) => warn!("Failed to change log filter: {}", e),
+    }
+}
+
+/// Most recent log lines, oldest first
+pub fn recent_logs() -> Vec<String> {
+    RECENT_LOGS.lock().unwrap().iter().cloned().collect()
+}
+
+/// Log files written for `--log-file`, newest first
+pub fn log_files() -> Vec<PathBuf> {
+    let Some((log_dir, prefix)) = LOG_FILE.get() else {
+        return Vec::new();
+    };
+    
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(log_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix.as_str()))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Keeps formatted log lines in `RECENT_LOGS`
+struct RecentLogWriter;
+
+impl Write for RecentLogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // The formatter writes one whole event per call
+        let mut recent = RECENT_LOGS.lock().unwrap();
+        for line in String::from_utf8_lossy(buf).lines() {
+            if recent.len() == RECENT_LOG_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Value of a `--name value` or `--name=value` command line argument
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
@@ -1,33 +1,47 @@
 use anyhow::Result;
 use tracing::{info, error, debug, warn};
-use tracing_subscriber;
 use tokio::sync::mpsc;
 
+#[cfg(feature = "gui")]
 use retrosave::ui::{SystemTray, tray::TrayMessage, SettingsWindow, NotificationManager, AudioFeedback};
-use retrosave::storage::{Database, SettingsManager};
+#[cfg(feature = "gui")]
 use retrosave::hotkey::{HotkeyManager, HotkeyEvent};
-use retrosave::sync::{AuthManager, SyncService, SyncEvent};
+use retrosave::settings::Settings;
+use retrosave::storage::{Database, SettingsManager};
+use retrosave::sync::{AuthManager, CloudApi, SyncService, SyncEvent};
+use retrosave::supervisor::{supervise, supervise_with_heartbeat, Failure, Heartbeat};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load .env file if it exists (for development)
     dotenv::dotenv().ok();
     
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter("retrosave=debug")
-        .init();
-
-    info!("Starting Retrosave...");
-
     // Get data directory
     let data_dir = dirs::data_dir()
         .map(|d| d.join("retrosave"))
         .unwrap_or_else(|| std::path::PathBuf::from(".retrosave"));
     
+    // `retrosave doctor` prints a self-test report and exits, nonzero if a critical check failed
+    if std::env::args().nth(1).as_deref() == Some("doctor") {
+        let report = retrosave::diagnostics::run_doctor(&data_dir).await;
+        print!("{}", report);
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+    
     // Create data directory if it doesn't exist
     tokio::fs::create_dir_all(&data_dir).await?;
+    
+    // `retrosave login` signs in from a terminal, for headless installs
+    if std::env::args().nth(1).as_deref() == Some("login") {
+        return login(&data_dir).await;
+    }
+    
+    // Initialize logging, the guard flushes the log file when main returns
+    let _log_guard = retrosave::logging::init(&data_dir)?;
+
+    info!("Starting Retrosave...");
 
     // Initialize database
     let db_path = data_dir.join("retrosave.db");
@@ -42,13 +56,43 @@ async fn main() -> Result<()> {
     let settings_manager = Arc::new(SettingsManager::new(db.clone()));
     let saved_settings = settings_manager.load_settings().await?;
     info!("Settings loaded from database");
+    retrosave::logging::set_verbose(saved_settings.verbose_logging);
+    
+    // Must happen before any HTTP or WebSocket client is created
+    retrosave::sync::http::set_client_options(retrosave::sync::http::ClientOptions {
+        ca_cert_path: saved_settings.custom_ca_cert_path.as_ref().map(std::path::PathBuf::from),
+        accept_invalid_certs: saved_settings.accept_invalid_certs,
+        proxy_url: saved_settings.proxy_url.clone(),
+    });
+    
+    // Compact the database while nothing else is using it yet
+    if saved_settings.auto_vacuum_enabled {
+        if let Err(e) = db.vacuum_if_due(chrono::Duration::days(7)).await {
+            warn!("Failed to vacuum database: {}", e);
+        }
+    }
+
+    // Refresh the autostart entry in case the executable moved since it was written
+    if saved_settings.start_on_boot {
+        if let Err(e) = retrosave::startup::set_start_on_boot(true) {
+            warn!("Failed to update start on boot entry: {}", e);
+        }
+    }
 
     // Initialize system tray
+    #[cfg(feature = "gui")]
     let (tray, mut tray_receiver) = SystemTray::new()?;
+    #[cfg(feature = "gui")]
     info!("System tray initialized");
 
     // Initialize auth manager early so we can pass it to settings window
-    let auth_manager = Arc::new(AuthManager::new(saved_settings.cloud_api_url.clone()));
+    let profile = db.get_active_profile().await.unwrap_or_else(|e| {
+        warn!("Failed to load active profile: {}", e);
+        retrosave::storage::database::DEFAULT_PROFILE.to_string()
+    });
+    info!("Using cloud profile {}", profile);
+    let auth_manager = Arc::new(AuthManager::with_profile(saved_settings.cloud_api_url.clone(), profile));
+    auth_manager.clone().start_refresh_task();
     
     // Initialize auth manager to load tokens from keyring and sync settings
     let mut synced_settings = saved_settings.clone();
@@ -58,7 +102,7 @@ async fn main() -> Result<()> {
         let settings_manager_for_sync = settings_manager.clone();
         
         // Use a channel to get the synced settings back
-        let (settings_tx, mut settings_rx) = mpsc::channel::<retrosave::ui::settings::Settings>(1);
+        let (settings_tx, mut settings_rx) = mpsc::channel::<Settings>(1);
         
         tokio::spawn(async move {
             if let Err(e) = auth_manager_clone.init().await {
@@ -110,60 +154,121 @@ async fn main() -> Result<()> {
     }
     
     // Create settings window with settings manager and auth manager (sync service will be added later)
+    #[cfg(feature = "gui")]
     let settings_window = Arc::new(SettingsWindow::with_auth_manager(
         synced_settings.clone(),  // Use synced settings instead of saved_settings
         settings_manager.clone(),
         auth_manager.clone()
     )?);
-
+    
+    // Current settings for the background tasks, edited in the settings window
+    // or only updated from the cloud in headless builds
+    #[cfg(feature = "gui")]
+    let shared_settings = settings_window.shared_settings();
+    #[cfg(not(feature = "gui"))]
+    let shared_settings = Arc::new(std::sync::Mutex::new(synced_settings.clone()));
+    
     // Create notification manager for desktop notifications
+    #[cfg(feature = "gui")]
     let notif_manager = Arc::new(NotificationManager::new());
     
     // Create audio feedback for save events
+    #[cfg(feature = "gui")]
     let audio_feedback = Arc::new(AudioFeedback::default());
 
     // Create channels for monitor communication
     let (monitor_sender, mut monitor_receiver) = mpsc::channel::<retrosave::monitor::MonitorEvent>(100);
     let (cmd_sender, cmd_receiver) = mpsc::channel::<retrosave::monitor::MonitorCommand>(10);
     
-    // Create hotkey manager
-    let (hotkey_sender, mut hotkey_receiver) = mpsc::channel::<HotkeyEvent>(100);
-    let hotkey_manager = Arc::new(HotkeyManager::new(hotkey_sender)?);
-    
-    // Set up initial hotkey from settings
     let settings = synced_settings.clone();  // Use synced settings
-    if settings.hotkey_enabled {
-        hotkey_manager.set_save_hotkey(settings.save_hotkey.clone())?;
-    }
     
-    // Start hotkey listener
-    hotkey_manager.clone().start_listening();
+    // Create hotkey manager, set up the initial hotkey from settings and start listening
+    #[cfg(feature = "gui")]
+    let (_hotkey_manager, mut hotkey_receiver) = {
+        let (hotkey_sender, hotkey_receiver) = mpsc::channel::<HotkeyEvent>(100);
+        let hotkey_manager = Arc::new(HotkeyManager::new(hotkey_sender)?);
+        if settings.hotkey_enabled {
+            hotkey_manager.set_save_hotkey(settings.save_hotkey.clone())?;
+        }
+        hotkey_manager.clone().start_listening();
+        (hotkey_manager, hotkey_receiver)
+    };
     
     // Initialize cloud sync service
     let (sync_event_sender, sync_event_receiver) = mpsc::unbounded_channel::<SyncEvent>();
-    let sync_service = Arc::new(SyncService::new(
-        auth_manager.clone(),
-        db.clone(),
-        settings.cloud_api_url.clone(),
-        Some(data_dir.clone()),
-    ));
+    let device_name = gethostname::gethostname().to_string_lossy().to_string();
+    let sync_api: Option<Arc<dyn CloudApi>> = if let Some(folder) = settings.sync_folder() {
+        info!("Syncing through {} instead of the cloud", folder.display());
+        Some(Arc::new(retrosave::sync::FsCloudApi::new(folder, device_name)))
+    } else if let Some(remote) = settings.rclone_target() {
+        info!("Syncing through rclone remote {} instead of the cloud", remote);
+        Some(Arc::new(retrosave::sync::RcloneCloudApi::new(remote, device_name)))
+    } else {
+        None
+    };
+    let sync_service = Arc::new(match sync_api {
+        Some(api) => SyncService::with_api(api, auth_manager.clone(), db.clone(), Some(data_dir.clone())),
+        None => SyncService::new(
+            auth_manager.clone(),
+            db.clone(),
+            settings.cloud_api_url.clone(),
+            Some(data_dir.clone()),
+        ),
+    });
+    
+    sync_service.set_bandwidth_limit(settings.max_sync_bandwidth_kbps);
+    sync_service.set_download_concurrency(settings.parallel_downloads as usize);
+    sync_service.set_upload_concurrency(settings.parallel_uploads as usize);
+    sync_service.set_sync_after_closing_only(settings.sync_after_closing_only);
+    sync_service.set_restore_deleted_saves(settings.restore_deleted_saves);
+    // The environment variable is for headless installs without a settings window
+    sync_service.set_failure_webhook(settings.failure_webhook_url.clone()
+        .or_else(|| std::env::var(retrosave::sync::webhook::FAILURE_WEBHOOK_ENV).ok()));
+    // Locked until the passphrase is entered in the settings window, unless
+    // the key was imported
+    sync_service.set_encryption_required(settings.encryption_enabled);
+    if settings.encryption_enabled {
+        match sync_service.unlock_imported_key().await {
+            Ok(true) => info!("E2E encryption unlocked with the imported key"),
+            Ok(false) => {}
+            Err(e) => warn!("Failed to unlock the imported encryption key: {}", e),
+        }
+    }
+    sync_service.set_conflict_strategy(settings.conflict_strategy).await;
     
     // Set sync service in settings window so it can trigger manual syncs
+    #[cfg(feature = "gui")]
     settings_window.set_sync_service(sync_service.clone());
     
     // Start sync service if cloud sync is enabled
     if settings.cloud_sync_enabled {
         let sync_service_clone = sync_service.clone();
+        let sync_event_receiver = Arc::new(tokio::sync::Mutex::new(sync_event_receiver));
+        #[cfg(feature = "gui")]
+        let on_sync_failure = {
+            let notif_manager = notif_manager.clone();
+            move |_: &Failure| notif_manager.show_error("Cloud Sync Restarted", "Cloud sync stopped unexpectedly and is being restarted")
+        };
+        #[cfg(not(feature = "gui"))]
+        let on_sync_failure = |_: &Failure| {};
         tokio::spawn(async move {
-            if let Err(e) = sync_service_clone.start(sync_event_receiver).await {
-                error!("Sync service error: {}", e);
-            }
+            supervise("Sync service", move || {
+                let sync_service = sync_service_clone.clone();
+                let receiver = sync_event_receiver.clone();
+                async move {
+                    // Waiting here would hang the restart behind a task that hasn't stopped
+                    let Ok(mut receiver) = receiver.try_lock_owned() else {
+                        anyhow::bail!("the previous sync task hasn't stopped yet");
+                    };
+                    sync_service.start(&mut receiver).await
+                }
+            }, on_sync_failure).await;
         });
         
         // Register for settings updates via WebSocket
         let sync_service_for_settings = sync_service.clone();
         let settings_manager_for_ws = settings_manager.clone();
-        let settings_window_for_ws = settings_window.clone();
+        let settings_for_ws = shared_settings.clone();
         tokio::spawn(async move {
             // Give sync service time to initialize
             tokio::time::sleep(std::time::Duration::from_secs(1)).await;
@@ -176,14 +281,12 @@ async fn main() -> Result<()> {
                 event_handler.on_settings_update(move |cloud_settings| {
                     info!("Received settings update via WebSocket");
                     
-                    // Get current local settings
-                    let current_settings = settings_window_for_ws.get_settings();
-                    
-                    // Merge cloud settings with local settings
-                    let merged = retrosave::sync::settings_sync::merge_settings(&current_settings, cloud_settings);
-                    
-                    // Update settings in window
-                    settings_window_for_ws.update_settings(merged.clone());
+                    // Merge cloud settings with the current local settings
+                    let merged = {
+                        let mut settings = settings_for_ws.lock().unwrap();
+                        *settings = retrosave::sync::settings_sync::merge_settings(&settings, cloud_settings);
+                        settings.clone()
+                    };
                     
                     // Save to local database
                     let settings_manager = settings_manager_for_ws.clone();
@@ -210,29 +313,165 @@ async fn main() -> Result<()> {
     } else {
         None
     };
+    let cmd_receiver = Arc::new(tokio::sync::Mutex::new(cmd_receiver));
+    // Set when the monitor restarts, so its settings are sent again
+    let monitor_restarted = Arc::new(AtomicBool::new(false));
+    let monitor_restarted_clone = monitor_restarted.clone();
+    #[cfg(feature = "gui")]
+    let on_monitor_failure = {
+        let notif_manager = notif_manager.clone();
+        move |failure: &Failure| {
+            monitor_restarted_clone.store(true, Ordering::SeqCst);
+            if *failure == Failure::Stalled {
+                notif_manager.show_warning("Monitoring Stalled", "Save monitoring stopped responding and is being restarted");
+            } else {
+                notif_manager.show_error("Monitoring Restarted", "Save monitoring stopped unexpectedly and is being restarted");
+            }
+        }
+    };
+    #[cfg(not(feature = "gui"))]
+    let on_monitor_failure = move |_: &Failure| monitor_restarted_clone.store(true, Ordering::SeqCst);
+    let heartbeat = Heartbeat::new();
     let monitor_handle = tokio::spawn(async move {
-        if let Err(e) = retrosave::monitor::start_monitoring_with_sync(
-            monitor_sender, 
-            db_clone, 
-            cmd_receiver,
-            sync_sender_for_monitor
-        ).await {
-            error!("Monitor error: {}", e);
+        supervise_with_heartbeat("Monitor", heartbeat.clone(), retrosave::monitor::STALL_TIMEOUT, move || {
+            let monitor_sender = monitor_sender.clone();
+            let db = db_clone.clone();
+            let sync_sender = sync_sender_for_monitor.clone();
+            let receiver = cmd_receiver.clone();
+            let heartbeat = heartbeat.clone();
+            async move {
+                // A stalled monitor stuck in a blocking call keeps the receiver until
+                // it yields. Waiting for it would stall this attempt too, failing
+                // restarts with backoff until it's gone.
+                let Ok(mut receiver) = receiver.try_lock_owned() else {
+                    anyhow::bail!("the previous monitor task hasn't stopped yet");
+                };
+                retrosave::monitor::start_monitoring_with_sync(monitor_sender, db, &mut receiver, sync_sender, heartbeat).await
+            }
+        }, on_monitor_failure).await;
+    });
+    
+    // Keep the monitor's auto-save timer, retention, emulators, allowlist and scan interval in step with the settings window
+    let settings_for_monitor = shared_settings.clone();
+    let cmd_sender_settings = cmd_sender.clone();
+    tokio::spawn(async move {
+        let mut applied: Option<(bool, u32)> = None;
+        let mut applied_retention: Option<(u32, u32)> = None;
+        let mut applied_disabled: Option<Vec<String>> = None;
+        let mut applied_allowlist: Option<Option<Vec<String>>> = None;
+        let mut applied_scan_interval: Option<u32> = None;
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(2));
+        loop {
+            interval.tick().await;
+            if monitor_restarted.swap(false, Ordering::SeqCst) {
+                applied = None;
+                applied_retention = None;
+                applied_disabled = None;
+                applied_allowlist = None;
+                applied_scan_interval = None;
+            }
+            let settings = settings_for_monitor.lock().unwrap().clone();
+            let current = (settings.auto_save_enabled, settings.save_interval_minutes);
+            if applied != Some(current) {
+                applied = Some(current);
+                let cmd = retrosave::monitor::MonitorCommand::SetAutoSave {
+                    enabled: current.0,
+                    interval_minutes: current.1,
+                };
+                if cmd_sender_settings.send(cmd).await.is_err() {
+                    break;
+                }
+            }
+            let retention = (settings.max_saves_per_game, settings.max_save_states_per_game);
+            if applied_retention != Some(retention) {
+                applied_retention = Some(retention);
+                let cmd = retrosave::monitor::MonitorCommand::SetRetention {
+                    saves: retention.0,
+                    save_states: retention.1,
+                };
+                if cmd_sender_settings.send(cmd).await.is_err() {
+                    break;
+                }
+            }
+            if applied_disabled.as_ref() != Some(&settings.disabled_emulators) {
+                applied_disabled = Some(settings.disabled_emulators.clone());
+                let cmd = retrosave::monitor::MonitorCommand::SetDisabledEmulators(settings.disabled_emulators.clone());
+                if cmd_sender_settings.send(cmd).await.is_err() {
+                    break;
+                }
+            }
+            let allowlist = settings.allowlist_enabled.then(|| settings.allowlisted_games.clone());
+            if applied_allowlist.as_ref() != Some(&allowlist) {
+                applied_allowlist = Some(allowlist.clone());
+                let cmd = retrosave::monitor::MonitorCommand::SetAllowlist(allowlist);
+                if cmd_sender_settings.send(cmd).await.is_err() {
+                    break;
+                }
+            }
+            if applied_scan_interval != Some(settings.process_scan_interval_secs) {
+                applied_scan_interval = Some(settings.process_scan_interval_secs);
+                let cmd = retrosave::monitor::MonitorCommand::SetScanInterval { seconds: settings.process_scan_interval_secs };
+                if cmd_sender_settings.send(cmd).await.is_err() {
+                    break;
+                }
+            }
         }
     });
 
+    // `retrosave --scan-existing` imports saves that predate Retrosave on startup
+    if std::env::args().any(|arg| arg == "--scan-existing") {
+        let _ = cmd_sender.send(retrosave::monitor::MonitorCommand::ScanExisting).await;
+    }
+    
+    // `retrosave --metrics-addr 0.0.0.0:9898` serves counters for Prometheus
+    #[cfg(feature = "metrics")]
+    if let Some(addr) = metrics_addr() {
+        tokio::spawn(async move {
+            if let Err(e) = retrosave::metrics::serve(&addr).await {
+                error!("Metrics endpoint stopped: {:#}", e);
+            }
+        });
+    }
+    
     // Handle monitor events and update tray
+    let _sync_event_sender_clone = sync_event_sender.clone();
+    let sync_service_clone = sync_service.clone();
+    #[cfg(feature = "gui")]
     let cmd_sender_clone = cmd_sender.clone();
+    #[cfg(feature = "gui")]
     let settings_window_clone = settings_window.clone();
+    #[cfg(feature = "gui")]
     let notif_manager_clone = notif_manager.clone();
+    #[cfg(feature = "gui")]
     let audio_feedback_clone = audio_feedback.clone();
+    #[cfg(feature = "gui")]
     let cmd_sender_hotkey = cmd_sender.clone();
-    let _sync_event_sender_clone = sync_event_sender.clone();
-    let sync_service_clone = sync_service.clone();
+    #[cfg(feature = "gui")]
+    let mut online_rx = sync_service.subscribe_online();
+    #[cfg(feature = "gui")]
+    let mut progress_rx = sync_service.subscribe_progress();
+    #[cfg(feature = "gui")]
+    let (quit_sender, mut quit_receiver) = mpsc::channel::<()>(1);
+    #[cfg(feature = "gui")]
+    let db_for_diagnostics = db.clone();
     
+    #[cfg(feature = "gui")]
     let event_handle = tokio::spawn(async move {
+        let mut monitoring_paused = false;
         loop {
             tokio::select! {
+                Ok(()) = online_rx.changed() => {
+                    let is_online = *online_rx.borrow_and_update();
+                    if is_online {
+                        tray.update_status("Monitoring");
+                    } else {
+                        tray.update_status("Offline - saves will sync when connection returns");
+                    }
+                }
+                Ok(()) = progress_rx.changed() => {
+                    let progress = *progress_rx.borrow_and_update();
+                    tray.show_progress(progress.map(|p| p.to_string()).as_deref());
+                }
                 Some(hotkey_event) = hotkey_receiver.recv() => {
                     match hotkey_event {
                         HotkeyEvent::SaveNow => {
@@ -245,6 +484,9 @@ async fn main() -> Result<()> {
                 Some(event) = monitor_receiver.recv() => {
                     match event {
                         retrosave::monitor::MonitorEvent::EmulatorStarted(name) => {
+                            // Before the sync below, so it can't write under the emulator
+                            sync_service_clone.set_emulator_running(&name, true).await;
+                            
                             let msg = format!("{} detected", name);
                             tray.update_status(&msg);
                             
@@ -259,15 +501,11 @@ async fn main() -> Result<()> {
                             // Trigger sync when emulator starts to ensure latest saves
                             if settings_window_clone.get_settings().cloud_sync_enabled {
                                 info!("Triggering sync on {} start", name);
-                                let sync_service = sync_service_clone.clone();
-                                tokio::spawn(async move {
-                                    if let Err(e) = sync_service.trigger_sync().await {
-                                        error!("Failed to trigger sync on emulator start: {}", e);
-                                    }
-                                });
+                                spawn_sync(&sync_service_clone, "emulator start");
                             }
                         }
                         retrosave::monitor::MonitorEvent::EmulatorStopped(name) => {
+                            sync_service_clone.set_emulator_running(&name, false).await;
                             tray.update_status("Monitoring");
                             
                             // Show desktop notification if enabled
@@ -281,12 +519,7 @@ async fn main() -> Result<()> {
                             // Trigger sync when emulator stops to ensure all saves are uploaded
                             if settings_window_clone.get_settings().cloud_sync_enabled {
                                 info!("Triggering sync on {} stop", name);
-                                let sync_service = sync_service_clone.clone();
-                                tokio::spawn(async move {
-                                    if let Err(e) = sync_service.trigger_sync().await {
-                                        error!("Failed to trigger sync on emulator stop: {}", e);
-                                    }
-                                });
+                                spawn_sync(&sync_service_clone, "emulator stop");
                             }
                         }
                         retrosave::monitor::MonitorEvent::GameDetected(name) => {
@@ -311,6 +544,23 @@ async fn main() -> Result<()> {
                             // Note: The sync event is already sent from monitor/mod.rs when it detects a save
                             // No need to duplicate it here as it causes double uploads
                         }
+                        retrosave::monitor::MonitorEvent::MonitoringStateChanged { paused } => {
+                            monitoring_paused = paused;
+                            tray.update_status(if paused { "Paused" } else { "Monitoring" });
+                            
+                            // Running emulators are announced again after a resume
+                            if !paused {
+                                sync_service_clone.reset_running_emulators().await;
+                            }
+                        }
+                        retrosave::monitor::MonitorEvent::ExistingSavesImported(count) => {
+                            if count > 0 {
+                                let msg = format!("Backed up {} existing save(s)", count);
+                                tray.show_notification("Existing Saves Imported", &msg);
+                            } else {
+                                info!("No existing saves needed importing");
+                            }
+                        }
                         retrosave::monitor::MonitorEvent::ManualSaveResult(result) => {
                             // Play audio feedback
                             audio_feedback_clone.play_save_result(&result);
@@ -365,6 +615,23 @@ async fn main() -> Result<()> {
                             
                             let _ = cmd_sender_clone.send(retrosave::monitor::MonitorCommand::TriggerManualSave).await;
                         }
+                        TrayMessage::ToggleMonitoring => {
+                            let cmd = if monitoring_paused {
+                                retrosave::monitor::MonitorCommand::Resume
+                            } else {
+                                retrosave::monitor::MonitorCommand::Pause
+                            };
+                            let _ = cmd_sender_clone.send(cmd).await;
+                        }
+                        TrayMessage::ScanExistingRequested => {
+                            info!("Existing save scan requested by user");
+                            let _ = cmd_sender_clone.send(retrosave::monitor::MonitorCommand::ScanExisting).await;
+                        }
+                        TrayMessage::Quit => {
+                            info!("Quit requested by user");
+                            tray.exit();
+                            let _ = quit_sender.send(()).await;
+                        }
                         TrayMessage::OpenDashboard => {
                             info!("Opening dashboard in browser");
                             
@@ -397,6 +664,26 @@ async fn main() -> Result<()> {
                                 }
                             });
                         }
+                        TrayMessage::OpenSaveBrowser => {
+                            info!("Opening save browser");
+                            let settings_clone = settings_window_clone.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = settings_clone.show_save_browser().await {
+                                    error!("Failed to show save browser: {}", e);
+                                }
+                            });
+                        }
+                        TrayMessage::ExportDiagnostics => {
+                            let path = retrosave::diagnostics::default_export_path();
+                            let settings = settings_window_clone.get_settings();
+                            match retrosave::diagnostics::export_diagnostics(&path, &db_for_diagnostics, &settings).await {
+                                Ok(()) => tray.show_notification("Diagnostics Exported", &format!("Saved to {}", path.display())),
+                                Err(e) => {
+                                    error!("Failed to export diagnostics: {}", e);
+                                    tray.show_notification("Diagnostics Export Failed", &e.to_string());
+                                }
+                            }
+                        }
                         TrayMessage::HotkeyChanged(new_hotkey) => {
                             info!("Hotkey changed to: {:?}", new_hotkey);
                             // The actual hotkey update is handled by the settings window
@@ -438,13 +725,133 @@ async fn main() -> Result<()> {
             }
         }
     });
-
-    // Wait for Ctrl+C
-    tokio::signal::ctrl_c().await?;
+    
+    // Headless builds only keep the sync service in step with the emulators
+    #[cfg(not(feature = "gui"))]
+    let event_handle = tokio::spawn(async move {
+        while let Some(event) = monitor_receiver.recv().await {
+            match event {
+                retrosave::monitor::MonitorEvent::EmulatorStarted(name) => {
+                    sync_service_clone.set_emulator_running(&name, true).await;
+                    if shared_settings.lock().unwrap().cloud_sync_enabled {
+                        info!("Triggering sync on {} start", name);
+                        spawn_sync(&sync_service_clone, "emulator start");
+                    }
+                }
+                retrosave::monitor::MonitorEvent::EmulatorStopped(name) => {
+                    sync_service_clone.set_emulator_running(&name, false).await;
+                    if shared_settings.lock().unwrap().cloud_sync_enabled {
+                        info!("Triggering sync on {} stop", name);
+                        spawn_sync(&sync_service_clone, "emulator stop");
+                    }
+                }
+                retrosave::monitor::MonitorEvent::MonitoringStateChanged { paused: false } => {
+                    sync_service_clone.reset_running_emulators().await;
+                }
+                other => debug!("Monitor event: {:?}", other),
+            }
+        }
+    });
+    
+    // Wait for Ctrl+C/SIGTERM, Quit from the tray, or the settings window closing when it isn't minimized to the tray
+    #[cfg(feature = "gui")]
+    let quit_requested = async {
+        tokio::select! {
+            _ = quit_receiver.recv() => {}
+            _ = settings_window.quit_requested() => {}
+        }
+    };
+    #[cfg(not(feature = "gui"))]
+    let quit_requested = std::future::pending::<()>();
+    tokio::select! {
+        result = shutdown_signal() => result?,
+        _ = quit_requested => {}
+    }
     info!("Shutting down Retrosave...");
 
+    // Stop detecting saves before flushing what is already queued
     monitor_handle.abort();
     event_handle.abort();
     
+    // Let an in-flight upload finish and persist the rest of the queue
+    if let Err(e) = sync_service.shutdown().await {
+        error!("Failed to shut down sync service: {}", e);
+    }
+    
+    #[cfg(feature = "gui")]
+    if let Err(e) = settings_window.close().await {
+        debug!("Settings window already closed: {}", e);
+    }
+    
+    Ok(())
+}
+
+/// Sign the active profile in through the browser flow, printing the URL
+/// instead of opening a browser, and store the tokens like the settings window does
+async fn login(data_dir: &std::path::Path) -> Result<()> {
+    let db = Arc::new(Database::new(Some(data_dir.join("retrosave.db"))).await?);
+    let settings = SettingsManager::new(db.clone()).load_settings().await?;
+    retrosave::sync::http::set_client_options(retrosave::sync::http::ClientOptions {
+        ca_cert_path: settings.custom_ca_cert_path.as_ref().map(std::path::PathBuf::from),
+        accept_invalid_certs: settings.accept_invalid_certs,
+        proxy_url: settings.proxy_url.clone(),
+    });
+    
+    let profile = db.get_active_profile().await?;
+    let auth_manager = AuthManager::with_profile(settings.cloud_api_url.clone(), profile.clone());
+    let oauth = retrosave::auth::BrowserOAuth::new(settings.cloud_api_url.clone());
+    let tokens = oauth.authenticate_with(|auth_url, port| {
+        println!("Open this address in a browser to sign in to profile {}:\n\n  {}\n", profile, auth_url);
+        println!("The browser is sent back to http://127.0.0.1:{}. On another machine, forward", port);
+        println!("that port first, e.g. ssh -L {0}:127.0.0.1:{0} <this host>.", port);
+        Ok(())
+    }).await?;
+    
+    auth_manager.save_tokens(tokens.access_token, tokens.refresh_token, tokens.user.clone()).await?;
+    println!("Signed in as {}", tokens.user.email);
+    Ok(())
+}
+
+/// Address given with `--metrics-addr <host:port>` or `--metrics-addr=<host:port>`
+#[cfg(feature = "metrics")]
+fn metrics_addr() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--metrics-addr" {
+            return args.next();
+        }
+        if let Some(addr) = arg.strip_prefix("--metrics-addr=") {
+            return Some(addr.to_string());
+        }
+    }
+    None
+}
+
+/// Sync in the background, `reason` is only for the log
+fn spawn_sync(sync_service: &Arc<SyncService>, reason: &'static str) {
+    let sync_service = sync_service.clone();
+    tokio::spawn(async move {
+        if let Err(e) = sync_service.trigger_sync().await {
+            error!("Failed to trigger sync on {}: {}", reason, e);
+        }
+    });
+}
+
+/// Resolves on Ctrl+C, or on SIGTERM from systemd and other service managers
+async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => info!("Received SIGTERM"),
+        }
+    }
+    
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await?;
+    
     Ok(())
-}
\ No newline at end of file
+}
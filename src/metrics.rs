@@ -0,0 +1,171 @@
+//! Backup health counters, updated where saves are recorded and synced and
+//! served in the Prometheus text format with `--metrics-addr`
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters of this process, all starting at zero on launch
+pub static METRICS: Metrics = Metrics::new();
+
+#[derive(Debug)]
+pub struct Metrics {
+    saves_detected: AtomicU64,
+    uploads_completed: AtomicU64,
+    downloads_completed: AtomicU64,
+    sync_failures: AtomicU64,
+    upload_queue_depth: AtomicU64,
+    /// Unix time of the last finished sync, 0 before the first one
+    last_sync_timestamp: AtomicU64,
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Self {
+            saves_detected: AtomicU64::new(0),
+            uploads_completed: AtomicU64::new(0),
+            downloads_completed: AtomicU64::new(0),
+            sync_failures: AtomicU64::new(0),
+            upload_queue_depth: AtomicU64::new(0),
+            last_sync_timestamp: AtomicU64::new(0),
+        }
+    }
+    
+    /// The monitor recorded a new save version
+    pub fn save_detected(&self) {
+        self.saves_detected.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn upload_completed(&self) {
+        self.uploads_completed.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn download_completed(&self) {
+        self.downloads_completed.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    /// A sync finished with errors or timed out
+    pub fn sync_failed(&self) {
+        self.sync_failures.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn set_upload_queue_depth(&self, depth: usize) {
+        self.upload_queue_depth.store(depth as u64, Ordering::Relaxed);
+    }
+    
+    pub fn sync_finished(&self, at: chrono::DateTime<chrono::Utc>) {
+        self.last_sync_timestamp.store(at.timestamp().max(0) as u64, Ordering::Relaxed);
+    }
+    
+    /// Every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let metrics = [
+            ("retrosave_saves_detected_total", "counter", "Save versions recorded by the monitor", &self.saves_detected),
+            ("retrosave_uploads_completed_total", "counter", "Saves uploaded to the cloud", &self.uploads_completed),
+            ("retrosave_downloads_completed_total", "counter", "Cloud saves restored locally", &self.downloads_completed),
+            ("retrosave_sync_failures_total", "counter", "Syncs that finished with errors or timed out", &self.sync_failures),
+            ("retrosave_upload_queue_depth", "gauge", "Saves waiting to be uploaded", &self.upload_queue_depth),
+            ("retrosave_last_sync_timestamp_seconds", "gauge", "Unix time of the last finished sync", &self.last_sync_timestamp),
+        ];
+        
+        let mut output = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            let _ = writeln!(output, "{} {}", name, value.load(Ordering::Relaxed));
+        }
+        output
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serve [`METRICS`] on `GET /metrics` until the listener fails
+#[cfg(feature = "metrics")]
+pub async fn serve(addr: &str) -> anyhow::Result<()> {
+    use anyhow::Context;
+    
+    let listener = tokio::net::TcpListener::bind(addr).await
+        .with_context(|| format!("Failed to listen for metrics requests on {}", addr))?;
+    tracing::info!("Serving metrics on http://{}/metrics", listener.local_addr()?);
+    serve_on(listener).await
+}
+
+#[cfg(feature = "metrics")]
+async fn serve_on(listener: tokio::net::TcpListener) -> anyhow::Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream).await {
+                tracing::debug!("Failed to answer metrics request from {}: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Answer one request and close the connection
+#[cfg(feature = "metrics")]
+async fn respond(mut stream: tokio::net::TcpStream) -> anyhow::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    
+    // Only the request line matters, headers and body are ignored
+    let mut request = [0u8; 1024];
+    let read = tokio::time::timeout(std::time::Duration::from_secs(5), stream.read(&mut request)).await??;
+    let request = String::from_utf8_lossy(&request[..read]);
+    let request_line = request.lines().next().unwrap_or_default();
+    
+    let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+        ["GET", "/metrics"] => ("200 OK", METRICS.render()),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.save_detected();
+        metrics.save_detected();
+        metrics.set_upload_queue_depth(4);
+        
+        let output = metrics.render();
+        assert!(output.contains("# TYPE retrosave_saves_detected_total counter\nretrosave_saves_detected_total 2\n"));
+        assert!(output.contains("retrosave_upload_queue_depth 4\n"));
+        assert!(output.contains("retrosave_sync_failures_total 0\n"));
+    }
+    
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_serves_metrics() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_on(listener));
+        
+        let get = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream.write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("retrosave_saves_detected_total"));
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+    }
+}
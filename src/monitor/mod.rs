@@ -2,16 +2,18 @@ pub mod process;
 
 use anyhow::Result;
 use std::time::Duration;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::time;
 use tokio::sync::mpsc;
 use tracing::{info, debug, warn, error};
 
 use crate::storage::{Database, SaveWatcher, SaveEvent, SaveBackupManager};
+use crate::storage::watcher::glob_match;
 use crate::sync::SyncEvent;
-use crate::emulators::Emulator;
+use crate::supervisor::Heartbeat;
+use crate::emulators::{Emulator, UNKNOWN_GAME, unknown_game_name, unknown_game_name_with_id, is_unknown_game};
 
 #[derive(Debug, Clone)]
 pub enum MonitorEvent {
@@ -24,13 +26,43 @@ pub enum MonitorEvent {
         file_path: String,
     },
     ManualSaveResult(SaveResult),
+    /// Existing saves were imported as initial versions
+    ExistingSavesImported(usize),
+    MonitoringStateChanged { paused: bool },
 }
 
 #[derive(Debug, Clone)]
 pub enum MonitorCommand {
     TriggerManualSave,
+    /// Import saves already on disk that have no version yet
+    ScanExisting,
+    /// Stop watching emulators and saves until resumed
+    Pause,
+    Resume,
+    /// Apply the `auto_save_enabled` and `save_interval_minutes` settings
+    SetAutoSave { enabled: bool, interval_minutes: u32 },
+    /// Apply the `max_saves_per_game` and `max_save_states_per_game` settings
+    SetRetention { saves: u32, save_states: u32 },
+    /// Apply the `disabled_emulators` setting, those are never detected or watched
+    SetDisabledEmulators(Vec<String>),
+    /// Apply the `process_scan_interval_secs` setting
+    SetScanInterval { seconds: u32 },
+    /// Apply the `allowlisted_games` setting, `None` when `allowlist_enabled` is off
+    SetAllowlist(Option<Vec<String>>),
 }
 
+/// Settings key holding extra PCSX2 memory card folders to watch, one per line
+pub const EXTRA_MEMCARD_DIRS_SETTING: &str = "pcsx2_extra_memcard_dirs";
+/// Settings key holding the emulators that are never detected or watched, one per line
+pub const DISABLED_EMULATORS_SETTING: &str = "disabled_emulators";
+/// Settings key turning on allowlist mode, only saves of allowlisted games are recorded
+pub const ALLOWLIST_ENABLED_SETTING: &str = "allowlist_enabled";
+/// Settings key holding the allowlisted game names or patterns, one per line
+pub const ALLOWLISTED_GAMES_SETTING: &str = "allowlisted_games";
+
+/// Emulators the monitor knows how to watch
+pub(crate) const EMULATORS: &[&str] = &["PCSX2", "Dolphin", "RPCS3", "Citra", "RetroArch", "Yuzu", "Ryujinx", "PPSSPP"];
+
 #[derive(Debug, Clone)]
 pub enum SaveResult {
     Success { game_name: String, file_count: usize },
@@ -60,47 +92,111 @@ pub async fn start_monitoring_with_db(
 pub async fn start_monitoring_with_commands(
     sender: mpsc::Sender<MonitorEvent>,
     database: Arc<Database>,
-    cmd_receiver: mpsc::Receiver<MonitorCommand>,
+    mut cmd_receiver: mpsc::Receiver<MonitorCommand>,
 ) -> Result<()> {
-    start_monitoring_with_sync(sender, database, cmd_receiver, None).await
+    start_monitoring_with_sync(sender, database, &mut cmd_receiver, None, Heartbeat::new()).await
 }
 
+/// Borrows `cmd_receiver` so commands queued while the monitor restarts aren't lost,
+/// and beats `heartbeat` at least every [`HEARTBEAT_INTERVAL`] while it's running
 pub async fn start_monitoring_with_sync(
     sender: mpsc::Sender<MonitorEvent>,
     database: Arc<Database>,
-    mut cmd_receiver: mpsc::Receiver<MonitorCommand>,
+    cmd_receiver: &mut mpsc::Receiver<MonitorCommand>,
     sync_sender: Option<mpsc::UnboundedSender<SyncEvent>>,
+    heartbeat: Heartbeat,
 ) -> Result<()> {
     info!("Process monitoring started with save detection");
     
-    let mut interval = time::interval(Duration::from_secs(5));
+    let mut interval = scan_interval(DEFAULT_SCAN_INTERVAL_SECS);
+    // Separate from the scan so a long scan interval doesn't look like a stall
+    let mut heartbeat_timer = time::interval(HEARTBEAT_INTERVAL);
     let mut tracked_emulators = HashSet::new();
-    let mut save_watcher: Option<SaveWatcher> = None;
+    // Scans in a row the tracked emulator wasn't running for
+    let mut missed_scans = 0;
+    // One watcher per save directory of the running emulator, all reporting to `save_receiver`
+    let mut save_watchers: Vec<SaveWatcher> = Vec::new();
     let mut save_receiver: Option<mpsc::Receiver<SaveEvent>> = None;
-    let backup_manager = SaveBackupManager::new(None)?;
+    let mut backup_manager = SaveBackupManager::new(None)?;
     let mut current_game_name: Option<String> = None;
+    // Emulator whose save directory didn't exist yet when it started
+    let mut awaiting_save_dir: Option<String> = None;
+    // Last time the watched directories were compared against the emulator's configuration
+    let mut save_dirs_checked = time::Instant::now();
+    let mut paused = false;
+    // Loaded up front so disabled emulators aren't picked up before the settings are applied
+    let mut disabled_emulators: Vec<String> = match database.get_setting(DISABLED_EMULATORS_SETTING).await {
+        Ok(value) => value.map(|v| v.lines().map(|l| l.to_string()).collect()).unwrap_or_default(),
+        Err(e) => {
+            warn!("Failed to load disabled emulators: {}", e);
+            Vec::new()
+        }
+    };
+    // Same for the allowlist, so other games' saves aren't imported on startup
+    let mut allowlist = load_allowlist(&database).await;
+    // Changes detected while automatic saves are off, recorded on the next manual save
+    let mut auto_save_enabled = true;
+    let mut pending_saves: HashMap<PathBuf, SaveEvent> = HashMap::new();
+    // Saves whose game couldn't be identified, recorded once the running game is
+    let mut unnamed_saves: HashMap<PathBuf, SaveEvent> = HashMap::new();
+    let mut auto_save_timer = auto_save_interval(DEFAULT_SAVE_INTERVAL_MINUTES);
     
     loop {
+        heartbeat.beat();
         tokio::select! {
+            _ = heartbeat_timer.tick() => {
+                continue;
+            }
             _ = interval.tick() => {
                 // Regular monitoring tick
             }
+            _ = auto_save_timer.tick() => {
+                // Periodic check in case the watcher missed a write
+                if auto_save_enabled && !paused && !save_watchers.is_empty() {
+                    match check_for_changes(&save_watchers).await {
+                        Ok(changes) if changes > 0 => info!("Periodic auto-save found {} changed saves", changes),
+                        Ok(_) => debug!("Periodic auto-save found no changes"),
+                        Err(e) => warn!("Periodic auto-save check failed: {}", e),
+                    }
+                }
+            }
             Some(cmd) = cmd_receiver.recv() => {
                 // Handle commands
                 match cmd {
                     MonitorCommand::TriggerManualSave => {
                         info!("Manual save triggered");
                         // Force save detection for all tracked saves
-                        let result = if let Some(ref watcher) = save_watcher {
+                        let result = if paused {
+                            SaveResult::Failed("Monitoring is paused".to_string())
+                        } else if !save_watchers.is_empty() {
                             // Check for actual file changes
-                            match watcher.check_for_changes().await {
-                                Ok(changes) => {
-                                    if changes > 0 {
+                            match check_for_changes(&save_watchers).await {
+                                Ok(_) => {
+                                    // Record right away, along with anything held back
+                                    // while automatic saves were disabled
+                                    let mut events: Vec<SaveEvent> = pending_saves.drain().map(|(_, event)| event).collect();
+                                    if let Some(receiver) = &mut save_receiver {
+                                        while let Ok(save_event) = receiver.try_recv() {
+                                            events.push(save_event);
+                                        }
+                                    }
+                                    
+                                    let mut recorded = 0;
+                                    for save_event in events {
+                                        let Some(save_event) = name_save_event(save_event, current_game_name.as_deref(), &mut unnamed_saves) else {
+                                            continue;
+                                        };
+                                        if record_save_event(save_event, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref(), true).await {
+                                            recorded += 1;
+                                        }
+                                    }
+                                    
+                                    if recorded > 0 {
                                         let game_name = current_game_name.clone()
-                                            .unwrap_or_else(|| "Unknown Game".to_string());
+                                            .unwrap_or_else(|| UNKNOWN_GAME.to_string());
                                         SaveResult::Success { 
                                             game_name,
-                                            file_count: changes 
+                                            file_count: recorded 
                                         }
                                     } else {
                                         SaveResult::NoChanges
@@ -115,492 +211,279 @@ pub async fn start_monitoring_with_sync(
                         // Send result back through event system
                         let _ = sender.send(MonitorEvent::ManualSaveResult(result)).await;
                     }
-                }
-                continue;
-            }
-        }
-        
-        // Check for save events
-        if let Some(receiver) = &mut save_receiver {
-            while let Ok(save_event) = receiver.try_recv() {
-                info!("Save detected: {} - {}", save_event.game_name, save_event.file_path.display());
-                
-                // Record save in database with game_id if available
-                let game_result = if let Some(ref game_id) = save_event.game_id {
-                    database.get_or_create_game_with_id(&save_event.game_name, &save_event.emulator, Some(game_id)).await
-                } else {
-                    database.get_or_create_game(&save_event.game_name, &save_event.emulator).await
-                };
-                
-                match game_result {
-                    Ok(game) => {
-                        // Record the save
-                        match database.record_save(
-                            game.id,
-                            &save_event.file_path.to_string_lossy(),
-                            &save_event.file_hash,
-                            save_event.file_size as i64,
-                            None,
-                        ).await {
-                            Ok(save) => {
-                                info!("Recorded save #{} for {}", save.version, game.name);
-                                
-                                // Backup the save
-                                match backup_manager.backup_save(
-                                    &save_event.file_path,
-                                    &game.name,
-                                    save.version as u32,
-                                ) {
-                                    Ok((_backup_path, stats)) => {
-                                        if let Some(compression_stats) = stats {
-                                            debug!(
-                                                "Compressed backup: {} -> {} ({}% saved)",
-                                                compression_stats.original_size,
-                                                compression_stats.compressed_size,
-                                                compression_stats.space_saved_percent() as u32
-                                            );
-                                        }
-                                    }
-                                    Err(e) => warn!("Failed to backup save: {}", e),
-                                }
-                                
-                                // Clean up old saves (keep last 5)
-                                if let Err(e) = database.cleanup_old_saves(game.id, 5).await {
-                                    warn!("Failed to cleanup old saves: {}", e);
-                                }
-                                
-                                // Clean up old backups
-                                if let Err(e) = backup_manager.cleanup_old_backups(&game.name) {
-                                    warn!("Failed to cleanup old backups: {}", e);
-                                }
-                                
-                                // Send monitor event
-                                let _ = sender.send(MonitorEvent::SaveDetected {
-                                    game_name: game.name.clone(),
-                                    emulator: save_event.emulator.clone(),
-                                    file_path: save_event.file_path.to_string_lossy().to_string(),
-                                }).await;
-                                
-                                // Send sync event if sync is enabled
-                                if let Some(ref sync_tx) = sync_sender {
-                                    let _ = sync_tx.send(SyncEvent::SaveDetected {
-                                        game_name: game.name,
-                                        emulator: save_event.emulator,
-                                        file_path: save_event.file_path.to_string_lossy().to_string(),
-                                        file_hash: save_event.file_hash,
-                                        file_size: save_event.file_size as i64,
-                                    });
-                                }
+                    MonitorCommand::ScanExisting => {
+                        info!("Scanning for existing saves");
+                        let mut imported = 0;
+                        if !save_watchers.is_empty() {
+                            for watcher in &save_watchers {
+                                heartbeat.beat();
+                                imported += import_existing_saves(watcher, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref()).await;
                             }
-                            Err(e) => error!("Failed to record save: {}", e),
-                        }
-                    }
-                    Err(e) => error!("Failed to get/create game: {}", e),
-                }
-            }
-        }
-        
-        // Check for running emulators
-        let emulators = process::detect_running_emulators();
-        if let Some(emulator) = emulators.first() {
-            let emulator_name = match &emulator {
-                process::EmulatorProcess::PCSX2 { .. } => "PCSX2",
-                process::EmulatorProcess::Dolphin { .. } => "Dolphin",
-                process::EmulatorProcess::RPCS3 { .. } => "RPCS3",
-                process::EmulatorProcess::Citra { .. } => "Citra",
-                process::EmulatorProcess::RetroArch { .. } => "RetroArch",
-                process::EmulatorProcess::Yuzu { .. } => "Yuzu",
-                process::EmulatorProcess::Ryujinx { .. } => "Ryujinx",
-                process::EmulatorProcess::PPSSPP { .. } => "PPSSPP",
-            };
-            
-            // Check if this is a newly detected emulator
-            if !tracked_emulators.contains(emulator_name) {
-                tracked_emulators.insert(emulator_name.to_string());
-                info!("{} started", emulator_name);
-                let _ = sender.send(MonitorEvent::EmulatorStarted(emulator_name.to_string())).await;
-                
-                // Start save watching for the emulator
-                match emulator_name {
-                    "PCSX2" => {
-                    if let Some(save_dir) = process::get_pcsx2_save_directory() {
-                        let save_dir = PathBuf::from(save_dir);
-                        match SaveWatcher::new_with_emulator(save_dir.clone(), database.clone(), "PCSX2".to_string()) {
-                            Ok((mut watcher, receiver)) => {
-                                if let Err(e) = watcher.start().await {
-                                    warn!("Failed to start save watcher: {}", e);
-                                } else {
-                                    info!("Started save watcher for PCSX2");
-                                    save_watcher = Some(watcher);
-                                    save_receiver = Some(receiver);
+                        } else {
+                            // Nothing running, check every emulator we can find saves for
+                            let (unused_sender, _receiver) = mpsc::channel(1);
+                            for emulator_name in EMULATORS.iter().filter(|e| !disabled_emulators.iter().any(|d| d == *e)) {
+                                for save_dir in find_save_directories(emulator_name, &database).await {
+                                    heartbeat.beat();
+                                    let mut watcher = create_save_watcher(emulator_name, save_dir, &database, unused_sender.clone());
+                                    watcher.load_ignore_patterns().await;
+                                    watcher.load_exclusions().await;
+                                    imported += import_existing_saves(&watcher, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref()).await;
                                 }
                             }
-                            Err(e) => warn!("Failed to create save watcher: {}", e),
                         }
-                    } else {
-                        warn!("Could not find PCSX2 save directory");
-                    }
+                        let _ = sender.send(MonitorEvent::ExistingSavesImported(imported)).await;
                     }
-                    "Dolphin" => {
-                        if let process::EmulatorProcess::Dolphin { .. } = &emulator {
-                            let dolphin = crate::emulators::dolphin::Dolphin::new();
-                            if let Some(save_dir) = dolphin.get_save_directory() {
-                                let save_dir = PathBuf::from(save_dir);
-                                match SaveWatcher::new_with_emulator(save_dir.clone(), database.clone(), "Dolphin".to_string()) {
-                                    Ok((mut watcher, receiver)) => {
-                                        if let Err(e) = watcher.start().await {
-                                            warn!("Failed to start save watcher: {}", e);
-                                        } else {
-                                            info!("Started save watcher for Dolphin");
-                                            save_watcher = Some(watcher);
-                                            save_receiver = Some(receiver);
-                                        }
+                    MonitorCommand::Pause => {
+                        if !paused {
+                            paused = true;
+                            
+                            // Handle anything already detected before dropping the watcher
+                            if let Some(mut receiver) = save_receiver.take() {
+                                while let Ok(save_event) = receiver.try_recv() {
+                                    if auto_save_enabled {
+                                        record_save_event(save_event, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref(), true).await;
+                                    } else {
+                                        pending_saves.insert(save_event.file_path.clone(), save_event);
                                     }
-                                    Err(e) => warn!("Failed to create save watcher: {}", e),
                                 }
-                            } else {
-                                warn!("Could not find Dolphin save directory");
                             }
+                            for mut watcher in save_watchers.drain(..) {
+                                watcher.stop();
+                            }
+                            
+                            // Emulators are picked up again from scratch on resume
+                            tracked_emulators.clear();
+                            awaiting_save_dir = None;
+                            current_game_name = None;
+                            
+                            info!("Monitoring paused");
+                            let _ = sender.send(MonitorEvent::MonitoringStateChanged { paused: true }).await;
                         }
                     }
-                    "RPCS3" => {
-                        if let process::EmulatorProcess::RPCS3 { .. } = &emulator {
-                            let rpcs3 = crate::emulators::rpcs3::RPCS3::new();
-                            if let Some(save_dir) = rpcs3.get_save_directory() {
-                                let save_dir = PathBuf::from(save_dir);
-                                match SaveWatcher::new(save_dir.clone(), database.clone()) {
-                                    Ok((mut watcher, receiver)) => {
-                                        if let Err(e) = watcher.start().await {
-                                            warn!("Failed to start save watcher: {}", e);
-                                        } else {
-                                            info!("Started save watcher for RPCS3");
-                                            save_watcher = Some(watcher);
-                                            save_receiver = Some(receiver);
-                                        }
-                                    }
-                                    Err(e) => warn!("Failed to create save watcher: {}", e),
-                                }
-                            } else {
-                                warn!("Could not find RPCS3 save directory");
-                            }
+                    MonitorCommand::Resume => {
+                        if paused {
+                            paused = false;
+                            info!("Monitoring resumed");
+                            let _ = sender.send(MonitorEvent::MonitoringStateChanged { paused: false }).await;
                         }
                     }
-                    "Citra" => {
-                        if let process::EmulatorProcess::Citra { .. } = &emulator {
-                            let citra = crate::emulators::citra::Citra::new();
-                            if let Some(save_dir) = citra.get_save_directory() {
-                                let save_dir = PathBuf::from(save_dir);
-                                match SaveWatcher::new(save_dir.clone(), database.clone()) {
-                                    Ok((mut watcher, receiver)) => {
-                                        if let Err(e) = watcher.start().await {
-                                            warn!("Failed to start save watcher: {}", e);
-                                        } else {
-                                            info!("Started save watcher for Citra");
-                                            save_watcher = Some(watcher);
-                                            save_receiver = Some(receiver);
-                                        }
-                                    }
-                                    Err(e) => warn!("Failed to create save watcher: {}", e),
-                                }
-                            } else {
-                                warn!("Could not find Citra save directory");
-                            }
+                    MonitorCommand::SetAutoSave { enabled, interval_minutes } => {
+                        if enabled != auto_save_enabled {
+                            auto_save_enabled = enabled;
+                            info!("Automatic saves {}", if enabled { "enabled" } else { "disabled" });
+                        }
+                        let interval_minutes = interval_minutes.max(1);
+                        if interval_minutes != auto_save_minutes(&auto_save_timer) {
+                            info!("Auto-save interval set to {} minutes", interval_minutes);
+                            auto_save_timer = auto_save_interval(interval_minutes);
                         }
                     }
-                    "RetroArch" => {
-                        if let process::EmulatorProcess::RetroArch { .. } = &emulator {
-                            let retroarch = crate::emulators::retroarch::RetroArch::new();
-                            if let Some(save_dir) = retroarch.get_save_directory() {
-                                let save_dir = PathBuf::from(save_dir);
-                                match SaveWatcher::new(save_dir.clone(), database.clone()) {
-                                    Ok((mut watcher, receiver)) => {
-                                        if let Err(e) = watcher.start().await {
-                                            warn!("Failed to start save watcher: {}", e);
-                                        } else {
-                                            info!("Started save watcher for RetroArch");
-                                            save_watcher = Some(watcher);
-                                            save_receiver = Some(receiver);
-                                        }
-                                    }
-                                    Err(e) => warn!("Failed to create save watcher: {}", e),
-                                }
-                            } else {
-                                warn!("Could not find RetroArch save directory");
-                            }
+                    MonitorCommand::SetRetention { saves, save_states } => {
+                        let retention = (saves as usize, save_states as usize);
+                        if retention != backup_manager.retention() {
+                            info!("Keeping {} saves and {} save-states per game", saves, save_states);
+                            backup_manager.set_retention(retention.0, retention.1);
                         }
                     }
-                    "Yuzu" => {
-                        if let process::EmulatorProcess::Yuzu { .. } = &emulator {
-                            let yuzu = crate::emulators::yuzu_ryujinx::YuzuRyujinx::new_yuzu();
-                            if let Some(save_dir) = yuzu.get_save_directory() {
-                                let save_dir = PathBuf::from(save_dir);
-                                match SaveWatcher::new(save_dir.clone(), database.clone()) {
-                                    Ok((mut watcher, receiver)) => {
-                                        if let Err(e) = watcher.start().await {
-                                            warn!("Failed to start save watcher: {}", e);
-                                        } else {
-                                            info!("Started save watcher for Yuzu");
-                                            save_watcher = Some(watcher);
-                                            save_receiver = Some(receiver);
-                                        }
-                                    }
-                                    Err(e) => warn!("Failed to create save watcher: {}", e),
-                                }
-                            } else {
-                                warn!("Could not find Yuzu save directory");
-                            }
+                    MonitorCommand::SetDisabledEmulators(disabled) => {
+                        // A running emulator that was just disabled is handled like it stopped
+                        if disabled != disabled_emulators {
+                            info!("Disabled emulators: {:?}", disabled);
+                            disabled_emulators = disabled;
                         }
                     }
-                    "Ryujinx" => {
-                        if let process::EmulatorProcess::Ryujinx { .. } = &emulator {
-                            let ryujinx = crate::emulators::yuzu_ryujinx::YuzuRyujinx::new_ryujinx();
-                            if let Some(save_dir) = ryujinx.get_save_directory() {
-                                let save_dir = PathBuf::from(save_dir);
-                                match SaveWatcher::new(save_dir.clone(), database.clone()) {
-                                    Ok((mut watcher, receiver)) => {
-                                        if let Err(e) = watcher.start().await {
-                                            warn!("Failed to start save watcher: {}", e);
-                                        } else {
-                                            info!("Started save watcher for Ryujinx");
-                                            save_watcher = Some(watcher);
-                                            save_receiver = Some(receiver);
-                                        }
-                                    }
-                                    Err(e) => warn!("Failed to create save watcher: {}", e),
-                                }
-                            } else {
-                                warn!("Could not find Ryujinx save directory");
+                    MonitorCommand::SetAllowlist(games) => {
+                        if games != allowlist {
+                            match &games {
+                                Some(games) => info!("Only backing up allowlisted games: {:?}", games),
+                                None => info!("Allowlist mode off, backing up all games"),
                             }
+                            allowlist = games;
                         }
                     }
-                    "PPSSPP" => {
-                        if let process::EmulatorProcess::PPSSPP { .. } = &emulator {
-                            let ppsspp = crate::emulators::ppsspp::PPSSPP::new();
-                            if let Some(save_dir) = ppsspp.get_save_directory() {
-                                info!("Setting up save monitoring for PPSSPP saves at: {}", save_dir);
-                                
-                                let save_dir = PathBuf::from(save_dir);
-                                match SaveWatcher::new(save_dir.clone(), database.clone()) {
-                                    Ok((mut watcher, receiver)) => {
-                                        if let Err(e) = watcher.start().await {
-                                            warn!("Failed to start save watcher: {}", e);
-                                        } else {
-                                            info!("Started save watcher for PPSSPP");
-                                            save_watcher = Some(watcher);
-                                            save_receiver = Some(receiver);
-                                        }
-                                    }
-                                    Err(e) => warn!("Failed to create save watcher: {}", e),
-                                }
-                            } else {
-                                warn!("Could not find PPSSPP save directory");
-                            }
+                    MonitorCommand::SetScanInterval { seconds } => {
+                        let seconds = seconds.max(1);
+                        if u64::from(seconds) != interval.period().as_secs() {
+                            info!("Checking for emulators every {} seconds", seconds);
+                            interval = scan_interval(seconds);
                         }
                     }
-                    _ => {}
                 }
-                
-                // Try to detect the game after a short delay
-                tokio::time::sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        }
+        
+        if paused {
+            continue;
+        }
+        
+        // Check for save events
+        if let Some(receiver) = &mut save_receiver {
+            while let Ok(save_event) = receiver.try_recv() {
+                let Some(save_event) = name_save_event(save_event, current_game_name.as_deref(), &mut unnamed_saves) else {
+                    continue;
+                };
+                if auto_save_enabled {
+                    record_save_event(save_event, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref(), true).await;
+                } else {
+                    debug!("Automatic saves disabled, holding {} for the next manual save", save_event.file_path.display());
+                    pending_saves.insert(save_event.file_path.clone(), save_event);
+                }
             }
+        }
+        
+        // Check for running emulators. Listing processes blocks, keep it off
+        // the async task so the heartbeat and commands aren't held up.
+        let disabled = disabled_emulators.clone();
+        let emulators = match tokio::task::spawn_blocking(move || process::detect_running_emulators(&disabled)).await {
+            Ok(emulators) => emulators,
+            Err(e) => {
+                warn!("Emulator detection failed: {}", e);
+                continue;
+            }
+        };
+        if let Some(emulator) = emulators.first() {
+            let emulator_name = emulator.name();
+            missed_scans = 0;
             
-            match &emulator {
-                process::EmulatorProcess::PCSX2 { pid, exe_path } => {
-                    debug!("PCSX2 running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_pcsx2_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
-                            }
-                        }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
+            // The user may have moved the saves in the emulator's settings,
+            // stop watching the old place and pick up the new one below
+            if !save_watchers.is_empty() && save_dirs_checked.elapsed() >= SAVE_DIR_RECHECK_INTERVAL {
+                save_dirs_checked = time::Instant::now();
+                let save_dirs = find_save_directories(emulator_name, &database).await;
+                if save_dirs_moved(&save_watchers, &save_dirs) {
+                    info!("{} save directories changed to {:?}", emulator_name, save_dirs);
+                    for mut watcher in save_watchers.drain(..) {
+                        watcher.stop();
                     }
+                    save_receiver = None;
+                    awaiting_save_dir = Some(emulator_name.to_string());
                 }
-                process::EmulatorProcess::Dolphin { pid, exe_path } => {
-                    debug!("Dolphin running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_dolphin_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown GameCube/Wii Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown GameCube/Wii Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
+            }
+            
+            // Start watching once a missing or moved save directory shows up
+            if awaiting_save_dir.as_deref() == Some(emulator_name) {
+                let save_dirs = find_save_directories(emulator_name, &database).await;
+                if !save_dirs.is_empty() {
+                    info!("{} save directory found: {}", emulator_name, save_dirs[0].display());
+                    awaiting_save_dir = None;
+                    if let Some((watchers, receiver)) = start_save_watchers(emulator_name, save_dirs, &database).await {
+                        let mut imported = 0;
+                        for watcher in &watchers {
+                            if let Some(game) = current_game_name.clone().filter(|g| !is_unknown_game(g)) {
+                                watcher.set_current_game(Some(game)).await;
                             }
-                        }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
-                    }
-                }
-                process::EmulatorProcess::RPCS3 { pid, exe_path } => {
-                    debug!("RPCS3 running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_rpcs3_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown PS3 Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown PS3 Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
+                            if auto_save_enabled {
+                                imported += import_existing_saves(watcher, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref()).await;
                             }
                         }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
-                    }
-                }
-                process::EmulatorProcess::Citra { pid, exe_path } => {
-                    debug!("Citra running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_citra_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown 3DS Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown 3DS Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
-                            }
+                        if imported > 0 {
+                            let _ = sender.send(MonitorEvent::ExistingSavesImported(imported)).await;
                         }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
+                        save_watchers = watchers;
+                        save_receiver = Some(receiver);
                     }
                 }
-                process::EmulatorProcess::RetroArch { pid, exe_path } => {
-                    debug!("RetroArch running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_retroarch_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown RetroArch Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown RetroArch Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
-                            }
+            }
+            
+            // Check if this is a newly detected emulator
+            if !tracked_emulators.contains(emulator_name) {
+                tracked_emulators.insert(emulator_name.to_string());
+                save_dirs_checked = time::Instant::now();
+                info!("{} started", emulator_name);
+                let _ = sender.send(MonitorEvent::EmulatorStarted(emulator_name.to_string())).await;
+                
+                // Start save watching for the emulator
+                let save_dirs = find_save_directories(emulator_name, &database).await;
+                if save_dirs.is_empty() {
+                    // Fresh installs create the directory on first save, keep checking until it exists
+                    warn!("Could not find {} save directory, will start watching once it is created", emulator_name);
+                    awaiting_save_dir = Some(emulator_name.to_string());
+                } else if let Some((watchers, receiver)) = start_save_watchers(emulator_name, save_dirs, &database).await {
+                    // Give saves that predate Retrosave an initial version right away
+                    if auto_save_enabled {
+                        let mut imported = 0;
+                        for watcher in &watchers {
+                            imported += import_existing_saves(watcher, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref()).await;
                         }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
-                    }
-                }
-                process::EmulatorProcess::Yuzu { pid, exe_path } => {
-                    debug!("Yuzu running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_yuzu_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown Switch Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown Switch Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
-                            }
+                        if imported > 0 {
+                            let _ = sender.send(MonitorEvent::ExistingSavesImported(imported)).await;
                         }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
                     }
+                    save_watchers = watchers;
+                    save_receiver = Some(receiver);
                 }
-                process::EmulatorProcess::Ryujinx { pid, exe_path } => {
-                    debug!("Ryujinx running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_ryujinx_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown Switch Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown Switch Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
-                            }
-                        }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
-                    }
+                
+                // Try to detect the game after a short delay
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            }
+            
+            let detected = match process::detect_game_name(emulator).await {
+                Ok(detected) => detected,
+                Err(e) => {
+                    // Keep the current game until detection answers again
+                    warn!("{}", e);
+                    continue;
                 }
-                process::EmulatorProcess::PPSSPP { pid, exe_path } => {
-                    debug!("PPSSPP running - PID: {}, Path: {}", pid, exe_path);
-                    
-                    // Try to get the actual game name
-                    let detected_game = process::get_ppsspp_game_name(*pid)
-                        .unwrap_or_else(|| "Unknown PSP Game".to_string());
-                    
-                    // Only send event if game changed
-                    if current_game_name.as_ref() != Some(&detected_game) {
-                        current_game_name = Some(detected_game.clone());
-                        
-                        // Update SaveWatcher with the current game name
-                        if let Some(ref watcher) = save_watcher {
-                            if detected_game != "Unknown PSP Game" {
-                                watcher.set_current_game(Some(detected_game.clone())).await;
-                            } else {
-                                watcher.set_current_game(None).await;
-                            }
+            };
+            debug!("{} running - PID: {}, Path: {}", emulator_name, emulator.pid(), emulator.exe_path());
+            let detected_game = detected.unwrap_or_else(|| unknown_game_name(emulator_name));
+            
+            // Only send event if game changed
+            if current_game_name.as_ref() != Some(&detected_game) {
+                current_game_name = Some(detected_game.clone());
+                let known = !is_unknown_game(&detected_game);
+                
+                // Update the save watchers with the current game name
+                for watcher in &save_watchers {
+                    watcher.set_current_game(known.then(|| detected_game.clone())).await;
+                }
+                
+                // Saves made before the game could be identified belong to it
+                if known && !unnamed_saves.is_empty() {
+                    info!("Recording {} saves made before {} was identified", unnamed_saves.len(), detected_game);
+                    for (_, mut save_event) in unnamed_saves.drain() {
+                        save_event.game_name = detected_game.clone();
+                        if auto_save_enabled {
+                            record_save_event(save_event, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref(), true).await;
+                        } else {
+                            pending_saves.insert(save_event.file_path.clone(), save_event);
                         }
-                        
-                        let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
                     }
                 }
+                
+                let _ = sender.send(MonitorEvent::GameDetected(detected_game)).await;
             }
         } else {
-            // Check if any tracked emulator has stopped
+            // Check if any tracked emulator has stopped, a frontend relaunching it
+            // between games shouldn't stop and restart everything
             if !tracked_emulators.is_empty() {
-                // Stop save watcher
-                if let Some(mut watcher) = save_watcher.take() {
+                missed_scans += 1;
+                if missed_scans < STOPPED_AFTER_MISSED_SCANS {
+                    debug!("{:?} not running, checking again before treating it as stopped", tracked_emulators);
+                    continue;
+                }
+                missed_scans = 0;
+                
+                // Stop save watchers
+                for mut watcher in save_watchers.drain(..) {
                     // Clear game name before stopping
                     watcher.set_current_game(None).await;
                     watcher.stop();
                     info!("Stopped save watcher");
                 }
                 save_receiver = None;
+                awaiting_save_dir = None;
+                
+                // Their game was never identified, record them under placeholders
+                for (_, save_event) in unnamed_saves.drain() {
+                    if auto_save_enabled {
+                        record_save_event(save_event, &database, &backup_manager, &sender, &sync_sender, allowlist.as_deref(), true).await;
+                    } else {
+                        pending_saves.insert(save_event.file_path.clone(), save_event);
+                    }
+                }
                 
                 for emulator in tracked_emulators.drain() {
                     info!("{} stopped", emulator);
@@ -612,6 +495,416 @@ pub async fn start_monitoring_with_sync(
     }
 }
 
+/// Matches the `process_scan_interval_secs` default in the settings
+const DEFAULT_SCAN_INTERVAL_SECS: u32 = 5;
+
+/// How often the monitor beats its heartbeat, whatever the scan interval
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// The monitor counts as stalled after three missed heartbeats
+pub const STALL_TIMEOUT: Duration = Duration::from_secs(HEARTBEAT_INTERVAL.as_secs() * 3);
+
+/// Scans in a row an emulator has to be missing from before it counts as stopped
+const STOPPED_AFTER_MISSED_SCANS: u32 = 2;
+
+/// How often the save directories of a running emulator are resolved again
+const SAVE_DIR_RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Matches the `save_interval_minutes` default in the settings
+const DEFAULT_SAVE_INTERVAL_MINUTES: u32 = 5;
+
+/// Timer for periodic auto-save checks, the first tick fires after one full interval
+fn auto_save_interval(minutes: u32) -> time::Interval {
+    let period = Duration::from_secs(u64::from(minutes.max(1)) * 60);
+    let mut timer = time::interval_at(time::Instant::now() + period, period);
+    timer.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+    timer
+}
+
+/// Timer for checking running emulators and recording saves, the first tick fires right away
+fn scan_interval(seconds: u32) -> time::Interval {
+    let mut timer = time::interval(Duration::from_secs(u64::from(seconds.max(1))));
+    timer.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+    timer
+}
+
+fn auto_save_minutes(timer: &time::Interval) -> u32 {
+    (timer.period().as_secs() / 60) as u32
+}
+
+/// Give a save whose game couldn't be identified the running game's name. Without
+/// one it's held in `unnamed` until the game is known or the emulator closes,
+/// and `None` is returned.
+fn name_save_event(
+    mut save_event: SaveEvent,
+    current_game: Option<&str>,
+    unnamed: &mut HashMap<PathBuf, SaveEvent>,
+) -> Option<SaveEvent> {
+    // A deletion supersedes whatever was held for the file
+    if save_event.deleted {
+        unnamed.remove(&save_event.file_path);
+        return Some(save_event);
+    }
+    if !is_unknown_game(&save_event.game_name) {
+        return Some(save_event);
+    }
+    
+    match current_game.filter(|game| !is_unknown_game(game)) {
+        Some(game) => {
+            save_event.game_name = game.to_string();
+            Some(save_event)
+        }
+        None => {
+            info!("Holding {} until the running game is identified", save_event.file_path.display());
+            unnamed.insert(save_event.file_path.clone(), save_event);
+            None
+        }
+    }
+}
+
+/// Identity of an unidentified game, stable across restarts: its save file.
+/// Unidentified games sharing a memory card share it too.
+fn stable_game_id(save_event: &SaveEvent) -> String {
+    let key = format!("{}:{}", save_event.emulator, save_event.file_path.display());
+    crate::storage::hasher::hash_bytes(key.as_bytes())
+}
+
+/// Record existing saves that have no version yet, returns how many were imported
+async fn import_existing_saves(
+    watcher: &SaveWatcher,
+    database: &Arc<Database>,
+    backup_manager: &SaveBackupManager,
+    sender: &mpsc::Sender<MonitorEvent>,
+    sync_sender: &Option<mpsc::UnboundedSender<SyncEvent>>,
+    allowlist: Option<&[String]>,
+) -> usize {
+    let events = match watcher.scan_existing().await {
+        Ok(events) => events,
+        Err(e) => {
+            warn!("Failed to scan existing saves: {}", e);
+            return 0;
+        }
+    };
+    
+    let mut imported = 0;
+    for event in events {
+        // One summary event instead of a notification per save
+        if record_save_event(event, database, backup_manager, sender, sync_sender, allowlist, false).await {
+            imported += 1;
+        }
+    }
+    
+    if imported > 0 {
+        info!("Imported {} existing saves", imported);
+    }
+    imported
+}
+
+/// Note that the user deleted a save file. Its versions and cloud saves are
+/// kept, it's only marked so syncing doesn't bring it straight back.
+async fn record_save_deletion(file_path: &Path, database: &Arc<Database>) {
+    // Emulators sometimes delete and rewrite a file when saving
+    if file_path.exists() {
+        debug!("{} was recreated after being removed", file_path.display());
+        return;
+    }
+    
+    warn!("Save file deleted locally: {}, keeping its backups and cloud saves", file_path.display());
+    if let Err(e) = database.mark_save_file_deleted(&file_path.to_string_lossy()).await {
+        warn!("Failed to record deletion of {}: {}", file_path.display(), e);
+    }
+}
+
+/// Record a detected save as a new version, back it up and queue it for sync.
+/// Returns whether a new version was recorded.
+async fn record_save_event(
+    save_event: SaveEvent,
+    database: &Arc<Database>,
+    backup_manager: &SaveBackupManager,
+    sender: &mpsc::Sender<MonitorEvent>,
+    sync_sender: &Option<mpsc::UnboundedSender<SyncEvent>>,
+    allowlist: Option<&[String]>,
+    announce: bool,
+) -> bool {
+    if save_event.deleted {
+        record_save_deletion(&save_event.file_path, database).await;
+        return false;
+    }
+    if !is_allowlisted(&save_event, allowlist) {
+        debug!("{} isn't allowlisted, not backing up {}", save_event.game_name, save_event.file_path.display());
+        return false;
+    }
+    
+    info!("Save detected: {} - {}", save_event.game_name, save_event.file_path.display());
+    if let Err(e) = database.clear_save_file_deleted(&save_event.file_path.to_string_lossy()).await {
+        warn!("Failed to clear deletion of {}: {}", save_event.file_path.display(), e);
+    }
+    
+    // Unidentified games are told apart by their save file instead of all
+    // becoming versions of one "Unknown" game, and named once detected. A
+    // memory card holds saves of several games, so the game it's written by
+    // later isn't necessarily the one that was unknown; those keep their name.
+    let stable_id = stable_game_id(&save_event);
+    let identified = !is_unknown_game(&save_event.game_name);
+    if identified && !save_event.save_type.is_memory_card() {
+        if let Err(e) = database.name_unknown_game(&save_event.emulator, &stable_id, &save_event.game_name).await {
+            warn!("Failed to name unidentified game: {}", e);
+        }
+    }
+    
+    // Record save in database with game_id if available
+    let game_result = if !identified {
+        let name = unknown_game_name_with_id(&save_event.emulator, &stable_id);
+        database.get_or_create_game_with_id(&name, &save_event.emulator, save_event.game_id.as_deref(), Some(&stable_id)).await
+    } else if let Some(ref game_id) = save_event.game_id {
+        database.get_or_create_game_with_id(&save_event.game_name, &save_event.emulator, Some(game_id), None).await
+    } else {
+        database.get_or_create_game(&save_event.game_name, &save_event.emulator).await
+    };
+    
+    match game_result {
+        Ok(game) => {
+            // Emulators sometimes rewrite a save without changing it; don't
+            // create a redundant version, backup, or upload for that
+            match database.is_duplicate_save(game.id, &save_event.file_hash).await {
+                Ok(true) => {
+                    debug!("Save for {} unchanged since last version, skipping", game.name);
+                    return false;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Failed to check for duplicate save: {}", e),
+            }
+            
+            // Record the save
+            match database.record_save(
+                game.id,
+                &save_event.file_path.to_string_lossy(),
+                &save_event.file_hash,
+                save_event.file_size as i64,
+                None,
+            ).await {
+                Ok(save) => {
+                    info!("Recorded save #{} for {}", save.version, game.name);
+                    
+                    // Backup the save
+                    match backup_manager.backup_save(
+                        &save_event.file_path,
+                        &game.name,
+                        save.version as u32,
+                    ) {
+                        Ok((backup_path, stats)) => {
+                            if let Err(e) = database.set_save_backup_path(save.id, &backup_path.to_string_lossy()).await {
+                                warn!("Failed to record backup path: {}", e);
+                            }
+                            if let Some(compression_stats) = stats {
+                                debug!(
+                                    "Compressed backup: {} -> {} ({}% saved)",
+                                    compression_stats.original_size,
+                                    compression_stats.compressed_size,
+                                    compression_stats.space_saved_percent() as u32
+                                );
+                            }
+                        }
+                        Err(e) => warn!("Failed to backup save: {}", e),
+                    }
+                    
+                    // Clean up old versions, save-states and persistent saves separately
+                    let (keep_saves, keep_save_states) = backup_manager.retention();
+                    match database.cleanup_old_saves_by_class(game.id, &game.emulator, keep_saves, keep_save_states).await {
+                        Ok(deleted) => {
+                            for backup_path in deleted.iter().filter_map(|s| s.backup_path.as_ref()) {
+                                if let Err(e) = std::fs::remove_file(backup_path) {
+                                    debug!("Failed to delete backup {}: {}", backup_path, e);
+                                }
+                            }
+                        }
+                        Err(e) => warn!("Failed to cleanup old saves: {}", e),
+                    }
+                    
+                    // Clean up old backups, keeping the ones of pinned versions
+                    match database.get_pinned_backup_paths(game.id).await {
+                        Ok(paths) => {
+                            let pinned: Vec<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+                            if let Err(e) = backup_manager.cleanup_old_backups(&game.name, &pinned) {
+                                warn!("Failed to cleanup old backups: {}", e);
+                            }
+                        }
+                        Err(e) => warn!("Skipping backup cleanup, failed to load pinned saves: {}", e),
+                    }
+                    
+                    // Send monitor event
+                    if announce {
+                        let _ = sender.send(MonitorEvent::SaveDetected {
+                            game_name: game.name.clone(),
+                            emulator: save_event.emulator.clone(),
+                            file_path: save_event.file_path.to_string_lossy().to_string(),
+                        }).await;
+                    }
+                    
+                    // Send sync event if sync is enabled globally and for this game
+                    if !game.sync_enabled {
+                        debug!("Cloud sync disabled for {}, keeping save local only", game.name);
+                    } else if is_unknown_game(&game.name) {
+                        // Unless the user named it in the save browser
+                        debug!("{} isn't identified yet, keeping its saves local until it is", game.name);
+                    } else if let Some(ref sync_tx) = sync_sender {
+                        let _ = sync_tx.send(SyncEvent::SaveDetected {
+                            game_name: game.name,
+                            emulator: save_event.emulator,
+                            file_path: save_event.file_path.to_string_lossy().to_string(),
+                            file_hash: save_event.file_hash,
+                            file_size: save_event.file_size as i64,
+                        });
+                    }
+                    
+                    crate::metrics::METRICS.save_detected();
+                    return true;
+                }
+                Err(e) => error!("Failed to record save: {}", e),
+            }
+        }
+        Err(e) => error!("Failed to get/create game: {}", e),
+    }
+    
+    false
+}
+
+/// Allowlisted games from the settings table, `None` unless allowlist mode is on
+async fn load_allowlist(database: &Database) -> Option<Vec<String>> {
+    let enabled = match database.get_setting(ALLOWLIST_ENABLED_SETTING).await {
+        Ok(value) => value.as_deref() == Some("true"),
+        Err(e) => {
+            warn!("Failed to load allowlist mode: {}", e);
+            false
+        }
+    };
+    if !enabled {
+        return None;
+    }
+    
+    match database.get_setting(ALLOWLISTED_GAMES_SETTING).await {
+        Ok(value) => Some(value.map(|v| v.lines().map(|l| l.to_string()).collect()).unwrap_or_default()),
+        Err(e) => {
+            // Recording nothing beats recording games the user wanted left alone
+            warn!("Failed to load allowlisted games: {}", e);
+            Some(Vec::new())
+        }
+    }
+}
+
+/// Whether the save's game name or serial matches an allowlist entry. Entries
+/// are case-insensitive and may use `*` and `?`. Everything is allowed
+/// without an allowlist, unidentified games never are with one.
+fn is_allowlisted(save_event: &SaveEvent, allowlist: Option<&[String]>) -> bool {
+    let Some(allowlist) = allowlist else {
+        return true;
+    };
+    if is_unknown_game(&save_event.game_name) {
+        return false;
+    }
+    
+    let names = [Some(&save_event.game_name), save_event.game_id.as_ref()];
+    allowlist.iter()
+        .map(|entry| entry.trim().to_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| names.iter().flatten().any(|name| glob_match(&entry, &name.to_lowercase())))
+}
+
+/// Save directory for an emulator, `None` if it doesn't exist yet
+pub(crate) fn find_save_directory(emulator_name: &str) -> Option<PathBuf> {
+    use crate::emulators::{dolphin::Dolphin, rpcs3::RPCS3, citra::Citra, retroarch::RetroArch, yuzu_ryujinx::YuzuRyujinx, ppsspp::PPSSPP};
+    
+    let save_dir = match emulator_name {
+        "PCSX2" => process::get_pcsx2_save_directory(),
+        "Dolphin" => Dolphin::new().get_save_directory(),
+        "RPCS3" => RPCS3::new().get_save_directory(),
+        "Citra" => Citra::new().get_save_directory(),
+        "RetroArch" => RetroArch::new().get_save_directory(),
+        "Yuzu" => YuzuRyujinx::new_yuzu().get_save_directory(),
+        "Ryujinx" => YuzuRyujinx::new_ryujinx().get_save_directory(),
+        "PPSSPP" => PPSSPP::new().get_save_directory(),
+        _ => None,
+    };
+    
+    save_dir.map(PathBuf::from).filter(|dir| dir.exists())
+}
+
+/// Every save directory of an emulator: for PCSX2 the memory card folders
+/// from its configuration plus the ones the user added, otherwise the one
+/// from `find_save_directory`
+async fn find_save_directories(emulator_name: &str, database: &Database) -> Vec<PathBuf> {
+    if emulator_name != "PCSX2" {
+        return find_save_directory(emulator_name).into_iter().collect();
+    }
+    
+    let mut dirs = process::get_pcsx2_memcard_directories();
+    match database.get_setting(EXTRA_MEMCARD_DIRS_SETTING).await {
+        Ok(Some(value)) => {
+            for dir in value.lines().map(str::trim).filter(|dir| !dir.is_empty()).map(PathBuf::from) {
+                if !dir.is_dir() {
+                    warn!("Extra memory card folder {} does not exist", dir.display());
+                } else if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to load extra memory card folders: {}", e),
+    }
+    dirs
+}
+
+fn create_save_watcher(
+    emulator_name: &str,
+    save_dir: PathBuf,
+    database: &Arc<Database>,
+    sender: mpsc::Sender<SaveEvent>,
+) -> SaveWatcher {
+    let emulator_name = match emulator_name {
+        "PCSX2" | "Dolphin" => emulator_name,
+        _ => "Unknown",
+    };
+    SaveWatcher::with_sender(save_dir, database.clone(), emulator_name.to_string(), sender)
+}
+
+/// Create and start a save watcher for each of an emulator's save directories.
+/// `None` if none of them could be watched.
+async fn start_save_watchers(
+    emulator_name: &str,
+    save_dirs: Vec<PathBuf>,
+    database: &Arc<Database>,
+) -> Option<(Vec<SaveWatcher>, mpsc::Receiver<SaveEvent>)> {
+    let (sender, receiver) = mpsc::channel(100);
+    let mut watchers = Vec::new();
+    
+    for save_dir in save_dirs {
+        info!("Setting up save monitoring for {} saves at: {}", emulator_name, save_dir.display());
+        let mut watcher = create_save_watcher(emulator_name, save_dir, database, sender.clone());
+        if let Err(e) = watcher.start().await {
+            warn!("Failed to start save watcher: {}", e);
+        } else {
+            info!("Started save watcher for {}", emulator_name);
+            watchers.push(watcher);
+        }
+    }
+    
+    (!watchers.is_empty()).then_some((watchers, receiver))
+}
+
+/// Whether `save_dirs` differ from the directories `watchers` watch
+fn save_dirs_moved(watchers: &[SaveWatcher], save_dirs: &[PathBuf]) -> bool {
+    watchers.len() != save_dirs.len()
+        || watchers.iter().any(|watcher| !save_dirs.iter().any(|dir| dir == watcher.save_dir()))
+}
+
+/// Look for changes every watcher missed, returns how many saves changed
+async fn check_for_changes(watchers: &[SaveWatcher]) -> Result<usize> {
+    let mut changes = 0;
+    for watcher in watchers {
+        changes += watcher.check_for_changes().await?;
+    }
+    Ok(changes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -664,6 +957,86 @@ mod tests {
         }
     }
 
+    fn save_event(game_name: &str, path: &str) -> SaveEvent {
+        SaveEvent {
+            game_name: game_name.to_string(),
+            game_id: None,
+            emulator: "PCSX2".to_string(),
+            file_path: PathBuf::from(path),
+            file_hash: "hash".to_string(),
+            file_size: 0,
+            save_type: crate::storage::SaveType::detect(std::path::Path::new(path), "PCSX2"),
+            is_empty: false,
+            deleted: false,
+        }
+    }
+
+    #[test]
+    fn test_unnamed_saves_wait_for_a_game() {
+        let mut unnamed = HashMap::new();
+        
+        let event = name_save_event(save_event("Final Fantasy X", "/saves/a"), None, &mut unnamed);
+        assert_eq!(event.unwrap().game_name, "Final Fantasy X");
+        
+        assert!(name_save_event(save_event(UNKNOWN_GAME, "/saves/Mcd001.ps2"), None, &mut unnamed).is_none());
+        assert!(name_save_event(save_event(UNKNOWN_GAME, "/saves/Mcd002.ps2"), Some("Unknown PS2 Game"), &mut unnamed).is_none());
+        assert_eq!(unnamed.len(), 2);
+        
+        let event = name_save_event(save_event(UNKNOWN_GAME, "/saves/Mcd001.ps2"), Some("Kingdom Hearts"), &mut unnamed);
+        assert_eq!(event.unwrap().game_name, "Kingdom Hearts");
+        
+        // Deleting a held save drops it instead of waiting for the game
+        let deleted = SaveEvent { deleted: true, ..save_event(UNKNOWN_GAME, "/saves/Mcd002.ps2") };
+        assert!(name_save_event(deleted, None, &mut unnamed).unwrap().deleted);
+        assert!(!unnamed.contains_key(Path::new("/saves/Mcd002.ps2")));
+    }
+    
+    #[test]
+    fn test_allowlist() {
+        let allowlist = vec!["kingdom hearts*".to_string(), "SLUS-20312".to_string(), " ".to_string()];
+        let allowed = |event: &SaveEvent| is_allowlisted(event, Some(&allowlist));
+        
+        assert!(allowed(&save_event("Kingdom Hearts II", "/saves/a")));
+        assert!(!allowed(&save_event("Final Fantasy X", "/saves/b")));
+        assert!(allowed(&SaveEvent { game_id: Some("SLUS-20312".to_string()), ..save_event("Final Fantasy X", "/saves/b") }));
+        assert!(!allowed(&save_event(UNKNOWN_GAME, "/saves/c")));
+        
+        // Everything is backed up when allowlist mode is off, nothing with an empty list
+        assert!(is_allowlisted(&save_event(UNKNOWN_GAME, "/saves/c"), None));
+        assert!(!is_allowlisted(&save_event("Kingdom Hearts", "/saves/a"), Some(&[])));
+    }
+    
+    #[tokio::test]
+    async fn test_deleted_save_is_marked_until_recreated() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let database = Arc::new(Database::new(Some(temp_dir.path().join("test.db"))).await.unwrap());
+        let save_path = temp_dir.path().join("Mcd001.ps2");
+        
+        record_save_deletion(&save_path, &database).await;
+        assert!(database.save_file_deleted_at(&save_path.to_string_lossy()).await.unwrap().is_some());
+        
+        // Removed and written again before the event was handled
+        let rewritten = temp_dir.path().join("Mcd002.ps2");
+        std::fs::write(&rewritten, b"card").unwrap();
+        record_save_deletion(&rewritten, &database).await;
+        assert!(database.save_file_deleted_at(&rewritten.to_string_lossy()).await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_save_dirs_moved() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let database = Arc::new(Database::new(Some(temp_dir.path().join("test.db"))).await.unwrap());
+        let (sender, _receiver) = mpsc::channel(1);
+        let old_dir = temp_dir.path().join("memcards");
+        let new_dir = temp_dir.path().join("moved");
+        let watchers = vec![create_save_watcher("PCSX2", old_dir.clone(), &database, sender)];
+        
+        assert!(!save_dirs_moved(&watchers, &[old_dir.clone()]));
+        assert!(save_dirs_moved(&watchers, &[new_dir.clone()]));
+        assert!(save_dirs_moved(&watchers, &[old_dir, new_dir]));
+        assert!(save_dirs_moved(&watchers, &[]));
+    }
+    
     #[tokio::test]
     async fn test_monitor_command() {
         let cmd = MonitorCommand::TriggerManualSave;
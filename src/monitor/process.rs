@@ -1,8 +1,12 @@
+use anyhow::Result;
 use sysinfo::{System, ProcessesToUpdate};
 use tracing::{debug, info};
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+#[cfg(target_os = "linux")]
+use crate::emulators::paths;
 
 #[derive(Debug, Clone)]
 pub enum EmulatorProcess {
@@ -41,181 +45,306 @@ pub enum EmulatorProcess {
     // Future emulators
 }
 
-pub fn detect_running_emulators() -> Vec<EmulatorProcess> {
-    let mut system = System::new_all();
-    system.refresh_processes(ProcessesToUpdate::All, true);
-    let mut emulators = Vec::new();
+/// Executable names of each emulator across platforms and builds, lowercase
+/// and without `.exe`
+const EMULATOR_EXECUTABLES: &[(&str, &[&str])] = &[
+    ("PCSX2", &["pcsx2", "pcsx2-qt", "pcsx2-qtx64", "pcsx2-qtx64-avx2", "pcsx2x64", "pcsx2x64-avx2", "pcsx2-avx2"]),
+    ("Dolphin", &["dolphin-emu", "dolphin-emu-qt2", "dolphin-emu-nogui", "dolphin"]),
+    ("RPCS3", &["rpcs3"]),
+    ("Citra", &["citra", "citra-qt"]),
+    ("RetroArch", &["retroarch"]),
+    ("Yuzu", &["yuzu", "yuzu-cmd"]),
+    ("Ryujinx", &["ryujinx", "ryujinx.ava"]),
+    ("PPSSPP", &["ppsspp", "ppssppqt", "ppssppsdl", "ppssppwindows", "ppssppwindows64"]),
+];
+
+/// Linux truncates process names to this many characters
+const MAX_PROCESS_NAME_LEN: usize = 15;
+
+/// Which emulator a process is, from its name and, where the name alone is
+/// ambiguous, its executable path. Names have to be one of the known
+/// executables, optionally followed by a version (`pcsx2-v2.0.2`), so helpers
+/// like `yuzu-helper` or KDE's `dolphin` file manager aren't mistaken for one.
+pub fn match_emulator(process_name: &str, exe_path: Option<&str>) -> Option<&'static str> {
+    let name = process_name.to_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+    let name = name.strip_suffix(".appimage").unwrap_or(name);
     
-    for (pid, process) in system.processes() {
-        let process_name = process.name().to_string_lossy().to_lowercase();
-        
-        // Check for PCSX2
-        if process_name.contains("pcsx2") {
-            debug!("Found PCSX2 process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::PCSX2 {
-                pid: pid.as_u32(),
-                exe_path,
-            });
-        }
-        
-        // Check for Dolphin
-        if process_name.contains("dolphin") {
-            debug!("Found Dolphin process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::Dolphin {
-                pid: pid.as_u32(),
-                exe_path,
-            });
+    let (emulator, executable) = EMULATOR_EXECUTABLES.iter().find_map(|(emulator, executables)| {
+        executables
+            .iter()
+            .find(|executable| is_executable_name(name, executable))
+            .map(|executable| (*emulator, *executable))
+    })?;
+    
+    // KDE's file manager is a `dolphin` too, only trust the exe path
+    if executable == "dolphin" {
+        let exe_path = exe_path?.to_lowercase().replace('\\', "/");
+        let is_emulator = exe_path.ends_with(".exe") || exe_path.contains("dolphin.app/") || exe_path.contains("dolphin-emu");
+        if !is_emulator {
+            debug!("Ignoring {} at {}, not Dolphin Emulator", process_name, exe_path);
+            return None;
         }
-        
-        // Check for RPCS3
-        if process_name.contains("rpcs3") {
-            debug!("Found RPCS3 process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::RPCS3 {
-                pid: pid.as_u32(),
-                exe_path,
-            });
+    }
+    
+    Some(emulator)
+}
+
+/// `name` is `executable`, cut short by the kernel, or followed by a version
+fn is_executable_name(name: &str, executable: &str) -> bool {
+    if name == executable {
+        return true;
+    }
+    if name.len() == MAX_PROCESS_NAME_LEN && executable.starts_with(name) {
+        return true;
+    }
+    
+    let Some(suffix) = name.strip_prefix(executable).and_then(|s| s.strip_prefix(['-', '_'])) else {
+        return false;
+    };
+    let version = suffix.strip_prefix('v').unwrap_or(suffix);
+    version.starts_with(|c: char| c.is_ascii_digit())
+}
+
+impl EmulatorProcess {
+    fn new(name: &str, pid: u32, exe_path: String) -> Option<Self> {
+        Some(match name {
+            "PCSX2" => EmulatorProcess::PCSX2 { pid, exe_path },
+            "Dolphin" => EmulatorProcess::Dolphin { pid, exe_path },
+            "RPCS3" => EmulatorProcess::RPCS3 { pid, exe_path },
+            "Citra" => EmulatorProcess::Citra { pid, exe_path },
+            "RetroArch" => EmulatorProcess::RetroArch { pid, exe_path },
+            "Yuzu" => EmulatorProcess::Yuzu { pid, exe_path },
+            "Ryujinx" => EmulatorProcess::Ryujinx { pid, exe_path },
+            "PPSSPP" => EmulatorProcess::PPSSPP { pid, exe_path },
+            _ => return None,
+        })
+    }
+    
+    /// Emulator name as used throughout the monitor and the settings
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmulatorProcess::PCSX2 { .. } => "PCSX2",
+            EmulatorProcess::Dolphin { .. } => "Dolphin",
+            EmulatorProcess::RPCS3 { .. } => "RPCS3",
+            EmulatorProcess::Citra { .. } => "Citra",
+            EmulatorProcess::RetroArch { .. } => "RetroArch",
+            EmulatorProcess::Yuzu { .. } => "Yuzu",
+            EmulatorProcess::Ryujinx { .. } => "Ryujinx",
+            EmulatorProcess::PPSSPP { .. } => "PPSSPP",
         }
-        
-        // Check for Citra
-        if process_name.contains("citra") {
-            debug!("Found Citra process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::Citra {
-                pid: pid.as_u32(),
-                exe_path,
-            });
+    }
+    
+    /// Path of the running executable, `unknown` when the OS didn't say
+    pub fn exe_path(&self) -> &str {
+        match self {
+            EmulatorProcess::PCSX2 { exe_path, .. }
+            | EmulatorProcess::Dolphin { exe_path, .. }
+            | EmulatorProcess::RPCS3 { exe_path, .. }
+            | EmulatorProcess::Citra { exe_path, .. }
+            | EmulatorProcess::RetroArch { exe_path, .. }
+            | EmulatorProcess::Yuzu { exe_path, .. }
+            | EmulatorProcess::Ryujinx { exe_path, .. }
+            | EmulatorProcess::PPSSPP { exe_path, .. } => exe_path,
         }
-        
-        // Check for RetroArch
-        if process_name.contains("retroarch") {
-            debug!("Found RetroArch process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::RetroArch {
-                pid: pid.as_u32(),
-                exe_path,
-            });
+    }
+    
+    pub fn pid(&self) -> u32 {
+        match self {
+            EmulatorProcess::PCSX2 { pid, .. }
+            | EmulatorProcess::Dolphin { pid, .. }
+            | EmulatorProcess::RPCS3 { pid, .. }
+            | EmulatorProcess::Citra { pid, .. }
+            | EmulatorProcess::RetroArch { pid, .. }
+            | EmulatorProcess::Yuzu { pid, .. }
+            | EmulatorProcess::Ryujinx { pid, .. }
+            | EmulatorProcess::PPSSPP { pid, .. } => *pid,
         }
-        
-        // Check for Yuzu
-        if process_name.contains("yuzu") {
-            debug!("Found Yuzu process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::Yuzu {
-                pid: pid.as_u32(),
-                exe_path,
-            });
+    }
+    
+    /// Game running in the emulator, from its window title, command line, logs or config.
+    /// Blocks, and can hang while the X server is unresponsive, see [`detect_game_name`].
+    pub fn game_name(&self) -> Option<String> {
+        match self {
+            EmulatorProcess::PCSX2 { pid, .. } => get_pcsx2_game_name(*pid),
+            EmulatorProcess::Dolphin { pid, .. } => get_dolphin_game_name(*pid),
+            EmulatorProcess::RPCS3 { pid, .. } => get_rpcs3_game_name(*pid),
+            EmulatorProcess::Citra { pid, .. } => get_citra_game_name(*pid),
+            EmulatorProcess::RetroArch { pid, .. } => get_retroarch_game_name(*pid),
+            EmulatorProcess::Yuzu { pid, .. } => get_yuzu_game_name(*pid),
+            EmulatorProcess::Ryujinx { pid, .. } => get_ryujinx_game_name(*pid),
+            EmulatorProcess::PPSSPP { pid, .. } => get_ppsspp_game_name(*pid),
         }
-        
-        // Check for Ryujinx
-        if process_name.contains("ryujinx") {
-            debug!("Found Ryujinx process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::Ryujinx {
-                pid: pid.as_u32(),
-                exe_path,
-            });
+    }
+}
+
+/// How long game detection may take before the monitor carries on without it
+const GAME_DETECTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Set while a detection runs, so a hung X server doesn't pile up blocked threads
+static GAME_DETECTION_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// [`EmulatorProcess::game_name`] on a blocking thread, so the X11 and Win32 calls
+/// can't stall the async runtime. Fails when detection takes longer than
+/// [`GAME_DETECTION_TIMEOUT`] or an earlier one still hasn't returned.
+pub async fn detect_game_name(emulator: &EmulatorProcess) -> Result<Option<String>> {
+    if GAME_DETECTION_RUNNING.swap(true, Ordering::SeqCst) {
+        anyhow::bail!("Previous game detection for {} hasn't finished yet", emulator.name());
+    }
+    
+    let process = emulator.clone();
+    let detection = tokio::task::spawn_blocking(move || {
+        let name = process.game_name();
+        GAME_DETECTION_RUNNING.store(false, Ordering::SeqCst);
+        name
+    });
+    
+    match tokio::time::timeout(GAME_DETECTION_TIMEOUT, detection).await {
+        Ok(Ok(name)) => Ok(name),
+        Ok(Err(e)) => {
+            // Panicked before clearing the flag
+            GAME_DETECTION_RUNNING.store(false, Ordering::SeqCst);
+            Err(anyhow::anyhow!("Game detection for {} failed: {}", emulator.name(), e))
         }
-        
-        // Check for PPSSPP
-        if process_name.contains("ppsspp") {
-            debug!("Found PPSSPP process: {:?} (PID: {})", process.name(), pid);
-            
-            let exe_path = process
-                .exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            emulators.push(EmulatorProcess::PPSSPP {
-                pid: pid.as_u32(),
-                exe_path,
-            });
+        Err(_) => anyhow::bail!(
+            "Game detection for {} timed out after {}s, is the display server responding?",
+            emulator.name(),
+            GAME_DETECTION_TIMEOUT.as_secs()
+        ),
+    }
+}
+
+/// Running emulators, leaving out the `disabled` ones. Processes aren't
+/// scanned at all when every emulator is disabled.
+pub fn detect_running_emulators(disabled: &[String]) -> Vec<EmulatorProcess> {
+    let mut emulators = Vec::new();
+    if EMULATOR_EXECUTABLES.iter().all(|(name, _)| disabled.iter().any(|d| d == name)) {
+        return emulators;
+    }
+    
+    // Only the process list is needed, not CPU, memory or disk stats
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    
+    for (pid, process) in system.processes() {
+        let exe = process.exe().map(|p| p.to_string_lossy().to_string());
+        let Some(name) = match_emulator(&process.name().to_string_lossy(), exe.as_deref()) else {
+            continue;
+        };
+        if disabled.iter().any(|d| d == name) {
+            continue;
         }
+        debug!("Found {} process: {:?} (PID: {})", name, process.name(), pid);
         
-        // Future: Add more emulator checks here
+        let exe_path = exe.unwrap_or_else(|| "unknown".to_string());
+        emulators.extend(EmulatorProcess::new(name, pid.as_u32(), exe_path));
     }
     
     emulators
 }
 
-pub fn get_pcsx2_save_directory() -> Option<String> {
+/// PCSX2 configuration folders, each with its `inis` and default `memcards`
+fn pcsx2_config_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    
     #[cfg(target_os = "windows")]
     {
         if let Ok(home) = std::env::var("USERPROFILE") {
-            let save_path = format!("{}\\Documents\\PCSX2\\memcards", home);
-            if std::path::Path::new(&save_path).exists() {
-                return Some(save_path);
-            }
+            roots.push(PathBuf::from(format!("{}\\Documents\\PCSX2", home)));
         }
     }
     
     #[cfg(target_os = "linux")]
     {
-        if let Ok(home) = std::env::var("HOME") {
-            // Check Flatpak location first (most common nowadays)
-            let flatpak_path = format!("{}/.var/app/net.pcsx2.PCSX2/config/PCSX2/memcards", home);
-            if std::path::Path::new(&flatpak_path).exists() {
-                return Some(flatpak_path);
-            }
-            
-            // Check new location
-            let save_path = format!("{}/.config/PCSX2/memcards", home);
-            if std::path::Path::new(&save_path).exists() {
-                return Some(save_path);
-            }
-            
-            // Check old location
-            let old_save_path = format!("{}/.pcsx2/memcards", home);
-            if std::path::Path::new(&old_save_path).exists() {
-                return Some(old_save_path);
+        // Flatpak first (most common nowadays), then the new and old locations
+        roots.extend([
+            paths::flatpak_path("net.pcsx2.PCSX2", "config/PCSX2"),
+            paths::config_path("PCSX2"),
+            paths::home_path(".pcsx2"),
+        ].into_iter().flatten());
+    }
+    
+    roots
+}
+
+/// Memory card folder set in a PCSX2 ini, `MemoryCards` under `[Folders]`.
+/// PCSX2 1.6 ignores it while `UseDefaultMemoryCards` is enabled.
+fn parse_memory_cards_folder(ini: &str) -> Option<String> {
+    let mut in_folders = false;
+    let mut folder = None;
+    let mut use_default = false;
+    
+    for line in ini.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_folders = line.eq_ignore_ascii_case("[Folders]");
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"');
+        match key.trim() {
+            "MemoryCards" if in_folders && !value.is_empty() => folder = Some(value.to_string()),
+            "UseDefaultMemoryCards" => use_default = value.eq_ignore_ascii_case("enabled") || value == "true",
+            _ => {}
+        }
+    }
+    
+    folder.filter(|_| !use_default)
+}
+
+/// Every existing PCSX2 memory card folder configured in `PCSX2.ini` (or
+/// `PCSX2_ui.ini`/`PCSX2_vm.ini` of older versions). Only when none is, the
+/// first default folder that exists. Relative folders are resolved against the
+/// configuration folder like PCSX2 does.
+pub fn get_pcsx2_memcard_directories() -> Vec<PathBuf> {
+    memcard_directories_in(&pcsx2_config_roots())
+}
+
+/// `get_pcsx2_memcard_directories` for the configuration folders `roots`
+fn memcard_directories_in(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        for ini in ["PCSX2.ini", "PCSX2_ui.ini", "PCSX2_vm.ini"] {
+            let Ok(contents) = fs::read_to_string(root.join("inis").join(ini)) else {
+                continue;
+            };
+            if let Some(folder) = parse_memory_cards_folder(&contents) {
+                debug!("{} sets the memory card folder to {}", ini, folder);
+                let dir = root.join(folder);
+                if dir.is_dir() && !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
             }
         }
     }
     
-    None
+    // PCSX2 ignores its default folder once another one is set
+    if dirs.is_empty() {
+        dirs.extend(roots.iter().map(|root| root.join("memcards")).find(|dir| dir.is_dir()));
+    }
+    dirs
+}
+
+/// Main PCSX2 memory card folder, see `get_pcsx2_memcard_directories`
+pub fn get_pcsx2_save_directory() -> Option<String> {
+    get_pcsx2_memcard_directories()
+        .into_iter()
+        .next()
+        .map(|dir| dir.to_string_lossy().to_string())
 }
 
 /// Try to get the current game name from PCSX2 using multiple methods
 pub fn get_pcsx2_game_name(pid: u32) -> Option<String> {
     info!("Attempting to detect PCSX2 game for PID {}", pid);
     
+    // Method 0: Ask PCSX2 itself over PINE, only answers when IPC is enabled
+    if let Some(game_name) = get_game_from_pine() {
+        info!("Got game from PINE: {}", game_name);
+        return Some(game_name);
+    }
+    
     // Method 1: Try to get from window title (most accurate for running game)
     if let Some(game_name) = get_game_from_window_title(pid) {
         info!("Got game from window title: {}", game_name);
@@ -238,176 +367,318 @@ pub fn get_pcsx2_game_name(pid: u32) -> Option<String> {
     None
 }
 
-/// Get game name from PCSX2 window title using native platform APIs
-fn get_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        get_game_from_window_title_linux()
+/// PCSX2's default PINE slot, used unless the user picked another one
+const PINE_DEFAULT_SLOT: u16 = 28011;
+const PINE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+/// PINE opcodes, see https://projects.govanify.com/govanify/pine
+const PINE_MSG_TITLE: u8 = 0x0B;
+const PINE_MSG_ID: u8 = 0x0C;
+const PINE_RESULT_OK: u8 = 0x00;
+
+/// Get the running game from PCSX2's PINE IPC server: the serial resolved
+/// through the game database, or the title PCSX2 reports for unknown serials
+fn get_game_from_pine() -> Option<String> {
+    let serial = pine_query_string(PINE_MSG_ID)?;
+    if serial.is_empty() {
+        // Socket is up but nothing is booted
+        return None;
+    }
+    debug!("PINE reports serial {}", serial);
+    
+    if let Some(name) = crate::storage::game_database::lookup_game_name(&serial) {
+        return Some(name);
+    }
+    match pine_query_string(PINE_MSG_TITLE).filter(|title| !title.is_empty()) {
+        Some(title) => Some(title),
+        None => Some(format!("PS2 Game [{}]", serial)),
+    }
+}
+
+/// Send a string query to PCSX2's PINE server, `None` if it isn't reachable
+fn pine_query_string(opcode: u8) -> Option<String> {
+    use std::io::{Read, Write};
+    
+    let mut stream = pine_connect()?;
+    
+    // Requests are the total length (u32 LE, header included) followed by the opcode
+    let mut request = 5u32.to_le_bytes().to_vec();
+    request.push(opcode);
+    stream.write_all(&request).ok()?;
+    
+    let mut size = [0u8; 4];
+    stream.read_exact(&mut size).ok()?;
+    let size = u32::from_le_bytes(size) as usize;
+    if !(5..=4096).contains(&size) {
+        debug!("Unexpected PINE reply size {}", size);
+        return None;
+    }
+    let mut reply = vec![0u8; size - 4];
+    stream.read_exact(&mut reply).ok()?;
+    
+    parse_pine_string_reply(&reply)
+}
+
+/// Parse a PINE reply after its size header: a result code, then the string
+/// length (u32 LE, including the trailing NUL) and the string itself
+fn parse_pine_string_reply(reply: &[u8]) -> Option<String> {
+    let (&result, rest) = reply.split_first()?;
+    if result != PINE_RESULT_OK {
+        debug!("PINE request failed with result {:#x}", result);
+        return None;
+    }
+    
+    let len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let bytes = rest.get(4..4 + len)?;
+    let bytes = bytes.split(|&b| b == 0).next().unwrap_or_default();
+    Some(String::from_utf8_lossy(bytes).trim().to_string())
+}
+
+#[cfg(unix)]
+fn pine_connect() -> Option<std::os::unix::net::UnixStream> {
+    let stream = pine_socket_paths()
+        .into_iter()
+        .filter(|path| path.exists())
+        .find_map(|path| std::os::unix::net::UnixStream::connect(path).ok())?;
+    stream.set_read_timeout(Some(PINE_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(PINE_TIMEOUT)).ok()?;
+    Some(stream)
+}
+
+/// Where PCSX2 creates its PINE socket, the slot is appended unless it is the default
+#[cfg(unix)]
+fn pine_socket_paths() -> Vec<PathBuf> {
+    let mut socket_dirs = Vec::new();
+    #[cfg(target_os = "macos")]
+    if let Ok(tmp) = std::env::var("TMPDIR") {
+        socket_dirs.push(PathBuf::from(tmp));
     }
+    if let Some(runtime) = dirs::runtime_dir() {
+        // Flatpak gives the app its own runtime directory
+        socket_dirs.push(runtime.join("app").join("net.pcsx2.PCSX2"));
+        socket_dirs.push(runtime);
+    }
+    socket_dirs.push(PathBuf::from("/tmp"));
     
+    let slot = pine_slot();
+    let name = if slot == PINE_DEFAULT_SLOT {
+        "pcsx2.sock".to_string()
+    } else {
+        format!("pcsx2.sock.{}", slot)
+    };
+    socket_dirs.into_iter().map(|dir| dir.join(&name)).collect()
+}
+
+#[cfg(windows)]
+fn pine_connect() -> Option<std::net::TcpStream> {
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], pine_slot()));
+    let stream = std::net::TcpStream::connect_timeout(&addr, PINE_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(PINE_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(PINE_TIMEOUT)).ok()?;
+    Some(stream)
+}
+
+/// PINE slot from PCSX2's config, the default if it was never changed
+fn pine_slot() -> u16 {
+    pcsx2_config_roots()
+        .iter()
+        .map(|root| root.join("inis").join("PCSX2.ini"))
+        .find_map(|ini| fs::read_to_string(ini).ok())
+        .and_then(|ini| parse_pine_slot(&ini))
+        .unwrap_or(PINE_DEFAULT_SLOT)
+}
+
+/// `PINESlot` from the [EmuCore] section of PCSX2.ini
+fn parse_pine_slot(ini: &str) -> Option<u16> {
+    let mut in_emucore = false;
+    for line in ini.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_emucore = line.eq_ignore_ascii_case("[EmuCore]");
+        } else if in_emucore {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "PINESlot" {
+                    return value.trim().parse().ok().filter(|&slot| slot != 0);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Get game name from PCSX2 window title using native platform APIs
+fn get_game_from_window_title(_pid: u32) -> Option<String> {
     #[cfg(target_os = "windows")]
     {
         get_game_from_window_title_windows()
     }
     
-    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    #[cfg(not(target_os = "windows"))]
     {
-        None
+        find_window_title_by_class("pcsx2", parse_pcsx2_title)
     }
 }
 
-#[cfg(target_os = "linux")]
-fn get_game_from_window_title_linux() -> Option<String> {
+/// Game window titles are the bare game name
+fn parse_pcsx2_title(title: &str) -> Option<String> {
+    (!title.is_empty() && !title.starts_with("PCSX2") && title != "pcsx2-qt").then(|| title.to_string())
+}
+
+/// Title of the first window whose class contains `class_substr` that `parse`
+/// finds a game name in, checking `_NET_WM_NAME` and then `WM_NAME`
+#[cfg(all(target_os = "linux", feature = "gui"))]
+fn find_window_title_by_class(class_substr: &str, parse: fn(&str) -> Option<String>) -> Option<String> {
+    let display = x11_windows::Display::open()?;
+    let windows = display.client_windows();
+    debug!("Checking {} X11 windows for {}", windows.len(), class_substr);
+    
+    windows
+        .into_iter()
+        .filter(|&window| display.class(window).is_some_and(|class| class.contains(class_substr)))
+        .filter_map(|window| display.title(window))
+        .find_map(|title| {
+            debug!("Found {} window with title: {}", class_substr, title);
+            parse(&title)
+        })
+}
+
+#[cfg(not(all(target_os = "linux", feature = "gui")))]
+fn find_window_title_by_class(_class_substr: &str, _parse: fn(&str) -> Option<String>) -> Option<String> {
+    None
+}
+
+/// The Xlib calls behind [`find_window_title_by_class`], with every allocation
+/// freed on drop
+#[cfg(all(target_os = "linux", feature = "gui"))]
+mod x11_windows {
+    use std::ffi::CStr;
     use x11::xlib;
     
-    debug!("Starting X11 window title detection for PCSX2");
+    pub struct Display(*mut xlib::Display);
     
-    unsafe {
-        // Open X11 display
-        let display = xlib::XOpenDisplay(std::ptr::null());
-        if display.is_null() {
-            debug!("Failed to open X11 display");
-            return None;
+    /// Data returned by Xlib, freed with `XFree`
+    struct XData<T>(*mut T);
+    
+    impl<T> Drop for XData<T> {
+        fn drop(&mut self) {
+            if !self.0.is_null() {
+                unsafe { xlib::XFree(self.0.cast()) };
+            }
+        }
+    }
+    
+    impl Display {
+        pub fn open() -> Option<Self> {
+            let display = unsafe { xlib::XOpenDisplay(std::ptr::null()) };
+            if display.is_null() {
+                tracing::debug!("Failed to open X11 display");
+                return None;
+            }
+            Some(Self(display))
         }
         
-        // Get root window
-        let root = xlib::XDefaultRootWindow(display);
-        
-        // Get window list property
-        let mut actual_type = 0;
-        let mut actual_format = 0;
-        let mut num_items = 0;
-        let mut bytes_after = 0;
-        let mut properties: *mut u8 = std::ptr::null_mut();
+        /// Existing atom called `name`, which must be null-terminated. Atoms are
+        /// never freed, so this doesn't create the ones the server doesn't know.
+        fn atom(&self, name: &[u8]) -> Option<xlib::Atom> {
+            assert_eq!(name.last(), Some(&0), "atom name must be null-terminated");
+            let atom = unsafe { xlib::XInternAtom(self.0, name.as_ptr().cast(), xlib::True) };
+            (atom != 0).then_some(atom)
+        }
         
-        let net_client_list = xlib::XInternAtom(
-            display,
-            b"_NET_CLIENT_LIST\0".as_ptr() as *const i8,
-            xlib::False
-        );
+        /// Property of `window` as `items` values of `kind`, `None` when unset or of another type
+        fn property(&self, window: xlib::Window, property: xlib::Atom, kind: xlib::Atom) -> Option<(XData<u8>, usize)> {
+            let mut actual_type = 0;
+            let mut actual_format = 0;
+            let mut items = 0;
+            let mut bytes_after = 0;
+            let mut data: *mut u8 = std::ptr::null_mut();
+            let status = unsafe {
+                xlib::XGetWindowProperty(
+                    self.0,
+                    window,
+                    property,
+                    0,
+                    1024,
+                    xlib::False,
+                    kind,
+                    &mut actual_type,
+                    &mut actual_format,
+                    &mut items,
+                    &mut bytes_after,
+                    &mut data,
+                )
+            };
+            let data = XData(data);
+            (status == 0 && actual_type == kind && !data.0.is_null()).then_some((data, items as usize))
+        }
         
-        if xlib::XGetWindowProperty(
-            display,
-            root,
-            net_client_list,
-            0,
-            1024,
-            xlib::False,
-            xlib::XA_WINDOW,
-            &mut actual_type,
-            &mut actual_format,
-            &mut num_items,
-            &mut bytes_after,
-            &mut properties
-        ) == 0 && !properties.is_null() {
-            let windows = std::slice::from_raw_parts(
-                properties as *const xlib::Window,
-                num_items as usize
-            );
-            
-            debug!("Found {} windows to check", num_items);
-            
-            let net_wm_name = xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                xlib::False
-            );
-            let utf8_string = xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                xlib::False
-            );
-            
-            for &window in windows {
-                // Get window class to check if it's PCSX2
-                let mut class_hint = xlib::XClassHint {
-                    res_name: std::ptr::null_mut(),
-                    res_class: std::ptr::null_mut(),
-                };
-                
-                if xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
-                    let is_pcsx2 = if !class_hint.res_name.is_null() {
-                        let class_name = std::ffi::CStr::from_ptr(class_hint.res_name)
-                            .to_string_lossy()
-                            .to_lowercase();
-                        xlib::XFree(class_hint.res_name as *mut _);
-                        if !class_hint.res_class.is_null() {
-                            xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        class_name.contains("pcsx2")
-                    } else {
-                        if !class_hint.res_class.is_null() {
-                            xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        false
-                    };
-                    
-                    if is_pcsx2 {
-                        // Get window title
-                        let mut title_type = 0;
-                        let mut title_format = 0;
-                        let mut title_items = 0;
-                        let mut title_bytes = 0;
-                        let mut title_prop: *mut u8 = std::ptr::null_mut();
-                        
-                        if xlib::XGetWindowProperty(
-                            display,
-                            window,
-                            net_wm_name,
-                            0,
-                            1024,
-                            xlib::False,
-                            utf8_string,
-                            &mut title_type,
-                            &mut title_format,
-                            &mut title_items,
-                            &mut title_bytes,
-                            &mut title_prop
-                        ) == 0 && !title_prop.is_null() {
-                            let title = std::ffi::CStr::from_ptr(title_prop as *const i8)
-                                .to_string_lossy()
-                                .to_string();
-                            xlib::XFree(title_prop as *mut _);
-                            
-                            debug!("Found PCSX2 window with title: {}", title);
-                            
-                            // Skip generic PCSX2 titles and empty titles
-                            if !title.is_empty() && !title.starts_with("PCSX2") && title != "pcsx2-qt" {
-                                xlib::XFree(properties as *mut _);
-                                xlib::XCloseDisplay(display);
-                                info!("Detected game from X11 window title: {}", title);
-                                return Some(title);
-                            }
-                        } else {
-                            // Try fallback to WM_NAME if _NET_WM_NAME doesn't work
-                            let mut title_prop: *mut i8 = std::ptr::null_mut();
-                            if xlib::XFetchName(display, window, &mut title_prop) != 0 && !title_prop.is_null() {
-                                let title = std::ffi::CStr::from_ptr(title_prop)
-                                    .to_string_lossy()
-                                    .to_string();
-                                xlib::XFree(title_prop as *mut _);
-                                
-                                debug!("Found PCSX2 window with WM_NAME: {}", title);
-                                
-                                if !title.is_empty() && !title.starts_with("PCSX2") && title != "pcsx2-qt" {
-                                    xlib::XFree(properties as *mut _);
-                                    xlib::XCloseDisplay(display);
-                                    info!("Detected game from X11 WM_NAME: {}", title);
-                                    return Some(title);
-                                }
-                            }
-                        }
-                    }
-                }
+        /// Top-level windows as listed by the window manager, or the root's children without one
+        pub fn client_windows(&self) -> Vec<xlib::Window> {
+            let root = unsafe { xlib::XDefaultRootWindow(self.0) };
+            let client_list = self.atom(b"_NET_CLIENT_LIST\0")
+                .and_then(|atom| self.property(root, atom, xlib::XA_WINDOW));
+            if let Some((data, items)) = client_list {
+                // Format 32 properties come back as longs, the size of a Window
+                return unsafe { std::slice::from_raw_parts(data.0 as *const xlib::Window, items) }.to_vec();
             }
             
-            xlib::XFree(properties as *mut _);
+            let mut root_return = 0;
+            let mut parent_return = 0;
+            let mut children: *mut xlib::Window = std::ptr::null_mut();
+            let mut count = 0;
+            let status = unsafe {
+                xlib::XQueryTree(self.0, root, &mut root_return, &mut parent_return, &mut children, &mut count)
+            };
+            let children = XData(children);
+            if status == 0 || children.0.is_null() {
+                return Vec::new();
+            }
+            unsafe { std::slice::from_raw_parts(children.0, count as usize) }.to_vec()
         }
         
-        xlib::XCloseDisplay(display);
+        /// Lowercased instance and class names of `window`
+        pub fn class(&self, window: xlib::Window) -> Option<String> {
+            let mut hint = xlib::XClassHint {
+                res_name: std::ptr::null_mut(),
+                res_class: std::ptr::null_mut(),
+            };
+            if unsafe { xlib::XGetClassHint(self.0, window, &mut hint) } == 0 {
+                return None;
+            }
+            let name = XData(hint.res_name);
+            let class = XData(hint.res_class);
+            
+            let names: Vec<String> = [&name, &class]
+                .iter()
+                .filter(|data| !data.0.is_null())
+                .map(|data| unsafe { CStr::from_ptr(data.0) }.to_string_lossy().to_lowercase())
+                .collect();
+            (!names.is_empty()).then(|| names.join(" "))
+        }
+        
+        /// `_NET_WM_NAME` of `window`, falling back to `WM_NAME`
+        pub fn title(&self, window: xlib::Window) -> Option<String> {
+            let net_wm_name = self.atom(b"_NET_WM_NAME\0")
+                .zip(self.atom(b"UTF8_STRING\0"))
+                .and_then(|(name, utf8_string)| self.property(window, name, utf8_string));
+            if let Some((data, len)) = net_wm_name {
+                let bytes = unsafe { std::slice::from_raw_parts(data.0, len) };
+                return Some(String::from_utf8_lossy(bytes).into_owned());
+            }
+            
+            let mut name: *mut std::os::raw::c_char = std::ptr::null_mut();
+            let status = unsafe { xlib::XFetchName(self.0, window, &mut name) };
+            let name = XData(name);
+            if status == 0 || name.0.is_null() {
+                return None;
+            }
+            Some(unsafe { CStr::from_ptr(name.0) }.to_string_lossy().into_owned())
+        }
     }
     
-    None
+    impl Drop for Display {
+        fn drop(&mut self) {
+            unsafe { xlib::XCloseDisplay(self.0) };
+        }
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -468,17 +739,8 @@ fn get_game_from_window_title_windows() -> Option<String> {
 
 /// Get game info from PCSX2 game settings files
 fn get_game_from_settings_files() -> Option<String> {
-    let settings_dirs = vec![
-        // Flatpak location
-        format!("{}/.var/app/net.pcsx2.PCSX2/config/PCSX2/gamesettings", std::env::var("HOME").ok()?),
-        // Standard location
-        format!("{}/.config/PCSX2/gamesettings", std::env::var("HOME").ok()?),
-        // Old location
-        format!("{}/.pcsx2/gamesettings", std::env::var("HOME").ok()?),
-    ];
-    
-    for dir_path in settings_dirs {
-        let path = Path::new(&dir_path);
+    for root in pcsx2_config_roots() {
+        let path = root.join("gamesettings");
         if !path.exists() {
             continue;
         }
@@ -612,126 +874,21 @@ pub fn get_dolphin_game_name(pid: u32) -> Option<String> {
 }
 
 fn get_dolphin_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        unsafe {
-            // Similar to PCSX2 but looking for Dolphin windows
-            let display = x11::xlib::XOpenDisplay(std::ptr::null());
-            if display.is_null() {
-                return None;
-            }
-            
-            let root = x11::xlib::XDefaultRootWindow(display);
-            let mut root_return = 0;
-            let mut parent_return = 0;
-            let mut children: *mut x11::xlib::Window = std::ptr::null_mut();
-            let mut n_children = 0;
-            
-            if x11::xlib::XQueryTree(
-                display,
-                root,
-                &mut root_return,
-                &mut parent_return,
-                &mut children,
-                &mut n_children
-            ) == 0 {
-                x11::xlib::XCloseDisplay(display);
-                return None;
-            }
-            
-            let windows = std::slice::from_raw_parts(children, n_children as usize);
-            let net_wm_name = x11::xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            let utf8_string = x11::xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            
-            for &window in windows {
-                // Get window class to check if it's Dolphin
-                let mut class_hint = x11::xlib::XClassHint {
-                    res_name: std::ptr::null_mut(),
-                    res_class: std::ptr::null_mut(),
-                };
-                
-                if x11::xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
-                    let is_dolphin = if !class_hint.res_name.is_null() {
-                        let class_name = std::ffi::CStr::from_ptr(class_hint.res_name)
-                            .to_string_lossy()
-                            .to_lowercase();
-                        x11::xlib::XFree(class_hint.res_name as *mut _);
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        class_name.contains("dolphin")
-                    } else {
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        false
-                    };
-                    
-                    if is_dolphin {
-                        // Get window title
-                        let mut title_type = 0;
-                        let mut title_format = 0;
-                        let mut title_items = 0;
-                        let mut title_bytes = 0;
-                        let mut title_prop: *mut u8 = std::ptr::null_mut();
-                        
-                        if x11::xlib::XGetWindowProperty(
-                            display,
-                            window,
-                            net_wm_name,
-                            0,
-                            1024,
-                            x11::xlib::False,
-                            utf8_string,
-                            &mut title_type,
-                            &mut title_format,
-                            &mut title_items,
-                            &mut title_bytes,
-                            &mut title_prop
-                        ) == 0 && !title_prop.is_null() {
-                            let title = std::ffi::CStr::from_ptr(title_prop as *const i8)
-                                .to_string_lossy()
-                                .to_string();
-                            x11::xlib::XFree(title_prop as *mut _);
-                            
-                            // Dolphin window title format: "Dolphin {Version} | {CPU} | {Backend} | {Game Title}"
-                            // Example: "Dolphin 2506a | JIT64 DC | OpenGL | HLE | The Legend of Zelda: The Wind Waker"
-                            let parts: Vec<&str> = title.split(" | ").collect();
-                            if parts.len() >= 4 {
-                                // The game title is usually the last part
-                                let game_title = parts.last().unwrap_or(&"");
-                                if !game_title.is_empty() && 
-                                   !game_title.starts_with("Dolphin") && 
-                                   *game_title != "dolphin-emu" {
-                                    x11::xlib::XFree(children as *mut _);
-                                    x11::xlib::XCloseDisplay(display);
-                                    return Some(game_title.to_string());
-                                }
-                            } else if parts.len() == 1 && !title.starts_with("Dolphin") && title != "dolphin-emu" {
-                                // Sometimes just the game title is shown
-                                x11::xlib::XFree(children as *mut _);
-                                x11::xlib::XCloseDisplay(display);
-                                return Some(title);
-                            }
-                        }
-                    }
-                }
-            }
-            
-            x11::xlib::XFree(children as *mut _);
-            x11::xlib::XCloseDisplay(display);
-        }
-    }
-    
-    None
+    // Not just "dolphin", that's also KDE's file manager, whose folder names
+    // would pass for game titles
+    find_window_title_by_class("dolphin-emu", parse_dolphin_title)
+}
+
+/// "Dolphin {Version} | {CPU} | {Backend} | {Game Title}", or just the game title
+/// e.g. "Dolphin 2506a | JIT64 DC | OpenGL | HLE | The Legend of Zelda: The Wind Waker"
+fn parse_dolphin_title(title: &str) -> Option<String> {
+    let parts: Vec<&str> = title.split(" | ").collect();
+    let game = match parts.len() {
+        1 => title,
+        n if n >= 4 => parts[n - 1],
+        _ => return None,
+    };
+    (!game.is_empty() && !game.starts_with("Dolphin") && game != "dolphin-emu").then(|| game.to_string())
 }
 
 fn get_dolphin_game_from_process_cmd(pid: u32) -> Option<String> {
@@ -756,23 +913,21 @@ fn get_dolphin_game_from_config() -> Option<String> {
     // Try to read the last played game from Dolphin's config
     #[cfg(target_os = "linux")]
     {
-        if let Ok(home) = std::env::var("HOME") {
-            // Check various Dolphin config locations
-            let config_paths = [
-                format!("{}/.var/app/org.DolphinEmu.dolphin-emu/config/dolphin-emu/Dolphin.ini", home),
-                format!("{}/.config/dolphin-emu/Dolphin.ini", home),
-                format!("{}/.dolphin-emu/Config/Dolphin.ini", home),
-            ];
-            
-            for config_path in &config_paths {
-                if let Ok(content) = fs::read_to_string(config_path) {
-                    // Look for LastFilename in the config
-                    for line in content.lines() {
-                        if line.starts_with("LastFilename = ") {
-                            let path = line.trim_start_matches("LastFilename = ");
-                            if let Some(filename) = Path::new(path).file_stem() {
-                                return Some(filename.to_string_lossy().to_string());
-                            }
+        // Check various Dolphin config locations
+        let config_paths = [
+            paths::flatpak_path("org.DolphinEmu.dolphin-emu", "config/dolphin-emu/Dolphin.ini"),
+            paths::config_path("dolphin-emu/Dolphin.ini"),
+            paths::home_path(".dolphin-emu/Config/Dolphin.ini"),
+        ];
+        
+        for config_path in config_paths.iter().flatten() {
+            if let Ok(content) = fs::read_to_string(config_path) {
+                // Look for LastFilename in the config
+                for line in content.lines() {
+                    if line.starts_with("LastFilename = ") {
+                        let path = line.trim_start_matches("LastFilename = ");
+                        if let Some(filename) = Path::new(path).file_stem() {
+                            return Some(filename.to_string_lossy().to_string());
                         }
                     }
                 }
@@ -819,135 +974,32 @@ pub fn get_rpcs3_game_name(pid: u32) -> Option<String> {
 }
 
 fn get_rpcs3_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        unsafe {
-            let display = x11::xlib::XOpenDisplay(std::ptr::null());
-            if display.is_null() {
-                return None;
-            }
-            
-            let root = x11::xlib::XDefaultRootWindow(display);
-            let mut root_return = 0;
-            let mut parent_return = 0;
-            let mut children: *mut x11::xlib::Window = std::ptr::null_mut();
-            let mut n_children = 0;
-            
-            if x11::xlib::XQueryTree(
-                display,
-                root,
-                &mut root_return,
-                &mut parent_return,
-                &mut children,
-                &mut n_children
-            ) == 0 {
-                x11::xlib::XCloseDisplay(display);
-                return None;
-            }
-            
-            let windows = std::slice::from_raw_parts(children, n_children as usize);
-            let net_wm_name = x11::xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            let utf8_string = x11::xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            
-            for &window in windows {
-                // Get window class to check if it's RPCS3
-                let mut class_hint = x11::xlib::XClassHint {
-                    res_name: std::ptr::null_mut(),
-                    res_class: std::ptr::null_mut(),
-                };
-                
-                if x11::xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
-                    let is_rpcs3 = if !class_hint.res_name.is_null() {
-                        let class_name = std::ffi::CStr::from_ptr(class_hint.res_name)
-                            .to_string_lossy()
-                            .to_lowercase();
-                        x11::xlib::XFree(class_hint.res_name as *mut _);
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        class_name.contains("rpcs3")
-                    } else {
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        false
-                    };
-                    
-                    if is_rpcs3 {
-                        // Get window title
-                        let mut title_type = 0;
-                        let mut title_format = 0;
-                        let mut title_items = 0;
-                        let mut title_bytes = 0;
-                        let mut title_prop: *mut u8 = std::ptr::null_mut();
-                        
-                        if x11::xlib::XGetWindowProperty(
-                            display,
-                            window,
-                            net_wm_name,
-                            0,
-                            1024,
-                            x11::xlib::False,
-                            utf8_string,
-                            &mut title_type,
-                            &mut title_format,
-                            &mut title_items,
-                            &mut title_bytes,
-                            &mut title_prop
-                        ) == 0 && !title_prop.is_null() {
-                            let title = std::ffi::CStr::from_ptr(title_prop as *const i8)
-                                .to_string_lossy()
-                                .to_string();
-                            x11::xlib::XFree(title_prop as *mut _);
-                            
-                            // RPCS3 window title format: "Game Title [GAMEID] - RPCS3"
-                            if let Some(game_part) = title.split(" - RPCS3").next() {
-                                if !game_part.is_empty() && game_part != "RPCS3" {
-                                    x11::xlib::XFree(children as *mut _);
-                                    x11::xlib::XCloseDisplay(display);
-                                    return Some(game_part.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            
-            x11::xlib::XFree(children as *mut _);
-            x11::xlib::XCloseDisplay(display);
-        }
-    }
-    
-    None
+    find_window_title_by_class("rpcs3", parse_rpcs3_title)
+}
+
+/// "Game Title [GAMEID] - RPCS3"
+fn parse_rpcs3_title(title: &str) -> Option<String> {
+    let game = title.split(" - RPCS3").next()?;
+    (!game.is_empty() && game != "RPCS3").then(|| game.to_string())
 }
 
 fn get_rpcs3_game_from_logs() -> Option<String> {
     // Try to read RPCS3 log files to find recently launched game
     #[cfg(target_os = "linux")]
     {
-        if let Ok(home) = std::env::var("HOME") {
-            let log_paths = [
-                format!("{}/.config/rpcs3/RPCS3.log", home),
-                format!("{}/.var/app/net.rpcs3.RPCS3/config/rpcs3/RPCS3.log", home),
-            ];
-            
-            for log_path in &log_paths {
-                if let Ok(content) = fs::read_to_string(log_path) {
-                    // Look for game boot messages in the log
-                    for line in content.lines().rev() {
-                        if line.contains("Boot successful") || line.contains("Game:") {
-                            // Extract game name from log line
-                            if let Some(game_info) = line.split("Game:").nth(1) {
-                                return Some(game_info.trim().to_string());
-                            }
+        let log_paths = [
+            paths::config_path("rpcs3/RPCS3.log"),
+            paths::flatpak_path("net.rpcs3.RPCS3", "config/rpcs3/RPCS3.log"),
+        ];
+        
+        for log_path in log_paths.iter().flatten() {
+            if let Ok(content) = fs::read_to_string(log_path) {
+                // Look for game boot messages in the log
+                for line in content.lines().rev() {
+                    if line.contains("Boot successful") || line.contains("Game:") {
+                        // Extract game name from log line
+                        if let Some(game_info) = line.split("Game:").nth(1) {
+                            return Some(game_info.trim().to_string());
                         }
                     }
                 }
@@ -994,137 +1046,33 @@ pub fn get_citra_game_name(pid: u32) -> Option<String> {
 }
 
 fn get_citra_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        unsafe {
-            let display = x11::xlib::XOpenDisplay(std::ptr::null());
-            if display.is_null() {
-                return None;
-            }
-            
-            let root = x11::xlib::XDefaultRootWindow(display);
-            let mut root_return = 0;
-            let mut parent_return = 0;
-            let mut children: *mut x11::xlib::Window = std::ptr::null_mut();
-            let mut n_children = 0;
-            
-            if x11::xlib::XQueryTree(
-                display,
-                root,
-                &mut root_return,
-                &mut parent_return,
-                &mut children,
-                &mut n_children
-            ) == 0 {
-                x11::xlib::XCloseDisplay(display);
-                return None;
-            }
-            
-            let windows = std::slice::from_raw_parts(children, n_children as usize);
-            let net_wm_name = x11::xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            let utf8_string = x11::xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            
-            for &window in windows {
-                // Get window class to check if it's Citra
-                let mut class_hint = x11::xlib::XClassHint {
-                    res_name: std::ptr::null_mut(),
-                    res_class: std::ptr::null_mut(),
-                };
-                
-                if x11::xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
-                    let is_citra = if !class_hint.res_name.is_null() {
-                        let class_name = std::ffi::CStr::from_ptr(class_hint.res_name)
-                            .to_string_lossy()
-                            .to_lowercase();
-                        x11::xlib::XFree(class_hint.res_name as *mut _);
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        class_name.contains("citra")
-                    } else {
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        false
-                    };
-                    
-                    if is_citra {
-                        // Get window title
-                        let mut title_type = 0;
-                        let mut title_format = 0;
-                        let mut title_items = 0;
-                        let mut title_bytes = 0;
-                        let mut title_prop: *mut u8 = std::ptr::null_mut();
-                        
-                        if x11::xlib::XGetWindowProperty(
-                            display,
-                            window,
-                            net_wm_name,
-                            0,
-                            1024,
-                            x11::xlib::False,
-                            utf8_string,
-                            &mut title_type,
-                            &mut title_format,
-                            &mut title_items,
-                            &mut title_bytes,
-                            &mut title_prop
-                        ) == 0 && !title_prop.is_null() {
-                            let title = std::ffi::CStr::from_ptr(title_prop as *const i8)
-                                .to_string_lossy()
-                                .to_string();
-                            x11::xlib::XFree(title_prop as *mut _);
-                            
-                            // Citra window title format: "Citra | Game Title"
-                            if let Some(game_part) = title.split(" | ").nth(1) {
-                                if !game_part.is_empty() {
-                                    x11::xlib::XFree(children as *mut _);
-                                    x11::xlib::XCloseDisplay(display);
-                                    return Some(game_part.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            
-            x11::xlib::XFree(children as *mut _);
-            x11::xlib::XCloseDisplay(display);
-        }
-    }
-    
-    None
+    find_window_title_by_class("citra", parse_citra_title)
+}
+
+/// "Citra | Game Title", Yuzu uses the same format
+fn parse_citra_title(title: &str) -> Option<String> {
+    title.split(" | ").nth(1).filter(|game| !game.is_empty()).map(str::to_string)
 }
 
 fn get_citra_game_from_config() -> Option<String> {
     // Try to read recently played game from Citra's config
     #[cfg(target_os = "linux")]
     {
-        if let Ok(home) = std::env::var("HOME") {
-            let config_paths = [
-                format!("{}/.var/app/org.citra_emu.citra/config/citra-emu/qt-config.ini", home),
-                format!("{}/.config/citra-emu/qt-config.ini", home),
-                format!("{}/.citra-emu/qt-config.ini", home),
-            ];
-            
-            for config_path in &config_paths {
-                if let Ok(content) = fs::read_to_string(config_path) {
-                    // Look for recent files in the config
-                    for line in content.lines() {
-                        if line.starts_with("recent_files\\") && line.contains(".3ds") {
-                            // Extract game name from path
-                            if let Some(path_part) = line.split('=').nth(1) {
-                                if let Some(filename) = Path::new(path_part).file_stem() {
-                                    return Some(filename.to_string_lossy().to_string());
-                                }
+        let config_paths = [
+            paths::flatpak_path("org.citra_emu.citra", "config/citra-emu/qt-config.ini"),
+            paths::config_path("citra-emu/qt-config.ini"),
+            paths::home_path(".citra-emu/qt-config.ini"),
+        ];
+        
+        for config_path in config_paths.iter().flatten() {
+            if let Ok(content) = fs::read_to_string(config_path) {
+                // Look for recent files in the config
+                for line in content.lines() {
+                    if line.starts_with("recent_files\\") && line.contains(".3ds") {
+                        // Extract game name from path
+                        if let Some(path_part) = line.split('=').nth(1) {
+                            if let Some(filename) = Path::new(path_part).file_stem() {
+                                return Some(filename.to_string_lossy().to_string());
                             }
                         }
                     }
@@ -1174,141 +1122,37 @@ pub fn get_retroarch_game_name(pid: u32) -> Option<String> {
 }
 
 fn get_retroarch_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        unsafe {
-            let display = x11::xlib::XOpenDisplay(std::ptr::null());
-            if display.is_null() {
-                return None;
-            }
-            
-            let root = x11::xlib::XDefaultRootWindow(display);
-            let mut root_return = 0;
-            let mut parent_return = 0;
-            let mut children: *mut x11::xlib::Window = std::ptr::null_mut();
-            let mut n_children = 0;
-            
-            if x11::xlib::XQueryTree(
-                display,
-                root,
-                &mut root_return,
-                &mut parent_return,
-                &mut children,
-                &mut n_children
-            ) == 0 {
-                x11::xlib::XCloseDisplay(display);
-                return None;
-            }
-            
-            let windows = std::slice::from_raw_parts(children, n_children as usize);
-            let net_wm_name = x11::xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            let utf8_string = x11::xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            
-            for &window in windows {
-                // Get window class to check if it's RetroArch
-                let mut class_hint = x11::xlib::XClassHint {
-                    res_name: std::ptr::null_mut(),
-                    res_class: std::ptr::null_mut(),
-                };
-                
-                if x11::xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
-                    let is_retroarch = if !class_hint.res_name.is_null() {
-                        let class_name = std::ffi::CStr::from_ptr(class_hint.res_name)
-                            .to_string_lossy()
-                            .to_lowercase();
-                        x11::xlib::XFree(class_hint.res_name as *mut _);
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        class_name.contains("retroarch")
-                    } else {
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        false
-                    };
-                    
-                    if is_retroarch {
-                        // Get window title
-                        let mut title_type = 0;
-                        let mut title_format = 0;
-                        let mut title_items = 0;
-                        let mut title_bytes = 0;
-                        let mut title_prop: *mut u8 = std::ptr::null_mut();
-                        
-                        if x11::xlib::XGetWindowProperty(
-                            display,
-                            window,
-                            net_wm_name,
-                            0,
-                            1024,
-                            x11::xlib::False,
-                            utf8_string,
-                            &mut title_type,
-                            &mut title_format,
-                            &mut title_items,
-                            &mut title_bytes,
-                            &mut title_prop
-                        ) == 0 && !title_prop.is_null() {
-                            let title = std::ffi::CStr::from_ptr(title_prop as *const i8)
-                                .to_string_lossy()
-                                .to_string();
-                            x11::xlib::XFree(title_prop as *mut _);
-                            
-                            // RetroArch window title format varies, but often includes game name
-                            // Format examples: "RetroArch - Game Name" or "Game Name - RetroArch Core"
-                            if title.contains("RetroArch") {
-                                // Try to extract game name
-                                if let Some(game_part) = title.split(" - ").find(|p| !p.contains("RetroArch")) {
-                                    if !game_part.is_empty() {
-                                        x11::xlib::XFree(children as *mut _);
-                                        x11::xlib::XCloseDisplay(display);
-                                        return Some(game_part.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            
-            x11::xlib::XFree(children as *mut _);
-            x11::xlib::XCloseDisplay(display);
-        }
+    find_window_title_by_class("retroarch", parse_retroarch_title)
+}
+
+/// Varies by core, e.g. "RetroArch - Game Name" or "Game Name - RetroArch Core"
+fn parse_retroarch_title(title: &str) -> Option<String> {
+    if !title.contains("RetroArch") {
+        return None;
     }
-    
-    None
+    title.split(" - ").find(|part| !part.contains("RetroArch") && !part.is_empty()).map(str::to_string)
 }
 
 fn get_retroarch_game_from_history() -> Option<String> {
     // Try to read recent content from RetroArch's history
     #[cfg(target_os = "linux")]
     {
-        if let Ok(home) = std::env::var("HOME") {
-            let history_paths = [
-                format!("{}/.var/app/org.libretro.RetroArch/config/retroarch/content_history.lpl", home),
-                format!("{}/.config/retroarch/content_history.lpl", home),
-            ];
-            
-            for history_path in &history_paths {
-                if let Ok(content) = fs::read_to_string(history_path) {
-                    // RetroArch history is in JSON format
-                    // Look for the most recent entry
-                    if let Some(start) = content.find("\"path\": \"") {
-                        let path_start = start + 9;
-                        if let Some(end) = content[path_start..].find("\"") {
-                            let game_path = &content[path_start..path_start + end];
-                            if let Some(filename) = Path::new(game_path).file_stem() {
-                                return Some(filename.to_string_lossy().to_string());
-                            }
+        let history_paths = [
+            paths::flatpak_path("org.libretro.RetroArch", "config/retroarch/content_history.lpl"),
+            paths::snap_path("retroarch", ".config/retroarch/content_history.lpl"),
+            paths::config_path("retroarch/content_history.lpl"),
+        ];
+        
+        for history_path in history_paths.iter().flatten() {
+            if let Ok(content) = fs::read_to_string(history_path) {
+                // RetroArch history is in JSON format
+                // Look for the most recent entry
+                if let Some(start) = content.find("\"path\": \"") {
+                    let path_start = start + 9;
+                    if let Some(end) = content[path_start..].find("\"") {
+                        let game_path = &content[path_start..path_start + end];
+                        if let Some(filename) = Path::new(game_path).file_stem() {
+                            return Some(filename.to_string_lossy().to_string());
                         }
                     }
                 }
@@ -1364,225 +1208,16 @@ pub fn get_ryujinx_game_name(pid: u32) -> Option<String> {
 }
 
 fn get_yuzu_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        unsafe {
-            let display = x11::xlib::XOpenDisplay(std::ptr::null());
-            if display.is_null() {
-                return None;
-            }
-            
-            let root = x11::xlib::XDefaultRootWindow(display);
-            let mut root_return = 0;
-            let mut parent_return = 0;
-            let mut children: *mut x11::xlib::Window = std::ptr::null_mut();
-            let mut n_children = 0;
-            
-            if x11::xlib::XQueryTree(
-                display,
-                root,
-                &mut root_return,
-                &mut parent_return,
-                &mut children,
-                &mut n_children
-            ) == 0 {
-                x11::xlib::XCloseDisplay(display);
-                return None;
-            }
-            
-            let windows = std::slice::from_raw_parts(children, n_children as usize);
-            let net_wm_name = x11::xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            let utf8_string = x11::xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            
-            for &window in windows {
-                // Get window class to check if it's Yuzu
-                let mut class_hint = x11::xlib::XClassHint {
-                    res_name: std::ptr::null_mut(),
-                    res_class: std::ptr::null_mut(),
-                };
-                
-                if x11::xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
-                    let is_yuzu = if !class_hint.res_name.is_null() {
-                        let class_name = std::ffi::CStr::from_ptr(class_hint.res_name)
-                            .to_string_lossy()
-                            .to_lowercase();
-                        x11::xlib::XFree(class_hint.res_name as *mut _);
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        class_name.contains("yuzu")
-                    } else {
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        false
-                    };
-                    
-                    if is_yuzu {
-                        // Get window title
-                        let mut title_type = 0;
-                        let mut title_format = 0;
-                        let mut title_items = 0;
-                        let mut title_bytes = 0;
-                        let mut title_prop: *mut u8 = std::ptr::null_mut();
-                        
-                        if x11::xlib::XGetWindowProperty(
-                            display,
-                            window,
-                            net_wm_name,
-                            0,
-                            1024,
-                            x11::xlib::False,
-                            utf8_string,
-                            &mut title_type,
-                            &mut title_format,
-                            &mut title_items,
-                            &mut title_bytes,
-                            &mut title_prop
-                        ) == 0 && !title_prop.is_null() {
-                            let title = std::ffi::CStr::from_ptr(title_prop as *const i8)
-                                .to_string_lossy()
-                                .to_string();
-                            x11::xlib::XFree(title_prop as *mut _);
-                            
-                            // Yuzu window title format: "yuzu | Game Title"
-                            if let Some(game_part) = title.split(" | ").nth(1) {
-                                if !game_part.is_empty() {
-                                    x11::xlib::XFree(children as *mut _);
-                                    x11::xlib::XCloseDisplay(display);
-                                    return Some(game_part.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            
-            x11::xlib::XFree(children as *mut _);
-            x11::xlib::XCloseDisplay(display);
-        }
-    }
-    
-    None
+    find_window_title_by_class("yuzu", parse_citra_title)
 }
 
 fn get_ryujinx_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        unsafe {
-            let display = x11::xlib::XOpenDisplay(std::ptr::null());
-            if display.is_null() {
-                return None;
-            }
-            
-            let root = x11::xlib::XDefaultRootWindow(display);
-            let mut root_return = 0;
-            let mut parent_return = 0;
-            let mut children: *mut x11::xlib::Window = std::ptr::null_mut();
-            let mut n_children = 0;
-            
-            if x11::xlib::XQueryTree(
-                display,
-                root,
-                &mut root_return,
-                &mut parent_return,
-                &mut children,
-                &mut n_children
-            ) == 0 {
-                x11::xlib::XCloseDisplay(display);
-                return None;
-            }
-            
-            let windows = std::slice::from_raw_parts(children, n_children as usize);
-            let net_wm_name = x11::xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            let utf8_string = x11::xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            
-            for &window in windows {
-                // Get window class to check if it's Ryujinx
-                let mut class_hint = x11::xlib::XClassHint {
-                    res_name: std::ptr::null_mut(),
-                    res_class: std::ptr::null_mut(),
-                };
-                
-                if x11::xlib::XGetClassHint(display, window, &mut class_hint) != 0 {
-                    let is_ryujinx = if !class_hint.res_name.is_null() {
-                        let class_name = std::ffi::CStr::from_ptr(class_hint.res_name)
-                            .to_string_lossy()
-                            .to_lowercase();
-                        x11::xlib::XFree(class_hint.res_name as *mut _);
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        class_name.contains("ryujinx")
-                    } else {
-                        if !class_hint.res_class.is_null() {
-                            x11::xlib::XFree(class_hint.res_class as *mut _);
-                        }
-                        false
-                    };
-                    
-                    if is_ryujinx {
-                        // Get window title
-                        let mut title_type = 0;
-                        let mut title_format = 0;
-                        let mut title_items = 0;
-                        let mut title_bytes = 0;
-                        let mut title_prop: *mut u8 = std::ptr::null_mut();
-                        
-                        if x11::xlib::XGetWindowProperty(
-                            display,
-                            window,
-                            net_wm_name,
-                            0,
-                            1024,
-                            x11::xlib::False,
-                            utf8_string,
-                            &mut title_type,
-                            &mut title_format,
-                            &mut title_items,
-                            &mut title_bytes,
-                            &mut title_prop
-                        ) == 0 && !title_prop.is_null() {
-                            let title = std::ffi::CStr::from_ptr(title_prop as *const i8)
-                                .to_string_lossy()
-                                .to_string();
-                            x11::xlib::XFree(title_prop as *mut _);
-                            
-                            // Ryujinx window title format: "Ryujinx - Game Title"
-                            if let Some(game_part) = title.split(" - ").nth(1) {
-                                if !game_part.is_empty() {
-                                    x11::xlib::XFree(children as *mut _);
-                                    x11::xlib::XCloseDisplay(display);
-                                    return Some(game_part.to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            
-            x11::xlib::XFree(children as *mut _);
-            x11::xlib::XCloseDisplay(display);
-        }
-    }
-    
-    None
+    find_window_title_by_class("ryujinx", parse_ryujinx_title)
+}
+
+/// "Ryujinx - Game Title"
+fn parse_ryujinx_title(title: &str) -> Option<String> {
+    title.split(" - ").nth(1).filter(|game| !game.is_empty()).map(str::to_string)
 }
 
 pub fn get_ppsspp_game_name(pid: u32) -> Option<String> {
@@ -1602,93 +1237,17 @@ pub fn get_ppsspp_game_name(pid: u32) -> Option<String> {
 }
 
 fn get_ppsspp_game_from_window_title(_pid: u32) -> Option<String> {
-    #[cfg(target_os = "linux")]
-    {
-        unsafe {
-            let display = x11::xlib::XOpenDisplay(std::ptr::null());
-            if display.is_null() {
-                return None;
-            }
-            
-            let root = x11::xlib::XDefaultRootWindow(display);
-            let mut root_return = 0;
-            let mut parent_return = 0;
-            let mut children: *mut x11::xlib::Window = std::ptr::null_mut();
-            let mut n_children = 0;
-            
-            if x11::xlib::XQueryTree(
-                display,
-                root,
-                &mut root_return,
-                &mut parent_return,
-                &mut children,
-                &mut n_children
-            ) == 0 {
-                x11::xlib::XCloseDisplay(display);
-                return None;
-            }
-            
-            let windows = std::slice::from_raw_parts(children, n_children as usize);
-            let net_wm_name = x11::xlib::XInternAtom(
-                display,
-                b"_NET_WM_NAME\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            let utf8_string = x11::xlib::XInternAtom(
-                display,
-                b"UTF8_STRING\0".as_ptr() as *const i8,
-                x11::xlib::False
-            );
-            
-            for &window in windows {
-                let mut actual_type = 0;
-                let mut actual_format = 0;
-                let mut n_items = 0;
-                let mut bytes_after = 0;
-                let mut prop: *mut u8 = std::ptr::null_mut();
-                
-                if x11::xlib::XGetWindowProperty(
-                    display,
-                    window,
-                    net_wm_name,
-                    0,
-                    1024,
-                    x11::xlib::False,
-                    utf8_string,
-                    &mut actual_type,
-                    &mut actual_format,
-                    &mut n_items,
-                    &mut bytes_after,
-                    &mut prop
-                ) == 0 && !prop.is_null() {
-                    let title = std::ffi::CStr::from_ptr(prop as *const i8)
-                        .to_string_lossy()
-                        .to_string();
-                    
-                    x11::xlib::XFree(prop as *mut _);
-                    
-                    // PPSSPP window titles typically show game name
-                    if title.to_lowercase().contains("ppsspp") {
-                        // Extract game name from title
-                        // Format varies but often: "PPSSPP - Game Name"
-                        if let Some(idx) = title.find(" - ") {
-                            let game = title[(idx + 3)..].trim().to_string();
-                            if !game.is_empty() {
-                                x11::xlib::XFree(children as *mut _);
-                                x11::xlib::XCloseDisplay(display);
-                                return Some(game);
-                            }
-                        }
-                    }
-                }
-            }
-            
-            x11::xlib::XFree(children as *mut _);
-            x11::xlib::XCloseDisplay(display);
-        }
+    find_window_title_by_class("ppsspp", parse_ppsspp_title)
+}
+
+/// Usually "PPSSPP - Game Name"
+fn parse_ppsspp_title(title: &str) -> Option<String> {
+    if !title.to_lowercase().contains("ppsspp") {
+        return None;
     }
-    
-    None
+    let (_, game) = title.split_once(" - ")?;
+    let game = game.trim();
+    (!game.is_empty()).then(|| game.to_string())
 }
 
 fn get_ppsspp_game_from_saves() -> Option<String> {
@@ -1747,24 +1306,14 @@ fn get_ppsspp_save_directory() -> Option<String> {
     
     #[cfg(target_os = "linux")]
     {
-        if let Ok(home) = std::env::var("HOME") {
-            // Check Flatpak location first
-            let flatpak_path = format!("{}/.var/app/org.ppsspp.PPSSPP/.config/ppsspp/PSP/SAVEDATA", home);
-            if Path::new(&flatpak_path).exists() {
-                return Some(flatpak_path);
-            }
-            
-            // Check standard location
-            let save_path = format!("{}/.config/ppsspp/PSP/SAVEDATA", home);
-            if Path::new(&save_path).exists() {
-                return Some(save_path);
-            }
-            
-            // Check old location
-            let old_path = format!("{}/.ppsspp/PSP/SAVEDATA", home);
-            if Path::new(&old_path).exists() {
-                return Some(old_path);
-            }
+        let candidates: Vec<PathBuf> = [
+            paths::flatpak_path("org.ppsspp.PPSSPP", ".config/ppsspp/PSP/SAVEDATA"),
+            paths::snap_path("ppsspp-emu", ".config/ppsspp/PSP/SAVEDATA"),
+            paths::config_path("ppsspp/PSP/SAVEDATA"),
+            paths::home_path(".ppsspp/PSP/SAVEDATA"),
+        ].into_iter().flatten().collect();
+        if let Some(dir) = paths::resolve_emulator_dir(&candidates) {
+            return Some(dir.to_string_lossy().to_string());
         }
     }
     
@@ -1803,3 +1352,96 @@ fn get_ppsspp_game_name_from_id(game_id: &str) -> Option<String> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_memory_cards_folder() {
+        let qt = "[UI]\nTheme = darkfusion\n\n[Folders]\nBios = bios\nMemoryCards = /mnt/games/ps2/memcards\n";
+        assert_eq!(parse_memory_cards_folder(qt).as_deref(), Some("/mnt/games/ps2/memcards"));
+        
+        // PCSX2 1.6 keeps the folder but only uses it with the default turned off
+        let legacy = "UseDefaultMemoryCards=enabled\n[Folders]\nMemoryCards=D:\\PS2\\memcards\n";
+        assert_eq!(parse_memory_cards_folder(legacy), None);
+        let legacy = legacy.replace("=enabled", "=disabled");
+        assert_eq!(parse_memory_cards_folder(&legacy).as_deref(), Some("D:\\PS2\\memcards"));
+        
+        // Only the Folders section counts
+        assert_eq!(parse_memory_cards_folder("[Other]\nMemoryCards = elsewhere\n"), None);
+    }
+    
+    #[test]
+    fn test_memcard_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_install = temp_dir.path().join("old");
+        let install = temp_dir.path().join("new");
+        for dir in [old_install.join("memcards"), install.join("memcards"), install.join("inis"), install.join("cards")] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let roots = vec![old_install.clone(), install.clone()];
+        
+        // Without a configured folder only the first default one
+        assert_eq!(memcard_directories_in(&roots), vec![old_install.join("memcards")]);
+        
+        fs::write(install.join("inis").join("PCSX2.ini"), "[Folders]\nMemoryCards = cards\n").unwrap();
+        assert_eq!(memcard_directories_in(&roots), vec![install.join("cards")]);
+    }
+    
+    #[test]
+    fn test_emulator_process_names() {
+        for (name, _) in EMULATOR_EXECUTABLES {
+            let process = EmulatorProcess::new(name, 1, "unknown".to_string()).unwrap();
+            assert_eq!(process.name(), *name);
+        }
+        assert!(EmulatorProcess::new("Cemu", 1, "unknown".to_string()).is_none());
+        
+        // Nothing is scanned when every emulator is disabled
+        let all: Vec<String> = EMULATOR_EXECUTABLES.iter().map(|(name, _)| name.to_string()).collect();
+        assert!(detect_running_emulators(&all).is_empty());
+    }
+    
+    #[test]
+    fn test_parse_pine_string_reply() {
+        let mut reply = vec![PINE_RESULT_OK];
+        reply.extend_from_slice(&11u32.to_le_bytes());
+        reply.extend_from_slice(b"SLUS-20552\0");
+        assert_eq!(parse_pine_string_reply(&reply).as_deref(), Some("SLUS-20552"));
+        
+        // Nothing booted
+        let mut empty = vec![PINE_RESULT_OK];
+        empty.extend_from_slice(&1u32.to_le_bytes());
+        empty.push(0);
+        assert_eq!(parse_pine_string_reply(&empty).as_deref(), Some(""));
+        
+        assert_eq!(parse_pine_string_reply(&[0xFF]), None);
+        // Length running past the reply
+        assert_eq!(parse_pine_string_reply(&reply[..8]), None);
+    }
+    
+    #[test]
+    fn test_parse_window_titles() {
+        assert_eq!(parse_pcsx2_title("Shadow of the Colossus").as_deref(), Some("Shadow of the Colossus"));
+        assert_eq!(parse_pcsx2_title("PCSX2 v2.0.2"), None);
+        
+        let dolphin = "Dolphin 2506a | JIT64 DC | OpenGL | HLE | The Legend of Zelda: The Wind Waker";
+        assert_eq!(parse_dolphin_title(dolphin).as_deref(), Some("The Legend of Zelda: The Wind Waker"));
+        assert_eq!(parse_dolphin_title("Dolphin 2506a"), None);
+        
+        assert_eq!(parse_rpcs3_title("Demon's Souls [BLUS30443] - RPCS3").as_deref(), Some("Demon's Souls [BLUS30443]"));
+        assert_eq!(parse_citra_title("Citra | Pokemon Y").as_deref(), Some("Pokemon Y"));
+        assert_eq!(parse_citra_title("Citra"), None);
+        assert_eq!(parse_retroarch_title("RetroArch - Chrono Trigger").as_deref(), Some("Chrono Trigger"));
+        assert_eq!(parse_ryujinx_title("Ryujinx - Super Mario Odyssey").as_deref(), Some("Super Mario Odyssey"));
+        assert_eq!(parse_ppsspp_title("PPSSPP v1.17 - Lumines ").as_deref(), Some("Lumines"));
+        assert_eq!(parse_ppsspp_title("Lumines"), None);
+    }
+    
+    #[test]
+    fn test_parse_pine_slot() {
+        assert_eq!(parse_pine_slot("[EmuCore]\nEnablePINE = true\nPINESlot = 28012\n"), Some(28012));
+        assert_eq!(parse_pine_slot("[EmuCore]\nEnablePINE = true\n"), None);
+        assert_eq!(parse_pine_slot("[UI]\nPINESlot = 28012\n"), None);
+    }
+}
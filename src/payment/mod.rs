@@ -46,6 +46,22 @@ impl SubscriptionTier {
             Self::Lifetime => 20,
         }
     }
+    
+    /// Tier for a backend tier id such as `"pro"`
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.to_lowercase().as_str() {
+            "free" => Some(Self::Free),
+            "pro" => Some(Self::Pro),
+            "family" => Some(Self::Family),
+            "lifetime" => Some(Self::Lifetime),
+            _ => None,
+        }
+    }
+    
+    /// Lifetime accounts are never held back by the client
+    pub fn is_unlimited(&self) -> bool {
+        matches!(self, Self::Lifetime)
+    }
 }
 
 /// Billing period for subscriptions
@@ -149,5 +165,62 @@ impl UsageStats {
     pub fn is_near_limit(&self) -> bool {
         self.saves_percentage() > 80.0 || self.storage_percentage() > 80.0
     }
+    
+    /// No more saves can be uploaded until something is deleted or the plan is upgraded
+    pub fn is_over_limit(&self) -> bool {
+        self.saves_percentage() >= 100.0 || self.storage_percentage() >= 100.0
+    }
+    
+    /// Whether uploading one more save of `size` bytes would go past a limit
+    pub fn would_exceed(&self, size: i64) -> bool {
+        (self.saves_limit > 0 && self.saves_count >= self.saves_limit)
+            || (self.storage_limit_bytes > 0 && self.storage_bytes + size > self.storage_limit_bytes)
+    }
+    
+    /// Fill in limits the server didn't report with the tier's defaults
+    pub fn with_tier_defaults(mut self, tier: SubscriptionTier) -> Self {
+        if self.saves_limit <= 0 {
+            self.saves_limit = i32::try_from(tier.max_saves()).unwrap_or(i32::MAX);
+        }
+        if self.storage_limit_bytes <= 0 {
+            self.storage_limit_bytes = tier.max_storage_gb() as i64 * 1_073_741_824;
+        }
+        self
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn usage(saves_count: i32, saves_limit: i32, storage_bytes: i64, storage_limit_bytes: i64) -> UsageStats {
+        UsageStats {
+            saves_count,
+            saves_limit,
+            storage_bytes,
+            storage_limit_bytes,
+            devices_count: 1,
+            devices_limit: 2,
+        }
+    }
+    
+    #[test]
+    fn test_would_exceed() {
+        assert!(!usage(99, 100, 0, 1000).would_exceed(10));
+        assert!(usage(100, 100, 0, 1000).would_exceed(10));
+        assert!(usage(10, 100, 995, 1000).would_exceed(10));
+        assert!(!usage(10, 0, 995, 0).would_exceed(10));
+    }
+    
+    #[test]
+    fn test_tier_defaults_fill_missing_limits() {
+        let filled = usage(100, 0, 0, 0).with_tier_defaults(SubscriptionTier::Free);
+        assert_eq!(filled.saves_limit, 100);
+        assert_eq!(filled.storage_limit_bytes, 1_073_741_824);
+        assert!(filled.would_exceed(0));
+        
+        let lifetime = usage(100, 0, 0, 0).with_tier_defaults(SubscriptionTier::Lifetime);
+        assert_eq!(lifetime.saves_limit, i32::MAX);
+    }
+}
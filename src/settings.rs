@@ -0,0 +1,234 @@
+//! User settings, shared by the settings window and the headless daemon
+use crate::sync::service::ConflictResolutionStrategy;
+use crate::sync::EncryptionMode;
+
+/// Missing fields take their default, so exports from older versions import
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub auto_save_enabled: bool,
+    pub save_interval_minutes: u32,
+    pub process_scan_interval_secs: u32,  // Longer uses less CPU, shorter notices emulators and saves sooner
+    pub max_saves_per_game: u32,  // Memory cards and other persistent saves
+    pub max_save_states_per_game: u32,  // Save-states are bulkier and usually near-identical
+    pub start_on_boot: bool,
+    pub minimize_to_tray: bool,
+    pub show_notifications: bool,
+    pub cloud_sync_enabled: bool,
+    #[serde(skip)]
+    pub cloud_api_url: String,
+    pub cloud_auto_sync: bool,
+    pub sync_backend: SyncBackend,  // Applies after restarting
+    pub local_sync_folder: Option<String>,  // NAS share or file sync folder used by SyncBackend::LocalFolder
+    pub rclone_remote: Option<String>,  // remote:path used by SyncBackend::Rclone
+    pub hotkey_enabled: bool,
+    pub save_hotkey: Option<String>,
+    pub compression_enabled: bool,
+    pub compression_level: i32,
+    pub auto_vacuum_enabled: bool,
+    pub max_sync_bandwidth_kbps: u32,  // 0 = unlimited
+    pub parallel_downloads: u32,  // Cloud saves downloaded at once
+    pub parallel_uploads: u32,  // Saves of different games uploaded at once
+    pub conflict_strategy: ConflictResolutionStrategy,
+    pub sync_after_closing_only: bool,  // Hold uploads while the save's emulator is running
+    pub restore_deleted_saves: bool,  // Cloud always wins, even over saves deleted locally
+    pub failure_webhook_url: Option<String>,  // Discord, Slack or generic JSON webhook told about sync failures
+    pub encryption_enabled: bool,  // Uploads wait while the key is locked
+    pub encryption_mode: EncryptionMode,
+    pub watcher_ignore_patterns: Vec<String>,  // Added to the watcher's built-in ignore list
+    pub backup_exclusions: Vec<String>,  // Files and folders never backed up, relative to the save directory
+    pub pcsx2_extra_memcard_dirs: Vec<String>,  // Watched alongside the memcard folders found in PCSX2's config
+    pub disabled_emulators: Vec<String>,  // Never detected or watched
+    pub allowlist_enabled: bool,  // Only back up games matching allowlisted_games
+    pub allowlisted_games: Vec<String>,  // Game names or serials, * and ? wildcards
+    pub verbose_logging: bool,  // Debug logs for capturing a bug report
+    pub theme: ThemePreference,
+    pub custom_ca_cert_path: Option<String>,  // PEM with extra root certificates for self-hosted servers
+    pub accept_invalid_certs: bool,  // Development only, disables certificate checks
+    pub proxy_url: Option<String>,  // Overrides the HTTP(S)_PROXY/ALL_PROXY environment variables
+    #[serde(skip)]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,  // Last saved on this device, see settings_sync::MergePolicy
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        // API URL is determined by environment variable or build configuration
+        // Users cannot change this - it's managed automatically
+        let cloud_api_url = Self::get_api_url();
+
+        Self {
+            auto_save_enabled: true,
+            save_interval_minutes: 5,
+            process_scan_interval_secs: 5,
+            max_saves_per_game: 5,
+            max_save_states_per_game: 3,
+            start_on_boot: false,
+            minimize_to_tray: true,
+            show_notifications: true,
+            cloud_sync_enabled: false,
+            cloud_api_url,
+            cloud_auto_sync: true,
+            sync_backend: SyncBackend::Cloud,
+            local_sync_folder: None,
+            rclone_remote: None,
+            hotkey_enabled: true,
+            save_hotkey: Some("Ctrl+Shift+S".to_string()),
+            compression_enabled: true,
+            compression_level: 3,
+            auto_vacuum_enabled: true,
+            max_sync_bandwidth_kbps: 0,
+            parallel_downloads: 4,
+            parallel_uploads: 3,
+            conflict_strategy: ConflictResolutionStrategy::NewerWins,
+            sync_after_closing_only: false,
+            restore_deleted_saves: false,
+            failure_webhook_url: None,
+            encryption_enabled: false,
+            encryption_mode: EncryptionMode::Passphrase,
+            watcher_ignore_patterns: Vec::new(),
+            backup_exclusions: Vec::new(),
+            pcsx2_extra_memcard_dirs: Vec::new(),
+            disabled_emulators: Vec::new(),
+            allowlist_enabled: false,
+            allowlisted_games: Vec::new(),
+            verbose_logging: false,
+            theme: ThemePreference::System,
+            custom_ca_cert_path: None,
+            accept_invalid_certs: false,
+            proxy_url: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Get the API URL based on environment configuration
+    /// This is not user-configurable - it's determined automatically
+    pub fn get_api_url() -> String {
+        // First check environment variable (for development/testing)
+        if let Ok(url) = std::env::var("RETROSAVE_API_URL") {
+            return url;
+        }
+        
+        // Otherwise use build configuration
+        #[cfg(debug_assertions)]
+        {
+            // Development build - use local server
+            "http://localhost:8080".to_string()
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            // Production build - use cloud API
+            "https://api.retrosave.cloud".to_string()
+        }
+    }
+    
+    /// Ensure API URL is always using the correct value
+    /// Call this when loading settings from disk to override any stored value
+    pub fn update_api_url(&mut self) {
+        self.cloud_api_url = Self::get_api_url();
+    }
+    
+    /// Folder to sync through instead of the Retrosave server, if that backend is chosen
+    pub fn sync_folder(&self) -> Option<std::path::PathBuf> {
+        match self.sync_backend {
+            SyncBackend::LocalFolder => self.local_sync_folder.as_deref()
+                .map(str::trim)
+                .filter(|folder| !folder.is_empty())
+                .map(std::path::PathBuf::from),
+            SyncBackend::Cloud | SyncBackend::Rclone => None,
+        }
+    }
+    
+    /// rclone `remote:path` to sync through instead of the Retrosave server, if that backend is chosen
+    pub fn rclone_target(&self) -> Option<String> {
+        match self.sync_backend {
+            SyncBackend::Rclone => self.rclone_remote.as_deref()
+                .map(str::trim)
+                .filter(|remote| crate::sync::rclone::is_valid_remote(remote))
+                .map(str::to_string),
+            SyncBackend::Cloud | SyncBackend::LocalFolder => None,
+        }
+    }
+    
+    /// Pull values the settings window couldn't have produced back into the
+    /// ranges its controls allow, for settings that came from a file
+    pub fn clamp_to_valid_ranges(&mut self) {
+        self.save_interval_minutes = self.save_interval_minutes.clamp(1, 60);
+        self.process_scan_interval_secs = self.process_scan_interval_secs.clamp(1, 60);
+        self.max_saves_per_game = self.max_saves_per_game.clamp(1, 20);
+        self.max_save_states_per_game = self.max_save_states_per_game.clamp(1, 20);
+        self.compression_level = self.compression_level.clamp(1, 22);
+        self.parallel_downloads = self.parallel_downloads.clamp(1, 16);
+        self.parallel_uploads = self.parallel_uploads.clamp(1, 16);
+        self.disabled_emulators.retain(|name| crate::monitor::EMULATORS.contains(&name.as_str()));
+        self.disabled_emulators.dedup();
+    }
+}
+
+/// Where saves are synced to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncBackend {
+    /// The Retrosave server, needs an account
+    #[default]
+    Cloud,
+    /// A folder other devices also see, through a NAS or a file sync tool
+    LocalFolder,
+    /// Storage the user already has, through an installed rclone
+    Rclone,
+}
+
+impl SyncBackend {
+    pub const ALL: [SyncBackend; 3] = [SyncBackend::Cloud, SyncBackend::LocalFolder, SyncBackend::Rclone];
+    
+    /// Human readable name for the settings window
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncBackend::Cloud => "Cloud",
+            SyncBackend::LocalFolder => "Local folder",
+            SyncBackend::Rclone => "rclone remote",
+        }
+    }
+}
+
+/// Which visuals the windows use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreference {
+    /// Follow the operating system's light/dark setting
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    pub const ALL: [ThemePreference; 3] = [
+        ThemePreference::System,
+        ThemePreference::Light,
+        ThemePreference::Dark,
+    ];
+
+    /// Stable identifier used when persisting the setting
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemePreference::System => "system",
+            ThemePreference::Light => "light",
+            ThemePreference::Dark => "dark",
+        }
+    }
+
+    pub fn from_key(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+
+    /// Human readable name for the settings window
+    pub fn label(&self) -> &'static str {
+        match self {
+            ThemePreference::System => "System",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Name used for the autostart entry on every platform
+const APP_NAME: &str = "Retrosave";
+
+/// Register or unregister Retrosave to start when the user logs in.
+///
+/// - Linux: `~/.config/autostart/retrosave.desktop`
+/// - Windows: `Retrosave` value under `HKCU\Software\Microsoft\Windows\CurrentVersion\Run`
+/// - macOS: `~/Library/LaunchAgents/cloud.retrosave.client.plist`
+///
+/// Enabling again rewrites the entry, so it picks up a moved executable.
+/// Disabling doesn't look at the executable, so it still works after the
+/// binary was moved or replaced.
+pub fn set_start_on_boot(enabled: bool) -> Result<()> {
+    if enabled {
+        let exe = current_exe()?;
+        enable(&exe)?;
+        info!("Registered Retrosave to start on boot: {}", exe.display());
+    } else {
+        disable()?;
+        info!("Removed Retrosave from startup");
+    }
+    
+    Ok(())
+}
+
+/// Path of the running executable with symlinks resolved
+fn current_exe() -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("Could not determine the Retrosave executable path")?;
+    // AppImages run from a temporary mount, the stable path is in $APPIMAGE
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    Ok(exe.canonicalize().unwrap_or(exe))
+}
+
+#[cfg(target_os = "linux")]
+fn autostart_file() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir().context("Could not find the config directory")?;
+    Ok(config_dir.join("autostart").join("retrosave.desktop"))
+}
+
+#[cfg(target_os = "linux")]
+fn enable(exe: &Path) -> Result<()> {
+    let path = autostart_file()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&path, desktop_entry(exe))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(target_os = "linux")]
+fn disable() -> Result<()> {
+    let path = autostart_file()?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+    }
+}
+
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> Result<std::process::Output> {
+    use std::os::windows::process::CommandExt;
+    // Don't flash a console window
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    
+    std::process::Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .context("Failed to run reg.exe")
+}
+
+#[cfg(windows)]
+fn enable(exe: &Path) -> Result<()> {
+    let command = format!("\"{}\"", exe.display());
+    let output = reg(&["add", RUN_KEY, "/v", APP_NAME, "/t", "REG_SZ", "/d", &command, "/f"])?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to add startup registry value: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn disable() -> Result<()> {
+    // Nothing to remove if the value was never added
+    if !reg(&["query", RUN_KEY, "/v", APP_NAME])?.status.success() {
+        return Ok(());
+    }
+    let output = reg(&["delete", RUN_KEY, "/v", APP_NAME, "/f"])?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to remove startup registry value: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn launch_agent_file() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not find the home directory")?;
+    Ok(home.join("Library").join("LaunchAgents").join("cloud.retrosave.client.plist"))
+}
+
+#[cfg(target_os = "macos")]
+fn enable(exe: &Path) -> Result<()> {
+    let path = launch_agent_file()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&path, launch_agent_plist(exe))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(target_os = "macos")]
+fn disable() -> Result<()> {
+    let path = launch_agent_file()?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove {}", path.display())),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn enable(_exe: &Path) -> Result<()> {
+    anyhow::bail!("Starting on boot is not supported on this platform")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn disable() -> Result<()> {
+    Ok(())
+}
+
+/// XDG autostart entry launching `exe`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn desktop_entry(exe: &Path) -> String {
+    // Exec arguments are quoted, with ", `, $ and \ escaped inside the quotes
+    // and % doubled so it isn't read as a field code
+    let mut exec = String::from("\"");
+    for c in exe.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                exec.push('\\');
+                exec.push(c);
+            }
+            '%' => exec.push_str("%%"),
+            _ => exec.push(c),
+        }
+    }
+    exec.push('"');
+    // The key's value is a string too, which escapes every backslash once more
+    let exec = exec.replace('\\', "\\\\");
+    
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={}\n\
+         Comment=Automatic save management for retro game emulators\n\
+         Exec={}\n\
+         Icon=retrosave\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        APP_NAME, exec
+    )
+}
+
+/// LaunchAgent that runs `exe` at login
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launch_agent_plist(exe: &Path) -> String {
+    let program = exe.to_string_lossy()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>cloud.retrosave.client</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        program
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_desktop_entry_quotes_exec() {
+        let entry = desktop_entry(Path::new("/opt/My Apps/retro$ave"));
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("Exec=\"/opt/My Apps/retro\\\\$ave\"\n"));
+        assert!(entry.contains("Name=Retrosave\n"));
+    }
+    
+    #[test]
+    fn test_desktop_entry_escapes_backslashes_and_percent() {
+        let entry = desktop_entry(Path::new("/opt/100%\\retro/retrosave"));
+        assert!(entry.contains(r#"Exec="/opt/100%%\\\\retro/retrosave""#));
+    }
+    
+    #[test]
+    fn test_launch_agent_escapes_path() {
+        let plist = launch_agent_plist(Path::new("/Applications/R&D/Retrosave.app/Contents/MacOS/retrosave"));
+        assert!(plist.contains("<string>/Applications/R&amp;D/Retrosave.app/Contents/MacOS/retrosave</string>"));
+        assert!(plist.contains("<key>RunAtLoad</key>"));
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{Result, Context};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Temp file next to `path` for writing its replacement. It has to be on the
+/// same filesystem for the rename to be atomic, and the `.tmp` suffix keeps the
+/// save watcher from picking it up.
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "save".to_string());
+    path.with_file_name(format!(".{}.retrosave.tmp", file_name))
+}
+
+/// Write `data` to `path` so that a crash leaves either the old or the new
+/// contents, never a truncated file
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let temp_path = temp_path_for(path);
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .with_context(|| format!("Failed to write {:?}", temp_path));
+    
+    if let Err(e) = result {
+        std::fs::remove_file(&temp_path).ok();
+        return Err(e);
+    }
+    
+    replace_file(&temp_path, path)
+}
+
+/// Copy `source` over `dest` atomically
+pub fn copy_atomic(source: &Path, dest: &Path) -> Result<()> {
+    let temp_path = temp_path_for(dest);
+    if let Err(e) = std::fs::copy(source, &temp_path) {
+        std::fs::remove_file(&temp_path).ok();
+        return Err(e).with_context(|| format!("Failed to copy {:?}", source));
+    }
+    
+    replace_file(&temp_path, dest)
+}
+
+/// Move a fully written temp file into place. The temp file is removed if it
+/// can't be.
+pub fn replace_file(temp_path: &Path, dest: &Path) -> Result<()> {
+    // Make sure the data is on disk before the rename makes it visible
+    if let Ok(file) = File::open(temp_path) {
+        file.sync_all().ok();
+    }
+    
+    let result = rename_over(temp_path, dest);
+    if result.is_err() {
+        std::fs::remove_file(temp_path).ok();
+    }
+    result.with_context(|| format!("Failed to move {:?} into place", dest))?;
+    
+    debug!("Atomically replaced {:?}", dest);
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn rename_over(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::rename(from, to)
+}
+
+#[cfg(windows)]
+fn rename_over(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        // Replacing can fail for read-only targets or older filesystems; fall
+        // back to removing the target first. The window where neither file is
+        // in place is tiny, and the old contents are still in the backups.
+        Err(e) if to.exists() && matches!(
+            e.kind(),
+            std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::AlreadyExists
+        ) => {
+            let mut permissions = std::fs::metadata(to)?.permissions();
+            if permissions.readonly() {
+                #[allow(clippy::permissions_set_readonly_false)]
+                permissions.set_readonly(false);
+                std::fs::set_permissions(to, permissions)?;
+            }
+            std::fs::remove_file(to)?;
+            std::fs::rename(from, to)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    #[test]
+    fn test_write_atomic_replaces_contents() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("Mcd001.ps2");
+        std::fs::write(&path, b"old save data").unwrap();
+        
+        write_atomic(&path, b"new").unwrap();
+        
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+        assert!(!temp_path_for(&path).exists());
+    }
+    
+    #[test]
+    fn test_copy_atomic_creates_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("backup.bak");
+        let dest = temp_dir.path().join("Mcd001.ps2");
+        std::fs::write(&source, b"backup").unwrap();
+        
+        copy_atomic(&source, &dest).unwrap();
+        
+        assert_eq!(std::fs::read(&dest).unwrap(), b"backup");
+        assert!(!temp_path_for(&dest).exists());
+    }
+    
+    #[test]
+    fn test_temp_path_is_ignored_by_watcher() {
+        let temp_path = temp_path_for(Path::new("/saves/Mcd001.ps2"));
+        assert_eq!(temp_path, Path::new("/saves/.Mcd001.ps2.retrosave.tmp"));
+        assert!(temp_path.to_string_lossy().ends_with(".tmp"));
+    }
+}
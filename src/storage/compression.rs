@@ -19,12 +19,37 @@ pub struct CompressionStats {
 }
 
 impl CompressionStats {
+    /// Bytes saved by compression
+    pub fn space_saved(&self) -> u64 {
+        self.original_size.saturating_sub(self.compressed_size)
+    }
+    
     pub fn space_saved_percent(&self) -> f32 {
         if self.original_size == 0 {
             return 0.0;
         }
-        ((self.original_size - self.compressed_size) as f32 / self.original_size as f32) * 100.0
+        (self.space_saved() as f32 / self.original_size as f32) * 100.0
+    }
+}
+
+/// Size of the data in a .zst file once decompressed. Read from the frame
+/// header; only files without the size in it are decompressed to count.
+pub fn decompressed_size(path: &Path) -> Result<u64> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    
+    // A frame header is at most 18 bytes
+    let mut header = Vec::with_capacity(18);
+    (&mut file).take(18).read_to_end(&mut header)
+        .with_context(|| format!("Failed to read {:?}", path))?;
+    if let Ok(Some(size)) = zstd::zstd_safe::get_frame_content_size(&header) {
+        return Ok(size);
     }
+    
+    let mut decoder = zstd::stream::read::Decoder::new(header.as_slice().chain(file))
+        .context("Failed to create decoder")?;
+    std::io::copy(&mut decoder, &mut std::io::sink())
+        .with_context(|| format!("Failed to decompress {:?}", path))
 }
 
 pub struct Compressor {
@@ -90,8 +115,9 @@ impl Compressor {
         
         let original_size = source_data.len() as u64;
         
-        // Compress data
-        let compressed_data = encode_all(&source_data[..], self.compression_level)
+        // Compress data. Unlike streaming, this puts the original size in the
+        // frame header, so `decompressed_size` doesn't have to decompress
+        let compressed_data = zstd::bulk::compress(&source_data, self.compression_level)
             .context("Failed to compress data")?;
         
         let compressed_size = compressed_data.len() as u64;
@@ -188,8 +214,37 @@ impl Compressor {
     }
 }
 
+/// Levels tried by `benchmark_levels`, from fastest to smallest
+pub const BENCHMARK_LEVELS: &[i32] = &[1, 3, 6, 9, 12, 15, 19, 22];
+
+/// Compress `data` at each of `levels`, timing each run
+pub fn benchmark_levels(data: &[u8], levels: &[i32]) -> Result<Vec<(i32, CompressionStats)>> {
+    let original_size = data.len() as u64;
+    levels.iter().map(|&level| {
+        let compressor = Compressor::new(level, true);
+        let start = std::time::Instant::now();
+        let compressed = compressor.compress_data(data)?;
+        let compression_time_ms = start.elapsed().as_millis();
+        
+        let compressed_size = compressed.len() as u64;
+        let compression_ratio = if original_size == 0 {
+            1.0
+        } else {
+            compressed_size as f32 / original_size as f32
+        };
+        debug!("Level {}: {} -> {} in {}ms", level, format_size(original_size), format_size(compressed_size), compression_time_ms);
+        
+        Ok((compressor.compression_level, CompressionStats {
+            original_size,
+            compressed_size,
+            compression_ratio,
+            compression_time_ms,
+        }))
+    }).collect()
+}
+
 /// Format bytes as human-readable size
-fn format_size(bytes: u64) -> String {
+pub fn format_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
     let mut size = bytes as f64;
     let mut unit_index = 0;
@@ -239,6 +294,23 @@ mod tests {
         assert_eq!(restored_content, test_content);
     }
     
+    #[test]
+    fn test_decompressed_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("test.txt");
+        let compressed_path = temp_dir.path().join("test.txt.zst");
+        let streamed_path = temp_dir.path().join("streamed.txt.zst");
+        
+        let test_content = "Retrosave saves your saves! ".repeat(1000);
+        fs::write(&source_path, &test_content).unwrap();
+        Compressor::default().compress_file(&source_path, &compressed_path).unwrap();
+        assert_eq!(decompressed_size(&compressed_path).unwrap(), test_content.len() as u64);
+        
+        // Backups written before the size was in the header are decompressed instead
+        fs::write(&streamed_path, encode_all(test_content.as_bytes(), 3).unwrap()).unwrap();
+        assert_eq!(decompressed_size(&streamed_path).unwrap(), test_content.len() as u64);
+    }
+    
     #[test]
     fn test_compress_with_different_levels() {
         let temp_dir = TempDir::new().unwrap();
@@ -302,6 +374,19 @@ mod tests {
         assert_eq!(decompressed, data);
     }
     
+    #[test]
+    fn test_benchmark_levels() {
+        let data = b"Memory card block ".repeat(2000);
+        
+        let results = benchmark_levels(&data, &[1, 40]).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].0, 22); // Clamped like the slider
+        for (_, stats) in results {
+            assert_eq!(stats.original_size, data.len() as u64);
+            assert!(stats.compressed_size < stats.original_size);
+        }
+    }
+    
     #[test]
     fn test_format_size() {
         assert_eq!(format_size(512), "512 B");
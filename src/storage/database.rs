@@ -1,9 +1,10 @@
 use anyhow::{Result, Context};
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions, Row};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
 use tracing::{info, debug};
 use serde::{Serialize, Deserialize};
+use crate::storage::save_types::SaveType;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Game {
@@ -14,6 +15,7 @@ pub struct Game {
     pub path: Option<String>,
     pub last_played: Option<DateTime<Utc>>,
     pub total_saves: i32,
+    pub sync_enabled: bool,  // Whether saves for this game are uploaded to the cloud
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -26,6 +28,22 @@ pub struct Save {
     pub file_size: i64,
     pub version: i32,
     pub backup_path: Option<String>,
+    pub pinned: bool,  // Pinned versions are never removed by cleanup
+    pub note: Option<String>,  // Short user annotation, e.g. "before final boss"
+}
+
+/// A downloaded save that can't be written while its emulator has the file open.
+/// It's recorded as a save version once written.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeferredWrite {
+    pub file_path: String,
+    pub emulator: String,
+    pub game_id: i64,
+    pub game_name: String,
+    pub file_hash: String,
+    pub file_size: i64,
+    pub cloud_save_id: String,
+    pub data: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +57,189 @@ pub struct Database {
     db_path: PathBuf,
 }
 
+/// A single schema upgrade step
+struct Migration {
+    version: i64,
+    description: &'static str,
+    statements: &'static [&'static str],
+}
+
+/// Ordered list of schema migrations. Append new steps with the next
+/// version number; never edit or reorder a step that has shipped.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS games (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                emulator TEXT NOT NULL,
+                game_id TEXT,  -- PS2: SLES-12345, PSP: ULUS10041, etc.
+                path TEXT,
+                last_played DATETIME,
+                total_saves INTEGER DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(name, emulator)
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS saves (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                game_id INTEGER NOT NULL,
+                timestamp DATETIME NOT NULL,
+                file_path TEXT NOT NULL,
+                file_hash TEXT NOT NULL,
+                file_size INTEGER NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                backup_path TEXT,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_saves_game_id ON saves(game_id)",
+            "CREATE INDEX IF NOT EXISTS idx_saves_timestamp ON saves(timestamp)",
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "per-game sync toggle",
+        statements: &[
+            "ALTER TABLE games ADD COLUMN sync_enabled INTEGER NOT NULL DEFAULT 1",
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "pinned save versions",
+        statements: &[
+            "ALTER TABLE saves ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0",
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "save version notes",
+        statements: &[
+            "ALTER TABLE saves ADD COLUMN note TEXT",
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "cloud profiles",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS profiles (
+                name TEXT PRIMARY KEY,
+                active INTEGER NOT NULL DEFAULT 0,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+            "INSERT OR IGNORE INTO profiles (name, active) VALUES ('default', 1)",
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "stable identity for unidentified games",
+        statements: &[
+            "ALTER TABLE games ADD COLUMN stable_id TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_games_stable_id ON games(emulator, stable_id)",
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "previous names of renamed and merged games",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS game_aliases (
+                name TEXT NOT NULL,
+                emulator TEXT NOT NULL,
+                game_id INTEGER NOT NULL,
+                PRIMARY KEY (name, emulator),
+                FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 8,
+        description: "save files deleted locally",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS deleted_save_files (
+                file_path TEXT PRIMARY KEY,
+                deleted_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        ],
+    },
+    Migration {
+        version: 9,
+        description: "game search index",
+        statements: &[
+            // Databases that had the index before it was a migration keep theirs
+            "CREATE VIRTUAL TABLE IF NOT EXISTS games_fts USING fts5(name, emulator, content='games', content_rowid='id')",
+            "CREATE TRIGGER IF NOT EXISTS games_fts_insert AFTER INSERT ON games BEGIN
+                INSERT INTO games_fts(rowid, name, emulator) VALUES (new.id, new.name, new.emulator);
+             END",
+            "CREATE TRIGGER IF NOT EXISTS games_fts_delete AFTER DELETE ON games BEGIN
+                INSERT INTO games_fts(games_fts, rowid, name, emulator) VALUES ('delete', old.id, old.name, old.emulator);
+             END",
+            "CREATE TRIGGER IF NOT EXISTS games_fts_update AFTER UPDATE OF name, emulator ON games BEGIN
+                INSERT INTO games_fts(games_fts, rowid, name, emulator) VALUES ('delete', old.id, old.name, old.emulator);
+                INSERT INTO games_fts(rowid, name, emulator) VALUES (new.id, new.name, new.emulator);
+             END",
+            "INSERT INTO games_fts(games_fts) VALUES ('rebuild')",
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "cloud IDs of synced saves",
+        statements: &[
+            "ALTER TABLE saves ADD COLUMN cloud_save_id TEXT",
+            "CREATE INDEX IF NOT EXISTS idx_saves_file_hash ON saves(game_id, file_hash)",
+        ],
+    },
+    Migration {
+        version: 11,
+        description: "note edits waiting to sync",
+        statements: &[
+            "ALTER TABLE saves ADD COLUMN note_synced INTEGER NOT NULL DEFAULT 1",
+            // Notes written before they synced on their own
+            "UPDATE saves SET note_synced = 0 WHERE note IS NOT NULL",
+        ],
+    },
+    Migration {
+        version: 12,
+        description: "downloads held back while their emulator runs",
+        statements: &[
+            r#"
+            CREATE TABLE IF NOT EXISTS deferred_writes (
+                file_path TEXT PRIMARY KEY,
+                emulator TEXT NOT NULL,
+                game_id INTEGER NOT NULL,
+                game_name TEXT NOT NULL,
+                file_hash TEXT NOT NULL,
+                file_size INTEGER NOT NULL,
+                cloud_save_id TEXT NOT NULL,
+                data BLOB NOT NULL,
+                FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+            )
+            "#,
+        ],
+    },
+];
+
+/// Profile every installation starts with. Its tokens and device id use the
+/// storage keys from before profiles existed.
+pub const DEFAULT_PROFILE: &str = "default";
+
 impl Database {
     /// Create a new database connection
     pub async fn new(db_path: Option<PathBuf>) -> Result<Self> {
@@ -76,95 +277,128 @@ impl Database {
     }
 
     /// Run database migrations
+    ///
+    /// Applies every step in [`MIGRATIONS`] newer than the recorded schema
+    /// version, each in its own transaction.
     async fn migrate(&self) -> Result<()> {
         info!("Running database migrations");
 
-        // Create games table
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS games (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                emulator TEXT NOT NULL,
-                game_id TEXT,  -- PS2: SLES-12345, PSP: ULUS10041, etc.
-                path TEXT,
-                last_played DATETIME,
-                total_saves INTEGER DEFAULT 0,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                UNIQUE(name, emulator)
+            CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )
             "#
         )
         .execute(&self.pool)
         .await?;
 
-        // Create saves table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS saves (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                game_id INTEGER NOT NULL,
-                timestamp DATETIME NOT NULL,
-                file_path TEXT NOT NULL,
-                file_hash TEXT NOT NULL,
-                file_size INTEGER NOT NULL,
-                version INTEGER NOT NULL DEFAULT 1,
-                backup_path TEXT,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
-            )
-            "#
+        let current_version = self.schema_version().await?;
+        if current_version == 0 {
+            self.upgrade_legacy_schema().await?;
+        }
+
+        for migration in MIGRATIONS.iter().filter(|m| m.version > current_version) {
+            info!("Applying database migration {}: {}", migration.version, migration.description);
+
+            let mut tx = self.pool.begin().await?;
+            for statement in migration.statements {
+                sqlx::query(statement)
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Database migration {} failed", migration.version))?;
+            }
+            sqlx::query("INSERT INTO schema_version (version, description) VALUES (?, ?)")
+                .bind(migration.version)
+                .bind(migration.description)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+        }
+
+        debug!("Database migrations completed (schema version {})", self.schema_version().await?);
+        Ok(())
+    }
+
+    /// Bring databases created before schema versioning up to the baseline
+    /// shape, so the baseline migration can run against them unchanged
+    async fn upgrade_legacy_schema(&self) -> Result<()> {
+        let has_games: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'games'"
         )
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
-        
-        // Add game_id column if it doesn't exist (migration for existing DBs)
-        let _ = sqlx::query("ALTER TABLE games ADD COLUMN game_id TEXT")
-            .execute(&self.pool)
-            .await; // Ignore error if column already exists
 
-        // Create settings table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS settings (
-                key TEXT PRIMARY KEY,
-                value TEXT NOT NULL,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )
-            "#
+        if has_games == 0 {
+            return Ok(());
+        }
+
+        let has_game_id: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('games') WHERE name = 'game_id'"
         )
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
 
-        // Create indexes for better performance
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_saves_game_id ON saves(game_id)")
-            .execute(&self.pool)
-            .await?;
+        if has_game_id == 0 {
+            info!("Adding game_id column to legacy games table");
+            sqlx::query("ALTER TABLE games ADD COLUMN game_id TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_saves_timestamp ON saves(timestamp)")
-            .execute(&self.pool)
+        Ok(())
+    }
+
+    /// Get the current schema version (0 if no migrations have been applied)
+    pub async fn schema_version(&self) -> Result<i64> {
+        let version: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+            .fetch_one(&self.pool)
             .await?;
 
-        debug!("Database migrations completed");
-        Ok(())
+        Ok(version)
     }
 
     /// Get or create a game entry
     pub async fn get_or_create_game(&self, name: &str, emulator: &str) -> Result<Game> {
-        self.get_or_create_game_with_id(name, emulator, None).await
+        self.get_or_create_game_with_id(name, emulator, None, None).await
     }
     
-    pub async fn get_or_create_game_with_id(&self, name: &str, emulator: &str, game_id: Option<&str>) -> Result<Game> {
+    /// Get or create a game entry. A game whose title couldn't be detected is
+    /// found by `stable_id` instead of its placeholder name, so unidentified
+    /// games don't share one record; see `name_unknown_game`.
+    pub async fn get_or_create_game_with_id(
+        &self,
+        name: &str,
+        emulator: &str,
+        game_id: Option<&str>,
+        stable_id: Option<&str>,
+    ) -> Result<Game> {
+        if let Some(stable_id) = stable_id {
+            let existing = sqlx::query_as::<_, (i64, String, String, Option<String>, Option<String>, Option<DateTime<Utc>>, i32, bool)>(
+                "SELECT id, name, emulator, game_id, path, last_played, total_saves, sync_enabled FROM games WHERE emulator = ? AND stable_id = ?"
+            )
+            .bind(emulator)
+            .bind(stable_id)
+            .fetch_optional(&self.pool)
+            .await?;
+            
+            if let Some((id, name, emulator, game_id, path, last_played, total_saves, sync_enabled)) = existing {
+                return Ok(Game { id, name, emulator, game_id, path, last_played, total_saves, sync_enabled });
+            }
+        }
+        
         // Try to get existing game
-        let existing = sqlx::query_as::<_, (i64, String, String, Option<String>, Option<String>, Option<DateTime<Utc>>, i32)>(
-            "SELECT id, name, emulator, game_id, path, last_played, total_saves FROM games WHERE name = ? AND emulator = ?"
+        let existing = sqlx::query_as::<_, (i64, String, String, Option<String>, Option<String>, Option<DateTime<Utc>>, i32, bool)>(
+            "SELECT id, name, emulator, game_id, path, last_played, total_saves, sync_enabled FROM games WHERE name = ? AND emulator = ?"
         )
         .bind(name)
         .bind(emulator)
         .fetch_optional(&self.pool)
         .await?;
 
-        if let Some((id, name, emulator, existing_game_id, path, last_played, total_saves)) = existing {
+        if let Some((id, name, emulator, existing_game_id, path, last_played, total_saves, sync_enabled)) = existing {
             // Update game_id if provided and not already set
             let final_game_id = if existing_game_id.is_none() && game_id.is_some() {
                 sqlx::query("UPDATE games SET game_id = ? WHERE id = ?")
@@ -185,19 +419,49 @@ impl Database {
                 path,
                 last_played,
                 total_saves,
+                sync_enabled,
             });
         }
 
-        // Create new game
-        let id = sqlx::query(
-            "INSERT INTO games (name, emulator, game_id) VALUES (?, ?, ?)"
+        // A game that was renamed or merged away is still detected by its old name
+        let aliased = sqlx::query_as::<_, (i64, String, String, Option<String>, Option<String>, Option<DateTime<Utc>>, i32, bool)>(
+            "SELECT g.id, g.name, g.emulator, g.game_id, g.path, g.last_played, g.total_saves, g.sync_enabled
+             FROM games g JOIN game_aliases a ON a.game_id = g.id WHERE a.name = ? AND a.emulator = ?"
+        )
+        .bind(name)
+        .bind(emulator)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some((id, name, emulator, game_id, path, last_played, total_saves, sync_enabled)) = aliased {
+            return Ok(Game { id, name, emulator, game_id, path, last_played, total_saves, sync_enabled });
+        }
+
+        // Create new game. Parallel downloads can create the same one at once,
+        // the later insert then returns the game the first one created.
+        let inserted = sqlx::query(
+            "INSERT INTO games (name, emulator, game_id, stable_id) VALUES (?, ?, ?, ?)
+             ON CONFLICT(name, emulator) DO NOTHING"
         )
         .bind(name)
         .bind(emulator)
         .bind(game_id)
+        .bind(stable_id)
         .execute(&self.pool)
-        .await?
-        .last_insert_rowid();
+        .await?;
+
+        if inserted.rows_affected() == 0 {
+            let (id, name, emulator, game_id, path, last_played, total_saves, sync_enabled) =
+                sqlx::query_as::<_, (i64, String, String, Option<String>, Option<String>, Option<DateTime<Utc>>, i32, bool)>(
+                    "SELECT id, name, emulator, game_id, path, last_played, total_saves, sync_enabled FROM games WHERE name = ? AND emulator = ?"
+                )
+                .bind(name)
+                .bind(emulator)
+                .fetch_one(&self.pool)
+                .await?;
+            return Ok(Game { id, name, emulator, game_id, path, last_played, total_saves, sync_enabled });
+        }
+        let id = inserted.last_insert_rowid();
 
         info!("Created new game: {} ({}) with ID: {:?}", name, emulator, game_id);
 
@@ -209,9 +473,173 @@ impl Database {
             path: None,
             last_played: None,
             total_saves: 0,
+            sync_enabled: true,
         })
     }
 
+    /// Give the unidentified game known by `stable_id` its real name once it's
+    /// detected. Returns false if there's no such game, or the name already
+    /// belongs to another game, in which case both are left alone.
+    pub async fn name_unknown_game(&self, emulator: &str, stable_id: &str, name: &str) -> Result<bool> {
+        let unnamed = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM games WHERE emulator = ? AND stable_id = ?"
+        )
+        .bind(emulator)
+        .bind(stable_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(id) = unnamed else {
+            return Ok(false);
+        };
+        
+        let taken = sqlx::query_scalar::<_, i64>("SELECT id FROM games WHERE name = ? AND emulator = ?")
+            .bind(name)
+            .bind(emulator)
+            .fetch_optional(&self.pool)
+            .await?;
+        if taken.is_some() {
+            debug!("Not naming game {}, {} ({}) already exists", id, name, emulator);
+            return Ok(false);
+        }
+        
+        sqlx::query("UPDATE games SET name = ?, stable_id = NULL WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        
+        info!("Named unidentified game {} as {} ({})", id, name, emulator);
+        Ok(true)
+    }
+
+    /// Get a game by its database id
+    pub async fn get_game(&self, game_id: i64) -> Result<Option<Game>> {
+        let game = sqlx::query_as::<_, (i64, String, String, Option<String>, Option<String>, Option<DateTime<Utc>>, i32, bool)>(
+            "SELECT id, name, emulator, game_id, path, last_played, total_saves, sync_enabled FROM games WHERE id = ?"
+        )
+        .bind(game_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|(id, name, emulator, game_id, path, last_played, total_saves, sync_enabled)| {
+            Game { id, name, emulator, game_id, path, last_played, total_saves, sync_enabled }
+        });
+
+        Ok(game)
+    }
+
+    /// Correct the name of a game. The old name is kept as an alias, so saves
+    /// still detected under it land in the renamed game. Fails if another game
+    /// of the same emulator already has the name; merge them instead.
+    pub async fn rename_game(&self, game_id: i64, new_name: &str) -> Result<()> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(anyhow::anyhow!("Game name can't be empty"));
+        }
+        let game = self.get_game(game_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Game {} does not exist", game_id))?;
+        if game.name == new_name {
+            return Ok(());
+        }
+
+        let taken = sqlx::query_scalar::<_, i64>("SELECT id FROM games WHERE name = ? AND emulator = ?")
+            .bind(new_name)
+            .bind(&game.emulator)
+            .fetch_optional(&self.pool)
+            .await?;
+        if taken.is_some() {
+            return Err(anyhow::anyhow!("{} ({}) already exists", new_name, game.emulator));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE games SET name = ? WHERE id = ?")
+            .bind(new_name)
+            .bind(game_id)
+            .execute(&mut *tx)
+            .await?;
+        // The new name is real now, and naming a game back drops its alias
+        sqlx::query("DELETE FROM game_aliases WHERE name = ? AND emulator = ?")
+            .bind(new_name)
+            .bind(&game.emulator)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR REPLACE INTO game_aliases (name, emulator, game_id) VALUES (?, ?, ?)")
+            .bind(&game.name)
+            .bind(&game.emulator)
+            .bind(game_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!("Renamed game {} from {} to {} ({})", game_id, game.name, new_name, game.emulator);
+        Ok(())
+    }
+
+    /// Move every save of `from_id` to `into_id` and delete `from_id`, e.g. when
+    /// one game was recorded under two names. Versions of the merged game are
+    /// renumbered by time, and the removed name becomes an alias of `into_id`.
+    /// Backup files are not moved; see `SaveBackupManager::relink_backup`.
+    pub async fn merge_games(&self, from_id: i64, into_id: i64) -> Result<()> {
+        if from_id == into_id {
+            return Err(anyhow::anyhow!("Can't merge a game into itself"));
+        }
+        let from = self.get_game(from_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Game {} does not exist", from_id))?;
+        let into = self.get_game(into_id).await?
+            .ok_or_else(|| anyhow::anyhow!("Game {} does not exist", into_id))?;
+        if from.emulator != into.emulator {
+            return Err(anyhow::anyhow!("Can't merge a {} game into a {} game", from.emulator, into.emulator));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE saves SET game_id = ? WHERE game_id = ?")
+            .bind(into_id)
+            .bind(from_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "UPDATE saves SET version = (
+                SELECT COUNT(*) FROM saves s WHERE s.game_id = saves.game_id
+                AND (s.timestamp < saves.timestamp OR (s.timestamp = saves.timestamp AND s.id <= saves.id))
+             ) WHERE game_id = ?"
+        )
+        .bind(into_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "UPDATE games SET
+                total_saves = total_saves + ?,
+                last_played = ?,
+                game_id = COALESCE(game_id, ?),
+                stable_id = COALESCE(stable_id, (SELECT stable_id FROM games WHERE id = ?))
+             WHERE id = ?"
+        )
+        .bind(from.total_saves)
+        .bind(from.last_played.max(into.last_played))
+        .bind(&from.game_id)
+        .bind(from_id)
+        .bind(into_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("UPDATE game_aliases SET game_id = ? WHERE game_id = ?")
+            .bind(into_id)
+            .bind(from_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM games WHERE id = ?")
+            .bind(from_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR REPLACE INTO game_aliases (name, emulator, game_id) VALUES (?, ?, ?)")
+            .bind(&from.name)
+            .bind(&from.emulator)
+            .bind(into_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!("Merged game {} ({}) into {} ({})", from.name, from_id, into.name, into_id);
+        Ok(())
+    }
 
     /// Record a new save
     pub async fn record_save(
@@ -222,6 +650,14 @@ impl Database {
         file_size: i64,
         backup_path: Option<&str>,
     ) -> Result<Save> {
+        // Don't create a new version if the content hasn't changed
+        if let Some(latest) = self.get_latest_save(game_id).await? {
+            if latest.file_hash == file_hash {
+                debug!("Save for game {} unchanged (hash {}), keeping version {}", game_id, file_hash, latest.version);
+                return Ok(latest);
+            }
+        }
+
         // Get the next version number for this game
         let version: i32 = sqlx::query_scalar(
             "SELECT COALESCE(MAX(version), 0) + 1 FROM saves WHERE game_id = ?"
@@ -269,6 +705,8 @@ impl Database {
             file_size,
             version,
             backup_path: backup_path.map(|s| s.to_string()),
+            pinned: false,
+            note: None,
         })
     }
 
@@ -276,12 +714,12 @@ impl Database {
     pub async fn get_saves_for_game(&self, game_id: i64, limit: Option<i32>) -> Result<Vec<Save>> {
         let query = if let Some(limit) = limit {
             format!(
-                "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path 
+                "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path, pinned, note 
                  FROM saves WHERE game_id = ? ORDER BY timestamp DESC LIMIT {}",
                 limit
             )
         } else {
-            "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path 
+            "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path, pinned, note 
              FROM saves WHERE game_id = ? ORDER BY timestamp DESC".to_string()
         };
 
@@ -299,26 +737,23 @@ impl Database {
                 file_size: row.get(5),
                 version: row.get(6),
                 backup_path: row.get(7),
+                pinned: row.get(8),
+                note: row.get(9),
             })
             .collect();
 
         Ok(saves)
     }
 
-    /// Clean up old saves, keeping only the last N saves for a game
-    pub async fn cleanup_old_saves(&self, game_id: i64, keep_count: i32) -> Result<Vec<Save>> {
-        // Get saves to delete (older than keep_count)
-        let saves_to_delete = sqlx::query(&format!(
-            "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path 
-             FROM saves WHERE game_id = ? 
-             ORDER BY timestamp DESC 
-             LIMIT -1 OFFSET {}",
-            keep_count
-        ))
+    /// Get the most recently recorded save for a game
+    pub async fn get_latest_save(&self, game_id: i64) -> Result<Option<Save>> {
+        let save = sqlx::query(
+            "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path, pinned, note 
+             FROM saves WHERE game_id = ? ORDER BY timestamp DESC, id DESC LIMIT 1"
+        )
         .bind(game_id)
-        .fetch_all(&self.pool)
+        .fetch_optional(&self.pool)
         .await?
-        .into_iter()
         .map(|row| Save {
             id: row.get(0),
             game_id: row.get(1),
@@ -328,72 +763,509 @@ impl Database {
             file_size: row.get(5),
             version: row.get(6),
             backup_path: row.get(7),
-        })
-        .collect::<Vec<_>>();
-
-        if !saves_to_delete.is_empty() {
-            // Delete from database
-            let ids: Vec<i64> = saves_to_delete.iter().map(|s| s.id).collect();
-            let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
-            let query = format!("DELETE FROM saves WHERE id IN ({})", placeholders);
-            
-            let mut q = sqlx::query(&query);
-            for id in &ids {
-                q = q.bind(id);
-            }
-            q.execute(&self.pool).await?;
-
-            info!("Cleaned up {} old saves for game {}", saves_to_delete.len(), game_id);
-        }
+            pinned: row.get(8),
+            note: row.get(9),
+        });
 
-        Ok(saves_to_delete)
+        Ok(save)
     }
 
-    /// Get all games
-    pub async fn get_all_games(&self) -> Result<Vec<Game>> {
-        let games = sqlx::query(
-            "SELECT id, name, emulator, game_id, path, last_played, total_saves FROM games ORDER BY last_played DESC"
-        )
-        .fetch_all(&self.pool)
-        .await?
-        .into_iter()
-        .map(|row| Game {
-            id: row.get(0),
-            name: row.get(1),
-            emulator: row.get(2),
-            game_id: row.get(3),
-            path: row.get(4),
-            last_played: row.get(5),
-            total_saves: row.get(6),
-        })
-        .collect();
-
-        Ok(games)
+    /// Check whether a hash matches the latest recorded save for a game,
+    /// i.e. recording it would only create a redundant version
+    pub async fn is_duplicate_save(&self, game_id: i64, file_hash: &str) -> Result<bool> {
+        Ok(self.get_latest_save(game_id).await?
+            .is_some_and(|save| save.file_hash == file_hash))
     }
 
-    /// Get a setting value
-    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
-        let value = sqlx::query_scalar::<_, String>(
-            "SELECT value FROM settings WHERE key = ?"
+    /// Get the hash of the most recently recorded save for a file path
+    pub async fn get_latest_hash_for_path(&self, file_path: &str) -> Result<Option<String>> {
+        let hash = sqlx::query_scalar::<_, String>(
+            "SELECT file_hash FROM saves WHERE file_path = ? ORDER BY timestamp DESC, id DESC LIMIT 1"
         )
-        .bind(key)
+        .bind(file_path)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(value)
+        Ok(hash)
     }
 
-    /// Set a setting value
-    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
-        sqlx::query(
-            "INSERT INTO settings (key, value) VALUES (?, ?)
-             ON CONFLICT(key) DO UPDATE SET value = ?, updated_at = CURRENT_TIMESTAMP"
-        )
-        .bind(key)
-        .bind(value)
-        .bind(value)
-        .execute(&self.pool)
-        .await?;
+    /// Remember where the backup of a save version was written
+    pub async fn set_save_backup_path(&self, save_id: i64, backup_path: &str) -> Result<()> {
+        sqlx::query("UPDATE saves SET backup_path = ? WHERE id = ?")
+            .bind(backup_path)
+            .bind(save_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete a single save version, returning it if it existed
+    pub async fn delete_save(&self, save_id: i64) -> Result<Option<Save>> {
+        let save = sqlx::query(
+            "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path, pinned, note 
+             FROM saves WHERE id = ?"
+        )
+        .bind(save_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|row| Save {
+            id: row.get(0),
+            game_id: row.get(1),
+            timestamp: row.get(2),
+            file_path: row.get(3),
+            file_hash: row.get(4),
+            file_size: row.get(5),
+            version: row.get(6),
+            backup_path: row.get(7),
+            pinned: row.get(8),
+            note: row.get(9),
+        });
+
+        if save.is_some() {
+            sqlx::query("DELETE FROM saves WHERE id = ?")
+                .bind(save_id)
+                .execute(&self.pool)
+                .await?;
+            debug!("Deleted save {}", save_id);
+        }
+
+        Ok(save)
+    }
+
+    /// Mark a save version as kept forever, or let retention delete it again
+    pub async fn set_save_pinned(&self, save_id: i64, pinned: bool) -> Result<()> {
+        sqlx::query("UPDATE saves SET pinned = ? WHERE id = ?")
+            .bind(pinned)
+            .bind(save_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Set the note of a save version. An empty note removes it. The change
+    /// is pushed to the cloud on the next sync.
+    pub async fn set_save_note(&self, save_id: i64, note: &str) -> Result<()> {
+        let note = note.trim();
+        sqlx::query("UPDATE saves SET note = ?, note_synced = 0 WHERE id = ?")
+            .bind((!note.is_empty()).then_some(note))
+            .bind(save_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the note of the most recent version with this content, if any
+    pub async fn get_save_note_by_hash(&self, file_hash: &str) -> Result<Option<String>> {
+        let note = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT note FROM saves WHERE file_hash = ? ORDER BY timestamp DESC, id DESC LIMIT 1"
+        )
+        .bind(file_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(note.flatten())
+    }
+
+    /// Remember the cloud save holding this game's version with `file_hash`,
+    /// so it isn't uploaded again
+    pub async fn set_cloud_save_id(&self, game_id: i64, file_hash: &str, cloud_save_id: &str) -> Result<()> {
+        sqlx::query("UPDATE saves SET cloud_save_id = ? WHERE game_id = ? AND file_hash = ?")
+            .bind(cloud_save_id)
+            .bind(game_id)
+            .bind(file_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The cloud save holding this game's version with `file_hash`, if it was synced
+    pub async fn get_cloud_save_id(&self, game_id: i64, file_hash: &str) -> Result<Option<String>> {
+        let cloud_save_id = sqlx::query_scalar::<_, String>(
+            "SELECT cloud_save_id FROM saves WHERE game_id = ? AND file_hash = ? AND cloud_save_id IS NOT NULL LIMIT 1"
+        )
+        .bind(game_id)
+        .bind(file_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(cloud_save_id)
+    }
+
+    /// Notes edited since the last sync on versions that are in the cloud:
+    /// local save ID, cloud save ID and the note
+    pub async fn get_unsynced_notes(&self) -> Result<Vec<(i64, String, Option<String>)>> {
+        let notes = sqlx::query_as::<_, (i64, String, Option<String>)>(
+            "SELECT id, cloud_save_id, note FROM saves WHERE note_synced = 0 AND cloud_save_id IS NOT NULL"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(notes)
+    }
+
+    /// Mark a note as synced, unless it was edited again since `note` was sent
+    pub async fn mark_note_synced(&self, save_id: i64, note: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE saves SET note_synced = 1 WHERE id = ? AND note IS ?")
+            .bind(save_id)
+            .bind(note)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Take the note another device gave a cloud save. Local edits that haven't
+    /// synced yet win. Returns whether a note changed.
+    pub async fn apply_cloud_note(&self, cloud_save_id: &str, note: Option<&str>) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE saves SET note = ? WHERE cloud_save_id = ? AND note_synced = 1 AND note IS NOT ?"
+        )
+        .bind(note)
+        .bind(cloud_save_id)
+        .bind(note)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remember that the user deleted a save file, so older cloud saves
+    /// aren't restored over the deletion
+    pub async fn mark_save_file_deleted(&self, file_path: &str) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO deleted_save_files (file_path, deleted_at) VALUES (?, ?)")
+            .bind(file_path)
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Forget a deletion once the save file is back
+    pub async fn clear_save_file_deleted(&self, file_path: &str) -> Result<()> {
+        sqlx::query("DELETE FROM deleted_save_files WHERE file_path = ?")
+            .bind(file_path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// When the user deleted a save file, `None` if it wasn't deleted or is back
+    pub async fn save_file_deleted_at(&self, file_path: &str) -> Result<Option<DateTime<Utc>>> {
+        let deleted_at = sqlx::query_scalar::<_, DateTime<Utc>>("SELECT deleted_at FROM deleted_save_files WHERE file_path = ?")
+            .bind(file_path)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(deleted_at)
+    }
+
+    /// Hold a download until its emulator stops, replacing an older one for the same file
+    pub async fn defer_write(&self, write: &DeferredWrite) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO deferred_writes (file_path, emulator, game_id, game_name, file_hash, file_size, cloud_save_id, data)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&write.file_path)
+        .bind(&write.emulator)
+        .bind(write.game_id)
+        .bind(&write.game_name)
+        .bind(&write.file_hash)
+        .bind(write.file_size)
+        .bind(&write.cloud_save_id)
+        .bind(&write.data)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Downloads held back until their emulator stops
+    pub async fn get_deferred_writes(&self) -> Result<Vec<DeferredWrite>> {
+        let writes = sqlx::query_as::<_, DeferredWrite>("SELECT * FROM deferred_writes")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(writes)
+    }
+
+    /// Drop the held back download of a file, if any
+    pub async fn remove_deferred_write(&self, file_path: &str) -> Result<()> {
+        sqlx::query("DELETE FROM deferred_writes WHERE file_path = ?")
+            .bind(file_path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Backup files of a game's pinned versions
+    pub async fn get_pinned_backup_paths(&self, game_id: i64) -> Result<Vec<String>> {
+        let paths = sqlx::query_scalar::<_, String>(
+            "SELECT backup_path FROM saves WHERE game_id = ? AND pinned = 1 AND backup_path IS NOT NULL"
+        )
+        .bind(game_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(paths)
+    }
+
+    /// Clean up old saves, keeping only the last N unpinned saves for a game.
+    /// Pinned saves are never deleted and don't count towards N.
+    pub async fn cleanup_old_saves(&self, game_id: i64, keep_count: i32) -> Result<Vec<Save>> {
+        // Get saves to delete (older than keep_count)
+        let saves_to_delete = sqlx::query(&format!(
+            "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path, pinned, note 
+             FROM saves WHERE game_id = ? AND pinned = 0 
+             ORDER BY timestamp DESC 
+             LIMIT -1 OFFSET {}",
+            keep_count
+        ))
+        .bind(game_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| Save {
+            id: row.get(0),
+            game_id: row.get(1),
+            timestamp: row.get(2),
+            file_path: row.get(3),
+            file_hash: row.get(4),
+            file_size: row.get(5),
+            version: row.get(6),
+            backup_path: row.get(7),
+            pinned: row.get(8),
+            note: row.get(9),
+        })
+        .collect::<Vec<_>>();
+
+        self.delete_saves(&saves_to_delete).await?;
+        if !saves_to_delete.is_empty() {
+            info!("Cleaned up {} old saves for game {}", saves_to_delete.len(), game_id);
+        }
+
+        Ok(saves_to_delete)
+    }
+
+    /// Like `cleanup_old_saves`, but save-states and persistent saves (memory
+    /// cards, battery saves) each keep their own number of unpinned versions.
+    pub async fn cleanup_old_saves_by_class(
+        &self,
+        game_id: i64,
+        emulator: &str,
+        keep_saves: usize,
+        keep_save_states: usize,
+    ) -> Result<Vec<Save>> {
+        let saves = sqlx::query(
+            "SELECT id, game_id, timestamp, file_path, file_hash, file_size, version, backup_path, pinned, note 
+             FROM saves WHERE game_id = ? AND pinned = 0 
+             ORDER BY timestamp DESC, id DESC"
+        )
+        .bind(game_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| Save {
+            id: row.get(0),
+            game_id: row.get(1),
+            timestamp: row.get(2),
+            file_path: row.get(3),
+            file_hash: row.get(4),
+            file_size: row.get(5),
+            version: row.get(6),
+            backup_path: row.get(7),
+            pinned: row.get(8),
+            note: row.get(9),
+        });
+
+        let mut kept_saves = 0;
+        let mut kept_save_states = 0;
+        let mut saves_to_delete = Vec::new();
+        for save in saves {
+            let is_state = SaveType::detect(Path::new(&save.file_path), emulator).is_save_state();
+            let (kept, keep) = if is_state {
+                (&mut kept_save_states, keep_save_states)
+            } else {
+                (&mut kept_saves, keep_saves)
+            };
+            
+            if *kept < keep {
+                *kept += 1;
+            } else {
+                saves_to_delete.push(save);
+            }
+        }
+
+        self.delete_saves(&saves_to_delete).await?;
+        if !saves_to_delete.is_empty() {
+            info!("Cleaned up {} old saves for game {} (keeping {} saves, {} save-states)",
+                  saves_to_delete.len(), game_id, keep_saves, keep_save_states);
+        }
+
+        Ok(saves_to_delete)
+    }
+
+    async fn delete_saves(&self, saves: &[Save]) -> Result<()> {
+        if saves.is_empty() {
+            return Ok(());
+        }
+        
+        let placeholders = saves.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let query = format!("DELETE FROM saves WHERE id IN ({})", placeholders);
+        
+        let mut q = sqlx::query(&query);
+        for save in saves {
+            q = q.bind(save.id);
+        }
+        q.execute(&self.pool).await?;
+        
+        Ok(())
+    }
+
+    /// Get all games
+    pub async fn get_all_games(&self) -> Result<Vec<Game>> {
+        let games = sqlx::query(
+            "SELECT id, name, emulator, game_id, path, last_played, total_saves, sync_enabled FROM games ORDER BY last_played DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| Game {
+            id: row.get(0),
+            name: row.get(1),
+            emulator: row.get(2),
+            game_id: row.get(3),
+            path: row.get(4),
+            last_played: row.get(5),
+            total_saves: row.get(6),
+            sync_enabled: row.get(7),
+        })
+        .collect();
+
+        Ok(games)
+    }
+
+    /// Enable or disable cloud sync for a game
+    pub async fn set_game_sync_enabled(&self, game_id: i64, enabled: bool) -> Result<()> {
+        sqlx::query("UPDATE games SET sync_enabled = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(game_id)
+            .execute(&self.pool)
+            .await?;
+
+        info!("Cloud sync {} for game {}", if enabled { "enabled" } else { "disabled" }, game_id);
+        Ok(())
+    }
+
+    /// Search games by name or emulator. Matching is case-insensitive and
+    /// accepts partial words; results are ordered by most recently played.
+    pub async fn search_games(&self, query: &str) -> Result<Vec<Game>> {
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return self.get_all_games().await;
+        }
+
+        // Quote each term so FTS syntax characters are taken literally,
+        // and make it a prefix match so partial words still hit
+        let fts_query = terms.iter()
+            .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let games = sqlx::query(
+            "SELECT g.id, g.name, g.emulator, g.game_id, g.path, g.last_played, g.total_saves, g.sync_enabled
+             FROM games g JOIN games_fts f ON f.rowid = g.id
+             WHERE games_fts MATCH ?
+             ORDER BY g.last_played DESC"
+        )
+        .bind(&fts_query)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| Game {
+            id: row.get(0),
+            name: row.get(1),
+            emulator: row.get(2),
+            game_id: row.get(3),
+            path: row.get(4),
+            last_played: row.get(5),
+            total_saves: row.get(6),
+            sync_enabled: row.get(7),
+        })
+        .collect::<Vec<_>>();
+
+        // FTS only matches word prefixes, so fall through to LIKE for
+        // mid-word fragments
+        if !games.is_empty() {
+            return Ok(games);
+        }
+
+        // Every term must appear somewhere in the name or emulator
+        let conditions = terms.iter()
+            .map(|_| "(name LIKE ? ESCAPE '\\' OR emulator LIKE ? ESCAPE '\\')")
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let sql = format!(
+            "SELECT id, name, emulator, game_id, path, last_played, total_saves, sync_enabled FROM games
+             WHERE {} ORDER BY last_played DESC",
+            conditions
+        );
+
+        let mut q = sqlx::query(&sql);
+        for term in &terms {
+            let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            let pattern = format!("%{}%", escaped);
+            q = q.bind(pattern.clone()).bind(pattern);
+        }
+
+        let games = q
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| Game {
+                id: row.get(0),
+                name: row.get(1),
+                emulator: row.get(2),
+                game_id: row.get(3),
+                path: row.get(4),
+                last_played: row.get(5),
+                total_saves: row.get(6),
+                sync_enabled: row.get(7),
+            })
+            .collect();
+
+        Ok(games)
+    }
+
+    /// Get a setting value
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let value = sqlx::query_scalar::<_, String>(
+            "SELECT value FROM settings WHERE key = ?"
+        )
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(value)
+    }
+
+    /// Set a setting value
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO settings (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = ?, updated_at = CURRENT_TIMESTAMP"
+        )
+        .bind(key)
+        .bind(value)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
 
         Ok(())
     }
@@ -413,6 +1285,141 @@ impl Database {
         Ok(())
     }
 
+    /// Identifier for this installation in the active profile, generated on
+    /// first use and reused afterwards so the server sees the same device on every launch
+    pub async fn get_or_create_device_id(&self) -> Result<String> {
+        let profile = self.get_active_profile().await?;
+        let key = if profile == DEFAULT_PROFILE {
+            "device_id".to_string()
+        } else {
+            format!("device_id:{}", profile)
+        };
+        
+        if let Some(device_id) = self.get_setting(&key).await? {
+            return Ok(device_id);
+        }
+        
+        let device_id = uuid::Uuid::new_v4().to_string();
+        self.set_setting(&key, &device_id).await?;
+        info!("Generated device id {} for profile {}", device_id, profile);
+        Ok(device_id)
+    }
+
+    /// Names of all cloud profiles, oldest first
+    pub async fn get_profiles(&self) -> Result<Vec<String>> {
+        let profiles = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM profiles ORDER BY created_at, name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(profiles)
+    }
+
+    /// Profile whose account is used for cloud sync
+    pub async fn get_active_profile(&self) -> Result<String> {
+        let profile = sqlx::query_scalar::<_, String>(
+            "SELECT name FROM profiles WHERE active = 1 LIMIT 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(profile.unwrap_or_else(|| DEFAULT_PROFILE.to_string()))
+    }
+
+    /// Add a profile, doing nothing if it already exists
+    pub async fn create_profile(&self, name: &str) -> Result<()> {
+        sqlx::query("INSERT OR IGNORE INTO profiles (name) VALUES (?)")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Make an existing profile the active one
+    pub async fn set_active_profile(&self, name: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        
+        let exists: Option<String> = sqlx::query_scalar("SELECT name FROM profiles WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if exists.is_none() {
+            return Err(anyhow::anyhow!("Profile {} does not exist", name));
+        }
+        
+        sqlx::query("UPDATE profiles SET active = (name = ?)")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!("Active profile is now {}", name);
+        Ok(())
+    }
+
+    /// Remove a profile, its device id and its queued uploads. The default and the active profile can't be removed.
+    pub async fn delete_profile(&self, name: &str) -> Result<()> {
+        if name == DEFAULT_PROFILE || self.get_active_profile().await? == name {
+            return Err(anyhow::anyhow!("Profile {} is in use and can't be removed", name));
+        }
+        
+        sqlx::query("DELETE FROM profiles WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        self.delete_setting(&format!("device_id:{}", name)).await?;
+        self.delete_setting(&format!("upload_queue:{}", name)).await?;
+
+        Ok(())
+    }
+
+    /// Get the size of the database file on disk in bytes
+    pub async fn disk_size(&self) -> Result<u64> {
+        let metadata = tokio::fs::metadata(&self.db_path)
+            .await
+            .context("Failed to read database file metadata")?;
+        Ok(metadata.len())
+    }
+
+    /// Rebuild the database file to reclaim space left behind by deleted rows.
+    /// Returns the file size before and after compaction.
+    pub async fn vacuum(&self) -> Result<(u64, u64)> {
+        let before = self.disk_size().await?;
+        info!("Vacuuming database ({} bytes)", before);
+
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .context("Failed to vacuum database")?;
+
+        let after = self.disk_size().await?;
+        info!("Database vacuum complete: {} -> {} bytes ({} reclaimed)",
+            before, after, before.saturating_sub(after));
+
+        self.set_setting("last_vacuum_at", &Utc::now().to_rfc3339()).await?;
+        Ok((before, after))
+    }
+
+    /// Vacuum the database if it hasn't been vacuumed within `interval`.
+    /// Returns true if a vacuum was performed.
+    pub async fn vacuum_if_due(&self, interval: chrono::Duration) -> Result<bool> {
+        let last_vacuum = self.get_setting("last_vacuum_at").await?
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|time| time.with_timezone(&Utc));
+
+        if let Some(last) = last_vacuum {
+            if Utc::now() - last < interval {
+                debug!("Skipping database vacuum, last run at {}", last);
+                return Ok(false);
+            }
+        }
+
+        self.vacuum().await?;
+        Ok(true)
+    }
+
     /// Get database statistics
     pub async fn get_stats(&self) -> Result<(i32, i32)> {
         let total_games: i32 = sqlx::query_scalar("SELECT COUNT(*) FROM games")
@@ -526,6 +1533,47 @@ mod tests {
         assert!(saves.len() <= 5, "Should keep at most 5 saves, got {}", saves.len());
     }
 
+    #[tokio::test]
+    async fn test_identical_save_not_versioned() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "PCSX2").await.unwrap();
+        
+        let first = db.record_save(game.id, "/path/to/save.ps2", "same_hash", 5, None).await.unwrap();
+        assert!(db.is_duplicate_save(game.id, "same_hash").await.unwrap());
+        assert!(!db.is_duplicate_save(game.id, "other_hash").await.unwrap());
+        
+        // Rewriting identical content returns the existing version
+        let second = db.record_save(game.id, "/path/to/save.ps2", "same_hash", 5, None).await.unwrap();
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.version, 1);
+        assert_eq!(db.get_saves_for_game(game.id, None).await.unwrap().len(), 1);
+        
+        // A real change still creates a new version
+        let third = db.record_save(game.id, "/path/to/save.ps2", "new_hash", 5, None).await.unwrap();
+        assert_eq!(third.version, 2);
+        assert_eq!(
+            db.get_latest_hash_for_path("/path/to/save.ps2").await.unwrap().as_deref(),
+            Some("new_hash")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_game_sync_enabled() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Homebrew Test", "RetroArch").await.unwrap();
+        assert!(game.sync_enabled);
+        
+        db.set_game_sync_enabled(game.id, false).await.unwrap();
+        let game = db.get_or_create_game("Homebrew Test", "RetroArch").await.unwrap();
+        assert!(!game.sync_enabled);
+        assert!(!db.get_all_games().await.unwrap()[0].sync_enabled);
+        
+        db.set_game_sync_enabled(game.id, true).await.unwrap();
+        assert!(db.get_all_games().await.unwrap()[0].sync_enabled);
+    }
+
     #[tokio::test]
     async fn test_cleanup_old_saves() {
         let (db, _temp_dir) = create_test_db().await;
@@ -555,6 +1603,355 @@ mod tests {
         assert!(remaining.len() <= 3);
     }
 
+    #[tokio::test]
+    async fn test_cleanup_keeps_pinned_saves() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "PCSX2").await.unwrap();
+        let first = db.record_save(game.id, "/path/to/save.ps2", "hash_0", 10, None).await.unwrap();
+        db.set_save_pinned(first.id, true).await.unwrap();
+        for i in 1..=5 {
+            db.record_save(game.id, "/path/to/save.ps2", &format!("hash_{}", i), 10, None).await.unwrap();
+        }
+        
+        db.cleanup_old_saves(game.id, 2).await.unwrap();
+        
+        let remaining = db.get_saves_for_game(game.id, None).await.unwrap();
+        assert_eq!(remaining.len(), 3);
+        assert!(remaining.iter().any(|s| s.id == first.id && s.pinned));
+    }
+
+    #[tokio::test]
+    async fn test_unidentified_games_are_kept_apart() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let first = db.get_or_create_game_with_id("Unknown PS3 Game (aaaa)", "RPCS3", None, Some("aaaa")).await.unwrap();
+        let second = db.get_or_create_game_with_id("Unknown PS3 Game (bbbb)", "RPCS3", None, Some("bbbb")).await.unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(db.get_or_create_game_with_id("ignored", "RPCS3", None, Some("aaaa")).await.unwrap().id, first.id);
+        
+        // Backfilled once the title is detected
+        assert!(db.name_unknown_game("RPCS3", "aaaa", "Demon's Souls").await.unwrap());
+        let named = db.get_or_create_game("Demon's Souls", "RPCS3").await.unwrap();
+        assert_eq!(named.id, first.id);
+        assert!(!db.name_unknown_game("RPCS3", "aaaa", "Demon's Souls").await.unwrap());
+        
+        // A name that's already taken leaves both games alone
+        assert!(!db.name_unknown_game("RPCS3", "bbbb", "Demon's Souls").await.unwrap());
+        assert_eq!(db.get_all_games().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rename_game() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Final Fantasy", "PCSX2").await.unwrap();
+        db.get_or_create_game("Kingdom Hearts", "PCSX2").await.unwrap();
+        
+        db.rename_game(game.id, "Final Fantasy X").await.unwrap();
+        assert_eq!(db.get_game(game.id).await.unwrap().unwrap().name, "Final Fantasy X");
+        
+        // Saves detected under the old name still go to the renamed game
+        assert_eq!(db.get_or_create_game("Final Fantasy", "PCSX2").await.unwrap().id, game.id);
+        assert_eq!(db.get_all_games().await.unwrap().len(), 2);
+        
+        assert!(db.rename_game(game.id, "Kingdom Hearts").await.is_err());
+        assert!(db.rename_game(game.id, "  ").await.is_err());
+        
+        // Naming it back drops the alias
+        db.rename_game(game.id, "Final Fantasy").await.unwrap();
+        assert_eq!(db.get_or_create_game("Final Fantasy X", "PCSX2").await.unwrap().id, game.id);
+    }
+
+    #[tokio::test]
+    async fn test_merge_games() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let into = db.get_or_create_game("Final Fantasy X", "PCSX2").await.unwrap();
+        let from = db.get_or_create_game("Unknown PS2 Game", "PCSX2").await.unwrap();
+        db.record_save(into.id, "/saves/Mcd001.ps2", "hash_1", 10, None).await.unwrap();
+        db.record_save(from.id, "/saves/Mcd001.ps2", "hash_2", 10, None).await.unwrap();
+        db.record_save(into.id, "/saves/Mcd001.ps2", "hash_3", 10, None).await.unwrap();
+        
+        db.merge_games(from.id, into.id).await.unwrap();
+        assert!(db.get_game(from.id).await.unwrap().is_none());
+        assert_eq!(db.get_game(into.id).await.unwrap().unwrap().total_saves, 3);
+        
+        // Versions follow the order the saves were made in
+        let saves = db.get_saves_for_game(into.id, None).await.unwrap();
+        let versions: Vec<_> = saves.iter().map(|s| (s.file_hash.as_str(), s.version)).collect();
+        assert_eq!(versions, [("hash_3", 3), ("hash_2", 2), ("hash_1", 1)]);
+        
+        assert_eq!(db.get_or_create_game("Unknown PS2 Game", "PCSX2").await.unwrap().id, into.id);
+        assert!(db.merge_games(into.id, into.id).await.is_err());
+        let other = db.get_or_create_game("Final Fantasy X", "PPSSPP").await.unwrap();
+        assert!(db.merge_games(other.id, into.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_by_class_keeps_each_class() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "RetroArch").await.unwrap();
+        for i in 1..=4 {
+            db.record_save(game.id, "/saves/Game.srm", &format!("srm_{}", i), 10, None).await.unwrap();
+            db.record_save(game.id, "/states/Game.state1", &format!("state_{}", i), 10, None).await.unwrap();
+        }
+        
+        let deleted = db.cleanup_old_saves_by_class(game.id, "RetroArch", 3, 1).await.unwrap();
+        assert_eq!(deleted.len(), 4);
+        
+        let remaining = db.get_saves_for_game(game.id, None).await.unwrap();
+        assert_eq!(remaining.iter().filter(|s| s.file_path.ends_with(".srm")).count(), 3);
+        assert_eq!(remaining.iter().filter(|s| s.file_path.ends_with(".state1")).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_save() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "PCSX2").await.unwrap();
+        let first = db.record_save(game.id, "/path/to/save.ps2", "hash_1", 10, None).await.unwrap();
+        let second = db.record_save(game.id, "/path/to/save.ps2", "hash_2", 20, None).await.unwrap();
+        db.set_save_backup_path(first.id, "/backups/save_v1.zst").await.unwrap();
+        
+        let deleted = db.delete_save(first.id).await.unwrap().unwrap();
+        assert_eq!(deleted.backup_path.as_deref(), Some("/backups/save_v1.zst"));
+        assert!(db.delete_save(first.id).await.unwrap().is_none());
+        
+        let remaining = db.get_saves_for_game(game.id, None).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, second.id);
+    }
+
+    #[tokio::test]
+    async fn test_save_note() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "PCSX2").await.unwrap();
+        let save = db.record_save(game.id, "/path/to/save.ps2", "hash_1", 10, None).await.unwrap();
+        assert!(save.note.is_none());
+        
+        db.set_save_note(save.id, "  before final boss ").await.unwrap();
+        let latest = db.get_latest_save(game.id).await.unwrap().unwrap();
+        assert_eq!(latest.note.as_deref(), Some("before final boss"));
+        assert_eq!(db.get_save_note_by_hash("hash_1").await.unwrap().as_deref(), Some("before final boss"));
+        
+        // Clearing the note stores NULL again
+        db.set_save_note(save.id, "").await.unwrap();
+        assert!(db.get_save_note_by_hash("hash_1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cloud_save_id() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "PCSX2").await.unwrap();
+        let other = db.get_or_create_game("Other Game", "PCSX2").await.unwrap();
+        db.record_save(game.id, "/saves/Mcd001.ps2", "hash_1", 10, None).await.unwrap();
+        db.record_save(other.id, "/saves/Mcd001.ps2", "hash_1", 10, None).await.unwrap();
+        assert!(db.get_cloud_save_id(game.id, "hash_1").await.unwrap().is_none());
+        
+        db.set_cloud_save_id(game.id, "hash_1", "cloud-1").await.unwrap();
+        assert_eq!(db.get_cloud_save_id(game.id, "hash_1").await.unwrap().as_deref(), Some("cloud-1"));
+        // The same card content is a separate cloud save for every game on it
+        assert!(db.get_cloud_save_id(other.id, "hash_1").await.unwrap().is_none());
+        assert!(db.get_cloud_save_id(game.id, "hash_2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_note_sync_state() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "PCSX2").await.unwrap();
+        let save = db.record_save(game.id, "/saves/Mcd001.ps2", "hash_1", 10, None).await.unwrap();
+        db.set_save_note(save.id, "before final boss").await.unwrap();
+        // Not in the cloud yet, the upload carries the note
+        assert!(db.get_unsynced_notes().await.unwrap().is_empty());
+        
+        db.set_cloud_save_id(game.id, "hash_1", "cloud-1").await.unwrap();
+        assert_eq!(
+            db.get_unsynced_notes().await.unwrap(),
+            vec![(save.id, "cloud-1".to_string(), Some("before final boss".to_string()))]
+        );
+        
+        // A pending local edit isn't overwritten by the cloud
+        assert!(!db.apply_cloud_note("cloud-1", Some("old note")).await.unwrap());
+        
+        db.mark_note_synced(save.id, Some("before final boss")).await.unwrap();
+        assert!(db.get_unsynced_notes().await.unwrap().is_empty());
+        
+        assert!(db.apply_cloud_note("cloud-1", Some("after final boss")).await.unwrap());
+        assert!(!db.apply_cloud_note("cloud-1", Some("after final boss")).await.unwrap());
+        assert_eq!(db.get_save_note_by_hash("hash_1").await.unwrap().as_deref(), Some("after final boss"));
+        assert!(db.apply_cloud_note("cloud-1", None).await.unwrap());
+        assert!(db.get_save_note_by_hash("hash_1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deleted_save_files() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        assert!(db.save_file_deleted_at("/saves/Mcd001.ps2").await.unwrap().is_none());
+        
+        let before = Utc::now();
+        db.mark_save_file_deleted("/saves/Mcd001.ps2").await.unwrap();
+        let first = db.save_file_deleted_at("/saves/Mcd001.ps2").await.unwrap().unwrap();
+        assert!(first >= before - chrono::Duration::seconds(1));
+        assert!(db.save_file_deleted_at("/saves/Mcd002.ps2").await.unwrap().is_none());
+        
+        // Deleting it again moves the tombstone forward
+        db.mark_save_file_deleted("/saves/Mcd001.ps2").await.unwrap();
+        assert!(db.save_file_deleted_at("/saves/Mcd001.ps2").await.unwrap().unwrap() >= first);
+        
+        db.clear_save_file_deleted("/saves/Mcd001.ps2").await.unwrap();
+        assert!(db.save_file_deleted_at("/saves/Mcd001.ps2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_game_creation() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let games = futures_util::future::join_all(
+            (0..8).map(|_| db.get_or_create_game("Okami", "PCSX2"))
+        ).await;
+        let ids: std::collections::HashSet<i64> = games.into_iter().map(|game| game.unwrap().id).collect();
+        assert_eq!(ids.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deferred_writes() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Okami", "PCSX2").await.unwrap();
+        let mut write = DeferredWrite {
+            file_path: "/saves/Mcd001.ps2".to_string(),
+            emulator: "PCSX2".to_string(),
+            game_id: game.id,
+            game_name: "Okami".to_string(),
+            file_hash: "hash_1".to_string(),
+            file_size: 4,
+            cloud_save_id: "cloud-1".to_string(),
+            data: b"old!".to_vec(),
+        };
+        db.defer_write(&write).await.unwrap();
+        
+        // A newer download for the same file replaces the held one
+        write.file_hash = "hash_2".to_string();
+        write.data = b"new!".to_vec();
+        db.defer_write(&write).await.unwrap();
+        
+        let writes = db.get_deferred_writes().await.unwrap();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].file_hash, "hash_2");
+        assert_eq!(writes[0].data, b"new!");
+        
+        db.remove_deferred_write("/saves/Mcd001.ps2").await.unwrap();
+        assert!(db.get_deferred_writes().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_schema_version() {
+        let (db, temp_dir) = create_test_db().await;
+        
+        let latest = MIGRATIONS.last().unwrap().version;
+        assert_eq!(db.schema_version().await.unwrap(), latest);
+        
+        // Reopening an up-to-date database should not re-apply anything
+        drop(db);
+        let db = Database::new(Some(temp_dir.path().join("test.db"))).await.unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), latest);
+        
+        let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_version")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_database_upgrade() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("legacy.db");
+        
+        // Simulate a database created before schema versioning, without games.game_id
+        {
+            let pool = SqlitePoolOptions::new()
+                .connect(&format!("sqlite:{}?mode=rwc", db_path.display()))
+                .await
+                .unwrap();
+            sqlx::query("CREATE TABLE games (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL, emulator TEXT NOT NULL, path TEXT, last_played DATETIME, total_saves INTEGER DEFAULT 0, UNIQUE(name, emulator))")
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO games (name, emulator) VALUES ('Legacy Game', 'PCSX2')")
+                .execute(&pool)
+                .await
+                .unwrap();
+            pool.close().await;
+        }
+        
+        let db = Database::new(Some(db_path)).await.unwrap();
+        assert_eq!(db.schema_version().await.unwrap(), MIGRATIONS.last().unwrap().version);
+        
+        let game = db.get_or_create_game_with_id("Legacy Game", "PCSX2", Some("SLUS-12345"), None).await.unwrap();
+        assert_eq!(game.game_id.as_deref(), Some("SLUS-12345"));
+    }
+
+    #[tokio::test]
+    async fn test_device_id_is_stable() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let device_id = db.get_or_create_device_id().await.unwrap();
+        assert!(!device_id.is_empty());
+        assert_eq!(db.get_or_create_device_id().await.unwrap(), device_id);
+    }
+
+    #[tokio::test]
+    async fn test_profiles() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        assert_eq!(db.get_profiles().await.unwrap(), vec![DEFAULT_PROFILE.to_string()]);
+        assert_eq!(db.get_active_profile().await.unwrap(), DEFAULT_PROFILE);
+        let default_device = db.get_or_create_device_id().await.unwrap();
+        
+        db.create_profile("kids").await.unwrap();
+        db.set_active_profile("kids").await.unwrap();
+        assert_eq!(db.get_active_profile().await.unwrap(), "kids");
+        
+        // Each profile is its own device
+        let kids_device = db.get_or_create_device_id().await.unwrap();
+        assert_ne!(kids_device, default_device);
+        assert!(db.delete_profile("kids").await.is_err());
+        
+        db.set_active_profile(DEFAULT_PROFILE).await.unwrap();
+        assert_eq!(db.get_or_create_device_id().await.unwrap(), default_device);
+        assert!(db.set_active_profile("missing").await.is_err());
+        
+        db.delete_profile("kids").await.unwrap();
+        assert_eq!(db.get_profiles().await.unwrap(), vec![DEFAULT_PROFILE.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_vacuum() {
+        let (db, _temp_dir) = create_test_db().await;
+        
+        let game = db.get_or_create_game("Test Game", "PCSX2").await.unwrap();
+        let long_path = "x".repeat(4096);
+        for i in 0..50 {
+            db.record_save(game.id, &long_path, &format!("hash_{}", i), 1, None).await.unwrap();
+        }
+        db.cleanup_old_saves(game.id, 1).await.unwrap();
+        
+        let (before, after) = db.vacuum().await.unwrap();
+        assert!(after <= before);
+        assert_eq!(db.disk_size().await.unwrap(), after);
+        
+        // A vacuum was just recorded, so the next one isn't due yet
+        assert!(!db.vacuum_if_due(chrono::Duration::days(7)).await.unwrap());
+        assert!(db.vacuum_if_due(chrono::Duration::zero()).await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_database_stats() {
         let (db, _temp_dir) = create_test_db().await;
@@ -216,6 +216,52 @@ pub fn is_game_id_for_name(game_id: &str, game_name: &str) -> bool {
     }
 }
 
+/// Extract the canonical serial from a save directory name or game ID
+/// (e.g., "BESLES-52056HPA" -> "SLES-52056", "SLUS-20552" -> "SLUS-20552")
+pub fn normalize_serial(id: &str) -> Option<String> {
+    let bytes = id.as_bytes();
+    (0..bytes.len().saturating_sub(9)).find_map(|i| {
+        let candidate = &bytes[i..i + 10];
+        let is_serial = candidate[..4].iter().all(|b| b.is_ascii_alphabetic())
+            && candidate[4] == b'-'
+            && candidate[5..].iter().all(|b| b.is_ascii_digit());
+        is_serial.then(|| id[i..i + 10].to_ascii_uppercase())
+    })
+}
+
+/// Extract a serial from a per-game save folder name as used by PPSSPP and RPCS3
+/// (e.g., "ULUS10041SAVE00" -> "ULUS-10041", "BLUS30443" -> "BLUS-30443")
+pub fn serial_from_folder_name(name: &str) -> Option<String> {
+    let bytes = name.as_bytes();
+    if bytes.len() < 9 || !bytes[..4].iter().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    
+    let digits_start = if bytes[4] == b'-' { 5 } else { 4 };
+    let digits = bytes.get(digits_start..digits_start + 5)?;
+    if !digits.iter().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    
+    Some(format!(
+        "{}-{}",
+        name[..4].to_ascii_uppercase(),
+        &name[digits_start..digits_start + 5]
+    ))
+}
+
+/// Look up every serial (all regions and re-releases) for a game name
+pub fn lookup_game_ids_for_name(game_name: &str) -> Vec<String> {
+    let mut ids: Vec<String> = generated::GAME_DATABASE_GENERATED.iter()
+        .chain(GAME_DATABASE.iter())
+        .filter(|(_, name)| name.eq_ignore_ascii_case(game_name))
+        .map(|(id, _)| id.to_string())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
 /// Look up a game ID by its name (e.g., "FIFA Football 2005" -> "SLES-52563")
 /// 
 /// This is the REVERSE of lookup_game_name - finds the ID from the name
@@ -265,4 +311,27 @@ mod tests {
         
         assert_eq!(lookup_game_name("UNKNOWN-12345"), None);
     }
+    
+    #[test]
+    fn test_normalize_serial() {
+        assert_eq!(normalize_serial("BESLES-52056HPA"), Some("SLES-52056".to_string()));
+        assert_eq!(normalize_serial("BASLUS-20552"), Some("SLUS-20552".to_string()));
+        assert_eq!(normalize_serial("SCUS-97399"), Some("SCUS-97399".to_string()));
+        assert_eq!(normalize_serial("icon.sys"), None);
+    }
+    
+    #[test]
+    fn test_serial_from_folder_name() {
+        assert_eq!(serial_from_folder_name("ULUS10041SAVE00"), Some("ULUS-10041".to_string()));
+        assert_eq!(serial_from_folder_name("BLUS30443"), Some("BLUS-30443".to_string()));
+        assert_eq!(serial_from_folder_name("SLUS-20552"), Some("SLUS-20552".to_string()));
+        assert_eq!(serial_from_folder_name("Super Metroid"), None);
+    }
+    
+    #[test]
+    fn test_lookup_game_ids_for_name() {
+        let ids = lookup_game_ids_for_name("harry potter and the philosopher's stone");
+        assert!(ids.contains(&"SLES-52056".to_string()));
+        assert!(ids.contains(&"SLES-52055".to_string()));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use crate::storage::gamecube_database::lookup_gamecube_game_name;
+use crate::storage::gci_parser::GCIFile;
+use retrosave_shared::{MemoryCardMetadata, GameInfo};
+use tracing::debug;
+
+/// Size of a GameCube memory card block
+const BLOCK_SIZE: usize = 0x2000;
+/// Header, two directory copies and two block allocation maps
+const SYSTEM_BLOCKS: usize = 5;
+/// Size of a directory entry (same layout as a GCI header)
+const DENTRY_SIZE: usize = 0x40;
+/// Directory entries per directory block
+const DIRECTORY_ENTRIES: usize = 127;
+/// Offset of the update counter within a directory block
+const UPDATE_COUNTER_OFFSET: usize = 0x1FFA;
+
+/// GameCube Memory Card Parser
+///
+/// Parses raw memory card images as written by Dolphin (`.raw`/`.gcp`):
+/// - Size is a multiple of 8KB blocks (59, 251, 507, 1019 or 2043 user blocks)
+/// - Block 0 is the card header
+/// - Blocks 1 and 2 are the directory and its backup, the copy with the higher
+///   update counter is the active one
+/// - Blocks 3 and 4 are the block allocation maps
+pub struct GCMemoryCard {
+    pub data: Vec<u8>,
+}
+
+impl GCMemoryCard {
+    pub fn new(data: Vec<u8>) -> Option<Self> {
+        // Smallest card is 4Mbit (64 blocks), largest is 128Mbit (2048 blocks)
+        if !data.len().is_multiple_of(BLOCK_SIZE) || data.len() < 64 * BLOCK_SIZE || data.len() > 2048 * BLOCK_SIZE {
+            return None;
+        }
+
+        Some(GCMemoryCard { data })
+    }
+
+    /// Number of blocks available for saves
+    pub fn user_blocks(&self) -> usize {
+        self.data.len() / BLOCK_SIZE - SYSTEM_BLOCKS
+    }
+
+    /// Directory block currently in use
+    fn active_directory(&self) -> &[u8] {
+        let primary = &self.data[BLOCK_SIZE..2 * BLOCK_SIZE];
+        let backup = &self.data[2 * BLOCK_SIZE..3 * BLOCK_SIZE];
+
+        let counter = |dir: &[u8]| i16::from_be_bytes([
+            dir[UPDATE_COUNTER_OFFSET],
+            dir[UPDATE_COUNTER_OFFSET + 1],
+        ]);
+
+        if counter(backup) > counter(primary) {
+            backup
+        } else {
+            primary
+        }
+    }
+
+    /// Parse the saves listed in the active directory, keyed by
+    /// `{game_id}-{filename}` like Dolphin's exported GCI files
+    pub fn parse_saves(&self) -> HashMap<String, GCIFile> {
+        let directory = self.active_directory();
+        let mut saves = HashMap::new();
+
+        for index in 0..DIRECTORY_ENTRIES {
+            let entry = &directory[index * DENTRY_SIZE..(index + 1) * DENTRY_SIZE];
+
+            // Unused entries are filled with 0xFF
+            if entry[0..4] == [0xFF; 4] {
+                continue;
+            }
+
+            if let Some(save) = GCIFile::from_header(entry) {
+                if save.game_code.len() != 4 || save.block_count == 0 {
+                    debug!("Skipping invalid GameCube directory entry {}", index);
+                    continue;
+                }
+
+                saves.insert(format!("{}-{}", save.get_game_id(), save.filename), save);
+            }
+        }
+
+        debug!("Parsed {} saves from GameCube memory card", saves.len());
+        saves
+    }
+
+    /// Check if a specific game has saves on this card.
+    ///
+    /// `game_name` may be a title or a game ID (e.g. "GZLE" or "GZLE01").
+    /// Titles are matched through the GameCube game table, so every region
+    /// of a game counts.
+    pub fn has_game_saves(&self, game_name: &str) -> bool {
+        self.parse_saves().values().any(|save| {
+            let game_id = save.get_game_id();
+            game_id.eq_ignore_ascii_case(game_name)
+                || save.game_code.eq_ignore_ascii_case(game_name)
+                || lookup_gamecube_game_name(&game_id).eq_ignore_ascii_case(game_name)
+        })
+    }
+
+    /// Check if the card has any saves at all
+    pub fn has_any_saves(&self) -> bool {
+        !self.parse_saves().is_empty()
+    }
+
+    /// Generate metadata describing the games on this card
+    pub fn generate_metadata(&self, primary_game: String) -> MemoryCardMetadata {
+        let saves = self.parse_saves();
+
+        let mut games_map: HashMap<String, usize> = HashMap::new();
+        for save in saves.values() {
+            *games_map.entry(save.get_game_id()).or_insert(0) += 1;
+        }
+
+        let mut games_contained: Vec<GameInfo> = games_map.into_iter()
+            .map(|(game_id, save_count)| GameInfo {
+                game_name: lookup_gamecube_game_name(&game_id),
+                game_id,
+                save_count,
+            })
+            .collect();
+
+        games_contained.sort_by(|a, b| a.game_name.cmp(&b.game_name));
+
+        MemoryCardMetadata {
+            games_contained,
+            primary_game,
+            total_saves: saves.len(),
+            format_version: format!("GC_{}", self.user_blocks()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build an empty 251-block card
+    fn empty_card() -> Vec<u8> {
+        let mut data = vec![0u8; 256 * BLOCK_SIZE];
+        data[BLOCK_SIZE..3 * BLOCK_SIZE].fill(0xFF);
+        data[BLOCK_SIZE + UPDATE_COUNTER_OFFSET..BLOCK_SIZE + UPDATE_COUNTER_OFFSET + 2].copy_from_slice(&0u16.to_be_bytes());
+        data[2 * BLOCK_SIZE + UPDATE_COUNTER_OFFSET..2 * BLOCK_SIZE + UPDATE_COUNTER_OFFSET + 2].copy_from_slice(&0u16.to_be_bytes());
+        data
+    }
+
+    fn write_entry(data: &mut [u8], directory_block: usize, index: usize, game_code: &str, filename: &str) {
+        let offset = directory_block * BLOCK_SIZE + index * DENTRY_SIZE;
+        let entry = &mut data[offset..offset + DENTRY_SIZE];
+        entry[0..4].copy_from_slice(game_code.as_bytes());
+        entry[4..6].copy_from_slice(b"01");
+        entry[0x08..0x28].fill(0);
+        entry[0x08..0x08 + filename.len()].copy_from_slice(filename.as_bytes());
+        entry[0x38..0x3A].copy_from_slice(&1u16.to_be_bytes());
+    }
+
+    #[test]
+    fn test_invalid_card_size() {
+        assert!(GCMemoryCard::new(vec![0u8; 1000]).is_none());
+        assert!(GCMemoryCard::new(vec![0u8; 0x40]).is_none());
+        assert!(GCMemoryCard::new(empty_card()).is_some());
+    }
+
+    #[test]
+    fn test_empty_card() {
+        let card = GCMemoryCard::new(empty_card()).unwrap();
+        assert!(!card.has_any_saves());
+        assert_eq!(card.user_blocks(), 251);
+    }
+
+    #[test]
+    fn test_multi_game_card() {
+        let mut data = empty_card();
+        write_entry(&mut data, 1, 0, "GZLE", "gczelda");
+        write_entry(&mut data, 1, 1, "GALE", "SuperSmashBros0110290334");
+
+        let card = GCMemoryCard::new(data).unwrap();
+        assert!(card.has_game_saves("The Legend of Zelda: The Wind Waker"));
+        assert!(card.has_game_saves("GALE01"));
+        assert!(!card.has_game_saves("Metroid Prime"));
+
+        let metadata = card.generate_metadata("Super Smash Bros. Melee".to_string());
+        assert_eq!(metadata.total_saves, 2);
+        assert_eq!(metadata.games_contained.len(), 2);
+        assert_eq!(metadata.format_version, "GC_251");
+    }
+
+    #[test]
+    fn test_uses_newer_directory_copy() {
+        let mut data = empty_card();
+        write_entry(&mut data, 1, 0, "GZLE", "gczelda");
+        // The backup has a higher update counter and a different save
+        write_entry(&mut data, 2, 0, "GM8E", "MetroidPrime A");
+        let counter = 2 * BLOCK_SIZE + UPDATE_COUNTER_OFFSET;
+        data[counter..counter + 2].copy_from_slice(&1u16.to_be_bytes());
+
+        let card = GCMemoryCard::new(data).unwrap();
+        assert!(card.has_game_saves("Metroid Prime"));
+        assert!(!card.has_game_saves("The Legend of Zelda: The Wind Waker"));
+    }
+}
@@ -19,6 +19,15 @@ impl GCIFile {
         let mut header = [0u8; 0x40];
         file.read_exact(&mut header).ok()?;
         
+        Self::from_header(&header)
+    }
+    
+    /// Parse a 64-byte GCI header. Memory card directory entries use the same layout.
+    pub fn from_header(header: &[u8]) -> Option<Self> {
+        if header.len() < 0x40 {
+            return None;
+        }
+        
         // Extract game code (4 bytes)
         let game_code = String::from_utf8_lossy(&header[0..4])
             .trim_end_matches('\0')
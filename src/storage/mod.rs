@@ -1,5 +1,6 @@
 pub mod database;
 pub mod hasher;
+pub mod atomic;
 pub mod watcher;
 pub mod settings_manager;
 pub mod compression;
@@ -10,11 +11,13 @@ pub mod gamecube_database;
 pub mod memory_card_tracker;
 pub mod game_cover_fetcher;
 pub mod gci_parser;
+pub mod gc_memory_card;
+pub mod param_sfo;
 
 pub use database::{Database, Game, Save};
 pub use watcher::{SaveWatcher, SaveEvent, SaveBackupManager};
 pub use settings_manager::SettingsManager;
 pub use compression::{Compressor, CompressionStats, decompress};
-pub use save_types::{SaveType, MemoryCardFormat, FolderStructure};
+pub use save_types::{SaveType, MemoryCardFormat, FolderStructure, MemoryCardContents};
 pub use game_database::{lookup_game_name, is_game_id_for_name};
 pub use memory_card_tracker::{MemoryCardTracker, ChangedGame};
\ No newline at end of file
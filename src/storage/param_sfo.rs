@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Name of the metadata file in every PS3 and PSP save folder
+pub const PARAM_SFO: &str = "PARAM.SFO";
+
+const MAGIC: &[u8; 4] = b"\0PSF";
+const HEADER_LEN: usize = 0x14;
+const INDEX_ENTRY_LEN: usize = 0x10;
+/// UTF-8 string, null-terminated or not
+const FMT_UTF8_SPECIAL: u16 = 0x0004;
+const FMT_UTF8: u16 = 0x0204;
+const FMT_INT32: u16 = 0x0404;
+
+/// A value in a PARAM.SFO file
+#[derive(Debug, Clone, PartialEq)]
+pub enum SfoValue {
+    Text(String),
+    Integer(u32),
+}
+
+/// PlayStation PARAM.SFO metadata, as stored next to PS3 (RPCS3) and PSP
+/// (PPSSPP) saves
+#[derive(Debug, Clone, Default)]
+pub struct ParamSfo {
+    entries: HashMap<String, SfoValue>,
+}
+
+impl ParamSfo {
+    /// Parse a PARAM.SFO file
+    pub fn parse<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let data = std::fs::read(path.as_ref()).ok()?;
+        Self::from_bytes(&data)
+    }
+    
+    /// Parse the contents of a PARAM.SFO file, `None` if it isn't one
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN || &data[0..4] != MAGIC {
+            return None;
+        }
+        
+        let key_table = read_u32(data, 0x08)? as usize;
+        let data_table = read_u32(data, 0x0C)? as usize;
+        let count = read_u32(data, 0x10)? as usize;
+        
+        let mut entries = HashMap::new();
+        for i in 0..count {
+            let entry = HEADER_LEN + i * INDEX_ENTRY_LEN;
+            let key_offset = read_u16(data, entry)? as usize;
+            let format = read_u16(data, entry + 0x02)?;
+            let len = read_u32(data, entry + 0x04)? as usize;
+            let offset = read_u32(data, entry + 0x0C)? as usize;
+            
+            let key_start = key_table.checked_add(key_offset)?;
+            let key = data.get(key_start..)?;
+            let key = &key[..key.iter().position(|&b| b == 0)?];
+            let value = data.get(data_table.checked_add(offset)?..)?.get(..len)?;
+            
+            let value = match format {
+                FMT_UTF8 | FMT_UTF8_SPECIAL => {
+                    let text = value.split(|&b| b == 0).next().unwrap_or_default();
+                    SfoValue::Text(String::from_utf8_lossy(text).into_owned())
+                }
+                FMT_INT32 => SfoValue::Integer(read_u32(value, 0)?),
+                _ => continue,
+            };
+            entries.insert(String::from_utf8_lossy(key).into_owned(), value);
+        }
+        
+        Some(Self { entries })
+    }
+    
+    pub fn get(&self, key: &str) -> Option<&SfoValue> {
+        self.entries.get(key)
+    }
+    
+    /// Text value of `key`, `None` when missing, empty or not text
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.entries.get(key)? {
+            SfoValue::Text(text) if !text.trim().is_empty() => Some(text.trim()),
+            _ => None,
+        }
+    }
+    
+    /// Name of the game the save belongs to
+    pub fn title(&self) -> Option<&str> {
+        self.text("TITLE")
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Build a PARAM.SFO holding `entries`, for tests
+#[cfg(test)]
+pub(crate) fn build_sfo(entries: &[(&str, SfoValue)]) -> Vec<u8> {
+    let mut keys = Vec::new();
+    let mut values = Vec::new();
+    let mut index = Vec::new();
+    
+    for (key, value) in entries {
+        let (format, bytes) = match value {
+            SfoValue::Text(text) => (FMT_UTF8, [text.as_bytes(), &[0]].concat()),
+            SfoValue::Integer(n) => (FMT_INT32, n.to_le_bytes().to_vec()),
+        };
+        index.extend_from_slice(&(keys.len() as u16).to_le_bytes());
+        index.extend_from_slice(&format.to_le_bytes());
+        index.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        index.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        index.extend_from_slice(&(values.len() as u32).to_le_bytes());
+        keys.extend_from_slice(key.as_bytes());
+        keys.push(0);
+        values.extend_from_slice(&bytes);
+    }
+    
+    let key_table = HEADER_LEN + index.len();
+    let data_table = key_table + keys.len();
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&0x0101u32.to_le_bytes());
+    data.extend_from_slice(&(key_table as u32).to_le_bytes());
+    data.extend_from_slice(&(data_table as u32).to_le_bytes());
+    data.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    data.extend(index);
+    data.extend(keys);
+    data.extend(values);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_param_sfo() {
+        let data = build_sfo(&[
+            ("TITLE", SfoValue::Text("Demon's Souls".to_string())),
+            ("SUB_TITLE", SfoValue::Text("Slot 1".to_string())),
+            ("ATTRIBUTE", SfoValue::Integer(0)),
+        ]);
+        let sfo = ParamSfo::from_bytes(&data).unwrap();
+        
+        assert_eq!(sfo.title(), Some("Demon's Souls"));
+        assert_eq!(sfo.text("SUB_TITLE"), Some("Slot 1"));
+        assert_eq!(sfo.get("ATTRIBUTE"), Some(&SfoValue::Integer(0)));
+        assert_eq!(sfo.text("ATTRIBUTE"), None);
+        assert_eq!(sfo.text("DETAIL"), None);
+    }
+    
+    #[test]
+    fn test_rejects_invalid_data() {
+        assert!(ParamSfo::from_bytes(b"").is_none());
+        assert!(ParamSfo::from_bytes(b"not a param.sfo file").is_none());
+        
+        // Entries pointing past the end are an error, not a panic
+        let mut data = build_sfo(&[("TITLE", SfoValue::Text("Game".to_string()))]);
+        data.truncate(data.len() - 2);
+        assert!(ParamSfo::from_bytes(&data).is_none());
+    }
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use crate::storage::game_database::lookup_game_name;
+use crate::storage::game_database::{lookup_game_name, is_game_id_for_name, lookup_game_ids_for_name, normalize_serial};
 use retrosave_shared::{MemoryCardMetadata, GameInfo};
 use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
 use tracing::debug;
@@ -343,13 +343,29 @@ impl PS2MemoryCard {
         saves
     }
     
-    /// Check if a specific game has saves
+    /// Check if a specific game has saves on this card.
+    ///
+    /// `game_name` may be a title or a serial. Titles are resolved to every known
+    /// serial for the game (all regions), and the card's save directories are matched
+    /// by serial. Games missing from the serial table fall back to name matching.
     pub fn has_game_saves(&self, game_name: &str) -> bool {
         let saves = self.parse_saves();
         
+        let mut serials = lookup_game_ids_for_name(game_name);
+        serials.extend(normalize_serial(game_name));
+        
+        if !serials.is_empty() {
+            return saves.values().any(|save| {
+                normalize_serial(&save.name)
+                    .or_else(|| normalize_serial(&save.game_id))
+                    .is_some_and(|serial| serials.contains(&serial))
+            });
+        }
+        
+        let name_lower = game_name.to_lowercase();
         saves.values().any(|save| {
-            save.name.to_lowercase().contains(&game_name.to_lowercase()) ||
-            save.game_id.to_lowercase().contains(&game_name.to_lowercase())
+            save.name.to_lowercase().contains(&name_lower) ||
+            is_game_id_for_name(&save.game_id, game_name)
         })
     }
     
@@ -358,25 +374,6 @@ impl PS2MemoryCard {
         !self.parse_saves().is_empty()
     }
     
-    /// Get a more detailed check for Harry Potter saves specifically
-    pub fn has_harry_potter_save(&self) -> bool {
-        let saves = self.parse_saves();
-        
-        saves.values().any(|save| {
-            let name_lower = save.name.to_lowercase();
-            let id_lower = save.game_id.to_lowercase();
-            
-            name_lower.contains("harry") || 
-            name_lower.contains("potter") ||
-            name_lower.contains("hp") ||
-            id_lower.contains("52056") ||  // Harry Potter PAL
-            id_lower.contains("52055") ||  // Other PAL version
-            id_lower.contains("20826") ||  // NTSC-U
-            id_lower.contains("65465") ||  // NTSC-J
-            id_lower.contains("65650")     // NTSC-J EA Best Hits
-        })
-    }
-    
     /// Get the most recently modified save
     pub fn get_last_modified_save(&self) -> Option<PS2Save> {
         let saves = self.parse_saves();
@@ -457,6 +454,30 @@ mod tests {
         assert!(card.is_some());
     }
     
+    /// Build an 8MB card with one save file entry in the root directory
+    fn card_with_save(save_name: &str) -> PS2MemoryCard {
+        let mut data = vec![0u8; 8650752];
+        data[0..4].copy_from_slice(b"Sony");
+        data[0x3C..0x40].copy_from_slice(&8u32.to_le_bytes()); // Root directory at 0x2000
+        
+        let entry = 0x2000;
+        data[entry..entry + 4].copy_from_slice(&0x8010u32.to_le_bytes()); // In use, file
+        data[entry + 0x40..entry + 0x40 + save_name.len()].copy_from_slice(save_name.as_bytes());
+        
+        PS2MemoryCard::new(data).unwrap()
+    }
+    
+    #[test]
+    fn test_has_game_saves_by_serial() {
+        let card = card_with_save("BESLES-52056HPA");
+        
+        // Matched through the serial table, not the save name
+        assert!(card.has_game_saves("Harry Potter and the Philosopher's Stone"));
+        assert!(card.has_game_saves("SLES-52056"));
+        assert!(!card.has_game_saves("God of War"));
+        assert!(!card.has_game_saves("SCUS-97399"));
+    }
+    
     #[test]
     fn test_invalid_memory_card() {
         let data = vec![0xFF; 100]; // Too small
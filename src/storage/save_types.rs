@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use retrosave_shared::MemoryCardMetadata;
+use crate::storage::ps2_memory_card::PS2MemoryCard;
+use crate::storage::gc_memory_card::GCMemoryCard;
 
 /// Different types of save data formats used by emulators
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -24,6 +27,15 @@ pub enum SaveType {
         structure: FolderStructure,
         game_id: String,
     },
+    /// RetroArch battery save (.srm/.sav), one per ROM
+    RetroArchBattery {
+        game_id: String,
+    },
+    /// RetroArch save-state (.state, .stateN, .state.auto), one per ROM and slot
+    RetroArchState {
+        slot: Option<u8>,
+        game_id: String,
+    },
 }
 
 /// Types of memory card formats
@@ -35,6 +47,35 @@ pub enum MemoryCardFormat {
     Unknown,
 }
 
+/// A parsed memory card image that can tell which games it holds
+pub trait MemoryCardContents: Send + Sync {
+    /// Check if a specific game (by name or game ID) has saves on the card
+    fn has_game_saves(&self, game_name: &str) -> bool;
+    
+    /// Generate metadata describing the games on the card
+    fn generate_metadata(&self, primary_game: String) -> MemoryCardMetadata;
+}
+
+impl MemoryCardContents for PS2MemoryCard {
+    fn has_game_saves(&self, game_name: &str) -> bool {
+        PS2MemoryCard::has_game_saves(self, game_name)
+    }
+    
+    fn generate_metadata(&self, primary_game: String) -> MemoryCardMetadata {
+        PS2MemoryCard::generate_metadata(self, primary_game)
+    }
+}
+
+impl MemoryCardContents for GCMemoryCard {
+    fn has_game_saves(&self, game_name: &str) -> bool {
+        GCMemoryCard::has_game_saves(self, game_name)
+    }
+    
+    fn generate_metadata(&self, primary_game: String) -> MemoryCardMetadata {
+        GCMemoryCard::generate_metadata(self, primary_game)
+    }
+}
+
 /// Types of save folder structures
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum FolderStructure {
@@ -91,20 +132,23 @@ impl SaveType {
                 }
             },
             "retroarch" => {
-                // RetroArch uses individual save files
-                let game_name = file_path.file_stem()
+                // RetroArch keeps one battery save and a set of save-states per ROM
+                let file_name = file_path.file_name()
                     .and_then(|n| n.to_str())
-                    .unwrap_or("")
-                    .to_string();
+                    .unwrap_or("");
                 
-                if extension == "srm" || extension == "sav" {
-                    SaveType::IndividualFile {
-                        game_id: game_name,
-                    }
+                if let Some((game_id, slot)) = Self::parse_retroarch_state(file_name) {
+                    SaveType::RetroArchState { slot, game_id }
                 } else {
-                    SaveType::SaveState {
-                        slot: None,
-                        game_id: game_name,
+                    let game_id = file_path.file_stem()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("")
+                        .to_string();
+                    
+                    if extension.eq_ignore_ascii_case("srm") || extension.eq_ignore_ascii_case("sav") {
+                        SaveType::RetroArchBattery { game_id }
+                    } else {
+                        SaveType::IndividualFile { game_id }
                     }
                 }
             },
@@ -120,6 +164,28 @@ impl SaveType {
         }
     }
     
+    /// Split a RetroArch save-state file name into ROM name and slot.
+    /// "game.state" is slot 0, "game.state3" is slot 3 and "game.state.auto" has no slot.
+    fn parse_retroarch_state(file_name: &str) -> Option<(String, Option<u8>)> {
+        let index = file_name.to_ascii_lowercase().rfind(".state")?;
+        let (rom, suffix) = (&file_name[..index], &file_name[index + ".state".len()..]);
+        if rom.is_empty() {
+            return None;
+        }
+        
+        let slot = if suffix.is_empty() {
+            Some(0)
+        } else if suffix.eq_ignore_ascii_case(".auto") {
+            None
+        } else if suffix.chars().all(|c| c.is_ascii_digit()) {
+            Some(suffix.parse().ok()?)
+        } else {
+            return None; // e.g. "game.state1.png" thumbnails
+        };
+        
+        Some((rom.to_string(), slot))
+    }
+    
     /// Check if this save type uses memory cards
     pub fn is_memory_card(&self) -> bool {
         matches!(self, SaveType::MemoryCard { .. })
@@ -127,7 +193,18 @@ impl SaveType {
     
     /// Check if this save type represents individual game saves
     pub fn is_individual(&self) -> bool {
-        matches!(self, SaveType::IndividualFile { .. } | SaveType::SaveFolder { .. })
+        matches!(
+            self,
+            SaveType::IndividualFile { .. }
+                | SaveType::SaveFolder { .. }
+                | SaveType::RetroArchBattery { .. }
+                | SaveType::RetroArchState { .. }
+        )
+    }
+    
+    /// Check if this is a save-state, which retention keeps fewer of than real saves
+    pub fn is_save_state(&self) -> bool {
+        matches!(self, SaveType::SaveState { .. } | SaveType::RetroArchState { .. })
     }
 }
 
@@ -192,6 +269,17 @@ impl MemoryCardFormat {
         }
     }
     
+    /// Parse a memory card image, if this format has a per-game parser
+    pub fn parse(&self, data: Vec<u8>) -> Option<Box<dyn MemoryCardContents>> {
+        match self {
+            MemoryCardFormat::PS2 => PS2MemoryCard::new(data)
+                .map(|card| Box::new(card) as Box<dyn MemoryCardContents>),
+            MemoryCardFormat::GameCube => GCMemoryCard::new(data)
+                .map(|card| Box::new(card) as Box<dyn MemoryCardContents>),
+            _ => None,
+        }
+    }
+    
     /// Count the number of saves in a memory card
     pub fn count_saves(&self, data: &[u8]) -> u32 {
         match self {
@@ -215,6 +303,10 @@ impl MemoryCardFormat {
                 }
                 count
             },
+            MemoryCardFormat::GameCube => {
+                GCMemoryCard::new(data.to_vec())
+                    .map_or(0, |card| card.parse_saves().len() as u32)
+            },
             _ => 0, // TODO: Implement for other formats
         }
     }
@@ -235,7 +327,9 @@ mod tests {
         // Test RetroArch save detection
         let path = PathBuf::from("/home/user/.config/retroarch/saves/game.srm");
         let save_type = SaveType::detect(&path, "retroarch");
-        assert!(matches!(save_type, SaveType::IndividualFile { .. }));
+        assert!(matches!(save_type, SaveType::RetroArchBattery { .. }));
+        assert!(save_type.is_individual());
+        assert!(!save_type.is_save_state());
         
         // Test RPCS3 folder detection
         let path = PathBuf::from("/home/user/.config/rpcs3/savedata/BLUS30443");
@@ -243,6 +337,28 @@ mod tests {
         assert!(matches!(save_type, SaveType::SaveFolder { structure: FolderStructure::RPCS3, .. }));
     }
     
+    #[test]
+    fn test_retroarch_state_detection() {
+        let detect = |name: &str| SaveType::detect(&PathBuf::from("/roms/snes").join(name), "retroarch");
+        
+        assert_eq!(detect("Super Metroid (USA).state"), SaveType::RetroArchState {
+            slot: Some(0),
+            game_id: "Super Metroid (USA)".to_string(),
+        });
+        assert_eq!(detect("Super Metroid (USA).state3"), SaveType::RetroArchState {
+            slot: Some(3),
+            game_id: "Super Metroid (USA)".to_string(),
+        });
+        assert_eq!(detect("Super Metroid (USA).state.auto"), SaveType::RetroArchState {
+            slot: None,
+            game_id: "Super Metroid (USA)".to_string(),
+        });
+        assert!(detect("Super Metroid (USA).state.auto").is_save_state());
+        
+        // Thumbnails are not save-states
+        assert!(!detect("Super Metroid (USA).state1.png").is_save_state());
+    }
+    
     #[test]
     fn test_empty_ps2_memory_card() {
         // Create a mock empty PS2 memory card
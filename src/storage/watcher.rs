@@ -8,9 +8,24 @@ use tokio::sync::{mpsc, Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 
 use super::hasher::{hash_file, get_file_size};
+use super::atomic;
 use super::compression::{Compressor, CompressionStats};
 use super::Database;
 use super::save_types::SaveType;
+use super::param_sfo::{ParamSfo, PARAM_SFO};
+use crate::emulators::UNKNOWN_GAME;
+
+/// Temporary and lock files emulators leave in their save directories
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &["*.tmp", "*.bak", "*.lock", "*~", ".#*", "*.swp"];
+
+/// Settings key holding the user's extra ignore patterns, one per line
+pub const IGNORE_PATTERNS_SETTING: &str = "watcher_ignore_patterns";
+
+/// Settings key holding the user's backup exclusions, one per line
+pub const BACKUP_EXCLUSIONS_SETTING: &str = "backup_exclusions";
+
+/// Emulators keeping one folder per game, where any file in it is save data
+const PER_GAME_FOLDER_EMULATORS: &[&str] = &["PPSSPP", "RPCS3"];
 
 #[derive(Debug, Clone)]
 pub struct SaveEvent {
@@ -22,6 +37,7 @@ pub struct SaveEvent {
     pub file_size: u64,
     pub save_type: SaveType,
     pub is_empty: bool,  // For memory cards, indicates if it's empty
+    pub deleted: bool,  // The save file was removed, hash and size are its last known ones
 }
 
 pub struct SaveWatcher {
@@ -34,6 +50,9 @@ pub struct SaveWatcher {
     last_event_times: Arc<Mutex<HashMap<PathBuf, Instant>>>,
     memory_card_tracker: Arc<Mutex<crate::storage::memory_card_tracker::MemoryCardTracker>>,
     emulator_name: String,
+    ignore_patterns: Arc<Vec<String>>,
+    /// Paths and patterns relative to `save_dir` that are never backed up
+    exclusions: Arc<Vec<String>>,
 }
 
 impl SaveWatcher {
@@ -50,8 +69,18 @@ impl SaveWatcher {
         emulator_name: String,
     ) -> Result<(Self, mpsc::Receiver<SaveEvent>)> {
         let (sender, receiver) = mpsc::channel(100);
-        
-        let watcher = SaveWatcher {
+        Ok((Self::with_sender(save_dir, database, emulator_name, sender), receiver))
+    }
+    
+    /// Watcher reporting to an existing channel, so several save directories
+    /// of one emulator can share a receiver
+    pub fn with_sender(
+        save_dir: PathBuf,
+        database: Arc<Database>,
+        emulator_name: String,
+        sender: mpsc::Sender<SaveEvent>,
+    ) -> Self {
+        SaveWatcher {
             watcher: None,
             save_dir,
             database,
@@ -61,9 +90,14 @@ impl SaveWatcher {
             last_event_times: Arc::new(Mutex::new(HashMap::new())),
             memory_card_tracker: Arc::new(Mutex::new(crate::storage::memory_card_tracker::MemoryCardTracker::new())),
             emulator_name,
-        };
-        
-        Ok((watcher, receiver))
+            ignore_patterns: Arc::new(Self::default_ignore_patterns()),
+            exclusions: Arc::new(Vec::new()),
+        }
+    }
+    
+    /// Directory being watched
+    pub fn save_dir(&self) -> &Path {
+        &self.save_dir
     }
     
     pub async fn set_current_game(&self, game_name: Option<String>) {
@@ -76,19 +110,125 @@ impl SaveWatcher {
         }
     }
     
+    fn default_ignore_patterns() -> Vec<String> {
+        DEFAULT_IGNORE_PATTERNS.iter().map(|p| p.to_string()).collect()
+    }
+    
+    /// Replace the user-defined ignore patterns (the defaults always apply)
+    pub fn set_ignore_patterns(&mut self, patterns: &[String]) {
+        let mut all = Self::default_ignore_patterns();
+        all.extend(patterns.iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(|p| p.to_string()));
+        self.ignore_patterns = Arc::new(all);
+    }
+    
+    /// Load the user-defined ignore patterns from the settings table
+    pub async fn load_ignore_patterns(&mut self) {
+        match self.database.get_setting(IGNORE_PATTERNS_SETTING).await {
+            Ok(Some(value)) => {
+                let patterns: Vec<String> = value.lines().map(|l| l.to_string()).collect();
+                self.set_ignore_patterns(&patterns);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to load watcher ignore patterns: {}", e),
+        }
+    }
+    
+    /// Replace the backup exclusions, see `is_excluded`
+    pub fn set_exclusions(&mut self, exclusions: &[String]) {
+        self.exclusions = Arc::new(exclusions.iter()
+            .map(|e| normalize_exclusion(e))
+            .filter(|e| !e.is_empty())
+            .collect());
+    }
+    
+    /// Load the backup exclusions from the settings table
+    pub async fn load_exclusions(&mut self) {
+        match self.database.get_setting(BACKUP_EXCLUSIONS_SETTING).await {
+            Ok(Some(value)) => {
+                let exclusions: Vec<String> = value.lines().map(|l| l.to_string()).collect();
+                self.set_exclusions(&exclusions);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to load backup exclusions: {}", e),
+        }
+    }
+    
+    /// Check if `path` or one of its folders below `save_dir` matches a backup
+    /// exclusion, so `inis` excludes everything in that folder and `*.png`
+    /// any screenshot. Exclusions are set with `set_exclusions`.
+    pub fn is_excluded(path: &Path, save_dir: &Path, exclusions: &[String]) -> bool {
+        let Ok(relative) = path.strip_prefix(save_dir) else {
+            return false;
+        };
+        
+        let mut prefix = String::new();
+        for component in relative.components() {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(&component.as_os_str().to_string_lossy());
+            
+            let matched = exclusions.iter().any(|exclusion| {
+                // Windows paths are case-insensitive
+                if cfg!(windows) {
+                    glob_match(&exclusion.to_lowercase(), &prefix.to_lowercase())
+                } else {
+                    glob_match(exclusion, &prefix)
+                }
+            });
+            if matched {
+                return true;
+            }
+        }
+        false
+    }
+    
+    /// Check if the file name of `path` matches any of the ignore globs
+    pub fn is_ignored(path: &Path, patterns: &[String]) -> bool {
+        let Some(file_name) = path.file_name().map(|n| n.to_string_lossy()) else {
+            return false;
+        };
+        
+        patterns.iter().any(|pattern| {
+            // Windows file names are case-insensitive
+            if cfg!(windows) {
+                glob_match(&pattern.to_lowercase(), &file_name.to_lowercase())
+            } else {
+                glob_match(pattern, &file_name)
+            }
+        })
+    }
+    
     pub async fn check_for_changes(&self) -> Result<usize> {
         let mut changes_detected = 0;
         let mut hashes = self.file_hashes.lock().await;
         
         // Check all tracked files for changes
         for (path, old_hash) in hashes.clone().iter() {
+            if Self::is_ignored(path, &self.ignore_patterns) || Self::is_excluded(path, &self.save_dir, &self.exclusions) {
+                continue;
+            }
+            
             if path.exists() {
                 match hash_file(path) {
                     Ok(new_hash) => {
                         if &new_hash != old_hash {
-                            info!("File changed: {:?}", path);
                             hashes.insert(path.clone(), new_hash.clone());
-                            changes_detected += 1;
+                            
+                            // Already recorded (e.g. picked up by the file watcher) - not a new change
+                            let recorded_hash = self.database
+                                .get_latest_hash_for_path(&path.to_string_lossy())
+                                .await
+                                .unwrap_or(None);
+                            if recorded_hash.as_deref() == Some(new_hash.as_str()) {
+                                debug!("File rewritten with already recorded content: {:?}", path);
+                                continue;
+                            }
+                            
+                            info!("File changed: {:?}", path);
                             
                             // Get current game name or fallback to extraction
                             let game_name = {
@@ -98,63 +238,10 @@ impl SaveWatcher {
                                 })
                             };
                             
-                            // Detect save type and check if empty
-                            let mut save_type = SaveType::detect(path, &self.emulator_name);
-                            let mut is_empty = false;
-                            
-                            if let SaveType::MemoryCard { ref mut format, ref mut contains_saves, ref mut save_count } = save_type {
-                                // Read file to check if it's empty
-                                if let Ok(data) = tokio::fs::read(path).await {
-                                    is_empty = format.is_empty(&data);
-                                    *contains_saves = !is_empty;
-                                    *save_count = format.count_saves(&data);
-                                    
-                                    if is_empty {
-                                        info!("Skipping empty memory card in check_file: {:?}", path);
-                                        return Ok(0); // Skip empty memory cards, return 0 as file size
-                                    }
-                                }
+                            if let Some(event) = self.build_save_event(path, new_hash, Some(game_name)).await? {
+                                changes_detected += 1;
+                                let _ = self.sender.send(event).await;
                             }
-                            
-                            // Extract game_id from PS2 memory cards
-                            let game_id = if path.extension().map_or(false, |e| e == "ps2") {
-                                if let Ok(data) = tokio::fs::read(path).await {
-                                    if let Some(card) = crate::storage::ps2_memory_card::PS2MemoryCard::new(data) {
-                                        let saves = card.parse_saves();
-                                        // Get the first game ID we find
-                                        saves.values().next().map(|save| save.game_id.clone())
-                                    } else {
-                                        None
-                                    }
-                                } else {
-                                    None
-                                }
-                            } else if path.extension().map_or(false, |e| e == "gci") {
-                                // Extract game ID from GCI file
-                                if let Some(gci) = crate::storage::gci_parser::GCIFile::parse(path) {
-                                    // Combine game code and maker code for full ID (e.g., "GZLE01")
-                                    Some(format!("{}{}", gci.game_code, gci.maker_code))
-                                } else {
-                                    // Try to extract from filename
-                                    path.file_name()
-                                        .and_then(|name| name.to_str())
-                                        .and_then(|name| crate::storage::gci_parser::GCIFile::extract_game_id_from_filename(name))
-                                }
-                            } else {
-                                None
-                            };
-                            
-                            // Send save event
-                            let _ = self.sender.send(SaveEvent {
-                                file_path: path.clone(),
-                                file_hash: new_hash,
-                                file_size: std::fs::metadata(path)?.len(),
-                                game_name,
-                                game_id,
-                                emulator: self.emulator_name.clone(),
-                                save_type,
-                                is_empty,
-                            }).await;
                         }
                     }
                     Err(e) => {
@@ -167,6 +254,112 @@ impl SaveWatcher {
         Ok(changes_detected)
     }
     
+    /// Find saves already in the save directory that have no version in the
+    /// database yet, e.g. saves that predate Retrosave. The returned events
+    /// should be recorded as each file's initial version.
+    pub async fn scan_existing(&self) -> Result<Vec<SaveEvent>> {
+        let mut events = Vec::new();
+        
+        for path in self.collect_save_files()? {
+            let recorded = self.database
+                .get_latest_hash_for_path(&path.to_string_lossy())
+                .await
+                .unwrap_or(None);
+            if recorded.is_some() {
+                continue;
+            }
+            
+            let hash = match hash_file(&path) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    warn!("Failed to hash file {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            
+            // Existing files aren't necessarily from the running game, identify them by content
+            if let Some(event) = self.build_save_event(&path, hash, None).await? {
+                debug!("Found existing save: {:?}", path);
+                events.push(event);
+            }
+        }
+        
+        info!("Found {} existing saves not yet backed up in {:?}", events.len(), self.save_dir);
+        Ok(events)
+    }
+    
+    /// Build the event for a changed save file, `None` for empty memory cards.
+    /// Without a `game_name` the game is derived from the file's content or location.
+    async fn build_save_event(&self, path: &Path, hash: String, game_name: Option<String>) -> Result<Option<SaveEvent>> {
+        // Detect save type and check if empty
+        let mut save_type = SaveType::detect(path, &self.emulator_name);
+        let mut is_empty = false;
+        
+        if let SaveType::MemoryCard { ref mut format, ref mut contains_saves, ref mut save_count } = save_type {
+            // Read file to check if it's empty
+            if let Ok(data) = tokio::fs::read(path).await {
+                is_empty = format.is_empty(&data);
+                *contains_saves = !is_empty;
+                *save_count = format.count_saves(&data);
+                
+                if is_empty {
+                    info!("Skipping empty memory card in check_file: {:?}", path);
+                    return Ok(None);
+                }
+            }
+        }
+        
+        // Extract game_id from PS2 memory cards
+        let game_id = if path.extension().is_some_and(|e| e == "ps2") {
+            if let Ok(data) = tokio::fs::read(path).await {
+                if let Some(card) = crate::storage::ps2_memory_card::PS2MemoryCard::new(data) {
+                    let saves = card.parse_saves();
+                    // Get the first game ID we find
+                    saves.values().next().map(|save| save.game_id.clone())
+                } else {
+                    None
+                }
+            } else {
+                None
+            }
+        } else if path.extension().is_some_and(|e| e == "gci") {
+            // Extract game ID from GCI file
+            if let Some(gci) = crate::storage::gci_parser::GCIFile::parse(path) {
+                // Combine game code and maker code for full ID (e.g., "GZLE01")
+                Some(format!("{}{}", gci.game_code, gci.maker_code))
+            } else {
+                // Try to extract from filename
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(crate::storage::gci_parser::GCIFile::extract_game_id_from_filename)
+            }
+        } else {
+            Self::extract_folder_serial(path, &self.save_dir)
+        };
+        
+        let game_name = game_name.unwrap_or_else(|| {
+            match (&game_id, path.extension().is_some_and(|e| e == "gci")) {
+                (Some(id), true) => crate::storage::gamecube_database::lookup_gamecube_game_name(id),
+                (Some(id), false) => crate::storage::game_database::lookup_game_name(id)
+                    .or_else(|| Self::folder_title(path, &self.save_dir))
+                    .unwrap_or_else(|| Self::extract_game_name(path, &self.save_dir)),
+                (None, _) => Self::extract_game_name(path, &self.save_dir),
+            }
+        });
+        
+        Ok(Some(SaveEvent {
+            file_path: path.to_path_buf(),
+            file_hash: hash,
+            file_size: std::fs::metadata(path)?.len(),
+            game_name,
+            game_id,
+            emulator: self.emulator_name.clone(),
+            save_type,
+            is_empty,
+            deleted: false,
+        }))
+    }
+    
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting save watcher for: {:?}", self.save_dir);
         
@@ -176,6 +369,9 @@ impl SaveWatcher {
             return Ok(());
         }
         
+        self.load_ignore_patterns().await;
+        self.load_exclusions().await;
+        
         // Create file watcher
         let (tx, mut rx) = mpsc::channel(100);
         let file_hashes = self.file_hashes.clone();
@@ -185,6 +381,8 @@ impl SaveWatcher {
         let last_event_times = self.last_event_times.clone();
         let memory_card_tracker = self.memory_card_tracker.clone();
         let emulator_name = self.emulator_name.clone();
+        let ignore_patterns = self.ignore_patterns.clone();
+        let exclusions = self.exclusions.clone();
         
         // Spawn handler for file events
         tokio::spawn(async move {
@@ -198,6 +396,8 @@ impl SaveWatcher {
                     &last_event_times,
                     &memory_card_tracker,
                     &emulator_name,
+                    &ignore_patterns,
+                    &exclusions,
                 ).await {
                     error!("Error handling file event: {}", e);
                 }
@@ -243,14 +443,25 @@ impl SaveWatcher {
         last_event_times: &Arc<Mutex<HashMap<PathBuf, Instant>>>,
         memory_card_tracker: &Arc<Mutex<crate::storage::memory_card_tracker::MemoryCardTracker>>,
         emulator_name: &str,
+        ignore_patterns: &[String],
+        exclusions: &[String],
     ) -> Result<()> {
         const DEBOUNCE_DURATION: Duration = Duration::from_secs(3); // 3 seconds to group PCSX2's multiple writes during save
         
         match event.kind {
             EventKind::Create(_) | EventKind::Modify(_) => {
                 for path in event.paths {
-                    // Check if it's a save file (memory card or save state)
-                    if Self::is_save_file(&path) {
+                    if Self::is_ignored(&path, ignore_patterns) {
+                        debug!("Ignoring event for {:?}", path);
+                        continue;
+                    }
+                    if Self::is_excluded(&path, save_dir, exclusions) {
+                        debug!("Excluded from backup: {:?}", path);
+                        continue;
+                    }
+                    
+                    // Check if it's a save file (memory card, save state or per-game folder)
+                    if Self::is_save_file(&path, save_dir, emulator_name) {
                         // Check debounce - skip if event was too recent
                         let now = Instant::now();
                         let mut last_times = last_event_times.lock().await;
@@ -388,6 +599,10 @@ impl SaveWatcher {
                                     Self::extract_game_name(&path, save_dir)
                                 })
                             }
+                        } else if let Some(serial) = Self::extract_folder_serial(&path, save_dir) {
+                            // Nested per-game folder named after the game's serial
+                            crate::storage::game_database::lookup_game_name(&serial)
+                                .unwrap_or_else(|| Self::extract_game_name(&path, save_dir))
                         } else {
                             // For other saves, use the old approach
                             let current = current_game_name.read().await;
@@ -435,7 +650,7 @@ impl SaveWatcher {
                                         .and_then(|name| crate::storage::gci_parser::GCIFile::extract_game_id_from_filename(name))
                                 }
                         } else {
-                            None
+                            Self::extract_folder_serial(&path, save_dir)
                         };
                         
                         // Send save event
@@ -448,6 +663,7 @@ impl SaveWatcher {
                             file_size,
                             save_type,
                             is_empty,
+                            deleted: false,
                         };
                         
                         info!("Detected save: {} ({} bytes, empty: {})", path.display(), file_size, is_empty);
@@ -455,6 +671,31 @@ impl SaveWatcher {
                     }
                 }
             }
+            EventKind::Remove(_) => {
+                for removed_path in event.paths {
+                    // The file is gone, so only saves indexed before can be recognized
+                    let removed = Self::take_removed_saves(&mut *file_hashes.lock().await, &removed_path);
+                    
+                    for (path, hash) in removed {
+                        last_event_times.lock().await.remove(&path);
+                        memory_card_tracker.lock().await.previous_states.remove(&path);
+                        
+                        info!("Save file removed: {}", path.display());
+                        let event = SaveEvent {
+                            game_name: Self::extract_game_name(&path, save_dir),
+                            game_id: Self::extract_folder_serial(&path, save_dir),
+                            emulator: emulator_name.to_string(),
+                            save_type: SaveType::detect(&path, emulator_name),
+                            file_path: path,
+                            file_hash: hash,
+                            file_size: 0,
+                            is_empty: false,
+                            deleted: true,
+                        };
+                        let _ = sender.send(event).await;
+                    }
+                }
+            }
             _ => {}
         }
         
@@ -464,19 +705,14 @@ impl SaveWatcher {
     async fn scan_existing_saves(&mut self) -> Result<()> {
         debug!("Scanning existing saves in: {:?}", self.save_dir);
         
-        let entries = std::fs::read_dir(&self.save_dir)?;
+        let save_files = self.collect_save_files()?;
         let mut hashes = self.file_hashes.lock().await;
         
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-            
-            if Self::is_save_file(&path) {
-                // Calculate and store initial hash
-                if let Ok(hash) = hash_file(&path) {
-                    hashes.insert(path.clone(), hash);
-                    debug!("Indexed save file: {:?}", path);
-                }
+        for path in save_files {
+            // Calculate and store initial hash
+            if let Ok(hash) = hash_file(&path) {
+                debug!("Indexed save file: {:?}", path);
+                hashes.insert(path, hash);
             }
         }
         
@@ -484,37 +720,162 @@ impl SaveWatcher {
         Ok(())
     }
     
-    fn is_save_file(path: &Path) -> bool {
+    /// All save files in the save directory that aren't ignored
+    fn collect_save_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.save_dir.clone()];
+        
+        // Walk subdirectories too, emulators like PPSSPP and RPCS3 keep one folder per game
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                
+                if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                    pending.push(path);
+                    continue;
+                }
+                
+                if Self::is_save_file(&path, &self.save_dir, &self.emulator_name)
+                    && !Self::is_ignored(&path, &self.ignore_patterns)
+                    && !Self::is_excluded(&path, &self.save_dir, &self.exclusions)
+                {
+                    files.push(path);
+                }
+            }
+        }
+        
+        Ok(files)
+    }
+    
+    /// Forget the indexed saves at `path`, or below it when a whole folder was
+    /// removed, returning them with their last hash
+    fn take_removed_saves(file_hashes: &mut HashMap<PathBuf, String>, path: &Path) -> Vec<(PathBuf, String)> {
+        let removed: Vec<PathBuf> = file_hashes.keys()
+            .filter(|saved| saved.starts_with(path))
+            .cloned()
+            .collect();
+        removed.into_iter()
+            .filter_map(|saved| file_hashes.remove_entry(&saved))
+            .collect()
+    }
+    
+    fn is_save_file(path: &Path, save_dir: &Path, emulator_name: &str) -> bool {
         if let Some(extension) = path.extension() {
             let ext = extension.to_string_lossy().to_lowercase();
             // PCSX2 memory cards (.ps2) and save states (.p2s)
             // Dolphin GCI files (.gci) and raw memory cards (.raw)
-            matches!(ext.as_str(), "ps2" | "p2s" | "mcd" | "mcr" | "gci" | "raw")
-        } else {
-            false
+            // RetroArch battery saves (.srm/.sav) and save-states (.state, .stateN, .state.auto)
+            if matches!(ext.as_str(), "ps2" | "p2s" | "mcd" | "mcr" | "gci" | "raw" | "srm" | "sav" | "state")
+                || ext.strip_prefix("state").is_some_and(|slot| slot.chars().all(|c| c.is_ascii_digit()))
+                || path.to_string_lossy().to_lowercase().ends_with(".state.auto")
+            {
+                return true;
+            }
         }
+        
+        // Anything inside a per-game subfolder is save data, other emulators
+        // keep screenshots and configs in their subfolders
+        PER_GAME_FOLDER_EMULATORS.contains(&emulator_name)
+            && Self::game_folder(path, save_dir).is_some()
+            && path.is_file()
     }
     
-    fn extract_game_name(path: &Path, _save_dir: &Path) -> String {
+    /// First directory below the save directory that contains `path`
+    fn game_folder<'a>(path: &'a Path, save_dir: &Path) -> Option<&'a str> {
+        let relative = path.strip_prefix(save_dir).ok()?;
+        let mut components = relative.components();
+        components.next_back(); // The file itself
+        components.next().and_then(|c| c.as_os_str().to_str())
+    }
+    
+    /// Serial of the per-game folder a nested save lives in
+    /// (e.g. `SAVEDATA/ULUS10041SAVE00/DATA.BIN` -> "ULUS-10041")
+    fn extract_folder_serial(path: &Path, save_dir: &Path) -> Option<String> {
+        let relative = path.strip_prefix(save_dir).ok()?;
+        let mut components = relative.components();
+        components.next_back(); // The file itself
+        components
+            .filter_map(|c| c.as_os_str().to_str())
+            .find_map(crate::storage::game_database::serial_from_folder_name)
+    }
+    
+    /// Game title from the PARAM.SFO of the per-game folder a nested save
+    /// lives in, for PS3 and PSP games missing from the game database
+    fn folder_title(path: &Path, save_dir: &Path) -> Option<String> {
+        let folder = Self::game_folder(path, save_dir)?;
+        let sfo = ParamSfo::parse(save_dir.join(folder).join(PARAM_SFO))?;
+        sfo.title().map(str::to_string)
+    }
+    
+    fn extract_game_name(path: &Path, save_dir: &Path) -> String {
+        // Nested saves are named after their per-game folder
+        if let Some(folder) = Self::game_folder(path, save_dir) {
+            return folder.to_string();
+        }
+        
         // Try to extract game name from file name or directory structure
         if let Some(file_name) = path.file_stem() {
             let name = file_name.to_string_lossy();
             // Remove common prefixes like "Mcd001" or similar
             if name.starts_with("Mcd") || name.starts_with("Memory") {
-                "Unknown Game".to_string()
+                UNKNOWN_GAME.to_string()
             } else {
                 name.to_string()
             }
         } else {
-            "Unknown Game".to_string()
+            UNKNOWN_GAME.to_string()
+        }
+    }
+}
+
+/// Match a file name against a glob supporting `*` and `?`
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it currently covers up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` swallow one more character
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, n));
+        } else {
+            return false;
         }
     }
+    
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
+/// Exclusion as matched by `SaveWatcher::is_excluded`: forward slashes, no
+/// leading or trailing slash
+fn normalize_exclusion(exclusion: &str) -> String {
+    exclusion.trim().replace('\\', "/").trim_matches('/').to_string()
+}
+
+/// Subfolder of a game's backups holding the saves a cloud download replaced
+const PRE_DOWNLOAD_DIR: &str = "pre-download";
+/// Extension of the copies in `PRE_DOWNLOAD_DIR`
+const PRE_DOWNLOAD_EXTENSION: &str = "retrosave-prev";
+/// How many pre-download copies are kept per game
+const MAX_PRE_DOWNLOAD_SNAPSHOTS: usize = 3;
+
 /// Manager for handling save backup and versioning
 pub struct SaveBackupManager {
     backup_dir: PathBuf,
-    max_backups: usize,
+    /// Unpinned versions kept per game for persistent saves and save-states
+    keep_saves: usize,
+    keep_save_states: usize,
     compressor: Compressor,
 }
 
@@ -533,11 +894,23 @@ impl SaveBackupManager {
         
         Ok(Self {
             backup_dir,
-            max_backups: 5,
+            keep_saves: 5,
+            keep_save_states: 3,
             compressor: Compressor::default(),
         })
     }
     
+    /// Set how many versions of persistent saves and of save-states are kept
+    pub fn set_retention(&mut self, keep_saves: usize, keep_save_states: usize) {
+        self.keep_saves = keep_saves.max(1);
+        self.keep_save_states = keep_save_states.max(1);
+    }
+    
+    /// `(persistent saves, save-states)` kept per game
+    pub fn retention(&self) -> (usize, usize) {
+        (self.keep_saves, self.keep_save_states)
+    }
+    
     pub fn backup_save(&self, source: &Path, game_name: &str, version: u32) -> Result<(PathBuf, Option<CompressionStats>)> {
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
         
@@ -552,8 +925,10 @@ impl SaveBackupManager {
         
         // Compress and backup the save file
         let stats = if self.compressor.is_enabled() {
-            let compression_stats = self.compressor.compress_file(source, &backup_path)
+            let temp_path = atomic::temp_path_for(&backup_path);
+            let compression_stats = self.compressor.compress_file(source, &temp_path)
                 .context("Failed to compress and backup save file")?;
+            atomic::replace_file(&temp_path, &backup_path)?;
             
             info!(
                 "Backed up save to: {:?} (compressed {}% smaller)",
@@ -563,7 +938,7 @@ impl SaveBackupManager {
             
             Some(compression_stats)
         } else {
-            std::fs::copy(source, &backup_path)
+            atomic::copy_atomic(source, &backup_path)
                 .context("Failed to backup save file")?;
             info!("Backed up save to: {:?}", backup_path);
             None
@@ -575,11 +950,14 @@ impl SaveBackupManager {
     pub fn restore_save(&self, backup_path: &Path, dest: &Path) -> Result<()> {
         // Check if backup is compressed
         if backup_path.extension().map_or(false, |ext| ext == "zst") {
-            self.compressor.decompress_file(backup_path, dest)
+            // Decompress next to the save and swap it in, so a crash can't leave it half written
+            let temp_path = atomic::temp_path_for(dest);
+            self.compressor.decompress_file(backup_path, &temp_path)
                 .context("Failed to decompress and restore save")?;
+            atomic::replace_file(&temp_path, dest)?;
             info!("Restored compressed save from {:?} to {:?}", backup_path, dest);
         } else {
-            std::fs::copy(backup_path, dest)
+            atomic::copy_atomic(backup_path, dest)
                 .context("Failed to restore save file")?;
             info!("Restored save from {:?} to {:?}", backup_path, dest);
         }
@@ -595,7 +973,113 @@ impl SaveBackupManager {
         self.compressor.set_level(level);
     }
     
-    pub fn cleanup_old_backups(&self, game_name: &str) -> Result<()> {
+    /// Copy a save that is about to be overwritten by a cloud download, so a bad
+    /// download or conflict decision can be undone. Only the newest few copies
+    /// per game are kept; they live apart from the versioned backups.
+    pub fn snapshot_before_overwrite(&self, source: &Path, game_name: &str) -> Result<PathBuf> {
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S%3f");
+        let source_name = source.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "save".to_string());
+        
+        let mut snapshot_path = self.backup_dir.clone();
+        snapshot_path.push(game_name);
+        snapshot_path.push(PRE_DOWNLOAD_DIR);
+        std::fs::create_dir_all(&snapshot_path)?;
+        snapshot_path.push(format!("{}_{}.{}", source_name, timestamp, PRE_DOWNLOAD_EXTENSION));
+        
+        atomic::copy_atomic(source, &snapshot_path)
+            .context("Failed to copy save before overwriting it")?;
+        info!("Saved previous version of {:?} to {:?}", source, snapshot_path);
+        
+        if let Err(e) = self.cleanup_pre_download_snapshots(snapshot_path.parent().unwrap_or(&self.backup_dir)) {
+            warn!("Failed to clean up old pre-download copies: {}", e);
+        }
+        
+        Ok(snapshot_path)
+    }
+    
+    fn cleanup_pre_download_snapshots(&self, dir: &Path) -> Result<()> {
+        let mut snapshots: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.path().extension()
+                    .map(|ext| ext == PRE_DOWNLOAD_EXTENSION)
+                    .unwrap_or(false)
+            })
+            .collect();
+        
+        if snapshots.len() <= MAX_PRE_DOWNLOAD_SNAPSHOTS {
+            return Ok(());
+        }
+        
+        // Oldest first; names carry the timestamp for copies made in the same instant
+        snapshots.sort_by_key(|entry| {
+            (entry.metadata().and_then(|m| m.modified()).ok(), entry.file_name())
+        });
+        
+        let to_delete = snapshots.len() - MAX_PRE_DOWNLOAD_SNAPSHOTS;
+        for entry in snapshots.iter().take(to_delete) {
+            if let Err(e) = std::fs::remove_file(entry.path()) {
+                warn!("Failed to delete old pre-download copy: {}", e);
+            } else {
+                debug!("Deleted old pre-download copy: {:?}", entry.path());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Original vs stored size of all versioned backups. Uncompressed backups
+    /// count as the same size on both sides; unreadable ones are skipped.
+    pub fn aggregate_stats(&self) -> Result<CompressionStats> {
+        let start = Instant::now();
+        let mut original_size = 0;
+        let mut compressed_size = 0;
+        
+        for game_dir in std::fs::read_dir(&self.backup_dir)?.filter_map(|entry| entry.ok()) {
+            if !game_dir.path().is_dir() {
+                continue;
+            }
+            
+            for entry in std::fs::read_dir(game_dir.path())?.filter_map(|entry| entry.ok()) {
+                let path = entry.path();
+                let Ok(stored) = entry.metadata().map(|m| m.len()) else {
+                    continue;
+                };
+                
+                let original = match path.extension().and_then(|ext| ext.to_str()) {
+                    Some("zst") => match super::compression::decompressed_size(&path) {
+                        Ok(size) => size,
+                        Err(e) => {
+                            debug!("Skipping unreadable backup {:?}: {}", path, e);
+                            continue;
+                        }
+                    },
+                    Some("bak") => stored,
+                    _ => continue,
+                };
+                original_size += original;
+                compressed_size += stored;
+            }
+        }
+        
+        let compression_ratio = if original_size == 0 {
+            1.0
+        } else {
+            compressed_size as f32 / original_size as f32
+        };
+        Ok(CompressionStats {
+            original_size,
+            compressed_size,
+            compression_ratio,
+            compression_time_ms: start.elapsed().as_millis(),
+        })
+    }
+    
+    /// Delete the oldest backups of a game beyond the combined retention. Backups in
+    /// `protected` (pinned versions) are kept and don't count towards the limit.
+    pub fn cleanup_old_backups(&self, game_name: &str, protected: &[PathBuf]) -> Result<()> {
         let mut game_backup_dir = self.backup_dir.clone();
         game_backup_dir.push(game_name);
         
@@ -611,9 +1095,11 @@ impl SaveBackupManager {
                     .map(|ext| ext == "bak" || ext == "zst")
                     .unwrap_or(false)
             })
+            .filter(|entry| !protected.contains(&entry.path()))
             .collect();
         
-        if backups.len() <= self.max_backups {
+        let max_backups = self.keep_saves + self.keep_save_states;
+        if backups.len() <= max_backups {
             return Ok(());
         }
         
@@ -625,7 +1111,7 @@ impl SaveBackupManager {
         });
         
         // Delete oldest backups
-        let to_delete = backups.len() - self.max_backups;
+        let to_delete = backups.len() - max_backups;
         for entry in backups.iter().take(to_delete) {
             if let Err(e) = std::fs::remove_file(entry.path()) {
                 warn!("Failed to delete old backup: {}", e);
@@ -636,4 +1122,196 @@ impl SaveBackupManager {
         
         Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Move a versioned backup into the folder of `game_name`, after the game was
+    /// renamed or merged. Returns the new path, or None if it's already there.
+    /// The old folder is removed once nothing is left in it.
+    pub fn relink_backup(&self, backup_path: &Path, game_name: &str) -> Result<Option<PathBuf>> {
+        let file_name = backup_path.file_name()
+            .context("Backup path has no file name")?;
+        let mut target = self.backup_dir.clone();
+        target.push(game_name);
+        if backup_path.parent() == Some(target.as_path()) {
+            return Ok(None);
+        }
+        std::fs::create_dir_all(&target)?;
+        target.push(file_name);
+        if target.exists() {
+            anyhow::bail!("{:?} already exists", target);
+        }
+        
+        std::fs::rename(backup_path, &target)
+            .with_context(|| format!("Failed to move backup {:?}", backup_path))?;
+        debug!("Moved backup {:?} to {:?}", backup_path, target);
+        
+        if let Some(old_dir) = backup_path.parent() {
+            // Fails while other backups or pre-download copies are still in it
+            let _ = std::fs::remove_dir(old_dir);
+        }
+        
+        Ok(Some(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.tmp", "Mcd001.ps2.tmp"));
+        assert!(glob_match("*~", "Mcd001.ps2~"));
+        assert!(glob_match("Mcd00?.ps2", "Mcd002.ps2"));
+        assert!(glob_match("*card*", "my_card_backup.ps2"));
+        assert!(!glob_match("*.tmp", "Mcd001.ps2"));
+        assert!(!glob_match("Mcd00?.ps2", "Mcd0010.ps2"));
+    }
+    
+    #[test]
+    fn test_nested_saves_use_game_folder() {
+        let save_dir = Path::new("/home/user/PSP/SAVEDATA");
+        let nested = save_dir.join("ULUS10041SAVE00").join("DATA.BIN");
+        
+        assert_eq!(SaveWatcher::extract_folder_serial(&nested, save_dir), Some("ULUS-10041".to_string()));
+        assert_eq!(SaveWatcher::extract_game_name(&nested, save_dir), "ULUS10041SAVE00");
+        
+        let top_level = save_dir.join("Mcd001.ps2");
+        assert_eq!(SaveWatcher::extract_folder_serial(&top_level, save_dir), None);
+        assert!(SaveWatcher::is_save_file(&top_level, save_dir, "PCSX2"));
+    }
+    
+    #[test]
+    fn test_only_per_game_folder_emulators_take_any_nested_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let save_dir = temp_dir.path();
+        let screenshot = save_dir.join("snaps").join("Okami.png");
+        std::fs::create_dir_all(screenshot.parent().unwrap()).unwrap();
+        std::fs::write(&screenshot, b"png").unwrap();
+        
+        assert!(SaveWatcher::is_save_file(&screenshot, save_dir, "PPSSPP"));
+        assert!(SaveWatcher::is_save_file(&screenshot, save_dir, "RPCS3"));
+        assert!(!SaveWatcher::is_save_file(&screenshot, save_dir, "PCSX2"));
+        assert!(!SaveWatcher::is_save_file(&screenshot, save_dir, "Unknown"));
+    }
+    
+    #[test]
+    fn test_take_removed_saves() {
+        let save_dir = Path::new("/home/user/PSP/SAVEDATA");
+        let mut hashes = HashMap::new();
+        hashes.insert(save_dir.join("ULUS10041SAVE00").join("DATA.BIN"), "a".to_string());
+        hashes.insert(save_dir.join("ULUS10041SAVE00").join("PARAM.SFO"), "b".to_string());
+        hashes.insert(save_dir.join("ULUS10041SAVE01").join("DATA.BIN"), "c".to_string());
+        
+        // A single file
+        let removed = SaveWatcher::take_removed_saves(&mut hashes, &save_dir.join("ULUS10041SAVE01").join("DATA.BIN"));
+        assert_eq!(removed, vec![(save_dir.join("ULUS10041SAVE01").join("DATA.BIN"), "c".to_string())]);
+        
+        // A whole game folder, but not its sibling with a longer name
+        hashes.insert(save_dir.join("ULUS10041SAVE001").join("DATA.BIN"), "d".to_string());
+        let removed = SaveWatcher::take_removed_saves(&mut hashes, &save_dir.join("ULUS10041SAVE00"));
+        assert_eq!(removed.len(), 2);
+        assert_eq!(hashes.len(), 1);
+        
+        assert!(SaveWatcher::take_removed_saves(&mut hashes, &save_dir.join("Unknown.ps2")).is_empty());
+    }
+    
+    #[test]
+    fn test_folder_title_from_param_sfo() {
+        use crate::storage::param_sfo::{build_sfo, SfoValue};
+        
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let save_dir = temp_dir.path();
+        let folder = save_dir.join("BLUS30443-AUTOSAVE");
+        std::fs::create_dir(&folder).unwrap();
+        let save = folder.join("USR-DATA");
+        
+        assert_eq!(SaveWatcher::folder_title(&save, save_dir), None);
+        
+        let sfo = build_sfo(&[("TITLE", SfoValue::Text("Demon's Souls".to_string()))]);
+        std::fs::write(folder.join(PARAM_SFO), sfo).unwrap();
+        assert_eq!(SaveWatcher::folder_title(&save, save_dir), Some("Demon's Souls".to_string()));
+        assert_eq!(SaveWatcher::extract_folder_serial(&save, save_dir), Some("BLUS-30443".to_string()));
+    }
+    
+    #[test]
+    fn test_default_patterns_ignore_temp_files() {
+        let patterns = SaveWatcher::default_ignore_patterns();
+        assert!(SaveWatcher::is_ignored(Path::new("/saves/Mcd001.ps2.tmp"), &patterns));
+        assert!(SaveWatcher::is_ignored(Path::new("/saves/Mcd001.ps2.lock"), &patterns));
+        assert!(!SaveWatcher::is_ignored(Path::new("/saves/Mcd001.ps2"), &patterns));
+    }
+    
+    #[test]
+    fn test_exclusions_match_relative_to_save_dir() {
+        let save_dir = PathBuf::from("/saves");
+        assert_eq!(normalize_exclusion(" \\inis\\ "), "inis");
+        let exclusions = vec!["inis".to_string(), "*.png".to_string()];
+        
+        let excluded = |path: &str| SaveWatcher::is_excluded(&save_dir.join(path), &save_dir, &exclusions);
+        assert!(excluded("inis/SLUS-20312.ini"));
+        assert!(excluded("snaps/screenshot.png"));
+        assert!(!excluded("Mcd001.ps2"));
+        assert!(!excluded("inis.ps2"));
+        // Only the part below the save directory is matched
+        assert!(!SaveWatcher::is_excluded(Path::new("/inis/Mcd001.ps2"), &save_dir, &exclusions));
+        assert_eq!(excluded("INIS/SLUS-20312.ini"), cfg!(windows));
+    }
+    
+    #[test]
+    fn test_snapshot_before_overwrite_keeps_recent_copies() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = SaveBackupManager::new(Some(temp_dir.path().join("backups"))).unwrap();
+        let save = temp_dir.path().join("Mcd001.ps2");
+        
+        let mut last = PathBuf::new();
+        for i in 0..5u8 {
+            std::fs::write(&save, [i; 4]).unwrap();
+            last = manager.snapshot_before_overwrite(&save, "Test Game").unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        
+        let dir = temp_dir.path().join("backups").join("Test Game").join(PRE_DOWNLOAD_DIR);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), MAX_PRE_DOWNLOAD_SNAPSHOTS);
+        assert_eq!(std::fs::read(&last).unwrap(), vec![4u8; 4]);
+        
+        // Snapshots don't count as versioned backups
+        manager.cleanup_old_backups("Test Game", &[]).unwrap();
+        assert!(dir.exists());
+    }
+    
+    #[test]
+    fn test_aggregate_stats_sums_backups() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = SaveBackupManager::new(Some(temp_dir.path().join("backups"))).unwrap();
+        let save = temp_dir.path().join("Mcd001.ps2");
+        std::fs::write(&save, vec![0u8; 64 * 1024]).unwrap();
+        
+        manager.backup_save(&save, "Game A", 1).unwrap();
+        manager.backup_save(&save, "Game B", 1).unwrap();
+        manager.compressor.set_enabled(false);
+        manager.backup_save(&save, "Game B", 2).unwrap();
+        
+        let stats = manager.aggregate_stats().unwrap();
+        assert_eq!(stats.original_size, 3 * 64 * 1024);
+        assert!(stats.compressed_size < 2 * 64 * 1024);
+        assert_eq!(stats.space_saved(), stats.original_size - stats.compressed_size);
+    }
+    
+    #[test]
+    fn test_relink_backup_moves_into_game_folder() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let manager = SaveBackupManager::new(Some(temp_dir.path().join("backups"))).unwrap();
+        let save = temp_dir.path().join("Mcd001.ps2");
+        std::fs::write(&save, [1u8; 16]).unwrap();
+        
+        let (backup, _) = manager.backup_save(&save, "Unknown PS2 Game", 1).unwrap();
+        let moved = manager.relink_backup(&backup, "Final Fantasy X").unwrap().unwrap();
+        assert_eq!(moved.parent().unwrap(), temp_dir.path().join("backups").join("Final Fantasy X"));
+        assert!(!backup.exists());
+        assert!(!temp_dir.path().join("backups").join("Unknown PS2 Game").exists());
+        
+        assert!(manager.relink_backup(&moved, "Final Fantasy X").unwrap().is_none());
+        manager.restore_save(&moved, &save).unwrap();
+        assert_eq!(std::fs::read(&save).unwrap(), vec![1u8; 16]);
+    }
+}
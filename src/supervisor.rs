@@ -0,0 +1,217 @@
+//! Restarts background tasks that fail or panic
+use anyhow::Result;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+/// Delay before the first restart, doubled after each failure in a row
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+/// A task that ran this long before failing restarts after the minimum delay again
+const HEALTHY_RUN: Duration = Duration::from_secs(300);
+
+/// Why a supervised task was restarted
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure {
+    Error(String),
+    Panic(String),
+    /// No heartbeat within the timeout
+    Stalled,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Error(e) => write!(f, "{}", e),
+            Failure::Panic(message) => write!(f, "panicked: {}", message),
+            Failure::Stalled => write!(f, "stalled"),
+        }
+    }
+}
+
+/// Liveness signal a supervised task updates while it's making progress
+#[derive(Debug, Clone)]
+pub struct Heartbeat(Arc<Mutex<Instant>>);
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+    
+    pub fn beat(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+    
+    /// Time since the last beat
+    pub fn elapsed(&self) -> Duration {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run the task returned by `spawn` until it finishes successfully, restarting
+/// it with backoff when it returns an error or panics. `on_failure` is called
+/// with each failure before the restart.
+pub async fn supervise<F, Fut, N>(name: &str, spawn: F, on_failure: N)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+    N: FnMut(&Failure),
+{
+    run_supervised(name, None, spawn, on_failure).await
+}
+
+/// Like [`supervise`], also restarting the task when `heartbeat` hasn't
+/// beaten for `timeout`, which catches hangs that never panic
+pub async fn supervise_with_heartbeat<F, Fut, N>(
+    name: &str,
+    heartbeat: Heartbeat,
+    timeout: Duration,
+    spawn: F,
+    on_failure: N,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+    N: FnMut(&Failure),
+{
+    run_supervised(name, Some((heartbeat, timeout)), spawn, on_failure).await
+}
+
+async fn run_supervised<F, Fut, N>(
+    name: &str,
+    liveness: Option<(Heartbeat, Duration)>,
+    mut spawn: F,
+    mut on_failure: N,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+    N: FnMut(&Failure),
+{
+    let mut delay = Duration::ZERO;
+    loop {
+        let started = Instant::now();
+        if let Some((heartbeat, _)) = &liveness {
+            heartbeat.beat();
+        }
+        let mut task = tokio::spawn(spawn());
+        
+        let result = loop {
+            let Some((heartbeat, timeout)) = &liveness else {
+                break Some((&mut task).await);
+            };
+            let remaining = timeout.saturating_sub(heartbeat.elapsed());
+            if remaining.is_zero() {
+                // A task stuck in a blocking call only stops once it yields again
+                task.abort();
+                break None;
+            }
+            tokio::select! {
+                result = &mut task => break Some(result),
+                _ = tokio::time::sleep(remaining) => {}
+            }
+        };
+        
+        let failure = match result {
+            Some(Ok(Ok(()))) => {
+                info!("{} stopped", name);
+                return;
+            }
+            Some(Ok(Err(e))) => Failure::Error(e.to_string()),
+            Some(Err(e)) if e.is_panic() => Failure::Panic(panic_message(e.into_panic())),
+            // Aborted from outside, e.g. during shutdown
+            Some(Err(_)) => return,
+            None => Failure::Stalled,
+        };
+
+        delay = restart_delay(delay, started.elapsed());
+        error!("{} failed ({}), restarting in {}s", name, failure, delay.as_secs());
+        on_failure(&failure);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Backoff after a failure, given the previous delay and how long the task ran
+fn restart_delay(previous: Duration, ran_for: Duration) -> Duration {
+    if ran_for >= HEALTHY_RUN || previous.is_zero() {
+        MIN_RESTART_DELAY
+    } else {
+        (previous * 2).min(MAX_RESTART_DELAY)
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    
+    #[test]
+    fn test_restart_delay() {
+        let delay = restart_delay(Duration::ZERO, Duration::from_secs(1));
+        assert_eq!(delay, MIN_RESTART_DELAY);
+        let delay = restart_delay(delay, Duration::from_secs(1));
+        assert_eq!(delay, MIN_RESTART_DELAY * 2);
+
+        // Capped, and reset once the task stayed up for a while
+        assert_eq!(restart_delay(MAX_RESTART_DELAY, Duration::from_secs(1)), MAX_RESTART_DELAY);
+        assert_eq!(restart_delay(MAX_RESTART_DELAY, HEALTHY_RUN), MIN_RESTART_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_restarts_after_panic() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut failures = Vec::new();
+
+        let counter = attempts.clone();
+        supervise("test task", move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("first attempt");
+                }
+                Ok(())
+            }
+        }, |failure| failures.push(failure.clone())).await;
+        
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(failures, vec![Failure::Panic("first attempt".to_string())]);
+    }
+    
+    #[tokio::test]
+    async fn test_restarts_stalled_task() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut failures = Vec::new();
+        
+        let counter = attempts.clone();
+        supervise_with_heartbeat("test task", Heartbeat::new(), Duration::from_millis(50), move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    // Hangs without ever beating
+                    std::future::pending::<()>().await;
+                }
+                Ok(())
+            }
+        }, |failure| failures.push(failure.clone())).await;
+        
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(failures, vec![Failure::Stalled]);
+    }
+}
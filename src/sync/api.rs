@@ -1,13 +1,17 @@
 use anyhow::{Result, Context, anyhow};
+use async_trait::async_trait;
 use reqwest::{Client, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use tracing::{debug, warn};
 use tokio::sync::mpsc;
+use futures_util::StreamExt;
 use crate::payment::{SubscriptionStatus, UsageStats};
 use crate::sync::settings_sync::{UserSettingsResponse, UpdateUserSettings};
+use crate::sync::bandwidth::{BandwidthLimiter, THROTTLE_CHUNK_SIZE};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Game {
@@ -53,6 +57,24 @@ pub struct UploadSaveRequest {
     pub metadata: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct RegisterDeviceRequest {
+    pub device_id: String,
+    pub name: String,
+    pub device_type: String,
+}
+
+/// A device registered to the account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+    pub device_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub device_type: Option<String>,
+    #[serde(default)]
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UploadUrlResponse {
     pub save_id: Uuid,
@@ -71,6 +93,43 @@ pub struct ListSavesResponse {
     pub has_prev: bool,
 }
 
+/// Errors that callers handle differently from other API failures
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    /// The server didn't answer in time. Worth retrying later, unlike auth or quota errors.
+    #[error("{operation}: timed out after {}s, the connection may be slow", timeout.as_secs())]
+    Timeout { operation: &'static str, timeout: Duration },
+}
+
+impl ApiError {
+    /// Whether `err` or anything it wraps is a timeout
+    pub fn is_timeout(err: &anyhow::Error) -> bool {
+        err.chain().any(|cause| {
+            matches!(cause.downcast_ref::<ApiError>(), Some(ApiError::Timeout { .. }))
+                || cause.downcast_ref::<reqwest::Error>().is_some_and(|e| e.is_timeout())
+        })
+    }
+}
+
+/// How long API requests may take
+#[derive(Debug, Clone, Copy)]
+pub struct ApiTimeouts {
+    /// Small JSON requests such as listing games or requesting an upload URL
+    pub metadata: Duration,
+    /// Save uploads and downloads, stretched further when bandwidth is limited
+    pub transfer: Duration,
+}
+
+impl Default for ApiTimeouts {
+    fn default() -> Self {
+        Self {
+            metadata: Duration::from_secs(30),
+            // Room for an 8MB memory card on a slow link
+            transfer: Duration::from_secs(300),
+        }
+    }
+}
+
 /// Events that can occur during API operations
 #[derive(Debug, Clone)]
 pub enum ApiEvent {
@@ -83,18 +142,60 @@ pub struct SyncApi {
     pub base_url: String,
     auth_manager: Arc<super::AuthManager>,
     event_sender: Option<mpsc::UnboundedSender<ApiEvent>>,
+    bandwidth: Arc<BandwidthLimiter>,
+    timeouts: ApiTimeouts,
 }
 
 impl SyncApi {
     pub fn new(base_url: String, auth_manager: Arc<super::AuthManager>) -> Self {
         Self {
-            client: Client::new(),
+            client: super::http::http_client(),
             base_url,
             auth_manager,
             event_sender: None,
+            bandwidth: Arc::new(BandwidthLimiter::default()),
+            timeouts: ApiTimeouts::default(),
         }
     }
     
+    /// Use different request timeouts than the defaults
+    pub fn with_timeouts(mut self, timeouts: ApiTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+    
+    /// Limit save transfers to `kbps` KB/s across all uploads and downloads (0 = unlimited)
+    pub fn set_bandwidth_limit(&self, kbps: u32) {
+        self.bandwidth.set_limit_kbps(kbps);
+    }
+    
+    /// Lightweight connectivity check: any HTTP response from the API counts as reachable
+    pub async fn is_reachable(&self) -> bool {
+        self.client
+            .head(&self.base_url)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .is_ok()
+    }
+    
+    /// Unauthenticated health check, used to tell a wrong or unreachable
+    /// API URL apart from problems with the account
+    pub async fn test_connection(&self) -> Result<()> {
+        let url = format!("{}/health", self.base_url.trim_end_matches('/'));
+        let response = self.client
+            .get(&url)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .with_context(|| format!("Could not reach {}", self.base_url))?;
+        
+        if !response.status().is_success() {
+            return Err(anyhow!("{} answered with {}", url, response.status()));
+        }
+        Ok(())
+    }
+    
     /// Set the event sender for API events
     pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<ApiEvent>) {
         self.event_sender = Some(sender);
@@ -146,9 +247,10 @@ impl SyncApi {
                 emulator: emulator.to_string(),
                 game_id,
             })
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to register game")?;
+            .map_err(|e| send_error(e, "Failed to register game", self.timeouts.metadata))?;
         
         let response = self.check_response(response).await?;
 
@@ -169,9 +271,10 @@ impl SyncApi {
         let response = self.client
             .get(format!("{}/api/saves/games", self.base_url))
             .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to list games")?;
+            .map_err(|e| send_error(e, "Failed to list games", self.timeouts.metadata))?;
         
         let response = self.check_response(response).await?;
 
@@ -183,6 +286,84 @@ impl SyncApi {
             .context("Failed to parse games response")
     }
 
+    /// Register this device with the account, registering again just updates it
+    pub async fn register_device(&self, device_id: &str, name: &str) -> Result<()> {
+        let token = self.auth_manager.get_access_token().await
+            .context("Not authenticated")?;
+
+        let response = self.client
+            .post(format!("{}/api/devices", self.base_url))
+            .bearer_auth(token)
+            .json(&RegisterDeviceRequest {
+                device_id: device_id.to_string(),
+                name: name.to_string(),
+                device_type: std::env::consts::OS.to_string(),
+            })
+            .timeout(self.timeouts.metadata)
+            .send()
+            .await
+            .map_err(|e| send_error(e, "Failed to register device", self.timeouts.metadata))?;
+        
+        let response = self.check_response(response).await?;
+
+        if response.status() == StatusCode::CONFLICT {
+            debug!("Device {} already registered", device_id);
+            return Ok(());
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Failed to register device: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    /// List the devices registered to the account
+    pub async fn list_devices(&self) -> Result<Vec<Device>> {
+        let token = self.auth_manager.get_access_token().await
+            .context("Not authenticated")?;
+
+        let response = self.client
+            .get(format!("{}/api/devices", self.base_url))
+            .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
+            .send()
+            .await
+            .map_err(|e| send_error(e, "Failed to list devices", self.timeouts.metadata))?;
+        
+        let response = self.check_response(response).await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to list devices"));
+        }
+
+        response.json().await
+            .context("Failed to parse devices response")
+    }
+
+    /// Remove a device from the account, freeing its slot
+    pub async fn remove_device(&self, device_id: &str) -> Result<()> {
+        let token = self.auth_manager.get_access_token().await
+            .context("Not authenticated")?;
+
+        let response = self.client
+            .delete(format!("{}/api/devices/{}", self.base_url, device_id))
+            .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
+            .send()
+            .await
+            .map_err(|e| send_error(e, "Failed to remove device", self.timeouts.metadata))?;
+        
+        let response = self.check_response(response).await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Failed to remove device: {}", error_text));
+        }
+
+        Ok(())
+    }
+
     /// Request upload URL for a save file
     pub async fn request_upload_url(
         &self,
@@ -216,9 +397,10 @@ impl SyncApi {
                 client_timestamp: timestamp,
                 metadata,
             })
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to request upload URL")?;
+            .map_err(|e| send_error(e, "Failed to request upload URL", self.timeouts.metadata))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
@@ -241,12 +423,31 @@ impl SyncApi {
         
         debug!("Uploading file to S3: {}", fixed_url);
         
+        let content_length = data.len();
+        let timeout = transfer_timeout(self.timeouts.transfer, content_length, self.bandwidth.limit_kbps());
+        let body = if self.bandwidth.is_limited() {
+            let limiter = self.bandwidth.clone();
+            let chunks: Vec<Vec<u8>> = data.chunks(THROTTLE_CHUNK_SIZE).map(|c| c.to_vec()).collect();
+            let stream = futures_util::stream::iter(chunks).then(move |chunk| {
+                let limiter = limiter.clone();
+                async move {
+                    limiter.acquire(chunk.len()).await;
+                    Ok::<_, std::io::Error>(chunk)
+                }
+            });
+            reqwest::Body::wrap_stream(stream)
+        } else {
+            reqwest::Body::from(data)
+        };
+        
         let response = self.client
             .put(&fixed_url)
-            .body(data)
+            .header(reqwest::header::CONTENT_LENGTH, content_length)
+            .body(body)
+            .timeout(timeout)
             .send()
             .await
-            .context("Failed to upload save data")?;
+            .map_err(|e| send_error(e, "Failed to upload save data", timeout))?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -271,9 +472,10 @@ impl SyncApi {
         let response = self.client
             .get(url)
             .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to list saves")?;
+            .map_err(|e| send_error(e, "Failed to list saves", self.timeouts.metadata))?;
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("Failed to list saves"));
@@ -282,7 +484,7 @@ impl SyncApi {
         response.json().await
             .context("Failed to parse saves response")
     }
-
+    
     /// Get a specific save with download URL
     pub async fn get_save(&self, save_id: Uuid) -> Result<SaveMetadata> {
         let token = self.auth_manager.get_access_token().await
@@ -291,9 +493,10 @@ impl SyncApi {
         let response = self.client
             .get(format!("{}/api/saves/{}", self.base_url, save_id))
             .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to get save")?;
+            .map_err(|e| send_error(e, "Failed to get save", self.timeouts.metadata))?;
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("Failed to get save"));
@@ -305,19 +508,33 @@ impl SyncApi {
 
     /// Download save file data from presigned URL
     pub async fn download_save_data(&self, download_url: &str) -> Result<Vec<u8>> {
+        // The size isn't known up front, so assume a memory card sized save
+        let timeout = transfer_timeout(self.timeouts.transfer, EXPECTED_DOWNLOAD_SIZE, self.bandwidth.limit_kbps());
         let response = self.client
             .get(download_url)
+            .timeout(timeout)
             .send()
             .await
-            .context("Failed to download save data")?;
+            .map_err(|e| send_error(e, "Failed to download save data", timeout))?;
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("Failed to download save data from S3"));
         }
 
-        response.bytes().await
-            .map(|b| b.to_vec())
-            .context("Failed to read save data")
+        if !self.bandwidth.is_limited() {
+            return response.bytes().await
+                .map(|b| b.to_vec())
+                .context("Failed to read save data");
+        }
+        
+        let mut data = Vec::with_capacity(response.content_length().unwrap_or(0) as usize);
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("Failed to read save data")?;
+            self.bandwidth.acquire(chunk.len()).await;
+            data.extend_from_slice(&chunk);
+        }
+        Ok(data)
     }
 
     /// Delete a save
@@ -328,9 +545,10 @@ impl SyncApi {
         let response = self.client
             .delete(format!("{}/api/saves/{}", self.base_url, save_id))
             .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to delete save")?;
+            .map_err(|e| send_error(e, "Failed to delete save", self.timeouts.metadata))?;
 
         if !response.status().is_success() {
             return Err(anyhow::anyhow!("Failed to delete save"));
@@ -339,6 +557,27 @@ impl SyncApi {
         Ok(())
     }
 
+    /// Set or clear the note of a save
+    pub async fn update_save_note(&self, save_id: Uuid, note: Option<&str>) -> Result<()> {
+        let token = self.auth_manager.get_access_token().await
+            .context("Not authenticated")?;
+
+        let response = self.client
+            .patch(format!("{}/api/saves/{}", self.base_url, save_id))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "note": note }))
+            .timeout(self.timeouts.metadata)
+            .send()
+            .await
+            .map_err(|e| send_error(e, "Failed to update save note", self.timeouts.metadata))?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Failed to update save note: {}", response.status()));
+        }
+
+        Ok(())
+    }
+
     /// Get subscription status
     pub async fn get_subscription_status(&self) -> Result<SubscriptionStatus> {
         let token = self.auth_manager.get_access_token().await
@@ -349,9 +588,10 @@ impl SyncApi {
         let response = self.client
             .get(format!("{}/api/subscriptions/status", self.base_url))
             .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to get subscription status")?;
+            .map_err(|e| send_error(e, "Failed to get subscription status", self.timeouts.metadata))?;
 
         let response = self.check_response(response).await?;
 
@@ -367,9 +607,10 @@ impl SyncApi {
         let response = self.client
             .get(format!("{}/api/subscriptions/usage", self.base_url))
             .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
             .send()
             .await
-            .context("Failed to get usage stats")?;
+            .map_err(|e| send_error(e, "Failed to get usage stats", self.timeouts.metadata))?;
 
         let response = self.check_response(response).await?;
 
@@ -385,8 +626,10 @@ impl SyncApi {
         let response = self.client
             .get(format!("{}/api/settings", self.base_url))
             .bearer_auth(token)
+            .timeout(self.timeouts.metadata)
             .send()
-            .await?;
+            .await
+            .map_err(|e| send_error(e, "Failed to fetch settings", self.timeouts.metadata))?;
         
         let response = self.check_response(response).await?;
         
@@ -408,8 +651,10 @@ impl SyncApi {
             .put(format!("{}/api/settings", self.base_url))
             .bearer_auth(token)
             .json(&updates)
+            .timeout(self.timeouts.metadata)
             .send()
-            .await?;
+            .await
+            .map_err(|e| send_error(e, "Failed to update settings", self.timeouts.metadata))?;
         
         let response = self.check_response(response).await?;
         
@@ -421,4 +666,158 @@ impl SyncApi {
             Err(anyhow!("Failed to update settings: {}", error_text))
         }
     }
-}
\ No newline at end of file
+}
+
+/// What `SyncService` needs from the cloud. Implemented by [`SyncApi`], and by
+/// mock backends so the sync logic can be tested without a server.
+#[async_trait]
+pub trait CloudApi: Send + Sync {
+    fn base_url(&self) -> &str;
+    
+    /// Whether syncing needs a signed in account, backends that aren't the
+    /// Retrosave server may not
+    fn requires_auth(&self) -> bool {
+        true
+    }
+    
+    fn set_bandwidth_limit(&self, kbps: u32);
+    
+    async fn is_reachable(&self) -> bool;
+    
+    /// Register a new game or get the existing one
+    async fn register_game(&self, name: &str, emulator: &str) -> Result<Game> {
+        self.register_game_with_id(name, emulator, None).await
+    }
+    
+    async fn register_game_with_id(&self, name: &str, emulator: &str, game_id: Option<String>) -> Result<Game>;
+    
+    async fn register_device(&self, device_id: &str, name: &str) -> Result<()>;
+    
+    async fn request_upload_url_with_metadata(
+        &self,
+        game_id: Uuid,
+        file_hash: &str,
+        file_size: i64,
+        timestamp: DateTime<Utc>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<UploadUrlResponse>;
+    
+    async fn upload_save_data(&self, upload_url: &str, data: Vec<u8>) -> Result<()>;
+    
+    async fn list_saves(&self, game_id: Option<Uuid>, page: i64, per_page: i64) -> Result<ListSavesResponse>;
+    
+    async fn download_save_data(&self, download_url: &str) -> Result<Vec<u8>>;
+    
+    /// Set or clear the note of a save, `note` in its metadata
+    async fn update_save_note(&self, save_id: Uuid, note: Option<&str>) -> Result<()>;
+    
+    async fn get_subscription_status(&self) -> Result<SubscriptionStatus>;
+    
+    async fn get_usage_stats(&self) -> Result<UsageStats>;
+}
+
+#[async_trait]
+impl CloudApi for SyncApi {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+    
+    fn set_bandwidth_limit(&self, kbps: u32) {
+        SyncApi::set_bandwidth_limit(self, kbps)
+    }
+    
+    async fn is_reachable(&self) -> bool {
+        SyncApi::is_reachable(self).await
+    }
+    
+    async fn register_game_with_id(&self, name: &str, emulator: &str, game_id: Option<String>) -> Result<Game> {
+        SyncApi::register_game_with_id(self, name, emulator, game_id).await
+    }
+    
+    async fn register_device(&self, device_id: &str, name: &str) -> Result<()> {
+        SyncApi::register_device(self, device_id, name).await
+    }
+    
+    async fn request_upload_url_with_metadata(
+        &self,
+        game_id: Uuid,
+        file_hash: &str,
+        file_size: i64,
+        timestamp: DateTime<Utc>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<UploadUrlResponse> {
+        SyncApi::request_upload_url_with_metadata(self, game_id, file_hash, file_size, timestamp, metadata).await
+    }
+    
+    async fn upload_save_data(&self, upload_url: &str, data: Vec<u8>) -> Result<()> {
+        SyncApi::upload_save_data(self, upload_url, data).await
+    }
+    
+    async fn list_saves(&self, game_id: Option<Uuid>, page: i64, per_page: i64) -> Result<ListSavesResponse> {
+        SyncApi::list_saves(self, game_id, page, per_page).await
+    }
+    
+    async fn download_save_data(&self, download_url: &str) -> Result<Vec<u8>> {
+        SyncApi::download_save_data(self, download_url).await
+    }
+    
+    async fn update_save_note(&self, save_id: Uuid, note: Option<&str>) -> Result<()> {
+        SyncApi::update_save_note(self, save_id, note).await
+    }
+    
+    async fn get_subscription_status(&self) -> Result<SubscriptionStatus> {
+        SyncApi::get_subscription_status(self).await
+    }
+    
+    async fn get_usage_stats(&self) -> Result<UsageStats> {
+        SyncApi::get_usage_stats(self).await
+    }
+}
+
+/// Size used to stretch the download timeout when bandwidth is limited
+const EXPECTED_DOWNLOAD_SIZE: usize = 8 * 1024 * 1024;
+
+/// Turn a failed request into an error, keeping timeouts recognizable
+fn send_error(e: reqwest::Error, operation: &'static str, timeout: Duration) -> anyhow::Error {
+    if e.is_timeout() {
+        ApiError::Timeout { operation, timeout }.into()
+    } else {
+        anyhow::Error::new(e).context(operation)
+    }
+}
+
+/// Timeout for transferring `size` bytes: the base timeout, or twice the time
+/// the bandwidth limit needs for it if that is longer
+fn transfer_timeout(base: Duration, size: usize, limit_kbps: u32) -> Duration {
+    if limit_kbps == 0 {
+        return base;
+    }
+    let throttled = Duration::from_secs_f64(size as f64 / (limit_kbps as f64 * 1024.0) * 2.0);
+    base.max(throttled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_transfer_timeout_stretches_for_bandwidth_limit() {
+        let base = Duration::from_secs(300);
+        assert_eq!(transfer_timeout(base, 8 * 1024 * 1024, 0), base);
+        assert_eq!(transfer_timeout(base, 1024 * 1024, 1024), base);
+        // 8MB at 10 KB/s takes ~820s, doubled for headroom
+        assert_eq!(transfer_timeout(base, 8 * 1024 * 1024, 10).as_secs(), 1638);
+    }
+    
+    #[test]
+    fn test_timeout_errors_are_recognized() {
+        let err: anyhow::Error = ApiError::Timeout { operation: "Failed to upload save data", timeout: Duration::from_secs(300) }.into();
+        assert!(ApiError::is_timeout(&err));
+        assert!(ApiError::is_timeout(&err.context("Upload failed")));
+        assert!(!ApiError::is_timeout(&anyhow!("Failed to upload save data to S3: 403 Forbidden")));
+        assert_eq!(
+            ApiError::Timeout { operation: "Failed to list games", timeout: Duration::from_secs(30) }.to_string(),
+            "Failed to list games: timed out after 30s, the connection may be slow"
+        );
+    }
+}
@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::path::PathBuf;
 use tokio::sync::RwLock;
-use tracing::{info, debug};
+use tracing::{info, debug, warn};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
 use base64::{Engine as _, engine::general_purpose};
 use aes_gcm::{
     aead::{Aead, KeyInit, OsRng},
@@ -12,8 +14,20 @@ use aes_gcm::{
 use argon2::Argon2;
 use rand::RngCore;
 
+use crate::storage::database::DEFAULT_PROFILE;
+
 // File-based storage for cross-platform compatibility
 const AUTH_FILE_NAME: &str = "auth.json";
+/// Refresh the access token this long before it expires
+const REFRESH_AHEAD: Duration = Duration::from_secs(60);
+/// Longest the refresh task sleeps before re-checking, so new logins are picked up
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+/// Wait before retrying a failed proactive refresh
+const REFRESH_RETRY_DELAY: Duration = Duration::from_secs(30);
+/// Longest allowed profile name
+const MAX_PROFILE_NAME_LEN: usize = 32;
+/// How long `panic_logout` waits on the server for each session it revokes
+const PANIC_LOGOUT_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Storage format for tokens
 #[derive(Debug, Serialize, Deserialize)]
@@ -43,23 +57,54 @@ pub struct AuthState {
     pub is_authenticated: bool,
     pub user: Option<UserInfo>,
     pub tokens: Option<AuthTokens>,
+    pub expires_at: Option<DateTime<Utc>>,  // When the access token expires
 }
 
 pub struct AuthManager {
     state: Arc<RwLock<AuthState>>,
     api_base_url: String,
+    /// Profile whose tokens are loaded and stored
+    profile: std::sync::RwLock<String>,
 }
 
 impl AuthManager {
     pub fn new(api_base_url: String) -> Self {
+        Self::with_profile(api_base_url, DEFAULT_PROFILE.to_string())
+    }
+    
+    /// Auth manager for a named cloud profile
+    pub fn with_profile(api_base_url: String, profile: String) -> Self {
         Self {
             state: Arc::new(RwLock::new(AuthState {
                 is_authenticated: false,
                 user: None,
                 tokens: None,
+                expires_at: None,
             })),
             api_base_url,
+            profile: std::sync::RwLock::new(profile),
+        }
+    }
+    
+    /// Name of the profile currently in use
+    pub fn profile(&self) -> String {
+        self.profile.read().unwrap().clone()
+    }
+    
+    /// Switch to another profile's account. The current account stays logged in
+    /// on the server and its tokens are kept, so switching back needs no new login.
+    pub async fn switch_profile(&self, profile: &str) -> Result<()> {
+        {
+            let mut state = self.state.write().await;
+            state.is_authenticated = false;
+            state.user = None;
+            state.tokens = None;
+            state.expires_at = None;
         }
+        *self.profile.write().unwrap() = profile.to_string();
+        
+        info!("Switched to profile {}", profile);
+        self.init().await
     }
 
     /// Initialize by loading tokens from keyring
@@ -73,6 +118,7 @@ impl AuthManager {
                 let mut state = self.state.write().await;
                 state.is_authenticated = true;
                 state.user = Some(user.clone());
+                state.expires_at = Some(token_expiry(&tokens));
                 state.tokens = Some(tokens);
                 // Successfully authenticated with stored tokens
             } else {
@@ -87,6 +133,7 @@ impl AuthManager {
                             let mut state = self.state.write().await;
                             state.is_authenticated = true;
                             state.user = Some(user.clone());
+                            state.expires_at = Some(token_expiry(&new_tokens));
                             state.tokens = Some(new_tokens);
                             // Successfully authenticated after refresh
                         },
@@ -94,6 +141,7 @@ impl AuthManager {
                             // Still update tokens and mark as authenticated since refresh worked
                             let mut state = self.state.write().await;
                             state.is_authenticated = true;
+                            state.expires_at = Some(token_expiry(&new_tokens));
                             state.tokens = Some(new_tokens);
                         }
                     }
@@ -111,7 +159,7 @@ impl AuthManager {
 
     /// Register a new user
     pub async fn register(&self, email: &str, username: &str, password: &str) -> Result<()> {
-        let client = reqwest::Client::new();
+        let client = crate::sync::http::http_client();
         
         #[derive(Serialize)]
         struct RegisterRequest<'a> {
@@ -156,6 +204,7 @@ impl AuthManager {
         let mut state = self.state.write().await;
         state.is_authenticated = true;
         state.user = Some(register_response.user);
+        state.expires_at = Some(token_expiry(&tokens));
         state.tokens = Some(tokens);
 
         // Successfully registered and authenticated
@@ -164,7 +213,7 @@ impl AuthManager {
 
     /// Login with existing credentials
     pub async fn login(&self, email: &str, password: &str) -> Result<()> {
-        let client = reqwest::Client::new();
+        let client = crate::sync::http::http_client();
         
         #[derive(Serialize)]
         struct LoginRequest<'a> {
@@ -208,6 +257,7 @@ impl AuthManager {
         let mut state = self.state.write().await;
         state.is_authenticated = true;
         state.user = Some(login_response.user);
+        state.expires_at = Some(token_expiry(&tokens));
         state.tokens = Some(tokens);
 
         info!("Successfully logged in");
@@ -219,7 +269,7 @@ impl AuthManager {
         let state = self.state.read().await;
         
         if let Some(tokens) = &state.tokens {
-            let client = reqwest::Client::new();
+            let client = crate::sync::http::http_client();
             
             // Call logout endpoint to invalidate token on server
             let _ = client
@@ -237,11 +287,83 @@ impl AuthManager {
         state.is_authenticated = false;
         state.user = None;
         state.tokens = None;
+        state.expires_at = None;
 
         info!("Logged out successfully");
         Ok(())
     }
 
+    /// Sign out of every profile and delete all stored tokens, for a machine that
+    /// may be in the wrong hands. Sessions are revoked on the server where it can
+    /// be reached, but the local wipe doesn't depend on that. Fails if any token
+    /// file is left behind.
+    pub async fn panic_logout(&self) -> Result<()> {
+        // Drop the in-memory tokens first so the refresh task can't store new ones
+        let current = {
+            let mut state = self.state.write().await;
+            state.is_authenticated = false;
+            state.user = None;
+            state.expires_at = None;
+            state.tokens.take()
+        };
+        
+        let auth_files = Self::stored_auth_files()?;
+        let mut access_tokens: Vec<String> = current.into_iter().map(|t| t.access_token).collect();
+        for auth_file in &auth_files {
+            match Self::read_token_file(auth_file) {
+                Ok(tokens) if !access_tokens.contains(&tokens.access_token) => access_tokens.push(tokens.access_token),
+                Ok(_) => {}
+                Err(e) => debug!("Could not read {:?} for revocation: {}", auth_file, e),
+            }
+        }
+        
+        let client = crate::sync::http::http_client();
+        for access_token in &access_tokens {
+            let result = client
+                .post(format!("{}/api/auth/logout", self.api_base_url))
+                .bearer_auth(access_token)
+                .timeout(PANIC_LOGOUT_TIMEOUT)
+                .send()
+                .await;
+            if let Err(e) = result {
+                warn!("Could not revoke a session on the server: {}", e);
+            }
+        }
+        
+        let mut failed = Vec::new();
+        for auth_file in &auth_files {
+            if let Err(e) = std::fs::remove_file(auth_file) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to delete {:?}: {}", auth_file, e);
+                    failed.push(auth_file.display().to_string());
+                }
+            }
+        }
+        if !failed.is_empty() {
+            return Err(anyhow::anyhow!("Could not delete stored credentials: {}", failed.join(", ")));
+        }
+        
+        info!("Wiped local credentials of {} profile(s)", auth_files.len());
+        Ok(())
+    }
+    
+    /// Auth files of all profiles
+    fn stored_auth_files() -> Result<Vec<PathBuf>> {
+        let Some(data_dir) = dirs::data_dir() else {
+            return Ok(Vec::new());
+        };
+        let dir = data_dir.join("retrosave");
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        Ok(std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| is_auth_file_name(&entry.file_name().to_string_lossy()))
+            .map(|entry| entry.path())
+            .collect())
+    }
+
     /// Check if authenticated
     pub async fn is_authenticated(&self) -> bool {
         let state = self.state.read().await;
@@ -260,6 +382,56 @@ impl AuthManager {
         state.tokens.as_ref().map(|t| t.access_token.clone())
     }
     
+    /// When the current access token expires
+    pub async fn token_expiry(&self) -> Option<DateTime<Utc>> {
+        self.state.read().await.expires_at
+    }
+    
+    /// Exchange the refresh token for a new access token right away
+    pub async fn force_refresh(&self) -> Result<()> {
+        let refresh_token = {
+            let state = self.state.read().await;
+            state.tokens.as_ref()
+                .map(|t| t.refresh_token.clone())
+                .ok_or_else(|| anyhow::anyhow!("Not authenticated"))?
+        };
+        
+        let new_tokens = self.refresh_token(&refresh_token).await?;
+        self.store_tokens(&new_tokens)?;
+        
+        let mut state = self.state.write().await;
+        state.expires_at = Some(token_expiry(&new_tokens));
+        state.tokens = Some(new_tokens);
+        
+        info!("Access token refreshed, expires at {:?}", state.expires_at);
+        Ok(())
+    }
+    
+    /// Spawn a background task that refreshes the access token shortly before it expires,
+    /// so requests and WebSocket reconnects don't run into avoidable 401s
+    pub fn start_refresh_task(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let Some(expires_at) = self.token_expiry().await else {
+                    tokio::time::sleep(REFRESH_CHECK_INTERVAL).await;
+                    continue;
+                };
+                
+                let refresh_at = expires_at - chrono::Duration::from_std(REFRESH_AHEAD).unwrap_or_default();
+                let wait = (refresh_at - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+                if !wait.is_zero() {
+                    tokio::time::sleep(wait.min(REFRESH_CHECK_INTERVAL)).await;
+                    continue;
+                }
+                
+                if let Err(e) = self.force_refresh().await {
+                    warn!("Proactive token refresh failed: {}", e);
+                    tokio::time::sleep(REFRESH_RETRY_DELAY).await;
+                }
+            }
+        })
+    }
+    
     /// Save tokens from OAuth flow
     pub async fn save_tokens(
         &self,
@@ -288,6 +460,7 @@ impl AuthManager {
         let mut state = self.state.write().await;
         state.is_authenticated = true;
         state.user = Some(user_info.clone());
+        state.expires_at = Some(token_expiry(&tokens));
         state.tokens = Some(tokens);
         
         info!("Successfully saved OAuth tokens for user: {}", user_info.email);
@@ -296,7 +469,7 @@ impl AuthManager {
 
     /// Refresh the access token
     async fn refresh_token(&self, refresh_token: &str) -> Result<AuthTokens> {
-        let client = reqwest::Client::new();
+        let client = crate::sync::http::http_client();
         
         #[derive(Serialize)]
         struct RefreshRequest<'a> {
@@ -335,7 +508,7 @@ impl AuthManager {
 
     /// Get user info from API
     async fn fetch_user_info(&self, access_token: &str) -> Result<UserInfo> {
-        let client = reqwest::Client::new();
+        let client = crate::sync::http::http_client();
         
         debug!("Fetching user profile with token: {}...", &access_token[..20.min(access_token.len())]);
         
@@ -357,8 +530,8 @@ impl AuthManager {
             .context("Failed to parse user info")
     }
 
-    /// Get auth file path
-    fn get_auth_file_path() -> Result<PathBuf> {
+    /// Get auth file path of the current profile
+    fn get_auth_file_path(&self) -> Result<PathBuf> {
         let data_dir = dirs::data_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not determine data directory"))?
             .join("retrosave");
@@ -366,7 +539,25 @@ impl AuthManager {
         // Create directory if it doesn't exist
         std::fs::create_dir_all(&data_dir)?;
         
-        Ok(data_dir.join(AUTH_FILE_NAME))
+        Ok(data_dir.join(auth_file_name(&self.profile())))
+    }
+    
+    /// Whether tokens are stored for the current profile. Fails if they are
+    /// but can't be read or decrypted, e.g. after copying the data directory
+    /// to another machine.
+    pub fn has_readable_tokens(&self) -> Result<bool> {
+        Ok(self.load_tokens_from_keyring()?.is_some())
+    }
+
+    /// Remove the stored tokens of a profile that is being deleted
+    pub fn remove_profile_tokens(profile: &str) -> Result<()> {
+        if let Some(data_dir) = dirs::data_dir() {
+            let auth_file = data_dir.join("retrosave").join(auth_file_name(profile));
+            if auth_file.exists() {
+                std::fs::remove_file(&auth_file)?;
+            }
+        }
+        Ok(())
     }
     
     /// Derive encryption key from machine-specific data
@@ -398,7 +589,7 @@ impl AuthManager {
     
     /// Store tokens in file (encrypted)
     fn store_tokens(&self, tokens: &AuthTokens) -> Result<()> {
-        let auth_file = Self::get_auth_file_path()?;
+        let auth_file = self.get_auth_file_path()?;
         
         // Generate random salt and nonce
         let mut salt = [0u8; 32];
@@ -450,15 +641,22 @@ impl AuthManager {
 
     /// Load tokens from file
     fn load_tokens_from_keyring(&self) -> Result<Option<AuthTokens>> {
-        let auth_file = Self::get_auth_file_path()?;
+        let auth_file = self.get_auth_file_path()?;
         
         if !auth_file.exists() {
             debug!("No auth file found at {:?}", auth_file);
             return Ok(None);
         }
         
+        let tokens = Self::read_token_file(&auth_file)?;
+        info!("Found stored tokens in auth file");
+        Ok(Some(tokens))
+    }
+    
+    /// Read and decrypt an auth file
+    fn read_token_file(auth_file: &std::path::Path) -> Result<AuthTokens> {
         // Read file
-        let json = std::fs::read_to_string(&auth_file)
+        let json = std::fs::read_to_string(auth_file)
             .context("Failed to read auth file")?;
         
         // Parse JSON
@@ -483,16 +681,13 @@ impl AuthManager {
             .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))?;
         
         // Parse decrypted JSON to get tokens
-        let tokens: AuthTokens = serde_json::from_slice(&decrypted)
-            .context("Failed to parse decrypted tokens")?;
-        
-        info!("Found stored tokens in auth file");
-        Ok(Some(tokens))
+        serde_json::from_slice(&decrypted)
+            .context("Failed to parse decrypted tokens")
     }
 
     /// Clear tokens from file
     fn clear_tokens(&self) -> Result<()> {
-        let auth_file = Self::get_auth_file_path()?;
+        let auth_file = self.get_auth_file_path()?;
         
         if auth_file.exists() {
             std::fs::remove_file(&auth_file)?;
@@ -506,4 +701,90 @@ impl AuthManager {
     pub async fn get_state(&self) -> AuthState {
         self.state.read().await.clone()
     }
-}
\ No newline at end of file
+}
+
+/// Whether a profile name is usable, it ends up in the auth file name
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The default profile keeps the file name from before profiles existed
+fn auth_file_name(profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        AUTH_FILE_NAME.to_string()
+    } else {
+        format!("auth-{}.json", profile)
+    }
+}
+
+/// Whether a file in the data directory holds some profile's tokens
+fn is_auth_file_name(name: &str) -> bool {
+    name == AUTH_FILE_NAME
+        || name.strip_prefix("auth-")
+            .and_then(|rest| rest.strip_suffix(".json"))
+            .is_some_and(is_valid_profile_name)
+}
+
+/// Expiry of an access token, read from the JWT `exp` claim when present,
+/// otherwise `expires_in` seconds from now
+fn token_expiry(tokens: &AuthTokens) -> DateTime<Utc> {
+    jwt_expiry(&tokens.access_token)
+        .unwrap_or_else(|| Utc::now() + chrono::Duration::seconds(tokens.expires_in))
+}
+
+/// Read the `exp` claim from a JWT without verifying it
+fn jwt_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let payload = token.split('.').nth(1)?;
+    let decoded = general_purpose::URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&decoded).ok()?;
+    DateTime::from_timestamp(claims.get("exp")?.as_i64()?, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jwt_expiry() {
+        let payload = general_purpose::URL_SAFE_NO_PAD.encode(r#"{"sub":"user","exp":1700000000}"#);
+        let token = format!("header.{}.signature", payload);
+        assert_eq!(jwt_expiry(&token).unwrap().timestamp(), 1700000000);
+
+        assert!(jwt_expiry("not-a-jwt").is_none());
+    }
+
+    #[test]
+    fn test_profile_names() {
+        assert!(is_valid_profile_name("kids"));
+        assert!(is_valid_profile_name("player_2"));
+        assert!(!is_valid_profile_name(""));
+        assert!(!is_valid_profile_name("../auth"));
+        assert!(!is_valid_profile_name(&"a".repeat(MAX_PROFILE_NAME_LEN + 1)));
+        
+        assert_eq!(auth_file_name(DEFAULT_PROFILE), AUTH_FILE_NAME);
+        assert_eq!(auth_file_name("kids"), "auth-kids.json");
+    }
+
+    #[test]
+    fn test_auth_file_names() {
+        assert!(is_auth_file_name(AUTH_FILE_NAME));
+        assert!(is_auth_file_name(&auth_file_name("kids")));
+        assert!(!is_auth_file_name("auth-.json"));
+        assert!(!is_auth_file_name("encryption.json"));
+        assert!(!is_auth_file_name("retrosave.db"));
+    }
+
+    #[test]
+    fn test_token_expiry_falls_back_to_expires_in() {
+        let tokens = AuthTokens {
+            access_token: "opaque-token".to_string(),
+            refresh_token: "refresh".to_string(),
+            expires_in: 3600,
+        };
+        let expiry = token_expiry(&tokens);
+        let expected = Utc::now() + chrono::Duration::seconds(3600);
+        assert!((expected - expiry).num_seconds().abs() < 5);
+    }
+}
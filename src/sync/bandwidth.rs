@@ -0,0 +1,113 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Size of the chunks transfers are split into when a limit is active
+pub const THROTTLE_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Token bucket shared by every upload and download so the aggregate
+/// transfer rate stays under the configured cap
+pub struct BandwidthLimiter {
+    /// Limit in KB/s, 0 = unlimited
+    limit_kbps: AtomicU32,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    available: f64,
+    last_refill: Instant,
+}
+
+impl BandwidthLimiter {
+    pub fn new(limit_kbps: u32) -> Self {
+        Self {
+            limit_kbps: AtomicU32::new(limit_kbps),
+            bucket: Mutex::new(Bucket {
+                available: 0.0,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Change the limit, takes effect for the next chunk of every transfer
+    pub fn set_limit_kbps(&self, limit_kbps: u32) {
+        self.limit_kbps.store(limit_kbps, Ordering::Relaxed);
+    }
+
+    pub fn limit_kbps(&self) -> u32 {
+        self.limit_kbps.load(Ordering::Relaxed)
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.limit_kbps() > 0
+    }
+
+    /// Wait until `bytes` may be transferred without exceeding the limit
+    pub async fn acquire(&self, bytes: usize) {
+        let limit = self.limit_kbps();
+        if limit == 0 {
+            return;
+        }
+
+        let rate = limit as f64 * 1024.0; // bytes per second
+        let wait = {
+            let mut bucket = self.bucket.lock().await;
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            // Allow at most one second of burst
+            bucket.available = (bucket.available + elapsed * rate).min(rate);
+            bucket.last_refill = now;
+
+            // Reserve the bytes up front; a negative balance makes later callers wait their turn
+            bucket.available -= bytes as f64;
+            if bucket.available < 0.0 {
+                Duration::from_secs_f64(-bucket.available / rate)
+            } else {
+                Duration::ZERO
+            }
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl Default for BandwidthLimiter {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unlimited_does_not_wait() {
+        let limiter = BandwidthLimiter::default();
+        let start = Instant::now();
+        limiter.acquire(100 * 1024 * 1024).await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_limit_is_shared() {
+        let limiter = std::sync::Arc::new(BandwidthLimiter::new(100));
+        let start = Instant::now();
+
+        // Two concurrent transfers of 50KB each at 100KB/s should take about a second
+        let a = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire(50 * 1024).await })
+        };
+        let b = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire(50 * 1024).await })
+        };
+        a.await.unwrap();
+        b.await.unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(900));
+    }
+}
@@ -70,6 +70,12 @@ pub struct ResolutionResult {
 pub struct ConflictAnalyzer;
 
 impl ConflictAnalyzer {
+    /// Last modification time of a local save, used as its timestamp in conflict analysis
+    pub async fn file_modified_time(path: &std::path::Path) -> Option<DateTime<Utc>> {
+        let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+        Some(DateTime::<Utc>::from(modified))
+    }
+    
     /// Analyze conflicts between local and cloud memory cards
     pub fn analyze_memory_card_conflicts(
         local_metadata: &retrosave_shared::MemoryCardMetadata,
@@ -341,4 +347,53 @@ mod tests {
         assert_eq!(conflicts.len(), 1);
         assert_eq!(conflicts[0].conflict_type, ConflictType::LocalNewer);
     }
+    
+    fn card_metadata(save_count: usize) -> retrosave_shared::MemoryCardMetadata {
+        retrosave_shared::MemoryCardMetadata {
+            games_contained: vec![
+                retrosave_shared::GameInfo {
+                    game_id: "SLUS-20552".to_string(),
+                    game_name: "Test Game".to_string(),
+                    save_count,
+                },
+            ],
+            primary_game: "Test Game".to_string(),
+            total_saves: save_count as _,
+            format_version: "1.0".to_string(),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_file_mtime_decides_newer_side() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cloud_time = Utc::now() - chrono::Duration::hours(3);
+        
+        // Local card written before the cloud upload
+        let old_card = temp_dir.path().join("old.ps2");
+        std::fs::write(&old_card, b"old").unwrap();
+        std::fs::File::options().write(true).open(&old_card).unwrap()
+            .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(6 * 3600))
+            .unwrap();
+        
+        // Local card written after the cloud upload
+        let new_card = temp_dir.path().join("new.ps2");
+        std::fs::write(&new_card, b"new").unwrap();
+        
+        let old_time = ConflictAnalyzer::file_modified_time(&old_card).await.unwrap();
+        let new_time = ConflictAnalyzer::file_modified_time(&new_card).await.unwrap();
+        
+        let conflicts = ConflictAnalyzer::analyze_memory_card_conflicts(
+            &card_metadata(1), &card_metadata(2), "local", "cloud", old_time, cloud_time,
+        );
+        assert_eq!(conflicts[0].conflict_type, ConflictType::CloudNewer);
+        let result = ConflictAnalyzer::resolve_conflicts(&conflicts, ResolutionStrategy::AlwaysNewer);
+        assert_eq!(result.games_kept_cloud, vec!["Test Game".to_string()]);
+        
+        let conflicts = ConflictAnalyzer::analyze_memory_card_conflicts(
+            &card_metadata(2), &card_metadata(1), "local", "cloud", new_time, cloud_time,
+        );
+        assert_eq!(conflicts[0].conflict_type, ConflictType::LocalNewer);
+        let result = ConflictAnalyzer::resolve_conflicts(&conflicts, ResolutionStrategy::AlwaysNewer);
+        assert_eq!(result.games_kept_local, vec!["Test Game".to_string()]);
+    }
 }
\ No newline at end of file
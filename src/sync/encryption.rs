@@ -26,6 +26,47 @@ pub struct EncryptedSave {
     pub version: u8,
 }
 
+/// Where the encryption key's salt comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncryptionMode {
+    /// Random salt kept on this device, only this device can decrypt
+    #[default]
+    DeviceKey,
+    /// Salt derived from the account, any device with the passphrase can decrypt
+    Passphrase,
+}
+
+impl EncryptionMode {
+    pub const ALL: [EncryptionMode; 2] = [
+        EncryptionMode::Passphrase,
+        EncryptionMode::DeviceKey,
+    ];
+    
+    /// Stable identifier used when persisting the setting
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EncryptionMode::DeviceKey => "device_key",
+            EncryptionMode::Passphrase => "passphrase",
+        }
+    }
+    
+    pub fn from_key(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_str() == value)
+    }
+    
+    /// Human readable name for the settings window
+    pub fn label(&self) -> &'static str {
+        match self {
+            EncryptionMode::DeviceKey => "This device only",
+            EncryptionMode::Passphrase => "Passphrase (all my devices)",
+        }
+    }
+}
+
+/// Marks exported keys so a pasted one is recognisable
+const KEY_EXPORT_PREFIX: &str = "retrosave-key-v1:";
+
 /// E2E Encryption manager for save files
 pub struct EncryptionManager {
     /// User's encryption key derived from password
@@ -51,8 +92,9 @@ impl EncryptionManager {
     pub async fn init_with_password(&mut self, password: &str) -> Result<()> {
         info!("Initializing E2E encryption");
         
-        // Check if we have a stored key verification
-        if self.key_store_path.exists() {
+        // Check if we have a stored key verification. An imported key has no
+        // password and is replaced.
+        if self.key_store_path.exists() && self.stored_imported_key().await.is_none() {
             // Verify password against stored verification
             let stored = tokio::fs::read_to_string(&self.key_store_path).await
                 .context("Failed to read key store")?;
@@ -96,6 +138,77 @@ impl EncryptionManager {
         Ok(())
     }
     
+    /// Initialize with a passphrase shared between devices. The salt comes from
+    /// the account, so every device signed into it derives the same key from the
+    /// same passphrase. Only the salt and a hash to catch typos are stored.
+    ///
+    /// On a device that hasn't used the passphrase before, `cloud_sample` (a save
+    /// another device encrypted) catches a typo before it's stored.
+    pub async fn init_with_passphrase(
+        &mut self,
+        passphrase: &str,
+        account_id: &str,
+        cloud_sample: Option<&EncryptedSave>,
+    ) -> Result<()> {
+        info!("Initializing E2E encryption with a passphrase");
+        
+        let salt = Self::account_salt(account_id);
+        
+        // A passphrase set up on this device before can be checked right away
+        let mut verified = false;
+        if let Ok(stored) = tokio::fs::read_to_string(&self.key_store_path).await {
+            if let Ok(verification) = serde_json::from_str::<KeyVerification>(&stored) {
+                let same_account = verification.salt == general_purpose::STANDARD.encode(salt.as_str());
+                if verification.mode == EncryptionMode::Passphrase && same_account {
+                    if !self.verify_password(passphrase, &verification)? {
+                        return Err(anyhow::anyhow!("Invalid encryption passphrase"));
+                    }
+                    verified = true;
+                }
+            }
+        }
+        
+        let key = self.derive_key_from_password(passphrase, salt.as_str().as_bytes())?;
+        
+        // Otherwise only the saves already in the cloud can tell
+        if let (false, Some(sample)) = (verified, cloud_sample) {
+            let candidate = Self { master_key: Some(key), key_store_path: self.key_store_path.clone() };
+            if candidate.decrypt_save(sample).is_err() {
+                return Err(anyhow::anyhow!("The passphrase doesn't match the encrypted saves in the cloud"));
+            }
+        }
+        
+        let mut verification = self.create_key_verification(passphrase, salt.as_str())?;
+        verification.mode = EncryptionMode::Passphrase;
+        let json = serde_json::to_string_pretty(&verification)?;
+        if let Some(parent) = self.key_store_path.parent() {
+            tokio::fs::create_dir_all(parent).await.ok();
+        }
+        tokio::fs::write(&self.key_store_path, json).await
+            .context("Failed to store key verification")?;
+        
+        self.master_key = Some(key);
+        info!("E2E encryption initialized successfully");
+        Ok(())
+    }
+    
+    /// Mode of the key set up on this device, if any
+    pub async fn stored_mode(&self) -> Option<EncryptionMode> {
+        let stored = tokio::fs::read_to_string(&self.key_store_path).await.ok()?;
+        serde_json::from_str::<KeyVerification>(&stored).ok().map(|v| v.mode)
+    }
+    
+    /// Forget the key until it is unlocked again
+    pub fn lock(&mut self) {
+        self.master_key = None;
+    }
+    
+    /// Salt for passphrase keys, the same on every device of an account
+    fn account_salt(account_id: &str) -> SaltString {
+        let digest = Sha256::digest(format!("retrosave-e2e-v1:{}", account_id).as_bytes());
+        SaltString::encode_b64(&digest[..16]).expect("16 bytes is a valid salt length")
+    }
+    
     /// Encrypt save data before upload
     pub fn encrypt_save(&self, data: &[u8]) -> Result<EncryptedSave> {
         let key = self.master_key
@@ -228,6 +341,8 @@ impl EncryptionManager {
             hash: password_hash,
             salt: general_purpose::STANDARD.encode(salt),
             version: 1,
+            mode: EncryptionMode::DeviceKey,
+            key: None,
         })
     }
     
@@ -246,12 +361,17 @@ impl EncryptionManager {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("Encryption not initialized"))?;
         
-        Ok(general_purpose::STANDARD.encode(key))
+        Ok(format!("{}{}", KEY_EXPORT_PREFIX, general_purpose::STANDARD.encode(key)))
     }
     
-    /// Import encryption key (from backup)
-    pub fn import_key(&mut self, key_string: &str) -> Result<()> {
-        let key_bytes = general_purpose::STANDARD.decode(key_string)
+    /// Import encryption key (from backup). There's no password to derive it
+    /// from again, so the key is stored and unlocked on every start, see
+    /// `unlock_imported_key`.
+    pub async fn import_key(&mut self, key_string: &str) -> Result<()> {
+        // Keys exported before the prefix existed are plain base64
+        let key_string: String = key_string.chars().filter(|c| !c.is_whitespace()).collect();
+        let encoded = key_string.strip_prefix(KEY_EXPORT_PREFIX).unwrap_or(&key_string);
+        let key_bytes = general_purpose::STANDARD.decode(encoded)
             .context("Invalid key format")?;
         
         if key_bytes.len() != 32 {
@@ -260,10 +380,57 @@ impl EncryptionManager {
         
         let mut key = [0u8; 32];
         key.copy_from_slice(&key_bytes);
-        self.master_key = Some(key);
         
+        let verification = KeyVerification {
+            hash: Self::key_hash(&key),
+            salt: String::new(),
+            version: 1,
+            mode: EncryptionMode::DeviceKey,
+            key: Some(general_purpose::STANDARD.encode(key)),
+        };
+        let json = serde_json::to_string_pretty(&verification)?;
+        if let Some(parent) = self.key_store_path.parent() {
+            tokio::fs::create_dir_all(parent).await.ok();
+        }
+        tokio::fs::write(&self.key_store_path, json).await
+            .context("Failed to store imported key")?;
+        
+        self.master_key = Some(key);
         Ok(())
     }
+    
+    /// Unlock with a key imported before. Returns whether there was one.
+    pub async fn unlock_imported_key(&mut self) -> Result<bool> {
+        let Some(verification) = self.stored_imported_key().await else {
+            return Ok(false);
+        };
+        
+        let key_bytes = verification.key.as_deref()
+            .map(|key| general_purpose::STANDARD.decode(key))
+            .transpose()
+            .context("Invalid stored key")?
+            .unwrap_or_default();
+        let key: [u8; 32] = key_bytes.try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid stored key length"))?;
+        if Self::key_hash(&key) != verification.hash {
+            return Err(anyhow::anyhow!("Stored key doesn't match its verification"));
+        }
+        
+        self.master_key = Some(key);
+        Ok(true)
+    }
+    
+    /// The key store, if it holds an imported key
+    async fn stored_imported_key(&self) -> Option<KeyVerification> {
+        let stored = tokio::fs::read_to_string(&self.key_store_path).await.ok()?;
+        serde_json::from_str::<KeyVerification>(&stored).ok()
+            .filter(|verification| verification.key.is_some())
+    }
+    
+    /// Verification of an imported key, to catch a damaged key store
+    fn key_hash(key: &[u8; 32]) -> String {
+        format!("{:x}", Sha256::digest(key))
+    }
 }
 
 /// Key verification data stored on disk
@@ -275,6 +442,12 @@ struct KeyVerification {
     salt: String,
     /// Version of verification scheme
     version: u8,
+    /// Stores from before passphrase mode are device keys
+    #[serde(default)]
+    mode: EncryptionMode,
+    /// Imported key (base64), `hash` is its SHA256
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
 }
 
 #[cfg(test)]
@@ -341,4 +514,63 @@ mod tests {
         let decrypted2 = manager.decrypt_save(&encrypted2).unwrap();
         assert_eq!(decrypted2, data);
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_passphrase_works_across_devices() {
+        let device1 = TempDir::new().unwrap();
+        let device2 = TempDir::new().unwrap();
+        let mut manager1 = EncryptionManager::new(Some(device1.path().to_path_buf()));
+        let mut manager2 = EncryptionManager::new(Some(device2.path().to_path_buf()));
+        
+        manager1.init_with_passphrase("correct horse", "user-1", None).await.unwrap();
+        manager2.init_with_passphrase("correct horse", "user-1", None).await.unwrap();
+        assert_eq!(manager1.stored_mode().await, Some(EncryptionMode::Passphrase));
+        
+        let encrypted = manager1.encrypt_save(b"Memory card").unwrap();
+        assert_eq!(manager2.decrypt_save(&encrypted).unwrap(), b"Memory card");
+        
+        // Another account derives another key from the same passphrase
+        let device3 = TempDir::new().unwrap();
+        let mut manager3 = EncryptionManager::new(Some(device3.path().to_path_buf()));
+        manager3.init_with_passphrase("correct horse", "user-2", None).await.unwrap();
+        assert!(manager3.decrypt_save(&encrypted).is_err());
+        
+        // A typo is caught on a device that set the passphrase up before
+        let mut again = EncryptionManager::new(Some(device1.path().to_path_buf()));
+        assert!(again.init_with_passphrase("correct hose", "user-1", None).await.is_err());
+        
+        // And on a new device by a save another device encrypted
+        let device4 = TempDir::new().unwrap();
+        let mut manager4 = EncryptionManager::new(Some(device4.path().to_path_buf()));
+        assert!(manager4.init_with_passphrase("correct hose", "user-1", Some(&encrypted)).await.is_err());
+        assert_eq!(manager4.stored_mode().await, None);
+        manager4.init_with_passphrase("correct horse", "user-1", Some(&encrypted)).await.unwrap();
+        assert_eq!(manager4.decrypt_save(&encrypted).unwrap(), b"Memory card");
+    }
+    
+    #[tokio::test]
+    async fn test_key_export_import() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = EncryptionManager::new(Some(temp_dir.path().to_path_buf()));
+        manager.init_with_password("password").await.unwrap();
+        let encrypted = manager.encrypt_save(b"Save data").unwrap();
+        
+        let exported = manager.export_key().unwrap();
+        assert!(exported.starts_with(KEY_EXPORT_PREFIX));
+        
+        // Pasting tends to pick up line breaks
+        let other_dir = TempDir::new().unwrap();
+        let mut other = EncryptionManager::new(Some(other_dir.path().to_path_buf()));
+        other.import_key(&format!("  {}\n", exported)).await.unwrap();
+        assert_eq!(other.decrypt_save(&encrypted).unwrap(), b"Save data");
+        
+        assert!(other.import_key("retrosave-key-v1:dG9vIHNob3J0").await.is_err());
+        assert!(other.import_key("not a key").await.is_err());
+        
+        // The imported key is still there after a restart
+        let mut restarted = EncryptionManager::new(Some(other_dir.path().to_path_buf()));
+        assert!(restarted.unlock_imported_key().await.unwrap());
+        assert_eq!(restarted.decrypt_save(&encrypted).unwrap(), b"Save data");
+        assert!(!manager.unlock_imported_key().await.unwrap());
+    }
+}
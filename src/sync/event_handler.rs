@@ -3,7 +3,7 @@ use tokio::sync::RwLock;
 use tracing::{info, debug, warn};
 use crate::payment::{SubscriptionStatus, UsageStats, BackendSubscriptionTier, TierLimits, TierFeatures, TierPrice};
 use crate::sync::settings_sync::UserSettingsResponse;
-use super::websocket::WsMessage;
+use super::websocket::{WsMessage, ConnectionState};
 
 /// Callback types for different events
 pub type SubscriptionCallback = Arc<dyn Fn(SubscriptionStatus) + Send + Sync>;
@@ -11,6 +11,7 @@ pub type UsageCallback = Arc<dyn Fn(UsageStats) + Send + Sync>;
 pub type DeviceCallback = Arc<dyn Fn(String, String) + Send + Sync>;
 pub type WarningCallback = Arc<dyn Fn(String) + Send + Sync>;
 pub type SettingsCallback = Arc<dyn Fn(UserSettingsResponse) + Send + Sync>;
+pub type ConnectionStateCallback = Arc<dyn Fn(ConnectionState) + Send + Sync>;
 
 /// Event handler for WebSocket messages
 pub struct EventHandler {
@@ -20,6 +21,7 @@ pub struct EventHandler {
     device_removed_listeners: Arc<RwLock<Vec<DeviceCallback>>>,
     warning_listeners: Arc<RwLock<Vec<WarningCallback>>>,
     settings_listeners: Arc<RwLock<Vec<SettingsCallback>>>,
+    connection_state_listeners: Arc<RwLock<Vec<ConnectionStateCallback>>>,
 }
 
 impl EventHandler {
@@ -31,6 +33,7 @@ impl EventHandler {
             device_removed_listeners: Arc::new(RwLock::new(Vec::new())),
             warning_listeners: Arc::new(RwLock::new(Vec::new())),
             settings_listeners: Arc::new(RwLock::new(Vec::new())),
+            connection_state_listeners: Arc::new(RwLock::new(Vec::new())),
         }
     }
     
@@ -88,6 +91,23 @@ impl EventHandler {
         listeners.push(Arc::new(callback));
     }
     
+    /// Register a callback for connection state changes (connected, reconnecting, disconnected)
+    pub async fn on_connection_state_change<F>(&self, callback: F)
+    where
+        F: Fn(ConnectionState) + Send + Sync + 'static,
+    {
+        let mut listeners = self.connection_state_listeners.write().await;
+        listeners.push(Arc::new(callback));
+    }
+    
+    /// Notify listeners that the connection state changed
+    pub async fn notify_connection_state(&self, state: ConnectionState) {
+        let listeners = self.connection_state_listeners.read().await;
+        for listener in listeners.iter() {
+            listener(state.clone());
+        }
+    }
+    
     /// Handle incoming WebSocket message
     pub async fn handle_message(&self, message: WsMessage) {
         match message {
@@ -0,0 +1,428 @@
+//! A folder standing in for the Retrosave server. Pointed at a NAS share or
+//! a Syncthing folder, devices sync through it without an account.
+//!
+//! Every game and save is its own file, so file sync tools never have to
+//! merge an index that two devices changed at the same time:
+//! `retrosave/games/<id>.json`, `retrosave/saves/<id>.json` and the uploaded
+//! data next to it in `retrosave/saves/<id>.bin`. The rclone backend keeps
+//! the same layout on a remote.
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::api::{CloudApi, Game, ListSavesResponse, SaveMetadata, UploadUrlResponse};
+use crate::payment::{SubscriptionStatus, UsageStats};
+
+/// Where games are kept, relative to the chosen folder
+pub(crate) const GAMES_DIR: &str = "retrosave/games";
+/// Where save records and their data are kept, relative to the chosen folder
+pub(crate) const SAVES_DIR: &str = "retrosave/saves";
+const UPLOAD_URL_PREFIX: &str = "local-folder://upload/";
+const DOWNLOAD_URL_PREFIX: &str = "local-folder://saves/";
+
+/// `CloudApi` keeping games and saves in a folder instead of the cloud
+pub struct FsCloudApi {
+    folder: PathBuf,
+    base_url: String,
+    pending_uploads: PendingUploads,
+}
+
+impl FsCloudApi {
+    pub fn new(folder: PathBuf, device_name: String) -> Self {
+        Self {
+            base_url: folder.to_string_lossy().into_owned(),
+            folder,
+            pending_uploads: PendingUploads::new(device_name),
+        }
+    }
+    
+    fn games_dir(&self) -> PathBuf {
+        self.folder.join(GAMES_DIR)
+    }
+    
+    fn saves_dir(&self) -> PathBuf {
+        self.folder.join(SAVES_DIR)
+    }
+    
+    fn blob_path(&self, save_id: Uuid) -> PathBuf {
+        self.saves_dir().join(blob_name(save_id))
+    }
+    
+    /// Every save record in the folder whose data has fully arrived, newest first
+    async fn read_saves(&self) -> Result<Vec<SaveMetadata>> {
+        let saves_dir = self.saves_dir();
+        let mut entries = match tokio::fs::read_dir(&saves_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", saves_dir.display())),
+        };
+        
+        let mut records = Vec::new();
+        let mut blob_sizes = HashMap::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match read_json::<SaveMetadata>(&path).await {
+                Ok(save) => {
+                    if let Ok(blob) = tokio::fs::metadata(self.blob_path(save.id)).await {
+                        blob_sizes.insert(save.id, blob.len());
+                    }
+                    records.push(save);
+                }
+                Err(e) => warn!("Skipping unreadable save record {}: {}", path.display(), e),
+            }
+        }
+        Ok(complete_saves(records, &blob_sizes))
+    }
+}
+
+/// Save records handed out with an upload URL, waiting for their data
+pub(crate) struct PendingUploads {
+    device_name: String,
+    uploads: Mutex<HashMap<String, SaveMetadata>>,
+}
+
+impl PendingUploads {
+    pub(crate) fn new(device_name: String) -> Self {
+        Self {
+            device_name,
+            uploads: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Record a new save of `game_id`, which already has `game_saves` saves
+    pub(crate) fn request(
+        &self,
+        game_saves: usize,
+        game_id: Uuid,
+        file_hash: &str,
+        file_size: i64,
+        timestamp: DateTime<Utc>,
+        metadata: Option<serde_json::Value>,
+    ) -> UploadUrlResponse {
+        let save_id = Uuid::new_v4();
+        let upload_url = format!("{}{}", UPLOAD_URL_PREFIX, save_id);
+        let save = SaveMetadata {
+            id: save_id,
+            game_id,
+            file_hash: file_hash.to_string(),
+            file_size,
+            client_timestamp: timestamp,
+            created_at: Utc::now(),
+            download_url: Some(format!("{}{}", DOWNLOAD_URL_PREFIX, save_id)),
+            game_name: metadata.as_ref()
+                .and_then(|m| m.get("game_name"))
+                .and_then(|name| name.as_str())
+                .map(str::to_string),
+            metadata,
+            version: Some(game_saves as i32 + 1),
+            device_name: Some(self.device_name.clone()),
+        };
+        self.uploads.lock().unwrap_or_else(|e| e.into_inner()).insert(upload_url.clone(), save);
+        
+        UploadUrlResponse { save_id, upload_url, expires_in: 3600 }
+    }
+    
+    /// The record `upload_url` was handed out for, if `data_len` is the size it announced
+    pub(crate) fn take(&self, upload_url: &str, data_len: usize) -> Result<SaveMetadata> {
+        let save = self.uploads.lock().unwrap_or_else(|e| e.into_inner())
+            .remove(upload_url)
+            .ok_or_else(|| anyhow!("Unknown upload URL {}", upload_url))?;
+        if data_len as i64 != save.file_size {
+            return Err(anyhow!("Upload of save {} is {} bytes, expected {}", save.id, data_len, save.file_size));
+        }
+        Ok(save)
+    }
+}
+
+pub(crate) fn record_name(save_id: Uuid) -> String {
+    format!("{}.json", save_id)
+}
+
+pub(crate) fn blob_name(save_id: Uuid) -> String {
+    format!("{}.bin", save_id)
+}
+
+/// The save a download URL handed out by `PendingUploads` points at
+pub(crate) fn save_id_from_url(download_url: &str) -> Result<Uuid> {
+    download_url.strip_prefix(DOWNLOAD_URL_PREFIX)
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or_else(|| anyhow!("Not a sync folder URL: {}", download_url))
+}
+
+/// Put `note` in a save record's metadata, or take it out
+pub(crate) fn set_note(save: &mut SaveMetadata, note: Option<&str>) {
+    let metadata = save.metadata.get_or_insert_with(|| serde_json::json!({}));
+    if let Some(metadata) = metadata.as_object_mut() {
+        match note {
+            Some(note) => metadata.insert("note".to_string(), note.into()),
+            None => metadata.remove("note"),
+        };
+    }
+}
+
+/// The same on every device, so devices registering a game at the same time
+/// agree on its ID without talking to each other
+pub(crate) fn game_id(name: &str, emulator: &str) -> Uuid {
+    let hash = Sha256::digest(format!("{}\0{}", name, emulator).as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    Uuid::from_bytes(bytes)
+}
+
+/// `records` whose data is all there, newest first. File sync tools may
+/// deliver a record before its data, or copy a record on conflicts.
+pub(crate) fn complete_saves(records: Vec<SaveMetadata>, blob_sizes: &HashMap<Uuid, u64>) -> Vec<SaveMetadata> {
+    let mut saves: HashMap<Uuid, SaveMetadata> = HashMap::new();
+    for save in records {
+        if blob_sizes.get(&save.id).is_none_or(|size| *size as i64 != save.file_size) {
+            debug!("Data of save {} hasn't fully arrived yet", save.id);
+            continue;
+        }
+        saves.insert(save.id, save);
+    }
+    
+    let mut saves: Vec<SaveMetadata> = saves.into_values().collect();
+    saves.sort_by_key(|save| std::cmp::Reverse(save.created_at));
+    saves
+}
+
+/// One page of `saves`, like the server pages them
+pub(crate) fn page_of(saves: Vec<SaveMetadata>, game_id: Option<Uuid>, page: i64, per_page: i64) -> ListSavesResponse {
+    let saves: Vec<SaveMetadata> = saves.into_iter()
+        .filter(|save| game_id.is_none_or(|id| save.game_id == id))
+        .collect();
+    
+    let page = page.max(1);
+    let per_page = per_page.max(1);
+    let total = saves.len() as i64;
+    let total_pages = (total + per_page - 1) / per_page;
+    let items = saves.into_iter()
+        .skip(((page - 1) * per_page) as usize)
+        .take(per_page as usize)
+        .collect();
+    
+    ListSavesResponse {
+        items,
+        total,
+        page,
+        per_page,
+        total_pages,
+        has_next: page < total_pages,
+        has_prev: page > 1,
+    }
+}
+
+/// Record for a game registered by this device
+pub(crate) fn new_game(name: &str, emulator: &str) -> Game {
+    let now = Utc::now();
+    Game {
+        id: game_id(name, emulator),
+        name: name.to_string(),
+        emulator: emulator.to_string(),
+        save_count: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let data = tokio::fs::read(path).await?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+async fn write_json<T: Serialize>(path: PathBuf, value: &T) -> Result<()> {
+    let data = serde_json::to_vec_pretty(value)?;
+    write_file(path, data).await
+}
+
+async fn write_file(path: PathBuf, data: Vec<u8>) -> Result<()> {
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        crate::storage::atomic::write_atomic(&path, &data)
+    })
+    .await?
+}
+
+#[async_trait]
+impl CloudApi for FsCloudApi {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+    
+    fn requires_auth(&self) -> bool {
+        false
+    }
+    
+    fn set_bandwidth_limit(&self, _kbps: u32) {}
+    
+    /// A missing folder is usually an unmounted share, saves queue until it's back
+    async fn is_reachable(&self) -> bool {
+        tokio::fs::metadata(&self.folder).await.is_ok_and(|m| m.is_dir())
+    }
+    
+    async fn register_game_with_id(&self, name: &str, emulator: &str, _game_id: Option<String>) -> Result<Game> {
+        let path = self.games_dir().join(format!("{}.json", game_id(name, emulator)));
+        if let Ok(game) = read_json::<Game>(&path).await {
+            return Ok(game);
+        }
+        
+        let game = new_game(name, emulator);
+        write_json(path, &game).await?;
+        info!("Registered {} ({}) in the sync folder", name, emulator);
+        Ok(game)
+    }
+    
+    async fn register_device(&self, _device_id: &str, _name: &str) -> Result<()> {
+        Ok(())
+    }
+    
+    async fn request_upload_url_with_metadata(
+        &self,
+        game_id: Uuid,
+        file_hash: &str,
+        file_size: i64,
+        timestamp: DateTime<Utc>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<UploadUrlResponse> {
+        let game_saves = self.read_saves().await?
+            .into_iter()
+            .filter(|save| save.game_id == game_id)
+            .count();
+        Ok(self.pending_uploads.request(game_saves, game_id, file_hash, file_size, timestamp, metadata))
+    }
+    
+    async fn upload_save_data(&self, upload_url: &str, data: Vec<u8>) -> Result<()> {
+        let save = self.pending_uploads.take(upload_url, data.len())?;
+        
+        // Data first, so other devices never see a record without it
+        write_file(self.blob_path(save.id), data).await?;
+        write_json(self.saves_dir().join(record_name(save.id)), &save).await?;
+        debug!("Wrote save {} to the sync folder", save.id);
+        Ok(())
+    }
+    
+    async fn list_saves(&self, game_id: Option<Uuid>, page: i64, per_page: i64) -> Result<ListSavesResponse> {
+        Ok(page_of(self.read_saves().await?, game_id, page, per_page))
+    }
+    
+    async fn download_save_data(&self, download_url: &str) -> Result<Vec<u8>> {
+        let path = self.blob_path(save_id_from_url(download_url)?);
+        tokio::fs::read(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+    
+    async fn update_save_note(&self, save_id: Uuid, note: Option<&str>) -> Result<()> {
+        let path = self.saves_dir().join(record_name(save_id));
+        let mut save = read_json::<SaveMetadata>(&path).await
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        set_note(&mut save, note);
+        write_json(path, &save).await
+    }
+    
+    async fn get_subscription_status(&self) -> Result<SubscriptionStatus> {
+        Err(anyhow!("Syncing through a local folder has no subscription"))
+    }
+    
+    async fn get_usage_stats(&self) -> Result<UsageStats> {
+        Err(anyhow!("Syncing through a local folder has no usage limits"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    async fn upload(api: &FsCloudApi, game_id: Uuid, data: &[u8]) -> Result<Uuid> {
+        let response = api.request_upload_url_with_metadata(
+            game_id,
+            "hash",
+            data.len() as i64,
+            Utc::now(),
+            Some(serde_json::json!({ "game_name": "Okami", "file_path": "/saves/okami.ps2" })),
+        ).await?;
+        api.upload_save_data(&response.upload_url, data.to_vec()).await?;
+        Ok(response.save_id)
+    }
+    
+    #[tokio::test]
+    async fn test_devices_share_games_and_saves() -> Result<()> {
+        let folder = TempDir::new()?;
+        let desktop = FsCloudApi::new(folder.path().to_path_buf(), "desktop".to_string());
+        let laptop = FsCloudApi::new(folder.path().to_path_buf(), "laptop".to_string());
+        
+        let game = desktop.register_game("Okami", "PCSX2").await?;
+        assert_eq!(laptop.register_game("Okami", "PCSX2").await?.id, game.id);
+        
+        upload(&desktop, game.id, b"first").await?;
+        let second = upload(&desktop, game.id, b"second save").await?;
+        
+        let saves = laptop.list_saves(Some(game.id), 1, 100).await?;
+        assert_eq!(saves.total, 2);
+        let newest = &saves.items[0];
+        assert_eq!(newest.id, second);
+        assert_eq!(newest.version, Some(2));
+        assert_eq!(newest.device_name.as_deref(), Some("desktop"));
+        assert_eq!(newest.game_name.as_deref(), Some("Okami"));
+        
+        let data = laptop.download_save_data(newest.download_url.as_deref().unwrap()).await?;
+        assert_eq!(data, b"second save");
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_saves_without_their_data_are_hidden() -> Result<()> {
+        let folder = TempDir::new()?;
+        let api = FsCloudApi::new(folder.path().to_path_buf(), "desktop".to_string());
+        let game = api.register_game("Okami", "PCSX2").await?;
+        let save_id = upload(&api, game.id, b"save data").await?;
+        
+        // As if the file sync tool is still copying the data
+        std::fs::write(api.blob_path(save_id), b"save")?;
+        assert_eq!(api.list_saves(None, 1, 100).await?.total, 0);
+        
+        std::fs::write(api.blob_path(save_id), b"save data")?;
+        assert_eq!(api.list_saves(None, 1, 100).await?.total, 1);
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_update_save_note() -> Result<()> {
+        let folder = TempDir::new()?;
+        let api = FsCloudApi::new(folder.path().to_path_buf(), "desktop".to_string());
+        let game = api.register_game("Okami", "PCSX2").await?;
+        let save_id = upload(&api, game.id, b"save data").await?;
+        
+        api.update_save_note(save_id, Some("before the last boss")).await?;
+        let save = &api.list_saves(None, 1, 100).await?.items[0];
+        let metadata = save.metadata.as_ref().unwrap();
+        assert_eq!(metadata["note"], "before the last boss");
+        assert_eq!(metadata["game_name"], "Okami");
+        
+        api.update_save_note(save_id, None).await?;
+        let save = &api.list_saves(None, 1, 100).await?.items[0];
+        assert!(save.metadata.as_ref().unwrap().get("note").is_none());
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_missing_folder_is_unreachable() {
+        let folder = TempDir::new().unwrap();
+        let api = FsCloudApi::new(folder.path().join("unmounted"), "desktop".to_string());
+        assert!(!api.is_reachable().await);
+        assert!(FsCloudApi::new(folder.path().to_path_buf(), "desktop".to_string()).is_reachable().await);
+    }
+}
@@ -6,10 +6,18 @@ use tokio::sync::Mutex;
 use serde_json::Value;
 
 /// Configuration for message throttling
+///
+/// By default these event types get priority and are never deduplicated or throttled:
+/// `subscription_updated`, `settings_updated`, `error`, `auth`, `storage_limit_warning`
+/// and `save_limit_warning`. Everything else is deduplicated and rate limited per event type.
 #[derive(Debug, Clone)]
 pub struct ThrottleConfig {
-    /// Minimum time between UI updates for the same event type
+    /// Window used to rate limit UI updates for the same event type
     pub ui_update_interval: Duration,
+    /// Maximum UI updates per event type within `ui_update_interval`
+    pub max_messages_per_window: usize,
+    /// Event types (the message's `type` tag) that bypass deduplication and throttling
+    pub high_priority_types: HashSet<String>,
     /// Time window for deduplication
     pub dedup_window: Duration,
     /// Maximum messages to batch before forcing flush
@@ -22,6 +30,15 @@ impl Default for ThrottleConfig {
     fn default() -> Self {
         Self {
             ui_update_interval: Duration::from_millis(100),
+            max_messages_per_window: 1,
+            high_priority_types: [
+                "subscription_updated",
+                "settings_updated",
+                "error",
+                "auth",
+                "storage_limit_warning",
+                "save_limit_warning",
+            ].into_iter().map(String::from).collect(),
             dedup_window: Duration::from_millis(500),
             batch_size: 10,
             batch_timeout: Duration::from_millis(50),
@@ -78,28 +95,31 @@ impl MessageDeduplicator {
 
 /// UI update throttler to prevent excessive updates
 struct UiThrottler {
-    last_updates: HashMap<String, Instant>,
+    last_updates: HashMap<String, Vec<Instant>>,
     interval: Duration,
+    max_per_window: usize,
 }
 
 impl UiThrottler {
-    fn new(interval: Duration) -> Self {
+    fn new(interval: Duration, max_per_window: usize) -> Self {
         Self {
             last_updates: HashMap::new(),
             interval,
+            max_per_window: max_per_window.max(1),
         }
     }
 
     fn should_update(&mut self, event_type: &str) -> bool {
         let now = Instant::now();
+        let updates = self.last_updates.entry(event_type.to_string()).or_default();
 
-        if let Some(last_update) = self.last_updates.get(event_type) {
-            if now.duration_since(*last_update) < self.interval {
-                return false;
-            }
+        // Only count updates still inside the window
+        updates.retain(|timestamp| now.duration_since(*timestamp) < self.interval);
+        if updates.len() >= self.max_per_window {
+            return false;
         }
 
-        self.last_updates.insert(event_type.to_string(), now);
+        updates.push(now);
         true
     }
 
@@ -153,13 +173,18 @@ impl MessageThrottler {
                 MessageDeduplicator::new(config.dedup_window)
             )),
             ui_throttler: Arc::new(Mutex::new(
-                UiThrottler::new(config.ui_update_interval)
+                UiThrottler::new(config.ui_update_interval, config.max_messages_per_window)
             )),
             batches: Arc::new(Mutex::new(HashMap::new())),
             config,
         }
     }
 
+    /// Configuration this throttler was created with
+    pub fn config(&self) -> &ThrottleConfig {
+        &self.config
+    }
+
     /// Process incoming message with deduplication
     pub async fn process_incoming(&self, message: Value) -> Option<Value> {
         let mut dedup = self.deduplicator.lock().await;
@@ -242,20 +267,19 @@ pub struct PriorityProcessor {
 
 impl PriorityProcessor {
     pub fn new(throttler: Arc<MessageThrottler>) -> Self {
-        let mut high_priority = HashSet::new();
-        // Define high priority event types that should bypass throttling
-        high_priority.insert("subscription_updated".to_string());
-        high_priority.insert("error".to_string());
-        high_priority.insert("auth".to_string());
-        high_priority.insert("storage_limit_warning".to_string());
-        high_priority.insert("save_limit_warning".to_string());
+        // High priority event types bypass throttling, see `ThrottleConfig`
+        let high_priority_types = throttler.config().high_priority_types.clone();
 
         Self {
-            high_priority_types: high_priority,
+            high_priority_types,
             throttler,
         }
     }
 
+    pub fn throttler(&self) -> &Arc<MessageThrottler> {
+        &self.throttler
+    }
+
     pub async fn process(&self, event_type: &str, message: Value) -> ProcessResult {
         // High priority messages bypass throttling
         if self.high_priority_types.contains(event_type) {
@@ -356,6 +380,45 @@ mod tests {
         assert_eq!(batch.unwrap().len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_max_messages_per_window() {
+        let config = ThrottleConfig {
+            ui_update_interval: Duration::from_millis(100),
+            max_messages_per_window: 3,
+            ..Default::default()
+        };
+        let throttler = MessageThrottler::new(config);
+
+        assert!(throttler.should_update_ui("usage_updated").await);
+        assert!(throttler.should_update_ui("usage_updated").await);
+        assert!(throttler.should_update_ui("usage_updated").await);
+        assert!(!throttler.should_update_ui("usage_updated").await);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(throttler.should_update_ui("usage_updated").await);
+    }
+
+    #[tokio::test]
+    async fn test_custom_priority_types() {
+        let config = ThrottleConfig {
+            high_priority_types: ["usage_updated".to_string()].into_iter().collect(),
+            ..Default::default()
+        };
+        let processor = PriorityProcessor::new(Arc::new(MessageThrottler::new(config)));
+
+        let msg = serde_json::json!({"saves_count": 1});
+        for _ in 0..3 {
+            match processor.process("usage_updated", msg.clone()).await {
+                ProcessResult::Immediate(_) => {}
+                _ => panic!("Expected immediate processing"),
+            }
+        }
+
+        // Types dropped from the priority list are throttled like anything else
+        assert!(matches!(processor.process("subscription_updated", msg.clone()).await, ProcessResult::Normal(_)));
+        assert!(matches!(processor.process("subscription_updated", msg).await, ProcessResult::Duplicate));
+    }
+
     #[tokio::test]
     async fn test_priority_processing() {
         let throttler = Arc::new(MessageThrottler::new(ThrottleConfig::default()));
@@ -7,12 +7,21 @@ pub mod event_handler;
 pub mod message_throttler;
 pub mod conflict_resolution;
 pub mod settings_sync;
+pub mod bandwidth;
+pub mod http;
+pub mod webhook;
+pub mod local_folder;
+pub mod rclone;
 
 
 pub use auth::AuthManager;
-pub use api::SyncApi;
-pub use service::{SyncService, SyncEvent};
-pub use encryption::EncryptionManager;
-pub use websocket::{WebSocketClient, WsMessage};
+pub use api::{CloudApi, SyncApi};
+pub use service::{SyncService, SyncEvent, SyncPhase, SyncProgress};
+pub use encryption::{EncryptionManager, EncryptionMode};
+pub use websocket::{WebSocketClient, WsMessage, ConnectionState};
 pub use event_handler::EventHandler;
-pub use message_throttler::{MessageThrottler, ThrottleConfig, PriorityProcessor};
\ No newline at end of file
+pub use message_throttler::{MessageThrottler, ThrottleConfig, PriorityProcessor};
+pub use bandwidth::BandwidthLimiter;
+pub use webhook::FailureWebhook;
+pub use local_folder::FsCloudApi;
+pub use rclone::RcloneCloudApi;
\ No newline at end of file
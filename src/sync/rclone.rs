@@ -0,0 +1,418 @@
+//! Sync through storage the user already has (S3, Google Drive, Dropbox...)
+//! by running an installed `rclone` against a configured remote. Games and
+//! saves are laid out like in a local sync folder.
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::api::{ApiError, CloudApi, Game, ListSavesResponse, SaveMetadata, UploadUrlResponse};
+use super::local_folder::{
+    blob_name, complete_saves, game_id, new_game, page_of, record_name, save_id_from_url, set_note, PendingUploads,
+    GAMES_DIR, SAVES_DIR,
+};
+use crate::payment::{SubscriptionStatus, UsageStats};
+
+/// rclone binary, looked up in PATH
+pub const RCLONE_BINARY: &str = "rclone";
+
+/// Listing and small files
+const METADATA_TIMEOUT: Duration = Duration::from_secs(60);
+/// Save data, slow remotes can take a while
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How long a listing of the remote's saves is reused, long enough to cover
+/// the paging and upload requests of one sync
+const LISTING_TTL: Duration = Duration::from_secs(30);
+
+/// rclone's exit codes for a missing directory or file
+const EXIT_DIRECTORY_NOT_FOUND: i32 = 3;
+const EXIT_FILE_NOT_FOUND: i32 = 4;
+
+/// rclone exited with an error
+#[derive(Debug, thiserror::Error)]
+#[error("rclone {command} failed: {message}")]
+pub struct RcloneError {
+    command: String,
+    code: Option<i32>,
+    message: String,
+}
+
+impl RcloneError {
+    /// Whether `err` is rclone not finding the directory or file it was given
+    fn is_not_found(err: &anyhow::Error) -> bool {
+        matches!(
+            err.downcast_ref::<RcloneError>().and_then(|e| e.code),
+            Some(EXIT_DIRECTORY_NOT_FOUND | EXIT_FILE_NOT_FOUND)
+        )
+    }
+}
+
+/// Entry printed by `rclone lsjson`
+#[derive(Debug, Deserialize)]
+struct ListEntry {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Size")]
+    size: i64,
+}
+
+/// `CloudApi` keeping games and saves on an rclone remote
+pub struct RcloneCloudApi {
+    /// `remote:path` as understood by rclone
+    remote: String,
+    max_kbps: AtomicU32,
+    pending_uploads: PendingUploads,
+    /// Last `read_saves` result and when it was read
+    listing: Mutex<Option<(Instant, Vec<SaveMetadata>)>>,
+}
+
+impl RcloneCloudApi {
+    pub fn new(remote: String, device_name: String) -> Self {
+        Self {
+            remote,
+            max_kbps: AtomicU32::new(0),
+            pending_uploads: PendingUploads::new(device_name),
+            listing: Mutex::new(None),
+        }
+    }
+    
+    /// `path` on the remote
+    fn remote_path(&self, path: &str) -> String {
+        remote_path(&self.remote, path)
+    }
+    
+    fn save_path(&self, name: &str) -> String {
+        self.remote_path(&format!("{}/{}", SAVES_DIR, name))
+    }
+    
+    /// Run rclone with `args` (subcommand and flags) on `paths`
+    async fn rclone(&self, args: &[&str], paths: &[&str], input: Option<Vec<u8>>, timeout: Duration) -> Result<Vec<u8>> {
+        let max_kbps = self.max_kbps.load(Ordering::Relaxed);
+        let bwlimit = format!("{}k", max_kbps);
+        let bwlimit = (max_kbps > 0).then_some(bwlimit.as_str());
+        run_rclone(&command_line(args, bwlimit, paths), input, timeout).await
+    }
+    
+    fn cached_listing(&self) -> std::sync::MutexGuard<'_, Option<(Instant, Vec<SaveMetadata>)>> {
+        self.listing.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Every save record on the remote whose data has fully arrived, newest
+    /// first. Reused for `LISTING_TTL` so a sync lists the remote once.
+    async fn read_saves(&self) -> Result<Vec<SaveMetadata>> {
+        if let Some((read_at, saves)) = self.cached_listing().as_ref() {
+            if read_at.elapsed() < LISTING_TTL {
+                return Ok(saves.clone());
+            }
+        }
+        
+        let saves = self.read_saves_uncached().await?;
+        *self.cached_listing() = Some((Instant::now(), saves.clone()));
+        Ok(saves)
+    }
+    
+    async fn read_saves_uncached(&self) -> Result<Vec<SaveMetadata>> {
+        let saves_dir = self.remote_path(SAVES_DIR);
+        let listing = match self.rclone(&["lsjson", "--files-only"], &[&saves_dir], None, METADATA_TIMEOUT).await {
+            Ok(listing) => listing,
+            Err(e) if RcloneError::is_not_found(&e) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let entries: Vec<ListEntry> = serde_json::from_slice(&listing).context("Unexpected rclone lsjson output")?;
+        
+        let mut blob_sizes = HashMap::new();
+        let mut record_names = Vec::new();
+        for entry in entries {
+            match entry.name.rsplit_once('.') {
+                Some((id, "bin")) => {
+                    if let Ok(id) = Uuid::parse_str(id) {
+                        blob_sizes.insert(id, entry.size.max(0) as u64);
+                    }
+                }
+                Some((_, "json")) => record_names.push(entry.name),
+                _ => {}
+            }
+        }
+        if record_names.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        // One call for all records, rclone prints them back to back
+        let output = self.rclone(&["cat", "--include", "*.json"], &[&saves_dir], None, METADATA_TIMEOUT).await?;
+        let records = match serde_json::Deserializer::from_slice(&output)
+            .into_iter::<SaveMetadata>()
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(records) => records,
+            Err(e) => {
+                // The rest can't be told apart from the broken record, read them one by one
+                debug!("Reading save records one by one, one of them is unreadable: {}", e);
+                self.read_records(&record_names).await
+            }
+        };
+        Ok(complete_saves(records, &blob_sizes))
+    }
+    
+    /// Save records named `names`, skipping any that can't be read
+    async fn read_records(&self, names: &[String]) -> Vec<SaveMetadata> {
+        let mut records = Vec::new();
+        for name in names {
+            let path = self.save_path(name);
+            let record = self.rclone(&["cat"], &[&path], None, METADATA_TIMEOUT).await
+                .and_then(|data| serde_json::from_slice::<SaveMetadata>(&data).map_err(Into::into));
+            match record {
+                Ok(save) => records.push(save),
+                Err(e) => warn!("Skipping unreadable save record {}: {}", path, e),
+            }
+        }
+        records
+    }
+}
+
+/// Whether rclone takes `remote` as a remote rather than one of its flags
+pub fn is_valid_remote(remote: &str) -> bool {
+    let remote = remote.trim();
+    !remote.is_empty() && !remote.starts_with('-')
+}
+
+/// rclone arguments for running `args` on `paths`. The paths come after `--`
+/// so one starting with `-` isn't taken for a flag.
+fn command_line<'a>(args: &[&'a str], bwlimit: Option<&'a str>, paths: &[&'a str]) -> Vec<&'a str> {
+    let mut command = args.to_vec();
+    if let Some(bwlimit) = bwlimit {
+        command.extend(["--bwlimit", bwlimit]);
+    }
+    command.push("--");
+    command.extend(paths);
+    command
+}
+
+/// `path` under `remote`, which may be just a remote name like `gdrive:`
+fn remote_path(remote: &str, path: &str) -> String {
+    let remote = remote.trim_end_matches('/');
+    if remote.ends_with(':') {
+        format!("{}{}", remote, path)
+    } else {
+        format!("{}/{}", remote, path)
+    }
+}
+
+async fn run_rclone(args: &[&str], input: Option<Vec<u8>>, timeout: Duration) -> Result<Vec<u8>> {
+    let mut command = tokio::process::Command::new(RCLONE_BINARY);
+    command
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    
+    let subcommand = args.first().copied().unwrap_or_default();
+    let run = async move {
+        let mut child = command.spawn().context("Failed to run rclone, is it installed?")?;
+        if let Some(input) = input {
+            let mut stdin = child.stdin.take().context("rclone's stdin is not piped")?;
+            stdin.write_all(&input).await?;
+            // Closing stdin ends the upload
+            drop(stdin);
+        }
+        Ok::<_, anyhow::Error>(child.wait_with_output().await?)
+    };
+    let output = tokio::time::timeout(timeout, run).await
+        .map_err(|_| ApiError::Timeout { operation: "rclone", timeout })??;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RcloneError {
+            command: subcommand.to_string(),
+            code: output.status.code(),
+            message: stderr.lines().last().unwrap_or("no output").trim().to_string(),
+        }.into());
+    }
+    Ok(output.stdout)
+}
+
+/// Installed rclone's version, `None` when it can't be run
+pub async fn rclone_version() -> Option<String> {
+    let output = run_rclone(&["version"], None, METADATA_TIMEOUT).await.ok()?;
+    String::from_utf8_lossy(&output).lines().next().map(|line| line.trim().to_string())
+}
+
+/// Remotes set up with `rclone config`, like `gdrive:`
+pub async fn list_remotes() -> Result<Vec<String>> {
+    let output = run_rclone(&["listremotes"], None, METADATA_TIMEOUT).await?;
+    Ok(String::from_utf8_lossy(&output)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+#[async_trait]
+impl CloudApi for RcloneCloudApi {
+    fn base_url(&self) -> &str {
+        &self.remote
+    }
+    
+    fn requires_auth(&self) -> bool {
+        false
+    }
+    
+    fn set_bandwidth_limit(&self, kbps: u32) {
+        self.max_kbps.store(kbps, Ordering::Relaxed);
+    }
+    
+    /// The path is created by the first upload, so only the remote has to answer
+    async fn is_reachable(&self) -> bool {
+        match self.rclone(&["lsf", "--max-depth", "1"], &[&self.remote], None, METADATA_TIMEOUT).await {
+            Ok(_) => true,
+            Err(e) if RcloneError::is_not_found(&e) => true,
+            Err(e) => {
+                debug!("rclone remote {} unreachable: {}", self.remote, e);
+                false
+            }
+        }
+    }
+    
+    async fn register_game_with_id(&self, name: &str, emulator: &str, _game_id: Option<String>) -> Result<Game> {
+        let path = self.remote_path(&format!("{}/{}.json", GAMES_DIR, game_id(name, emulator)));
+        match self.rclone(&["cat"], &[&path], None, METADATA_TIMEOUT).await {
+            Ok(data) => {
+                if let Ok(game) = serde_json::from_slice::<Game>(&data) {
+                    return Ok(game);
+                }
+            }
+            Err(e) if RcloneError::is_not_found(&e) => {}
+            Err(e) => return Err(e),
+        }
+        
+        let game = new_game(name, emulator);
+        self.rclone(&["rcat"], &[&path], Some(serde_json::to_vec_pretty(&game)?), METADATA_TIMEOUT).await?;
+        info!("Registered {} ({}) on {}", name, emulator, self.remote);
+        Ok(game)
+    }
+    
+    async fn register_device(&self, _device_id: &str, _name: &str) -> Result<()> {
+        Ok(())
+    }
+    
+    async fn request_upload_url_with_metadata(
+        &self,
+        game_id: Uuid,
+        file_hash: &str,
+        file_size: i64,
+        timestamp: DateTime<Utc>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<UploadUrlResponse> {
+        let game_saves = self.read_saves().await?
+            .into_iter()
+            .filter(|save| save.game_id == game_id)
+            .count();
+        Ok(self.pending_uploads.request(game_saves, game_id, file_hash, file_size, timestamp, metadata))
+    }
+    
+    async fn upload_save_data(&self, upload_url: &str, data: Vec<u8>) -> Result<()> {
+        let save = self.pending_uploads.take(upload_url, data.len())?;
+        
+        // Data first, so other devices never see a record without it
+        self.rclone(&["rcat"], &[&self.save_path(&blob_name(save.id))], Some(data), TRANSFER_TIMEOUT).await?;
+        let record = serde_json::to_vec_pretty(&save)?;
+        self.rclone(&["rcat"], &[&self.save_path(&record_name(save.id))], Some(record), METADATA_TIMEOUT).await?;
+        debug!("Uploaded save {} to {}", save.id, self.remote);
+        
+        // Keep the listing current, the next upload of this game counts it
+        if let Some((_, saves)) = self.cached_listing().as_mut() {
+            saves.insert(0, save);
+        }
+        Ok(())
+    }
+    
+    async fn list_saves(&self, game_id: Option<Uuid>, page: i64, per_page: i64) -> Result<ListSavesResponse> {
+        Ok(page_of(self.read_saves().await?, game_id, page, per_page))
+    }
+    
+    async fn download_save_data(&self, download_url: &str) -> Result<Vec<u8>> {
+        let path = self.save_path(&blob_name(save_id_from_url(download_url)?));
+        self.rclone(&["cat"], &[&path], None, TRANSFER_TIMEOUT).await
+    }
+    
+    async fn update_save_note(&self, save_id: Uuid, note: Option<&str>) -> Result<()> {
+        let path = self.save_path(&record_name(save_id));
+        let record = self.rclone(&["cat"], &[&path], None, METADATA_TIMEOUT).await?;
+        let mut save: SaveMetadata = serde_json::from_slice(&record)
+            .with_context(|| format!("Unreadable save record {}", path))?;
+        set_note(&mut save, note);
+        self.rclone(&["rcat"], &[&path], Some(serde_json::to_vec_pretty(&save)?), METADATA_TIMEOUT).await?;
+        
+        if let Some((_, saves)) = self.cached_listing().as_mut() {
+            if let Some(cached) = saves.iter_mut().find(|cached| cached.id == save_id) {
+                set_note(cached, note);
+            }
+        }
+        Ok(())
+    }
+    
+    async fn get_subscription_status(&self) -> Result<SubscriptionStatus> {
+        Err(anyhow!("Syncing through rclone has no subscription"))
+    }
+    
+    async fn get_usage_stats(&self) -> Result<UsageStats> {
+        Err(anyhow!("Syncing through rclone has no usage limits"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_remotes_that_look_like_flags_are_invalid() {
+        assert!(is_valid_remote("gdrive:retrosave"));
+        assert!(is_valid_remote("/mnt/backup"));
+        assert!(!is_valid_remote("--config=/tmp/evil.conf"));
+        assert!(!is_valid_remote(" -v"));
+        assert!(!is_valid_remote(""));
+    }
+    
+    #[test]
+    fn test_remote_path() {
+        assert_eq!(remote_path("gdrive:", SAVES_DIR), "gdrive:retrosave/saves");
+        assert_eq!(remote_path("s3:bucket/games/", SAVES_DIR), "s3:bucket/games/retrosave/saves");
+        assert_eq!(remote_path("/mnt/backup", "retrosave/games/x.json"), "/mnt/backup/retrosave/games/x.json");
+    }
+    
+    #[test]
+    fn test_paths_follow_the_flags() {
+        assert_eq!(
+            command_line(&["cat"], Some("512k"), &["-v:saves/x.json"]),
+            vec!["cat", "--bwlimit", "512k", "--", "-v:saves/x.json"]
+        );
+        assert_eq!(command_line(&["lsjson", "--files-only"], None, &["gdrive:"]), vec!["lsjson", "--files-only", "--", "gdrive:"]);
+    }
+    
+    #[test]
+    fn test_not_found_exit_codes() {
+        let error = |code| anyhow::Error::from(RcloneError {
+            command: "cat".to_string(),
+            code: Some(code),
+            message: String::new(),
+        });
+        assert!(RcloneError::is_not_found(&error(EXIT_DIRECTORY_NOT_FOUND)));
+        assert!(RcloneError::is_not_found(&error(EXIT_FILE_NOT_FOUND)));
+        assert!(!RcloneError::is_not_found(&error(1)));
+    }
+}
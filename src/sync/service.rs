@@ -1,18 +1,49 @@
 use anyhow::{Result, Context};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use tokio::sync::{mpsc, watch, RwLock};
 use tokio::time::{interval, Duration};
 use tracing::{info, error, debug, warn};
 use chrono::Utc;
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
 use uuid::Uuid;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use sha2::{Sha256, Digest};
 
-use crate::storage::database::Database;
+use crate::metrics::METRICS;
+use crate::payment::{SubscriptionTier, UsageStats};
+use crate::storage::database::{Database, DeferredWrite, DEFAULT_PROFILE};
+use crate::storage::SaveBackupManager;
 use crate::storage::save_types::{SaveType, MemoryCardFormat};
-use crate::storage::ps2_memory_card::PS2MemoryCard;
-use super::{AuthManager, SyncApi, EncryptionManager, WebSocketClient, WsMessage};
-use super::api::SaveMetadata;
+use super::{AuthManager, SyncApi, EncryptionManager, EncryptionMode, WebSocketClient, WsMessage};
+use super::api::{ApiError, CloudApi, SaveMetadata};
+use super::webhook::FailureWebhook;
+
+/// How often connectivity is re-checked while offline
+const CONNECTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How long shutdown waits for an upload that is already in flight
+const SHUTDOWN_UPLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long fetched usage stats are trusted before asking the API again
+const USAGE_CACHE_TTL: Duration = Duration::from_secs(300);
+/// How many times a download that fails verification is fetched before giving up
+const DOWNLOAD_ATTEMPTS: u32 = 2;
+/// Unmarked cloud saves downloaded to find an encrypted one for checking a passphrase
+const PASSPHRASE_CHECK_DOWNLOADS: usize = 5;
+/// Cloud saves downloaded at the same time by default
+const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
+/// Uploads running at the same time by default, at most one per game
+const DEFAULT_UPLOAD_CONCURRENCY: usize = 3;
+
+/// Levels for `quota_warning_level`
+const QUOTA_OK: u8 = 0;
+const QUOTA_NEAR_LIMIT: u8 = 1;
+const QUOTA_OVER_LIMIT: u8 = 2;
+
+/// Unpinned versions per game kept locally while the account is over its
+/// cloud limit, for saves and for save-states
+const OVER_LIMIT_KEEP_SAVES: usize = 3;
+const OVER_LIMIT_KEEP_SAVE_STATES: usize = 1;
 
 #[derive(Debug, Clone)]
 pub enum SyncEvent {
@@ -35,31 +66,155 @@ pub struct SyncStatus {
     pub pending_uploads: usize,
     pub pending_downloads: usize,
     pub total_synced: usize,
+    pub is_online: bool,
+    /// The last sync gave up because the server didn't answer in time
+    pub last_sync_timed_out: bool,
+}
+
+impl SyncStatus {
+    /// Also reported as the upload queue depth metric
+    fn set_pending_uploads(&mut self, pending: usize) {
+        self.pending_uploads = pending;
+        METRICS.set_upload_queue_depth(pending);
+    }
+}
+
+/// Part of a sync that `SyncProgress` counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPhase {
+    Uploading,
+    Downloading,
+}
+
+/// Item-level progress of the running sync, see `SyncService::subscribe_progress`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncProgress {
+    pub phase: SyncPhase,
+    /// Saves finished so far, including the ones skipped
+    pub current: usize,
+    pub total: usize,
+}
+
+impl SyncProgress {
+    /// How far along the phase is, from 0.0 to 1.0
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.current as f32 / self.total as f32).min(1.0)
+    }
+}
+
+impl std::fmt::Display for SyncProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = match self.phase {
+            SyncPhase::Uploading => "Uploading",
+            SyncPhase::Downloading => "Checking cloud saves",
+        };
+        write!(f, "{} {}/{} ({:.0}%)", verb, self.current, self.total, self.fraction() * 100.0)
+    }
 }
 
 pub struct SyncService {
     auth_manager: Arc<AuthManager>,
-    api: Arc<SyncApi>,
+    api: Arc<dyn CloudApi>,
     database: Arc<Database>,
     encryption: Arc<RwLock<EncryptionManager>>,
     websocket: Arc<RwLock<Option<Arc<WebSocketClient>>>>,
     status: Arc<RwLock<SyncStatus>>,
     upload_queue: Arc<RwLock<VecDeque<UploadTask>>>,
+    /// Tasks taken off the queue that haven't finished uploading yet
+    current_uploads: Arc<RwLock<Vec<UploadTask>>>,
+    shutting_down: AtomicBool,
+    /// Uploads for a running emulator wait until it stops
+    sync_after_closing_only: AtomicBool,
+    /// Cloud saves older than the local deletion of their file are restored anyway
+    restore_deleted_saves: AtomicBool,
+    /// How many cloud saves are downloaded in parallel
+    download_concurrency: AtomicUsize,
+    /// How many saves of different games are uploaded in parallel
+    upload_concurrency: AtomicUsize,
+    /// Encryption is turned on in the settings, see `set_encryption_required`
+    encryption_required: AtomicBool,
+    /// Last usage stats from the API and when they were fetched
+    usage_cache: Arc<RwLock<Option<(std::time::Instant, UsageStats)>>>,
+    /// Highest `QUOTA_*` level the user was told about, so they're not told on every sync
+    quota_warning_level: AtomicU8,
+    /// Uploads are held back until then after the API rejected one for the quota
+    quota_blocked_until: Arc<RwLock<Option<std::time::Instant>>>,
+    /// Subscription tier, fetched along with the usage stats
+    subscription_tier: Arc<RwLock<Option<SubscriptionTier>>>,
+    /// Cloud game IDs of the active profile's account
     game_cache: Arc<RwLock<HashMap<String, Uuid>>>,
-    conflict_strategy: ConflictResolutionStrategy,
-    device_id: String,
+    conflict_strategy: Arc<RwLock<ConflictResolutionStrategy>>,
+    /// Stable id for this installation in the active profile, loaded from the database on first use
+    device_id: RwLock<Option<String>>,
     device_name: String,
+    /// Posts repeated sync failures and sign-outs to the user's webhook, if any
+    failure_webhook: FailureWebhook,
+    #[cfg(feature = "gui")]
     notification_service: Option<Arc<crate::ui::notifications::NotificationManager>>,
+    online: watch::Sender<bool>,
+    /// Progress of the upload or download pass running right now
+    progress: watch::Sender<Option<SyncProgress>>,
+    /// Emulators the monitor reports as running
+    running_emulators: RwLock<HashSet<String>>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ConflictResolutionStrategy {
+    #[default]
     NewerWins,      // Default: newer timestamp wins
     LocalFirst,     // Always prefer local changes
     CloudFirst,     // Always prefer cloud changes
     Manual,         // Ask user (not implemented yet)
 }
 
+impl ConflictResolutionStrategy {
+    pub const ALL: [ConflictResolutionStrategy; 4] = [
+        ConflictResolutionStrategy::NewerWins,
+        ConflictResolutionStrategy::LocalFirst,
+        ConflictResolutionStrategy::CloudFirst,
+        ConflictResolutionStrategy::Manual,
+    ];
+    
+    /// Stable identifier used when persisting the setting
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictResolutionStrategy::NewerWins => "newer_wins",
+            ConflictResolutionStrategy::LocalFirst => "local_first",
+            ConflictResolutionStrategy::CloudFirst => "cloud_first",
+            ConflictResolutionStrategy::Manual => "manual",
+        }
+    }
+    
+    pub fn from_key(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+    
+    /// Human readable name for the settings window
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictResolutionStrategy::NewerWins => "Newer save wins",
+            ConflictResolutionStrategy::LocalFirst => "Always keep local save",
+            ConflictResolutionStrategy::CloudFirst => "Always use cloud save",
+            ConflictResolutionStrategy::Manual => "Ask me (uses newer save for now)",
+        }
+    }
+}
+
+/// Aborts the tasks when dropped, including when their owner panics
+struct AbortOnDrop(Vec<tokio::task::JoinHandle<()>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct UploadTask {
     game_name: String,
@@ -68,6 +223,37 @@ struct UploadTask {
     file_hash: String,
     file_size: i64,
     timestamp: chrono::DateTime<Utc>,
+    /// Backup to read instead of `file_path`, for a version that may have
+    /// been overwritten on disk since
+    #[serde(default)]
+    backup_path: Option<String>,
+}
+
+/// Why a cloud save couldn't be restored
+enum DownloadFailure {
+    /// Download failed or the data didn't match its hash
+    Failed,
+    /// Encrypted with a key this device doesn't have
+    Encrypted,
+}
+
+/// What happened to one queued upload
+enum UploadOutcome {
+    Uploaded,
+    /// The cloud already has a save with the same content
+    AlreadyInCloud,
+    /// Sync was turned off for the game, the task is dropped
+    Skipped,
+    /// Put back at the front of the queue, e.g. over the storage limit
+    Requeued,
+}
+
+/// What happened to one cloud save during a download pass
+enum DownloadOutcome {
+    Downloaded,
+    /// Up to date locally, unsafe to restore, or the download failed
+    Skipped,
+    Encrypted,
 }
 
 impl SyncService {
@@ -77,11 +263,21 @@ impl SyncService {
         api_base_url: String,
         data_dir: Option<std::path::PathBuf>,
     ) -> Self {
-        let api = Arc::new(SyncApi::new(api_base_url.clone(), auth_manager.clone()));
+        let api = Arc::new(SyncApi::new(api_base_url, auth_manager.clone()));
+        Self::with_api(api, auth_manager, database, data_dir)
+    }
+    
+    /// Sync service talking to `api` instead of the Retrosave server, so
+    /// tests can drive uploads, downloads and conflicts against a mock backend
+    pub fn with_api(
+        api: Arc<dyn CloudApi>,
+        auth_manager: Arc<AuthManager>,
+        database: Arc<Database>,
+        data_dir: Option<std::path::PathBuf>,
+    ) -> Self {
         let encryption = Arc::new(RwLock::new(EncryptionManager::new(data_dir)));
         
-        // Generate device ID and name
-        let device_id = Uuid::new_v4().to_string();
+        // The device ID is persisted in the database, see `device_id()`
         let device_name = gethostname::gethostname()
             .to_string_lossy()
             .to_string();
@@ -98,17 +294,71 @@ impl SyncService {
                 pending_uploads: 0,
                 pending_downloads: 0,
                 total_synced: 0,
+                is_online: true,
+                last_sync_timed_out: false,
             })),
             upload_queue: Arc::new(RwLock::new(VecDeque::new())),
+            current_uploads: Arc::new(RwLock::new(Vec::new())),
+            shutting_down: AtomicBool::new(false),
+            sync_after_closing_only: AtomicBool::new(false),
+            restore_deleted_saves: AtomicBool::new(false),
+            download_concurrency: AtomicUsize::new(DEFAULT_DOWNLOAD_CONCURRENCY),
+            upload_concurrency: AtomicUsize::new(DEFAULT_UPLOAD_CONCURRENCY),
+            encryption_required: AtomicBool::new(false),
+            usage_cache: Arc::new(RwLock::new(None)),
+            quota_warning_level: AtomicU8::new(QUOTA_OK),
+            quota_blocked_until: Arc::new(RwLock::new(None)),
+            subscription_tier: Arc::new(RwLock::new(None)),
             game_cache: Arc::new(RwLock::new(HashMap::new())),
-            conflict_strategy: ConflictResolutionStrategy::NewerWins,
-            device_id,
+            conflict_strategy: Arc::new(RwLock::new(ConflictResolutionStrategy::NewerWins)),
+            device_id: RwLock::new(None),
+            failure_webhook: FailureWebhook::new(device_name.clone()),
             device_name,
+            #[cfg(feature = "gui")]
             notification_service: None,
+            online: watch::channel(true).0,
+            progress: watch::channel(None).0,
+            running_emulators: RwLock::new(HashSet::new()),
         }
     }
     
+    /// Hold uploads of saves made while their emulator is running until it stops
+    pub fn set_sync_after_closing_only(&self, enabled: bool) {
+        self.sync_after_closing_only.store(enabled, Ordering::SeqCst);
+    }
+    
+    /// Let the cloud always win over local deletions. Otherwise a deleted save
+    /// file only comes back for cloud saves made after it was deleted.
+    pub fn set_restore_deleted_saves(&self, enabled: bool) {
+        self.restore_deleted_saves.store(enabled, Ordering::SeqCst);
+    }
+    
+    /// Webhook (Discord, Slack or any URL taking a JSON POST) told about
+    /// repeated sync failures and sign-outs, `None` to turn it off
+    pub fn set_failure_webhook(&self, url: Option<String>) {
+        self.failure_webhook.set_url(url);
+    }
+    
+    /// How many cloud saves to download at once. Saves sharing a file are
+    /// always written one after another.
+    pub fn set_download_concurrency(&self, concurrency: usize) {
+        self.download_concurrency.store(concurrency.max(1), Ordering::SeqCst);
+    }
+    
+    /// How many saves to upload at once. Saves of the same game are always
+    /// uploaded one after another, oldest first.
+    pub fn set_upload_concurrency(&self, concurrency: usize) {
+        self.upload_concurrency.store(concurrency.max(1), Ordering::SeqCst);
+    }
+    
+    /// Cap save transfer speed in KB/s (0 = unlimited)
+    pub fn set_bandwidth_limit(&self, kbps: u32) {
+        info!("Sync bandwidth limit set to {}", if kbps == 0 { "unlimited".to_string() } else { format!("{} KB/s", kbps) });
+        self.api.set_bandwidth_limit(kbps);
+    }
+    
     /// Set the notification service
+    #[cfg(feature = "gui")]
     pub fn with_notification_service(mut self, service: Arc<crate::ui::notifications::NotificationManager>) -> Self {
         self.notification_service = Some(service);
         self
@@ -117,7 +367,7 @@ impl SyncService {
     /// Start the sync service
     pub async fn start(
         self: Arc<Self>,
-        mut event_rx: mpsc::UnboundedReceiver<SyncEvent>,
+        event_rx: &mut mpsc::UnboundedReceiver<SyncEvent>,
     ) -> Result<()> {
         info!("Starting sync service");
 
@@ -126,14 +376,18 @@ impl SyncService {
             warn!("Failed to initialize auth: {}", e);
         }
         
-        // Restore upload queue from database
-        if let Err(e) = self.restore_upload_queue().await {
-            warn!("Failed to restore upload queue: {}", e);
+        // Restore upload queue from database, unless this is a restart and it's still in memory
+        if self.upload_queue.read().await.is_empty() {
+            if let Err(e) = self.restore_upload_queue().await {
+                warn!("Failed to restore upload queue: {}", e);
+            }
         }
         
-        // Initialize WebSocket if authenticated
+        // Initialize WebSocket if authenticated, there's no server to connect to with a local folder
         let auth_state = self.auth_manager.get_state().await;
-        if auth_state.is_authenticated {
+        if auth_state.is_authenticated && self.api.requires_auth() {
+            self.register_device().await;
+            
             if let Some(tokens) = auth_state.tokens {
                 let service_arc = self.clone();
                 if let Err(e) = service_arc.init_websocket(tokens.access_token).await {
@@ -145,22 +399,42 @@ impl SyncService {
         } else {
         }
 
+        // Stopped with this call so a restarted service doesn't run them twice
+        let mut background_tasks = AbortOnDrop(Vec::new());
+        
+        // Watch connectivity and drain the queue once we're back online
+        let sync_service = self.clone();
+        background_tasks.0.push(tokio::spawn(async move {
+            let mut check_interval = interval(CONNECTIVITY_CHECK_INTERVAL);
+            loop {
+                check_interval.tick().await;
+                if sync_service.is_online() {
+                    continue; // perform_sync notices when we go offline
+                }
+                
+                if sync_service.check_connectivity().await && sync_service.can_sync().await {
+                    if let Err(e) = sync_service.perform_sync().await {
+                        error!("Sync after reconnect failed: {}", e);
+                    }
+                }
+            }
+        }));
+        
         // Spawn periodic sync task
         let sync_service = self.clone();
-        tokio::spawn(async move {
+        background_tasks.0.push(tokio::spawn(async move {
             let mut sync_interval = interval(Duration::from_secs(1800)); // Sync every 30 minutes
             
             loop {
                 sync_interval.tick().await;
                 
-                let auth_state = sync_service.auth_manager.get_state().await;
-                if auth_state.is_authenticated {
+                if sync_service.can_sync().await {
                     if let Err(e) = sync_service.perform_sync().await {
                         error!("Periodic sync failed: {}", e);
                     }
                 }
             }
-        });
+        }));
 
         // Handle events
         while let Some(event) = event_rx.recv().await {
@@ -176,13 +450,14 @@ impl SyncService {
                         file_hash,
                         file_size,
                         timestamp: Utc::now(),
+                        backup_path: None,
                     };
                     
                     let mut queue = self.upload_queue.write().await;
                     queue.push_back(task);
                     
                     let mut status = self.status.write().await;
-                    status.pending_uploads = queue.len();
+                    status.set_pending_uploads(queue.len());
                     drop(queue); // Release lock before persisting
                     
                     // Persist queue to database
@@ -190,9 +465,12 @@ impl SyncService {
                         warn!("Failed to persist upload queue: {}", e);
                     }
                     
-                    // Trigger sync if authenticated
-                    let auth_state = self.auth_manager.get_state().await;
-                    if auth_state.is_authenticated {
+                    drop(status);
+                    
+                    // Trigger sync if authenticated and online, otherwise the save just waits in the queue
+                    if !self.is_online() {
+                        debug!("Offline, queued upload for later");
+                    } else if self.can_sync().await {
                         let sync_service = self.clone();
                         tokio::spawn(async move {
                             if let Err(e) = sync_service.perform_sync().await {
@@ -204,8 +482,7 @@ impl SyncService {
                 
                 SyncEvent::SyncRequested => {
                     info!("Manual sync requested");
-                    let auth_state = self.auth_manager.get_state().await;
-                    if auth_state.is_authenticated {
+                    if self.can_sync().await {
                         let sync_service = self.clone();
                         tokio::spawn(async move {
                             if let Err(e) = sync_service.perform_sync().await {
@@ -218,9 +495,10 @@ impl SyncService {
                 }
                 
                 SyncEvent::AuthChanged(is_authenticated) => {
-                    if is_authenticated {
+                    if is_authenticated && self.api.requires_auth() {
                         // Clear game cache to refresh from server
                         self.game_cache.write().await.clear();
+                        self.register_device().await;
                         
                         // Initialize WebSocket
                         let auth_state = self.auth_manager.get_state().await;
@@ -248,6 +526,7 @@ impl SyncService {
                 
                 SyncEvent::AuthFailed(reason) => {
                     warn!("Authentication failed: {}", reason);
+                    self.failure_webhook.auth_failed(&reason);
                     // Auth manager already handles logout/cleanup
                     // Just disconnect WebSocket
                     self.disconnect_websocket().await;
@@ -257,9 +536,10 @@ impl SyncService {
 
         Ok(())
     }
-
-    /// Perform synchronization
-    async fn perform_sync(&self) -> Result<()> {
+    
+    /// Upload the queue and download new cloud saves. Public so tests can run
+    /// a sync without the event loop, the app goes through `trigger_sync`.
+    pub async fn perform_sync(&self) -> Result<()> {
         // Check if already syncing
         {
             let mut status = self.status.write().await;
@@ -270,6 +550,15 @@ impl SyncService {
             status.is_syncing = true;
         }
 
+        // Defer everything to the queue while offline, the connectivity task drains it later
+        if !self.check_connectivity().await {
+            debug!("Offline, deferring sync");
+            self.status.write().await.is_syncing = false;
+            METRICS.sync_failed();
+            self.failure_webhook.sync_failed("the cloud is unreachable");
+            return Ok(());
+        }
+        
         info!("Starting sync");
         
         // Notify sync started via WebSocket
@@ -279,9 +568,15 @@ impl SyncService {
         let upload_result = self.process_upload_queue().await;
         let uploads = upload_result.as_ref().copied().unwrap_or(0);
         
+        // Push notes edited since they were uploaded
+        if let Err(e) = self.push_note_edits().await {
+            warn!("Failed to sync save notes: {}", e);
+        }
+        
         // Download new saves
         let download_result = self.download_new_saves().await;
         let downloads = 0; // TODO: Track download count in download_new_saves
+        self.progress.send_replace(None);
         
         // Update status
         {
@@ -289,10 +584,30 @@ impl SyncService {
             status.is_syncing = false;
             status.last_sync = Some(Utc::now());
             
+            let timed_out = [upload_result.as_ref().err(), download_result.as_ref().err()]
+                .into_iter()
+                .flatten()
+                .any(ApiError::is_timeout);
+            status.last_sync_timed_out = timed_out;
+            
+            METRICS.sync_finished(Utc::now());
             if upload_result.is_ok() && download_result.is_ok() {
                 info!("Sync completed successfully");
+                self.failure_webhook.sync_succeeded();
+            } else if timed_out {
+                METRICS.sync_failed();
+                warn!("Sync timed out, queued saves will be retried on the next sync");
+                self.failure_webhook.sync_failed("the sync timed out");
             } else {
+                METRICS.sync_failed();
                 warn!("Sync completed with errors");
+                let error = [upload_result.as_ref().err(), download_result.as_ref().err()]
+                    .into_iter()
+                    .flatten()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                self.failure_webhook.sync_failed(&error);
             }
         }
         
@@ -302,156 +617,296 @@ impl SyncService {
         Ok(())
     }
 
-    /// Process upload queue
-    async fn process_upload_queue(&self) -> Result<usize> {
-        let mut processed = 0;
-        
-        loop {
-            let task = {
-                let mut queue = self.upload_queue.write().await;
-                queue.pop_front()
-            };
-            
-            let Some(task) = task else {
-                break;
-            };
-            
-            debug!("Processing upload: {} for {}", task.game_name, task.emulator);
-            
-            // Read file data first to extract game_id for PS2 memory cards
-            let data = tokio::fs::read(&task.file_path).await
-                .context("Failed to read save file")?;
-            
-            // Extract game_id - just look it up from our database using the game name!
-            let extracted_game_id = if task.emulator.to_lowercase() == "pcsx2" {
-                // Simply look up the game ID by the window title name
-                let game_id = crate::storage::game_database::lookup_game_id_by_name(&task.game_name);
-                
-                if let Some(ref id) = game_id {
-                    info!("Found game_id {} for '{}' in database", id, task.game_name);
-                } else {
-                    warn!("Could not find game_id for '{}' in database", task.game_name);
+    /// Usage stats for the account, refreshed at most every `USAGE_CACHE_TTL`.
+    /// Limits the server leaves out are filled in from the subscription tier.
+    async fn cached_usage_stats(&self) -> Option<UsageStats> {
+        let cached = self.usage_cache.read().await.clone();
+        let usage = match &cached {
+            Some((fetched_at, usage)) if fetched_at.elapsed() < USAGE_CACHE_TTL => Some(usage.clone()),
+            _ => {
+                match self.api.get_subscription_status().await {
+                    Ok(subscription) => {
+                        *self.subscription_tier.write().await = SubscriptionTier::from_id(&subscription.tier.id);
+                    }
+                    Err(e) => debug!("Failed to fetch subscription status: {}", e),
                 }
                 
-                game_id
-            } else if task.emulator.to_lowercase() == "dolphin" {
-                // Check if it's a GCI file
-                let path = std::path::Path::new(&task.file_path);
-                if path.extension().map_or(false, |e| e == "gci") {
-                    // Extract game ID from GCI file
-                    if let Some(gci) = crate::storage::gci_parser::GCIFile::parse(&task.file_path) {
-                        let game_id = gci.get_game_id();
-                        info!("Extracted game_id {} from GCI file for '{}'", game_id, task.game_name);
-                        Some(game_id)
-                    } else {
-                        warn!("Could not parse GCI file to extract game_id for '{}'", task.game_name);
-                        None
+                match self.api.get_usage_stats().await {
+                    Ok(usage) => {
+                        *self.usage_cache.write().await = Some((std::time::Instant::now(), usage.clone()));
+                        Some(usage)
+                    }
+                    Err(e) => {
+                        // Don't hold back uploads just because the stats are unavailable
+                        debug!("Failed to fetch usage stats: {}", e);
+                        cached.map(|(_, usage)| usage)
                     }
-                } else {
-                    None
                 }
-            } else {
-                None
-            };
-            
-            // Get or register game with cloud (returns UUID)
-            let cloud_game_id = self.get_or_register_game_with_id(&task.game_name, &task.emulator, extracted_game_id.clone()).await?;
-            
-            // Also ensure we have a local game record
-            let _local_game = self.database
-                .get_or_create_game(&task.game_name, &task.emulator)
-                .await?;
-            
-            // Optionally encrypt before compression
-            let processed_data = {
-                let encryption = self.encryption.read().await;
-                if encryption.is_enabled() {
-                    debug!("Encrypting save before upload");
-                    let encrypted_save = encryption.encrypt_save(&data)
-                        .context("Failed to encrypt save")?;
-                    serde_json::to_vec(&encrypted_save)
-                        .context("Failed to serialize encrypted save")?
-                } else {
-                    data
+            }
+        }?;
+        
+        Some(match *self.subscription_tier.read().await {
+            Some(tier) => usage.with_tier_defaults(tier),
+            None => usage,
+        })
+    }
+    
+    /// Use usage stats pushed by the server instead of waiting for the next fetch
+    pub async fn set_usage_stats(&self, usage: UsageStats) {
+        if !usage.is_over_limit() {
+            *self.quota_blocked_until.write().await = None;
+        }
+        *self.usage_cache.write().await = Some((std::time::Instant::now(), usage));
+    }
+    
+    /// Count an upload against the cached usage until the next fetch
+    async fn record_upload_usage(&self, size: i64) {
+        if let Some((_, ref mut usage)) = *self.usage_cache.write().await {
+            usage.saves_count += 1;
+            usage.storage_bytes += size;
+        }
+    }
+    
+    /// Check the account quota before uploading `upload_size` more bytes and
+    /// return the `QUOTA_*` level, telling the user once each time usage
+    /// crosses a threshold. The server still has the final say, this only
+    /// avoids requests that are bound to be rejected.
+    async fn check_quota(&self, upload_size: i64) -> u8 {
+        let usage = self.cached_usage_stats().await;
+        
+        if self.subscription_tier.read().await.is_some_and(|tier| tier.is_unlimited()) {
+            return QUOTA_OK;
+        }
+        
+        if let Some(until) = *self.quota_blocked_until.read().await {
+            if std::time::Instant::now() < until {
+                debug!("Cloud sync limit reached recently, not uploading");
+                return QUOTA_OVER_LIMIT;
+            }
+        }
+        
+        let Some(usage) = usage else {
+            return QUOTA_OK;
+        };
+        
+        let level = if usage.would_exceed(upload_size) {
+            QUOTA_OVER_LIMIT
+        } else if usage.is_near_limit() {
+            QUOTA_NEAR_LIMIT
+        } else {
+            QUOTA_OK
+        };
+        let previous = self.quota_warning_level.swap(level, Ordering::SeqCst);
+        
+        if level == QUOTA_OVER_LIMIT {
+            warn!("Cloud storage limit reached ({}/{} saves, {}/{} bytes), pausing uploads",
+                usage.saves_count, usage.saves_limit, usage.storage_bytes, usage.storage_limit_bytes);
+            if previous < QUOTA_OVER_LIMIT {
+                let pruned = self.prune_local_saves().await;
+                if pruned > 0 {
+                    info!("Removed {} older local versions of games waiting to upload", pruned);
+                }
+                self.notify_limit_reached("New saves are kept locally but won't sync to the cloud, and only the newest few versions of each waiting game are kept. Delete old cloud saves or upgrade your plan to resume syncing.");
+            }
+        } else if level == QUOTA_NEAR_LIMIT && previous < QUOTA_NEAR_LIMIT {
+            warn!("Cloud storage is almost full ({:.0}% of saves, {:.0}% of storage)",
+                usage.saves_percentage(), usage.storage_percentage());
+            self.notify_warning(
+                "Cloud Storage Almost Full",
+                &format!("You've used {:.0}% of your saves and {:.0}% of your storage. Only the newest save of each game will be synced until you free up space or upgrade.",
+                    usage.saves_percentage(), usage.storage_percentage())
+            );
+        }
+        
+        level
+    }
+    
+    /// Apply the tighter over-limit retention to the games waiting to upload,
+    /// so old versions don't pile up while nothing can sync. Pinned versions
+    /// are kept. Returns how many versions were removed.
+    async fn prune_local_saves(&self) -> usize {
+        let games: HashSet<(String, String)> = self.upload_queue.read().await
+            .iter()
+            .map(|task| (task.game_name.clone(), task.emulator.clone()))
+            .collect();
+        
+        let mut removed = 0;
+        for (name, emulator) in games {
+            let game = match self.database.get_or_create_game(&name, &emulator).await {
+                Ok(game) => game,
+                Err(e) => {
+                    warn!("Failed to look up {} to clean up old saves: {}", name, e);
+                    continue;
                 }
             };
-            
-            // Compress data
-            let compressed_data = zstd::encode_all(processed_data.as_slice(), 3)
-                .context("Failed to compress save")?;
-            
-            // Calculate hash of compressed data
-            let mut hasher = Sha256::new();
-            hasher.update(&compressed_data);
-            let hash = format!("{:x}", hasher.finalize());
-            
-            // Request upload URL with file path in metadata
-            let upload_response = match self.api
-                .request_upload_url_with_metadata(
-                    cloud_game_id, 
-                    &hash, 
-                    compressed_data.len() as i64, 
-                    task.timestamp,
-                    Some(serde_json::json!({
-                        "file_path": task.file_path.clone(),
-                        "game_name": task.game_name.clone(),
-                        "emulator": task.emulator.clone(),
-                        "game_id": extracted_game_id.clone(),
-                    }))
-                )
-                .await {
-                    Ok(response) => response,
-                    Err(e) => {
-                        // Check if this is a limit exceeded error
-                        let error_str = e.to_string();
-                        if error_str.contains("402") || error_str.contains("limit") || error_str.contains("exceeded") {
-                            warn!("Cloud sync limit exceeded for {}: {}", task.game_name, e);
-                            
-                            // Show notification about limit
-                            if let Some(ref notif) = self.notification_service {
-                                notif.show_warning(
-                                    "Cloud Sync Limit Reached",
-                                    &format!("Save for {} was saved locally but couldn't sync to cloud. Upgrade your plan for more cloud storage.", task.game_name)
-                                );
-                            }
-                            
-                            // Keep the task in queue for later retry
-                            continue;
+            match self.database.cleanup_old_saves_by_class(game.id, &game.emulator, OVER_LIMIT_KEEP_SAVES, OVER_LIMIT_KEEP_SAVE_STATES).await {
+                Ok(deleted) => {
+                    for backup_path in deleted.iter().filter_map(|s| s.backup_path.as_ref()) {
+                        if let Err(e) = std::fs::remove_file(backup_path) {
+                            debug!("Failed to delete backup {}: {}", backup_path, e);
                         }
-                        
-                        // For other errors, propagate them
-                        return Err(e);
                     }
+                    removed += deleted.len();
+                }
+                Err(e) => warn!("Failed to clean up old saves of {}: {}", name, e),
+            }
+        }
+        removed
+    }
+    
+    /// Drop queued uploads that have a newer save of the same file queued.
+    /// Returns how many were dropped.
+    async fn compact_upload_queue(&self) -> usize {
+        let mut queue = self.upload_queue.write().await;
+        let before = queue.len();
+        
+        let mut newest: HashMap<String, chrono::DateTime<Utc>> = HashMap::new();
+        for task in queue.iter() {
+            let timestamp = newest.entry(task.file_path.clone()).or_insert(task.timestamp);
+            if task.timestamp > *timestamp {
+                *timestamp = task.timestamp;
+            }
+        }
+        queue.retain(|task| newest.get(&task.file_path) == Some(&task.timestamp));
+        
+        let dropped = before - queue.len();
+        if dropped > 0 {
+            self.status.write().await.set_pending_uploads(queue.len());
+        }
+        dropped
+    }
+    
+    fn notify_limit_reached(&self, message: &str) {
+        self.notify_warning("Cloud Sync Limit Reached", message);
+    }
+    
+    /// Desktop notification, headless builds only log it
+    fn notify_warning(&self, title: &str, message: &str) {
+        #[cfg(feature = "gui")]
+        if let Some(ref notif) = self.notification_service {
+            notif.show_warning(title, message);
+        }
+        #[cfg(not(feature = "gui"))]
+        info!("{}: {}", title, message);
+    }
+    
+    /// Upload the queued saves, returns how many were uploaded
+    pub async fn process_upload_queue(&self) -> Result<usize> {
+        let mut processed = 0;
+        let mut skipped = 0;
+        let mut already_in_cloud = 0;
+        
+        if self.encryption_required.load(Ordering::SeqCst) && !self.encryption.read().await.is_enabled() {
+            if !self.upload_queue.read().await.is_empty() {
+                warn!("Encryption is locked, holding uploads until the passphrase is entered");
+            }
+            return Ok(0);
+        }
+        
+        if !self.upload_queue.read().await.is_empty() {
+            let quota = self.check_quota(0).await;
+            // Near the limit only the newest save of each file is worth a slot
+            if quota >= QUOTA_NEAR_LIMIT {
+                let dropped = self.compact_upload_queue().await;
+                if dropped > 0 {
+                    info!("Dropped {} older queued uploads to save cloud space", dropped);
+                }
+            }
+            // Leave the queue alone while the account is over its limit
+            if quota == QUOTA_OVER_LIMIT {
+                return Ok(0);
+            }
+        }
+        
+        let concurrency = self.upload_concurrency.load(Ordering::SeqCst).max(1);
+        let mut in_flight = FuturesUnordered::new();
+        let mut busy_games: HashSet<String> = HashSet::new();
+        let mut stop = false;
+        let mut first_error = None;
+        let mut finished = 0;
+        
+        let queued = self.upload_queue.read().await.len();
+        if queued > 0 {
+            self.progress.send_replace(Some(SyncProgress { phase: SyncPhase::Uploading, current: 0, total: queued }));
+        }
+        
+        loop {
+            while !stop && in_flight.len() < concurrency {
+                // Leave the rest of the queue for the next start
+                if self.shutting_down.load(Ordering::SeqCst) {
+                    debug!("Shutting down, not starting more uploads");
+                    stop = true;
+                    break;
+                }
+                
+                // Mid-session saves stay queued, in order, until the game is closed
+                let held_emulators = if self.sync_after_closing_only.load(Ordering::SeqCst) {
+                    self.running_emulators.read().await.clone()
+                } else {
+                    HashSet::new()
                 };
+                let task = {
+                    let mut queue = self.upload_queue.write().await;
+                    Self::take_next_upload(&mut queue, &busy_games, &held_emulators)
+                };
+                
+                let Some(task) = task else {
+                    break;
+                };
+                
+                busy_games.insert(task.game_name.clone());
+                self.current_uploads.write().await.push(task.clone());
+                in_flight.push(async move {
+                    let key = (task.game_name.clone(), task.file_path.clone(), task.timestamp);
+                    (key, self.upload_task(task).await)
+                });
+            }
             
-            // Upload data
-            self.api
-                .upload_save_data(&upload_response.upload_url, compressed_data)
-                .await?;
-            
-            processed += 1;
-            info!("Uploaded save for {}", task.game_name);
+            // Uploads already started are always allowed to finish
+            let Some(((game_name, file_path, timestamp), result)) = in_flight.next().await else {
+                break;
+            };
+            busy_games.remove(&game_name);
+            self.current_uploads.write().await
+                .retain(|task| task.file_path != file_path || task.timestamp != timestamp);
             
-            // Notify via WebSocket that a save was uploaded
-            self.notify_save_uploaded(
-                cloud_game_id.to_string(),
-                task.game_name.clone(),
-                task.emulator.clone(),
-                upload_response.save_id.to_string()
-            ).await;
+            // Saves queued meanwhile count towards the total
+            finished += 1;
+            let remaining = self.upload_queue.read().await.len() + in_flight.len();
+            self.progress.send_replace(Some(SyncProgress {
+                phase: SyncPhase::Uploading,
+                current: finished,
+                total: finished + remaining,
+            }));
             
-            // Update status
-            {
-                let mut status = self.status.write().await;
-                let queue = self.upload_queue.read().await;
-                status.pending_uploads = queue.len();
-                status.total_synced += 1;
+            match result {
+                Ok(UploadOutcome::Uploaded) => processed += 1,
+                Ok(UploadOutcome::AlreadyInCloud) => already_in_cloud += 1,
+                Ok(UploadOutcome::Skipped) => skipped += 1,
+                // Over the storage limit, nothing else will get through either
+                Ok(UploadOutcome::Requeued) => stop = true,
+                Err(e) => {
+                    stop = true;
+                    if first_error.is_none() {
+                        first_error = Some(e);
+                    } else {
+                        warn!("Upload failed: {}", e);
+                    }
+                }
             }
         }
         
+        self.progress.send_replace(None);
+        
         if processed > 0 {
             info!("Uploaded {} saves", processed);
-            
+        }
+        
+        if already_in_cloud > 0 {
+            info!("Skipped {} saves the cloud already had", already_in_cloud);
+        }
+        
+        if processed > 0 || skipped > 0 || already_in_cloud > 0 {
             // Persist updated queue (or clear if empty)
             let queue = self.upload_queue.read().await;
             if queue.is_empty() {
@@ -466,11 +921,257 @@ impl SyncService {
             }
         }
         
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+        
         Ok(processed)
     }
+    
+    /// Take the first queued upload that may start now: its emulator isn't held
+    /// and no earlier save of the same game is uploading or still queued, so a
+    /// game's versions reach the cloud in the order they were made
+    fn take_next_upload(
+        queue: &mut VecDeque<UploadTask>,
+        busy_games: &HashSet<String>,
+        held_emulators: &HashSet<String>,
+    ) -> Option<UploadTask> {
+        let mut passed_games: HashSet<&str> = HashSet::new();
+        let mut next = None;
+        for (index, task) in queue.iter().enumerate() {
+            if !busy_games.contains(&task.game_name)
+                && !passed_games.contains(task.game_name.as_str())
+                && !Self::emulator_in_use(held_emulators, &task.emulator)
+            {
+                next = Some(index);
+                break;
+            }
+            passed_games.insert(&task.game_name);
+        }
+        next.and_then(|index| queue.remove(index))
+    }
+    
+    /// Upload one queued save
+    async fn upload_task(&self, mut task: UploadTask) -> Result<UploadOutcome> {
+        debug!("Processing upload: {} for {}", task.game_name, task.emulator);
+        
+        // Sync may have been disabled for this game after the task was queued
+        let local_game = self.database
+            .get_or_create_game(&task.game_name, &task.emulator)
+            .await?;
+        // The game may have been renamed or merged since, upload it under its current name
+        if local_game.name != task.game_name {
+            debug!("{} is now known as {}", task.game_name, local_game.name);
+            task.game_name = local_game.name.clone();
+        }
+        if !local_game.sync_enabled {
+            info!("Cloud sync disabled for {}, dropping queued upload", task.game_name);
+            let mut status = self.status.write().await;
+            let queue = self.upload_queue.read().await;
+            status.set_pending_uploads(queue.len());
+            return Ok(UploadOutcome::Skipped);
+        }
+        
+        // A save detected again after a restart may already have been synced
+        match self.database.get_cloud_save_id(local_game.id, &task.file_hash).await {
+            Ok(Some(cloud_save_id)) => {
+                info!("Cloud already has this save of {} ({}), not uploading it again", task.game_name, cloud_save_id);
+                let mut status = self.status.write().await;
+                let queue = self.upload_queue.read().await;
+                status.set_pending_uploads(queue.len());
+                status.total_synced += 1;
+                return Ok(UploadOutcome::AlreadyInCloud);
+            }
+            Ok(None) => {}
+            Err(e) => debug!("Couldn't look up the cloud copy of {}'s save: {}", task.game_name, e),
+        }
+        
+        // Read file data first to extract game_id for PS2 memory cards
+        let data = match &task.backup_path {
+            Some(backup_path) => Self::read_backup(backup_path).await?,
+            None => tokio::fs::read(&task.file_path).await
+                .context("Failed to read save file")?,
+        };
+        
+        // Extract game_id - just look it up from our database using the game name!
+        let extracted_game_id = if task.emulator.to_lowercase() == "pcsx2" {
+            // Simply look up the game ID by the window title name
+            let game_id = crate::storage::game_database::lookup_game_id_by_name(&task.game_name);
+            
+            if let Some(ref id) = game_id {
+                info!("Found game_id {} for '{}' in database", id, task.game_name);
+            } else {
+                warn!("Could not find game_id for '{}' in database", task.game_name);
+            }
+            
+            game_id
+        } else if task.emulator.to_lowercase() == "dolphin" {
+            // Check if it's a GCI file
+            let path = std::path::Path::new(&task.file_path);
+            if path.extension().is_some_and(|e| e == "gci") {
+                // Extract game ID from GCI file
+                if let Some(gci) = crate::storage::gci_parser::GCIFile::parse(&task.file_path) {
+                    let game_id = gci.get_game_id();
+                    info!("Extracted game_id {} from GCI file for '{}'", game_id, task.game_name);
+                    Some(game_id)
+                } else {
+                    warn!("Could not parse GCI file to extract game_id for '{}'", task.game_name);
+                    None
+                }
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+        
+        // Get or register game with cloud (returns UUID)
+        let cloud_game_id = self.get_or_register_game_with_id(&task.game_name, &task.emulator, extracted_game_id.clone()).await?;
+        
+        // Optionally encrypt before compression
+        let (processed_data, encrypted) = {
+            let encryption = self.encryption.read().await;
+            if encryption.is_enabled() {
+                debug!("Encrypting save before upload");
+                let encrypted_save = encryption.encrypt_save(&data)
+                    .context("Failed to encrypt save")?;
+                let encrypted_data = serde_json::to_vec(&encrypted_save)
+                    .context("Failed to serialize encrypted save")?;
+                (encrypted_data, true)
+            } else {
+                (data, false)
+            }
+        };
+        
+        // Compress data
+        let compressed_data = zstd::encode_all(processed_data.as_slice(), 3)
+            .context("Failed to compress save")?;
+        
+        // Calculate hash of compressed data
+        let mut hasher = Sha256::new();
+        hasher.update(&compressed_data);
+        let hash = format!("{:x}", hasher.finalize());
+        
+        // Don't ask for an upload URL the server is going to refuse
+        if self.check_quota(compressed_data.len() as i64).await == QUOTA_OVER_LIMIT {
+            self.upload_queue.write().await.push_front(task);
+            return Ok(UploadOutcome::Requeued);
+        }
+        
+        // Notes are added in the save browser, possibly while the upload was queued
+        let note = match self.database.get_save_note_by_hash(&task.file_hash).await {
+            Ok(note) => note,
+            Err(e) => {
+                warn!("Failed to load note for {}: {}", task.game_name, e);
+                None
+            }
+        };
+        
+        // Request upload URL with file path in metadata
+        let upload_response = match self.api
+            .request_upload_url_with_metadata(
+                cloud_game_id, 
+                &hash, 
+                compressed_data.len() as i64, 
+                task.timestamp,
+                Some(serde_json::json!({
+                    "file_path": task.file_path.clone(),
+                    "game_name": task.game_name.clone(),
+                    "emulator": task.emulator.clone(),
+                    "game_id": extracted_game_id.clone(),
+                    "note": note,
+                    "encrypted": encrypted,
+                }))
+            )
+            .await {
+                Ok(response) => response,
+                Err(e) => {
+                    // Check if this is a limit exceeded error
+                    let error_str = e.to_string();
+                    if error_str.contains("402") || error_str.contains("limit") || error_str.contains("exceeded") {
+                        warn!("Cloud sync limit exceeded for {}: {}", task.game_name, e);
+                        
+                        // Show notification about limit
+                        if self.quota_warning_level.swap(QUOTA_OVER_LIMIT, Ordering::SeqCst) < QUOTA_OVER_LIMIT {
+                            self.notify_limit_reached(&format!("Save for {} was saved locally but couldn't sync to cloud. Upgrade your plan for more cloud storage.", task.game_name));
+                        }
+                        
+                        // Keep the task in queue for later retry, and don't
+                        // try again until the usage stats are due for a refresh
+                        self.upload_queue.write().await.push_front(task);
+                        *self.quota_blocked_until.write().await = Some(std::time::Instant::now() + USAGE_CACHE_TTL);
+                        *self.usage_cache.write().await = None;
+                        return Ok(UploadOutcome::Requeued);
+                    }
+                    
+                    // Timeouts are retried on the next sync
+                    if ApiError::is_timeout(&e) {
+                        warn!("Requesting an upload URL for {} timed out, keeping it queued", task.game_name);
+                        self.upload_queue.write().await.push_front(task);
+                    }
+                    
+                    // For other errors, propagate them
+                    return Err(e);
+                }
+            };
+        
+        // Upload data
+        let upload_size = compressed_data.len() as i64;
+        if let Err(e) = self.api
+            .upload_save_data(&upload_response.upload_url, compressed_data)
+            .await
+        {
+            if ApiError::is_timeout(&e) {
+                warn!("Upload of {} timed out, keeping it queued", task.game_name);
+                self.upload_queue.write().await.push_front(task);
+            }
+            return Err(e);
+        }
+        self.record_upload_usage(upload_size).await;
+        info!("Uploaded save for {}", task.game_name);
+        if let Err(e) = self.database.set_cloud_save_id(local_game.id, &task.file_hash, &upload_response.save_id.to_string()).await {
+            warn!("Failed to remember the cloud copy of {}'s save: {}", task.game_name, e);
+        }
+        
+        // Notify via WebSocket that a save was uploaded
+        self.notify_save_uploaded(
+            cloud_game_id.to_string(),
+            task.game_name.clone(),
+            task.emulator.clone(),
+            upload_response.save_id.to_string()
+        ).await;
+        
+        // Update status
+        {
+            let mut status = self.status.write().await;
+            let queue = self.upload_queue.read().await;
+            status.set_pending_uploads(queue.len());
+            status.total_synced += 1;
+        }
+        METRICS.upload_completed();
+        
+        Ok(UploadOutcome::Uploaded)
+    }
+
+    /// Send note edits of saves that are already in the cloud
+    async fn push_note_edits(&self) -> Result<()> {
+        for (save_id, cloud_save_id, note) in self.database.get_unsynced_notes().await? {
+            let Ok(cloud_id) = Uuid::parse_str(&cloud_save_id) else {
+                warn!("Invalid cloud save ID {}", cloud_save_id);
+                continue;
+            };
+            self.api.update_save_note(cloud_id, note.as_deref()).await?;
+            self.database.mark_note_synced(save_id, note.as_deref()).await?;
+        }
+        
+        Ok(())
+    }
 
     /// Download new saves from cloud
-    async fn download_new_saves(&self) -> Result<()> {
+    pub async fn download_new_saves(&self) -> Result<()> {
+        // Downloads held back by an emulator that has closed since, e.g. before a restart
+        self.write_deferred_downloads().await;
+        
         // Get list of saves from server
         let saves_response = self.api.list_saves(None, 1, 100).await?;
         
@@ -495,6 +1196,16 @@ impl SyncService {
                 game_name, version, &save.file_hash[0..8], has_metadata, save.client_timestamp);
         }
         
+        // Take notes other devices wrote on saves we have
+        for save in &saves_response.items {
+            let note = save.metadata.as_ref()
+                .and_then(|m| m.get("note"))
+                .and_then(|n| n.as_str());
+            if self.database.apply_cloud_note(&save.id.to_string(), note).await? {
+                debug!("Updated note of save {} from the cloud", save.id);
+            }
+        }
+        
         // Group saves by file path to avoid downloading multiple versions of the same file
         // IMPORTANT: Group by game name + file name to handle old saves without full paths
         let mut saves_by_path: std::collections::HashMap<String, Vec<SaveMetadata>> = std::collections::HashMap::new();
@@ -579,379 +1290,572 @@ impl SyncService {
         
         // Track downloads
         let mut downloaded = 0;
-        let mut pending_downloads = newest_saves.len();
+        let mut encrypted_skipped = 0;
         
         // Update pending downloads count
         {
             let mut status = self.status.write().await;
-            status.pending_downloads = pending_downloads;
+            status.pending_downloads = newest_saves.len();
         }
+        self.progress.send_replace(Some(SyncProgress {
+            phase: SyncPhase::Downloading,
+            current: 0,
+            total: newest_saves.len(),
+        }));
         
-        // Process each cloud save (now deduplicated by path)
+        // Saves of several games can share one file (PS2 memory cards), so
+        // downloads for the same path run one after another and only
+        // different paths are fetched in parallel
+        let mut saves_by_file: Vec<(String, Vec<SaveMetadata>)> = Vec::new();
         for cloud_save in newest_saves {
-            // For now, skip saves we can't map to local games
-            // In a full implementation, we'd maintain a UUID->i64 mapping
-            // or store cloud game IDs in local database
-            
-            // Extract game info from metadata if available
-            let game_name = cloud_save.metadata
-                .as_ref()
-                .and_then(|m| m.get("game_name"))
-                .and_then(|n| n.as_str());
-            let emulator = cloud_save.metadata
-                .as_ref()
-                .and_then(|m| m.get("emulator"))
-                .and_then(|e| e.as_str());
+            let file_key = cloud_save.metadata.as_ref()
+                .and_then(|m| m.get("file_path"))
+                .and_then(|p| p.as_str())
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| format!("cloud_save_{}", cloud_save.id));
+            match saves_by_file.iter_mut().find(|(key, _)| *key == file_key) {
+                Some((_, saves)) => saves.push(cloud_save),
+                None => saves_by_file.push((file_key, vec![cloud_save])),
+            }
+        }
+        
+        let concurrency = self.download_concurrency.load(Ordering::SeqCst).max(1);
+        let mut downloads = futures_util::stream::iter(saves_by_file)
+            .map(|(_, saves)| self.download_cloud_saves_in_order(saves))
+            .buffer_unordered(concurrency);
+        
+        while let Some(result) = downloads.next().await {
+            let (file_downloaded, file_encrypted) = result?;
+            downloaded += file_downloaded;
+            encrypted_skipped += file_encrypted;
+        }
+        
+        if downloaded > 0 {
+            info!("Downloaded {} saves from cloud", downloaded);
+        }
+        
+        if encrypted_skipped > 0 {
+            self.notify_warning(
+                "Encrypted Saves Not Restored",
+                &format!("{} cloud save(s) are encrypted. Turn on encryption or enter your passphrase in Settings to restore them.", encrypted_skipped),
+            );
+        }
+        
+        Ok(())
+    }
+    
+    /// Restore saves that share a file one at a time. Returns how many were
+    /// downloaded and how many were skipped as encrypted.
+    async fn download_cloud_saves_in_order(&self, saves: Vec<SaveMetadata>) -> Result<(usize, usize)> {
+        let mut downloaded = 0;
+        let mut encrypted_skipped = 0;
+        
+        for cloud_save in saves {
+            let outcome = self.download_cloud_save(cloud_save).await;
             
-            // Skip if we don't have game info
-            let (game_name, emulator) = match (game_name, emulator) {
-                (Some(name), Some(emu)) => (name, emu),
-                _ => {
-                    debug!("Skipping save {} - no game metadata", cloud_save.id);
-                    continue;
+            {
+                let mut status = self.status.write().await;
+                status.pending_downloads = status.pending_downloads.saturating_sub(1);
+            }
+            self.progress.send_modify(|progress| {
+                if let Some(progress) = progress.as_mut().filter(|p| p.phase == SyncPhase::Downloading) {
+                    progress.current = (progress.current + 1).min(progress.total);
                 }
-            };
-            
-            // Get or create local game
-            let local_game = self.database
-                .get_or_create_game(game_name, emulator)
-                .await?;
-            
-            let local_saves = self.database
-                .get_saves_for_game(local_game.id, Some(10))
-                .await?;
-            
-            // Check if we already have this save by hash
-            let have_locally = local_saves.iter().any(|s| s.file_hash == cloud_save.file_hash);
+            });
             
-            // For memory cards, also check if the actual file hash matches
-            let file_hash_matches = if let Some(metadata) = &cloud_save.metadata {
-                if let Some(file_path) = metadata.get("file_path").and_then(|p| p.as_str()) {
-                    if let Ok(data) = tokio::fs::read(file_path).await {
-                        use sha2::{Sha256, Digest};
-                        let mut hasher = Sha256::new();
-                        hasher.update(&data);
-                        let hash = format!("{:x}", hasher.finalize());
-                        let matches = hash == cloud_save.file_hash;
-                        if !matches {
-                            info!("Memory card hash mismatch - local: {}, cloud: {}", hash, cloud_save.file_hash);
-                        }
-                        matches
-                    } else {
-                        false
+            match outcome? {
+                DownloadOutcome::Downloaded => {
+                    METRICS.download_completed();
+                    downloaded += 1;
+                }
+                DownloadOutcome::Encrypted => encrypted_skipped += 1,
+                DownloadOutcome::Skipped => {}
+            }
+        }
+        
+        Ok((downloaded, encrypted_skipped))
+    }
+    
+    /// Check one cloud save against the local state and restore it if needed
+    async fn download_cloud_save(&self, cloud_save: SaveMetadata) -> Result<DownloadOutcome> {
+        // For now, skip saves we can't map to local games
+        // In a full implementation, we'd maintain a UUID->i64 mapping
+        // or store cloud game IDs in local database
+        
+        // Extract game info from metadata if available
+        let game_name = cloud_save.metadata
+            .as_ref()
+            .and_then(|m| m.get("game_name"))
+            .and_then(|n| n.as_str());
+        let emulator = cloud_save.metadata
+            .as_ref()
+            .and_then(|m| m.get("emulator"))
+            .and_then(|e| e.as_str());
+        
+        // Skip if we don't have game info
+        let (game_name, emulator) = match (game_name, emulator) {
+            (Some(name), Some(emu)) => (name, emu),
+            _ => {
+                debug!("Skipping save {} - no game metadata", cloud_save.id);
+                return Ok(DownloadOutcome::Skipped);
+            }
+        };
+        
+        // Get or create local game
+        let local_game = self.database
+            .get_or_create_game(game_name, emulator)
+            .await?;
+        
+        let local_saves = self.database
+            .get_saves_for_game(local_game.id, Some(10))
+            .await?;
+        
+        // Check if we already have this save by hash
+        let have_locally = local_saves.iter().any(|s| s.file_hash == cloud_save.file_hash);
+        
+        // For memory cards, also check if the actual file hash matches
+        let file_hash_matches = if let Some(metadata) = &cloud_save.metadata {
+            if let Some(file_path) = metadata.get("file_path").and_then(|p| p.as_str()) {
+                if let Ok(data) = tokio::fs::read(file_path).await {
+                    use sha2::{Sha256, Digest};
+                    let mut hasher = Sha256::new();
+                    hasher.update(&data);
+                    let hash = format!("{:x}", hasher.finalize());
+                    let matches = hash == cloud_save.file_hash;
+                    if !matches {
+                        info!("Memory card hash mismatch - local: {}, cloud: {}", hash, cloud_save.file_hash);
                     }
+                    matches
                 } else {
                     false
                 }
             } else {
                 false
-            };
-            
-            // Check if the actual file exists on disk and has the specific game save
-            let (file_exists, needs_restore) = if let Some(metadata) = &cloud_save.metadata {
-                if let Some(file_path) = metadata.get("file_path").and_then(|p| p.as_str()) {
-                    if tokio::fs::metadata(file_path).await.is_ok() {
-                        // File exists, check if it has the actual game saves
-                        let emulator = metadata.get("emulator")
-                            .and_then(|e| e.as_str())
-                            .unwrap_or("");
-                        
-                        let save_type = SaveType::detect(&std::path::PathBuf::from(file_path), emulator);
-                        
-                        if let SaveType::MemoryCard { format, .. } = save_type {
-                            // Read file and check content
-                            if let Ok(data) = tokio::fs::read(file_path).await {
-                                match format {
-                                    MemoryCardFormat::PS2 => {
-                                        // Parse PS2 memory card to check for game saves
-                                        if let Some(card) = PS2MemoryCard::new(data.clone()) {
-                                            let game_name = metadata.get("game_name")
-                                                .and_then(|g| g.as_str())
-                                                .unwrap_or("Harry Potter");
-                                            
-                                            // Generate metadata for safety checks
-                                            let local_metadata = card.generate_metadata(game_name.to_string());
-                                            
-                                            // Check if we have the specific game save
-                                            let has_game_save = card.has_game_saves(game_name) || 
-                                                               card.has_harry_potter_save();
+            }
+        } else {
+            false
+        };
+        
+        // Check if the actual file exists on disk and has the specific game save
+        let (file_exists, needs_restore) = if let Some(metadata) = &cloud_save.metadata {
+            if let Some(file_path) = metadata.get("file_path").and_then(|p| p.as_str()) {
+                if tokio::fs::metadata(file_path).await.is_ok() {
+                    // File exists, check if it has the actual game saves
+                    let emulator = metadata.get("emulator")
+                        .and_then(|e| e.as_str())
+                        .unwrap_or("");
+                    
+                    let save_type = SaveType::detect(&std::path::PathBuf::from(file_path), emulator);
+                    
+                    if let SaveType::MemoryCard { format, .. } = save_type {
+                        // Read file and check content
+                        if let Ok(data) = tokio::fs::read(file_path).await {
+                            match format {
+                                MemoryCardFormat::PS2 | MemoryCardFormat::GameCube => {
+                                    // Parse the memory card to check for game saves
+                                    if let Some(card) = format.parse(data.clone()) {
+                                        // Without a game name we can't tell which saves on a shared card are ours
+                                        let Some(game_name) = metadata.get("game_name").and_then(|g| g.as_str()) else {
+                                            warn!("Cloud save {} has no game name in its metadata, skipping restore of {}", cloud_save.id, file_path);
+                                            return Ok(DownloadOutcome::Skipped);
+                                        };
+                                        
+                                        // Generate metadata for safety checks
+                                        let local_metadata = card.generate_metadata(game_name.to_string());
+                                        
+                                        // Check if we have the specific game save
+                                        let has_game_save = card.has_game_saves(game_name);
+                                        
+                                        // SAFETY CHECK: If we have OTHER games, be careful
+                                        if local_metadata.games_contained.len() > 1 {
+                                            // Parse cloud metadata if available
+                                            let cloud_metadata = metadata.get("memory_card_metadata")
+                                                .and_then(|m| serde_json::from_value::<retrosave_shared::MemoryCardMetadata>(m.clone()).ok());
                                             
-                                            // SAFETY CHECK: If we have OTHER games, be careful
-                                            if local_metadata.games_contained.len() > 1 {
-                                                // Parse cloud metadata if available
-                                                let cloud_metadata = metadata.get("memory_card_metadata")
-                                                    .and_then(|m| serde_json::from_value::<retrosave_shared::MemoryCardMetadata>(m.clone()).ok());
+                                            if let Some(cloud_meta) = cloud_metadata {
+                                                // Analyze conflicts
+                                                use crate::sync::conflict_resolution::{ConflictAnalyzer, ResolutionStrategy};
+                                                
+                                                let local_hash = crate::storage::hasher::hash_bytes(&data);
+                                                let cloud_hash = cloud_save.file_hash.clone();
+                                                let local_time = ConflictAnalyzer::file_modified_time(std::path::Path::new(file_path))
+                                                    .await
+                                                    .unwrap_or_else(|| {
+                                                        warn!("Could not read modification time of {}, assuming now", file_path);
+                                                        chrono::Utc::now()
+                                                    });
+                                                let cloud_time = cloud_save.client_timestamp;
                                                 
-                                                if let Some(cloud_meta) = cloud_metadata {
-                                                    // Analyze conflicts
-                                                    use crate::sync::conflict_resolution::{ConflictAnalyzer, ResolutionStrategy};
+                                                let conflicts = ConflictAnalyzer::analyze_memory_card_conflicts(
+                                                    &local_metadata,
+                                                    &cloud_meta,
+                                                    &local_hash,
+                                                    &cloud_hash,
+                                                    local_time,
+                                                    cloud_time,
+                                                );
+                                                
+                                                if !conflicts.is_empty() {
+                                                    warn!("Found {} conflicts in memory card", conflicts.len());
+                                                    for conflict in &conflicts {
+                                                        warn!("  - {} ({}): {:?}", 
+                                                            conflict.game_name, 
+                                                            conflict.game_id,
+                                                            conflict.conflict_type
+                                                        );
+                                                    }
                                                     
-                                                    let local_hash = crate::storage::hasher::hash_bytes(&data);
-                                                    let cloud_hash = cloud_save.file_hash.clone();
-                                                    let local_time = chrono::Utc::now(); // Should get actual file time
-                                                    let cloud_time = cloud_save.client_timestamp;
+                                                    // For now, use smart strategy (can be made interactive later)
+                                                    let resolution = ConflictAnalyzer::resolve_conflicts(
+                                                        &conflicts,
+                                                        ResolutionStrategy::Smart,
+                                                    );
                                                     
-                                                    let conflicts = ConflictAnalyzer::analyze_memory_card_conflicts(
-                                                        &local_metadata,
-                                                        &cloud_meta,
-                                                        &local_hash,
-                                                        &cloud_hash,
-                                                        local_time,
-                                                        cloud_time,
+                                                    info!("Conflict resolution: {} local, {} cloud, {} merged",
+                                                        resolution.games_kept_local.len(),
+                                                        resolution.games_kept_cloud.len(),
+                                                        resolution.games_merged.len()
                                                     );
                                                     
-                                                    if !conflicts.is_empty() {
-                                                        warn!("Found {} conflicts in memory card", conflicts.len());
-                                                        for conflict in &conflicts {
-                                                            warn!("  - {} ({}): {:?}", 
-                                                                conflict.game_name, 
-                                                                conflict.game_id,
-                                                                conflict.conflict_type
-                                                            );
-                                                        }
-                                                        
-                                                        // For now, use smart strategy (can be made interactive later)
-                                                        let resolution = ConflictAnalyzer::resolve_conflicts(
-                                                            &conflicts,
-                                                            ResolutionStrategy::Smart,
-                                                        );
-                                                        
-                                                        info!("Conflict resolution: {} local, {} cloud, {} merged",
-                                                            resolution.games_kept_local.len(),
-                                                            resolution.games_kept_cloud.len(),
-                                                            resolution.games_merged.len()
-                                                        );
-                                                        
-                                                        // If we're keeping any local games, don't overwrite
-                                                        if !resolution.games_kept_local.is_empty() {
-                                                            warn!("Keeping local games, skipping download to prevent data loss");
-                                                            (true, false) // Don't download
-                                                        } else {
-                                                            (true, true) // Safe to download
-                                                        }
+                                                    // If we're keeping any local games, don't overwrite
+                                                    if !resolution.games_kept_local.is_empty() {
+                                                        warn!("Keeping local games, skipping download to prevent data loss");
+                                                        (true, false) // Don't download
                                                     } else {
-                                                        // No conflicts, safe to proceed
-                                                        if !has_game_save {
-                                                            info!("Memory card exists but doesn't have {} saves", game_name);
-                                                            (true, true) // Exists but needs restore
-                                                        } else {
-                                                            debug!("Memory card has {} saves", game_name);
-                                                            (true, false) // Has saves, don't need restore
-                                                        }
+                                                        (true, true) // Safe to download
                                                     }
                                                 } else {
-                                                    // No cloud metadata (old save format)
-                                                    // Check if we should allow download for migration
-                                                    let allow_migration = {
-                                                        // Allow if memory card is mostly empty
-                                                        let empty_games = local_metadata.games_contained.iter()
-                                                            .filter(|g| g.save_count == 0)
-                                                            .count();
-                                                        let total_games = local_metadata.games_contained.len();
-                                                        
-                                                        // If most games are empty, allow migration
-                                                        if empty_games > total_games / 2 {
-                                                            info!("Memory card is mostly empty ({}/{} empty), allowing migration from old cloud save", 
-                                                                empty_games, total_games);
-                                                            true
-                                                        } else if !has_game_save && local_metadata.games_contained.len() <= 5 {
-                                                            // If we don't have this specific game and there are few games
-                                                            info!("Memory card missing {} and has few games ({}), allowing migration",
-                                                                game_name, local_metadata.games_contained.len());
-                                                            true
-                                                        } else {
-                                                            warn!("No cloud metadata available, being cautious with {} games. Consider clearing memory card for migration.", 
-                                                                local_metadata.games_contained.len());
-                                                            false
-                                                        }
-                                                    };
-                                                    
-                                                    if allow_migration && !has_game_save {
-                                                        (true, true) // Allow restore for migration
+                                                    // No conflicts, safe to proceed
+                                                    if !has_game_save {
+                                                        info!("Memory card exists but doesn't have {} saves", game_name);
+                                                        (true, true) // Exists but needs restore
                                                     } else {
-                                                        (true, false) // Don't download without metadata
+                                                        debug!("Memory card has {} saves", game_name);
+                                                        (true, false) // Has saves, don't need restore
                                                     }
                                                 }
                                             } else {
-                                                // Single game or empty, safe to proceed
-                                                if !has_game_save {
-                                                    info!("Memory card exists but doesn't have {} saves", game_name);
-                                                    (true, true) // Exists but needs restore
+                                                // No cloud metadata (old save format)
+                                                // Check if we should allow download for migration
+                                                let allow_migration = {
+                                                    // Allow if memory card is mostly empty
+                                                    let empty_games = local_metadata.games_contained.iter()
+                                                        .filter(|g| g.save_count == 0)
+                                                        .count();
+                                                    let total_games = local_metadata.games_contained.len();
+                                                    
+                                                    // If most games are empty, allow migration
+                                                    if empty_games > total_games / 2 {
+                                                        info!("Memory card is mostly empty ({}/{} empty), allowing migration from old cloud save", 
+                                                            empty_games, total_games);
+                                                        true
+                                                    } else if !has_game_save && local_metadata.games_contained.len() <= 5 {
+                                                        // If we don't have this specific game and there are few games
+                                                        info!("Memory card missing {} and has few games ({}), allowing migration",
+                                                            game_name, local_metadata.games_contained.len());
+                                                        true
+                                                    } else {
+                                                        warn!("No cloud metadata available, being cautious with {} games. Consider clearing memory card for migration.", 
+                                                            local_metadata.games_contained.len());
+                                                        false
+                                                    }
+                                                };
+                                                
+                                                if allow_migration && !has_game_save {
+                                                    (true, true) // Allow restore for migration
                                                 } else {
-                                                    debug!("Memory card has {} saves", game_name);
-                                                    (true, false) // Has saves, don't need restore
+                                                    (true, false) // Don't download without metadata
                                                 }
                                             }
                                         } else {
-                                            info!("Invalid PS2 memory card format");
-                                            (true, true) // Invalid, needs restore
+                                            // Single game or empty, safe to proceed
+                                            if !has_game_save {
+                                                info!("Memory card exists but doesn't have {} saves", game_name);
+                                                (true, true) // Exists but needs restore
+                                            } else {
+                                                debug!("Memory card has {} saves", game_name);
+                                                (true, false) // Has saves, don't need restore
+                                            }
                                         }
-                                    },
-                                    _ => {
-                                        // For other formats, use simple empty check
+                                    } else if format == MemoryCardFormat::GameCube {
+                                        // Dolphin also stores individual .gci files, which aren't full card images
                                         let is_empty = format.is_empty(&data);
-                                        if is_empty {
-                                            info!("Memory card exists but is empty: {}", file_path);
-                                        }
+                                        debug!("Not a raw GameCube memory card, empty: {}", is_empty);
                                         (true, is_empty)
+                                    } else {
+                                        info!("Invalid {:?} memory card format", format);
+                                        (true, true) // Invalid, needs restore
+                                    }
+                                },
+                                _ => {
+                                    // For other formats, use simple empty check
+                                    let is_empty = format.is_empty(&data);
+                                    if is_empty {
+                                        info!("Memory card exists but is empty: {}", file_path);
                                     }
+                                    (true, is_empty)
                                 }
-                            } else {
-                                (true, false)
                             }
                         } else {
                             (true, false)
                         }
                     } else {
-                        (false, true) // Doesn't exist, needs restore
+                        (true, false)
                     }
+                } else if !self.restore_deleted_saves.load(Ordering::SeqCst)
+                    && self.database.save_file_deleted_at(file_path).await.ok().flatten()
+                        .is_some_and(|deleted_at| cloud_save.created_at <= deleted_at)
+                {
+                    // Only a save made after the deletion, on another device, brings it back
+                    info!("{} was deleted locally after cloud save {} was made, not restoring it", file_path, cloud_save.id);
+                    return Ok(DownloadOutcome::Skipped);
                 } else {
-                    (false, true)
+                    (false, true) // Doesn't exist, needs restore
                 }
             } else {
                 (false, true)
-            };
-            
-            // Download based on conflict resolution strategy
-            // For memory cards: respect the safety checks from above
-            let should_download = if !file_hash_matches && needs_restore {
-                // Only download if safety checks passed
-                info!("Will download save - hash mismatch and safety checks passed, path: {:?}", 
-                    cloud_save.metadata.as_ref()
-                        .and_then(|m| m.get("file_path"))
-                        .and_then(|p| p.as_str()));
-                true
-            } else if !file_exists || needs_restore {
-                info!("Will download save - exists: {}, needs_restore: {}, path: {:?}", 
-                    file_exists, needs_restore,
-                    cloud_save.metadata.as_ref()
-                        .and_then(|m| m.get("file_path"))
-                        .and_then(|p| p.as_str()));
-                true
-            } else if have_locally {
-                match self.conflict_strategy {
-                    ConflictResolutionStrategy::CloudFirst => true,
-                    ConflictResolutionStrategy::LocalFirst => false,
-                    ConflictResolutionStrategy::NewerWins => {
-                        // Check if cloud version is newer
-                        local_saves.iter()
-                            .filter(|s| s.file_hash == cloud_save.file_hash)
-                            .all(|s| cloud_save.created_at > s.timestamp)
-                    },
-                    ConflictResolutionStrategy::Manual => {
-                        warn!("Manual conflict resolution not implemented, using NewerWins");
-                        local_saves.iter()
-                            .filter(|s| s.file_hash == cloud_save.file_hash)
-                            .all(|s| cloud_save.created_at > s.timestamp)
-                    }
+            }
+        } else {
+            (false, true)
+        };
+        
+        // Download based on conflict resolution strategy
+        // For memory cards: respect the safety checks from above
+        let should_download = if !file_hash_matches && needs_restore {
+            // Only download if safety checks passed
+            info!("Will download save - hash mismatch and safety checks passed, path: {:?}", 
+                cloud_save.metadata.as_ref()
+                    .and_then(|m| m.get("file_path"))
+                    .and_then(|p| p.as_str()));
+            true
+        } else if !file_exists || needs_restore {
+            info!("Will download save - exists: {}, needs_restore: {}, path: {:?}", 
+                file_exists, needs_restore,
+                cloud_save.metadata.as_ref()
+                    .and_then(|m| m.get("file_path"))
+                    .and_then(|p| p.as_str()));
+            true
+        } else if have_locally {
+            let strategy = *self.conflict_strategy.read().await;
+            match strategy {
+                ConflictResolutionStrategy::CloudFirst => true,
+                ConflictResolutionStrategy::LocalFirst => false,
+                ConflictResolutionStrategy::NewerWins => {
+                    // Check if cloud version is newer
+                    local_saves.iter()
+                        .filter(|s| s.file_hash == cloud_save.file_hash)
+                        .all(|s| cloud_save.created_at > s.timestamp)
+                },
+                ConflictResolutionStrategy::Manual => {
+                    warn!("Manual conflict resolution not implemented, using NewerWins");
+                    local_saves.iter()
+                        .filter(|s| s.file_hash == cloud_save.file_hash)
+                        .all(|s| cloud_save.created_at > s.timestamp)
                 }
-            } else {
-                true // No local save, download it
-            };
-            
-            if should_download {
-                debug!("Downloading save {} from {}", cloud_save.file_hash, cloud_save.created_at);
-                
-                // Get download URL from API
-                if let Some(download_url) = cloud_save.download_url {
-                    // Download the save data
-                    match self.api.download_save_data(&download_url).await {
-                        Ok(compressed_data) => {
-                            info!("Downloaded {} compressed bytes from S3", compressed_data.len());
-                            // Decompress the data
-                            match zstd::decode_all(compressed_data.as_slice()) {
-                                Ok(decompressed_data) => {
-                                    // Check if data is encrypted and decrypt if needed
-                                    let final_data = {
-                                        // Try to parse as encrypted save
-                                        if let Ok(encrypted_save) = serde_json::from_slice::<super::encryption::EncryptedSave>(&decompressed_data) {
-                                            let encryption = self.encryption.read().await;
-                                            if encryption.is_enabled() {
-                                                debug!("Decrypting downloaded save");
-                                                match encryption.decrypt_save(&encrypted_save) {
-                                                    Ok(decrypted) => decrypted,
-                                                    Err(e) => {
-                                                        warn!("Failed to decrypt save: {}", e);
-                                                        decompressed_data // Use as-is if decryption fails
-                                                    }
-                                                }
-                                            } else {
-                                                warn!("Encrypted save received but encryption not enabled");
-                                                decompressed_data
-                                            }
-                                        } else {
-                                            // Not encrypted, use as-is
-                                            decompressed_data
-                                        }
-                                    };
-                                    
-                                    // Extract file path from metadata
-                                    let file_path = cloud_save.metadata
-                                        .as_ref()
-                                        .and_then(|m| m.get("file_path"))
-                                        .and_then(|p| p.as_str())
-                                        .map(|s| s.to_string())
-                                        .unwrap_or_else(|| format!("cloud_save_{}", cloud_save.id));
-                                    
-                                    // Record in database
-                                    self.database.record_save(
-                                        local_game.id,
-                                        &file_path,
-                                        &cloud_save.file_hash,
-                                        cloud_save.file_size,
-                                        Some(&format!("cloud_{}", cloud_save.id)),
-                                    ).await?;
-                                    
-                                    // Try to restore the actual file if we have a valid path
-                                    if let Some(metadata) = &cloud_save.metadata {
-                                        if let Some(original_path) = metadata.get("file_path").and_then(|p| p.as_str()) {
-                                            let path = std::path::PathBuf::from(original_path);
-                                            if let Some(parent) = path.parent() {
-                                                tokio::fs::create_dir_all(parent).await.ok();
-                                            }
-                                            
-                                            // Debug: Log data size and first bytes
-                                            info!("Writing {} bytes to {}", final_data.len(), original_path);
-                                            if final_data.len() > 0 {
-                                                debug!("First 16 bytes: {:?}", &final_data[..16.min(final_data.len())]);
-                                            }
-                                            
-                                            if let Err(e) = tokio::fs::write(&path, &final_data).await {
-                                                warn!("Failed to write save file {}: {}", original_path, e);
-                                            } else {
-                                                info!("Downloaded and restored save: {}", original_path);
-                                            }
-                                        }
-                                    }
-                                    
-                                    downloaded += 1;
-                                },
-                                Err(e) => warn!("Failed to decompress save data: {}", e),
-                            }
-                        },
-                        Err(e) => warn!("Failed to download save {}: {}", cloud_save.id, e),
+            }
+        } else {
+            true // No local save, download it
+        };
+        
+        if should_download {
+            debug!("Downloading save {} from {}", cloud_save.file_hash, cloud_save.created_at);
+            
+            // Get download URL from API
+            if let Some(download_url) = cloud_save.download_url.as_deref() {
+                // Corrupted downloads are dropped rather than written over the local save
+                let fetched = self.fetch_verified_save(&cloud_save, download_url).await;
+                if let Err(DownloadFailure::Encrypted) = fetched {
+                    return Ok(DownloadOutcome::Encrypted);
+                }
+                if let Ok(final_data) = fetched {
+                    let original_path = cloud_save.metadata
+                        .as_ref()
+                        .and_then(|m| m.get("file_path"))
+                        .and_then(|p| p.as_str());
+                    let write = DeferredWrite {
+                        file_path: original_path
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| format!("cloud_save_{}", cloud_save.id)),
+                        emulator: emulator.to_string(),
+                        game_id: local_game.id,
+                        game_name: game_name.to_string(),
+                        file_hash: cloud_save.file_hash.clone(),
+                        file_size: cloud_save.file_size,
+                        cloud_save_id: cloud_save.id.to_string(),
+                        data: final_data,
+                    };
+                    
+                    // Without a path there's nothing to write, only the version to remember
+                    if original_path.is_none() {
+                        self.record_download(&write).await?;
+                        return Ok(DownloadOutcome::Downloaded);
                     }
-                } else {
-                    debug!("No download URL for save {}", cloud_save.id);
+                    
+                    // Emulators like PCSX2 keep memory cards open, so writing under
+                    // them corrupts the card or gets overwritten when they exit
+                    if self.is_emulator_running(emulator).await {
+                        info!("{} is running, holding download of {} until it closes", emulator, write.file_path);
+                        self.database.defer_write(&write).await?;
+                        return Ok(DownloadOutcome::Downloaded);
+                    }
+                    
+                    // A held back older download mustn't replace this one later
+                    self.database.remove_deferred_write(&write.file_path).await?;
+                    if self.write_download(write).await? {
+                        return Ok(DownloadOutcome::Downloaded);
+                    }
+                    return Ok(DownloadOutcome::Skipped);
                 }
             } else {
-                debug!("Skipping save {} - local version is up to date", cloud_save.file_hash);
+                debug!("No download URL for save {}", cloud_save.id);
             }
+        } else {
+            debug!("Skipping save {} - local version is up to date", cloud_save.file_hash);
+        }
+        
+        Ok(DownloadOutcome::Skipped)
+    }
+    
+    /// Download a cloud save and turn it back into the bytes that belong on disk.
+    ///
+    /// The hash the server stores is the one computed at upload time over the
+    /// compressed (and possibly encrypted) payload, so that's what the download is
+    /// checked against. Older uploads hashed the plain file instead, which is
+    /// accepted too. A mismatch is retried once before the save is skipped.
+    async fn fetch_verified_save(&self, cloud_save: &SaveMetadata, download_url: &str) -> Result<Vec<u8>, DownloadFailure> {
+        for attempt in 1..=DOWNLOAD_ATTEMPTS {
+            let compressed_data = match self.api.download_save_data(download_url).await {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Failed to download save {}: {}", cloud_save.id, e);
+                    return Err(DownloadFailure::Failed);
+                }
+            };
+            info!("Downloaded {} compressed bytes from S3", compressed_data.len());
             
-            pending_downloads -= 1;
+            let payload_hash = crate::storage::hasher::hash_bytes(&compressed_data);
+            let payload_matches = payload_hash == cloud_save.file_hash;
+            let decompressed_data = match zstd::decode_all(compressed_data.as_slice()) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Failed to decompress save {} (attempt {}/{}): {}", cloud_save.id, attempt, DOWNLOAD_ATTEMPTS, e);
+                    continue;
+                }
+            };
             
-            // Update status
-            {
-                let mut status = self.status.write().await;
-                status.pending_downloads = pending_downloads;
+            match self.decrypt_download(decompressed_data).await {
+                Some(final_data) if payload_matches || crate::storage::hasher::hash_bytes(&final_data) == cloud_save.file_hash => {
+                    return Ok(final_data);
+                }
+                // Intact but encrypted with a key we don't have, downloading again won't help
+                None if payload_matches => {
+                    warn!("Save {} is encrypted and can't be decrypted here, not restoring it", cloud_save.id);
+                    return Err(DownloadFailure::Encrypted);
+                }
+                _ => {}
             }
+            
+            warn!("Downloaded save {} doesn't match its hash (expected {}, got {}), attempt {}/{}",
+                cloud_save.id, &cloud_save.file_hash[..8.min(cloud_save.file_hash.len())], &payload_hash[..8], attempt, DOWNLOAD_ATTEMPTS);
         }
         
-        if downloaded > 0 {
-            info!("Downloaded {} saves from cloud", downloaded);
+        error!("Save {} failed verification, leaving the local file untouched", cloud_save.id);
+        Err(DownloadFailure::Failed)
+    }
+    
+    /// Write a downloaded save over the local file, keeping a copy of what was there
+    async fn restore_save_file(path: std::path::PathBuf, data: Vec<u8>, game_name: &str) -> bool {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.ok();
         }
         
-        Ok(())
+        // Debug: Log data size and first bytes
+        info!("Writing {} bytes to {:?}", data.len(), path);
+        if !data.is_empty() {
+            debug!("First 16 bytes: {:?}", &data[..16.min(data.len())]);
+        }
+        
+        // Keep what's on disk in case the download turns out to be the wrong call
+        if !Self::preserve_local_save(&path, &data, game_name).await {
+            warn!("Not overwriting {:?} without a copy of the local save", path);
+            return false;
+        }
+        
+        match Self::write_save_atomic(path.clone(), data).await {
+            Ok(()) => {
+                info!("Downloaded and restored save: {:?}", path);
+                true
+            }
+            Err(e) => {
+                warn!("Failed to write save file {:?}: {}", path, e);
+                false
+            }
+        }
+    }
+    
+    /// Snapshot an existing save before a download replaces it. Returns whether
+    /// it's safe to write: nothing there, identical content, or a copy was made.
+    async fn preserve_local_save(path: &std::path::Path, new_data: &[u8], game_name: &str) -> bool {
+        match tokio::fs::read(path).await {
+            Ok(existing) if existing == new_data => return true,
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return true,
+            Err(e) => {
+                warn!("Failed to read existing save {:?}: {}", path, e);
+                return false;
+            }
+        }
+        
+        let path = path.to_path_buf();
+        let game_name = game_name.to_string();
+        let result = tokio::task::spawn_blocking(move || {
+            SaveBackupManager::new(None)?.snapshot_before_overwrite(&path, &game_name)
+        }).await;
+        
+        match result {
+            Ok(Ok(_)) => true,
+            Ok(Err(e)) => {
+                warn!("Failed to back up local save before download: {}", e);
+                false
+            }
+            Err(e) => {
+                warn!("Backup task failed: {}", e);
+                false
+            }
+        }
+    }
+    
+    /// Replace a save file without ever leaving it half written
+    async fn write_save_atomic(path: std::path::PathBuf, data: Vec<u8>) -> Result<()> {
+        tokio::task::spawn_blocking(move || crate::storage::atomic::write_atomic(&path, &data))
+            .await
+            .context("Save write task failed")?
+    }
+    
+    /// Decrypt a decompressed download if it's an encrypted save. `None` if it
+    /// is encrypted and can't be decrypted; the encrypted bytes are useless to
+    /// an emulator.
+    async fn decrypt_download(&self, decompressed_data: Vec<u8>) -> Option<Vec<u8>> {
+        // Try to parse as encrypted save
+        if let Ok(encrypted_save) = serde_json::from_slice::<super::encryption::EncryptedSave>(&decompressed_data) {
+            let encryption = self.encryption.read().await;
+            if encryption.is_enabled() {
+                debug!("Decrypting downloaded save");
+                match encryption.decrypt_save(&encrypted_save) {
+                    Ok(decrypted) => Some(decrypted),
+                    Err(e) => {
+                        warn!("Failed to decrypt save: {}", e);
+                        None
+                    }
+                }
+            } else {
+                warn!("Encrypted save received but encryption not enabled");
+                None
+            }
+        } else {
+            // Not encrypted, use as-is
+            Some(decompressed_data)
+        }
     }
 
     /// Get sync status
@@ -960,9 +1864,53 @@ impl SyncService {
     }
     
     /// Trigger manual sync
+    /// Whether the API was reachable on the last check
+    pub fn is_online(&self) -> bool {
+        *self.online.borrow()
+    }
+    
+    /// Subscribe to online/offline transitions
+    pub fn subscribe_online(&self) -> watch::Receiver<bool> {
+        self.online.subscribe()
+    }
+    
+    /// Subscribe to item-level progress, `None` while no sync is running
+    pub fn subscribe_progress(&self) -> watch::Receiver<Option<SyncProgress>> {
+        self.progress.subscribe()
+    }
+    
+    /// Progress of the running sync, if any
+    pub fn progress(&self) -> Option<SyncProgress> {
+        *self.progress.borrow()
+    }
+    
+    /// Check whether the API is reachable and record the result
+    pub async fn check_connectivity(&self) -> bool {
+        let online = self.api.is_reachable().await;
+        let changed = self.online.send_if_modified(|current| {
+            let changed = *current != online;
+            *current = online;
+            changed
+        });
+        
+        if changed {
+            if online {
+                info!("Connectivity restored, resuming sync");
+            } else {
+                warn!("API unreachable, switching to offline mode (saves will be queued)");
+            }
+        }
+        self.status.write().await.is_online = online;
+        online
+    }
+    
+    /// Signed in, or syncing with a backend that doesn't need an account
+    pub async fn can_sync(&self) -> bool {
+        !self.api.requires_auth() || self.auth_manager.is_authenticated().await
+    }
+    
     pub async fn trigger_sync(&self) -> Result<()> {
-        let auth_state = self.auth_manager.get_state().await;
-        if !auth_state.is_authenticated {
+        if !self.can_sync().await {
             return Err(anyhow::anyhow!("Not authenticated"));
         }
         
@@ -1010,8 +1958,8 @@ impl SyncService {
         None
     }
     
-    /// Get or register a game
-    async fn get_or_register_game(&self, name: &str, emulator: &str) -> Result<Uuid> {
+    /// Cloud ID of a game, registering it on first use
+    pub async fn get_or_register_game(&self, name: &str, emulator: &str) -> Result<Uuid> {
         self.get_or_register_game_with_id(name, emulator, None).await
     }
     
@@ -1040,29 +1988,293 @@ impl SyncService {
     }
     
     
+    /// Record an emulator starting or stopping. Downloads held back while it was
+    /// running are written once it stops.
+    pub async fn set_emulator_running(&self, emulator: &str, running: bool) {
+        if running {
+            self.running_emulators.write().await.insert(emulator.to_string());
+            return;
+        }
+        
+        self.running_emulators.write().await.remove(emulator);
+        self.write_deferred_downloads().await;
+    }
+    
+    /// Write the held back downloads whose emulator isn't running anymore
+    async fn write_deferred_downloads(&self) {
+        let writes = match self.database.get_deferred_writes().await {
+            Ok(writes) => writes,
+            Err(e) => {
+                warn!("Failed to load held back downloads: {}", e);
+                return;
+            }
+        };
+        
+        let ready: Vec<_> = {
+            let running = self.running_emulators.read().await;
+            writes.into_iter()
+                .filter(|write| !Self::emulator_in_use(&running, &write.emulator))
+                .collect()
+        };
+        
+        if !ready.is_empty() {
+            info!("Writing {} held back downloads", ready.len());
+        }
+        for write in ready {
+            // Tried once, a failed write is picked up again by the next download
+            if let Err(e) = self.database.remove_deferred_write(&write.file_path).await {
+                warn!("Failed to drop held back download of {}: {}", write.file_path, e);
+                continue;
+            }
+            if let Err(e) = self.write_download(write).await {
+                warn!("Failed to record held back download: {}", e);
+            }
+        }
+    }
+    
+    /// Write a download to its save file, recording the version once it's on disk.
+    /// Returns whether it was written.
+    async fn write_download(&self, mut write: DeferredWrite) -> Result<bool> {
+        let path = std::path::PathBuf::from(&write.file_path);
+        if !Self::restore_save_file(path, std::mem::take(&mut write.data), &write.game_name).await {
+            return Ok(false);
+        }
+        
+        self.record_download(&write).await?;
+        Ok(true)
+    }
+    
+    /// Record a downloaded cloud save as a local version
+    async fn record_download(&self, write: &DeferredWrite) -> Result<()> {
+        self.database.record_save(
+            write.game_id,
+            &write.file_path,
+            &write.file_hash,
+            write.file_size,
+            Some(&format!("cloud_{}", write.cloud_save_id)),
+        ).await?;
+        self.database.set_cloud_save_id(write.game_id, &write.file_hash, &write.cloud_save_id).await
+    }
+    
+    /// Forget which emulators are running, for when the monitor starts detecting
+    /// them from scratch. Held back downloads stay held until an emulator stops,
+    /// the next download finds theirs closed or a newer download for the same save arrives.
+    pub async fn reset_running_emulators(&self) {
+        self.running_emulators.write().await.clear();
+    }
+    
+    async fn is_emulator_running(&self, emulator: &str) -> bool {
+        Self::emulator_in_use(&*self.running_emulators.read().await, emulator)
+    }
+    
+    /// Whether a save of `emulator` may be in use by one of the `running` ones.
+    /// Saves from emulators the watcher doesn't know by name ("Unknown") could
+    /// belong to whichever emulator is running.
+    fn emulator_in_use(running: &HashSet<String>, emulator: &str) -> bool {
+        running.contains(emulator) || (emulator == "Unknown" && !running.is_empty())
+    }
+    
     /// Set conflict resolution strategy
-    pub fn set_conflict_strategy(&mut self, strategy: ConflictResolutionStrategy) {
-        self.conflict_strategy = strategy;
+    pub async fn set_conflict_strategy(&self, strategy: ConflictResolutionStrategy) {
+        *self.conflict_strategy.write().await = strategy;
         info!("Conflict resolution strategy set to: {:?}", strategy);
     }
     
+    /// Current conflict resolution strategy
+    pub async fn conflict_strategy(&self) -> ConflictResolutionStrategy {
+        *self.conflict_strategy.read().await
+    }
+    
     /// Get pending upload count
     pub async fn get_pending_uploads(&self) -> usize {
         self.upload_queue.read().await.len()
     }
     
+    /// File hashes of saves that are queued or uploading right now
+    pub async fn pending_upload_hashes(&self) -> HashSet<String> {
+        let mut hashes: HashSet<String> = self.upload_queue.read().await
+            .iter()
+            .map(|task| task.file_hash.clone())
+            .collect();
+        for task in self.current_uploads.read().await.iter() {
+            hashes.insert(task.file_hash.clone());
+        }
+        hashes
+    }
+    
+    /// Queue the latest save of a renamed or merged game, so the next sync
+    /// uploads it under the game's current name and the cloud has it there
+    pub async fn resync_game(&self, game_id: i64) -> Result<()> {
+        let Some(game) = self.database.get_game(game_id).await? else {
+            return Ok(());
+        };
+        if !game.sync_enabled {
+            return Ok(());
+        }
+        
+        if self.queue_latest_save(&game).await? {
+            info!("Queued the latest save of {} to sync it under its new name", game.name);
+            self.persist_upload_queue().await?;
+        }
+        Ok(())
+    }
+    
+    /// Queue the latest save of every synced game and upload them, for when
+    /// the cloud lost data or this computer moved to another account. Unlike
+    /// `retry_failed_uploads` this doesn't depend on what is still queued.
+    /// Saves the cloud already has are skipped while uploading. Returns how
+    /// many saves were queued.
+    pub async fn force_reupload_all(&self) -> Result<usize> {
+        if !self.can_sync().await {
+            return Err(anyhow::anyhow!("Not authenticated"));
+        }
+        
+        let mut queued = 0;
+        for game in self.database.get_all_games().await? {
+            if !game.sync_enabled || crate::emulators::is_unknown_game(&game.name) {
+                continue;
+            }
+            if self.queue_latest_save(&game).await? {
+                queued += 1;
+            }
+        }
+        
+        info!("Queued {} saves to upload again", queued);
+        if queued > 0 {
+            self.persist_upload_queue().await?;
+            self.process_upload_queue().await?;
+        }
+        Ok(queued)
+    }
+    
+    /// Queue the latest save of `game` unless it is queued already or neither
+    /// its backup nor its file hold it anymore, returns whether it was queued.
+    /// The backup is preferred, the file may hold newer progress by now.
+    async fn queue_latest_save(&self, game: &crate::storage::database::Game) -> Result<bool> {
+        let Some(save) = self.database.get_latest_save(game.id).await? else {
+            return Ok(false);
+        };
+        let backup_path = save.backup_path.filter(|path| std::path::Path::new(path).is_file());
+        if backup_path.is_none() {
+            let unchanged = crate::storage::hasher::hash_file(std::path::Path::new(&save.file_path))
+                .is_ok_and(|hash| hash == save.file_hash);
+            if !unchanged {
+                debug!("Latest save of {} has no backup and {} changed or is gone, not queueing it", game.name, save.file_path);
+                return Ok(false);
+            }
+        }
+        
+        let mut queue = self.upload_queue.write().await;
+        if queue.iter().any(|task| task.emulator == game.emulator && task.file_hash == save.file_hash) {
+            return Ok(false);
+        }
+        queue.push_back(UploadTask {
+            game_name: game.name.clone(),
+            emulator: game.emulator.clone(),
+            file_path: save.file_path,
+            file_hash: save.file_hash,
+            file_size: save.file_size,
+            timestamp: Utc::now(),
+            backup_path,
+        });
+        self.status.write().await.set_pending_uploads(queue.len());
+        Ok(true)
+    }
+    
+    /// Contents of a save backup, decompressed if it's a .zst one
+    async fn read_backup(backup_path: &str) -> Result<Vec<u8>> {
+        let data = tokio::fs::read(backup_path).await
+            .with_context(|| format!("Failed to read backup {}", backup_path))?;
+        if backup_path.ends_with(".zst") {
+            return crate::storage::compression::decompress(&data);
+        }
+        Ok(data)
+    }
+    
     /// Clear upload queue
     pub async fn clear_upload_queue(&self) {
         let mut queue = self.upload_queue.write().await;
         let count = queue.len();
         queue.clear();
+        drop(queue);
+        
+        self.status.write().await.set_pending_uploads(0);
         info!("Cleared {} pending uploads from queue", count);
     }
     
+    /// Clear the cached cloud game IDs so games are re-registered on next upload
+    pub async fn clear_game_cache(&self) {
+        let mut cache = self.game_cache.write().await;
+        let count = cache.len();
+        cache.clear();
+        info!("Cleared {} cached cloud game IDs", count);
+    }
+    
+    /// Move sync over to another cloud profile: drop everything that belongs to
+    /// the previous account and connect with the new one if it is logged in.
+    /// The upload queue is kept, queued saves are uploaded to the new account.
+    pub async fn switch_profile(self: &Arc<Self>, profile: &str) -> Result<()> {
+        self.disconnect_websocket().await;
+        
+        // Queued saves belong to the previous account, they wait until it's active again
+        self.persist_upload_queue().await?;
+        if self.upload_queue.read().await.is_empty() {
+            self.clear_persisted_queue().await?;
+        }
+        self.clear_upload_queue().await;
+        
+        self.auth_manager.switch_profile(profile).await?;
+        
+        // Passphrase keys belong to the previous account
+        self.encryption.write().await.lock();
+        self.clear_game_cache().await;
+        *self.device_id.write().await = None;
+        *self.usage_cache.write().await = None;
+        *self.subscription_tier.write().await = None;
+        *self.quota_blocked_until.write().await = None;
+        self.quota_warning_level.store(QUOTA_OK, Ordering::SeqCst);
+        
+        let auth_state = self.auth_manager.get_state().await;
+        if let Some(tokens) = auth_state.tokens {
+            self.register_device().await;
+            if let Err(e) = self.clone().init_websocket(tokens.access_token).await {
+                warn!("Failed to initialize WebSocket for profile {}: {}", profile, e);
+            }
+        }
+        
+        self.restore_upload_queue().await
+    }
+    
+    /// Kill switch for a lost or stolen machine: disconnect, revoke and delete the
+    /// stored tokens of every profile. Sync stays off until someone signs in again.
+    /// Queued uploads are kept, they're local saves rather than credentials.
+    pub async fn wipe_local_auth(&self) -> Result<()> {
+        warn!("Wiping local credentials of all profiles");
+        self.disconnect_websocket().await;
+        
+        *self.usage_cache.write().await = None;
+        *self.subscription_tier.write().await = None;
+        self.clear_game_cache().await;
+        self.encryption.write().await.lock();
+        
+        self.auth_manager.panic_logout().await
+    }
+    
+    /// Reset all local sync state: the in-memory and persisted upload queue
+    /// and the game cache. Saves themselves are not touched.
+    pub async fn reset_sync_state(&self) -> Result<()> {
+        info!("Resetting sync state");
+        
+        self.clear_upload_queue().await;
+        self.clear_persisted_queue().await?;
+        self.clear_game_cache().await;
+        
+        Ok(())
+    }
+    
     /// Retry failed uploads
     pub async fn retry_failed_uploads(&self) -> Result<()> {
-        let auth_state = self.auth_manager.get_state().await;
-        if !auth_state.is_authenticated {
+        if !self.can_sync().await {
             return Err(anyhow::anyhow!("Not authenticated"));
         }
         
@@ -1070,6 +2282,16 @@ impl SyncService {
         self.process_upload_queue().await.map(|_| ())
     }
     
+    /// Setting the upload queue of the current profile is persisted under
+    fn upload_queue_key(&self) -> String {
+        let profile = self.auth_manager.profile();
+        if profile == DEFAULT_PROFILE {
+            "upload_queue".to_string()
+        } else {
+            format!("upload_queue:{}", profile)
+        }
+    }
+    
     /// Save upload queue to database for persistence
     pub async fn persist_upload_queue(&self) -> Result<()> {
         let queue = self.upload_queue.read().await;
@@ -1084,7 +2306,7 @@ impl SyncService {
         
         // Store in database settings or a dedicated table
         self.database
-            .save_setting("upload_queue", &queue_data)
+            .save_setting(&self.upload_queue_key(), &queue_data)
             .await
             .context("Failed to persist upload queue")?;
         
@@ -1095,7 +2317,7 @@ impl SyncService {
     /// Restore upload queue from database
     pub async fn restore_upload_queue(&self) -> Result<()> {
         // Load from database
-        let queue_data = match self.database.get_setting("upload_queue").await? {
+        let queue_data = match self.database.get_setting(&self.upload_queue_key()).await? {
             Some(data) => data,
             None => {
                 debug!("No persisted upload queue found");
@@ -1121,7 +2343,7 @@ impl SyncService {
         
         // Update status
         let mut status = self.status.write().await;
-        status.pending_uploads = queue.len();
+        status.set_pending_uploads(queue.len());
         
         Ok(())
     }
@@ -1129,7 +2351,7 @@ impl SyncService {
     /// Clear persisted queue from database
     pub async fn clear_persisted_queue(&self) -> Result<()> {
         self.database
-            .delete_setting("upload_queue")
+            .delete_setting(&self.upload_queue_key())
             .await
             .context("Failed to clear persisted queue")?;
         
@@ -1140,6 +2362,23 @@ impl SyncService {
     /// Shutdown handler - persist any pending uploads
     pub async fn shutdown(&self) -> Result<()> {
         info!("Shutting down sync service");
+        self.shutting_down.store(true, Ordering::SeqCst);
+        
+        // Give an upload that is already in flight a chance to finish
+        let deadline = tokio::time::Instant::now() + SHUTDOWN_UPLOAD_TIMEOUT;
+        while self.status.read().await.is_syncing && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        
+        // Anything that didn't finish is uploaded again on the next start
+        let unfinished: Vec<UploadTask> = self.current_uploads.write().await.drain(..).collect();
+        if !unfinished.is_empty() {
+            let mut queue = self.upload_queue.write().await;
+            for task in unfinished.into_iter().rev() {
+                warn!("Upload for {} did not finish, requeueing it", task.game_name);
+                queue.push_front(task);
+            }
+        }
         
         // Persist any pending uploads
         let queue = self.upload_queue.read().await;
@@ -1155,21 +2394,90 @@ impl SyncService {
         Ok(())
     }
     
-    /// Enable E2E encryption with password
-    pub async fn enable_encryption(&self, password: &str) -> Result<()> {
-        let mut encryption = self.encryption.write().await;
-        encryption.init_with_password(password).await?;
-        info!("E2E encryption enabled for sync");
+    /// Enable E2E encryption, or unlock it after a restart. Passphrase keys are
+    /// tied to the signed in account.
+    pub async fn enable_encryption(&self, mode: EncryptionMode, password: &str) -> Result<()> {
+        match mode {
+            EncryptionMode::DeviceKey => self.encryption.write().await.init_with_password(password).await?,
+            EncryptionMode::Passphrase => {
+                let user = self.auth_manager.get_user_info().await
+                    .ok_or_else(|| anyhow::anyhow!("Sign in to use a passphrase"))?;
+                // Fetched before taking the lock, uploads need it meanwhile
+                let sample = self.find_encrypted_cloud_save().await;
+                self.encryption.write().await
+                    .init_with_passphrase(password, &user.id, sample.as_ref()).await?;
+            }
+        }
+        self.encryption_required.store(true, Ordering::SeqCst);
+        info!("E2E encryption enabled for sync ({})", mode.as_str());
+        Ok(())
+    }
+    
+    /// A save another device encrypted, to check a passphrase against. Saves
+    /// marked as encrypted are tried first, then a few of the others.
+    async fn find_encrypted_cloud_save(&self) -> Option<super::encryption::EncryptedSave> {
+        let saves = match self.api.list_saves(None, 1, 100).await {
+            Ok(response) => response.items,
+            Err(e) => {
+                debug!("Couldn't list cloud saves to check the passphrase: {}", e);
+                return None;
+            }
+        };
+        
+        let (marked, unmarked): (Vec<_>, Vec<_>) = saves.into_iter()
+            .partition(|save| save.metadata.as_ref()
+                .and_then(|m| m.get("encrypted"))
+                .and_then(|e| e.as_bool())
+                .unwrap_or(false));
+        for save in marked.into_iter().chain(unmarked.into_iter().take(PASSPHRASE_CHECK_DOWNLOADS)) {
+            let Some(download_url) = save.download_url.as_deref() else {
+                continue;
+            };
+            let Ok(compressed_data) = self.api.download_save_data(download_url).await else {
+                continue;
+            };
+            let encrypted = zstd::decode_all(compressed_data.as_slice()).ok()
+                .and_then(|data| serde_json::from_slice(&data).ok());
+            if encrypted.is_some() {
+                return encrypted;
+            }
+        }
+        
+        None
+    }
+    
+    /// Unlock encryption with a key exported from another device
+    pub async fn import_encryption_key(&self, key: &str) -> Result<()> {
+        self.encryption.write().await.import_key(key).await?;
+        self.encryption_required.store(true, Ordering::SeqCst);
+        info!("E2E encryption unlocked with an imported key");
         Ok(())
     }
     
-    /// Disable E2E encryption
+    /// Unlock encryption with a key imported on an earlier run. Returns whether
+    /// there was one.
+    pub async fn unlock_imported_key(&self) -> Result<bool> {
+        self.encryption.write().await.unlock_imported_key().await
+    }
+    
+    /// The unlocked encryption key, for backing it up or moving it to another device
+    pub async fn export_encryption_key(&self) -> Result<String> {
+        self.encryption.read().await.export_key()
+    }
+    
+    /// Disable E2E encryption. Saves are uploaded unencrypted from now on.
     pub async fn disable_encryption(&self) {
-        let mut encryption = self.encryption.write().await;
-        *encryption = EncryptionManager::new(None);
+        self.encryption_required.store(false, Ordering::SeqCst);
+        self.encryption.write().await.lock();
         info!("E2E encryption disabled");
     }
     
+    /// Whether uploads must be encrypted. While that's set and the key isn't
+    /// unlocked yet, uploads wait rather than going out in plain text.
+    pub fn set_encryption_required(&self, required: bool) {
+        self.encryption_required.store(required, Ordering::SeqCst);
+    }
+    
     /// Check if encryption is enabled
     pub async fn is_encryption_enabled(&self) -> bool {
         let encryption = self.encryption.read().await;
@@ -1182,18 +2490,6 @@ impl SyncService {
         encryption.change_password(old_password, new_password).await
     }
     
-    /// Export encryption key for backup
-    pub async fn export_encryption_key(&self) -> Result<String> {
-        let encryption = self.encryption.read().await;
-        encryption.export_key()
-    }
-    
-    /// Import encryption key from backup
-    pub async fn import_encryption_key(&self, key: &str) -> Result<()> {
-        let mut encryption = self.encryption.write().await;
-        encryption.import_key(key)
-    }
-    
     /// Test method for WebSocket initialization (public for testing)
     pub async fn init_websocket_test(self: Arc<Self>, token: String) -> Result<()> {
         self.init_websocket(token).await
@@ -1203,9 +2499,10 @@ impl SyncService {
     async fn init_websocket(self: Arc<Self>, token: String) -> Result<()> {
         let (ws_tx, mut ws_rx) = mpsc::unbounded_channel::<WsMessage>();
         
-        let api_url = self.api.base_url.clone();
+        let api_url = self.api.base_url().to_string();
         
-        let mut client = WebSocketClient::new(api_url, ws_tx);
+        let client = WebSocketClient::new(api_url, ws_tx)
+            .with_auth_manager(self.auth_manager.clone());
         
         client.set_token(token).await;
         
@@ -1311,12 +2608,39 @@ impl SyncService {
         }
     }
     
+    /// Stable id for this installation
+    pub async fn device_id(&self) -> String {
+        let mut cached = self.device_id.write().await;
+        if let Some(ref device_id) = *cached {
+            return device_id.clone();
+        }
+        
+        let device_id = match self.database.get_or_create_device_id().await {
+            Ok(device_id) => device_id,
+            Err(e) => {
+                warn!("Failed to load device id, using a temporary one: {}", e);
+                Uuid::new_v4().to_string()
+            }
+        };
+        *cached = Some(device_id.clone());
+        device_id
+    }
+    
+    /// Register this device with the account so it counts once towards the device limit
+    async fn register_device(&self) {
+        let device_id = self.device_id().await;
+        match self.api.register_device(&device_id, &self.device_name).await {
+            Ok(()) => info!("Registered device {} ({})", self.device_name, device_id),
+            Err(e) => warn!("Failed to register device: {}", e),
+        }
+    }
+    
     /// Notify sync started via WebSocket
     async fn notify_sync_started(&self) {
         let ws = self.websocket.read().await;
         if let Some(client) = ws.as_ref() {
             if let Err(e) = client.notify_sync_started(
-                self.device_id.clone(),
+                self.device_id().await,
                 self.device_name.clone()
             ).await {
                 debug!("Failed to notify sync started: {}", e);
@@ -1329,7 +2653,7 @@ impl SyncService {
         let ws = self.websocket.read().await;
         if let Some(client) = ws.as_ref() {
             if let Err(e) = client.notify_sync_completed(
-                self.device_id.clone(),
+                self.device_id().await,
                 self.device_name.clone(),
                 uploads,
                 downloads
@@ -1354,4 +2678,83 @@ impl SyncService {
         }
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn task(game_name: &str, emulator: &str, version: i64) -> UploadTask {
+        UploadTask {
+            game_name: game_name.to_string(),
+            emulator: emulator.to_string(),
+            file_path: format!("/saves/{}.sav", game_name),
+            file_hash: format!("{}-{}", game_name, version),
+            file_size: version,
+            timestamp: Utc::now() + chrono::Duration::seconds(version),
+            backup_path: None,
+        }
+    }
+    
+    #[test]
+    fn test_parallel_uploads_keep_per_game_order() {
+        let mut queue = VecDeque::new();
+        for version in 0..10 {
+            for game in ["Game A", "Game B", "Game C"] {
+                queue.push_back(task(game, "PCSX2", version));
+            }
+        }
+        queue.push_back(task("Game D", "Dolphin", 0));
+        
+        // Run up to 3 uploads at a time, finishing the newest started first
+        let mut busy_games = HashSet::new();
+        let mut in_flight: Vec<UploadTask> = Vec::new();
+        let mut finished: Vec<UploadTask> = Vec::new();
+        while !queue.is_empty() || !in_flight.is_empty() {
+            while in_flight.len() < 3 {
+                let Some(next) = SyncService::take_next_upload(&mut queue, &busy_games, &HashSet::new()) else {
+                    break;
+                };
+                assert!(busy_games.insert(next.game_name.clone()), "two uploads of one game at once");
+                in_flight.push(next);
+            }
+            let done = in_flight.pop().unwrap();
+            busy_games.remove(&done.game_name);
+            finished.push(done);
+        }
+        
+        assert_eq!(finished.len(), 31);
+        for game in ["Game A", "Game B", "Game C"] {
+            let versions: Vec<i64> = finished.iter()
+                .filter(|t| t.game_name == game)
+                .map(|t| t.file_size)
+                .collect();
+            assert_eq!(versions, (0..10).collect::<Vec<_>>());
+        }
+    }
+    
+    #[test]
+    fn test_held_emulator_blocks_later_saves_of_its_games() {
+        let mut queue = VecDeque::from(vec![
+            task("Game A", "PCSX2", 0),
+            task("Game A", "PCSX2", 1),
+            task("Game B", "Dolphin", 0),
+        ]);
+        let held: HashSet<String> = ["PCSX2".to_string()].into_iter().collect();
+        
+        let next = SyncService::take_next_upload(&mut queue, &HashSet::new(), &held).unwrap();
+        assert_eq!(next.game_name, "Game B");
+        assert!(SyncService::take_next_upload(&mut queue, &HashSet::new(), &held).is_none());
+        assert_eq!(queue.len(), 2);
+    }
+    
+    #[test]
+    fn test_sync_progress() {
+        let progress = SyncProgress { phase: SyncPhase::Uploading, current: 3, total: 12 };
+        assert_eq!(progress.fraction(), 0.25);
+        assert_eq!(progress.to_string(), "Uploading 3/12 (25%)");
+        
+        let empty = SyncProgress { phase: SyncPhase::Downloading, current: 0, total: 0 };
+        assert_eq!(empty.fraction(), 0.0);
+    }
+}
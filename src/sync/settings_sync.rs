@@ -3,10 +3,13 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use tracing::{warn, error};
 
-use crate::ui::settings::Settings;
+use crate::settings::Settings;
 use super::api::SyncApi;
 
+/// Fields the server leaves out take their default, so a server that's older
+/// or newer than this client doesn't break settings sync
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UserSettingsResponse {
     // Save preferences
     pub auto_save_enabled: bool,
@@ -29,6 +32,28 @@ pub struct UserSettingsResponse {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Default for UserSettingsResponse {
+    /// The client's own defaults, dated as old as possible so they never
+    /// win a last-write-wins merge against a local change
+    fn default() -> Self {
+        let settings = Settings::default();
+        Self {
+            auto_save_enabled: settings.auto_save_enabled,
+            save_interval_minutes: settings.save_interval_minutes as i32,
+            max_saves_per_game: settings.max_saves_per_game as i32,
+            email_weekly_summary: false,
+            email_product_updates: false,
+            desktop_save_completed: settings.show_notifications,
+            desktop_sync_errors: settings.show_notifications,
+            compression_enabled: settings.compression_enabled,
+            compression_level: settings.compression_level,
+            auto_cleanup_days: None,
+            settings_version: 0,
+            updated_at: DateTime::<Utc>::default(),  // The Unix epoch
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateUserSettings {
     // Save preferences
@@ -50,44 +75,145 @@ pub struct UpdateUserSettings {
 
 // Settings API methods are implemented in api.rs
 
-/// Merge cloud settings with local settings
+/// Settings key holding when the settings were last saved on this device
+pub const SETTINGS_UPDATED_AT_SETTING: &str = "settings_updated_at";
+
+/// Which side wins when a setting differs between this device and the cloud
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// The cloud value always wins
+    Cloud,
+    /// Whichever side changed last wins, the cloud's `updated_at` against
+    /// `Settings::updated_at`. The cloud wins ties and when this device never saved.
+    LastWriteWins,
+    /// The cloud value is ignored
+    Local,
+}
+
+/// Settings that are stored in the cloud and how each is merged. Everything
+/// else (`start_on_boot`, hotkeys, emulator folders, ...) is specific to this
+/// device and always kept.
+pub const DEFAULT_MERGE_POLICIES: &[(&str, MergePolicy)] = &[
+    ("auto_save_enabled", MergePolicy::LastWriteWins),
+    ("save_interval_minutes", MergePolicy::LastWriteWins),
+    ("max_saves_per_game", MergePolicy::LastWriteWins),
+    ("compression_enabled", MergePolicy::LastWriteWins),
+    ("compression_level", MergePolicy::LastWriteWins),
+    ("show_notifications", MergePolicy::LastWriteWins),
+];
+
+/// Settings specific to this device, never uploaded nor taken from the cloud.
+/// One machine's hotkey, startup behaviour or emulator folders mean nothing
+/// on another.
+pub const LOCAL_ONLY_SETTINGS: &[&str] = &[
+    "process_scan_interval_secs",
+    "max_save_states_per_game",
+    "start_on_boot",
+    "minimize_to_tray",
+    "cloud_sync_enabled",
+    "cloud_api_url",
+    "cloud_auto_sync",
+    "sync_backend",
+    "local_sync_folder",
+    "rclone_remote",
+    "hotkey_enabled",
+    "save_hotkey",
+    "auto_vacuum_enabled",
+    "max_sync_bandwidth_kbps",
+    "parallel_downloads",
+    "parallel_uploads",
+    "conflict_strategy",
+    "sync_after_closing_only",
+    "restore_deleted_saves",
+    "failure_webhook_url",
+    "encryption_enabled",
+    "encryption_mode",
+    "watcher_ignore_patterns",
+    "backup_exclusions",
+    "pcsx2_extra_memcard_dirs",
+    "disabled_emulators",
+    "allowlist_enabled",
+    "allowlisted_games",
+    "verbose_logging",
+    "theme",
+    "custom_ca_cert_path",
+    "accept_invalid_certs",
+    "proxy_url",
+    "updated_at",
+];
+
+/// Policy for `field`, `Local` when it isn't listed
+pub fn merge_policy(policies: &[(&str, MergePolicy)], field: &str) -> MergePolicy {
+    policies.iter()
+        .find(|(name, _)| *name == field)
+        .map_or(MergePolicy::Local, |(_, policy)| *policy)
+}
+
+/// Merge cloud settings with local settings using `DEFAULT_MERGE_POLICIES`
 pub fn merge_settings(local: &Settings, cloud: UserSettingsResponse) -> Settings {
-    Settings {
-        // Cloud-synced settings
-        auto_save_enabled: cloud.auto_save_enabled,
-        save_interval_minutes: cloud.save_interval_minutes as u32,
-        max_saves_per_game: cloud.max_saves_per_game as u32,
-        compression_enabled: cloud.compression_enabled,
-        compression_level: cloud.compression_level,
-        show_notifications: cloud.desktop_save_completed || cloud.desktop_sync_errors,
-        
-        // Keep local-only settings unchanged
-        start_on_boot: local.start_on_boot,
-        minimize_to_tray: local.minimize_to_tray,
-        cloud_sync_enabled: local.cloud_sync_enabled,
-        cloud_api_url: local.cloud_api_url.clone(),
-        cloud_auto_sync: local.cloud_auto_sync,
-        hotkey_enabled: local.hotkey_enabled,
-        save_hotkey: local.save_hotkey.clone(),
+    merge_settings_with(local, cloud, DEFAULT_MERGE_POLICIES)
+}
+
+/// Merge cloud settings with local settings, field by field as `policies`
+/// says. Only the cloud-backed settings can change, device-specific ones and
+/// `updated_at` are always the local values. Out of range cloud values are ignored.
+pub fn merge_settings_with(local: &Settings, cloud: UserSettingsResponse, policies: &[(&str, MergePolicy)]) -> Settings {
+    let cloud_is_newer = local.updated_at.is_none_or(|updated_at| cloud.updated_at >= updated_at);
+    let use_cloud = |field: &str| match merge_policy(policies, field) {
+        MergePolicy::Cloud => true,
+        MergePolicy::LastWriteWins => cloud_is_newer,
+        MergePolicy::Local => false,
+    };
+    
+    let mut merged = local.clone();
+    if use_cloud("auto_save_enabled") {
+        merged.auto_save_enabled = cloud.auto_save_enabled;
+    }
+    if use_cloud("save_interval_minutes") {
+        if let Some(minutes) = u32::try_from(cloud.save_interval_minutes).ok().filter(|m| *m > 0) {
+            merged.save_interval_minutes = minutes;
+        }
     }
+    if use_cloud("max_saves_per_game") {
+        if let Ok(max) = u32::try_from(cloud.max_saves_per_game) {
+            merged.max_saves_per_game = max;
+        }
+    }
+    if use_cloud("compression_enabled") {
+        merged.compression_enabled = cloud.compression_enabled;
+    }
+    if use_cloud("compression_level") {
+        merged.compression_level = cloud.compression_level.clamp(1, 22);
+    }
+    if use_cloud("show_notifications") {
+        merged.show_notifications = cloud.desktop_save_completed || cloud.desktop_sync_errors;
+    }
+    merged
 }
 
 /// Convert local settings to update request
 pub fn settings_to_update(settings: &Settings) -> UpdateUserSettings {
+    settings_to_update_with(settings, DEFAULT_MERGE_POLICIES)
+}
+
+/// Convert local settings to update request, leaving out the fields whose
+/// policy is `Local` so this device never overwrites them in the cloud
+pub fn settings_to_update_with(settings: &Settings, policies: &[(&str, MergePolicy)]) -> UpdateUserSettings {
+    let synced = |field: &str| merge_policy(policies, field) != MergePolicy::Local;
     UpdateUserSettings {
-        auto_save_enabled: Some(settings.auto_save_enabled),
-        save_interval_minutes: Some(settings.save_interval_minutes as i32),
-        max_saves_per_game: Some(settings.max_saves_per_game as i32),
+        auto_save_enabled: synced("auto_save_enabled").then_some(settings.auto_save_enabled),
+        save_interval_minutes: synced("save_interval_minutes").then_some(settings.save_interval_minutes as i32),
+        max_saves_per_game: synced("max_saves_per_game").then_some(settings.max_saves_per_game as i32),
         
         // Map notification settings
         email_weekly_summary: None, // Don't update email settings from desktop
         email_product_updates: None, // Don't update email settings from desktop
-        desktop_save_completed: Some(settings.show_notifications),
-        desktop_sync_errors: Some(settings.show_notifications),
+        desktop_save_completed: synced("show_notifications").then_some(settings.show_notifications),
+        desktop_sync_errors: synced("show_notifications").then_some(settings.show_notifications),
         
         // Storage settings
-        compression_enabled: Some(settings.compression_enabled),
-        compression_level: Some(settings.compression_level),
+        compression_enabled: synced("compression_enabled").then_some(settings.compression_enabled),
+        compression_level: synced("compression_level").then_some(settings.compression_level),
         auto_cleanup_days: None, // Managed from web UI
     }
 }
@@ -127,4 +253,255 @@ pub async fn push_settings_to_cloud(api: &SyncApi, settings: &Settings) -> Resul
             Err(e)
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    fn time(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, hour, 0, 0).unwrap()
+    }
+    
+    /// Cloud settings that differ from `Settings::default()` in every field
+    fn cloud_settings(updated_at: DateTime<Utc>) -> UserSettingsResponse {
+        UserSettingsResponse {
+            auto_save_enabled: false,
+            save_interval_minutes: 15,
+            max_saves_per_game: 10,
+            email_weekly_summary: true,
+            email_product_updates: true,
+            desktop_save_completed: false,
+            desktop_sync_errors: false,
+            compression_enabled: false,
+            compression_level: 9,
+            auto_cleanup_days: Some(30),
+            settings_version: 3,
+            updated_at,
+        }
+    }
+    
+    fn assert_cloud_values(merged: &Settings) {
+        assert!(!merged.auto_save_enabled);
+        assert_eq!(merged.save_interval_minutes, 15);
+        assert_eq!(merged.max_saves_per_game, 10);
+        assert!(!merged.compression_enabled);
+        assert_eq!(merged.compression_level, 9);
+        assert!(!merged.show_notifications);
+    }
+    
+    fn assert_local_values(merged: &Settings, local: &Settings) {
+        assert_eq!(merged.auto_save_enabled, local.auto_save_enabled);
+        assert_eq!(merged.save_interval_minutes, local.save_interval_minutes);
+        assert_eq!(merged.max_saves_per_game, local.max_saves_per_game);
+        assert_eq!(merged.compression_enabled, local.compression_enabled);
+        assert_eq!(merged.compression_level, local.compression_level);
+        assert_eq!(merged.show_notifications, local.show_notifications);
+    }
+    
+    #[test]
+    fn test_cloud_wins_when_never_saved_locally() {
+        let local = Settings::default();
+        let merged = merge_settings(&local, cloud_settings(time(12)));
+        assert_cloud_values(&merged);
+        assert_eq!(merged.updated_at, None);
+    }
+    
+    #[test]
+    fn test_last_write_wins() {
+        let mut local = Settings::default();
+        
+        // Changed on another device after this one saved
+        local.updated_at = Some(time(10));
+        assert_cloud_values(&merge_settings(&local, cloud_settings(time(12))));
+        
+        // Saved here but not pushed yet, e.g. while offline
+        local.updated_at = Some(time(14));
+        let merged = merge_settings(&local, cloud_settings(time(12)));
+        assert_local_values(&merged, &local);
+        assert_eq!(merged.updated_at, Some(time(14)));
+        
+        // The cloud wins ties, it's what a successful push looks like
+        local.updated_at = Some(time(12));
+        assert_cloud_values(&merge_settings(&local, cloud_settings(time(12))));
+    }
+    
+    #[test]
+    fn test_field_policies() {
+        let mut local = Settings::default();
+        local.updated_at = Some(time(14));
+        let policies = [
+            ("compression_level", MergePolicy::Cloud),
+            ("auto_save_enabled", MergePolicy::Local),
+            ("save_interval_minutes", MergePolicy::LastWriteWins),
+        ];
+        
+        let merged = merge_settings_with(&local, cloud_settings(time(12)), &policies);
+        assert_eq!(merged.compression_level, 9);
+        assert_eq!(merged.auto_save_enabled, local.auto_save_enabled);
+        assert_eq!(merged.save_interval_minutes, local.save_interval_minutes);
+        // Unlisted fields are local
+        assert_eq!(merged.max_saves_per_game, local.max_saves_per_game);
+        
+        // Local never takes the cloud value, even when the cloud is newer
+        let merged = merge_settings_with(&local, cloud_settings(time(16)), &policies);
+        assert_eq!(merged.auto_save_enabled, local.auto_save_enabled);
+        assert_eq!(merged.save_interval_minutes, 15);
+        
+        assert_eq!(merge_policy(DEFAULT_MERGE_POLICIES, "start_on_boot"), MergePolicy::Local);
+        assert_eq!(merge_policy(DEFAULT_MERGE_POLICIES, "compression_level"), MergePolicy::LastWriteWins);
+    }
+    
+    #[test]
+    fn test_device_settings_are_never_clobbered() {
+        let mut local = Settings::default();
+        local.start_on_boot = true;
+        local.minimize_to_tray = false;
+        local.cloud_sync_enabled = true;
+        local.hotkey_enabled = false;
+        local.save_hotkey = Some("Ctrl+Alt+F5".to_string());
+        local.max_save_states_per_game = 7;
+        local.max_sync_bandwidth_kbps = 512;
+        local.pcsx2_extra_memcard_dirs = vec!["/mnt/memcards".to_string()];
+        local.disabled_emulators = vec!["Citra".to_string()];
+        local.proxy_url = Some("http://proxy:3128".to_string());
+        
+        let all_cloud: Vec<(&str, MergePolicy)> = DEFAULT_MERGE_POLICIES.iter()
+            .map(|(field, _)| (*field, MergePolicy::Cloud))
+            .chain([("start_on_boot", MergePolicy::Cloud), ("save_hotkey", MergePolicy::Cloud)])
+            .collect();
+        let merged = merge_settings_with(&local, cloud_settings(time(12)), &all_cloud);
+        
+        assert_cloud_values(&merged);
+        assert!(merged.start_on_boot);
+        assert!(!merged.minimize_to_tray);
+        assert!(merged.cloud_sync_enabled);
+        assert!(!merged.hotkey_enabled);
+        assert_eq!(merged.save_hotkey, local.save_hotkey);
+        assert_eq!(merged.max_save_states_per_game, 7);
+        assert_eq!(merged.max_sync_bandwidth_kbps, 512);
+        assert_eq!(merged.pcsx2_extra_memcard_dirs, local.pcsx2_extra_memcard_dirs);
+        assert_eq!(merged.disabled_emulators, local.disabled_emulators);
+        assert_eq!(merged.proxy_url, local.proxy_url);
+        assert_eq!(merged.cloud_api_url, local.cloud_api_url);
+    }
+    
+    #[test]
+    fn test_every_setting_is_classified() {
+        // Adding a field to Settings stops this from compiling until it's
+        // listed in DEFAULT_MERGE_POLICIES or LOCAL_ONLY_SETTINGS below
+        let Settings {
+            auto_save_enabled: _,
+            save_interval_minutes: _,
+            process_scan_interval_secs: _,
+            max_saves_per_game: _,
+            max_save_states_per_game: _,
+            start_on_boot: _,
+            minimize_to_tray: _,
+            show_notifications: _,
+            cloud_sync_enabled: _,
+            cloud_api_url: _,
+            cloud_auto_sync: _,
+            sync_backend: _,
+            local_sync_folder: _,
+            rclone_remote: _,
+            hotkey_enabled: _,
+            save_hotkey: _,
+            compression_enabled: _,
+            compression_level: _,
+            auto_vacuum_enabled: _,
+            max_sync_bandwidth_kbps: _,
+            parallel_downloads: _,
+            parallel_uploads: _,
+            conflict_strategy: _,
+            sync_after_closing_only: _,
+            restore_deleted_saves: _,
+            failure_webhook_url: _,
+            encryption_enabled: _,
+            encryption_mode: _,
+            watcher_ignore_patterns: _,
+            backup_exclusions: _,
+            pcsx2_extra_memcard_dirs: _,
+            disabled_emulators: _,
+            allowlist_enabled: _,
+            allowlisted_games: _,
+            verbose_logging: _,
+            theme: _,
+            custom_ca_cert_path: _,
+            accept_invalid_certs: _,
+            proxy_url: _,
+            updated_at: _,
+        } = Settings::default();
+        
+        assert_eq!(DEFAULT_MERGE_POLICIES.len() + LOCAL_ONLY_SETTINGS.len(), 40);
+        for (field, _) in DEFAULT_MERGE_POLICIES {
+            assert!(!LOCAL_ONLY_SETTINGS.contains(field), "{} is both synced and local", field);
+        }
+        for field in LOCAL_ONLY_SETTINGS {
+            assert_eq!(merge_policy(DEFAULT_MERGE_POLICIES, field), MergePolicy::Local);
+        }
+    }
+    
+    #[test]
+    fn test_local_policies_are_not_uploaded() {
+        let settings = Settings::default();
+        let policies = [
+            ("auto_save_enabled", MergePolicy::LastWriteWins),
+            ("compression_level", MergePolicy::Cloud),
+            ("show_notifications", MergePolicy::Local),
+        ];
+        
+        let update = settings_to_update_with(&settings, &policies);
+        assert_eq!(update.auto_save_enabled, Some(settings.auto_save_enabled));
+        assert_eq!(update.compression_level, Some(settings.compression_level));
+        assert_eq!(update.desktop_save_completed, None);
+        assert_eq!(update.desktop_sync_errors, None);
+        assert_eq!(update.save_interval_minutes, None);
+    }
+    
+    #[test]
+    fn test_partial_cloud_payload_parses() {
+        let cloud: UserSettingsResponse = serde_json::from_str(
+            r#"{"auto_save_enabled": false, "updated_at": "2024-03-01T12:00:00Z", "field_from_the_future": 1}"#
+        ).unwrap();
+        assert!(!cloud.auto_save_enabled);
+        assert_eq!(cloud.compression_level, Settings::default().compression_level);
+        assert_eq!(cloud.updated_at, time(12));
+        
+        // Without a date the cloud never beats a local change
+        let cloud: UserSettingsResponse = serde_json::from_str(r#"{"save_interval_minutes": 30}"#).unwrap();
+        let mut local = Settings::default();
+        local.updated_at = Some(time(1));
+        assert_eq!(merge_settings(&local, cloud).save_interval_minutes, local.save_interval_minutes);
+    }
+    
+    #[test]
+    fn test_invalid_cloud_values_are_ignored() {
+        let local = Settings::default();
+        let mut cloud = cloud_settings(time(12));
+        cloud.save_interval_minutes = 0;
+        cloud.max_saves_per_game = -1;
+        cloud.compression_level = 40;
+        
+        let merged = merge_settings(&local, cloud);
+        assert_eq!(merged.save_interval_minutes, local.save_interval_minutes);
+        assert_eq!(merged.max_saves_per_game, local.max_saves_per_game);
+        assert_eq!(merged.compression_level, 22);
+    }
+    
+    #[test]
+    fn test_settings_to_update() {
+        let mut local = Settings::default();
+        local.auto_save_enabled = false;
+        local.compression_level = 7;
+        local.show_notifications = false;
+        
+        let update = settings_to_update(&local);
+        assert_eq!(update.auto_save_enabled, Some(false));
+        assert_eq!(update.compression_level, Some(7));
+        assert_eq!(update.desktop_save_completed, Some(false));
+        // Email and cleanup preferences are managed on the web
+        assert_eq!(update.email_weekly_summary, None);
+        assert_eq!(update.auto_cleanup_days, None);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Environment variable with a webhook URL for devices without a settings window
+pub const FAILURE_WEBHOOK_ENV: &str = "RETROSAVE_FAILURE_WEBHOOK";
+
+/// Syncs that must fail in a row before an alert, so a single dropped
+/// connection doesn't page anyone
+const SYNC_FAILURES_BEFORE_ALERT: u32 = 3;
+/// Alerts of one kind are sent at most this often while the failure persists
+const MIN_ALERT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What went wrong, sent as the payload's `event`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FailureKind {
+    SyncFailed,
+    AuthFailed,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::SyncFailed => "sync_failed",
+            FailureKind::AuthFailed => "auth_failed",
+        }
+    }
+}
+
+/// Posts sync failures to a user-configured webhook. The payload has
+/// `content` for Discord, `text` for Slack and the details as separate fields
+/// for anything else.
+pub struct FailureWebhook {
+    url: RwLock<Option<String>>,
+    device_name: String,
+    alerts: Mutex<AlertState>,
+}
+
+#[derive(Default)]
+struct AlertState {
+    consecutive_failures: u32,
+    last_sent: HashMap<FailureKind, Instant>,
+}
+
+impl AlertState {
+    /// Whether a failure of `kind` should be posted now, recording it if so
+    fn should_alert(&mut self, kind: FailureKind, now: Instant) -> bool {
+        if kind == FailureKind::SyncFailed {
+            self.consecutive_failures += 1;
+            if self.consecutive_failures < SYNC_FAILURES_BEFORE_ALERT {
+                return false;
+            }
+        }
+        
+        if self.last_sent.get(&kind).is_some_and(|sent| now.duration_since(*sent) < MIN_ALERT_INTERVAL) {
+            return false;
+        }
+        self.last_sent.insert(kind, now);
+        true
+    }
+    
+    /// A sync went through, start counting the next streak from zero. The
+    /// hourly limit still applies so a flapping connection can't spam alerts.
+    fn recovered(&mut self) {
+        self.consecutive_failures = 0;
+    }
+}
+
+impl FailureWebhook {
+    pub fn new(device_name: String) -> Self {
+        Self {
+            url: RwLock::new(None),
+            device_name,
+            alerts: Mutex::new(AlertState::default()),
+        }
+    }
+    
+    /// Where to post failures, `None` or blank turns alerts off
+    pub fn set_url(&self, url: Option<String>) {
+        let url = url.map(|url| url.trim().to_string()).filter(|url| !url.is_empty());
+        info!("Failure webhook {}", if url.is_some() { "enabled" } else { "disabled" });
+        *self.url.write().unwrap_or_else(|e| e.into_inner()) = url;
+    }
+    
+    pub fn sync_failed(&self, error: &str) {
+        self.alert(FailureKind::SyncFailed, error);
+    }
+    
+    pub fn sync_succeeded(&self) {
+        self.alerts.lock().unwrap_or_else(|e| e.into_inner()).recovered();
+    }
+    
+    pub fn auth_failed(&self, reason: &str) {
+        self.alert(FailureKind::AuthFailed, reason);
+    }
+    
+    fn alert(&self, kind: FailureKind, message: &str) {
+        let Some(url) = self.url.read().unwrap_or_else(|e| e.into_inner()).clone() else {
+            return;
+        };
+        let failures = {
+            let mut alerts = self.alerts.lock().unwrap_or_else(|e| e.into_inner());
+            if !alerts.should_alert(kind, Instant::now()) {
+                debug!("Not posting {} to the failure webhook yet", kind.as_str());
+                return;
+            }
+            alerts.consecutive_failures
+        };
+        
+        let payload = payload(kind, message, &self.device_name, failures);
+        tokio::spawn(async move {
+            // The URL is a secret for most services, so it's never logged
+            match crate::sync::http::http_client().post(&url).json(&payload).timeout(REQUEST_TIMEOUT).send().await {
+                Ok(response) if response.status().is_success() => debug!("Posted {} to the failure webhook", kind.as_str()),
+                Ok(response) => warn!("Failure webhook answered {}", response.status()),
+                Err(e) => warn!("Failed to post to the failure webhook: {}", e.without_url()),
+            }
+        });
+    }
+}
+
+fn payload(kind: FailureKind, message: &str, device_name: &str, consecutive_failures: u32) -> serde_json::Value {
+    let text = match kind {
+        FailureKind::SyncFailed => format!(
+            "Retrosave on {} failed to sync {} times in a row: {}",
+            device_name, consecutive_failures, message
+        ),
+        FailureKind::AuthFailed => format!(
+            "Retrosave on {} was signed out of the cloud and stopped syncing: {}",
+            device_name, message
+        ),
+    };
+    
+    serde_json::json!({
+        "content": text,
+        "text": text,
+        "event": kind.as_str(),
+        "device": device_name,
+        "message": message,
+        "consecutive_failures": consecutive_failures,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_repeated_sync_failures_alert_once() {
+        let mut alerts = AlertState::default();
+        let now = Instant::now();
+        
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, now));
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, now));
+        assert!(alerts.should_alert(FailureKind::SyncFailed, now));
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, now + Duration::from_secs(60)));
+        assert!(alerts.should_alert(FailureKind::SyncFailed, now + MIN_ALERT_INTERVAL));
+        
+        // Auth failures alert right away, limited separately
+        assert!(alerts.should_alert(FailureKind::AuthFailed, now));
+        assert!(!alerts.should_alert(FailureKind::AuthFailed, now));
+    }
+    
+    #[test]
+    fn test_recovery_resets_the_streak() {
+        let mut alerts = AlertState::default();
+        let now = Instant::now();
+        for _ in 0..SYNC_FAILURES_BEFORE_ALERT {
+            alerts.should_alert(FailureKind::SyncFailed, now);
+        }
+        
+        alerts.recovered();
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, now));
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, now));
+        // A new streak within the hour stays quiet
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, now));
+        
+        alerts.recovered();
+        let later = now + MIN_ALERT_INTERVAL;
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, later));
+        assert!(!alerts.should_alert(FailureKind::SyncFailed, later));
+        assert!(alerts.should_alert(FailureKind::SyncFailed, later));
+    }
+    
+    #[test]
+    fn test_payload() {
+        let payload = payload(FailureKind::SyncFailed, "server unavailable", "htpc", 3);
+        assert_eq!(payload["event"], "sync_failed");
+        assert_eq!(payload["consecutive_failures"], 3);
+        assert_eq!(payload["content"], payload["text"]);
+        assert_eq!(payload["content"], "Retrosave on htpc failed to sync 3 times in a row: server unavailable");
+    }
+}
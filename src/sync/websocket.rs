@@ -1,12 +1,15 @@
 use anyhow::{Result, Context};
-use tokio_tungstenite::{connect_async, tungstenite::Message, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream, MaybeTlsStream};
 use futures_util::{StreamExt, SinkExt};
 use tokio::sync::{mpsc, RwLock};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rand::Rng;
 use serde::{Serialize, Deserialize};
-use tracing::{info, warn, error};
-use tokio::time::{Duration, interval};
+use tracing::{info, warn, error, debug};
+use tokio::time::{Duration, Instant, interval};
 use super::event_handler::EventHandler;
+use super::message_throttler::{MessageThrottler, PriorityProcessor, ProcessResult, ThrottleConfig};
 
 /// WebSocket message types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -122,19 +125,58 @@ pub struct SubscriptionLimits {
     pub family_members: i32,
 }
 
+/// First reconnect delay, doubled on every failed attempt
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Upper bound for the reconnect delay before jitter
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+/// Default time between heartbeat pings
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// Default time to wait for a pong before the connection is considered dead
+const DEFAULT_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the read loop waits for a frame before releasing the connection lock,
+/// so heartbeats and outgoing messages aren't starved on idle connections
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Connection state of the WebSocket client
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Connected,
+    /// Connection was lost, `attempt` counts failed reconnects so far
+    Reconnecting { attempt: u32 },
+    Disconnected,
+}
+
 /// WebSocket client for real-time updates
 pub struct WebSocketClient {
     url: String,
-    token: Option<String>,
+    token: Arc<RwLock<Option<String>>>,
+    auth_manager: Option<Arc<super::AuthManager>>,
     connection: Arc<RwLock<Option<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>>>>,
     event_tx: mpsc::UnboundedSender<WsMessage>,
     event_handler: Arc<EventHandler>,
     reconnect_attempts: Arc<RwLock<u32>>,
+    processor: Arc<PriorityProcessor>,
+    state: Arc<RwLock<ConnectionState>>,
+    shutdown: Arc<AtomicBool>,
+    heartbeat_interval: Duration,
+    heartbeat_timeout: Duration,
+    last_pong: Arc<RwLock<Instant>>,
 }
 
 impl WebSocketClient {
     /// Create a new WebSocket client
     pub fn new(base_url: String, event_tx: mpsc::UnboundedSender<WsMessage>) -> Self {
+        Self::with_throttle_config(base_url, event_tx, ThrottleConfig::default())
+    }
+    
+    /// Create a new WebSocket client with custom throttling of incoming messages.
+    /// Throttled messages still reach `event_tx` but skip the event handler callbacks;
+    /// duplicates are dropped entirely. See `ThrottleConfig` for the default priority types.
+    pub fn with_throttle_config(
+        base_url: String,
+        event_tx: mpsc::UnboundedSender<WsMessage>,
+        throttle_config: ThrottleConfig,
+    ) -> Self {
         // Convert HTTP URL to WebSocket URL
         let ws_url = base_url
             .replace("http://", "ws://")
@@ -142,34 +184,85 @@ impl WebSocketClient {
         
         Self {
             url: format!("{}/ws", ws_url),
-            token: None,
+            token: Arc::new(RwLock::new(None)),
+            auth_manager: None,
             connection: Arc::new(RwLock::new(None)),
             event_tx,
             event_handler: Arc::new(EventHandler::new()),
             reconnect_attempts: Arc::new(RwLock::new(0)),
+            processor: Arc::new(PriorityProcessor::new(Arc::new(MessageThrottler::new(throttle_config)))),
+            state: Arc::new(RwLock::new(ConnectionState::Disconnected)),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            heartbeat_timeout: DEFAULT_HEARTBEAT_TIMEOUT,
+            last_pong: Arc::new(RwLock::new(Instant::now())),
         }
     }
     
+    /// Send a ping every `interval` and reconnect if no pong arrives within `timeout`
+    pub fn with_heartbeat(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self.heartbeat_timeout = timeout;
+        self
+    }
+    
+    /// Fetch a fresh access token from the auth manager whenever (re)connecting
+    pub fn with_auth_manager(mut self, auth_manager: Arc<super::AuthManager>) -> Self {
+        self.auth_manager = Some(auth_manager);
+        self
+    }
+    
+    /// Throttling configuration used for incoming messages
+    pub fn throttle_config(&self) -> &ThrottleConfig {
+        self.processor.throttler().config()
+    }
+    
     /// Get a reference to the event handler for registering callbacks
     pub fn event_handler(&self) -> Arc<EventHandler> {
         self.event_handler.clone()
     }
     
     /// Set authentication token
-    pub async fn set_token(&mut self, token: String) {
-        self.token = Some(token);
+    pub async fn set_token(&self, token: String) {
+        *self.token.write().await = Some(token);
+    }
+    
+    /// Current connection state
+    pub async fn connection_state(&self) -> ConnectionState {
+        self.state.read().await.clone()
+    }
+    
+    async fn set_state(&self, state: ConnectionState) {
+        {
+            let mut current = self.state.write().await;
+            if *current == state {
+                return;
+            }
+            *current = state.clone();
+        }
+        info!("WebSocket connection state: {:?}", state);
+        self.event_handler.notify_connection_state(state).await;
     }
     
     /// Connect to WebSocket server
     pub async fn connect(&self) -> Result<()> {
+        self.shutdown.store(false, Ordering::SeqCst);
         
-        let (ws_stream, _response) = connect_async(&self.url).await
+        // Prefer a fresh token so reconnects after sleep don't authenticate with an expired one
+        if let Some(ref auth_manager) = self.auth_manager {
+            if let Some(token) = auth_manager.get_access_token().await {
+                self.set_token(token).await;
+            }
+        }
+        
+        let (ws_stream, _response) = super::http::connect_websocket(&self.url).await
             .context("Failed to connect to WebSocket")?;
         
         {
             let mut connection = self.connection.write().await;
             *connection = Some(ws_stream);
         } // Explicitly drop the write lock here
+        *self.last_pong.write().await = Instant::now();
         
         // Reset reconnect attempts on successful connection
         {
@@ -180,9 +273,10 @@ impl WebSocketClient {
         info!("WebSocket connected successfully");
         
         // Send authentication message if we have a token
-        if let Some(token) = &self.token {
+        let token = self.token.read().await.clone();
+        if let Some(token) = token {
             match self.send_message(WsMessage::Auth { 
-                token: token.clone() 
+                token
             }).await {
                 Ok(_) => {
                 }
@@ -194,31 +288,40 @@ impl WebSocketClient {
         } else {
         }
         
+        self.set_state(ConnectionState::Connected).await;
         Ok(())
     }
     
-    /// Disconnect from WebSocket server
+    /// Disconnect from WebSocket server and stop reconnecting
     pub async fn disconnect(&self) -> Result<()> {
+        self.shutdown.store(true, Ordering::SeqCst);
         let mut connection = self.connection.write().await;
         if let Some(mut ws) = connection.take() {
             ws.close(None).await?;
             info!("WebSocket disconnected");
         }
+        drop(connection);
+        self.set_state(ConnectionState::Disconnected).await;
         Ok(())
     }
     
     /// Send a message to the server
     pub async fn send_message(&self, message: WsMessage) -> Result<()> {
+        let json = serde_json::to_string(&message)?;
+        self.send_frame(Message::Text(json)).await
+    }
+    
+    /// Send a raw frame to the server
+    async fn send_frame(&self, frame: Message) -> Result<()> {
         let connection = self.connection.read().await;
         
         if let Some(_ws) = connection.as_ref() {
-            let json = serde_json::to_string(&message)?;
             drop(connection); // Drop read lock before acquiring write lock
             
             let mut ws = self.connection.write().await;
             
             if let Some(stream) = ws.as_mut() {
-                match stream.send(Message::Text(json)).await {
+                match stream.send(frame).await {
                     Ok(_) => {
                         // Message sent successfully
                     }
@@ -245,43 +348,61 @@ impl WebSocketClient {
         // Spawn heartbeat task
         let client = self.clone();
         tokio::spawn(async move {
-            let mut heartbeat = interval(Duration::from_secs(30));
-            loop {
-                heartbeat.tick().await;
-                if let Err(_) = client.send_message(WsMessage::Ping).await {
-                    // Heartbeat failed, will be handled by reconnect logic
-                }
-            }
+            client.run_heartbeat().await;
         });
         
         // Main message loop
         loop {
+            if self.shutdown.load(Ordering::SeqCst) {
+                info!("WebSocket listener stopped");
+                break;
+            }
+            
             let connection = self.connection.read().await;
             if connection.is_none() {
+                drop(connection);
                 // Try to reconnect
                 if let Err(e) = self.reconnect().await {
-                    error!("Failed to reconnect: {}", e);
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                    continue;
+                    warn!("Failed to reconnect WebSocket: {}", e);
                 }
+                continue;
             }
             drop(connection);
             
             // Read messages
             let mut connection = self.connection.write().await;
             if let Some(ws) = connection.as_mut() {
-                match ws.next().await {
+                let Ok(frame) = tokio::time::timeout(READ_POLL_INTERVAL, ws.next()).await else {
+                    continue; // Nothing received, release the lock
+                };
+                match frame {
                     Some(Ok(Message::Text(text))) => {
                         match serde_json::from_str::<WsMessage>(&text) {
                             Ok(message) => {
-                                // Process message without logging
+                                if matches!(message, WsMessage::Pong) {
+                                    *self.last_pong.write().await = Instant::now();
+                                }
+                                
+                                let value: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+                                let event_type = value.get("type").and_then(|t| t.as_str()).unwrap_or_default().to_string();
                                 
-                                // Process message through event handler
-                                let event_handler = self.event_handler.clone();
-                                let msg_clone = message.clone();
-                                tokio::spawn(async move {
-                                    event_handler.handle_message(msg_clone).await;
-                                });
+                                match self.processor.process(&event_type, value).await {
+                                    ProcessResult::Immediate(_) | ProcessResult::Normal(_) => {
+                                        // Process message through event handler
+                                        let event_handler = self.event_handler.clone();
+                                        let msg_clone = message.clone();
+                                        tokio::spawn(async move {
+                                            event_handler.handle_message(msg_clone).await;
+                                        });
+                                    }
+                                    ProcessResult::Throttled => {
+                                        debug!("Throttled UI update for {}", event_type);
+                                    }
+                                    ProcessResult::Duplicate => {
+                                        debug!("Dropped duplicate {} message", event_type);
+                                        continue;
+                                    }
+                                }
                                 
                                 // Also send through the channel for backward compatibility
                                 if let Err(e) = self.event_tx.send(message) {
@@ -302,19 +423,25 @@ impl WebSocketClient {
                         }
                     }
                     Some(Ok(Message::Pong(_))) => {
-                        // Pong received
+                        *self.last_pong.write().await = Instant::now();
                     }
                     Some(Ok(Message::Close(_))) => {
                         info!("WebSocket closed by server");
                         *connection = None;
+                        drop(connection);
+                        self.set_state(ConnectionState::Reconnecting { attempt: 0 }).await;
                     }
                     Some(Err(e)) => {
                         error!("WebSocket error: {}", e);
                         *connection = None;
+                        drop(connection);
+                        self.set_state(ConnectionState::Reconnecting { attempt: 0 }).await;
                     }
                     None => {
                         info!("WebSocket stream ended");
                         *connection = None;
+                        drop(connection);
+                        self.set_state(ConnectionState::Reconnecting { attempt: 0 }).await;
                     }
                     _ => {}
                 }
@@ -322,19 +449,53 @@ impl WebSocketClient {
         }
     }
     
-    /// Reconnect to WebSocket server with exponential backoff
-    async fn reconnect(&self) -> Result<()> {
-        let mut attempts = self.reconnect_attempts.write().await;
-        *attempts += 1;
+    /// Ping the server periodically and drop the connection if pongs stop arriving,
+    /// which hands it to the reconnect path in `start_listening`
+    async fn run_heartbeat(&self) {
+        let mut heartbeat = interval(self.heartbeat_interval);
+        heartbeat.tick().await; // First tick completes immediately
         
-        if *attempts > 10 {
-            return Err(anyhow::anyhow!("Max reconnection attempts reached"));
+        loop {
+            heartbeat.tick().await;
+            if self.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+            if self.connection.read().await.is_none() {
+                continue;
+            }
+            
+            let since_pong = self.last_pong.read().await.elapsed();
+            if since_pong > self.heartbeat_interval + self.heartbeat_timeout {
+                warn!("No WebSocket pong for {:?}, treating connection as dead", since_pong);
+                *self.connection.write().await = None;
+                self.set_state(ConnectionState::Reconnecting { attempt: 0 }).await;
+                continue;
+            }
+            
+            if let Err(e) = self.send_frame(Message::Ping(Vec::new())).await {
+                debug!("Failed to send heartbeat ping: {}", e);
+            }
         }
+    }
+    
+    /// Reconnect to WebSocket server with exponential backoff and jitter.
+    /// Event handler callbacks live on the client, so they keep working after a reconnect.
+    async fn reconnect(&self) -> Result<()> {
+        let attempt = {
+            let mut attempts = self.reconnect_attempts.write().await;
+            *attempts += 1;
+            *attempts
+        };
+        self.set_state(ConnectionState::Reconnecting { attempt }).await;
         
-        let delay = Duration::from_secs(2_u64.pow((*attempts).min(5)));
-        info!("Reconnecting to WebSocket (attempt {})", attempts);
+        let delay = reconnect_delay(attempt);
+        info!("Reconnecting to WebSocket in {:?} (attempt {})", delay, attempt);
         tokio::time::sleep(delay).await;
         
+        if self.shutdown.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        
         self.connect().await
     }
     
@@ -378,4 +539,15 @@ impl WebSocketClient {
             downloads,
         }).await
     }
-}
\ No newline at end of file
+}
+
+/// Exponential backoff capped at `RECONNECT_MAX_DELAY`, plus up to 50% random jitter
+/// so many clients don't reconnect in lockstep after a server restart
+fn reconnect_delay(attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    let base = RECONNECT_BASE_DELAY
+        .saturating_mul(1 << exponent)
+        .min(RECONNECT_MAX_DELAY);
+    let jitter = rand::thread_rng().gen_range(0..=base.as_millis() as u64 / 2);
+    base + Duration::from_millis(jitter)
+}
@@ -3,8 +3,11 @@ pub mod settings;
 pub mod notifications;
 pub mod audio;
 pub mod conflict_dialog;
+pub mod save_browser;
+pub mod theme;
 
 pub use tray::SystemTray;
 pub use settings::SettingsWindow;
 pub use notifications::NotificationManager;
-pub use audio::AudioFeedback;
\ No newline at end of file
+pub use audio::AudioFeedback;
+pub use save_browser::SaveBrowser;
\ No newline at end of file
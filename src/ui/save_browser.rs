@@ -0,0 +1,660 @@
+use egui::{Context, RichText, Window};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::storage::compression::format_size;
+use crate::storage::{Database, Game, Save, SaveBackupManager};
+use crate::sync::SyncService;
+use crate::ui::theme::Theme;
+
+/// Notes are meant as short labels like "before final boss"
+const MAX_NOTE_LEN: usize = 80;
+
+/// Restore, delete or merge waiting for the user to confirm it
+enum PendingAction {
+    Restore(Game, Save),
+    Delete(Game, Save),
+    /// Move the saves of the first game into the second
+    Merge(Game, Game),
+}
+
+/// Window listing the games in the database and every recorded save version,
+/// with actions to restore, pin, annotate or delete a version
+pub struct SaveBrowser {
+    database: Arc<Database>,
+    sync_service: Option<Arc<SyncService>>,
+    runtime: tokio::runtime::Handle,
+    open: bool,
+    // Games and hashes of saves waiting to be uploaded
+    games: Vec<Game>,
+    pending_uploads: HashSet<String>,
+    games_rx: Option<Receiver<(Vec<Game>, HashSet<String>)>>,
+    // Save versions of the games that were expanded
+    saves: HashMap<i64, Vec<Save>>,
+    saves_loading: HashSet<i64>,
+    saves_tx: Sender<(i64, Vec<Save>)>,
+    saves_rx: Receiver<(i64, Vec<Save>)>,
+    // Game id, save id and text of the note being edited
+    editing_note: Option<(i64, i64, String)>,
+    focus_note: bool,
+    // Game id and text of the name being edited
+    renaming: Option<(i64, String)>,
+    focus_rename: bool,
+    // Restore/delete in progress and the outcome of the last one
+    pending_action: Option<PendingAction>,
+    action_rx: Option<Receiver<(i64, Result<String, String>)>>,
+    message: Option<Result<String, String>>,
+    // The running action renames or merges games, so the list is reloaded after it
+    reload_games: bool,
+}
+
+impl SaveBrowser {
+    pub fn new(
+        database: Arc<Database>,
+        sync_service: Option<Arc<SyncService>>,
+        runtime: tokio::runtime::Handle,
+    ) -> Self {
+        let (saves_tx, saves_rx) = channel();
+        Self {
+            database,
+            sync_service,
+            runtime,
+            open: false,
+            games: Vec::new(),
+            pending_uploads: HashSet::new(),
+            games_rx: None,
+            saves: HashMap::new(),
+            saves_loading: HashSet::new(),
+            saves_tx,
+            saves_rx,
+            editing_note: None,
+            focus_note: false,
+            renaming: None,
+            focus_rename: false,
+            pending_action: None,
+            action_rx: None,
+            message: None,
+            reload_games: false,
+        }
+    }
+    
+    /// Show the window and reload its contents
+    pub fn open(&mut self, ctx: &Context) {
+        self.open = true;
+        self.message = None;
+        self.refresh(ctx);
+    }
+    
+    pub fn set_sync_service(&mut self, sync_service: Option<Arc<SyncService>>) {
+        self.sync_service = sync_service;
+    }
+    
+    fn refresh(&mut self, ctx: &Context) {
+        self.saves.clear();
+        self.saves_loading.clear();
+        
+        let database = self.database.clone();
+        let sync_service = self.sync_service.clone();
+        let (tx, rx) = channel();
+        self.games_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let games = match database.get_all_games().await {
+                Ok(games) => games,
+                Err(e) => {
+                    error!("Failed to load games: {}", e);
+                    Vec::new()
+                }
+            };
+            let pending_uploads = match sync_service {
+                Some(sync_service) => sync_service.pending_upload_hashes().await,
+                None => HashSet::new(),
+            };
+            let _ = tx.send((games, pending_uploads));
+            ctx.request_repaint();
+        });
+    }
+    
+    fn load_saves(&mut self, game_id: i64, ctx: &Context) {
+        self.saves_loading.insert(game_id);
+        
+        let database = self.database.clone();
+        let tx = self.saves_tx.clone();
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let saves = match database.get_saves_for_game(game_id, None).await {
+                Ok(saves) => saves,
+                Err(e) => {
+                    error!("Failed to load saves for game {}: {}", game_id, e);
+                    Vec::new()
+                }
+            };
+            let _ = tx.send((game_id, saves));
+            ctx.request_repaint();
+        });
+    }
+    
+    fn run_action(&mut self, action: PendingAction, ctx: &Context) {
+        let database = self.database.clone();
+        let sync_service = self.sync_service.clone();
+        let (tx, rx) = channel();
+        self.action_rx = Some(rx);
+        
+        let game_id = match action {
+            PendingAction::Restore(ref game, _) | PendingAction::Delete(ref game, _) => game.id,
+            PendingAction::Merge(_, ref into) => {
+                self.reload_games = true;
+                into.id
+            }
+        };
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = match action {
+                PendingAction::Restore(game, save) => restore_version(&game, &save)
+                    .map(|()| format!("Restored version {} of {}", save.version, game.name)),
+                PendingAction::Delete(game, save) => delete_version(&database, &save).await
+                    .map(|()| format!("Deleted version {} of {}", save.version, game.name))
+                    .map_err(|e| format!("Failed to delete save: {}", e)),
+                PendingAction::Merge(from, into) => merge_games(&database, sync_service, &from, &into).await
+                    .map(|()| format!("Merged {} into {}", from.name, into.name))
+                    .map_err(|e| format!("Failed to merge games: {}", e)),
+            };
+            match result {
+                Ok(ref message) => info!("{}", message),
+                Err(ref e) => error!("{}", e),
+            }
+            let _ = tx.send((game_id, result));
+            ctx.request_repaint();
+        });
+    }
+    
+    /// Pin or unpin a version right away, pinning is easily undone so it isn't confirmed
+    fn toggle_pinned(&mut self, game: &Game, save: &Save, ctx: &Context) {
+        let database = self.database.clone();
+        let (tx, rx) = channel();
+        self.action_rx = Some(rx);
+        
+        let game_id = game.id;
+        let game_name = game.name.clone();
+        let save_id = save.id;
+        let version = save.version;
+        let pinned = !save.pinned;
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = database.set_save_pinned(save_id, pinned).await
+                .map(|()| if pinned {
+                    format!("Pinned version {} of {}, it will be kept until unpinned", version, game_name)
+                } else {
+                    format!("Unpinned version {} of {}", version, game_name)
+                })
+                .map_err(|e| format!("Failed to update save: {}", e));
+            if let Err(ref e) = result {
+                error!("{}", e);
+            }
+            let _ = tx.send((game_id, result));
+            ctx.request_repaint();
+        });
+    }
+    
+    /// Rename a game right away. A name another game already has asks to merge
+    /// the two instead.
+    fn rename(&mut self, game: Game, new_name: String, ctx: &Context) {
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() || new_name == game.name {
+            return;
+        }
+        if let Some(other) = self.games.iter().find(|g| g.id != game.id && g.emulator == game.emulator && g.name == new_name) {
+            self.pending_action = Some(PendingAction::Merge(game, other.clone()));
+            return;
+        }
+        
+        let database = self.database.clone();
+        let sync_service = self.sync_service.clone();
+        let (tx, rx) = channel();
+        self.action_rx = Some(rx);
+        self.reload_games = true;
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = rename_game(&database, sync_service, &game, &new_name).await
+                .map(|()| format!("Renamed {} to {}", game.name, new_name))
+                .map_err(|e| format!("Failed to rename game: {}", e));
+            match result {
+                Ok(ref message) => info!("{}", message),
+                Err(ref e) => error!("{}", e),
+            }
+            let _ = tx.send((game.id, result));
+            ctx.request_repaint();
+        });
+    }
+    
+    fn save_note(&mut self, game_id: i64, save_id: i64, note: String, ctx: &Context) {
+        let database = self.database.clone();
+        let (tx, rx) = channel();
+        self.action_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = database.set_save_note(save_id, &note).await
+                .map(|()| if note.trim().is_empty() { "Note removed".to_string() } else { "Note saved".to_string() })
+                .map_err(|e| format!("Failed to save note: {}", e));
+            if let Err(ref e) = result {
+                error!("{}", e);
+            }
+            let _ = tx.send((game_id, result));
+            ctx.request_repaint();
+        });
+    }
+    
+    /// Draw the window if it is open. `cloud_sync` is whether saves are
+    /// currently being uploaded at all.
+    pub fn show(&mut self, ctx: &Context, cloud_sync: bool) {
+        if !self.open {
+            return;
+        }
+        
+        if let Some(ref rx) = self.games_rx {
+            if let Ok((games, pending_uploads)) = rx.try_recv() {
+                self.games = games;
+                self.pending_uploads = pending_uploads;
+                self.games_rx = None;
+            }
+        }
+        
+        while let Ok((game_id, saves)) = self.saves_rx.try_recv() {
+            self.saves_loading.remove(&game_id);
+            self.saves.insert(game_id, saves);
+        }
+        
+        if let Some(ref rx) = self.action_rx {
+            if let Ok((game_id, result)) = rx.try_recv() {
+                self.message = Some(result);
+                self.action_rx = None;
+                // Reload the versions of the game that changed
+                self.saves.remove(&game_id);
+                if std::mem::take(&mut self.reload_games) {
+                    self.refresh(ctx);
+                }
+            }
+        }
+        
+        let theme = Theme::current(ctx);
+        let mut open = self.open;
+        let mut requested = None;
+        let mut toggle_pin = None;
+        let mut edit_note = None;
+        let mut commit_note = false;
+        let mut cancel_note = false;
+        let mut start_rename = None;
+        let mut commit_rename = None;
+        let mut cancel_rename = false;
+        let mut refresh = false;
+        let mut load = Vec::new();
+        
+        Window::new("Saves")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(560.0)
+            .default_height(500.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} games", self.games.len()));
+                    if ui.add_enabled(self.games_rx.is_none(), egui::Button::new("🔃 Refresh")).clicked() {
+                        refresh = true;
+                    }
+                });
+                
+                match self.message {
+                    Some(Ok(ref message)) => {
+                        ui.colored_label(theme.success, format!("✓ {}", message));
+                    }
+                    Some(Err(ref e)) => {
+                        ui.colored_label(theme.error, format!("⚠ {}", e));
+                    }
+                    None => {}
+                }
+                ui.separator();
+                
+                if self.games_rx.is_some() {
+                    ui.spinner();
+                    return;
+                }
+                if self.games.is_empty() {
+                    ui.label("No saves recorded yet");
+                    return;
+                }
+                
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for game in &self.games {
+                        let header = egui::CollapsingHeader::new(format!("{} ({})", game.name, game.emulator))
+                            .id_salt(("save_browser_game", game.id))
+                            .show(ui, |ui| {
+                                let busy = self.action_rx.is_some();
+                                ui.horizontal(|ui| {
+                                    match self.renaming {
+                                        Some((game_id, ref mut text)) if game_id == game.id => {
+                                            let response = ui.add(egui::TextEdit::singleline(text)
+                                                .hint_text("Game name")
+                                                .desired_width(240.0));
+                                            if self.focus_rename {
+                                                response.request_focus();
+                                                self.focus_rename = false;
+                                            }
+                                            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                            if ui.add_enabled(!busy, egui::Button::new("Save")).clicked() || (entered && !busy) {
+                                                commit_rename = Some((game.clone(), text.clone()));
+                                            }
+                                            if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                                                cancel_rename = true;
+                                            }
+                                        }
+                                        _ => {
+                                            if ui.add_enabled(!busy, egui::Button::new("✏ Rename"))
+                                                .on_hover_text("Correct the name of this game. Its saves and backups move along.")
+                                                .clicked()
+                                            {
+                                                start_rename = Some((game.id, game.name.clone()));
+                                            }
+                                        }
+                                    }
+                                    
+                                    let others: Vec<&Game> = self.games.iter()
+                                        .filter(|other| other.id != game.id && other.emulator == game.emulator)
+                                        .collect();
+                                    if !busy && !others.is_empty() {
+                                        egui::ComboBox::from_id_salt(("save_browser_merge", game.id))
+                                            .selected_text("Merge into…")
+                                            .show_ui(ui, |ui| {
+                                                for other in others {
+                                                    if ui.selectable_label(false, &other.name).clicked() {
+                                                        requested = Some(PendingAction::Merge(game.clone(), other.clone()));
+                                                    }
+                                                }
+                                            })
+                                            .response
+                                            .on_hover_text("Move all versions of this game into another one, e.g. when it was recorded under two names");
+                                    }
+                                });
+                                
+                                let Some(saves) = self.saves.get(&game.id) else {
+                                    ui.spinner();
+                                    return;
+                                };
+                                if saves.is_empty() {
+                                    ui.label("No versions kept for this game");
+                                    return;
+                                }
+                                
+                                egui::Grid::new(("save_browser_versions", game.id))
+                                    .num_columns(8)
+                                    .spacing([12.0, 4.0])
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for save in saves {
+                                            if save.pinned {
+                                                ui.label("📌").on_hover_text("Pinned, kept when old versions are cleaned up");
+                                            } else {
+                                                ui.label("");
+                                            }
+                                            ui.label(format!("v{}", save.version));
+                                            ui.label(save.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string());
+                                            ui.label(format_size(save.file_size.max(0) as u64));
+                                            ui.label(RichText::new(&save.file_hash[..save.file_hash.len().min(8)]).monospace())
+                                                .on_hover_text(&save.file_hash);
+                                            
+                                            let (status, color) = if !cloud_sync || !game.sync_enabled {
+                                                ("Local only", theme.muted)
+                                            } else if self.pending_uploads.contains(&save.file_hash) {
+                                                ("Pending upload", theme.warning)
+                                            } else {
+                                                ("Synced", theme.success)
+                                            };
+                                            ui.label(RichText::new(status).color(color));
+                                            
+                                            match self.editing_note {
+                                                Some((_, save_id, ref mut text)) if save_id == save.id => {
+                                                    let response = ui.add(egui::TextEdit::singleline(text)
+                                                        .char_limit(MAX_NOTE_LEN)
+                                                        .hint_text("Note")
+                                                        .desired_width(140.0));
+                                                    if self.focus_note {
+                                                        response.request_focus();
+                                                        self.focus_note = false;
+                                                    }
+                                                    if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                                                        cancel_note = true;
+                                                    } else if response.lost_focus() {
+                                                        commit_note = true;
+                                                    }
+                                                }
+                                                _ => {
+                                                    let note = save.note.as_deref().unwrap_or("");
+                                                    let response = ui.add(egui::Label::new(RichText::new(note).italics().color(theme.muted))
+                                                        .sense(egui::Sense::click()))
+                                                        .on_hover_text("Click to edit the note");
+                                                    if response.clicked() {
+                                                        edit_note = Some((game.id, save.id, note.to_string()));
+                                                    }
+                                                }
+                                            }
+                                            
+                                            ui.horizontal(|ui| {
+                                                if ui.add_enabled(!busy && save.backup_path.is_some(), egui::Button::new("Restore"))
+                                                    .on_disabled_hover_text("No backup of this version is available")
+                                                    .clicked()
+                                                {
+                                                    requested = Some(PendingAction::Restore(game.clone(), save.clone()));
+                                                }
+                                                let pin_label = if save.pinned { "Unpin" } else { "Pin" };
+                                                if ui.add_enabled(!busy, egui::Button::new(pin_label))
+                                                    .on_hover_text("Pinned versions are never removed automatically")
+                                                    .clicked()
+                                                {
+                                                    toggle_pin = Some((game.clone(), save.clone()));
+                                                }
+                                                if ui.add_enabled(!busy, egui::Button::new("✏"))
+                                                    .on_hover_text("Add a note to this version")
+                                                    .clicked()
+                                                {
+                                                    edit_note = Some((game.id, save.id, save.note.clone().unwrap_or_default()));
+                                                }
+                                                if ui.add_enabled(!busy, egui::Button::new("Delete")).clicked() {
+                                                    requested = Some(PendingAction::Delete(game.clone(), save.clone()));
+                                                }
+                                            });
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        if header.body_response.is_some()
+                            && !self.saves.contains_key(&game.id)
+                            && !self.saves_loading.contains(&game.id)
+                        {
+                            load.push(game.id);
+                        }
+                    }
+                });
+            });
+        
+        self.open = open;
+        if refresh {
+            self.refresh(ctx);
+        }
+        for game_id in load {
+            self.load_saves(game_id, ctx);
+        }
+        if cancel_note {
+            self.editing_note = None;
+        } else if commit_note {
+            if let Some((game_id, save_id, note)) = self.editing_note.take() {
+                self.save_note(game_id, save_id, note, ctx);
+            }
+        }
+        if edit_note.is_some() {
+            self.editing_note = edit_note;
+            self.focus_note = true;
+        }
+        if cancel_rename {
+            self.renaming = None;
+        } else if let Some((game, new_name)) = commit_rename {
+            self.renaming = None;
+            self.rename(game, new_name, ctx);
+        }
+        if start_rename.is_some() {
+            self.renaming = start_rename;
+            self.focus_rename = true;
+        }
+        if let Some((game, save)) = toggle_pin {
+            self.toggle_pinned(&game, &save, ctx);
+        }
+        if requested.is_some() {
+            self.pending_action = requested;
+        }
+        
+        self.show_confirmation(ctx);
+    }
+    
+    /// Ask before overwriting the current save, deleting a version or merging games
+    fn show_confirmation(&mut self, ctx: &Context) {
+        let Some(ref action) = self.pending_action else {
+            return;
+        };
+        
+        let (title, text) = match action {
+            PendingAction::Restore(game, save) => (
+                "Restore save?",
+                format!("Replace the current save of {} with version {} from {}?",
+                    game.name, save.version, save.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")),
+            ),
+            PendingAction::Delete(game, save) => (
+                "Delete version?",
+                if save.pinned {
+                    format!("Version {} of {} is pinned. Delete it anyway? Its backup file is removed as well.", save.version, game.name)
+                } else {
+                    format!("Delete version {} of {}? Its backup file is removed as well.", save.version, game.name)
+                },
+            ),
+            PendingAction::Merge(from, into) => (
+                "Merge games?",
+                format!("Move every version of {} into {} and remove {}? \
+                    Saves still detected as {} are recorded under {} from now on.",
+                    from.name, into.name, from.name, from.name, into.name),
+            ),
+        };
+        
+        let mut confirmed = false;
+        let mut cancelled = false;
+        Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(text);
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Confirm").clicked() {
+                        confirmed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+        
+        if confirmed {
+            if let Some(action) = self.pending_action.take() {
+                self.run_action(action, ctx);
+            }
+        } else if cancelled {
+            self.pending_action = None;
+        }
+    }
+}
+
+/// Copy a backed up version over the game's save file
+fn restore_version(game: &Game, save: &Save) -> Result<(), String> {
+    let backup_path = save.backup_path.as_deref()
+        .ok_or_else(|| "No backup of this version is available".to_string())?;
+    if !Path::new(backup_path).exists() {
+        return Err(format!("The backup of version {} of {} no longer exists", save.version, game.name));
+    }
+    
+    let backup_manager = SaveBackupManager::new(None)
+        .map_err(|e| format!("Failed to open backups: {}", e))?;
+    backup_manager.restore_save(Path::new(backup_path), Path::new(&save.file_path))
+        .map_err(|e| format!("Failed to restore save: {}", e))
+}
+
+/// Rename a game and move its backups along. The latest save is queued so
+/// the cloud gets it under the new name on the next sync.
+async fn rename_game(
+    database: &Database,
+    sync_service: Option<Arc<SyncService>>,
+    game: &Game,
+    new_name: &str,
+) -> anyhow::Result<()> {
+    database.rename_game(game.id, new_name).await?;
+    relink_backups(database, game.id, new_name.trim()).await?;
+    resync_game(sync_service, game.id).await;
+    Ok(())
+}
+
+/// Merge one game into another and move the merged backups into its folder
+async fn merge_games(
+    database: &Database,
+    sync_service: Option<Arc<SyncService>>,
+    from: &Game,
+    into: &Game,
+) -> anyhow::Result<()> {
+    database.merge_games(from.id, into.id).await?;
+    relink_backups(database, into.id, &into.name).await?;
+    resync_game(sync_service, into.id).await;
+    Ok(())
+}
+
+/// Point the versions of a game at backups in the folder of its current name
+async fn relink_backups(database: &Database, game_id: i64, game_name: &str) -> anyhow::Result<()> {
+    let backup_manager = SaveBackupManager::new(None)?;
+    for save in database.get_saves_for_game(game_id, None).await? {
+        let Some(backup_path) = save.backup_path else {
+            continue;
+        };
+        match backup_manager.relink_backup(Path::new(&backup_path), game_name) {
+            Ok(Some(new_path)) => database.set_save_backup_path(save.id, &new_path.to_string_lossy()).await?,
+            Ok(None) => {}
+            Err(e) => warn!("Failed to move backup {}: {}", backup_path, e),
+        }
+    }
+    
+    Ok(())
+}
+
+/// The rename already happened locally, so failing to queue the upload is only logged
+async fn resync_game(sync_service: Option<Arc<SyncService>>, game_id: i64) {
+    if let Some(sync_service) = sync_service {
+        if let Err(e) = sync_service.resync_game(game_id).await {
+            warn!("Failed to queue game {} for upload: {}", game_id, e);
+        }
+    }
+}
+
+/// Remove a version from the database along with its backup file
+async fn delete_version(database: &Database, save: &Save) -> anyhow::Result<()> {
+    let Some(deleted) = database.delete_save(save.id).await? else {
+        return Ok(());
+    };
+    
+    if let Some(backup_path) = deleted.backup_path {
+        match std::fs::remove_file(&backup_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove backup {}: {}", backup_path, e),
+        }
+    }
+    
+    Ok(())
+}
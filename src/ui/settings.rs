@@ -4,83 +4,44 @@ use std::sync::{Arc, Mutex};
 use tracing::{debug, info, error, warn};
 use tokio::sync::mpsc;
 use crate::storage::SettingsManager;
-use crate::sync::{AuthManager, api::SyncApi, WebSocketClient};
+use crate::storage::Game;
+use crate::storage::{CompressionStats, SaveBackupManager};
+use crate::storage::compression::{format_size, benchmark_levels, BENCHMARK_LEVELS};
+use crate::sync::{AuthManager, api::{SyncApi, Device}, WebSocketClient, ConnectionState};
+use crate::sync::auth::is_valid_profile_name;
+use crate::storage::database::DEFAULT_PROFILE;
+use crate::sync::service::{SyncStatus, ConflictResolutionStrategy};
+use crate::sync::EncryptionMode;
 use crate::payment::{SubscriptionStatus, UsageStats};
+use crate::settings::{Settings, SyncBackend, ThemePreference};
+use crate::ui::theme::Theme;
 
-#[derive(Debug, Clone)]
-pub struct Settings {
-    pub auto_save_enabled: bool,
-    pub save_interval_minutes: u32,
-    pub max_saves_per_game: u32,
-    pub start_on_boot: bool,
-    pub minimize_to_tray: bool,
-    pub show_notifications: bool,
-    pub cloud_sync_enabled: bool,
-    pub cloud_api_url: String,
-    pub cloud_auto_sync: bool,
-    pub hotkey_enabled: bool,
-    pub save_hotkey: Option<String>,
-    pub compression_enabled: bool,
-    pub compression_level: i32,
-}
+/// How often the sync status panel refreshes
+const SYNC_STATUS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// How often the progress bar redraws while a sync is running
+const SYNC_PROGRESS_REPAINT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+/// Pending uploads that sit in the queue longer than this are considered stuck
+const STUCK_UPLOAD_THRESHOLD: std::time::Duration = std::time::Duration::from_secs(10 * 60);
 
-impl Default for Settings {
-    fn default() -> Self {
-        // API URL is determined by environment variable or build configuration
-        // Users cannot change this - it's managed automatically
-        let cloud_api_url = Self::get_api_url();
-
-        Self {
-            auto_save_enabled: true,
-            save_interval_minutes: 5,
-            max_saves_per_game: 5,
-            start_on_boot: false,
-            minimize_to_tray: true,
-            show_notifications: true,
-            cloud_sync_enabled: false,
-            cloud_api_url,
-            cloud_auto_sync: true,
-            hotkey_enabled: true,
-            save_hotkey: Some("Ctrl+Shift+S".to_string()),
-            compression_enabled: true,
-            compression_level: 3,
-        }
-    }
-}
+/// Fallback runtime for when the settings window is created outside of Tokio
+static UI_RUNTIME: once_cell::sync::OnceCell<tokio::runtime::Runtime> = once_cell::sync::OnceCell::new();
 
-impl Settings {
-    /// Get the API URL based on environment configuration
-    /// This is not user-configurable - it's determined automatically
-    pub fn get_api_url() -> String {
-        // First check environment variable (for development/testing)
-        if let Ok(url) = std::env::var("RETROSAVE_API_URL") {
-            return url;
-        }
-        
-        // Otherwise use build configuration
-        #[cfg(debug_assertions)]
-        {
-            // Development build - use local server
-            "http://localhost:8080".to_string()
-        }
-        #[cfg(not(debug_assertions))]
-        {
-            // Production build - use cloud API
-            "https://api.retrosave.cloud".to_string()
-        }
-    }
-    
-    /// Ensure API URL is always using the correct value
-    /// Call this when loading settings from disk to override any stored value
-    pub fn update_api_url(&mut self) {
-        self.cloud_api_url = Self::get_api_url();
-    }
+/// File the settings are exported to and imported from unless the user types another
+fn default_settings_file_path() -> std::path::PathBuf {
+    dirs::document_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("retrosave-settings.json")
 }
 
 #[derive(Debug, Clone)]
 pub enum SettingsCommand {
     Show,
+    /// Show the window with the save browser open
+    ShowSaveBrowser,
     Hide,
+    /// Close the window for good as part of shutting down
+    Quit,
 }
 
 pub struct SettingsWindow {
@@ -88,6 +49,8 @@ pub struct SettingsWindow {
     settings: Arc<Mutex<Settings>>,
     settings_manager: Option<Arc<SettingsManager>>,
     sync_service: Arc<Mutex<Option<Arc<crate::sync::SyncService>>>>,
+    runtime: tokio::runtime::Handle,
+    quit_requested: Arc<tokio::sync::Notify>,
 }
 
 impl SettingsWindow {
@@ -99,10 +62,14 @@ impl SettingsWindow {
         let (tx, rx) = mpsc::channel::<SettingsCommand>(10);
         let settings = Arc::new(Mutex::new(initial_settings));
         let settings_clone = settings.clone();
+        let runtime = Self::runtime_handle()?;
+        let runtime_clone = runtime.clone();
+        let quit_requested = Arc::new(tokio::sync::Notify::new());
+        let quit_requested_clone = quit_requested.clone();
         
         // Start the settings window in a dedicated thread
         std::thread::spawn(move || {
-            if let Err(e) = Self::run_window(settings_clone, rx, None, None, None, Arc::new(Mutex::new(None))) {
+            if let Err(e) = Self::run_window(settings_clone, rx, None, None, None, Arc::new(Mutex::new(None)), runtime_clone, quit_requested_clone) {
                 error!("Settings window thread error: {}", e);
             }
         });
@@ -112,6 +79,8 @@ impl SettingsWindow {
             settings,
             settings_manager: None,
             sync_service: Arc::new(Mutex::new(None)),
+            runtime,
+            quit_requested,
         })
     }
     
@@ -120,10 +89,14 @@ impl SettingsWindow {
         let settings = Arc::new(Mutex::new(initial_settings));
         let settings_clone = settings.clone();
         let settings_manager_clone = Some(settings_manager.clone());
+        let runtime = Self::runtime_handle()?;
+        let runtime_clone = runtime.clone();
+        let quit_requested = Arc::new(tokio::sync::Notify::new());
+        let quit_requested_clone = quit_requested.clone();
         
         // Start the settings window in a dedicated thread
         std::thread::spawn(move || {
-            if let Err(e) = Self::run_window(settings_clone, rx, settings_manager_clone, None, None, Arc::new(Mutex::new(None))) {
+            if let Err(e) = Self::run_window(settings_clone, rx, settings_manager_clone, None, None, Arc::new(Mutex::new(None)), runtime_clone, quit_requested_clone) {
                 error!("Settings window thread error: {}", e);
             }
         });
@@ -133,6 +106,8 @@ impl SettingsWindow {
             settings,
             settings_manager: Some(settings_manager),
             sync_service: Arc::new(Mutex::new(None)),
+            runtime,
+            quit_requested,
         })
     }
     
@@ -162,6 +137,10 @@ impl SettingsWindow {
         let auth_manager_clone = Some(auth_manager.clone());
         let sync_service_wrapped = Arc::new(Mutex::new(sync_service.clone()));
         let sync_service_clone = sync_service_wrapped.clone();
+        let runtime = Self::runtime_handle()?;
+        let runtime_clone = runtime.clone();
+        let quit_requested = Arc::new(tokio::sync::Notify::new());
+        let quit_requested_clone = quit_requested.clone();
         
         // Create API client
         let api_client = Some(Arc::new(SyncApi::new(
@@ -171,7 +150,7 @@ impl SettingsWindow {
         
         // Start the settings window in a dedicated thread
         std::thread::spawn(move || {
-            if let Err(e) = Self::run_window(settings_clone, rx, settings_manager_clone, auth_manager_clone, api_client, sync_service_clone) {
+            if let Err(e) = Self::run_window(settings_clone, rx, settings_manager_clone, auth_manager_clone, api_client, sync_service_clone, runtime_clone, quit_requested_clone) {
                 error!("Settings window thread error: {}", e);
             }
         });
@@ -181,9 +160,24 @@ impl SettingsWindow {
             settings,
             settings_manager: Some(settings_manager),
             sync_service: sync_service_wrapped,
+            runtime,
+            quit_requested,
         })
     }
     
+    /// Get a handle to the runtime used for all background UI work.
+    /// Reuses the caller's runtime when there is one, otherwise a single
+    /// process-wide runtime is created on first use.
+    fn runtime_handle() -> Result<tokio::runtime::Handle> {
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            return Ok(handle);
+        }
+        
+        let runtime = UI_RUNTIME.get_or_try_init(tokio::runtime::Runtime::new)?;
+        Ok(runtime.handle().clone())
+    }
+
+    
     pub fn set_sync_service(&self, sync_service: Arc<crate::sync::SyncService>) {
         let mut service = self.sync_service.lock().unwrap();
         *service = Some(sync_service);
@@ -196,34 +190,47 @@ impl SettingsWindow {
         auth_manager: Option<Arc<AuthManager>>,
         api_client: Option<Arc<SyncApi>>,
         sync_service: Arc<Mutex<Option<Arc<crate::sync::SyncService>>>>,
+        runtime: tokio::runtime::Handle,
+        quit_requested: Arc<tokio::sync::Notify>,
     ) -> Result<()> {
         // Wait for the first Show command before creating the window
-        let runtime = tokio::runtime::Runtime::new()?;
+        let app_runtime = runtime.clone();
         
         runtime.block_on(async move {
             let mut command_receiver = command_receiver;
             // Wait for first show command
             info!("Settings window thread waiting for first show command");
-            loop {
+            let first_command = loop {
                 if let Some(cmd) = command_receiver.recv().await {
                     match cmd {
                         SettingsCommand::Show => {
                             info!("First show command received, creating settings window");
-                            break;
+                            break None;
+                        }
+                        SettingsCommand::ShowSaveBrowser => {
+                            info!("Save browser requested, creating settings window");
+                            break Some(SettingsCommand::ShowSaveBrowser);
                         }
                         SettingsCommand::Hide => {
                             // Ignore hide commands before window exists
                             continue;
                         }
+                        SettingsCommand::Quit => {
+                            return Ok(());
+                        }
                     }
                 } else {
                     // Channel closed, exit
                     return Ok(());
                 }
-            }
+            };
             
             // Now create the channel for the window
             let (tx, rx) = std::sync::mpsc::channel::<SettingsCommand>();
+            // Replay a command the window still has to act on
+            if let Some(cmd) = first_command {
+                let _ = tx.send(cmd);
+            }
             
             // Forward remaining commands to the window
             let tx_clone = tx.clone();
@@ -237,7 +244,7 @@ impl SettingsWindow {
             
             // Load window icon
             let mut viewport = egui::ViewportBuilder::default()
-                .with_inner_size([650.0, 800.0])  // Increased width and height
+                .with_inner_size([800.0, 800.0])  // Room for the section sidebar
                 .with_resizable(true)
                 .with_visible(true); // Show immediately since we got a Show command
                 
@@ -268,17 +275,14 @@ impl SettingsWindow {
                         let auth_manager_clone = auth_manager.clone();
                         let (tx, rx) = std::sync::mpsc::channel();
                         
-                        std::thread::spawn(move || {
-                            let rt = tokio::runtime::Runtime::new().unwrap();
-                            rt.block_on(async {
-                                let is_auth = auth_manager_clone.is_authenticated().await;
-                                if is_auth {
-                                    let user = auth_manager_clone.get_user_info().await;
-                                    let _ = tx.send((true, user.map(|u| u.email)));
-                                } else {
-                                    let _ = tx.send((false, None));
-                                }
-                            });
+                        app_runtime.spawn(async move {
+                            let is_auth = auth_manager_clone.is_authenticated().await;
+                            if is_auth {
+                                let user = auth_manager_clone.get_user_info().await;
+                                let _ = tx.send((true, user.map(|u| u.email)));
+                            } else {
+                                let _ = tx.send((false, None));
+                            }
                         });
                         
                         // Try to get immediate result, otherwise store receiver
@@ -295,6 +299,7 @@ impl SettingsWindow {
                     };
                     
                     let mut app = SettingsApp {
+                        runtime: app_runtime.clone(),
                         settings: settings.clone(),
                         command_receiver: rx,
                         visible: true, // Start visible since we're responding to Show
@@ -302,6 +307,8 @@ impl SettingsWindow {
                         settings_manager: settings_manager.clone(),
                         auth_manager: auth_manager.clone(),
                         sync_service: sync_service.clone(),
+                        quit_requested: quit_requested.clone(),
+                        quitting: false,
                         // Auth state
                         is_authenticated,
                         user_email: user_email.clone(),
@@ -321,6 +328,75 @@ impl SettingsWindow {
                         ws_initialized: false,
                         ws_subscription_rx: None,
                         ws_usage_rx: None,
+                        ws_state_rx: None,
+                        ws_connection_state: None,
+                        // Manual sync state
+                        sync_in_progress: false,
+                        sync_result_rx: None,
+                        last_sync_result: None,
+                        // Sync status panel
+                        sync_status: None,
+                        sync_status_rx: None,
+                        last_status_poll: None,
+                        pending_uploads_since: None,
+                        // Maintenance state
+                        pending_maintenance: None,
+                        maintenance_rx: None,
+                        maintenance_result: None,
+                        // Per-game sync toggles
+                        games: Vec::new(),
+                        games_rx: None,
+                        games_loaded: false,
+                        // Compression statistics
+                        compression_stats: None,
+                        compression_stats_rx: None,
+                        compression_stats_loaded: false,
+                        compression_benchmark: None,
+                        compression_benchmark_rx: None,
+                        // Account devices
+                        devices: Vec::new(),
+                        devices_rx: None,
+                        devices_loaded: false,
+                        devices_error: None,
+                        current_device_id: None,
+                        ws_devices_rx: None,
+                        // Cloud profiles
+                        profiles: Vec::new(),
+                        active_profile: None,
+                        profiles_rx: None,
+                        profiles_loaded: false,
+                        profile_error: None,
+                        new_profile_name: String::new(),
+                        // API connection test
+                        connection_test_rx: None,
+                        connection_test_result: None,
+                        rclone_rx: None,
+                        rclone_info: None,
+                        // End-to-end encryption
+                        encryption_passphrase: String::new(),
+                        encryption_confirm: String::new(),
+                        encryption_unlocked: None,
+                        encryption_rx: None,
+                        encryption_error: None,
+                        confirm_show_key: false,
+                        exported_key: None,
+                        exported_key_rx: None,
+                        import_key_input: String::new(),
+                        // Save browser window
+                        save_browser: None,
+                        applied_theme: None,
+                        // Section sidebar and search
+                        search_query: String::new(),
+                        focus_search: false,
+                        selected_section: None,
+                        scroll_to_section: None,
+                        // Start on boot
+                        startup_error: None,
+                        // Diagnostics export
+                        diagnostics_path: crate::diagnostics::default_export_path().to_string_lossy().to_string(),
+                        // Settings import/export
+                        settings_file_path: default_settings_file_path().to_string_lossy().to_string(),
+                        settings_import_rx: None,
                     };
                     
                     // If authenticated on startup, fetch subscription status
@@ -344,6 +420,14 @@ impl SettingsWindow {
         Ok(())
     }
     
+    /// Show the window and open the save browser in it
+    pub async fn show_save_browser(&self) -> Result<()> {
+        info!("Showing save browser");
+        self.command_sender.send(SettingsCommand::ShowSaveBrowser).await
+            .map_err(|_| anyhow::anyhow!("Failed to send show save browser command"))?;
+        Ok(())
+    }
+    
     pub async fn hide(&self) -> Result<()> {
         info!("Hiding settings window");
         self.command_sender.send(SettingsCommand::Hide).await
@@ -351,6 +435,24 @@ impl SettingsWindow {
         Ok(())
     }
     
+    /// Close the window and end its thread
+    pub async fn close(&self) -> Result<()> {
+        info!("Closing settings window");
+        self.command_sender.send(SettingsCommand::Quit).await
+            .map_err(|_| anyhow::anyhow!("Failed to send quit command"))?;
+        Ok(())
+    }
+    
+    /// Resolves once the user closes the window with "Minimize to system tray" turned off
+    pub async fn quit_requested(&self) {
+        self.quit_requested.notified().await;
+    }
+    
+    /// The settings the window edits, for tasks that follow the changes made in it
+    pub fn shared_settings(&self) -> Arc<Mutex<Settings>> {
+        self.settings.clone()
+    }
+    
     pub fn get_settings(&self) -> Settings {
         self.settings.lock().unwrap().clone()
     }
@@ -364,36 +466,29 @@ impl SettingsWindow {
     fn setup_custom_fonts(ctx: &egui::Context) {
         let mut fonts = egui::FontDefinitions::default();
         
-        // Load Inter font
-        let font_path = std::path::Path::new("client/assets/fonts/Inter-Regular.ttf");
-        if let Ok(font_data) = std::fs::read(font_path) {
-            fonts.font_data.insert(
-                "Inter".to_owned(),
-                egui::FontData::from_owned(font_data),
-            );
-            
-            // Insert Inter as the first priority for all font families
-            fonts.families.entry(egui::FontFamily::Proportional)
-                .or_default()
-                .insert(0, "Inter".to_owned());
-            
-            fonts.families.entry(egui::FontFamily::Monospace)
-                .or_default()
-                .insert(0, "Inter".to_owned());
-                
-            ctx.set_fonts(fonts);
-            
-            // Also set a slightly larger default text size
-            let mut style = (*ctx.style()).clone();
-            style.text_styles = [
-                (egui::TextStyle::Small, egui::FontId::new(11.0, egui::FontFamily::Proportional)),
-                (egui::TextStyle::Body, egui::FontId::new(13.0, egui::FontFamily::Proportional)),
-                (egui::TextStyle::Button, egui::FontId::new(13.0, egui::FontFamily::Proportional)),
-                (egui::TextStyle::Heading, egui::FontId::new(18.0, egui::FontFamily::Proportional)),
-                (egui::TextStyle::Monospace, egui::FontId::new(12.0, egui::FontFamily::Monospace)),
-            ].iter().cloned().collect();
-            ctx.set_style(style);
-        }
+        // Inter is embedded so the UI doesn't depend on the working directory.
+        // egui's bundled emoji fonts stay behind it as fallbacks, which covers
+        // the icons used in labels (⚠, ✅, 🔄, ...) on every platform.
+        fonts.font_data.insert(
+            "Inter".to_owned(),
+            egui::FontData::from_static(include_bytes!("../../assets/fonts/Inter-Regular.ttf")),
+        );
+        fonts.families.entry(egui::FontFamily::Proportional)
+            .or_default()
+            .insert(0, "Inter".to_owned());
+        
+        ctx.set_fonts(fonts);
+        
+        // Also set a slightly larger default text size
+        let mut style = (*ctx.style()).clone();
+        style.text_styles = [
+            (egui::TextStyle::Small, egui::FontId::new(11.0, egui::FontFamily::Proportional)),
+            (egui::TextStyle::Body, egui::FontId::new(13.0, egui::FontFamily::Proportional)),
+            (egui::TextStyle::Button, egui::FontId::new(13.0, egui::FontFamily::Proportional)),
+            (egui::TextStyle::Heading, egui::FontId::new(18.0, egui::FontFamily::Proportional)),
+            (egui::TextStyle::Monospace, egui::FontId::new(12.0, egui::FontFamily::Monospace)),
+        ].iter().cloned().collect();
+        ctx.set_style(style);
     }
     
     fn load_window_icon() -> Option<egui::IconData> {
@@ -444,6 +539,7 @@ impl SettingsWindow {
 }
 
 struct SettingsApp {
+    runtime: tokio::runtime::Handle,
     settings: Arc<Mutex<Settings>>,
     command_receiver: std::sync::mpsc::Receiver<SettingsCommand>,
     visible: bool,
@@ -451,6 +547,8 @@ struct SettingsApp {
     settings_manager: Option<Arc<SettingsManager>>,
     auth_manager: Option<Arc<AuthManager>>,
     sync_service: Arc<Mutex<Option<Arc<crate::sync::SyncService>>>>,
+    quit_requested: Arc<tokio::sync::Notify>,
+    quitting: bool,
     // Auth state
     is_authenticated: bool,
     user_email: Option<String>,
@@ -469,6 +567,151 @@ struct SettingsApp {
     ws_initialized: bool,
     ws_subscription_rx: Option<std::sync::mpsc::Receiver<SubscriptionStatus>>,
     ws_usage_rx: Option<std::sync::mpsc::Receiver<UsageStats>>,
+    ws_state_rx: Option<std::sync::mpsc::Receiver<ConnectionState>>,
+    ws_connection_state: Option<ConnectionState>,
+    // Manual sync state
+    sync_in_progress: bool,
+    sync_result_rx: Option<std::sync::mpsc::Receiver<ManualSyncResult>>,
+    last_sync_result: Option<ManualSyncResult>,
+    // Sync status panel
+    sync_status: Option<SyncStatus>,
+    sync_status_rx: Option<std::sync::mpsc::Receiver<SyncStatus>>,
+    last_status_poll: Option<std::time::Instant>,
+    pending_uploads_since: Option<std::time::Instant>,
+    // Maintenance state
+    pending_maintenance: Option<MaintenanceAction>,
+    maintenance_rx: Option<std::sync::mpsc::Receiver<Result<String, String>>>,
+    maintenance_result: Option<Result<String, String>>,
+    // Save browser window, created when first opened
+    save_browser: Option<crate::ui::SaveBrowser>,
+    // Theme last passed to the egui context
+    applied_theme: Option<ThemePreference>,
+    // Section sidebar and search
+    search_query: String,
+    focus_search: bool,
+    selected_section: Option<SettingsSection>,
+    scroll_to_section: Option<SettingsSection>,
+    // Per-game sync toggles
+    games: Vec<Game>,
+    games_rx: Option<std::sync::mpsc::Receiver<Vec<Game>>>,
+    games_loaded: bool,
+    // Compression statistics
+    compression_stats: Option<CompressionStats>,
+    compression_stats_rx: Option<std::sync::mpsc::Receiver<Option<CompressionStats>>>,
+    compression_stats_loaded: bool,
+    compression_benchmark: Option<Result<CompressionBenchmark, String>>,
+    compression_benchmark_rx: Option<std::sync::mpsc::Receiver<Result<CompressionBenchmark, String>>>,
+    // Account devices
+    devices: Vec<Device>,
+    devices_rx: Option<std::sync::mpsc::Receiver<DevicesResult>>,
+    devices_loaded: bool,
+    devices_error: Option<String>,
+    current_device_id: Option<String>,
+    ws_devices_rx: Option<std::sync::mpsc::Receiver<()>>,
+    // Cloud profiles
+    profiles: Vec<String>,
+    active_profile: Option<String>,
+    profiles_rx: Option<std::sync::mpsc::Receiver<Result<ProfilesUpdate, String>>>,
+    profiles_loaded: bool,
+    profile_error: Option<String>,
+    new_profile_name: String,
+    // API connection test
+    connection_test_rx: Option<std::sync::mpsc::Receiver<Result<(), String>>>,
+    connection_test_result: Option<Result<(), String>>,
+    // Installed rclone's version and remotes
+    rclone_rx: Option<std::sync::mpsc::Receiver<Result<RcloneInfo, String>>>,
+    rclone_info: Option<Result<RcloneInfo, String>>,
+    // End-to-end encryption
+    encryption_passphrase: String,
+    encryption_confirm: String,
+    encryption_unlocked: Option<bool>,
+    encryption_rx: Option<std::sync::mpsc::Receiver<Result<bool, String>>>,
+    encryption_error: Option<String>,
+    confirm_show_key: bool,
+    exported_key: Option<String>,
+    exported_key_rx: Option<std::sync::mpsc::Receiver<Result<String, String>>>,
+    import_key_input: String,
+    // Start on boot
+    startup_error: Option<String>,
+    // Diagnostics export
+    diagnostics_path: String,
+    // Settings import/export
+    settings_file_path: String,
+    settings_import_rx: Option<std::sync::mpsc::Receiver<Result<Settings, String>>>,
+}
+
+/// Compression levels tried on one of the user's saves
+#[derive(Debug, Clone)]
+struct CompressionBenchmark {
+    sample: String,
+    original_size: u64,
+    results: Vec<(i32, CompressionStats)>,
+}
+
+/// Encryption change requested in the Cloud section
+#[derive(Debug, Clone)]
+enum EncryptionAction {
+    Check,
+    Enable(EncryptionMode, String),
+    ImportKey(String),
+    Disable,
+}
+
+/// Change to the list of cloud profiles requested in the Cloud section
+#[derive(Debug, Clone)]
+enum ProfileChange {
+    Create(String),
+    Switch(String),
+    Remove(String),
+}
+
+/// The account's devices and this device's ID, or why they couldn't be loaded
+type DevicesResult = Result<(Vec<Device>, Option<String>), String>;
+
+/// Profiles after a change, with the new account's auth state after a switch
+struct ProfilesUpdate {
+    profiles: Vec<String>,
+    active: String,
+    switched_auth: Option<(bool, Option<String>)>,
+}
+
+/// What the settings window shows about the installed rclone
+struct RcloneInfo {
+    version: String,
+    remotes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MaintenanceAction {
+    ResetSyncState,
+    ClearGameCache,
+    ReuploadAll,
+    WipeLocalAuth,
+}
+
+impl MaintenanceAction {
+    fn confirmation_text(&self) -> &'static str {
+        match self {
+            MaintenanceAction::ResetSyncState => {
+                "This discards all pending uploads and cached cloud game IDs. Your local saves are not affected, but saves that have not been uploaded yet will only be uploaded again when they next change."
+            }
+            MaintenanceAction::ClearGameCache => {
+                "This forgets cached cloud game IDs so games are re-registered on the next upload."
+            }
+            MaintenanceAction::ReuploadAll => {
+                "This uploads the latest local save of every synced game again, for example after the cloud lost data or when this computer moved to another account. Saves the cloud already has are skipped."
+            }
+            MaintenanceAction::WipeLocalAuth => {
+                "This signs out of every profile, revokes their sessions where the server can be reached and deletes all stored credentials from this computer. Cloud sync stays off until you sign in again. Your local saves are not affected."
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ManualSyncResult {
+    Success { finished_at: chrono::DateTime<chrono::Local> },
+    Error(String),
 }
 
 #[derive(Debug, Clone)]
@@ -477,6 +720,138 @@ enum AuthResult {
     Error(String),
 }
 
+/// Sections listed in the sidebar, in the order they appear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsSection {
+    General,
+    System,
+    Hotkeys,
+    Cloud,
+    Compression,
+    Games,
+    Maintenance,
+}
+
+impl SettingsSection {
+    const ALL: [SettingsSection; 7] = [
+        SettingsSection::General,
+        SettingsSection::System,
+        SettingsSection::Hotkeys,
+        SettingsSection::Cloud,
+        SettingsSection::Compression,
+        SettingsSection::Games,
+        SettingsSection::Maintenance,
+    ];
+    
+    fn title(&self) -> &'static str {
+        match self {
+            SettingsSection::General => "General",
+            SettingsSection::System => "System",
+            SettingsSection::Hotkeys => "Hotkeys",
+            SettingsSection::Cloud => "Cloud Sync",
+            SettingsSection::Compression => "Compression",
+            SettingsSection::Games => "Games & Devices",
+            SettingsSection::Maintenance => "Maintenance",
+        }
+    }
+    
+    /// Labels of the settings in this section, searched by the filter box
+    fn labels(&self) -> &'static [&'static str] {
+        match self {
+            SettingsSection::General => &[
+                "Enable automatic saves",
+                "Save interval (minutes):",
+                "Emulator check interval (seconds):",
+                "Max saves per game:",
+                "Max save states per game:",
+                "Ignore files matching",
+                "Exclude from backup",
+                "Watch these emulators",
+                "Extra PCSX2 memory card folders",
+            ],
+            SettingsSection::System => &[
+                "Start Retrosave on system boot",
+                "Minimize to system tray",
+                "Show notifications",
+                "Verbose logging (for bug reports)",
+                "Theme:",
+            ],
+            SettingsSection::Hotkeys => &["Enable global hotkeys", "Save Now hotkey:"],
+            SettingsSection::Cloud => &[
+                "Backend:",
+                "Sync folder:",
+                "Sync saves through this folder",
+                "rclone remote:",
+                "Sync saves through this remote",
+                "Profile:",
+                "Test connection",
+                "Proxy URL:",
+                "Custom CA certificate:",
+                "Accept invalid certificates",
+                "Enable cloud sync",
+                "Sign in",
+                "Sync Now",
+                "Logout",
+                "Subscription",
+                "Usage",
+                "Automatically sync saves",
+                "Max transfer speed (KB/s):",
+                "When saves conflict:",
+                "Only sync after closing the game",
+                "Restore deleted saves from the cloud",
+                "Failure webhook URL:",
+                "End-to-end encryption",
+            ],
+            SettingsSection::Compression => &["Enable save compression", "Compression level:", "Benchmark compression"],
+            SettingsSection::Games => &["Browse Saves", "Only back up these games", "Games", "Devices"],
+            SettingsSection::Maintenance => &[
+                "Compact the local database weekly",
+                "Reset sync state",
+                "Clear game cache",
+                "Re-upload all saves",
+                "Sign out everywhere",
+                "Export settings",
+                "Import settings",
+                "Export diagnostics",
+            ],
+        }
+    }
+}
+
+/// Decides which settings are shown for the text in the search box
+struct SettingsFilter {
+    query: String,
+}
+
+impl SettingsFilter {
+    fn new(query: &str) -> Self {
+        Self { query: query.trim().to_lowercase() }
+    }
+    
+    fn matches(&self, text: &str) -> bool {
+        self.query.is_empty() || text.to_lowercase().contains(&self.query)
+    }
+    
+    /// Whether anything in the section matches
+    fn shows_section(&self, section: SettingsSection) -> bool {
+        self.matches(section.title()) || section.labels().iter().any(|label| self.matches(label))
+    }
+    
+    /// Whether a single setting matches. Searching for a section's title shows all of it.
+    fn shows(&self, section: SettingsSection, label: &str) -> bool {
+        self.matches(section.title()) || self.matches(label)
+    }
+}
+
+/// Section heading that scrolls into view when picked in the sidebar
+fn section_heading(ui: &mut egui::Ui, section: SettingsSection, scroll_to: &mut Option<SettingsSection>) {
+    let response = ui.heading(section.title());
+    if *scroll_to == Some(section) {
+        response.scroll_to_me(Some(egui::Align::TOP));
+        *scroll_to = None;
+    }
+}
+
 impl eframe::App for SettingsApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Initialize WebSocket for real-time updates if authenticated
@@ -497,10 +872,23 @@ impl eframe::App for SettingsApp {
         if let Some(ref rx) = self.ws_usage_rx {
             if let Ok(stats) = rx.try_recv() {
                 info!("Received real-time usage update in UI");
+                if let Some(sync_service) = self.sync_service.lock().unwrap().clone() {
+                    let stats = stats.clone();
+                    self.runtime.spawn(async move {
+                        sync_service.set_usage_stats(stats).await;
+                    });
+                }
                 self.usage_stats = Some(stats);
             }
         }
         
+        // Check for WebSocket connection state changes
+        if let Some(ref rx) = self.ws_state_rx {
+            while let Ok(state) = rx.try_recv() {
+                self.ws_connection_state = Some(state);
+            }
+        }
+        
         // Check if window just gained focus after being in background (user might have upgraded in browser)
         static mut LAST_FOCUS_STATE: bool = false;
         let is_focused = ctx.input(|i| i.focused);
@@ -532,8 +920,11 @@ impl eframe::App for SettingsApp {
         
         // Check for commands
         if let Ok(cmd) = self.command_receiver.try_recv() {
+            if matches!(cmd, SettingsCommand::ShowSaveBrowser) {
+                self.open_save_browser(ctx);
+            }
             match cmd {
-                SettingsCommand::Show => {
+                SettingsCommand::Show | SettingsCommand::ShowSaveBrowser => {
                     info!("Settings window received show command");
                     self.visible = true;
                     if self.first_show {
@@ -552,17 +943,14 @@ impl eframe::App for SettingsApp {
                         let auth_manager_clone = auth_manager.clone();
                         let (tx, rx) = std::sync::mpsc::channel();
                         
-                        std::thread::spawn(move || {
-                            let rt = tokio::runtime::Runtime::new().unwrap();
-                            rt.block_on(async {
-                                let is_auth = auth_manager_clone.is_authenticated().await;
-                                if is_auth {
-                                    let user = auth_manager_clone.get_user_info().await;
-                                    let _ = tx.send((true, user.map(|u| u.email)));
-                                } else {
-                                    let _ = tx.send((false, None));
-                                }
-                            });
+                        self.runtime.spawn(async move {
+                            let is_auth = auth_manager_clone.is_authenticated().await;
+                            if is_auth {
+                                let user = auth_manager_clone.get_user_info().await;
+                                let _ = tx.send((true, user.map(|u| u.email)));
+                            } else {
+                                let _ = tx.send((false, None));
+                            }
                         });
                         
                         // Store the receiver to check later
@@ -575,6 +963,11 @@ impl eframe::App for SettingsApp {
                     self.visible = false;
                     ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
                 }
+                SettingsCommand::Quit => {
+                    info!("Settings window received quit command");
+                    self.quitting = true;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
             }
         }
         
@@ -609,6 +1002,180 @@ impl eframe::App for SettingsApp {
             }
         }
         
+        // Refresh the sync status panel
+        if self.visible && self.is_authenticated {
+            self.poll_sync_status(ctx);
+        }
+        if let Some(ref rx) = self.sync_status_rx {
+            if let Ok(status) = rx.try_recv() {
+                if status.pending_uploads == 0 {
+                    self.pending_uploads_since = None;
+                } else if self.pending_uploads_since.is_none() {
+                    self.pending_uploads_since = Some(std::time::Instant::now());
+                }
+                self.sync_status = Some(status);
+                self.sync_status_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for maintenance results
+        if let Some(ref rx) = self.maintenance_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.maintenance_result = Some(result);
+                self.maintenance_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for imported settings
+        if let Some(ref rx) = self.settings_import_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.maintenance_result = Some(result.map(|settings| {
+                    *self.settings.lock().unwrap() = settings;
+                    format!("Imported settings from {}", self.settings_file_path.trim())
+                }));
+                self.settings_import_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for loaded device list
+        if let Some(ref rx) = self.devices_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok((devices, current_device_id)) => {
+                        self.devices = devices;
+                        self.current_device_id = current_device_id;
+                        self.devices_error = None;
+                    }
+                    Err(e) => self.devices_error = Some(e),
+                }
+                self.devices_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for the API connection test result
+        if let Some(ref rx) = self.connection_test_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.connection_test_result = Some(result);
+                self.connection_test_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for rclone detection
+        if let Some(ref rx) = self.rclone_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.rclone_info = Some(result);
+                self.rclone_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for the result of an encryption change
+        if let Some(ref rx) = self.encryption_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(unlocked) => {
+                        // Turned on or unlocked
+                        if unlocked {
+                            self.settings.lock().unwrap().encryption_enabled = true;
+                        }
+                        self.encryption_unlocked = Some(unlocked);
+                        self.encryption_error = None;
+                    }
+                    Err(e) => self.encryption_error = Some(e),
+                }
+                self.encryption_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for the exported encryption key
+        if let Some(ref rx) = self.exported_key_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(key) => self.exported_key = Some(key),
+                    Err(e) => self.encryption_error = Some(e),
+                }
+                self.exported_key_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for profile changes
+        if let Some(ref rx) = self.profiles_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.profiles_rx = None;
+                match result {
+                    Ok(update) => {
+                        self.profiles = update.profiles;
+                        self.active_profile = Some(update.active);
+                        self.profile_error = None;
+                        if let Some((is_authenticated, email)) = update.switched_auth {
+                            self.disconnect_ws_client();
+                            self.clear_account_state();
+                            self.ws_initialized = false;
+                            self.is_authenticated = is_authenticated;
+                            self.user_email = email;
+                            if is_authenticated {
+                                self.fetch_subscription_status(ctx);
+                            }
+                        }
+                    }
+                    Err(e) => self.profile_error = Some(e),
+                }
+                ctx.request_repaint();
+            }
+        }
+        
+        // Reload the device list when devices are added or removed elsewhere
+        if let Some(ref rx) = self.ws_devices_rx {
+            if rx.try_recv().is_ok() {
+                while rx.try_recv().is_ok() {}
+                self.devices_loaded = false;
+            }
+        }
+        
+        // Check for loaded game list
+        if let Some(ref rx) = self.games_rx {
+            if let Ok(games) = rx.try_recv() {
+                self.games = games;
+                self.games_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for compression statistics
+        if let Some(ref rx) = self.compression_stats_rx {
+            if let Ok(stats) = rx.try_recv() {
+                self.compression_stats = stats;
+                self.compression_stats_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for compression benchmark results
+        if let Some(ref rx) = self.compression_benchmark_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.compression_benchmark = Some(result);
+                self.compression_benchmark_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
+        // Check for manual sync results
+        if let Some(ref rx) = self.sync_result_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.sync_in_progress = false;
+                self.last_sync_result = Some(result);
+                self.sync_result_rx = None;
+                ctx.request_repaint();
+            }
+        }
+        
         // Check for auth results
         if let Some(ref rx) = self.auth_result_rx {
             if let Ok(result) = rx.try_recv() {
@@ -660,17 +1227,14 @@ impl eframe::App for SettingsApp {
                     let auth_manager_clone = auth_manager.clone();
                     let (tx, rx) = std::sync::mpsc::channel();
                     
-                    std::thread::spawn(move || {
-                        let rt = tokio::runtime::Runtime::new().unwrap();
-                        rt.block_on(async {
-                            let is_auth = auth_manager_clone.is_authenticated().await;
-                            if is_auth {
-                                let user = auth_manager_clone.get_user_info().await;
-                                let _ = tx.send((true, user.map(|u| u.email)));
-                            } else {
-                                let _ = tx.send((false, None));
-                            }
-                        });
+                    self.runtime.spawn(async move {
+                        let is_auth = auth_manager_clone.is_authenticated().await;
+                        if is_auth {
+                            let user = auth_manager_clone.get_user_info().await;
+                            let _ = tx.send((true, user.map(|u| u.email)));
+                        } else {
+                            let _ = tx.send((false, None));
+                        }
                     });
                     
                     // Check immediately without blocking
@@ -704,16 +1268,82 @@ impl eframe::App for SettingsApp {
             return;
         }
         
-        // Action flag to avoid borrow checker issues
+        // Follow theme changes made in the settings below
+        let theme_preference = self.settings.lock().unwrap().theme;
+        if self.applied_theme != Some(theme_preference) {
+            crate::ui::theme::apply(ctx, theme_preference);
+            self.applied_theme = Some(theme_preference);
+        }
+        let theme = Theme::current(ctx);
+        
+        // Action flags to avoid borrow checker issues
         let mut should_logout = false;
+        let mut profile_change = None;
+        
+        // Ctrl+F jumps to the search box, Escape clears it
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)) {
+            self.focus_search = true;
+        }
+        
+        egui::SidePanel::left("settings_sections")
+            .resizable(false)
+            .exact_width(150.0)
+            .show(ctx, |ui| {
+                ui.add_space(6.0);
+                let search = ui.add(egui::TextEdit::singleline(&mut self.search_query)
+                    .hint_text("Search (Ctrl+F)"));
+                if self.focus_search {
+                    search.request_focus();
+                    self.focus_search = false;
+                }
+                if (search.has_focus() || search.lost_focus()) && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    self.search_query.clear();
+                }
+                ui.separator();
+                
+                let filter = SettingsFilter::new(&self.search_query);
+                for section in SettingsSection::ALL {
+                    if !filter.shows_section(section) {
+                        continue;
+                    }
+                    if ui.selectable_label(self.selected_section == Some(section), section.title()).clicked() {
+                        self.selected_section = Some(section);
+                        self.scroll_to_section = Some(section);
+                    }
+                }
+            });
+        
+        let filter = SettingsFilter::new(&self.search_query);
         
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("Retrosave Settings");
             ui.separator();
             
+            // Keep quota problems visible wherever the window is scrolled to
+            if let Some(ref usage) = self.usage_stats {
+                if self.is_authenticated && usage.is_near_limit() {
+                    let (color, text) = if usage.is_over_limit() {
+                        (theme.error,
+                         "⚠ Cloud storage limit reached. New saves are only kept locally until you free up space or upgrade your plan.".to_string())
+                    } else {
+                        (theme.warning,
+                         format!("⚠ Cloud storage almost full: {:.0}% of saves and {:.0}% of storage used.",
+                            usage.saves_percentage(), usage.storage_percentage()))
+                    };
+                    egui::Frame::none()
+                        .fill(theme.warning_fill)
+                        .inner_margin(egui::Margin::same(6.0))
+                        .rounding(egui::Rounding::same(4.0))
+                        .show(ui, |ui| {
+                            ui.colored_label(color, text);
+                        });
+                    ui.add_space(5.0);
+                }
+            }
+            
             // Wrap everything in a scroll area
             egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.set_min_width(550.0);
+                ui.set_min_width(450.0);
                 
                 // Settings sections with proper locking
                 let mut cloud_sync_enabled;
@@ -721,344 +1351,1140 @@ impl eframe::App for SettingsApp {
                     let mut settings = self.settings.lock().unwrap();
             
             // General Settings
-            ui.label("General Settings");
-            ui.checkbox(&mut settings.auto_save_enabled, "Enable automatic saves");
-            
-            ui.horizontal(|ui| {
-                ui.label("Save interval (minutes):");
-                ui.add(egui::Slider::new(&mut settings.save_interval_minutes, 1..=60));
-            });
-            
-            ui.horizontal(|ui| {
-                ui.label("Max saves per game:");
-                ui.add(egui::Slider::new(&mut settings.max_saves_per_game, 1..=20));
-            });
-            
-            ui.separator();
-            
-            // System Settings
-            ui.label("System Settings");
-            ui.checkbox(&mut settings.start_on_boot, "Start Retrosave on system boot");
-            ui.checkbox(&mut settings.minimize_to_tray, "Minimize to system tray");
-            ui.checkbox(&mut settings.show_notifications, "Show notifications");
-            
-            ui.separator();
-            
-            // Hotkey Settings
-            ui.label("Hotkey Settings");
-            ui.checkbox(&mut settings.hotkey_enabled, "Enable global hotkeys");
-            
-            if settings.hotkey_enabled {
-                ui.horizontal(|ui| {
-                    ui.label("Save Now hotkey:");
-                    
-                    let mut hotkey_text = settings.save_hotkey.clone().unwrap_or_else(|| "Not set".to_string());
-                    let response = ui.text_edit_singleline(&mut hotkey_text);
-                    
-                    if response.changed() {
-                        // Update the hotkey when text changes
-                        if hotkey_text.is_empty() || hotkey_text == "Not set" {
-                            settings.save_hotkey = None;
-                        } else {
-                            settings.save_hotkey = Some(hotkey_text);
-                        }
+            if filter.shows_section(SettingsSection::General) {
+                section_heading(ui, SettingsSection::General, &mut self.scroll_to_section);
+                if filter.shows(SettingsSection::General, "Enable automatic saves") {
+                    ui.checkbox(&mut settings.auto_save_enabled, "Enable automatic saves");
+                }
+                
+                if filter.shows(SettingsSection::General, "Save interval (minutes):") {
+                    ui.horizontal(|ui| {
+                        ui.label("Save interval (minutes):");
+                        ui.add(egui::Slider::new(&mut settings.save_interval_minutes, 1..=60));
+                    });
+                }
+                
+                if filter.shows(SettingsSection::General, "Emulator check interval (seconds):") {
+                    ui.horizontal(|ui| {
+                        ui.label("Emulator check interval (seconds):");
+                        ui.add(egui::Slider::new(&mut settings.process_scan_interval_secs, 1..=60))
+                            .on_hover_text("How often Retrosave looks for running emulators and new saves. Longer saves battery, shorter picks up changes sooner.");
+                    });
+                }
+                
+                if filter.shows(SettingsSection::General, "Max saves per game:") {
+                    ui.horizontal(|ui| {
+                        ui.label("Max saves per game:");
+                        ui.add(egui::Slider::new(&mut settings.max_saves_per_game, 1..=20))
+                            .on_hover_text("Versions kept of memory cards and other in-game saves");
+                    });
+                }
+                
+                if filter.shows(SettingsSection::General, "Max save states per game:") {
+                    ui.horizontal(|ui| {
+                        ui.label("Max save states per game:");
+                        ui.add(egui::Slider::new(&mut settings.max_save_states_per_game, 1..=20))
+                            .on_hover_text("Versions kept of emulator save-states. Pinned versions are always kept.");
+                    });
+                }
+                
+                if filter.shows(SettingsSection::General, "Ignore files matching") {
+                    ui.label("Ignore files matching (one pattern per line, * and ? wildcards):");
+                    let mut patterns_text = settings.watcher_ignore_patterns.join("\n");
+                    if ui.add(egui::TextEdit::multiline(&mut patterns_text)
+                        .desired_rows(3)
+                        .hint_text("e.g. *.backup")).changed()
+                    {
+                        settings.watcher_ignore_patterns = patterns_text.split('\n').map(|p| p.to_string()).collect();
                     }
-                    
-                    if ui.button("Clear").clicked() {
-                        settings.save_hotkey = None;
+                    ui.label(egui::RichText::new(format!("Always ignored: {}", crate::storage::watcher::DEFAULT_IGNORE_PATTERNS.join(", ")))
+                        .color(theme.muted)
+                        .size(11.0));
+                }
+                
+                if filter.shows(SettingsSection::General, "Exclude from backup") {
+                    ui.label("Exclude from backup:")
+                        .on_hover_text("Files or folders inside an emulator's save directory, e.g. inis or *.png. A folder excludes everything in it.");
+                    let mut remove = None;
+                    for (i, exclusion) in settings.backup_exclusions.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(exclusion)
+                                .hint_text("e.g. inis or snaps/*.png"));
+                            if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                remove = Some(i);
+                            }
+                        });
                     }
-                });
+                    if let Some(i) = remove {
+                        settings.backup_exclusions.remove(i);
+                    }
+                    if ui.button("➕ Add exclusion").clicked() {
+                        settings.backup_exclusions.push(String::new());
+                    }
+                    ui.label(egui::RichText::new("Relative to the save directory. Changes apply the next time the emulator starts.")
+                        .color(theme.muted)
+                        .size(11.0));
+                }
+                
+                if filter.shows(SettingsSection::General, "Watch these emulators") {
+                    ui.label("Watch these emulators:");
+                    ui.horizontal_wrapped(|ui| {
+                        for emulator in crate::monitor::EMULATORS {
+                            let mut enabled = !settings.disabled_emulators.iter().any(|d| d == emulator);
+                            if ui.checkbox(&mut enabled, *emulator).changed() {
+                                if enabled {
+                                    settings.disabled_emulators.retain(|d| d != emulator);
+                                } else {
+                                    settings.disabled_emulators.push(emulator.to_string());
+                                }
+                            }
+                        }
+                    });
+                }
+                
+                if filter.shows(SettingsSection::General, "Extra PCSX2 memory card folders") {
+                    ui.label("Extra PCSX2 memory card folders (one path per line):");
+                    let mut dirs_text = settings.pcsx2_extra_memcard_dirs.join("\n");
+                    if ui.add(egui::TextEdit::multiline(&mut dirs_text)
+                        .desired_rows(2)
+                        .hint_text("e.g. D:\\Games\\PS2\\memcards")).changed()
+                    {
+                        settings.pcsx2_extra_memcard_dirs = dirs_text.split('\n').map(|d| d.to_string()).collect();
+                    }
+                    // Reading PCSX2's config every frame would hit the disk constantly
+                    static DETECTED: once_cell::sync::Lazy<Vec<std::path::PathBuf>> =
+                        once_cell::sync::Lazy::new(crate::monitor::process::get_pcsx2_memcard_directories);
+                    let detected = &*DETECTED;
+                    let detected_text = if detected.is_empty() {
+                        "none".to_string()
+                    } else {
+                        detected.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", ")
+                    };
+                    ui.label(egui::RichText::new(format!("Detected from PCSX2 config: {}. Changes apply the next time PCSX2 starts.", detected_text))
+                        .color(theme.muted)
+                        .size(11.0));
+                }
                 
-                ui.label("Format: Ctrl+Shift+S, Alt+F5, etc.");
-                ui.label("The hotkey will trigger a manual save while in-game.");
+                ui.separator();
             }
             
-            ui.separator();
+            // System Settings
+            if filter.shows_section(SettingsSection::System) {
+                section_heading(ui, SettingsSection::System, &mut self.scroll_to_section);
+                if filter.shows(SettingsSection::System, "Start Retrosave on system boot") {
+                    if ui.checkbox(&mut settings.start_on_boot, "Start Retrosave on system boot").changed() {
+                        match crate::startup::set_start_on_boot(settings.start_on_boot) {
+                            Ok(()) => self.startup_error = None,
+                            Err(e) => {
+                                error!("Failed to update start on boot: {:#}", e);
+                                // Keep the checkbox in line with what is actually registered
+                                settings.start_on_boot = !settings.start_on_boot;
+                                self.startup_error = Some(format!("{:#}", e));
+                            }
+                        }
+                    }
+                    if let Some(ref err) = self.startup_error {
+                        ui.colored_label(theme.error, format!("⚠ {}", err));
+                    }
+                }
+                if filter.shows(SettingsSection::System, "Minimize to system tray") {
+                    ui.checkbox(&mut settings.minimize_to_tray, "Minimize to system tray");
+                }
+                if filter.shows(SettingsSection::System, "Show notifications") {
+                    ui.checkbox(&mut settings.show_notifications, "Show notifications");
+                }
+                if filter.shows(SettingsSection::System, "Verbose logging (for bug reports)")
+                    && ui.checkbox(&mut settings.verbose_logging, "Verbose logging (for bug reports)").changed()
+                {
+                    crate::logging::set_verbose(settings.verbose_logging);
+                }
+                if filter.shows(SettingsSection::System, "Theme:") {
+                    ui.horizontal(|ui| {
+                        ui.label("Theme:");
+                        egui::ComboBox::from_id_salt("theme")
+                            .selected_text(settings.theme.label())
+                            .show_ui(ui, |ui| {
+                                for preference in ThemePreference::ALL {
+                                    ui.selectable_value(&mut settings.theme, preference, preference.label());
+                                }
+                            });
+                    });
+                }
+                
+                ui.separator();
+            }
             
-            // Cloud Settings
-            ui.heading("Cloud Sync");
+            // Hotkey Settings
+            if filter.shows_section(SettingsSection::Hotkeys) {
+                section_heading(ui, SettingsSection::Hotkeys, &mut self.scroll_to_section);
+                ui.checkbox(&mut settings.hotkey_enabled, "Enable global hotkeys");
+                
+                if settings.hotkey_enabled {
+                    ui.horizontal(|ui| {
+                        ui.label("Save Now hotkey:");
+                        
+                        let mut hotkey_text = settings.save_hotkey.clone().unwrap_or_else(|| "Not set".to_string());
+                        let response = ui.text_edit_singleline(&mut hotkey_text);
+                        
+                        if response.changed() {
+                            // Update the hotkey when text changes
+                            if hotkey_text.is_empty() || hotkey_text == "Not set" {
+                                settings.save_hotkey = None;
+                            } else {
+                                settings.save_hotkey = Some(hotkey_text);
+                            }
+                        }
+                        
+                        if ui.button("Clear").clicked() {
+                            settings.save_hotkey = None;
+                        }
+                    });
+                    
+                    ui.label("Format: Ctrl+Shift+S, Alt+F5, etc.");
+                    ui.label("The hotkey will trigger a manual save while in-game.");
+                }
+                
+                ui.separator();
+            }
             
             cloud_sync_enabled = settings.cloud_sync_enabled;
             } // Drop settings lock
             
-            // Always show auth status
-            if self.is_authenticated {
-                egui::Frame::none()
-                    .fill(egui::Color32::from_rgb(40, 45, 40))
-                    .rounding(egui::Rounding::same(5.0))
-                    .inner_margin(egui::Margin::same(10.0))
-                    .show(ui, |ui| {
-                        ui.vertical(|ui| {
-                            // First row: Status
+            // Cloud Settings
+            if filter.shows_section(SettingsSection::Cloud) {
+                section_heading(ui, SettingsSection::Cloud, &mut self.scroll_to_section);
+                
+                // A NAS share or Syncthing folder instead of the Retrosave server, picked at startup
+                if filter.shows(SettingsSection::Cloud, "Backend:")
+                    || filter.shows(SettingsSection::Cloud, "Sync folder:")
+                    || filter.shows(SettingsSection::Cloud, "rclone remote:")
+                {
+                    let mut settings = self.settings.lock().unwrap();
+                    ui.horizontal(|ui| {
+                        ui.label("Backend:");
+                        egui::ComboBox::from_id_salt("sync_backend")
+                            .selected_text(settings.sync_backend.label())
+                            .show_ui(ui, |ui| {
+                                for backend in SyncBackend::ALL {
+                                    ui.selectable_value(&mut settings.sync_backend, backend, backend.label());
+                                }
+                            });
+                    });
+                    if settings.sync_backend == SyncBackend::LocalFolder {
+                        ui.horizontal(|ui| {
+                            ui.label("Sync folder:");
+                            let mut folder = settings.local_sync_folder.clone().unwrap_or_default();
+                            if ui.add(egui::TextEdit::singleline(&mut folder)
+                                .hint_text("NAS share or Syncthing folder")
+                                .desired_width(250.0))
+                                .changed()
+                            {
+                                let folder = folder.trim();
+                                settings.local_sync_folder = (!folder.is_empty()).then(|| folder.to_string());
+                            }
+                        });
+                        match settings.local_sync_folder {
+                            None => {
+                                ui.colored_label(theme.warning, "⚠ Saves sync to the cloud until a folder is chosen");
+                            }
+                            Some(ref folder) if !std::path::Path::new(folder).is_dir() => {
+                                ui.colored_label(theme.warning, "⚠ Folder not found, saves are queued until it's available");
+                            }
+                            Some(_) => {
+                                ui.label(egui::RichText::new("Every device syncing through this folder shares its saves, no account needed")
+                                    .color(theme.muted)
+                                    .size(11.0));
+                            }
+                        }
+                    }
+                    let detect_rclone = settings.sync_backend == SyncBackend::Rclone
+                        && self.rclone_info.is_none()
+                        && self.rclone_rx.is_none();
+                    if settings.sync_backend == SyncBackend::Rclone {
+                        ui.horizontal(|ui| {
+                            ui.label("rclone remote:");
+                            let mut remote = settings.rclone_remote.clone().unwrap_or_default();
+                            if ui.add(egui::TextEdit::singleline(&mut remote)
+                                .hint_text("gdrive:retrosave")
+                                .desired_width(250.0))
+                                .on_hover_text("A remote set up with `rclone config`, optionally followed by a path")
+                                .changed()
+                            {
+                                let remote = remote.trim();
+                                settings.rclone_remote = (!remote.is_empty()).then(|| remote.to_string());
+                            }
+                        });
+                        match self.rclone_info {
+                            Some(Ok(ref info)) => {
+                                ui.label(egui::RichText::new(&info.version).color(theme.muted).size(11.0));
+                                if info.remotes.is_empty() {
+                                    ui.colored_label(theme.warning, "⚠ No remotes yet, set one up with `rclone config`");
+                                }
+                                ui.horizontal_wrapped(|ui| {
+                                    for remote in &info.remotes {
+                                        if ui.button(remote).clicked() {
+                                            settings.rclone_remote = Some(format!("{}retrosave", remote));
+                                        }
+                                    }
+                                });
+                            }
+                            Some(Err(ref e)) => {
+                                ui.colored_label(theme.warning, format!("⚠ {}", e));
+                            }
+                            None => {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("Looking for rclone...");
+                                });
+                            }
+                        }
+                        match settings.rclone_remote {
+                            None => {
+                                ui.colored_label(theme.warning, "⚠ Saves sync to the cloud until a remote is chosen");
+                            }
+                            Some(ref remote) if !crate::sync::rclone::is_valid_remote(remote) => {
+                                ui.colored_label(theme.warning, "⚠ A remote can't start with -, saves sync to the cloud until it's fixed");
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                    ui.label(egui::RichText::new("Changes apply after restarting Retrosave").color(theme.muted).size(11.0));
+                    ui.add_space(5.0);
+                    drop(settings);
+                    if detect_rclone {
+                        self.detect_rclone(ui.ctx());
+                    }
+                }
+                // Syncing through the user's own storage needs no account
+                let own_storage = {
+                    let settings = self.settings.lock().unwrap();
+                    settings.sync_folder().is_some() || settings.rclone_target().is_some()
+                };
+                
+                // Each profile has its own account, e.g. for family members sharing this computer
+                if filter.shows(SettingsSection::Cloud, "Profile:") {
+                    if !self.profiles_loaded {
+                        self.load_profiles(None, ui.ctx());
+                    }
+                    let busy = self.profiles_rx.is_some();
+                    let active = self.active_profile.clone().unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+                    ui.horizontal(|ui| {
+                        ui.label("Profile:");
+                        ui.add_enabled_ui(!busy, |ui| {
+                            egui::ComboBox::from_id_salt("cloud_profile")
+                                .selected_text(active.as_str())
+                                .show_ui(ui, |ui| {
+                                    for profile in &self.profiles {
+                                        if ui.selectable_label(*profile == active, profile.as_str()).clicked() && *profile != active {
+                                            profile_change = Some(ProfileChange::Switch(profile.clone()));
+                                        }
+                                    }
+                                });
+                        });
+                        if busy {
+                            ui.spinner();
+                        }
+                    });
+                    
+                    ui.collapsing("Manage profiles", |ui| {
+                        for profile in &self.profiles {
                             ui.horizontal(|ui| {
-                                ui.label(egui::RichText::new("✅").size(20.0));
-                                ui.label(egui::RichText::new("Connected").color(egui::Color32::from_rgb(100, 255, 100)).size(14.0));
-                                if let Some(ref email) = self.user_email {
-                                    ui.label(egui::RichText::new(format!(" - {}", email)).size(12.0));
+                                ui.label(profile.as_str());
+                                if *profile == active {
+                                    ui.label(egui::RichText::new("(active)").color(theme.muted));
+                                } else if profile != DEFAULT_PROFILE
+                                    && ui.add_enabled(!busy, egui::Button::new("Remove"))
+                                        .on_hover_text("Forget this profile and its login on this computer")
+                                        .clicked()
+                                {
+                                    profile_change = Some(ProfileChange::Remove(profile.clone()));
                                 }
                             });
-                            
-                            // Second row: Buttons
-                            ui.add_space(5.0);
+                        }
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.new_profile_name)
+                                .hint_text("New profile name")
+                                .desired_width(150.0));
+                            let name = self.new_profile_name.trim().to_string();
+                            if ui.add_enabled(!busy && is_valid_profile_name(&name), egui::Button::new("➕ Add"))
+                                .on_disabled_hover_text("Letters, digits, - and _ only")
+                                .clicked()
+                            {
+                                profile_change = Some(ProfileChange::Create(name));
+                                self.new_profile_name.clear();
+                            }
+                        });
+                    });
+                    
+                    if let Some(ref error) = self.profile_error {
+                        ui.colored_label(theme.error, format!("⚠ {}", error));
+                    }
+                    ui.add_space(5.0);
+                }
+                
+                if filter.shows(SettingsSection::Cloud, "Test connection") {
+                    let api_url = self.settings.lock().unwrap().cloud_api_url.clone();
+                    ui.horizontal(|ui| {
+                        ui.label("API server:");
+                        ui.label(egui::RichText::new(api_url).monospace().color(theme.muted));
+                        let testing = self.connection_test_rx.is_some();
+                        if ui.add_enabled(!testing && self.api_client.is_some(), egui::Button::new("Test connection")).clicked() {
+                            self.test_connection(ui.ctx());
+                        }
+                        if testing {
+                            ui.spinner();
+                        }
+                    });
+                    match self.connection_test_result {
+                        Some(Ok(())) => {
+                            ui.colored_label(theme.success, "✓ Connected to the API server");
+                        }
+                        Some(Err(ref e)) => {
+                            ui.colored_label(theme.error, format!("✗ {}", e));
+                        }
+                        None => {}
+                    }
+                    ui.add_space(5.0);
+                }
+                
+                // Proxy and TLS options for corporate networks and self-hosted servers, read once at startup
+                if filter.shows(SettingsSection::Cloud, "Proxy URL:")
+                    || filter.shows(SettingsSection::Cloud, "Custom CA certificate:")
+                    || filter.shows(SettingsSection::Cloud, "Accept invalid certificates")
+                {
+                    let mut settings = self.settings.lock().unwrap();
+                    egui::CollapsingHeader::new("Network")
+                        .default_open(settings.proxy_url.is_some() || settings.custom_ca_cert_path.is_some() || settings.accept_invalid_certs)
+                        .show(ui, |ui| {
                             ui.horizontal(|ui| {
-                                if ui.button("🔄 Sync Now").clicked() {
-                                    info!("Manual sync requested - triggering sync");
-                                    let sync_service_guard = self.sync_service.lock().unwrap();
-                                    if let Some(ref sync_service) = *sync_service_guard {
-                                        let sync_service = sync_service.clone();
-                                        drop(sync_service_guard);
-                                        std::thread::spawn(move || {
-                                            let rt = tokio::runtime::Runtime::new().unwrap();
-                                            rt.block_on(async {
-                                                if let Err(e) = sync_service.trigger_sync().await {
-                                                    error!("Failed to trigger sync: {}", e);
-                                                } else {
-                                                    info!("Sync triggered successfully");
-                                                }
-                                            });
-                                        });
-                                    } else {
-                                        warn!("Sync service not available");
-                                    }
+                                ui.label("Proxy URL:");
+                                let mut proxy_url = settings.proxy_url.clone().unwrap_or_default();
+                                if ui.add(egui::TextEdit::singleline(&mut proxy_url)
+                                    .hint_text("http://proxy:3128 or socks5://proxy:1080")
+                                    .desired_width(250.0))
+                                    .changed()
+                                {
+                                    let proxy_url = proxy_url.trim();
+                                    settings.proxy_url = (!proxy_url.is_empty()).then(|| proxy_url.to_string());
                                 }
-                                if ui.button("📤 Logout").clicked() {
-                                    should_logout = true;
+                            });
+                            if let Some(ref proxy_url) = settings.proxy_url {
+                                if url::Url::parse(proxy_url).is_err() {
+                                    ui.colored_label(theme.warning, "⚠ Not a valid URL");
                                 }
-                                if ui.button("🔃 Refresh Status").clicked() {
-                                    self.fetch_subscription_status(ui.ctx());
+                            } else {
+                                ui.label(egui::RichText::new("Empty uses the HTTPS_PROXY, HTTP_PROXY and ALL_PROXY environment variables")
+                                    .color(theme.muted)
+                                    .size(11.0));
+                            }
+                            
+                            ui.horizontal(|ui| {
+                                ui.label("Custom CA certificate:");
+                                let mut path = settings.custom_ca_cert_path.clone().unwrap_or_default();
+                                if ui.add(egui::TextEdit::singleline(&mut path)
+                                    .hint_text("Path to a PEM file")
+                                    .desired_width(250.0))
+                                    .changed()
+                                {
+                                    let path = path.trim();
+                                    settings.custom_ca_cert_path = (!path.is_empty()).then(|| path.to_string());
                                 }
                             });
+                            if let Some(ref path) = settings.custom_ca_cert_path {
+                                if !std::path::Path::new(path).is_file() {
+                                    ui.colored_label(theme.warning, "⚠ File not found");
+                                }
+                            }
+                            
+                            ui.checkbox(&mut settings.accept_invalid_certs, "Accept invalid certificates (insecure, development only)");
+                            if settings.accept_invalid_certs {
+                                ui.colored_label(theme.error, "⚠ Connections are not protected against interception");
+                            }
+                            ui.label(egui::RichText::new("Changes apply after restarting Retrosave").color(theme.muted).size(11.0));
                         });
-                    });
-                ui.add_space(10.0);
+                    ui.add_space(5.0);
+                }
                 
-                // Show subscription status
-                if let Some(ref subscription) = self.subscription_status {
+                // Always show auth status
+                if own_storage {
+                    {
+                        let mut settings = self.settings.lock().unwrap();
+                        let label = if settings.sync_backend == SyncBackend::Rclone {
+                            "Sync saves through this remote"
+                        } else {
+                            "Sync saves through this folder"
+                        };
+                        ui.checkbox(&mut settings.cloud_sync_enabled, label);
+                        cloud_sync_enabled = settings.cloud_sync_enabled;
+                    }
+                    if cloud_sync_enabled {
+                        let sync_button_text = if self.sync_in_progress {
+                            "⏳ Syncing..."
+                        } else {
+                            "🔄 Sync Now"
+                        };
+                        if ui.add_enabled(!self.sync_in_progress, egui::Button::new(sync_button_text)).clicked() {
+                            self.start_manual_sync(ui.ctx());
+                        }
+                        match self.last_sync_result {
+                            Some(ManualSyncResult::Success { finished_at }) => {
+                                ui.label(egui::RichText::new(format!("Last sync succeeded at {}", finished_at.format("%H:%M:%S")))
+                                    .color(theme.muted)
+                                    .size(11.0));
+                            }
+                            Some(ManualSyncResult::Error(ref err)) => {
+                                ui.colored_label(theme.error, format!("⚠ Sync failed: {}", err));
+                            }
+                            None => {}
+                        }
+                    }
+                    ui.add_space(10.0);
+                } else if self.is_authenticated {
                     egui::Frame::none()
-                        .fill(egui::Color32::from_rgb(35, 35, 45))
+                        .fill(theme.panel)
                         .rounding(egui::Rounding::same(5.0))
                         .inner_margin(egui::Margin::same(10.0))
                         .show(ui, |ui| {
                             ui.vertical(|ui| {
-                                // Subscription tier
+                                // First row: Status
                                 ui.horizontal(|ui| {
-                                    ui.label(egui::RichText::new("📦").size(16.0));
-                                    ui.label(egui::RichText::new(format!("Plan: {}", subscription.tier.name))
-                                        .color(egui::Color32::from_rgb(150, 200, 255))
-                                        .size(14.0));
-                                    if subscription.is_active() {
-                                        ui.label(egui::RichText::new("Active").color(egui::Color32::from_rgb(100, 255, 100)).size(12.0));
-                                    }
-                                    
-                                    // Add upgrade button for non-lifetime plans
-                                    if subscription.tier.id != "lifetime" {
-                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                            if ui.button("⬆ Upgrade").clicked() {
-                                                // Open web dashboard for subscription management
-                                                let dashboard_url = format!("{}/dashboard/billing", 
-                                                    self.settings.lock().unwrap().cloud_api_url
-                                                        .replace("/api", "")
-                                                        .replace(":8080", ":3000")); // Handle local dev
-                                                
-                                                if let Err(e) = webbrowser::open(&dashboard_url) {
-                                                    error!("Failed to open browser: {}", e);
-                                                } else {
-                                                    info!("Opened dashboard URL: {}", dashboard_url);
-                                                }
-                                            }
-                                        });
+                                    ui.label(egui::RichText::new("✅").size(20.0));
+                                    ui.label(egui::RichText::new("Connected").color(theme.success).size(14.0));
+                                    if let Some(ref email) = self.user_email {
+                                        ui.label(egui::RichText::new(format!(" - {}", email)).size(12.0));
                                     }
                                 });
                                 
-                                // Usage stats
-                                if let Some(ref usage) = self.usage_stats {
-                                    ui.add_space(8.0);
-                                    
-                                    // Saves usage
+                                // Real-time updates are paused while the WebSocket reconnects
+                                if let Some(ConnectionState::Reconnecting { attempt }) = self.ws_connection_state {
                                     ui.horizontal(|ui| {
-                                        ui.label("Saves:");
-                                        let saves_pct = usage.saves_percentage();
-                                        let color = if saves_pct > 90.0 {
-                                            egui::Color32::from_rgb(255, 100, 100)
-                                        } else if saves_pct > 75.0 {
-                                            egui::Color32::from_rgb(255, 200, 100)
+                                        ui.spinner();
+                                        let text = if attempt == 0 {
+                                            "Live updates disconnected, reconnecting...".to_string()
                                         } else {
-                                            egui::Color32::from_rgb(100, 255, 100)
+                                            format!("Live updates disconnected, reconnecting (attempt {})...", attempt)
                                         };
-                                        ui.label(egui::RichText::new(format!("{}/{} ({:.0}%)", 
-                                            usage.saves_count, usage.saves_limit, saves_pct))
-                                            .color(color)
-                                            .size(12.0));
+                                        ui.label(egui::RichText::new(text)
+                                            .color(theme.warning)
+                                            .size(11.0));
                                     });
-                                    
-                                    // Storage usage
+                                }
+                                
+                                // Second row: Buttons
+                                ui.add_space(5.0);
+                                ui.horizontal(|ui| {
+                                    let sync_button_text = if self.sync_in_progress {
+                                        "⏳ Syncing..."
+                                    } else {
+                                        "🔄 Sync Now"
+                                    };
+                                    if ui.add_enabled(!self.sync_in_progress, egui::Button::new(sync_button_text)).clicked() {
+                                        self.start_manual_sync(ui.ctx());
+                                    }
+                                    if ui.button("📤 Logout").clicked() {
+                                        should_logout = true;
+                                    }
+                                    if ui.button("🔃 Refresh Status").clicked() {
+                                        self.fetch_subscription_status(ui.ctx());
+                                    }
+                                });
+                                
+                                // Third row: Result of the last manual sync
+                                match self.last_sync_result {
+                                    Some(ManualSyncResult::Success { finished_at }) => {
+                                        ui.label(egui::RichText::new(format!("Last sync succeeded at {}", finished_at.format("%H:%M:%S")))
+                                            .color(theme.muted)
+                                            .size(11.0));
+                                    }
+                                    Some(ManualSyncResult::Error(ref err)) => {
+                                        ui.colored_label(theme.error, format!("⚠ Sync failed: {}", err));
+                                    }
+                                    None => {}
+                                }
+                            });
+                        });
+                    ui.add_space(10.0);
+                    
+                    // Show sync status
+                    if let Some(ref status) = self.sync_status {
+                        let uploads_stuck = self.pending_uploads_since
+                            .is_some_and(|since| since.elapsed() > STUCK_UPLOAD_THRESHOLD);
+                        let muted = theme.muted;
+                        let progress = self.sync_service.lock().unwrap().as_ref().and_then(|s| s.progress());
+                        if progress.is_some() {
+                            ui.ctx().request_repaint_after(SYNC_PROGRESS_REPAINT_INTERVAL);
+                        }
+                        
+                        egui::Frame::none()
+                            .fill(theme.panel_alt)
+                            .rounding(egui::Rounding::same(5.0))
+                            .inner_margin(egui::Margin::same(10.0))
+                            .show(ui, |ui| {
+                                ui.vertical(|ui| {
                                     ui.horizontal(|ui| {
-                                        ui.label("Storage:");
-                                        let storage_pct = usage.storage_percentage();
-                                        let storage_gb = usage.storage_bytes as f64 / 1_073_741_824.0;
-                                        let storage_limit_gb = usage.storage_limit_bytes as f64 / 1_073_741_824.0;
-                                        let color = if storage_pct > 90.0 {
-                                            egui::Color32::from_rgb(255, 100, 100)
-                                        } else if storage_pct > 75.0 {
-                                            egui::Color32::from_rgb(255, 200, 100)
+                                        ui.label("Last synced:");
+                                        let last_sync = if status.is_syncing {
+                                            "syncing now...".to_string()
                                         } else {
-                                            egui::Color32::from_rgb(100, 255, 100)
+                                            status.last_sync
+                                                .map(format_relative_time)
+                                                .unwrap_or_else(|| "never".to_string())
                                         };
-                                        ui.label(egui::RichText::new(format!("{:.2}/{:.0} GB ({:.0}%)", 
-                                            storage_gb, storage_limit_gb, storage_pct))
-                                            .color(color)
-                                            .size(12.0));
+                                        ui.label(egui::RichText::new(last_sync).color(muted).size(12.0));
                                     });
                                     
-                                    // Devices usage
                                     ui.horizontal(|ui| {
-                                        ui.label("Devices:");
-                                        let devices_pct = if usage.devices_limit > 0 {
-                                            (usage.devices_count as f32 / usage.devices_limit as f32) * 100.0
+                                        ui.label("Pending uploads:");
+                                        let color = if uploads_stuck {
+                                            theme.warning
                                         } else {
-                                            0.0
+                                            muted
                                         };
-                                        let color = if devices_pct >= 100.0 {
-                                            egui::Color32::from_rgb(255, 100, 100)
-                                        } else if devices_pct > 80.0 {
-                                            egui::Color32::from_rgb(255, 200, 100)
-                                        } else {
-                                            egui::Color32::from_rgb(100, 255, 100)
-                                        };
-                                        ui.label(egui::RichText::new(format!("{}/{}", 
-                                            usage.devices_count, usage.devices_limit))
-                                            .color(color)
-                                            .size(12.0));
+                                        ui.label(egui::RichText::new(status.pending_uploads.to_string()).color(color).size(12.0));
+                                        
+                                        ui.label("Pending downloads:");
+                                        ui.label(egui::RichText::new(status.pending_downloads.to_string()).color(muted).size(12.0));
                                     });
                                     
-                                    // Warning if near limits
-                                    if usage.is_near_limit() {
-                                        ui.add_space(5.0);
-                                        ui.label(egui::RichText::new("⚠ Approaching limits")
-                                            .color(egui::Color32::from_rgb(255, 200, 100))
+                                    if let Some(progress) = progress {
+                                        ui.add(egui::ProgressBar::new(progress.fraction()).text(progress.to_string()));
+                                    }
+                                    
+                                    if status.last_sync_timed_out {
+                                        ui.label(egui::RichText::new("⚠ The last sync timed out, it will be retried")
+                                            .color(theme.warning)
                                             .size(11.0));
+                                    } else if uploads_stuck {
+                                        ui.label(egui::RichText::new("⚠ Some saves have been waiting to upload for a while")
+                                            .color(theme.warning)
+                                            .size(11.0));
+                                    }
+                                });
+                            });
+                        ui.add_space(10.0);
+                    }
+                    
+                    // Show subscription status
+                    if let Some(ref subscription) = self.subscription_status {
+                        egui::Frame::none()
+                            .fill(theme.panel_alt)
+                            .rounding(egui::Rounding::same(5.0))
+                            .inner_margin(egui::Margin::same(10.0))
+                            .show(ui, |ui| {
+                                ui.vertical(|ui| {
+                                    // Subscription tier
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new("📦").size(16.0));
+                                        ui.label(egui::RichText::new(format!("Plan: {}", subscription.tier.name))
+                                            .color(theme.accent)
+                                            .size(14.0));
+                                        if subscription.is_active() {
+                                            ui.label(egui::RichText::new("Active").color(theme.success).size(12.0));
+                                        }
+                                        
+                                        // Add upgrade button for non-lifetime plans
+                                        if subscription.tier.id != "lifetime" {
+                                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                                if ui.button("⬆ Upgrade").clicked() {
+                                                    // Open web dashboard for subscription management
+                                                    let dashboard_url = format!("{}/dashboard/billing", 
+                                                        self.settings.lock().unwrap().cloud_api_url
+                                                            .replace("/api", "")
+                                                            .replace(":8080", ":3000")); // Handle local dev
+                                                    
+                                                    if let Err(e) = webbrowser::open(&dashboard_url) {
+                                                        error!("Failed to open browser: {}", e);
+                                                    } else {
+                                                        info!("Opened dashboard URL: {}", dashboard_url);
+                                                    }
+                                                }
+                                            });
+                                        }
+                                    });
+                                    
+                                    // Usage stats
+                                    if let Some(ref usage) = self.usage_stats {
+                                        ui.add_space(8.0);
+                                        
+                                        // Saves usage
+                                        ui.horizontal(|ui| {
+                                            ui.label("Saves:");
+                                            let saves_pct = usage.saves_percentage();
+                                            let color = if saves_pct > 90.0 {
+                                                theme.error
+                                            } else if saves_pct > 75.0 {
+                                                theme.warning
+                                            } else {
+                                                theme.success
+                                            };
+                                            ui.label(egui::RichText::new(format!("{}/{} ({:.0}%)", 
+                                                usage.saves_count, usage.saves_limit, saves_pct))
+                                                .color(color)
+                                                .size(12.0));
+                                        });
+                                        
+                                        // Storage usage
+                                        ui.horizontal(|ui| {
+                                            ui.label("Storage:");
+                                            let storage_pct = usage.storage_percentage();
+                                            let storage_gb = usage.storage_bytes as f64 / 1_073_741_824.0;
+                                            let storage_limit_gb = usage.storage_limit_bytes as f64 / 1_073_741_824.0;
+                                            let color = if storage_pct > 90.0 {
+                                                theme.error
+                                            } else if storage_pct > 75.0 {
+                                                theme.warning
+                                            } else {
+                                                theme.success
+                                            };
+                                            ui.label(egui::RichText::new(format!("{:.2}/{:.0} GB ({:.0}%)", 
+                                                storage_gb, storage_limit_gb, storage_pct))
+                                                .color(color)
+                                                .size(12.0));
+                                        });
+                                        
+                                        // Devices usage
+                                        ui.horizontal(|ui| {
+                                            ui.label("Devices:");
+                                            let devices_pct = if usage.devices_limit > 0 {
+                                                (usage.devices_count as f32 / usage.devices_limit as f32) * 100.0
+                                            } else {
+                                                0.0
+                                            };
+                                            let color = if devices_pct >= 100.0 {
+                                                theme.error
+                                            } else if devices_pct > 80.0 {
+                                                theme.warning
+                                            } else {
+                                                theme.success
+                                            };
+                                            ui.label(egui::RichText::new(format!("{}/{}", 
+                                                usage.devices_count, usage.devices_limit))
+                                                .color(color)
+                                                .size(12.0));
+                                        });
+                                        
+                                        // Warning if near limits
+                                        if usage.is_near_limit() {
+                                            ui.add_space(5.0);
+                                            ui.label(egui::RichText::new("⚠ Approaching limits")
+                                                .color(theme.warning)
+                                                .size(11.0));
+                                        }
+                                    }
+                                });
+                            });
+                        ui.add_space(10.0);
+                    } else if self.subscription_loading {
+                        ui.label(egui::RichText::new("⏳ Loading subscription status...")
+                            .color(theme.muted)
+                            .size(12.0));
+                        ui.add_space(10.0);
+                    }
+                    
+                    // Cloud sync checkbox for authenticated users
+                    {
+                        let mut settings = self.settings.lock().unwrap();
+                        ui.checkbox(&mut settings.cloud_sync_enabled, "Enable cloud sync");
+                        cloud_sync_enabled = settings.cloud_sync_enabled;
+                    }
+                } else {
+                    // Not authenticated - show browser auth UI
+                    egui::Frame::none()
+                        .fill(theme.panel)
+                        .rounding(egui::Rounding::same(8.0))
+                        .inner_margin(egui::Margin::same(16.0))
+                        .show(ui, |ui| {
+                            ui.vertical_centered(|ui| {
+                                // Icon and heading
+                                ui.label(egui::RichText::new("🔐").size(32.0));
+                                ui.add_space(8.0);
+                                ui.label(egui::RichText::new("Sign in to enable cloud sync").size(16.0).strong());
+                                ui.add_space(4.0);
+                                ui.label(egui::RichText::new("Securely sync your saves across all devices").size(12.0).color(theme.muted));
+                                
+                                ui.add_space(16.0);
+                                
+                                // Browser auth button
+                                ui.add_enabled_ui(!self.auth_is_loading, |ui| {
+                                    let button_text = if self.auth_is_loading {
+                                        "⏳ Waiting for authentication..."
+                                    } else {
+                                        "🌐 Sign in with Browser"
+                                    };
+                                    
+                                    let button = egui::Button::new(
+                                        egui::RichText::new(button_text).size(14.0)
+                                    )
+                                    .min_size(egui::Vec2::new(200.0, 36.0))
+                                    .rounding(egui::Rounding::same(6.0));
+                                    
+                                    if ui.add(button).clicked() {
+                                        self.start_browser_auth();
                                     }
+                                });
+                                
+                                // Show loading message if authenticating
+                                if self.auth_is_loading {
+                                    ui.add_space(8.0);
+                                    ui.label(egui::RichText::new("⚠ Complete authentication in your browser").size(12.0).color(theme.warning));
+                                    ui.label(egui::RichText::new("This window will automatically update when done").size(11.0).color(theme.muted));
+                                }
+                                
+                                // Error message
+                                if let Some(ref error) = self.auth_error {
+                                    ui.add_space(8.0);
+                                    ui.colored_label(theme.error, format!("⚠ {}", error));
                                 }
+                                
+                                ui.add_space(12.0);
+                                ui.separator();
+                                ui.add_space(8.0);
+                                
+                                // Benefits list
+                                ui.vertical(|ui| {
+                                    ui.label(egui::RichText::new("✅ Secure OAuth 2.0 authentication").size(11.0).color(theme.muted));
+                                    ui.label(egui::RichText::new("✅ Two-factor authentication support").size(11.0).color(theme.muted));
+                                    ui.label(egui::RichText::new("✅ Sign in with Google or GitHub").size(11.0).color(theme.muted));
+                                });
                             });
                         });
                     ui.add_space(10.0);
-                } else if self.subscription_loading {
-                    ui.label(egui::RichText::new("⏳ Loading subscription status...")
-                        .color(egui::Color32::from_rgb(150, 150, 150))
-                        .size(12.0));
-                    ui.add_space(10.0);
                 }
                 
-                // Cloud sync checkbox for authenticated users
-                {
-                    let mut settings = self.settings.lock().unwrap();
-                    ui.checkbox(&mut settings.cloud_sync_enabled, "Enable cloud sync");
-                    cloud_sync_enabled = settings.cloud_sync_enabled;
-                }
-            } else {
-                // Not authenticated - show browser auth UI
-                egui::Frame::none()
-                    .fill(egui::Color32::from_rgb(40, 45, 40))
-                    .rounding(egui::Rounding::same(8.0))
-                    .inner_margin(egui::Margin::same(16.0))
-                    .show(ui, |ui| {
-                        ui.vertical_centered(|ui| {
-                            // Icon and heading
-                            ui.label(egui::RichText::new("🔐").size(32.0));
-                            ui.add_space(8.0);
-                            ui.label(egui::RichText::new("Sign in to enable cloud sync").size(16.0).strong());
-                            ui.add_space(4.0);
-                            ui.label(egui::RichText::new("Securely sync your saves across all devices").size(12.0).color(egui::Color32::from_rgb(150, 150, 150)));
+                // Cloud sync settings if authenticated and enabled
+                if (self.is_authenticated || own_storage) && cloud_sync_enabled {
+                    ui.indent("cloud_settings", |ui| {
+                        // Auto sync option
+                        {
+                            let mut settings = self.settings.lock().unwrap();
+                            ui.checkbox(&mut settings.cloud_auto_sync, "Automatically sync saves");
                             
-                            ui.add_space(16.0);
+                            // A local folder has no transfers worth limiting
+                            if settings.sync_folder().is_none() {
+                                ui.horizontal(|ui| {
+                                    ui.label("Max transfer speed (KB/s):");
+                                    ui.add(egui::DragValue::new(&mut settings.max_sync_bandwidth_kbps)
+                                        .range(0..=100_000)
+                                        .speed(16));
+                                });
+                                ui.label(egui::RichText::new("0 = unlimited. Applies to all uploads and downloads combined.")
+                                    .color(theme.muted)
+                                    .size(11.0));
+                            }
                             
-                            // Browser auth button
-                            ui.add_enabled_ui(!self.auth_is_loading, |ui| {
-                                let button_text = if self.auth_is_loading {
-                                    "⏳ Waiting for authentication..."
-                                } else {
-                                    "🌐 Sign in with Browser"
-                                };
-                                
-                                let button = egui::Button::new(
-                                    egui::RichText::new(button_text).size(14.0)
-                                )
-                                .min_size(egui::Vec2::new(200.0, 36.0))
-                                .rounding(egui::Rounding::same(6.0));
-                                
-                                if ui.add(button).clicked() {
-                                    self.start_browser_auth();
-                                }
+                            ui.horizontal(|ui| {
+                                ui.label("Parallel uploads:");
+                                ui.add(egui::DragValue::new(&mut settings.parallel_uploads)
+                                    .range(1..=16));
+                                ui.label("downloads:");
+                                ui.add(egui::DragValue::new(&mut settings.parallel_downloads)
+                                    .range(1..=16));
                             });
                             
-                            // Show loading message if authenticating
-                            if self.auth_is_loading {
-                                ui.add_space(8.0);
-                                ui.label(egui::RichText::new("⚠ Complete authentication in your browser").size(12.0).color(egui::Color32::from_rgb(255, 200, 100)));
-                                ui.label(egui::RichText::new("This window will automatically update when done").size(11.0).color(egui::Color32::from_rgb(150, 150, 150)));
-                            }
+                            ui.horizontal(|ui| {
+                                ui.label("When saves conflict:");
+                                egui::ComboBox::from_id_salt("conflict_strategy")
+                                    .selected_text(settings.conflict_strategy.label())
+                                    .show_ui(ui, |ui| {
+                                        for strategy in ConflictResolutionStrategy::ALL {
+                                            ui.selectable_value(&mut settings.conflict_strategy, strategy, strategy.label());
+                                        }
+                                    });
+                            });
                             
-                            // Error message
-                            if let Some(ref error) = self.auth_error {
-                                ui.add_space(8.0);
-                                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ {}", error));
-                            }
+                            ui.checkbox(&mut settings.sync_after_closing_only, "Only sync after closing the game")
+                                .on_hover_text("Saves made while playing are uploaded once the emulator exits");
                             
-                            ui.add_space(12.0);
-                            ui.separator();
-                            ui.add_space(8.0);
+                            ui.checkbox(&mut settings.restore_deleted_saves, "Restore deleted saves from the cloud")
+                                .on_hover_text("Deleting a save file never deletes its cloud saves. When this is off, a deleted save only comes back if another device uploads a newer one.");
                             
-                            // Benefits list
-                            ui.vertical(|ui| {
-                                ui.label(egui::RichText::new("✅ Secure OAuth 2.0 authentication").size(11.0).color(egui::Color32::from_rgb(130, 130, 130)));
-                                ui.label(egui::RichText::new("✅ Two-factor authentication support").size(11.0).color(egui::Color32::from_rgb(130, 130, 130)));
-                                ui.label(egui::RichText::new("✅ Sign in with Google or GitHub").size(11.0).color(egui::Color32::from_rgb(130, 130, 130)));
+                            ui.horizontal(|ui| {
+                                ui.label("Failure webhook URL:");
+                                let mut webhook_url = settings.failure_webhook_url.clone().unwrap_or_default();
+                                if ui.add(egui::TextEdit::singleline(&mut webhook_url)
+                                    .hint_text("https://discord.com/api/webhooks/...")
+                                    .password(true))
+                                    .on_hover_text("Discord, Slack or any URL accepting a JSON POST. Told when syncing fails 3 times in a row or this device is signed out, at most once an hour.")
+                                    .changed()
+                                {
+                                    let webhook_url = webhook_url.trim();
+                                    settings.failure_webhook_url = (!webhook_url.is_empty()).then(|| webhook_url.to_string());
+                                }
                             });
-                        });
+                        }
+                        
+                        egui::CollapsingHeader::new("🔒 End-to-end encryption")
+                            .id_salt("encryption")
+                            .show(ui, |ui| {
+                                self.show_encryption(ui, &theme);
+                            });
+                        
+                        ui.label("💡 Cloud sync keeps your saves synchronized across all devices");
                     });
-                ui.add_space(10.0);
+                }
+                
+                ui.separator();
             }
             
-            // Cloud sync settings if authenticated and enabled
-            if self.is_authenticated && cloud_sync_enabled {
-                ui.indent("cloud_settings", |ui| {
-                    // Auto sync option
+            if filter.shows_section(SettingsSection::Compression) {
+                section_heading(ui, SettingsSection::Compression, &mut self.scroll_to_section);
+                let mut settings = self.settings.lock().unwrap();
+                ui.checkbox(&mut settings.compression_enabled, "Enable save compression");
+                if settings.compression_enabled {
+                    ui.horizontal(|ui| {
+                        ui.label("Compression level:");
+                        ui.add(egui::Slider::new(&mut settings.compression_level, 1..=22)
+                            .text("Level"));
+                    });
+                    ui.label(format!("Level {}: {}", 
+                        settings.compression_level,
+                        match settings.compression_level {
+                            1..=3 => "Fast (less compression)",
+                            4..=9 => "Balanced",
+                            10..=15 => "Good compression",
+                            16..=22 => "Best compression (slower)",
+                            _ => "Unknown"
+                        }
+                    ));
+                    ui.label("💡 Level 3 recommended for best speed/size balance");
+                }
+                drop(settings);
+                
+                if !self.compression_stats_loaded {
+                    self.load_compression_stats(ui.ctx());
+                }
+                if self.compression_stats_rx.is_some() {
+                    ui.spinner();
+                } else if let Some(stats) = self.compression_stats {
+                    if stats.space_saved() > 0 {
+                        ui.label(format!(
+                            "Saved {} with compression ({:.0}% smaller backups)",
+                            format_size(stats.space_saved()),
+                            stats.space_saved_percent()
+                        ));
+                    }
+                }
+                
+                let benchmark_running = self.compression_benchmark_rx.is_some();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!benchmark_running, egui::Button::new("⏱ Benchmark compression"))
+                        .on_hover_text("Compress your most recent save at several levels to compare speed and size")
+                        .clicked()
                     {
-                        let mut settings = self.settings.lock().unwrap();
-                        ui.checkbox(&mut settings.cloud_auto_sync, "Automatically sync saves");
+                        self.benchmark_compression(ui.ctx());
+                    }
+                    if benchmark_running {
+                        ui.spinner();
                     }
-                    
-                    ui.label("💡 Cloud sync keeps your saves synchronized across all devices");
                 });
+                
+                let mut chosen_level = None;
+                match &self.compression_benchmark {
+                    Some(Ok(benchmark)) => {
+                        ui.label(format!("{} ({})", benchmark.sample, format_size(benchmark.original_size)));
+                        egui::Grid::new("compression_benchmark").striped(true).show(ui, |ui| {
+                            ui.strong("Level");
+                            ui.strong("Size");
+                            ui.strong("Saved");
+                            ui.strong("Time");
+                            ui.end_row();
+                            
+                            for (level, stats) in &benchmark.results {
+                                ui.label(level.to_string());
+                                ui.label(format_size(stats.compressed_size));
+                                ui.label(format!("{:.0}%", stats.space_saved_percent()));
+                                if stats.compression_time_ms == 0 {
+                                    ui.label("<1 ms");
+                                } else {
+                                    ui.label(format!("{} ms", stats.compression_time_ms));
+                                }
+                                if ui.small_button("Use").clicked() {
+                                    chosen_level = Some(*level);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(theme.error, format!("⚠ {}", e));
+                    }
+                    None => {}
+                }
+                if let Some(level) = chosen_level {
+                    let mut settings = self.settings.lock().unwrap();
+                    settings.compression_enabled = true;
+                    settings.compression_level = level;
+                }
+                
+                ui.separator();
             }
             
-            ui.separator();
+            // Games and devices
+            if filter.shows_section(SettingsSection::Games) {
+                section_heading(ui, SettingsSection::Games, &mut self.scroll_to_section);
+                if ui.button("📂 Browse Saves...").clicked() {
+                    self.open_save_browser(ui.ctx());
+                }
+                
+                if filter.shows(SettingsSection::Games, "Only back up these games") {
+                    let mut settings = self.settings.lock().unwrap();
+                    ui.checkbox(&mut settings.allowlist_enabled, "Only back up these games")
+                        .on_hover_text("Saves of other games, and of games that couldn't be identified, are ignored");
+                    if settings.allowlist_enabled {
+                        let mut remove = None;
+                        for (i, game) in settings.allowlisted_games.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(game)
+                                    .hint_text("e.g. Kingdom Hearts* or SLUS-20312"));
+                                if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+                        }
+                        if let Some(i) = remove {
+                            settings.allowlisted_games.remove(i);
+                        }
+                        if ui.button("➕ Add game").clicked() {
+                            settings.allowlisted_games.push(String::new());
+                        }
+                        ui.label(egui::RichText::new("Game names or serials, * and ? wildcards, not case-sensitive")
+                            .color(theme.muted)
+                            .size(11.0));
+                    }
+                }
+                
+                // Per-game sync toggles
+                let games_header = egui::CollapsingHeader::new("Games").show(ui, |ui| {
+                    let mut toggled = None;
+                    if self.games_rx.is_some() {
+                        ui.spinner();
+                    } else if self.games.is_empty() {
+                        ui.label("No games detected yet");
+                    } else {
+                        ui.label("Uncheck a game to keep its saves local only.");
+                        for game in &mut self.games {
+                            let label = format!("{} ({})", game.name, game.emulator);
+                            if ui.checkbox(&mut game.sync_enabled, label).changed() {
+                                toggled = Some((game.id, game.sync_enabled));
+                            }
+                        }
+                    }
+                    toggled
+                });
+                if games_header.body_response.is_some() && !self.games_loaded {
+                    self.load_games(ui.ctx());
+                }
+                if let Some(Some((game_id, enabled))) = games_header.body_returned {
+                    self.set_game_sync_enabled(game_id, enabled);
+                }
+                
+                // Devices registered to the account
+                if self.is_authenticated {
+                    let devices_header = egui::CollapsingHeader::new("Devices").show(ui, |ui| {
+                        let mut removed = None;
+                        if self.devices_rx.is_some() {
+                            ui.spinner();
+                        } else if let Some(ref err) = self.devices_error {
+                            ui.colored_label(theme.error, format!("⚠ {}", err));
+                        } else if self.devices.is_empty() {
+                            ui.label("No devices registered yet");
+                        } else {
+                            ui.label("Remove devices you no longer use to free up device slots.");
+                            for device in &self.devices {
+                                ui.horizontal(|ui| {
+                                    let mut label = device.name.clone();
+                                    if let Some(ref device_type) = device.device_type {
+                                        label.push_str(&format!(" ({})", device_type));
+                                    }
+                                    if self.current_device_id.as_deref() == Some(device.device_id.as_str()) {
+                                        label.push_str(" - this device");
+                                    }
+                                    ui.label(label);
+                                    if let Some(last_seen) = device.last_seen_at {
+                                        ui.label(egui::RichText::new(format!("last seen {}", last_seen.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")))
+                                            .color(theme.muted)
+                                            .size(11.0));
+                                    }
+                                    if ui.button("Remove").clicked() {
+                                        removed = Some(device.device_id.clone());
+                                    }
+                                });
+                            }
+                        }
+                        removed
+                    });
+                    if devices_header.body_response.is_some() && !self.devices_loaded {
+                        self.load_devices(None, ui.ctx());
+                    }
+                    if let Some(Some(device_id)) = devices_header.body_returned {
+                        self.load_devices(Some(device_id), ui.ctx());
+                    }
+                }
+                
+                ui.separator();
+            }
             
-            ui.heading("Compression");
-            {
-                let mut settings = self.settings.lock().unwrap();
-            ui.checkbox(&mut settings.compression_enabled, "Enable save compression");
-            if settings.compression_enabled {
+            // Maintenance
+            if filter.shows_section(SettingsSection::Maintenance) {
+                section_heading(ui, SettingsSection::Maintenance, &mut self.scroll_to_section);
+                {
+                    let mut settings = self.settings.lock().unwrap();
+                    ui.checkbox(&mut settings.auto_vacuum_enabled, "Compact the local database weekly");
+                }
+                ui.label("Use these if sync gets stuck or keeps uploading duplicates.");
+                ui.add_enabled_ui(self.maintenance_rx.is_none(), |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("Reset sync state").clicked() {
+                            self.pending_maintenance = Some(MaintenanceAction::ResetSyncState);
+                        }
+                        if ui.button("Clear game cache").clicked() {
+                            self.pending_maintenance = Some(MaintenanceAction::ClearGameCache);
+                        }
+                        if ui.button("Re-upload all saves").clicked() {
+                            self.pending_maintenance = Some(MaintenanceAction::ReuploadAll);
+                        }
+                    });
+                    ui.label("If this computer was lost or stolen, remove every stored sign-in at once.");
+                    if ui.button("Sign out everywhere").clicked() {
+                        self.pending_maintenance = Some(MaintenanceAction::WipeLocalAuth);
+                    }
+                });
+                ui.label("Move your settings to another computer without cloud sync. Proxy passwords aren't exported.");
                 ui.horizontal(|ui| {
-                    ui.label("Compression level:");
-                    ui.add(egui::Slider::new(&mut settings.compression_level, 1..=22)
-                        .text("Level"));
+                    ui.text_edit_singleline(&mut self.settings_file_path);
+                    let idle = self.maintenance_rx.is_none() && self.settings_import_rx.is_none();
+                    if ui.add_enabled(idle, egui::Button::new("Export settings")).clicked() {
+                        self.export_settings_file(ui.ctx());
+                    }
+                    if ui.add_enabled(idle, egui::Button::new("Import settings")).clicked() {
+                        self.import_settings_file(ui.ctx());
+                    }
                 });
-                ui.label(format!("Level {}: {}", 
-                    settings.compression_level,
-                    match settings.compression_level {
-                        1..=3 => "Fast (less compression)",
-                        4..=9 => "Balanced",
-                        10..=15 => "Good compression",
-                        16..=22 => "Best compression (slower)",
-                        _ => "Unknown"
+                ui.label("Attach a diagnostics bundle when reporting a bug. Emails and tokens are removed.");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.diagnostics_path);
+                    if ui.add_enabled(self.maintenance_rx.is_none(), egui::Button::new("Export diagnostics")).clicked() {
+                        self.export_diagnostics(ui.ctx());
                     }
-                ));
-                ui.label("💡 Level 3 recommended for best speed/size balance");
+                });
+                match self.maintenance_result {
+                    Some(Ok(ref message)) => {
+                        ui.label(egui::RichText::new(format!("✅ {}", message))
+                            .color(theme.muted)
+                            .size(11.0));
+                    }
+                    Some(Err(ref err)) => {
+                        ui.colored_label(theme.error, format!("⚠ {}", err));
+                    }
+                    None => {}
+                }
+                
+                ui.separator();
             }
             
-            ui.separator();
-            } // Drop settings lock
+            if !SettingsSection::ALL.iter().any(|section| filter.shows_section(*section)) {
+                ui.label(format!("No settings match \"{}\"", self.search_query.trim()));
+            }
             
             // Add some space before buttons
             ui.add_space(20.0);
@@ -1068,8 +2494,27 @@ impl eframe::App for SettingsApp {
             ui.horizontal(|ui| {
                 if ui.button("Save").clicked() {
                     info!("Settings saved button clicked");
-                    // Clone settings for saving
-                    let settings_to_save = self.settings.lock().unwrap().clone();
+                    // Clone settings for saving, stamped so the next cloud merge
+                    // doesn't undo changes the cloud hasn't seen yet
+                    let settings_to_save = {
+                        let mut settings = self.settings.lock().unwrap();
+                        settings.updated_at = Some(chrono::Utc::now());
+                        settings.clone()
+                    };
+                    
+                    // Apply sync settings to the running service immediately
+                    if let Some(sync_service) = self.sync_service.lock().unwrap().clone() {
+                        sync_service.set_bandwidth_limit(settings_to_save.max_sync_bandwidth_kbps);
+                        sync_service.set_download_concurrency(settings_to_save.parallel_downloads as usize);
+                        sync_service.set_upload_concurrency(settings_to_save.parallel_uploads as usize);
+                        sync_service.set_sync_after_closing_only(settings_to_save.sync_after_closing_only);
+                        sync_service.set_restore_deleted_saves(settings_to_save.restore_deleted_saves);
+                        sync_service.set_failure_webhook(settings_to_save.failure_webhook_url.clone());
+                        let strategy = settings_to_save.conflict_strategy;
+                        self.runtime.spawn(async move {
+                            sync_service.set_conflict_strategy(strategy).await;
+                        });
+                    }
                     
                     // Save directly using settings manager if available
                     if let Some(ref manager) = self.settings_manager {
@@ -1077,28 +2522,24 @@ impl eframe::App for SettingsApp {
                         let api_client = self.api_client.clone();
                         let settings_for_cloud = settings_to_save.clone();
                         
-                        // Spawn a thread to do the async save without blocking UI
-                        std::thread::spawn(move || {
-                            // Create a small runtime just for this save operation
-                            let rt = tokio::runtime::Runtime::new().unwrap();
-                            rt.block_on(async {
-                                info!("Saving settings to database...");
-                                if let Err(e) = manager_clone.save_settings(&settings_to_save).await {
-                                    error!("Failed to save settings: {}", e);
-                                } else {
-                                    info!("Settings successfully saved to database");
-                                    
-                                    // Also sync to cloud if authenticated
-                                    if let Some(ref api) = api_client {
-                                        use crate::sync::settings_sync;
-                                        if let Err(e) = settings_sync::push_settings_to_cloud(api, &settings_for_cloud).await {
-                                            error!("Failed to sync settings to cloud: {}", e);
-                                        } else {
-                                            info!("Settings synced to cloud");
-                                        }
+                        // Save in the background without blocking UI
+                        self.runtime.spawn(async move {
+                            info!("Saving settings to database...");
+                            if let Err(e) = manager_clone.save_settings(&settings_to_save).await {
+                                error!("Failed to save settings: {}", e);
+                            } else {
+                                info!("Settings successfully saved to database");
+                                
+                                // Also sync to cloud if authenticated
+                                if let Some(ref api) = api_client {
+                                    use crate::sync::settings_sync;
+                                    if let Err(e) = settings_sync::push_settings_to_cloud(api, &settings_for_cloud).await {
+                                        error!("Failed to sync settings to cloud: {}", e);
+                                    } else {
+                                        info!("Settings synced to cloud");
                                     }
                                 }
-                            });
+                            }
                         });
                     }
                     
@@ -1113,286 +2554,996 @@ impl eframe::App for SettingsApp {
                 }
             });
             
-            }); // End of ScrollArea
-        });
-        
-        // Handle window close button
-        if ctx.input(|i| i.viewport().close_requested()) {
-            self.visible = false;
-            // Don't actually close, just hide
-            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
-            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+            }); // End of ScrollArea
+        });
+        
+        let cloud_sync = self.is_authenticated && self.settings.lock().unwrap().cloud_sync_enabled;
+        if let Some(ref mut save_browser) = self.save_browser {
+            save_browser.show(ctx, cloud_sync);
+        }
+        
+        // Handle window close button
+        if ctx.input(|i| i.viewport().close_requested()) && !self.quitting {
+            self.visible = false;
+            if self.settings.lock().unwrap().minimize_to_tray {
+                // Don't actually close, just hide
+                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+            } else {
+                info!("Settings window closed, quitting");
+                self.quit_requested.notify_one();
+            }
+        }
+        
+        // Confirmation dialog for maintenance actions
+        if let Some(action) = self.pending_maintenance {
+            let mut confirmed = false;
+            let mut cancelled = false;
+            
+            egui::Window::new("Are you sure?")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(action.confirmation_text());
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Continue").clicked() {
+                            confirmed = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancelled = true;
+                        }
+                    });
+                });
+            
+            if confirmed {
+                self.pending_maintenance = None;
+                self.run_maintenance(action, ctx);
+            } else if cancelled {
+                self.pending_maintenance = None;
+            }
+        }
+        
+        // Handle deferred actions
+        if should_logout {
+            self.perform_logout(ctx);
+        }
+        if let Some(change) = profile_change {
+            self.load_profiles(Some(change), ctx);
+        }
+    }
+}
+
+impl SettingsApp {
+    fn initialize_websocket(&mut self, ctx: &egui::Context) {
+        if self.ws_initialized || !self.is_authenticated {
+            return;
+        }
+        
+        info!("Initializing WebSocket connection for real-time updates");
+        
+        // Get API URL from settings
+        let api_url = self.settings.lock().unwrap().cloud_api_url.clone();
+        
+        // Create WebSocket event channel
+        let (event_tx, _event_rx) = tokio::sync::mpsc::unbounded_channel();
+        
+        // Create WebSocket client, authenticating with a fresh token on every (re)connect
+        let mut ws_client = WebSocketClient::new(api_url.clone(), event_tx);
+        if let Some(ref auth_manager) = self.auth_manager {
+            ws_client = ws_client.with_auth_manager(auth_manager.clone());
+        }
+        let ws_client = Arc::new(ws_client);
+        let ws_client_clone = ws_client.clone();
+        
+        // Get auth token if available
+        if let Some(ref auth_manager) = self.auth_manager {
+            let auth_manager_clone = auth_manager.clone();
+            let ws_client_for_auth = ws_client.clone();
+            
+            // Set up WebSocket with auth token
+            self.runtime.spawn(async move {
+                if auth_manager_clone.get_access_token().await.is_some() {
+                    // Connect to WebSocket, the listener keeps retrying if this fails
+                    if let Err(e) = ws_client_for_auth.connect().await {
+                        error!("Failed to connect WebSocket: {}", e);
+                    } else {
+                        info!("WebSocket connected successfully");
+                    }
+                    
+                    // Start listening for messages
+                    let ws_listener = ws_client_for_auth.clone();
+                    tokio::spawn(async move {
+                        ws_listener.start_listening().await;
+                    });
+                }
+            });
+        }
+        
+        // Create channels to update the UI from callbacks
+        let (subscription_tx, subscription_rx) = std::sync::mpsc::channel::<SubscriptionStatus>();
+        let (usage_tx, usage_rx) = std::sync::mpsc::channel::<UsageStats>();
+        
+        let (state_tx, state_rx) = std::sync::mpsc::channel::<ConnectionState>();
+        let (devices_tx, devices_rx) = std::sync::mpsc::channel::<()>();
+        
+        // Store receivers so we can poll them in the update loop
+        self.ws_subscription_rx = Some(subscription_rx);
+        self.ws_usage_rx = Some(usage_rx);
+        self.ws_state_rx = Some(state_rx);
+        self.ws_devices_rx = Some(devices_rx);
+        
+        // Register subscription update callback
+        let ctx_clone = ctx.clone();
+        let ws_handler = ws_client.event_handler();
+        let subscription_callback = {
+            let ctx = ctx_clone.clone();
+            let tx = subscription_tx.clone();
+            move |status: SubscriptionStatus| {
+                info!("Received real-time subscription update: {:?}", status);
+                let _ = tx.send(status);
+                ctx.request_repaint();
+            }
+        };
+        
+        // Register usage update callback
+        let ctx_clone2 = ctx.clone();
+        let usage_callback = {
+            let ctx = ctx_clone2.clone();
+            let tx = usage_tx.clone();
+            move |stats: UsageStats| {
+                info!("Received real-time usage update: {:?}", stats);
+                let _ = tx.send(stats);
+                ctx.request_repaint();
+            }
+        };
+        
+        // Register device added callback
+        let ctx_clone3 = ctx.clone();
+        let device_added_callback = {
+            let ctx = ctx_clone3.clone();
+            let devices_tx = devices_tx.clone();
+            move |device_id: String, device_name: String| {
+                info!("Device added: {} ({})", device_name, device_id);
+                let _ = devices_tx.send(());
+                // Show notification to user
+                if let Err(e) = crate::ui::notifications::show_notification(
+                    "Device Added",
+                    &format!("New device '{}' has been added to your account", device_name),
+                    crate::ui::notifications::NotificationType::Info,
+                ) {
+                    error!("Failed to show device added notification: {}", e);
+                }
+                ctx.request_repaint();
+            }
+        };
+        
+        // Register device removed callback
+        let ctx_clone4 = ctx.clone();
+        let device_removed_callback = {
+            let ctx = ctx_clone4.clone();
+            let devices_tx = devices_tx.clone();
+            move |device_id: String, device_name: String| {
+                info!("Device removed: {} ({})", device_name, device_id);
+                let _ = devices_tx.send(());
+                // Show notification to user
+                if let Err(e) = crate::ui::notifications::show_notification(
+                    "Device Removed",
+                    &format!("Device '{}' has been removed from your account", device_name),
+                    crate::ui::notifications::NotificationType::Warning,
+                ) {
+                    error!("Failed to show device removed notification: {}", e);
+                }
+                ctx.request_repaint();
+            }
+        };
+        
+        // Register warning callback (for storage/save limits)
+        let ctx_clone5 = ctx.clone();
+        let warning_callback = {
+            let ctx = ctx_clone5.clone();
+            move |message: String| {
+                info!("Received warning: {}", message);
+                // Show warning notification to user
+                if let Err(e) = crate::ui::notifications::show_notification(
+                    "Storage Warning",
+                    &message,
+                    crate::ui::notifications::NotificationType::Warning,
+                ) {
+                    error!("Failed to show warning notification: {}", e);
+                }
+                ctx.request_repaint();
+            }
+        };
+        
+        // Register connection state callback for the reconnecting indicator
+        let connection_state_callback = {
+            let ctx = ctx.clone();
+            move |state: ConnectionState| {
+                let _ = state_tx.send(state);
+                ctx.request_repaint();
+            }
+        };
+        
+        // Store callbacks in the background to avoid blocking
+        let ws_handler_clone = ws_handler.clone();
+        self.runtime.spawn(async move {
+            ws_handler_clone.on_subscription_update(subscription_callback).await;
+            ws_handler_clone.on_usage_update(usage_callback).await;
+            ws_handler_clone.on_device_added(device_added_callback).await;
+            ws_handler_clone.on_device_removed(device_removed_callback).await;
+            ws_handler_clone.on_warning(warning_callback).await;
+            ws_handler_clone.on_connection_state_change(connection_state_callback).await;
+        });
+        
+        self.ws_client = Some(ws_client_clone);
+        self.ws_initialized = true;
+        
+        info!("WebSocket initialization complete");
+    }
+    
+    fn fetch_subscription_status(&mut self, ctx: &egui::Context) {
+        info!("fetch_subscription_status called, api_client exists: {}", self.api_client.is_some());
+        if let Some(ref api_client) = self.api_client {
+            if self.subscription_loading {
+                info!("Already loading subscription, skipping");
+                return; // Already loading
+            }
+            
+            info!("Starting subscription fetch");
+            self.subscription_loading = true;
+            let api_client_clone = api_client.clone();
+            let (tx, rx) = std::sync::mpsc::channel();
+            
+            self.runtime.spawn(async move {
+                let mut subscription = None;
+                let mut usage = None;
+                
+                // Fetch subscription status with retry on auth refresh
+                for attempt in 0..2 {
+                    match api_client_clone.get_subscription_status().await {
+                        Ok(status) => {
+                            subscription = Some(status);
+                            break;
+                        },
+                        Err(e) => {
+                            let error_str = e.to_string();
+                            if error_str.contains("Authentication refreshed") && attempt == 0 {
+                                // First attempt failed with auth refresh, retry
+                                info!("Retrying subscription fetch after auth refresh");
+                                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                                continue;
+                            }
+                            error!("Failed to fetch subscription status: {}", e);
+                            break;
+                        }
+                    }
+                }
+                
+                // Fetch usage stats with retry on auth refresh
+                for attempt in 0..2 {
+                    match api_client_clone.get_usage_stats().await {
+                        Ok(stats) => {
+                            usage = Some(stats);
+                            break;
+                        },
+                        Err(e) => {
+                            let error_str = e.to_string();
+                            if error_str.contains("Authentication refreshed") && attempt == 0 {
+                                // First attempt failed with auth refresh, retry
+                                info!("Retrying usage stats fetch after auth refresh");
+                                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                                continue;
+                            }
+                            error!("Failed to fetch usage stats: {}", e);
+                            break;
+                        }
+                    }
+                }
+                
+                let _ = tx.send((subscription, usage));
+            });
+            
+            self.subscription_rx = Some(rx);
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        }
+    }
+    
+    fn poll_sync_status(&mut self, ctx: &egui::Context) {
+        if self.sync_status_rx.is_some() {
+            return; // Already polling
+        }
+        if self.last_status_poll.is_some_and(|last| last.elapsed() < SYNC_STATUS_POLL_INTERVAL) {
+            return;
+        }
+        
+        let Some(sync_service) = self.sync_service.lock().unwrap().clone() else {
+            return;
+        };
+        
+        self.last_status_poll = Some(std::time::Instant::now());
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.sync_status_rx = Some(rx);
+        
+        let repaint = ctx.clone();
+        self.runtime.spawn(async move {
+            let _ = tx.send(sync_service.get_status().await);
+            repaint.request_repaint();
+        });
+        ctx.request_repaint_after(SYNC_STATUS_POLL_INTERVAL);
+    }
+    
+    fn open_save_browser(&mut self, ctx: &egui::Context) {
+        if self.save_browser.is_none() {
+            let Some(ref manager) = self.settings_manager else {
+                warn!("No database available for the save browser");
+                return;
+            };
+            self.save_browser = Some(crate::ui::SaveBrowser::new(manager.database(), None, self.runtime.clone()));
+        }
+        
+        if let Some(ref mut save_browser) = self.save_browser {
+            save_browser.set_sync_service(self.sync_service.lock().unwrap().clone());
+            save_browser.open(ctx);
+        }
+    }
+    
+    fn load_compression_stats(&mut self, ctx: &egui::Context) {
+        self.compression_stats_loaded = true;
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.compression_stats_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn_blocking(move || {
+            let stats = SaveBackupManager::new(None)
+                .and_then(|manager| manager.aggregate_stats())
+                .map_err(|e| warn!("Failed to read compression statistics: {}", e))
+                .ok();
+            let _ = tx.send(stats);
+            ctx.request_repaint();
+        });
+    }
+    
+    /// Compress the most recently played game's latest save at each of
+    /// `BENCHMARK_LEVELS`
+    fn benchmark_compression(&mut self, ctx: &egui::Context) {
+        let Some(ref manager) = self.settings_manager else {
+            return;
+        };
+        
+        let database = manager.database();
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.compression_benchmark_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = async {
+                let games = database.get_all_games().await.map_err(|e| e.to_string())?;
+                let mut sample = None;
+                for game in games {
+                    let Ok(Some(save)) = database.get_latest_save(game.id).await else {
+                        continue;
+                    };
+                    if let Ok(data) = tokio::fs::read(&save.file_path).await {
+                        sample = Some((game.name, data));
+                        break;
+                    }
+                }
+                let (game_name, data) = sample.ok_or("No saves to benchmark yet. Play a game first.")?;
+                
+                let original_size = data.len() as u64;
+                let results = tokio::task::spawn_blocking(move || benchmark_levels(&data, BENCHMARK_LEVELS))
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .map_err(|e| e.to_string())?;
+                Ok::<_, String>(CompressionBenchmark {
+                    sample: format!("Latest save of {}", game_name),
+                    original_size,
+                    results,
+                })
+            }.await;
+            
+            if let Err(ref e) = result {
+                warn!("Compression benchmark failed: {}", e);
+            }
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    fn load_games(&mut self, ctx: &egui::Context) {
+        let Some(ref manager) = self.settings_manager else {
+            return;
+        };
+        
+        self.games_loaded = true;
+        let database = manager.database();
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.games_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let games = match database.get_all_games().await {
+                Ok(games) => games,
+                Err(e) => {
+                    error!("Failed to load games: {}", e);
+                    Vec::new()
+                }
+            };
+            let _ = tx.send(games);
+            ctx.request_repaint();
+        });
+    }
+    
+    /// Fetch the account's devices, removing `remove_device_id` first if given
+    fn load_devices(&mut self, remove_device_id: Option<String>, ctx: &egui::Context) {
+        let Some(ref api_client) = self.api_client else {
+            return;
+        };
+        
+        self.devices_loaded = true;
+        let api_client = api_client.clone();
+        let sync_service = self.sync_service.lock().unwrap().clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.devices_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            if let Some(device_id) = remove_device_id {
+                info!("Removing device {}", device_id);
+                if let Err(e) = api_client.remove_device(&device_id).await {
+                    error!("Failed to remove device: {}", e);
+                    let _ = tx.send(Err(format!("Failed to remove device: {}", e)));
+                    ctx.request_repaint();
+                    return;
+                }
+            }
+            
+            let current_device_id = match sync_service {
+                Some(sync_service) => Some(sync_service.device_id().await),
+                None => None,
+            };
+            let result = api_client.list_devices().await
+                .map(|devices| (devices, current_device_id))
+                .map_err(|e| {
+                    error!("Failed to load devices: {}", e);
+                    format!("Failed to load devices: {}", e)
+                });
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    fn set_game_sync_enabled(&self, game_id: i64, enabled: bool) {
+        let Some(ref manager) = self.settings_manager else {
+            return;
+        };
+        
+        let database = manager.database();
+        self.runtime.spawn(async move {
+            if let Err(e) = database.set_game_sync_enabled(game_id, enabled).await {
+                error!("Failed to update sync flag for game {}: {}", game_id, e);
+            }
+        });
+    }
+    
+    fn run_maintenance(&mut self, action: MaintenanceAction, ctx: &egui::Context) {
+        let Some(sync_service) = self.sync_service.lock().unwrap().clone() else {
+            warn!("Sync service not available");
+            self.maintenance_result = Some(Err("Sync service not available".to_string()));
+            return;
+        };
+        
+        info!("Running maintenance action: {:?}", action);
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.maintenance_rx = Some(rx);
+        self.maintenance_result = None;
+        
+        // Nothing may sync again until someone signs in and turns it back on
+        if action == MaintenanceAction::WipeLocalAuth {
+            self.disconnect_ws_client();
+            self.clear_account_state();
+            self.settings.lock().unwrap().cloud_sync_enabled = false;
+            if let Some(ref manager) = self.settings_manager {
+                let manager = manager.clone();
+                self.runtime.spawn(async move {
+                    if let Err(e) = manager.save_setting("cloud_sync_enabled", "false").await {
+                        error!("Failed to turn off cloud sync: {}", e);
+                    }
+                });
+            }
+        }
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = match action {
+                MaintenanceAction::ResetSyncState => sync_service.reset_sync_state().await
+                    .map(|_| "Sync state reset".to_string())
+                    .map_err(|e| {
+                        error!("Failed to reset sync state: {}", e);
+                        format!("Failed to reset sync state: {}", e)
+                    }),
+                MaintenanceAction::ClearGameCache => {
+                    sync_service.clear_game_cache().await;
+                    Ok("Game cache cleared".to_string())
+                }
+                MaintenanceAction::ReuploadAll => match sync_service.force_reupload_all().await {
+                    Ok(0) => Ok("Nothing to re-upload".to_string()),
+                    Ok(queued) => Ok(format!("Re-uploaded {} saves, skipping any the cloud already had", queued)),
+                    Err(e) => {
+                        error!("Failed to re-upload saves: {}", e);
+                        Err(format!("Failed to re-upload saves: {}", e))
+                    }
+                },
+                MaintenanceAction::WipeLocalAuth => sync_service.wipe_local_auth().await
+                    .map(|_| "Signed out everywhere, no credentials are stored on this computer".to_string())
+                    .map_err(|e| {
+                        error!("Failed to wipe local credentials: {}", e);
+                        format!("Failed to wipe local credentials: {}", e)
+                    }),
+            };
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    fn export_settings_file(&mut self, ctx: &egui::Context) {
+        let Some(ref settings_manager) = self.settings_manager else {
+            self.maintenance_result = Some(Err("Database not available".to_string()));
+            return;
+        };
+        
+        let settings_manager = settings_manager.clone();
+        let settings = self.settings.lock().unwrap().clone();
+        let path = std::path::PathBuf::from(self.settings_file_path.trim());
+        
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.maintenance_rx = Some(rx);
+        self.maintenance_result = None;
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = settings_manager.export_to_file(&settings, &path)
+                .map(|_| format!("Settings saved to {}", path.display()))
+                .map_err(|e| {
+                    error!("Failed to export settings: {}", e);
+                    format!("Failed to export settings: {}", e)
+                });
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    fn import_settings_file(&mut self, ctx: &egui::Context) {
+        let Some(ref settings_manager) = self.settings_manager else {
+            self.maintenance_result = Some(Err("Database not available".to_string()));
+            return;
+        };
+        
+        let settings_manager = settings_manager.clone();
+        let path = std::path::PathBuf::from(self.settings_file_path.trim());
+        
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.settings_import_rx = Some(rx);
+        self.maintenance_result = None;
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = settings_manager.import_from_file(&path).await.map_err(|e| {
+                error!("Failed to import settings: {}", e);
+                format!("Failed to import settings: {}", e)
+            });
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    fn export_diagnostics(&mut self, ctx: &egui::Context) {
+        let Some(ref settings_manager) = self.settings_manager else {
+            self.maintenance_result = Some(Err("Database not available".to_string()));
+            return;
+        };
+        
+        let database = settings_manager.database();
+        let settings = self.settings.lock().unwrap().clone();
+        let path = std::path::PathBuf::from(self.diagnostics_path.trim());
+        
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.maintenance_rx = Some(rx);
+        self.maintenance_result = None;
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = crate::diagnostics::export_diagnostics(&path, &database, &settings).await
+                .map(|_| format!("Diagnostics saved to {}", path.display()))
+                .map_err(|e| {
+                    error!("Failed to export diagnostics: {}", e);
+                    format!("Failed to export diagnostics: {}", e)
+                });
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    fn start_manual_sync(&mut self, ctx: &egui::Context) {
+        info!("Manual sync requested - triggering sync");
+        
+        let sync_service = self.sync_service.lock().unwrap().clone();
+        let Some(sync_service) = sync_service else {
+            warn!("Sync service not available");
+            self.last_sync_result = Some(ManualSyncResult::Error("Sync service not available".to_string()));
+            return;
+        };
+        
+        self.sync_in_progress = true;
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.sync_result_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = match sync_service.trigger_sync().await {
+                Ok(()) => {
+                    info!("Manual sync completed");
+                    ManualSyncResult::Success { finished_at: chrono::Local::now() }
+                }
+                Err(e) => {
+                    error!("Failed to trigger sync: {}", e);
+                    ManualSyncResult::Error(e.to_string())
+                }
+            };
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    fn show_encryption(&mut self, ui: &mut egui::Ui, theme: &Theme) {
+        if self.encryption_unlocked.is_none() && self.encryption_rx.is_none() {
+            self.update_encryption(EncryptionAction::Check, ui.ctx());
+        }
+        
+        let busy = self.encryption_rx.is_some();
+        let (enabled, mode) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.encryption_enabled, settings.encryption_mode)
+        };
+        
+        if enabled && self.encryption_unlocked == Some(true) {
+            ui.colored_label(theme.success, format!("✓ Saves are encrypted before upload ({})", mode.label()));
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!busy, egui::Button::new("Turn off encryption")).clicked() {
+                    self.update_encryption(EncryptionAction::Disable, ui.ctx());
+                }
+                if self.exported_key.is_none() && ui.button("Show recovery key").clicked() {
+                    self.confirm_show_key = true;
+                }
+            });
+            self.show_recovery_key(ui, theme);
+        } else {
+            if enabled {
+                ui.colored_label(theme.warning, "Encryption is locked. Uploads wait until you enter your passphrase.");
+            } else {
+                ui.label("Encrypt saves on this computer so the server can't read them.");
+                let mut settings = self.settings.lock().unwrap();
+                ui.horizontal(|ui| {
+                    ui.label("Key:");
+                    egui::ComboBox::from_id_salt("encryption_mode")
+                        .selected_text(settings.encryption_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in EncryptionMode::ALL {
+                                ui.selectable_value(&mut settings.encryption_mode, mode, mode.label());
+                            }
+                        });
+                });
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("Passphrase:");
+                ui.add(egui::TextEdit::singleline(&mut self.encryption_passphrase).password(true));
+            });
+            let confirmed = if enabled {
+                true
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label("Confirm:");
+                    ui.add(egui::TextEdit::singleline(&mut self.encryption_confirm).password(true));
+                });
+                self.encryption_confirm == self.encryption_passphrase
+            };
+            
+            let button = if enabled { "Unlock" } else { "Turn on encryption" };
+            let ready = !busy && !self.encryption_passphrase.is_empty() && confirmed;
+            if ui.add_enabled(ready, egui::Button::new(button)).clicked() {
+                let passphrase = std::mem::take(&mut self.encryption_passphrase);
+                self.encryption_confirm.clear();
+                self.update_encryption(EncryptionAction::Enable(mode, passphrase), ui.ctx());
+            }
+            if !confirmed && !self.encryption_confirm.is_empty() {
+                ui.colored_label(theme.warning, "Passphrases don't match");
+            }
+            
+            let note = match mode {
+                EncryptionMode::Passphrase => "Use the same passphrase on your other devices. If you forget it, encrypted saves can't be recovered.",
+                EncryptionMode::DeviceKey => "Saves encrypted this way can only be restored on this computer.",
+            };
+            ui.label(egui::RichText::new(note).color(theme.muted).size(11.0));
+            
+            egui::CollapsingHeader::new("Use a recovery key instead")
+                .id_salt("import_encryption_key")
+                .show(ui, |ui| {
+                    ui.label("Paste the recovery key shown on the device that set up encryption.");
+                    ui.add(egui::TextEdit::singleline(&mut self.import_key_input)
+                        .password(true)
+                        .hint_text("retrosave-key-v1:..."));
+                    let ready = !busy && !self.import_key_input.trim().is_empty();
+                    if ui.add_enabled(ready, egui::Button::new("Use key")).clicked() {
+                        let key = std::mem::take(&mut self.import_key_input);
+                        self.update_encryption(EncryptionAction::ImportKey(key), ui.ctx());
+                    }
+                });
         }
         
-        // Handle deferred actions
-        if should_logout {
-            self.perform_logout(ctx);
+        if busy {
+            ui.spinner();
         }
-    }
-}
-
-impl SettingsApp {
-    fn initialize_websocket(&mut self, ctx: &egui::Context) {
-        if self.ws_initialized || !self.is_authenticated {
-            return;
+        if let Some(ref error) = self.encryption_error {
+            ui.colored_label(theme.error, format!("✗ {}", error));
         }
-        
-        info!("Initializing WebSocket connection for real-time updates");
-        
-        // Get API URL from settings
-        let api_url = self.settings.lock().unwrap().cloud_api_url.clone();
-        
-        // Create WebSocket event channel
-        let (event_tx, _event_rx) = tokio::sync::mpsc::unbounded_channel();
-        
-        // Create WebSocket client
-        let ws_client = Arc::new(WebSocketClient::new(api_url.clone(), event_tx));
-        let ws_client_clone = ws_client.clone();
-        
-        // Get auth token if available
-        if let Some(ref auth_manager) = self.auth_manager {
-            let auth_manager_clone = auth_manager.clone();
-            let ws_client_for_auth = ws_client.clone();
-            let api_url_clone = api_url.clone();
-            
-            // Set up WebSocket with auth token
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async {
-                    if let Some(token) = auth_manager_clone.get_access_token().await {
-                        // Create a new mutable WebSocket client with the token
-                        let mut ws = WebSocketClient::new(api_url_clone.clone(), tokio::sync::mpsc::unbounded_channel().0);
-                        ws.set_token(token).await;
-                        
-                        // Connect to WebSocket
-                        if let Err(e) = ws_client_for_auth.connect().await {
-                            error!("Failed to connect WebSocket: {}", e);
-                        } else {
-                            info!("WebSocket connected successfully");
-                            
-                            // Start listening for messages
-                            let ws_listener = ws_client_for_auth.clone();
-                            tokio::spawn(async move {
-                                ws_listener.start_listening().await;
-                            });
+    }
+    
+    /// Confirmation and display of the exported encryption key
+    fn show_recovery_key(&mut self, ui: &mut egui::Ui, theme: &Theme) {
+        if self.confirm_show_key {
+            egui::Frame::none()
+                .fill(theme.warning_fill)
+                .inner_margin(egui::Margin::same(6.0))
+                .rounding(egui::Rounding::same(4.0))
+                .show(ui, |ui| {
+                    ui.colored_label(theme.warning, "⚠ Anyone with the recovery key can read your encrypted saves. Keep it somewhere safe and private.");
+                    ui.colored_label(theme.warning, "If you lose both the key and your passphrase, your encrypted cloud saves can't be recovered by anyone, including us.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Show key").clicked() {
+                            self.confirm_show_key = false;
+                            self.export_encryption_key(ui.ctx());
                         }
-                    }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_show_key = false;
+                        }
+                    });
                 });
-            });
         }
         
-        // Create channels to update the UI from callbacks
-        let (subscription_tx, subscription_rx) = std::sync::mpsc::channel::<SubscriptionStatus>();
-        let (usage_tx, usage_rx) = std::sync::mpsc::channel::<UsageStats>();
+        let mut hide = false;
+        if let Some(ref key) = self.exported_key {
+            ui.label("Recovery key:");
+            let mut shown = key.as_str();
+            ui.add(egui::TextEdit::singleline(&mut shown).desired_width(f32::INFINITY));
+            ui.horizontal(|ui| {
+                if ui.button("📋 Copy").clicked() {
+                    ui.ctx().copy_text(key.clone());
+                }
+                hide = ui.button("Hide").clicked();
+            });
+        }
+        if hide {
+            self.exported_key = None;
+        }
+    }
+    
+    fn export_encryption_key(&mut self, ctx: &egui::Context) {
+        let Some(sync_service) = self.sync_service.lock().unwrap().clone() else {
+            return;
+        };
         
-        // Store receivers so we can poll them in the update loop
-        self.ws_subscription_rx = Some(subscription_rx);
-        self.ws_usage_rx = Some(usage_rx);
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.exported_key_rx = Some(rx);
         
-        // Register subscription update callback
-        let ctx_clone = ctx.clone();
-        let ws_handler = ws_client.event_handler();
-        let subscription_callback = {
-            let ctx = ctx_clone.clone();
-            let tx = subscription_tx.clone();
-            move |status: SubscriptionStatus| {
-                info!("Received real-time subscription update: {:?}", status);
-                let _ = tx.send(status);
-                ctx.request_repaint();
-            }
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = sync_service.export_encryption_key().await.map_err(|e| e.to_string());
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
+    }
+    
+    /// Turn encryption on or off, or check whether it's unlocked. Sends back
+    /// whether the key is unlocked afterwards.
+    fn update_encryption(&mut self, action: EncryptionAction, ctx: &egui::Context) {
+        let Some(sync_service) = self.sync_service.lock().unwrap().clone() else {
+            self.encryption_unlocked = Some(false);
+            return;
         };
         
-        // Register usage update callback
-        let ctx_clone2 = ctx.clone();
-        let usage_callback = {
-            let ctx = ctx_clone2.clone();
-            let tx = usage_tx.clone();
-            move |stats: UsageStats| {
-                info!("Received real-time usage update: {:?}", stats);
-                let _ = tx.send(stats);
-                ctx.request_repaint();
-            }
-        };
+        if let EncryptionAction::Disable = action {
+            self.settings.lock().unwrap().encryption_enabled = false;
+            self.exported_key = None;
+        }
         
-        // Register device added callback
-        let ctx_clone3 = ctx.clone();
-        let device_added_callback = {
-            let ctx = ctx_clone3.clone();
-            move |device_id: String, device_name: String| {
-                info!("Device added: {} ({})", device_name, device_id);
-                // Show notification to user
-                if let Err(e) = crate::ui::notifications::show_notification(
-                    "Device Added",
-                    &format!("New device '{}' has been added to your account", device_name),
-                    crate::ui::notifications::NotificationType::Info,
-                ) {
-                    error!("Failed to show device added notification: {}", e);
-                }
-                ctx.request_repaint();
-            }
-        };
+        let settings_manager = self.settings_manager.clone();
+        let mode = self.settings.lock().unwrap().encryption_mode;
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.encryption_rx = Some(rx);
         
-        // Register device removed callback
-        let ctx_clone4 = ctx.clone();
-        let device_removed_callback = {
-            let ctx = ctx_clone4.clone();
-            move |device_id: String, device_name: String| {
-                info!("Device removed: {} ({})", device_name, device_id);
-                // Show notification to user
-                if let Err(e) = crate::ui::notifications::show_notification(
-                    "Device Removed",
-                    &format!("Device '{}' has been removed from your account", device_name),
-                    crate::ui::notifications::NotificationType::Warning,
-                ) {
-                    error!("Failed to show device removed notification: {}", e);
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            // Settings to persist once the change went through
+            let (result, persist) = match action {
+                EncryptionAction::Check => (Ok(()), None),
+                EncryptionAction::Enable(mode, passphrase) => match sync_service.enable_encryption(mode, &passphrase).await {
+                    Ok(()) => (Ok(()), Some((true, mode))),
+                    Err(e) => {
+                        warn!("Failed to enable encryption: {}", e);
+                        (Err(e.to_string()), None)
+                    }
+                },
+                EncryptionAction::ImportKey(key) => match sync_service.import_encryption_key(&key).await {
+                    Ok(()) => (Ok(()), Some((true, mode))),
+                    Err(e) => {
+                        warn!("Failed to import encryption key: {}", e);
+                        (Err(e.to_string()), None)
+                    }
+                },
+                EncryptionAction::Disable => {
+                    sync_service.disable_encryption().await;
+                    (Ok(()), Some((false, mode)))
                 }
-                ctx.request_repaint();
-            }
-        };
-        
-        // Register warning callback (for storage/save limits)
-        let ctx_clone5 = ctx.clone();
-        let warning_callback = {
-            let ctx = ctx_clone5.clone();
-            move |message: String| {
-                info!("Received warning: {}", message);
-                // Show warning notification to user
-                if let Err(e) = crate::ui::notifications::show_notification(
-                    "Storage Warning",
-                    &message,
-                    crate::ui::notifications::NotificationType::Warning,
-                ) {
-                    error!("Failed to show warning notification: {}", e);
+            };
+            
+            if let (Some(manager), Some((enabled, mode))) = (settings_manager, persist) {
+                if let Err(e) = manager.save_setting("encryption_enabled", &enabled.to_string()).await {
+                    error!("Failed to save encryption setting: {}", e);
+                }
+                if let Err(e) = manager.save_setting("encryption_mode", mode.as_str()).await {
+                    error!("Failed to save encryption setting: {}", e);
                 }
-                ctx.request_repaint();
             }
+            
+            let unlocked = sync_service.is_encryption_enabled().await;
+            let _ = tx.send(result.map(|_| unlocked));
+            ctx.request_repaint();
+        });
+    }
+    
+    /// Check that the API server answers, without needing to be logged in
+    fn test_connection(&mut self, ctx: &egui::Context) {
+        let Some(ref api_client) = self.api_client else {
+            return;
         };
         
-        // Store callbacks using a separate thread to avoid blocking
-        let ws_handler_clone = ws_handler.clone();
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                ws_handler_clone.on_subscription_update(subscription_callback).await;
-                ws_handler_clone.on_usage_update(usage_callback).await;
-                ws_handler_clone.on_device_added(device_added_callback).await;
-                ws_handler_clone.on_device_removed(device_removed_callback).await;
-                ws_handler_clone.on_warning(warning_callback).await;
+        self.connection_test_result = None;
+        let api_client = api_client.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.connection_test_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = api_client.test_connection().await.map_err(|e| {
+                warn!("API connection test failed: {:#}", e);
+                format!("{:#}", e)
             });
+            let _ = tx.send(result);
+            ctx.request_repaint();
         });
+    }
+    
+    /// Look for an installed rclone and the remotes set up in it
+    fn detect_rclone(&mut self, ctx: &egui::Context) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.rclone_rx = Some(rx);
         
-        self.ws_client = Some(ws_client_clone);
-        self.ws_initialized = true;
-        
-        info!("WebSocket initialization complete");
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = match crate::sync::rclone::rclone_version().await {
+                Some(version) => crate::sync::rclone::list_remotes().await
+                    .map(|remotes| RcloneInfo { version, remotes })
+                    .map_err(|e| format!("{:#}", e)),
+                None => Err("rclone not found, install it and set up a remote with `rclone config`".to_string()),
+            };
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
     }
     
-    fn fetch_subscription_status(&mut self, ctx: &egui::Context) {
-        info!("fetch_subscription_status called, api_client exists: {}", self.api_client.is_some());
-        if let Some(ref api_client) = self.api_client {
-            if self.subscription_loading {
-                info!("Already loading subscription, skipping");
-                return; // Already loading
-            }
-            
-            info!("Starting subscription fetch");
-            self.subscription_loading = true;
-            let api_client_clone = api_client.clone();
-            let (tx, rx) = std::sync::mpsc::channel();
-            
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async {
-                    let mut subscription = None;
-                    let mut usage = None;
-                    
-                    // Fetch subscription status with retry on auth refresh
-                    for attempt in 0..2 {
-                        match api_client_clone.get_subscription_status().await {
-                            Ok(status) => {
-                                subscription = Some(status);
-                                break;
-                            },
-                            Err(e) => {
-                                let error_str = e.to_string();
-                                if error_str.contains("Authentication refreshed") && attempt == 0 {
-                                    // First attempt failed with auth refresh, retry
-                                    info!("Retrying subscription fetch after auth refresh");
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                                    continue;
-                                }
-                                error!("Failed to fetch subscription status: {}", e);
-                                break;
-                            }
-                        }
+    /// Create, switch or remove a profile, then reload the profile list
+    fn load_profiles(&mut self, change: Option<ProfileChange>, ctx: &egui::Context) {
+        let Some(ref manager) = self.settings_manager else {
+            return;
+        };
+        
+        self.profiles_loaded = true;
+        let database = manager.database();
+        let auth_manager = self.auth_manager.clone();
+        let sync_service = self.sync_service.lock().unwrap().clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.profiles_rx = Some(rx);
+        
+        let ctx = ctx.clone();
+        self.runtime.spawn(async move {
+            let result = async {
+                let mut switched_auth = None;
+                match change {
+                    Some(ProfileChange::Create(name)) => {
+                        database.create_profile(&name).await?;
+                        info!("Created profile {}", name);
                     }
-                    
-                    // Fetch usage stats with retry on auth refresh
-                    for attempt in 0..2 {
-                        match api_client_clone.get_usage_stats().await {
-                            Ok(stats) => {
-                                usage = Some(stats);
-                                break;
-                            },
-                            Err(e) => {
-                                let error_str = e.to_string();
-                                if error_str.contains("Authentication refreshed") && attempt == 0 {
-                                    // First attempt failed with auth refresh, retry
-                                    info!("Retrying usage stats fetch after auth refresh");
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                                    continue;
-                                }
-                                error!("Failed to fetch usage stats: {}", e);
-                                break;
-                            }
+                    Some(ProfileChange::Switch(name)) => {
+                        database.set_active_profile(&name).await?;
+                        match (sync_service, auth_manager.as_ref()) {
+                            (Some(sync_service), _) => sync_service.switch_profile(&name).await?,
+                            (None, Some(auth_manager)) => auth_manager.switch_profile(&name).await?,
+                            (None, None) => {}
+                        }
+                        if let Some(ref auth_manager) = auth_manager {
+                            let state = auth_manager.get_state().await;
+                            switched_auth = Some((state.is_authenticated, state.user.map(|u| u.email)));
                         }
                     }
-                    
-                    let _ = tx.send((subscription, usage));
-                });
+                    Some(ProfileChange::Remove(name)) => {
+                        database.delete_profile(&name).await?;
+                        AuthManager::remove_profile_tokens(&name)?;
+                        info!("Removed profile {}", name);
+                    }
+                    None => {}
+                }
+                
+                Ok::<_, anyhow::Error>(ProfilesUpdate {
+                    profiles: database.get_profiles().await?,
+                    active: database.get_active_profile().await?,
+                    switched_auth,
+                })
+            }.await.map_err(|e| {
+                error!("Failed to update profiles: {}", e);
+                e.to_string()
             });
-            
-            self.subscription_rx = Some(rx);
-            ctx.request_repaint_after(std::time::Duration::from_millis(100));
-        }
+            let _ = tx.send(result);
+            ctx.request_repaint();
+        });
     }
     
-    fn perform_logout(&mut self, ctx: &egui::Context) {
-        // Disconnect WebSocket if connected
+    /// Close the settings window's own WebSocket connection
+    fn disconnect_ws_client(&mut self) {
         if let Some(ref ws_client) = self.ws_client {
             let ws_client_clone = ws_client.clone();
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async {
-                    if let Err(e) = ws_client_clone.disconnect().await {
-                        error!("Failed to disconnect WebSocket: {}", e);
-                    } else {
-                        info!("WebSocket disconnected");
-                    }
-                });
+            self.runtime.spawn(async move {
+                if let Err(e) = ws_client_clone.disconnect().await {
+                    error!("Failed to disconnect WebSocket: {}", e);
+                } else {
+                    info!("WebSocket disconnected");
+                }
             });
             self.ws_client = None;
             self.ws_initialized = false;
             self.ws_subscription_rx = None;
             self.ws_usage_rx = None;
+            self.ws_state_rx = None;
+            self.ws_connection_state = None;
         }
+    }
+    
+    /// Forget everything shown about the account that was logged in
+    fn clear_account_state(&mut self) {
+        self.is_authenticated = false;
+        self.user_email = None;
+        self.subscription_status = None;
+        self.usage_stats = None;
+        self.devices.clear();
+        self.devices_loaded = false;
+        self.current_device_id = None;
+    }
+    
+    fn perform_logout(&mut self, ctx: &egui::Context) {
+        self.disconnect_ws_client();
         
         if let Some(ref auth_manager) = self.auth_manager {
             let auth_manager_clone = auth_manager.clone();
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async {
-                    if let Err(e) = auth_manager_clone.logout().await {
-                        error!("Logout failed: {}", e);
-                    } else {
-                        info!("Logged out successfully");
-                    }
-                });
+            self.runtime.spawn(async move {
+                if let Err(e) = auth_manager_clone.logout().await {
+                    error!("Logout failed: {}", e);
+                } else {
+                    info!("Logged out successfully");
+                }
             });
-            self.is_authenticated = false;
-            self.user_email = None;
-            self.subscription_status = None;
-            self.usage_stats = None;
+            self.clear_account_state();
         }
         ctx.request_repaint();
     }
@@ -1419,48 +3570,63 @@ impl SettingsApp {
         // Get auth manager reference
         let auth_manager = self.auth_manager.clone();
         
-        // Start OAuth flow in background thread
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                let oauth_client = BrowserOAuth::new(api_url);
-                
-                match oauth_client.authenticate().await {
-                    Ok(token_response) => {
-                        info!("OAuth flow successful, got tokens for user: {}", token_response.user.email);
-                        
-                        // Save tokens if we have an auth manager
-                        if let Some(auth_mgr) = auth_manager {
-                            if let Err(e) = auth_mgr.save_tokens(
-                                token_response.access_token.clone(),
-                                token_response.refresh_token.clone(),
-                                token_response.user.clone()
-                            ).await {
-                                error!("Failed to save OAuth tokens: {}", e);
-                                let _ = tx.send(AuthResult::Error(
-                                    format!("Failed to save authentication: {}", e)
-                                ));
-                                return;
-                            }
+        // Start OAuth flow in the background
+        self.runtime.spawn(async move {
+            let oauth_client = BrowserOAuth::new(api_url);
+            
+            match oauth_client.authenticate().await {
+                Ok(token_response) => {
+                    info!("OAuth flow successful, got tokens for user: {}", token_response.user.email);
+                    
+                    // Save tokens if we have an auth manager
+                    if let Some(auth_mgr) = auth_manager {
+                        if let Err(e) = auth_mgr.save_tokens(
+                            token_response.access_token.clone(),
+                            token_response.refresh_token.clone(),
+                            token_response.user.clone()
+                        ).await {
+                            error!("Failed to save OAuth tokens: {}", e);
+                            let _ = tx.send(AuthResult::Error(
+                                format!("Failed to save authentication: {}", e)
+                            ));
+                            return;
                         }
-                        
-                        let _ = tx.send(AuthResult::Success {
-                            email: token_response.user.email
-                        });
-                    }
-                    Err(e) => {
-                        error!("OAuth flow failed: {}", e);
-                        let error_msg = if e.to_string().contains("timeout") {
-                            "Authentication timed out. Please try again.".to_string()
-                        } else if e.to_string().contains("cancel") {
-                            "Authentication cancelled.".to_string()
-                        } else {
-                            format!("Authentication failed: {}", e)
-                        };
-                        let _ = tx.send(AuthResult::Error(error_msg));
                     }
+                    
+                    let _ = tx.send(AuthResult::Success {
+                        email: token_response.user.email
+                    });
                 }
-            });
+                Err(e) => {
+                    error!("OAuth flow failed: {}", e);
+                    let error_msg = if e.to_string().contains("timeout") {
+                        "Authentication timed out. Please try again.".to_string()
+                    } else if e.to_string().contains("cancel") {
+                        "Authentication cancelled.".to_string()
+                    } else {
+                        format!("Authentication failed: {}", e)
+                    };
+                    let _ = tx.send(AuthResult::Error(error_msg));
+                }
+            }
         });
     }
-}
\ No newline at end of file
+}
+
+/// Format a timestamp relative to now, e.g. "just now" or "5 minutes ago"
+fn format_relative_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    let elapsed = chrono::Utc::now().signed_duration_since(time);
+    
+    if elapsed.num_seconds() < 60 {
+        "just now".to_string()
+    } else if elapsed.num_minutes() < 60 {
+        let minutes = elapsed.num_minutes();
+        format!("{} minute{} ago", minutes, if minutes == 1 { "" } else { "s" })
+    } else if elapsed.num_hours() < 24 {
+        let hours = elapsed.num_hours();
+        format!("{} hour{} ago", hours, if hours == 1 { "" } else { "s" })
+    } else {
+        let days = elapsed.num_days();
+        format!("{} day{} ago", days, if days == 1 { "" } else { "s" })
+    }
+}
@@ -0,0 +1,70 @@
+use eframe::egui::{self, Color32};
+
+use crate::settings::ThemePreference;
+
+/// Switch the context to the preferred visuals
+pub fn apply(ctx: &egui::Context, preference: ThemePreference) {
+    ctx.set_theme(match preference {
+        ThemePreference::System => egui::ThemePreference::System,
+        ThemePreference::Light => egui::ThemePreference::Light,
+        ThemePreference::Dark => egui::ThemePreference::Dark,
+    });
+}
+
+/// Semantic colors that stay readable on both light and dark visuals
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Connected, active, within limits
+    pub success: Color32,
+    /// Approaching a limit, waiting on something
+    pub warning: Color32,
+    /// Failures and exceeded limits
+    pub error: Color32,
+    /// Secondary text such as hints and timestamps
+    pub muted: Color32,
+    /// Highlighted values such as the plan name
+    pub accent: Color32,
+    /// Background of the account card
+    pub panel: Color32,
+    /// Background of the subscription and sync status cards
+    pub panel_alt: Color32,
+    /// Background of the quota banner
+    pub warning_fill: Color32,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            success: Color32::from_rgb(100, 255, 100),
+            warning: Color32::from_rgb(255, 200, 100),
+            error: Color32::from_rgb(255, 100, 100),
+            muted: Color32::from_rgb(150, 150, 150),
+            accent: Color32::from_rgb(150, 200, 255),
+            panel: Color32::from_rgb(40, 45, 40),
+            panel_alt: Color32::from_rgb(35, 38, 45),
+            warning_fill: Color32::from_rgba_unmultiplied(255, 200, 100, 20),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            success: Color32::from_rgb(20, 130, 40),
+            warning: Color32::from_rgb(170, 100, 0),
+            error: Color32::from_rgb(200, 40, 40),
+            muted: Color32::from_rgb(100, 100, 100),
+            accent: Color32::from_rgb(30, 100, 200),
+            panel: Color32::from_rgb(232, 240, 232),
+            panel_alt: Color32::from_rgb(232, 236, 245),
+            warning_fill: Color32::from_rgba_unmultiplied(255, 190, 80, 60),
+        }
+    }
+
+    /// Colors matching the visuals currently in use
+    pub fn current(ctx: &egui::Context) -> Self {
+        if ctx.style().visuals.dark_mode {
+            Self::dark()
+        } else {
+            Self::light()
+        }
+    }
+}
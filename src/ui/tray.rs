@@ -3,7 +3,7 @@ use tray_icon::{
     menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
     TrayIconBuilder, TrayIconEvent, TrayIcon,
 };
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use notify_rust::{Notification, Timeout};
@@ -22,8 +22,15 @@ pub enum TrayMessage {
     SaveDetected(String),
     UpdateStatus(String),
     ManualSaveRequested,
+    ScanExistingRequested,
+    ToggleMonitoring,
+    /// Shut down gracefully, flushing the sync queue first
+    Quit,
     OpenSettings,
+    OpenSaveBrowser,
     OpenDashboard,
+    /// Write a diagnostics bundle for a bug report
+    ExportDiagnostics,
     HotkeyChanged(Option<String>),
     // Cloud sync messages
     SyncStarted,
@@ -80,14 +87,17 @@ impl SystemTray {
             // On Windows/macOS, create tray on current thread
             std::thread::spawn(move || {
                 match Self::create_tray_icon(sender_clone) {
-                    Ok(_tray_icon) => {
+                    Ok(tray_icon) => {
                         info!("Tray icon created successfully");
                         // Keep the tray icon alive by holding it in this thread
                         loop {
                             if let Some(msg) = control_receiver.blocking_recv() {
                                 match msg {
                                     TrayControl::UpdateStatus(status) => {
-                                        info!("Updating tray status: {}", status);
+                                        debug!("Updating tray status: {}", status);
+                                        if let Err(e) = tray_icon.set_tooltip(Some(format!("Retrosave - {}", status))) {
+                                            warn!("Failed to update tray tooltip: {}", e);
+                                        }
                                     }
                                     TrayControl::ShowNotification(title, message) => {
                                         Self::show_notification_internal(&title, &message);
@@ -136,6 +146,12 @@ impl SystemTray {
         let save_now_item = MenuItem::new("Save Now", true, None);
         menu.append(&save_now_item)?;
         
+        let scan_existing_item = MenuItem::new("Scan Existing Saves", true, None);
+        menu.append(&scan_existing_item)?;
+        
+        let pause_item = MenuItem::new("Pause/Resume Monitoring", true, None);
+        menu.append(&pause_item)?;
+        
         // Separator
         menu.append(&PredefinedMenuItem::separator())?;
         
@@ -143,10 +159,16 @@ impl SystemTray {
         let dashboard_item = MenuItem::new("Dashboard", true, None);
         menu.append(&dashboard_item)?;
         
+        let saves_item = MenuItem::new("Browse Saves", true, None);
+        menu.append(&saves_item)?;
+        
         // Settings item
         let settings_item = MenuItem::new("Settings", true, None);
         menu.append(&settings_item)?;
         
+        let diagnostics_item = MenuItem::new("Export Diagnostics", true, None);
+        menu.append(&diagnostics_item)?;
+        
         // About item
         let about_item = MenuItem::new("About", true, None);
         menu.append(&about_item)?;
@@ -154,15 +176,19 @@ impl SystemTray {
         // Separator
         menu.append(&PredefinedMenuItem::separator())?;
         
-        // Exit item
-        let exit_item = MenuItem::new("Exit", true, None);
-        menu.append(&exit_item)?;
+        // Quit item
+        let quit_item = MenuItem::new("Quit", true, None);
+        menu.append(&quit_item)?;
         
         // Store menu item IDs for the event handler
-        let exit_id = exit_item.id().clone();
+        let quit_id = quit_item.id().clone();
         let save_now_id = save_now_item.id().clone();
+        let scan_existing_id = scan_existing_item.id().clone();
+        let pause_id = pause_item.id().clone();
         let dashboard_id = dashboard_item.id().clone();
+        let saves_id = saves_item.id().clone();
         let settings_id = settings_item.id().clone();
+        let diagnostics_id = diagnostics_item.id().clone();
         let about_id = about_item.id().clone();
         
         // Load or create icon
@@ -187,8 +213,14 @@ impl SystemTray {
             if let Ok(msg) = control_receiver.try_recv() {
                 match msg {
                     TrayControl::UpdateStatus(status) => {
-                        info!("Updating tray status: {}", status);
-                        // TODO: Update tooltip or menu item
+                        debug!("Updating tray status: {}", status);
+                        if let Ok(tray_guard) = tray_icon_clone.lock() {
+                            if let Some(ref tray_icon) = *tray_guard {
+                                if let Err(e) = tray_icon.set_tooltip(Some(format!("Retrosave - {}", status))) {
+                                    warn!("Failed to update tray tooltip: {}", e);
+                                }
+                            }
+                        }
                     }
                     TrayControl::ShowNotification(title, message) => {
                         Self::show_notification_internal(&title, &message);
@@ -206,19 +238,30 @@ impl SystemTray {
             // Check for menu events
             if let Ok(event) = MenuEvent::receiver().try_recv() {
                 info!("Menu event received: {:?}", event.id);
-                if event.id == exit_id {
-                    info!("Exit requested from tray menu");
-                    gtk::main_quit();
-                    std::process::exit(0);
+                if event.id == quit_id {
+                    info!("Quit requested from tray menu");
+                    let _ = event_sender.try_send(TrayMessage::Quit);
                 } else if event.id == save_now_id {
                     info!("Manual save requested from tray menu");
                     let _ = event_sender.try_send(TrayMessage::ManualSaveRequested);
+                } else if event.id == scan_existing_id {
+                    info!("Existing save scan requested from tray menu");
+                    let _ = event_sender.try_send(TrayMessage::ScanExistingRequested);
+                } else if event.id == pause_id {
+                    info!("Pause/resume monitoring requested from tray menu");
+                    let _ = event_sender.try_send(TrayMessage::ToggleMonitoring);
                 } else if event.id == dashboard_id {
                     info!("Dashboard clicked");
                     let _ = event_sender.try_send(TrayMessage::OpenDashboard);
+                } else if event.id == saves_id {
+                    info!("Browse saves clicked");
+                    let _ = event_sender.try_send(TrayMessage::OpenSaveBrowser);
                 } else if event.id == settings_id {
                     info!("Settings clicked");
                     let _ = event_sender.try_send(TrayMessage::OpenSettings);
+                } else if event.id == diagnostics_id {
+                    info!("Diagnostics export requested from tray menu");
+                    let _ = event_sender.try_send(TrayMessage::ExportDiagnostics);
                 } else if event.id == about_id {
                     info!("About clicked");
                     // TODO: Show about dialog
@@ -268,6 +311,12 @@ impl SystemTray {
         let save_now_item = MenuItem::new("Save Now", true, None);
         menu.append(&save_now_item)?;
         
+        let scan_existing_item = MenuItem::new("Scan Existing Saves", true, None);
+        menu.append(&scan_existing_item)?;
+        
+        let pause_item = MenuItem::new("Pause/Resume Monitoring", true, None);
+        menu.append(&pause_item)?;
+        
         // Separator
         menu.append(&PredefinedMenuItem::separator())?;
         
@@ -275,10 +324,16 @@ impl SystemTray {
         let dashboard_item = MenuItem::new("Dashboard", true, None);
         menu.append(&dashboard_item)?;
         
+        let saves_item = MenuItem::new("Browse Saves", true, None);
+        menu.append(&saves_item)?;
+        
         // Settings item
         let settings_item = MenuItem::new("Settings", true, None);
         menu.append(&settings_item)?;
         
+        let diagnostics_item = MenuItem::new("Export Diagnostics", true, None);
+        menu.append(&diagnostics_item)?;
+        
         // About item
         let about_item = MenuItem::new("About", true, None);
         menu.append(&about_item)?;
@@ -286,15 +341,19 @@ impl SystemTray {
         // Separator
         menu.append(&PredefinedMenuItem::separator())?;
         
-        // Exit item
-        let exit_item = MenuItem::new("Exit", true, None);
-        menu.append(&exit_item)?;
+        // Quit item
+        let quit_item = MenuItem::new("Quit", true, None);
+        menu.append(&quit_item)?;
         
         // Store menu item IDs for the event handler
-        let exit_id = exit_item.id().clone();
+        let quit_id = quit_item.id().clone();
         let save_now_id = save_now_item.id().clone();
+        let scan_existing_id = scan_existing_item.id().clone();
+        let pause_id = pause_item.id().clone();
         let dashboard_id = dashboard_item.id().clone();
+        let saves_id = saves_item.id().clone();
         let settings_id = settings_item.id().clone();
+        let diagnostics_id = diagnostics_item.id().clone();
         let about_id = about_item.id().clone();
         
         // Load or create icon
@@ -317,18 +376,30 @@ impl SystemTray {
                 // Check for menu events
                 if let Ok(event) = menu_channel.try_recv() {
                     info!("Menu event received: {:?}", event.id);
-                    if event.id == exit_id {
-                        info!("Exit requested from tray menu");
-                        std::process::exit(0);
+                    if event.id == quit_id {
+                        info!("Quit requested from tray menu");
+                        let _ = event_sender.blocking_send(TrayMessage::Quit);
                     } else if event.id == save_now_id {
                         info!("Manual save requested from tray menu");
                         let _ = event_sender.blocking_send(TrayMessage::ManualSaveRequested);
+                    } else if event.id == scan_existing_id {
+                        info!("Existing save scan requested from tray menu");
+                        let _ = event_sender.blocking_send(TrayMessage::ScanExistingRequested);
+                    } else if event.id == pause_id {
+                        info!("Pause/resume monitoring requested from tray menu");
+                        let _ = event_sender.blocking_send(TrayMessage::ToggleMonitoring);
                     } else if event.id == dashboard_id {
                         info!("Dashboard clicked");
                         let _ = event_sender.blocking_send(TrayMessage::OpenDashboard);
+                    } else if event.id == saves_id {
+                        info!("Browse saves clicked");
+                        let _ = event_sender.blocking_send(TrayMessage::OpenSaveBrowser);
                     } else if event.id == settings_id {
                         info!("Settings clicked");
                         let _ = event_sender.blocking_send(TrayMessage::OpenSettings);
+                    } else if event.id == diagnostics_id {
+                        info!("Diagnostics export requested from tray menu");
+                        let _ = event_sender.blocking_send(TrayMessage::ExportDiagnostics);
                     } else if event.id == about_id {
                         info!("About clicked");
                         // TODO: Show about dialog
@@ -366,6 +437,21 @@ impl SystemTray {
         let _ = self.control_sender.try_send(TrayControl::UpdateStatus(status.to_string()));
     }
     
+    /// Show sync progress in the tooltip in place of the status until it is
+    /// `None`, which brings the status back
+    pub fn show_progress(&self, progress: Option<&str>) {
+        let text = match progress {
+            Some(progress) => format!("Syncing: {}", progress),
+            None => self.status.lock().map(|s| s.clone()).unwrap_or_default(),
+        };
+        let _ = self.control_sender.try_send(TrayControl::UpdateStatus(text));
+    }
+    
+    /// Remove the tray icon and stop the tray thread
+    pub fn exit(&self) {
+        let _ = self.control_sender.try_send(TrayControl::Exit);
+    }
+    
     pub async fn send_message(&self, message: TrayMessage) -> Result<()> {
         self.sender.send(message).await?;
         Ok(())
@@ -1,8 +1,17 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tempfile::TempDir;
 use std::fs;
 use std::io::Write;
+use uuid::Uuid;
+
+use retrosave::payment::{SubscriptionStatus, UsageStats};
+use retrosave::storage::hasher::hash_bytes;
+use retrosave::sync::api::{CloudApi, Game, ListSavesResponse, SaveMetadata, UploadUrlResponse};
 
 /// Test utilities for creating temporary test environments
 pub struct TestEnvironment {
@@ -85,6 +94,25 @@ impl MockProcess {
             cmd_args: vec![game_path.to_string()],
         }
     }
+    
+    pub fn dolphin_emu() -> Self {
+        Self {
+            name: "dolphin-emu".to_string(),
+            pid: 2345,
+            exe_path: "/usr/bin/dolphin-emu".to_string(),
+            cmd_args: vec![],
+        }
+    }
+    
+    /// KDE's file manager, which shares a name with Dolphin Emulator
+    pub fn kde_dolphin() -> Self {
+        Self {
+            name: "dolphin".to_string(),
+            pid: 3456,
+            exe_path: "/usr/bin/dolphin".to_string(),
+            cmd_args: vec![],
+        }
+    }
 }
 
 /// Helper to create a test database
@@ -108,4 +136,142 @@ where
     }
     
     false
-}
\ No newline at end of file
+}
+
+/// A save the mock backend received
+pub struct MockUpload {
+    pub game_id: Uuid,
+    pub metadata: serde_json::Value,
+    pub data: Vec<u8>,
+}
+
+/// In-memory `CloudApi` for sync tests: registers games, keeps uploads and serves `cloud_saves`
+#[derive(Default)]
+pub struct MockCloud {
+    pub games: Mutex<HashMap<String, Uuid>>,
+    pub registrations: Mutex<usize>,
+    pub pending_urls: Mutex<HashMap<String, (Uuid, serde_json::Value)>>,
+    pub uploads: Mutex<Vec<MockUpload>>,
+    pub cloud_saves: Mutex<Vec<SaveMetadata>>,
+    pub blobs: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MockCloud {
+    /// Offer `data` as a cloud save of `game_name` for `file_path`
+    pub fn add_cloud_save(&self, game_name: &str, emulator: &str, file_path: &str, data: &[u8]) -> SaveMetadata {
+        let compressed = zstd::encode_all(data, 3).unwrap();
+        let id = Uuid::new_v4();
+        let download_url = format!("mock://saves/{}", id);
+        let save = SaveMetadata {
+            id,
+            game_id: Uuid::new_v4(),
+            file_hash: hash_bytes(&compressed),
+            file_size: compressed.len() as i64,
+            client_timestamp: Utc::now(),
+            created_at: Utc::now(),
+            download_url: Some(download_url.clone()),
+            metadata: Some(serde_json::json!({
+                "file_path": file_path,
+                "game_name": game_name,
+                "emulator": emulator,
+            })),
+            version: Some(1),
+            game_name: Some(game_name.to_string()),
+            device_name: Some("other-device".to_string()),
+        };
+        self.blobs.lock().unwrap().insert(download_url, compressed);
+        self.cloud_saves.lock().unwrap().push(save.clone());
+        save
+    }
+}
+
+#[async_trait]
+impl CloudApi for MockCloud {
+    fn base_url(&self) -> &str {
+        "http://mock"
+    }
+    
+    fn set_bandwidth_limit(&self, _kbps: u32) {}
+    
+    async fn is_reachable(&self) -> bool {
+        true
+    }
+    
+    async fn register_game_with_id(&self, name: &str, emulator: &str, _game_id: Option<String>) -> Result<Game> {
+        *self.registrations.lock().unwrap() += 1;
+        let id = *self.games.lock().unwrap()
+            .entry(format!("{}:{}", name, emulator))
+            .or_insert_with(Uuid::new_v4);
+        Ok(Game {
+            id,
+            name: name.to_string(),
+            emulator: emulator.to_string(),
+            save_count: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+    }
+    
+    async fn register_device(&self, _device_id: &str, _name: &str) -> Result<()> {
+        Ok(())
+    }
+    
+    async fn request_upload_url_with_metadata(
+        &self,
+        game_id: Uuid,
+        _file_hash: &str,
+        _file_size: i64,
+        _timestamp: DateTime<Utc>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<UploadUrlResponse> {
+        let save_id = Uuid::new_v4();
+        let upload_url = format!("mock://upload/{}", save_id);
+        self.pending_urls.lock().unwrap().insert(upload_url.clone(), (game_id, metadata.unwrap_or_default()));
+        Ok(UploadUrlResponse { save_id, upload_url, expires_in: 3600 })
+    }
+    
+    async fn upload_save_data(&self, upload_url: &str, data: Vec<u8>) -> Result<()> {
+        let (game_id, metadata) = self.pending_urls.lock().unwrap().remove(upload_url)
+            .ok_or_else(|| anyhow!("Unknown upload URL {}", upload_url))?;
+        self.uploads.lock().unwrap().push(MockUpload { game_id, metadata, data });
+        Ok(())
+    }
+    
+    async fn list_saves(&self, game_id: Option<Uuid>, _page: i64, _per_page: i64) -> Result<ListSavesResponse> {
+        let items: Vec<SaveMetadata> = self.cloud_saves.lock().unwrap().iter()
+            .filter(|save| game_id.is_none_or(|id| save.game_id == id))
+            .cloned()
+            .collect();
+        Ok(ListSavesResponse {
+            total: items.len() as i64,
+            items,
+            page: 1,
+            per_page: 100,
+            total_pages: 1,
+            has_next: false,
+            has_prev: false,
+        })
+    }
+    
+    async fn download_save_data(&self, download_url: &str) -> Result<Vec<u8>> {
+        self.blobs.lock().unwrap().get(download_url).cloned()
+            .ok_or_else(|| anyhow!("Unknown download URL {}", download_url))
+    }
+    
+    async fn update_save_note(&self, save_id: Uuid, note: Option<&str>) -> Result<()> {
+        let mut cloud_saves = self.cloud_saves.lock().unwrap();
+        let save = cloud_saves.iter_mut().find(|save| save.id == save_id)
+            .ok_or_else(|| anyhow!("Unknown save {}", save_id))?;
+        let metadata = save.metadata.get_or_insert_with(|| serde_json::json!({}));
+        metadata["note"] = note.into();
+        Ok(())
+    }
+    
+    async fn get_subscription_status(&self) -> Result<SubscriptionStatus> {
+        Err(anyhow!("No subscription in the mock"))
+    }
+    
+    async fn get_usage_stats(&self) -> Result<UsageStats> {
+        Err(anyhow!("No usage stats in the mock"))
+    }
+}
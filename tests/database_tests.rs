@@ -305,4 +305,41 @@ async fn test_concurrent_database_access() -> Result<()> {
     assert!(versions.iter().max().unwrap() <= &10);
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_search_games() -> Result<()> {
+    let env = common::TestEnvironment::new()?;
+    let db = common::create_test_database(&env.db_path).await?;
+    
+    db.get_or_create_game("The Legend of Zelda: Ocarina of Time", "RetroArch").await?;
+    db.get_or_create_game("Final Fantasy X", "PCSX2").await?;
+    db.get_or_create_game("Final Fantasy VII", "DuckStation").await?;
+    db.get_or_create_game("Kingdom Hearts", "PCSX2").await?;
+    
+    // Case-insensitive word match
+    let results = db.search_games("final fantasy").await?;
+    assert_eq!(results.len(), 2);
+    
+    // Partial word (prefix) match
+    let results = db.search_games("ocar").await?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "The Legend of Zelda: Ocarina of Time");
+    
+    // Mid-word fragment
+    let results = db.search_games("ingdo").await?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "Kingdom Hearts");
+    
+    // Emulator names are searchable too
+    let results = db.search_games("pcsx2").await?;
+    assert_eq!(results.len(), 2);
+    
+    // Special characters are matched literally
+    assert!(db.search_games("100%").await?.is_empty());
+    
+    // An empty query returns everything
+    assert_eq!(db.search_games("  ").await?.len(), 4);
+    
+    Ok(())
+}
@@ -8,7 +8,9 @@ use std::time::Duration;
 
 extern crate retrosave;
 use retrosave::storage::{Database, SaveWatcher};
+use retrosave::storage::watcher::IGNORE_PATTERNS_SETTING;
 use retrosave::monitor::{MonitorEvent, SaveResult};
+use retrosave::monitor::process::match_emulator;
 
 #[tokio::test]
 async fn test_save_detection_workflow() -> Result<()> {
@@ -45,6 +47,60 @@ async fn test_save_detection_workflow() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_watcher_ignores_temp_files() -> Result<()> {
+    let env = common::TestEnvironment::new()?;
+    let db = Arc::new(common::create_test_database(&env.db_path).await?);
+    
+    // User-defined pattern on top of the built-in ones
+    db.set_setting(IGNORE_PATTERNS_SETTING, "ignored_*").await?;
+    
+    let (mut watcher, mut save_receiver) = SaveWatcher::new(env.save_dir.clone(), db.clone())?;
+    watcher.start().await?;
+    
+    env.create_save_file("Mcd001.ps2.tmp", b"temporary data")?;
+    env.create_save_file("ignored_state.p2s", b"save state data")?;
+    // Written last, so the watcher has seen the ignored files by the time it reports this one
+    let save_path = env.create_save_file("Mcd001.ps2", b"memory card data")?;
+    
+    let event = tokio::time::timeout(Duration::from_secs(2), save_receiver.recv()).await
+        .expect("The save next to the ignored files should be detected")
+        .expect("Watcher channel closed");
+    assert_eq!(event.file_path, save_path, "Ignored files should not emit a SaveEvent");
+    
+    let event = tokio::time::timeout(Duration::from_millis(500), save_receiver.recv()).await;
+    assert!(event.is_err(), "Ignored files should not emit a SaveEvent");
+    
+    watcher.stop();
+    
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_watcher_detects_nested_saves() -> Result<()> {
+    let env = common::TestEnvironment::new()?;
+    let db = Arc::new(common::create_test_database(&env.db_path).await?);
+    
+    // PPSSPP-style layout: SAVEDATA/<GAMEID>/DATA.BIN
+    let game_dir = env.save_dir.join("ULUS10041SAVE00");
+    std::fs::create_dir_all(&game_dir)?;
+    
+    let (mut watcher, mut save_receiver) = SaveWatcher::new_with_emulator(env.save_dir.clone(), db.clone(), "PPSSPP".to_string())?;
+    watcher.start().await?;
+    
+    std::fs::write(game_dir.join("DATA.BIN"), b"nested save data")?;
+    
+    let event = tokio::time::timeout(Duration::from_secs(2), save_receiver.recv()).await
+        .expect("Nested save should be detected")
+        .expect("Watcher channel closed");
+    assert_eq!(event.file_path, game_dir.join("DATA.BIN"));
+    assert_eq!(event.game_id.as_deref(), Some("ULUS-10041"));
+    
+    watcher.stop();
+    
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_manual_save_trigger() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -214,4 +270,31 @@ async fn test_multiple_games_tracking() -> Result<()> {
     assert_eq!(total_saves, 5);
     
     Ok(())
-}
\ No newline at end of file
+}
+#[test]
+fn test_process_matching_ignores_kde_dolphin() {
+    let file_manager = common::MockProcess::kde_dolphin();
+    assert_eq!(match_emulator(&file_manager.name, Some(&file_manager.exe_path)), None);
+    assert_eq!(match_emulator(&file_manager.name, None), None);
+    
+    let emulator = common::MockProcess::dolphin_emu();
+    assert_eq!(match_emulator(&emulator.name, Some(&emulator.exe_path)), Some("Dolphin"));
+    assert_eq!(match_emulator("Dolphin.exe", Some("C:\\Dolphin\\Dolphin.exe")), Some("Dolphin"));
+    assert_eq!(match_emulator("Dolphin", Some("/Applications/Dolphin.app/Contents/MacOS/Dolphin")), Some("Dolphin"));
+}
+
+#[test]
+fn test_process_matching_uses_known_executables() {
+    let pcsx2 = common::MockProcess::pcsx2();
+    assert_eq!(match_emulator(&pcsx2.name, Some(&pcsx2.exe_path)), Some("PCSX2"));
+    assert_eq!(match_emulator("pcsx2-qt", None), Some("PCSX2"));
+    assert_eq!(match_emulator("pcsx2-v2.0.2.AppImage", None), Some("PCSX2"));
+    assert_eq!(match_emulator("pcsx2-qtx64-avx", None), Some("PCSX2"));
+    assert_eq!(match_emulator("RetroArch.exe", None), Some("RetroArch"));
+    assert_eq!(match_emulator("Ryujinx.Ava", None), Some("Ryujinx"));
+    
+    assert_eq!(match_emulator("yuzu-helper", None), None);
+    assert_eq!(match_emulator("citra-updater", None), None);
+    assert_eq!(match_emulator("ppsspp-launcher", None), None);
+    assert_eq!(match_emulator("kdeconnect-pcsx2", None), None);
+}
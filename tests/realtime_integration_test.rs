@@ -14,6 +14,7 @@ async fn test_complete_event_pipeline() {
         dedup_window: Duration::from_millis(500),
         batch_size: 5,
         batch_timeout: Duration::from_millis(50),
+        ..Default::default()
     }));
     
     // Track received events
@@ -0,0 +1,232 @@
+mod common;
+
+use anyhow::Result;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+extern crate retrosave;
+use retrosave::storage::Database;
+use retrosave::storage::hasher::hash_bytes;
+use retrosave::sync::service::ConflictResolutionStrategy;
+use retrosave::sync::{AuthManager, SyncApi, SyncService};
+
+use common::MockCloud;
+
+/// Nothing listens here, so the service sees the cloud as unreachable
+const UNREACHABLE_API: &str = "http://127.0.0.1:9";
+
+/// Sync service backed by `cloud` and a fresh database, plus the save directory
+async fn sync_service(cloud: Arc<MockCloud>) -> Result<(TempDir, Arc<Database>, SyncService)> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir_all(temp_dir.path().join("saves"))?;
+    let database = Arc::new(Database::new(Some(temp_dir.path().join("test.db"))).await?);
+    let auth_manager = Arc::new(AuthManager::new("http://mock".to_string()));
+    let service = SyncService::with_api(cloud, auth_manager, database.clone(), Some(temp_dir.path().join("data")));
+    Ok((temp_dir, database, service))
+}
+
+/// Sync service whose cloud can't be reached, with a fresh database and save directory
+async fn offline_sync_service() -> Result<(TempDir, Arc<Database>, SyncService)> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir_all(temp_dir.path().join("saves"))?;
+    let database = Arc::new(Database::new(Some(temp_dir.path().join("test.db"))).await?);
+    let auth_manager = Arc::new(AuthManager::new(UNREACHABLE_API.to_string()));
+    let api = Arc::new(SyncApi::new(UNREACHABLE_API.to_string(), auth_manager.clone()));
+    let service = SyncService::with_api(api, auth_manager, database.clone(), Some(temp_dir.path().join("data")));
+    Ok((temp_dir, database, service))
+}
+
+#[tokio::test]
+async fn test_queued_save_is_uploaded() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (temp_dir, database, service) = sync_service(cloud.clone()).await?;
+    
+    let save_path = temp_dir.path().join("saves").join("Chrono Trigger.srm");
+    std::fs::write(&save_path, b"chrono trigger save")?;
+    let game = database.get_or_create_game("Chrono Trigger", "RetroArch").await?;
+    database.record_save(game.id, &save_path.to_string_lossy(), &hash_bytes(b"chrono trigger save"), 19, None).await?;
+    
+    service.resync_game(game.id).await?;
+    assert_eq!(service.process_upload_queue().await?, 1);
+    assert_eq!(service.get_pending_uploads().await, 0);
+    
+    let uploads = cloud.uploads.lock().unwrap();
+    assert_eq!(uploads.len(), 1);
+    assert_eq!(Some(&uploads[0].game_id), cloud.games.lock().unwrap().get("Chrono Trigger:RetroArch"));
+    assert_eq!(uploads[0].metadata["game_name"], "Chrono Trigger");
+    assert_eq!(zstd::decode_all(uploads[0].data.as_slice())?, b"chrono trigger save");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resync_uploads_the_recorded_version() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (temp_dir, database, service) = sync_service(cloud.clone()).await?;
+    
+    let save_path = temp_dir.path().join("saves").join("Illusion of Gaia.srm");
+    let backup_path = temp_dir.path().join("Illusion of Gaia_v1.bak");
+    std::fs::write(&backup_path, b"gaia save")?;
+    let game = database.get_or_create_game("Illusion of Gaia", "RetroArch").await?;
+    database.record_save(game.id, &save_path.to_string_lossy(), &hash_bytes(b"gaia save"), 9,
+        Some(&backup_path.to_string_lossy())).await?;
+    // Played on since, the file no longer matches the recorded version
+    std::fs::write(&save_path, b"gaia save, later")?;
+    
+    service.resync_game(game.id).await?;
+    assert_eq!(service.process_upload_queue().await?, 1);
+    let uploads = cloud.uploads.lock().unwrap();
+    assert_eq!(zstd::decode_all(uploads[0].data.as_slice())?, b"gaia save");
+    assert_eq!(uploads[0].metadata["file_path"], save_path.to_string_lossy().as_ref());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_synced_save_is_not_uploaded_again() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (temp_dir, database, service) = sync_service(cloud.clone()).await?;
+    
+    let save_path = temp_dir.path().join("saves").join("Secret of Mana.srm");
+    std::fs::write(&save_path, b"secret of mana save")?;
+    let game = database.get_or_create_game("Secret of Mana", "RetroArch").await?;
+    database.record_save(game.id, &save_path.to_string_lossy(), &hash_bytes(b"secret of mana save"), 19, None).await?;
+    
+    service.resync_game(game.id).await?;
+    assert_eq!(service.process_upload_queue().await?, 1);
+    
+    // Like the save being detected again after a restart
+    service.resync_game(game.id).await?;
+    assert_eq!(service.process_upload_queue().await?, 0);
+    assert_eq!(service.get_pending_uploads().await, 0);
+    assert_eq!(cloud.uploads.lock().unwrap().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_note_edits_sync_both_ways() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (temp_dir, database, service) = sync_service(cloud.clone()).await?;
+    
+    let save_path = temp_dir.path().join("saves").join("Terranigma.srm");
+    std::fs::write(&save_path, b"terranigma save")?;
+    let game = database.get_or_create_game("Terranigma", "RetroArch").await?;
+    let save = database.record_save(game.id, &save_path.to_string_lossy(), &hash_bytes(b"terranigma save"), 15, None).await?;
+    service.resync_game(game.id).await?;
+    assert_eq!(service.process_upload_queue().await?, 1);
+    
+    // The mock keeps uploads apart, list this one as a cloud save
+    let file_hash = hash_bytes(b"terranigma save");
+    let cloud_id = database.get_cloud_save_id(game.id, &file_hash).await?.unwrap();
+    cloud.add_cloud_save("Terranigma", "RetroArch", &save_path.to_string_lossy(), b"terranigma save");
+    {
+        let mut cloud_saves = cloud.cloud_saves.lock().unwrap();
+        cloud_saves[0].id = cloud_id.parse()?;
+        cloud_saves[0].file_hash = file_hash.clone();
+    }
+    
+    // Edited here after the upload
+    database.set_save_note(save.id, "before the tower").await?;
+    service.perform_sync().await?;
+    let metadata = cloud.cloud_saves.lock().unwrap()[0].metadata.clone().unwrap();
+    assert_eq!(metadata["note"], "before the tower");
+    
+    // Edited on another device
+    cloud.cloud_saves.lock().unwrap()[0].metadata.as_mut().unwrap()["note"] = "after the tower".into();
+    service.download_new_saves().await?;
+    assert_eq!(database.get_save_note_by_hash(&file_hash).await?.as_deref(), Some("after the tower"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_games_are_registered_once() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (_temp_dir, _database, service) = sync_service(cloud.clone()).await?;
+    
+    let first = service.get_or_register_game("Okami", "PCSX2").await?;
+    let second = service.get_or_register_game("Okami", "PCSX2").await?;
+    assert_eq!(first, second);
+    assert_eq!(*cloud.registrations.lock().unwrap(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_missing_save_is_restored_from_the_cloud() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (temp_dir, database, service) = sync_service(cloud.clone()).await?;
+    
+    let save_path = temp_dir.path().join("saves").join("Earthbound.srm");
+    cloud.add_cloud_save("Earthbound", "RetroArch", &save_path.to_string_lossy(), b"earthbound save");
+    
+    service.download_new_saves().await?;
+    assert_eq!(std::fs::read(&save_path)?, b"earthbound save");
+    
+    let game = database.get_or_create_game("Earthbound", "RetroArch").await?;
+    assert_eq!(database.get_saves_for_game(game.id, Some(10)).await?.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_download_waits_for_the_emulator_to_close() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (temp_dir, database, service) = sync_service(cloud.clone()).await?;
+    service.set_emulator_running("PCSX2", true).await;
+    
+    let save_path = temp_dir.path().join("saves").join("Mcd001.ps2");
+    cloud.add_cloud_save("Okami", "PCSX2", &save_path.to_string_lossy(), b"okami card");
+    
+    service.download_new_saves().await?;
+    assert!(!save_path.exists());
+    let game = database.get_or_create_game("Okami", "PCSX2").await?;
+    assert!(database.get_saves_for_game(game.id, Some(10)).await?.is_empty());
+    assert_eq!(database.get_deferred_writes().await?.len(), 1);
+    
+    service.set_emulator_running("PCSX2", false).await;
+    assert_eq!(std::fs::read(&save_path)?, b"okami card");
+    assert_eq!(database.get_saves_for_game(game.id, Some(10)).await?.len(), 1);
+    assert!(database.get_deferred_writes().await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_local_first_keeps_the_local_save() -> Result<()> {
+    let cloud = Arc::new(MockCloud::default());
+    let (temp_dir, database, service) = sync_service(cloud.clone()).await?;
+    service.set_conflict_strategy(ConflictResolutionStrategy::LocalFirst).await;
+    
+    let save_path = temp_dir.path().join("saves").join("Mother 3.gba.sav");
+    std::fs::write(&save_path, b"local progress")?;
+    let cloud_save = cloud.add_cloud_save("Mother 3", "RetroArch", &save_path.to_string_lossy(), b"cloud progress");
+    // This device has seen the cloud save before and played on since
+    let game = database.get_or_create_game("Mother 3", "RetroArch").await?;
+    database.record_save(game.id, &save_path.to_string_lossy(), &cloud_save.file_hash, cloud_save.file_size, None).await?;
+    
+    service.download_new_saves().await?;
+    assert_eq!(std::fs::read(&save_path)?, b"local progress");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_empty_upload_queue() -> Result<()> {
+    let (_temp_dir, _database, service) = offline_sync_service().await?;
+    
+    assert_eq!(service.process_upload_queue().await?, 0);
+    assert_eq!(service.get_pending_uploads().await, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_offline_sync_keeps_queued_saves() -> Result<()> {
+    let (temp_dir, database, service) = offline_sync_service().await?;
+    
+    let save_path = temp_dir.path().join("saves").join("Chrono Trigger.srm");
+    std::fs::write(&save_path, b"chrono trigger save")?;
+    let game = database.get_or_create_game("Chrono Trigger", "RetroArch").await?;
+    database.record_save(game.id, &save_path.to_string_lossy(), &hash_bytes(b"chrono trigger save"), 19, None).await?;
+    
+    service.resync_game(game.id).await?;
+    assert_eq!(service.get_pending_uploads().await, 1);
+    
+    service.perform_sync().await?;
+    assert_eq!(service.get_pending_uploads().await, 1);
+    assert!(!service.get_status().await.is_online);
+    Ok(())
+}